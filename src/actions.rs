@@ -1,10 +1,10 @@
 use crate::app::MyApp;
-use crate::config::{self, get_config_path};
+use crate::config;
 use crate::sync::{SyncCommand, SyncEvent}; // Import our new types
 // Removed unused imports: ApiTorrentListOpts, TorrentDetailsResponse
 use std::path::PathBuf;
-use opener; // Add use statement for opener crate
-use anyhow::{anyhow, Result}; // Import anyhow properly
+use anyhow::{anyhow, Context, Result}; // Import anyhow properly
+use tracing::{error, info, warn};
 
 // --- Action Helper Functions --- 
 // These functions are called by the UI to perform actions, often involving
@@ -14,12 +14,19 @@ use anyhow::{anyhow, Result}; // Import anyhow properly
 
 // Helper function to handle saving configuration changes
 pub fn save_config_changes(app: &mut MyApp) -> Result<()> {
-    println!("Action: Saving configuration changes");
+    info!("Action: Saving configuration changes");
 
     // Update the application config with input values
+    if app.config.torrent_url != app.config_edit_url {
+        // Resolved for the old URL; the next check fills it in again
+        app.ui_state.resolved_url = None;
+    }
     app.config.torrent_url = app.config_edit_url.clone();
+    // A newly saved path gets a fresh chance; the next refused write shows the hint again
+    app.ui_state.permission_denied_path = None;
     let path_str = app.config_edit_path_str.clone();
     app.config.download_path = PathBuf::from(path_str);
+    app.ui_state.network_share = crate::sync::network_share::network_share(&app.config);
     
     // Update the profile settings
     app.config.should_seed = app.config_edit_should_seed;
@@ -32,7 +39,7 @@ pub fn save_config_changes(app: &mut MyApp) -> Result<()> {
             Ok(speed) => Some(speed),
             Err(e) => {
                 let err_msg = format!("Invalid upload speed: {}", e);
-                println!("Action: {}", err_msg);
+                info!("Action: {}", err_msg);
                 app.last_error = Some(err_msg);
                 return Err(anyhow!("Invalid upload speed value"));
             }
@@ -46,7 +53,7 @@ pub fn save_config_changes(app: &mut MyApp) -> Result<()> {
             Ok(speed) => Some(speed),
             Err(e) => {
                 let err_msg = format!("Invalid download speed: {}", e);
-                println!("Action: {}", err_msg);
+                info!("Action: {}", err_msg);
                 app.last_error = Some(err_msg);
                 return Err(anyhow!("Invalid download speed value"));
             }
@@ -59,34 +66,26 @@ pub fn save_config_changes(app: &mut MyApp) -> Result<()> {
     let _sync_cmd_tx_clone = app.sync_cmd_tx.clone();
 
     // First notify the sync manager about the config update
-    if let Err(e) = app.sync_cmd_tx.send(SyncCommand::UpdateConfig(app.config.clone())) {
+    if let Err(e) = app.sync_cmd_tx.send(SyncCommand::UpdateConfig(Box::new(app.config.clone()))) {
         let err_msg = format!("Failed to send config update to sync manager: {}", e);
-        println!("Action: {}", err_msg);
+        info!("Action: {}", err_msg);
         app.last_error = Some(err_msg);
         return Err(anyhow!("Failed to send config update"));
     }
 
     // Spawn task to handle file I/O only, without notifying sync task
+    let config_store = app.config_store.clone();
     tokio::spawn(async move {
-        match get_config_path() {
-            Ok(config_path) => {
-                match config::save_config(&config_clone, &config_path) {
-                    Ok(_) => {
-                        println!("Configuration saved successfully.");
-                        let _ = ui_tx_clone.send(SyncEvent::Error("Configuration Saved".to_string()));
-                        // We no longer notify the sync task here
-                    }
-                    Err(e) => {
-                        eprintln!("Error saving configuration: {}", e);
-                        let _ = ui_tx_clone
-                            .send(SyncEvent::Error(format!("Failed to save config: {}", e)));
-                    }
-                }
+        match config_store.save(&config_clone) {
+            Ok(_) => {
+                info!("Configuration saved successfully.");
+                let _ = ui_tx_clone.send(SyncEvent::Error("Configuration Saved".to_string()));
+                // We no longer notify the sync task here
             }
             Err(e) => {
-                eprintln!("Error getting config path: {}", e);
+                error!("Error saving configuration to {}: {}", config_store.location(), e);
                 let _ = ui_tx_clone
-                    .send(SyncEvent::Error(format!("Failed to get config path: {}", e)));
+                    .send(SyncEvent::Error(format!("Failed to save config: {}", e)));
             }
         }
     });
@@ -97,59 +96,425 @@ pub fn save_config_changes(app: &mut MyApp) -> Result<()> {
 // Action to delete extra files found during verification
 pub(crate) fn delete_extra_files(app: &mut MyApp) {
     if let Some(files) = app.extra_files_to_prompt.take() { // Take ownership and clear prompt
-        println!("Action: Delete {} extra files requested", files.len());
+        info!("Action: Delete {} extra files requested", files.len());
         if let Err(e) = app.sync_cmd_tx.send(SyncCommand::DeleteFiles(files)) {
-            eprintln!("Action: Failed to send DeleteFiles command: {}", e);
+            error!("Action: Failed to send DeleteFiles command: {}", e);
             // Restore prompt state on error?
             // app.extra_files_to_prompt = Some(files); // Or handle error differently
             let _ = app.ui_tx.send(SyncEvent::Error(format!("Failed to send delete command: {}", e)));
         }
     } else {
-        println!("Action: delete_extra_files called but no files in prompt state.");
+        info!("Action: delete_extra_files called but no files in prompt state.");
     }
 }
 
 // Action to apply a remote torrent update
 pub(crate) fn apply_remote_update(app: &mut MyApp) {
     if let Some(torrent_data) = app.remote_update.take() { // Take ownership and clear prompt
-        println!("Action: Apply remote update requested ({} bytes)", torrent_data.len());
+        info!("Action: Apply remote update requested ({} bytes)", torrent_data.len());
         // Send the update to the sync task for processing
         if let Err(e) = app.sync_cmd_tx.send(SyncCommand::ApplyUpdate(torrent_data)) {
-            eprintln!("Action: Failed to send ApplyUpdate command: {}", e);
+            error!("Action: Failed to send ApplyUpdate command: {}", e);
             let _ = app.ui_tx.send(SyncEvent::Error(format!("Failed to apply update: {}", e)));
         }
     } else {
-        println!("Action: apply_remote_update called but no remote update data available.");
+        info!("Action: apply_remote_update called but no remote update data available.");
+    }
+}
+
+// Action to start seeding a public torrent once the user has agreed, remembering
+// the choice if they ticked "don't ask again"
+pub(crate) fn confirm_public_seeding(app: &mut MyApp) {
+    let Some(id) = app.seeding_confirmation.take() else {
+        info!("Action: confirm_public_seeding called but no confirmation pending.");
+        return;
+    };
+    info!("Action: Public seeding confirmed for torrent {}", id);
+    if app.ui_state.dont_ask_public_seeding {
+        app.config.public_seeding_acknowledged = true;
+        let _ = save_config_changes(app);
+    }
+    if let Err(e) = app.sync_cmd_tx.send(SyncCommand::ConfirmSeeding(id)) {
+        error!("Action: Failed to send ConfirmSeeding command: {}", e);
+        let _ = app.ui_tx.send(SyncEvent::Error(format!("Failed to start seeding: {}", e)));
+    }
+}
+
+// Action to start a torrent once the user has accepted the notice in its comment
+pub(crate) fn acknowledge_notice(app: &mut MyApp) {
+    let Some((id, _)) = app.notice_prompt.take() else {
+        info!("Action: acknowledge_notice called but no notice pending.");
+        return;
+    };
+    info!("Action: Notice accepted for torrent {}", id);
+    if let Err(e) = app.sync_cmd_tx.send(SyncCommand::AcknowledgeNotice(id)) {
+        error!("Action: Failed to send AcknowledgeNotice command: {}", e);
+        let _ = app.ui_tx.send(SyncEvent::Error(format!("Failed to start the torrent: {}", e)));
+    }
+}
+
+// Action to turn seeding off instead of seeding a public torrent; the torrent stays paused
+pub(crate) fn decline_public_seeding(app: &mut MyApp) {
+    if app.seeding_confirmation.take().is_some() {
+        info!("Action: Public seeding declined, turning seeding off");
+        app.config_edit_should_seed = false;
+        app.ui_state.should_seed = false;
+        let _ = save_config_changes(app);
+    }
+}
+
+// Action to take over a download folder owned by another torrent and re-send the refused command
+pub(crate) fn claim_folder_and_continue(app: &mut MyApp) {
+    if let Some((owner, retry)) = app.folder_owner_prompt.take() {
+        info!("Action: Claiming download folder from {}", owner.torrent_url);
+        // Commands are handled in order, so the claim lands before the retry
+        for cmd in [SyncCommand::ClaimFolder, retry] {
+            if let Err(e) = app.sync_cmd_tx.send(cmd) {
+                error!("Action: Failed to send command after claiming folder: {}", e);
+                let _ = app.ui_tx.send(SyncEvent::Error(format!("Failed to continue sync: {}", e)));
+                return;
+            }
+        }
+    } else {
+        info!("Action: claim_folder_and_continue called but no ownership prompt pending.");
+    }
+}
+
+// Action to set (or clear, with an empty string) the managed torrent's display label
+pub(crate) fn set_torrent_label(app: &mut MyApp, label: String) {
+    let label = label.trim();
+    app.config.torrent_label = if label.is_empty() { None } else { Some(label.to_string()) };
+    info!("Action: Torrent label set to {:?}", app.config.torrent_label);
+    persist_config(app, "label");
+}
+
+// Action to pause or resume downloading one file of the managed torrent
+pub(crate) fn set_file_paused(app: &mut MyApp, path: String, paused: bool) {
+    let paused_files = &mut app.config.paused_files;
+    if paused && !paused_files.contains(&path) {
+        info!("Action: Pausing file {}", path);
+        paused_files.push(path);
+    } else if !paused && paused_files.contains(&path) {
+        info!("Action: Resuming file {}", path);
+        paused_files.retain(|p| *p != path);
+    } else {
+        return;
+    }
+    // The sync manager applies the change to the live torrent when it gets the config
+    persist_config(app, "paused files");
+}
+
+// Action to remember which Files tab folders are open, for the next start
+pub(crate) fn set_expanded_folders(app: &mut MyApp, folders: Vec<String>) {
+    if app.config.expanded_folders == folders {
+        return;
+    }
+    app.config.expanded_folders = folders;
+    // Only the UI uses this, so the sync manager isn't told
+    save_config_in_background(app, "open folders");
+}
+
+// Action to write the managed torrent's file list to the path typed in the Files tab
+pub(crate) fn export_file_list(app: &mut MyApp) {
+    let mut output_path = PathBuf::from(app.ui_state.export_path_str.trim());
+    if output_path.as_os_str().is_empty() {
+        let _ = app.ui_tx.send(SyncEvent::Error("Enter a path to export the file list to".to_string()));
+        return;
+    }
+    let format = app.ui_state.export_format;
+    if output_path.extension().is_none() {
+        output_path.set_extension(format.extension());
+    }
+
+    let result = config::get_cached_torrent_path()
+        .and_then(|path| std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display())))
+        .and_then(|bytes| crate::sync::file_list::export_file_list(&bytes, &output_path, format));
+    match result {
+        Ok(count) => {
+            info!("Action: Exported {} files to {}", count, output_path.display());
+            app.ui_state.export_result = Some(format!("Exported {} files to {}", count, output_path.display()));
+        }
+        Err(e) => {
+            error!("Action: Failed to export file list: {:#}", e);
+            app.ui_state.export_result = None;
+            let _ = app.ui_tx.send(SyncEvent::Error(format!("{:#}", e)));
+        }
+    }
+}
+
+// Action to start the torrent session again after it failed at startup
+pub(crate) fn retry_session(app: &mut MyApp) {
+    let Some(failed) = app.failed_start.take() else {
+        return; // Running, or a retry is already underway
+    };
+    info!("Action: Retrying the torrent session");
+    app.ui_state.session_retrying = true;
+    app.engine_retry = Some(crate::sync::startup::retry_engine(app.config.clone(), app.ui_tx.clone(), failed.sync_cmd_rx));
+}
+
+// Action to delete damaged session files, then retry
+pub(crate) fn clear_session_and_retry(app: &mut MyApp) {
+    match config::clear_session_files() {
+        Ok(removed) => info!("Action: Cleared {} session files", removed),
+        Err(e) => {
+            error!("Action: Failed to clear session files: {:#}", e);
+            let _ = app.ui_tx.send(SyncEvent::Error(format!("{:#}", e)));
+            return;
+        }
+    }
+    retry_session(app);
+}
+
+// Action to listen on the port typed into the recovery banner, then retry
+pub(crate) fn use_listen_port_and_retry(app: &mut MyApp) {
+    let port = match app.ui_state.recovery_port_str.trim().parse::<u16>() {
+        Ok(port) if port > 0 => port,
+        _ => {
+            let _ = app.ui_tx.send(SyncEvent::Error("Enter a port between 1 and 65535".to_string()));
+            return;
+        }
+    };
+    info!("Action: Listening from port {} from now on", port);
+    app.config.listen_port = Some(port);
+    save_config_in_background(app, "listen port");
+    retry_session(app);
+}
+
+// Action to save the diagnostics bundle for a bug report
+pub(crate) fn save_diagnostics_bundle(app: &mut MyApp) {
+    let now = chrono::Local::now();
+    let mut output_path = PathBuf::from(app.ui_state.bundle_path_str.trim());
+    if output_path.as_os_str().is_empty() {
+        let _ = app.ui_tx.send(SyncEvent::Error("Enter a path to save the diagnostics bundle to".to_string()));
+        return;
+    }
+    if output_path.is_dir() {
+        output_path.push(format!("modsync-diagnostics-{}.zip", now.format("%Y%m%d-%H%M%S")));
+    } else if output_path.extension().is_none() {
+        output_path.set_extension("zip");
+    }
+
+    let inputs = crate::diagnostics::BundleInputs {
+        config: &app.config,
+        activity: app.ui_state.activity.entries().collect(),
+        last_error: app.last_error.as_deref(),
+        files: app.ui_state.torrent_files.as_ref(),
+        now,
+    };
+    match crate::diagnostics::write_bundle(&inputs, &output_path) {
+        Ok(()) => {
+            info!("Action: Saved diagnostics bundle to {}", output_path.display());
+            app.ui_state.bundle_result = Some(format!("Saved to {}", output_path.display()));
+        }
+        Err(e) => {
+            error!("Action: Failed to save diagnostics bundle: {:#}", e);
+            app.ui_state.bundle_result = None;
+            let _ = app.ui_tx.send(SyncEvent::Error(format!("{:#}", e)));
+        }
+    }
+}
+
+// Action to dry-check the URL and path as currently typed, without saving or syncing
+pub(crate) fn test_config(app: &mut MyApp) {
+    let mut config_to_test = app.config.clone();
+    config_to_test.torrent_url = app.config_edit_url.trim().to_string();
+    config_to_test.download_path = PathBuf::from(app.config_edit_path_str.trim());
+    
+    info!("Action: Testing configuration for {}", config_to_test.torrent_url);
+    if let Err(e) = app.sync_cmd_tx.send(SyncCommand::TestConfig(Box::new(config_to_test))) {
+        error!("Action: Failed to send config test request: {}", e);
+        return;
+    }
+    app.ui_state.config_test_running = true;
+    app.ui_state.config_test_results = None;
+}
+
+// Action to open the setup wizard, starting from the current config
+pub(crate) fn start_setup_wizard(app: &mut MyApp) {
+    info!("Action: Starting setup wizard");
+    app.ui_state.wizard = crate::ui::setup_wizard::SetupWizard::from_config(&app.config);
+    app.ui_state.config_test_results = None;
+    app.ui_state.modal_state = crate::ui::state::ModalState::SetupWizard;
+}
+
+// Action to run the config test on the wizard's URL and path
+pub(crate) fn test_setup_wizard(app: &mut MyApp) {
+    let url = app.ui_state.wizard.url.trim().to_string();
+    let path = app.ui_state.wizard.path.trim().to_string();
+    let mut config_to_test = app.config.clone();
+    config_to_test.torrent_url = url.clone();
+    config_to_test.download_path = PathBuf::from(&path);
+    
+    info!("Action: Testing setup wizard configuration for {}", url);
+    if let Err(e) = app.sync_cmd_tx.send(SyncCommand::TestConfig(Box::new(config_to_test))) {
+        error!("Action: Failed to send config test request: {}", e);
+        return;
+    }
+    app.ui_state.wizard.tested = Some((url, path));
+    app.ui_state.config_test_running = true;
+    app.ui_state.config_test_results = None;
+}
+
+// Action to pre-fill the wizard from a qBittorrent or Deluge install
+pub(crate) fn import_client_settings(app: &mut MyApp) {
+    let report = match config::import_from_client() {
+        Ok(import) => {
+            info!("Action: Importing {} settings from {}", import.client.name(), import.source.display());
+            Ok(app.ui_state.wizard.apply_import(&import))
+        }
+        Err(e) => {
+            info!("Action: Client settings import failed: {:#}", e);
+            Err(format!("{:#}", e))
+        }
+    };
+    app.ui_state.wizard.import_report = Some(report);
+}
+
+// Action to write the wizard's answers to the config and close it
+pub(crate) fn finish_setup_wizard(app: &mut MyApp) {
+    let wizard = app.ui_state.wizard.clone();
+    app.config_edit_url = wizard.url.trim().to_string();
+    app.config_edit_path_str = wizard.path.trim().to_string();
+    app.config_edit_should_seed = wizard.should_seed;
+    app.config_edit_max_upload_speed_str = wizard.max_upload_speed_str.trim().to_string();
+    app.config_edit_max_download_speed_str = wizard.max_download_speed_str.trim().to_string();
+    app.config.connection = wizard.connection;
+    
+    info!("Action: Finishing setup wizard");
+    if save_config_changes(app).is_ok() {
+        app.ui_state.modal_state = crate::ui::state::ModalState::None;
+        check_url(app);
+    }
+}
+
+// Action to check in the background that the typed URL serves a torrent
+pub(crate) fn check_url(app: &mut MyApp) {
+    app.ui_state.url_check_due = None;
+    let url = app.config_edit_url.trim().to_string();
+    if url.is_empty() {
+        app.ui_state.url_check = None;
+        return;
     }
+    // Saving an unchanged URL doesn't need another request, unless it was unreachable
+    if let Some((checked, result)) = &app.ui_state.url_check
+        && *checked == url
+        && !matches!(result, Some(crate::sync::preflight::UrlCheck::Unreachable(_)))
+    {
+        return;
+    }
+    if let Err(e) = app.sync_cmd_tx.send(SyncCommand::CheckUrl(url.clone())) {
+        error!("Action: Failed to send URL check request: {}", e);
+        return;
+    }
+    app.ui_state.url_check = Some((url, None));
+}
+
+// Action to start the managed torrent's displayed upload/download counters from zero
+pub(crate) fn reset_torrent_stats(app: &mut MyApp) {
+    let Some((id, stats)) = &app.managed_torrent_stats else {
+        info!("Action: reset_torrent_stats called but no torrent is managed.");
+        return;
+    };
+    let info_hash = match app.api.api_torrent_details((*id).into()) {
+        Ok(details) => details.info_hash,
+        Err(e) => {
+            error!("Action: Failed to get torrent details for stats reset: {}", e);
+            return;
+        }
+    };
+    info!("Action: Resetting stats for torrent {}", id);
+    let baseline = config::StatsBaseline {
+        uploaded_bytes: stats.uploaded_bytes,
+        downloaded_bytes: stats.progress_bytes,
+    };
+    app.config.torrent_stats_baselines.insert(info_hash, baseline);
+    persist_config(app, "stats reset");
+}
+
+// Action to start the session-wide seeding summary counters from zero
+pub(crate) fn reset_global_stats(app: &mut MyApp) {
+    let mut baseline = config::StatsBaseline::default();
+    let torrent_list = app.api.api_torrent_list_ext(librqbit::api::ApiTorrentListOpts { with_stats: true });
+    for stats in torrent_list.torrents.iter().filter_map(|t| t.stats.as_ref()) {
+        baseline.uploaded_bytes += stats.uploaded_bytes;
+        baseline.downloaded_bytes += stats.progress_bytes;
+    }
+    info!("Action: Resetting session stats at {:?}", baseline);
+    app.config.stats_baseline = baseline;
+    persist_config(app, "stats reset");
+}
+
+// Action to hold automatic update checks for a while, or resume them with None
+pub(crate) fn snooze_updates(app: &mut MyApp, choice: Option<crate::sync::snooze::SnoozeChoice>) {
+    let until = choice.map(|choice| choice.until(chrono::Local::now()));
+    info!("Action: Snoozing updates until {:?}", until);
+    app.config.updates_snoozed_until = until;
+    persist_config(app, "update snooze");
+}
+
+// Show a config the sync manager read again from disk, and edit that from now on
+pub(crate) fn adopt_reloaded_config(app: &mut MyApp, config: config::AppConfig) {
+    if app.config.torrent_url != config.torrent_url {
+        app.ui_state.resolved_url = None;
+    }
+    app.config_edit_url = config.torrent_url.clone();
+    app.config_edit_path_str = config.download_path.to_string_lossy().into_owned();
+    app.config_edit_should_seed = config.should_seed;
+    app.config_edit_max_upload_speed_str = config.max_upload_speed.map_or_else(String::new, |v| v.to_string());
+    app.config_edit_max_download_speed_str = config.max_download_speed.map_or_else(String::new, |v| v.to_string());
+    app.ui_state.network_share = crate::sync::network_share::network_share(&config);
+    app.config = config;
+}
+
+// Send the updated config to the sync manager and save it in the background
+fn persist_config(app: &MyApp, what: &'static str) {
+    // Keep the sync manager's copy of the config in step
+    if let Err(e) = app.sync_cmd_tx.send(SyncCommand::UpdateConfig(Box::new(app.config.clone()))) {
+        error!("Action: Failed to send config update to sync manager: {}", e);
+    }
+    save_config_in_background(app, what);
+}
+
+// Save the config file without blocking the UI
+fn save_config_in_background(app: &MyApp, what: &'static str) {
+    let config_clone = app.config.clone();
+    let ui_tx_clone = app.ui_tx.clone();
+    let config_store = app.config_store.clone();
+    tokio::spawn(async move {
+        if let Err(e) = config_store.save(&config_clone) {
+            error!("Error saving {}: {}", what, e);
+            let _ = ui_tx_clone.send(SyncEvent::Error(format!("Failed to save {}: {}", what, e)));
+        }
+    });
 }
 
 // Action to open the download folder in the system file explorer
 pub(crate) fn open_download_folder(app: &MyApp) {
     let path_to_open = app.config.download_path.clone();
     if path_to_open.as_os_str().is_empty() {
-        println!("Action: Cannot open folder, path is not set.");
+        info!("Action: Cannot open folder, path is not set.");
         let _ = app.ui_tx.send(SyncEvent::Error("Download path not configured".to_string()));
         return;
     }
     
     // Ensure the path exists before trying to open it
     if !path_to_open.exists() {
-         println!("Action: Download path does not exist: {}", path_to_open.display());
+         info!("Action: Download path does not exist: {}", path_to_open.display());
          let _ = app.ui_tx.send(SyncEvent::Error(format!("Directory does not exist: {}", path_to_open.display())));
          return;
     }
 
-    println!("Action: Attempting to open folder: {}", path_to_open.display());
+    info!("Action: Attempting to open folder: {}", path_to_open.display());
     // Use opener crate to open the path
     match opener::open(&path_to_open) {
         Ok(_) => {
-            println!("Action: Successfully requested to open folder.");
+            info!("Action: Successfully requested to open folder.");
             // Optionally send a success message to UI?
             // let _ = app.ui_tx.send(SyncEvent::Error("Opened folder".to_string()));
         }
         Err(e) => {
             let err_msg = format!("Failed to open folder {}: {}", path_to_open.display(), e);
-            eprintln!("Action: {}", err_msg);
+            warn!("Action: {}", err_msg);
             let _ = app.ui_tx.send(SyncEvent::Error(err_msg));
         }
     }
@@ -157,7 +522,7 @@ pub(crate) fn open_download_folder(app: &MyApp) {
 
 // Action to update from remote URL using the latest configuration values
 pub(crate) fn update_from_remote(app: &mut MyApp) {
-    println!("Action: Update from remote URL requested");
+    info!("Action: Update from remote URL requested");
     
     // First, update the configuration from the UI fields
     let new_url = app.config_edit_url.trim().to_string();
@@ -165,18 +530,21 @@ pub(crate) fn update_from_remote(app: &mut MyApp) {
     
     // Validate input
     if new_url.is_empty() {
-        println!("Error: Remote URL cannot be empty for update.");
+        info!("Error: Remote URL cannot be empty for update.");
         let _ = app.ui_tx.send(SyncEvent::Error("Remote URL cannot be empty".to_string()));
         return;
     }
     
     if new_path.to_string_lossy().is_empty() {
-        println!("Error: Local path cannot be empty for update.");
+        info!("Error: Local path cannot be empty for update.");
         let _ = app.ui_tx.send(SyncEvent::Error("Local path cannot be empty".to_string()));
         return;
     }
     
     // Update the config in MyApp state
+    if app.config.torrent_url != new_url {
+        app.ui_state.resolved_url = None;
+    }
     app.config.torrent_url = new_url.clone();
     app.config.download_path = new_path;
     
@@ -218,29 +586,22 @@ pub(crate) fn update_from_remote(app: &mut MyApp) {
     let sync_cmd_tx_clone = app.sync_cmd_tx.clone();
     
     // Spawn task to handle file I/O and trigger force download and compare
+    let config_store = app.config_store.clone();
     tokio::spawn(async move {
-        match get_config_path() {
-            Ok(config_path) => {
-                match config::save_config(&config_to_save, &config_path) {
-                    Ok(_) => {
-                        println!("Configuration saved successfully, triggering direct torrent download and comparison.");
-                        let _ = ui_tx_clone.send(SyncEvent::Error("Configuration Updated".to_string()));
-                        
-                        // Instead of TriggerManualRefresh, use our new message
-                        if let Err(e) = sync_cmd_tx_clone.send(SyncCommand::DownloadAndCompare(new_url)) {
-                            eprintln!("Failed to trigger direct download: {}", e);
-                            let _ = ui_tx_clone.send(SyncEvent::Error(format!("Failed to trigger direct download: {}", e)));
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Error saving configuration: {}", e);
-                        let _ = ui_tx_clone.send(SyncEvent::Error(format!("Failed to save config: {}", e)));
-                    }
+        match config_store.save(&config_to_save) {
+            Ok(_) => {
+                info!("Configuration saved successfully, triggering direct torrent download and comparison.");
+                let _ = ui_tx_clone.send(SyncEvent::Error("Configuration Updated".to_string()));
+                
+                // Instead of TriggerManualRefresh, use our new message
+                if let Err(e) = sync_cmd_tx_clone.send(SyncCommand::DownloadAndCompare(new_url)) {
+                    error!("Failed to trigger direct download: {}", e);
+                    let _ = ui_tx_clone.send(SyncEvent::Error(format!("Failed to trigger direct download: {}", e)));
                 }
             }
             Err(e) => {
-                eprintln!("Error getting config path: {}", e);
-                let _ = ui_tx_clone.send(SyncEvent::Error(format!("Failed to get config path: {}", e)));
+                error!("Error saving configuration to {}: {}", config_store.location(), e);
+                let _ = ui_tx_clone.send(SyncEvent::Error(format!("Failed to save config: {}", e)));
             }
         }
     });
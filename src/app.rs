@@ -1,25 +1,31 @@
 // src/app.rs
 
-use crate::config::AppConfig;
+use tracing::{error, info, warn};
+use crate::config::{AppConfig, ConfigStore};
 use crate::ui::utils::SyncStatus;
 use crate::ui::state::UiState;
-use crate::sync::{SyncCommand, SyncEvent};
+use crate::sync::{SyncCommand, SyncEvent, TimestampedEvent};
+use crate::sync::startup::FailedStart;
+use crate::sync::local::initial_check_percent;
+use crate::sync::owner::FolderOwner;
 use eframe::egui;
 use librqbit::api::{Api, TorrentStats};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 use std::path::PathBuf; // Import PathBuf
 use std::sync::Arc;
 use std::collections::HashSet;
+use std::time::Duration;
 
 // Main application struct
 pub struct MyApp {
     pub(crate) api: Api, // librqbit API handle
     pub(crate) managed_torrent_stats: Option<(usize, Arc<TorrentStats>)>,
     // Update channels to use our new message types
-    pub(crate) ui_rx: mpsc::UnboundedReceiver<SyncEvent>,          // Receive events from sync manager
+    pub(crate) ui_rx: mpsc::UnboundedReceiver<TimestampedEvent>,   // Receive stamped events from sync manager
     pub(crate) sync_cmd_tx: mpsc::UnboundedSender<SyncCommand>,    // Send commands to sync manager
     pub(crate) ui_tx: mpsc::UnboundedSender<SyncEvent>,            // For UI thread to send events
     pub(crate) config: AppConfig,                                     // Current application config
+    pub(crate) config_store: Arc<dyn ConfigStore>,                    // Where saved config changes go
     // Temporary fields for UI input before saving
     pub(crate) config_edit_url: String,       // Temp storage for URL input
     pub(crate) config_edit_path_str: String, // Temp storage for path input
@@ -32,8 +38,14 @@ pub struct MyApp {
     pub(crate) missing_files_to_prompt: Option<HashSet<PathBuf>>, // Missing files for prompt
     // New fields for remote update detection
     pub(crate) remote_update: Option<Vec<u8>>, // Torrent content from remote update
+    pub(crate) seeding_confirmation: Option<usize>, // Public torrent waiting for the OK to seed
+    pub(crate) notice_prompt: Option<(usize, String)>, // Torrent waiting for its notice to be accepted, and the notice
+    pub(crate) folder_owner_prompt: Option<(FolderOwner, SyncCommand)>, // Refused command awaiting confirmation
     // Time tracking
     last_refresh: Option<std::time::Instant>, // Track when we last refreshed stats
+    // Sync engine that failed to start, and a retry in progress
+    pub(crate) failed_start: Option<FailedStart>,
+    pub(crate) engine_retry: Option<oneshot::Receiver<Result<Api, FailedStart>>>,
     // UI State (persistent)
     pub(crate) ui_state: UiState, // Store persistent UI state here
 }
@@ -43,9 +55,10 @@ impl MyApp {
     pub fn new(
         api: Api,
         ui_tx: mpsc::UnboundedSender<SyncEvent>,
-        ui_rx: mpsc::UnboundedReceiver<SyncEvent>,
+        ui_rx: mpsc::UnboundedReceiver<TimestampedEvent>,
         sync_cmd_tx: mpsc::UnboundedSender<SyncCommand>,
         initial_config: AppConfig,
+        config_store: Arc<dyn ConfigStore>,
     ) -> Self {
         let config_edit_url = initial_config.torrent_url.clone();
         let config_edit_path_str = initial_config
@@ -59,7 +72,7 @@ impl MyApp {
             .map_or_else(String::new, |v| v.to_string());
         
         // Initialize persistent UI state
-        let initial_ui_state = UiState::new(
+        let mut initial_ui_state = UiState::new(
             config_edit_url.clone(), 
             config_edit_path_str.clone(),
             config_edit_should_seed,
@@ -67,6 +80,9 @@ impl MyApp {
             initial_config.max_download_speed,
         );
         // Potentially set other initial UI state fields here if needed
+        initial_ui_state.file_tree = crate::ui::torrent_file_tree::TorrentFileTree::with_expanded(&initial_config.expanded_folders);
+        initial_ui_state.network_share = crate::sync::network_share::network_share(&initial_config);
+        initial_ui_state.active_encryption_mode = initial_config.encryption_mode;
 
         Self {
             api,
@@ -75,6 +91,7 @@ impl MyApp {
             ui_rx,
             sync_cmd_tx,         // Store the sync command sender
             config: initial_config,
+            config_store,
             config_edit_url,
             config_edit_path_str,
             config_edit_should_seed,
@@ -85,38 +102,126 @@ impl MyApp {
             extra_files_to_prompt: None, // Initialize prompt state
             missing_files_to_prompt: None, // Initialize missing files prompt state
             remote_update: None, // Initialize remote update state
+            seeding_confirmation: None,
+            notice_prompt: None,
+            folder_owner_prompt: None,
             last_refresh: None, // Initialize last refresh state
+            failed_start: None,
+            engine_retry: None,
             ui_state: initial_ui_state, // Store the initialized UI state
         }
     }
+    
+    // Shows the recovery banner for a session that didn't start; `api` is a stand-in until a retry works
+    pub fn show_failed_start(&mut self, failed: FailedStart) {
+        self.ui_state.session_failure = Some(failed.failure.clone());
+        self.ui_state.recovery_port_str = self.config.listen_port.map_or_else(String::new, |port| port.to_string());
+        self.failed_start = Some(failed);
+    }
+
+    // Picks up the result of a retry from the recovery banner
+    fn poll_engine_retry(&mut self) {
+        let Some(retry) = self.engine_retry.as_mut() else {
+            return;
+        };
+        let result = match retry.try_recv() {
+            Ok(result) => result,
+            Err(oneshot::error::TryRecvError::Empty) => return,
+            Err(oneshot::error::TryRecvError::Closed) => {
+                // The retry task died; leave the banner up without a way to retry
+                self.engine_retry = None;
+                self.ui_state.session_retrying = false;
+                return;
+            }
+        };
+        self.engine_retry = None;
+        self.ui_state.session_retrying = false;
+        match result {
+            Ok(api) => {
+                info!("UI: Torrent session started");
+                self.ui_state.active_encryption_mode = self.config.encryption_mode;
+                self.api = api;
+                self.ui_state.session_failure = None;
+                self.last_error = None;
+            }
+            Err(failed) => self.show_failed_start(failed),
+        }
+    }
+
+    // Opens the setup wizard, for first runs without a config file
+    pub fn start_setup_wizard(&mut self) {
+        crate::actions::start_setup_wizard(self);
+    }
 }
 
 // Implement the eframe::App trait for the main application struct
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Schedule a repaint periodically to ensure UI stays fresh
-        ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        self.poll_engine_retry();
+        
+        // A .torrent dropped on the window is taken for "Verify against torrent file"
+        let dropped = ctx.input(|i| {
+            i.raw.dropped_files.iter().filter_map(|file| file.path.clone()).find(|path| {
+                path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("torrent"))
+            })
+        });
+        if let Some(path) = dropped {
+            info!("UI: {} dropped for an offline verify", path.display());
+            self.ui_state.offline_torrent_str = path.display().to_string();
+            self.ui_state.torrent_tab_state = crate::ui::state::TorrentTab::Diagnostics;
+        }
         
         // Process any messages received from the sync task via ui_rx
-        while let Ok(event) = self.ui_rx.try_recv() {
+        while let Ok(TimestampedEvent { seq, at, event }) = self.ui_rx.try_recv() {
+            if let Some(skipped) = self.ui_state.event_sequence.observe(seq) {
+                warn!("UI: Missed {} sync events before #{}", skipped, seq);
+            }
+            self.ui_state.last_event_at = Some(at);
+            self.ui_state.activity.record(&event, at);
             match event {
                 SyncEvent::ManagedTorrentUpdate(torrent_stats_opt) => {
-                    println!("UI received managed torrent stats update: {:?}", torrent_stats_opt.as_ref().map(|(id, _)| id));
+                    info!("UI received managed torrent stats update: {:?}", torrent_stats_opt.as_ref().map(|(id, _)| id));
+                    // Track the initial file check on the faster UI refresh cadence
+                    if let Some((_, stats)) = &torrent_stats_opt {
+                        match initial_check_percent(stats) {
+                            Some(percent) => self.sync_status = SyncStatus::Checking { percent },
+                            None if matches!(self.sync_status, SyncStatus::Checking { .. }) => {
+                                self.sync_status = SyncStatus::LocalActive;
+                            }
+                            None => {}
+                        }
+                    }
                     self.managed_torrent_stats = torrent_stats_opt;
                     self.last_error = None; 
                 }
                 SyncEvent::TorrentAdded(id) => {
-                    println!("UI notified: Torrent {} added/managed", id);
+                    info!("UI notified: Torrent {} added/managed", id);
                     self.last_error = None;
                     self.refresh_current_torrent_stats();
                 }
+                SyncEvent::SyncComplete { info_hash, bytes } => {
+                    // The activity feed already shows the download finishing
+                    info!("UI received SyncComplete: {} ({} bytes)", info_hash, bytes);
+                }
                 SyncEvent::Error(err_msg) => {
-                    eprintln!("UI received error: {}", err_msg);
+                    error!("UI received error: {}", err_msg);
                     self.last_error = Some(err_msg.clone());
                     self.sync_status = SyncStatus::Error(err_msg);
                 }
+                SyncEvent::Warning(message) => {
+                    info!("UI received warning: {}", message);
+                    self.ui_state.last_warning = Some(message);
+                }
+                SyncEvent::PermissionDenied(path) => {
+                    info!("UI received permission denied for {}", path.display());
+                    self.ui_state.permission_denied_path = Some(path);
+                }
                 SyncEvent::StatusUpdate(status) => {
-                    println!("UI received sync status update: {:?}", status);
+                    info!("UI received sync status update: {:?}", status);
+                    // A warning lasts until the next check, which repeats it if it still applies
+                    if status == SyncStatus::CheckingRemote {
+                        self.ui_state.last_warning = None;
+                    }
                     let should_refresh = status == SyncStatus::Idle;
                     self.sync_status = status;
                     if !matches!(self.sync_status, SyncStatus::Error(_)) {
@@ -127,15 +232,21 @@ impl eframe::App for MyApp {
                     }
                 }
                 SyncEvent::ExtraFilesFound(files) => {
-                    println!("UI received ExtraFilesFound: {} files", files.len());
+                    info!("UI received ExtraFilesFound: {} files", files.len());
                     if files.is_empty() {
                         self.extra_files_to_prompt = None;
                     } else {
                         self.extra_files_to_prompt = Some(files);
                     }
                 }
+                SyncEvent::PartialFilesCleaned(files) => {
+                    info!("UI received PartialFilesCleaned: {} files", files.len());
+                    for file in &files {
+                        info!("UI: Removed leftover partial file {}", file.display());
+                    }
+                }
                 SyncEvent::MissingFilesFound(files) => {
-                    println!("UI received MissingFilesFound: {} files", files.len());
+                    info!("UI received MissingFilesFound: {} files", files.len());
                     if files.is_empty() {
                         self.missing_files_to_prompt = None;
                     } else {
@@ -143,9 +254,140 @@ impl eframe::App for MyApp {
                     }
                 }
                 SyncEvent::RemoteUpdateFound(torrent_data) => {
-                    println!("UI received RemoteUpdateFound: {} bytes", torrent_data.len());
+                    info!("UI received RemoteUpdateFound: {} bytes", torrent_data.len());
+                    // With a grace period, the update applies on its own after a countdown
+                    if self.config.update_grace_seconds > 0 && !self.config.confirm_before_update {
+                        let _ = self.sync_cmd_tx.send(SyncCommand::ScheduleUpdate(torrent_data.clone()));
+                    }
                     self.remote_update = Some(torrent_data);
                 }
+                SyncEvent::UpdateCountdown(applies_at) => {
+                    info!("UI received UpdateCountdown: applies at {}", applies_at);
+                    self.ui_state.update_countdown = Some(applies_at);
+                }
+                SyncEvent::UpdateCountdownEnded { applied } => {
+                    info!("UI received UpdateCountdownEnded: applied {}", applied);
+                    self.ui_state.update_countdown = None;
+                    // A cancelled update falls back to the usual prompt
+                    if applied {
+                        self.remote_update = None;
+                    }
+                }
+                SyncEvent::RemoteUpdateSummary(summary) => {
+                    info!("UI received RemoteUpdateSummary: {:?}", summary);
+                    self.ui_state.remote_update_summary = Some(summary);
+                }
+                SyncEvent::Changelog(text) => {
+                    info!("UI received Changelog: {} bytes", text.len());
+                    self.ui_state.changelog = Some(text);
+                }
+                SyncEvent::SeedingConfirmationNeeded(id) => {
+                    info!("UI received SeedingConfirmationNeeded for torrent {}", id);
+                    self.seeding_confirmation = Some(id);
+                }
+                SyncEvent::NoticeAcknowledgementNeeded { id, notice } => {
+                    info!("UI received NoticeAcknowledgementNeeded for torrent {}", id);
+                    self.notice_prompt = Some((id, notice));
+                    self.ui_state.notice_dismissed = false;
+                }
+                SyncEvent::FolderOwnerMismatch { owner, retry } => {
+                    info!("UI received FolderOwnerMismatch: folder owned by {}", owner.torrent_url);
+                    self.folder_owner_prompt = Some((owner, *retry));
+                }
+                SyncEvent::RemoteResolved(resolved_url) => {
+                    info!("UI received RemoteResolved: {}", resolved_url);
+                    self.ui_state.resolved_url = Some(resolved_url);
+                }
+                SyncEvent::ServerListening { name, url } => {
+                    info!("UI received ServerListening: {} at {}", name, url);
+                    self.ui_state.servers.retain(|(existing, _)| *existing != name);
+                    self.ui_state.servers.push((name, url));
+                }
+                SyncEvent::FolderChanged(changes) => {
+                    info!("UI received FolderChanged: {}", changes.describe());
+                    self.ui_state.folder_changes = Some(changes);
+                }
+                SyncEvent::TorrentPublished(published) => {
+                    info!("UI received TorrentPublished: {}", published.path.display());
+                    self.ui_state.folder_changes = None;
+                    self.ui_state.last_published = Some(published);
+                }
+                SyncEvent::FilesPreserved(paths) => {
+                    // Shown in the activity feed
+                    info!("UI received FilesPreserved: {} files", paths.len());
+                }
+                SyncEvent::GlobalSeedGoalMet { uploaded_bytes, ratio } => {
+                    // Shown in the activity feed and the status line
+                    info!("UI received GlobalSeedGoalMet: {} bytes, ratio {:?}", uploaded_bytes, ratio);
+                }
+                SyncEvent::MemoryUsage(usage) => {
+                    self.ui_state.memory_usage = Some(usage);
+                }
+                SyncEvent::FilesRouted(summary) => {
+                    // Shown in the activity feed
+                    info!("UI received routing summary: {:?}", summary);
+                }
+                SyncEvent::ArchivesExtracted(summary) => {
+                    // Shown in the activity feed
+                    info!("UI received extraction summary: {:?}", summary);
+                }
+                SyncEvent::FileModesApplied(summary) => {
+                    // Shown in the activity feed
+                    info!("UI received file permissions summary: {:?}", summary);
+                }
+                SyncEvent::FilePrioritized(path) => {
+                    // Shown in the activity feed
+                    info!("UI received FilePrioritized: {}", path);
+                }
+                SyncEvent::HttpFallbackFetched(summary) => {
+                    // Shown in the activity feed
+                    info!("UI received HTTP fallback summary: {:?}", summary);
+                }
+                SyncEvent::SubsetVerified(result) => {
+                    info!("UI received spot check result: {:?}", result);
+                    self.ui_state.subset_verify_running = false;
+                    self.ui_state.subset_verify = Some(result);
+                }
+                SyncEvent::ConfigApplied { changed } => {
+                    // Shown in the activity feed
+                    info!("UI received ConfigApplied, changed: {:?}", changed);
+                }
+                SyncEvent::ConfigReloaded { config, changed } => {
+                    info!("UI received reloaded config, changed: {:?}", changed);
+                    crate::actions::adopt_reloaded_config(self, *config);
+                }
+                SyncEvent::VersionInfo { version } => {
+                    info!("UI received VersionInfo: {:?}", version);
+                    self.ui_state.synced_version = version;
+                }
+                SyncEvent::OfflineVerified(result) => {
+                    info!("UI received offline verify result: {:?}", result);
+                    self.ui_state.offline_verify_running = false;
+                    self.ui_state.offline_verify = Some(result);
+                }
+                SyncEvent::TrackerTestResult(result) => {
+                    info!("UI received tracker test result: {:?}", result);
+                    self.ui_state.tracker_test_running = false;
+                    self.ui_state.tracker_test = Some(result);
+                }
+                SyncEvent::PortCheckResult(result) => {
+                    info!("UI received port check result: {:?}", result);
+                    self.ui_state.port_check_running = false;
+                    self.ui_state.port_check = Some(result);
+                }
+                SyncEvent::ConfigTestResult { checks } => {
+                    info!("UI received ConfigTestResult: {} of {} checks passed", checks.iter().filter(|c| c.passed).count(), checks.len());
+                    self.ui_state.config_test_running = false;
+                    self.ui_state.config_test_results = Some(checks);
+                }
+                SyncEvent::UrlCheckResult { url, result } => {
+                    // A newer check for another URL may have replaced this one
+                    if let Some((checked, pending)) = &mut self.ui_state.url_check
+                        && *checked == url
+                    {
+                        *pending = Some(result);
+                    }
+                }
             }
         }
         
@@ -153,23 +395,72 @@ impl eframe::App for MyApp {
         // This ensures we always have fresh torrent stats even if no messages are received
         let now = std::time::Instant::now();
         let should_refresh = match self.last_refresh {
-            Some(last) => now.duration_since(last).as_secs() >= 2, // Refresh every 2 seconds
+            Some(last) => now.duration_since(last) >= self.stats_poll_interval(),
             None => true
         };
         
         if should_refresh {
             self.last_refresh = Some(now);
             self.refresh_current_torrent_stats();
+            self.refresh_listen_status();
         }
 
         // Draw the UI elements
         crate::ui::draw_ui(self, ctx);
+        
+        // Only schedule the next repaint on the stats cadence; incoming
+        // sync events wake the UI on their own (see repaint_on_events)
+        ctx.request_repaint_after(self.repaint_interval(ctx));
     }
 }
 
+/// Wraps the sync event receiver so that every incoming event triggers a repaint
+pub fn repaint_on_events(
+    ctx: egui::Context,
+    mut ui_rx: mpsc::UnboundedReceiver<TimestampedEvent>,
+) -> mpsc::UnboundedReceiver<TimestampedEvent> {
+    let (forward_tx, forward_rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(event) = ui_rx.recv().await {
+            if forward_tx.send(event).is_err() {
+                break; // UI has shut down
+            }
+            ctx.request_repaint();
+        }
+    });
+    forward_rx
+}
+
 // Add a helper method to MyApp to refresh the current torrent
 impl MyApp {
+    // Decide how long the UI may sleep before the next repaint
+    fn repaint_interval(&self, ctx: &egui::Context) -> Duration {
+        let hidden = ctx.input(|i| i.viewport().minimized.unwrap_or(false));
+        if hidden && self.managed_torrent_stats.is_none() {
+            // Nothing to show and nobody looking: back off
+            Duration::from_secs(self.config.idle_repaint_secs.max(1))
+        } else {
+            self.stats_poll_interval()
+        }
+    }
+    
+    // How often torrent stats are refreshed while the UI is active
+    pub(crate) fn stats_poll_interval(&self) -> Duration {
+        Duration::from_secs(self.config.stats_poll_interval_secs.max(1))
+    }
+    
     // Helper method to refresh the current torrent stats
+    // Track the bound listen port, remembering once a peer has connected in through it
+    fn refresh_listen_status(&mut self) {
+        let status = crate::sync::port_check::listen_status(&self.api, self.managed_torrent_stats.as_ref().map(|(id, _)| *id));
+        if status.port != self.ui_state.listen_status.port {
+            self.ui_state.incoming_seen = false;
+            self.ui_state.port_check = None;
+        }
+        self.ui_state.incoming_seen |= status.incoming_peers > 0;
+        self.ui_state.listen_status = status;
+    }
+
     fn refresh_current_torrent_stats(&self) {
         if let Some((id, _)) = &self.managed_torrent_stats {
             // Only attempt refresh if we have a managed torrent
@@ -179,11 +470,11 @@ impl MyApp {
                 Ok(stats) => {
                     // Send the updated stats - wrap in Arc
                     if let Err(e) = self.ui_tx.send(SyncEvent::ManagedTorrentUpdate(Some((torrent_id, Arc::new(stats))))) {
-                        eprintln!("Failed to send torrent stats update: {}", e);
+                        error!("Failed to send torrent stats update: {}", e);
                     }
                 }
                 Err(e) => {
-                    eprintln!("Failed to refresh torrent stats {}: {}", torrent_id, e);
+                    error!("Failed to refresh torrent stats {}: {}", torrent_id, e);
                 }
             }
         }
@@ -0,0 +1,87 @@
+// src/cli.rs
+
+//! Command-line arguments. With no subcommand, ModSync starts the GUI.
+
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(name = "modsync", version, about = "Keep a local folder in sync with a remote torrent")]
+pub struct Cli {
+    /// Run as a separate instance with its own config, profiles, cache and log,
+    /// so several can sync different mod sets at the same time
+    #[arg(long, global = true, value_name = "NAME")]
+    pub instance: Option<String>,
+
+    /// Use the named profile's config instead of the default one
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Override a config.toml setting for this run, e.g. --set max_download_speed=500.
+    /// MODSYNC_<KEY> environment variables override these in turn.
+    #[arg(long = "set", global = true, value_name = "KEY=VALUE")]
+    pub set: Vec<String>,
+
+    /// Print the available profiles and exit
+    #[arg(long)]
+    pub list_profiles: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Run the sync engine in the foreground without a GUI (e.g. under systemd).
+    /// Remote updates are applied automatically. Stops cleanly on SIGTERM or Ctrl+C.
+    Daemon,
+
+    /// Hash-check a folder against a .torrent file without touching the network, then exit.
+    /// Exits with an error when files are missing or changed.
+    VerifyTorrent {
+        /// The .torrent file to check against
+        torrent: PathBuf,
+        /// Folder holding the torrent's files; the configured download folder if left out
+        folder: Option<PathBuf>,
+    },
+
+    /// Print the sync state of this instance, asking the running one through its control
+    /// API, then exit. Scripts can branch on the exit code: 1 on an error, 2 when not synced.
+    Status {
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+        /// When to exit with a non-zero code
+        #[arg(long, value_enum, default_value_t = FailOn::NotSynced)]
+        fail_on: FailOn,
+    },
+
+    /// Manage the Windows service
+    #[cfg(windows)]
+    Service {
+        #[command(subcommand)]
+        action: ServiceAction,
+    },
+}
+
+/// When `modsync status` exits with a non-zero code
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailOn {
+    /// On an error, or while the folder isn't synced
+    NotSynced,
+    /// Only on an error
+    Error,
+    /// Never
+    Never,
+}
+
+#[cfg(windows)]
+#[derive(Subcommand, Debug)]
+pub enum ServiceAction {
+    /// Register ModSync as an auto-start Windows service
+    Install,
+    /// Stop and remove the Windows service
+    Uninstall,
+    /// Entry point used by the service control manager
+    Run,
+}
@@ -0,0 +1,216 @@
+// src/config/connection.rs
+
+//! Peer connection settings and the presets offered in the Advanced settings
+
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+use crate::i18n::tr;
+
+/// Connection tuning applied to the librqbit session (takes effect on restart).
+/// The default (everything off) keeps to outgoing connections to tracker peers.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct ConnectionSettings {
+    pub enable_dht: bool, // Find peers through the DHT as well as the tracker
+    pub accept_incoming: bool, // Listen for incoming peer connections
+    pub enable_upnp: bool, // Ask the router to forward the listen port
+    pub peer_connect_timeout_secs: Option<u64>, // None for the librqbit default
+    pub peer_read_write_timeout_secs: Option<u64>, // None for the librqbit default
+}
+
+/// Named bundles of connection settings for users who don't want to tune them by hand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionPreset {
+    Home,
+    Seedbox,
+    Restricted,
+    Custom,
+}
+
+impl ConnectionPreset {
+    /// Presets the user can pick from (Custom is only ever detected)
+    pub const SELECTABLE: [ConnectionPreset; 3] = [Self::Home, Self::Seedbox, Self::Restricted];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Home => tr("preset.home"),
+            Self::Seedbox => tr("preset.seedbox"),
+            Self::Restricted => tr("preset.restricted"),
+            Self::Custom => tr("preset.custom"),
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Home => tr("preset.home_description"),
+            Self::Seedbox => tr("preset.seedbox_description"),
+            Self::Restricted => tr("preset.restricted_description"),
+            Self::Custom => tr("preset.custom_description"),
+        }
+    }
+
+    /// The settings this preset stands for, or None for Custom
+    pub fn settings(&self) -> Option<ConnectionSettings> {
+        match self {
+            Self::Home => Some(ConnectionSettings {
+                enable_dht: true,
+                accept_incoming: true,
+                enable_upnp: true,
+                peer_connect_timeout_secs: None,
+                peer_read_write_timeout_secs: None,
+            }),
+            Self::Seedbox => Some(ConnectionSettings {
+                enable_dht: true,
+                accept_incoming: true,
+                enable_upnp: false,
+                peer_connect_timeout_secs: Some(5),
+                peer_read_write_timeout_secs: Some(15),
+            }),
+            Self::Restricted => Some(ConnectionSettings::default()),
+            Self::Custom => None,
+        }
+    }
+
+    /// Find the preset matching `settings`, falling back to Custom
+    pub fn detect(settings: &ConnectionSettings) -> Self {
+        Self::SELECTABLE
+            .into_iter()
+            .find(|preset| preset.settings().as_ref() == Some(settings))
+            .unwrap_or(Self::Custom)
+    }
+}
+
+/// Which IP stacks ModSync connects over, for networks where one of them is broken
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum IpMode {
+    Ipv4,
+    Ipv6,
+    #[default]
+    Dual,
+}
+
+impl IpMode {
+    pub const ALL: [IpMode; 3] = [Self::Dual, Self::Ipv4, Self::Ipv6];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Ipv4 => tr("ip_mode.ipv4"),
+            Self::Ipv6 => tr("ip_mode.ipv6"),
+            Self::Dual => tr("ip_mode.dual"),
+        }
+    }
+
+    /// Whether a connection to `addr` is allowed in this mode
+    pub fn allows(&self, addr: &SocketAddr) -> bool {
+        match self {
+            Self::Ipv4 => addr.is_ipv4(),
+            Self::Ipv6 => addr.is_ipv6(),
+            Self::Dual => true,
+        }
+    }
+
+    /// Stacks used for torrent file and tracker checks (ModSync's own HTTP requests)
+    pub fn http_stacks(&self) -> &'static str {
+        match self {
+            Self::Ipv4 => "IPv4",
+            Self::Ipv6 => "IPv6",
+            Self::Dual => "IPv4 + IPv6",
+        }
+    }
+
+    /// Stacks librqbit uses for peers, DHT and its own announces. It only listens and
+    /// announces over IPv4, so IPv6-only mode can't reach any peers.
+    pub fn peer_stacks(&self) -> &'static str {
+        match self {
+            Self::Ipv6 => tr("ip_mode.no_peers"),
+            Self::Ipv4 | Self::Dual => "IPv4",
+        }
+    }
+}
+
+/// BitTorrent protocol encryption (MSE/PE) wanted for peer connections, for ISPs that
+/// throttle plain BitTorrent traffic
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EncryptionMode {
+    #[default]
+    Disabled,
+    Enabled, // Encrypt where the peer supports it
+    Forced,  // Refuse unencrypted peer connections
+}
+
+impl EncryptionMode {
+    pub const ALL: [EncryptionMode; 3] = [Self::Disabled, Self::Enabled, Self::Forced];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Disabled => tr("encryption.disabled"),
+            Self::Enabled => tr("encryption.enabled"),
+            Self::Forced => tr("encryption.forced"),
+        }
+    }
+
+    /// What peer connections are with this mode. librqbit has no MSE/PE, so every
+    /// connection it makes is unencrypted.
+    pub fn peer_connections(&self) -> &'static str {
+        match self {
+            Self::Disabled => tr("encryption.peers_disabled"),
+            Self::Enabled => tr("encryption.peers_enabled"),
+            Self::Forced => tr("encryption.peers_forced"),
+        }
+    }
+
+    /// Why the torrent session mustn't start with this mode: forced encryption allows
+    /// no unencrypted connections, and librqbit can't make any other kind
+    pub fn session_refusal(&self) -> Option<&'static str> {
+        (*self == Self::Forced).then_some(
+            "encryption_mode is forced, but librqbit can't encrypt peer connections, so no peer may be connected. \
+             Set encryption_mode to enabled or disabled to download over BitTorrent.",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_detect_themselves() {
+        for preset in ConnectionPreset::SELECTABLE {
+            let settings = preset.settings().unwrap();
+            assert_eq!(ConnectionPreset::detect(&settings), preset);
+        }
+    }
+
+    #[test]
+    fn test_edited_preset_becomes_custom() {
+        let mut settings = ConnectionPreset::Home.settings().unwrap();
+        settings.enable_upnp = false;
+        assert_eq!(ConnectionPreset::detect(&settings), ConnectionPreset::Custom);
+    }
+
+    #[test]
+    fn test_default_is_restricted() {
+        assert_eq!(ConnectionPreset::detect(&ConnectionSettings::default()), ConnectionPreset::Restricted);
+    }
+
+    #[test]
+    fn test_ip_mode_filters_addresses() {
+        let v4: SocketAddr = "192.0.2.1:443".parse().unwrap();
+        let v6: SocketAddr = "[2001:db8::1]:443".parse().unwrap();
+        assert!(IpMode::Dual.allows(&v4) && IpMode::Dual.allows(&v6));
+        assert!(IpMode::Ipv4.allows(&v4) && !IpMode::Ipv4.allows(&v6));
+        assert!(!IpMode::Ipv6.allows(&v4) && IpMode::Ipv6.allows(&v6));
+        assert_eq!(toml::from_str::<std::collections::HashMap<String, IpMode>>("m = \"ipv6\"").unwrap()["m"], IpMode::Ipv6);
+    }
+
+    #[test]
+    fn test_only_forced_encryption_refuses_the_session() {
+        assert_eq!(toml::from_str::<std::collections::HashMap<String, EncryptionMode>>("m = \"forced\"").unwrap()["m"], EncryptionMode::Forced);
+        assert!(EncryptionMode::Forced.session_refusal().is_some());
+        assert_eq!(EncryptionMode::Enabled.session_refusal(), None);
+        assert_eq!(EncryptionMode::Disabled.session_refusal(), None);
+    }
+}
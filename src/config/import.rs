@@ -0,0 +1,217 @@
+// src/config/import.rs
+
+//! Importing settings from a qBittorrent or Deluge install, for users switching over.
+//! Only settings with a ModSync equivalent are taken; the rest are listed as skipped.
+
+use anyhow::{Context, Result, anyhow};
+use directories::BaseDirs;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Torrent clients settings can be imported from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientKind {
+    QBittorrent,
+    Deluge,
+}
+
+impl ClientKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::QBittorrent => "qBittorrent",
+            Self::Deluge => "Deluge",
+        }
+    }
+}
+
+/// One setting taken from the other client
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportedSetting {
+    DownloadPath(PathBuf),
+    MaxUploadSpeed(Option<u64>), // KB/s, None for unlimited
+    MaxDownloadSpeed(Option<u64>), // KB/s, None for unlimited
+    Dht(bool),
+    Upnp(bool),
+}
+
+impl ImportedSetting {
+    /// One line for the import report
+    pub fn describe(&self) -> String {
+        let limit = |value: &Option<u64>| value.map_or("unlimited".to_string(), |kbps| format!("{} KB/s", kbps));
+        let on_off = |value: bool| if value { "on" } else { "off" };
+        match self {
+            Self::DownloadPath(path) => format!("Download folder: {}", path.display()),
+            Self::MaxUploadSpeed(value) => format!("Upload limit: {}", limit(value)),
+            Self::MaxDownloadSpeed(value) => format!("Download limit: {}", limit(value)),
+            Self::Dht(value) => format!("DHT: {}", on_off(*value)),
+            Self::Upnp(value) => format!("UPnP port forwarding: {}", on_off(*value)),
+        }
+    }
+}
+
+/// What was read from the other client's config
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientImport {
+    pub client: ClientKind,
+    pub source: PathBuf,
+    pub settings: Vec<ImportedSetting>,
+    pub skipped: Vec<String>, // Settings found that ModSync has no equivalent for
+}
+
+/// A speed limit in KiB/s, where zero or negative means unlimited
+fn speed_limit(value: f64) -> Option<u64> {
+    (value >= 1.0).then_some(value as u64)
+}
+
+/// Read a qBittorrent.ini/.conf. Newer versions keep settings under
+/// `[BitTorrent] Session\...`; older ones under `[Preferences]`, so both are looked at.
+pub fn parse_qbittorrent(text: &str) -> (Vec<ImportedSetting>, Vec<String>) {
+    let values: HashMap<&str, &str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('[') && !line.starts_with(';') && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim().trim_matches('"')))
+        .collect();
+    let first = |keys: &[&str]| keys.iter().find_map(|key| values.get(key).copied());
+    let number = |keys: &[&str]| first(keys).and_then(|value| value.parse::<f64>().ok());
+    let flag = |keys: &[&str]| first(keys).and_then(|value| value.parse::<bool>().ok());
+
+    let mut settings = Vec::new();
+    if let Some(path) = first(&["Session\\DefaultSavePath", "Downloads\\SavePath"]).filter(|path| !path.is_empty()) {
+        settings.push(ImportedSetting::DownloadPath(PathBuf::from(path)));
+    }
+    if let Some(limit) = number(&["Session\\GlobalUPSpeedLimit", "Connection\\GlobalUPLimit"]) {
+        settings.push(ImportedSetting::MaxUploadSpeed(speed_limit(limit)));
+    }
+    if let Some(limit) = number(&["Session\\GlobalDLSpeedLimit", "Connection\\GlobalDLLimit"]) {
+        settings.push(ImportedSetting::MaxDownloadSpeed(speed_limit(limit)));
+    }
+    if let Some(dht) = flag(&["Session\\DHTEnabled", "Bittorrent\\DHT"]) {
+        settings.push(ImportedSetting::Dht(dht));
+    }
+    if let Some(upnp) = flag(&["PortForwardingEnabled", "Connection\\UPnP"]) {
+        settings.push(ImportedSetting::Upnp(upnp));
+    }
+
+    let mut skipped = Vec::new();
+    if let Some(port) = first(&["Session\\Port", "Connection\\PortRangeMin"]) {
+        skipped.push(format!("Listen port {} (ModSync picks its own port)", port));
+    }
+    (settings, skipped)
+}
+
+/// Read a Deluge core.conf: a small header object followed by the settings object
+pub fn parse_deluge(text: &str) -> Result<(Vec<ImportedSetting>, Vec<String>)> {
+    let core = serde_json::Deserializer::from_str(text)
+        .into_iter::<serde_json::Value>()
+        .filter_map(Result::ok)
+        .last()
+        .context("Deluge config has no settings")?;
+    let number = |key: &str| core.get(key).and_then(serde_json::Value::as_f64);
+    let flag = |key: &str| core.get(key).and_then(serde_json::Value::as_bool);
+
+    let mut settings = Vec::new();
+    if let Some(path) = core.get("download_location").and_then(serde_json::Value::as_str).filter(|path| !path.is_empty()) {
+        settings.push(ImportedSetting::DownloadPath(PathBuf::from(path)));
+    }
+    if let Some(limit) = number("max_upload_speed") {
+        settings.push(ImportedSetting::MaxUploadSpeed(speed_limit(limit)));
+    }
+    if let Some(limit) = number("max_download_speed") {
+        settings.push(ImportedSetting::MaxDownloadSpeed(speed_limit(limit)));
+    }
+    if let Some(dht) = flag("dht") {
+        settings.push(ImportedSetting::Dht(dht));
+    }
+    if let Some(upnp) = flag("upnp") {
+        settings.push(ImportedSetting::Upnp(upnp));
+    }
+
+    let mut skipped = Vec::new();
+    if let Some(port) = core.get("listen_ports").and_then(|ports| ports.get(0)).and_then(serde_json::Value::as_u64) {
+        skipped.push(format!("Listen port {} (ModSync picks its own port)", port));
+    }
+    if number("max_connections_global").is_some_and(|max| max > 0.0) {
+        skipped.push("Connection limit (not configurable in ModSync)".to_string());
+    }
+    Ok((settings, skipped))
+}
+
+/// Config files of supported clients found for this user, most likely first
+pub fn find_client_configs() -> Vec<(ClientKind, PathBuf)> {
+    let Some(dirs) = BaseDirs::new() else {
+        return Vec::new();
+    };
+    let config_dir = dirs.config_dir();
+    [
+        (ClientKind::QBittorrent, config_dir.join("qBittorrent").join("qBittorrent.ini")),
+        (ClientKind::QBittorrent, config_dir.join("qBittorrent").join("qBittorrent.conf")),
+        (ClientKind::Deluge, config_dir.join("deluge").join("core.conf")),
+    ]
+    .into_iter()
+    .filter(|(_, path)| path.is_file())
+    .collect()
+}
+
+/// Read the settings of the first client found
+pub fn import_from_client() -> Result<ClientImport> {
+    let (client, source) = find_client_configs()
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("No qBittorrent or Deluge settings found for this user"))?;
+    let text = std::fs::read_to_string(&source)
+        .with_context(|| format!("Failed to read {} settings: {}", client.name(), source.display()))?;
+    let (settings, skipped) = match client {
+        ClientKind::QBittorrent => parse_qbittorrent(&text),
+        ClientKind::Deluge => parse_deluge(&text)?,
+    };
+    Ok(ClientImport { client, source, settings, skipped })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_qbittorrent_new_and_legacy_keys() {
+        let ini = "[BitTorrent]\nSession\\DefaultSavePath=C:/Users/me/Downloads\nSession\\GlobalUPSpeedLimit=500\n\
+                   Session\\GlobalDLSpeedLimit=0\nSession\\Port=51413\nSession\\DHTEnabled=false\n\n\
+                   [Network]\nPortForwardingEnabled=true\n\n[Preferences]\nDownloads\\SavePath=C:/old\n";
+        let (settings, skipped) = parse_qbittorrent(ini);
+        assert_eq!(settings, vec![
+            ImportedSetting::DownloadPath(PathBuf::from("C:/Users/me/Downloads")),
+            ImportedSetting::MaxUploadSpeed(Some(500)),
+            ImportedSetting::MaxDownloadSpeed(None),
+            ImportedSetting::Dht(false),
+            ImportedSetting::Upnp(true),
+        ]);
+        assert_eq!(skipped, vec!["Listen port 51413 (ModSync picks its own port)".to_string()]);
+
+        let (legacy, _) = parse_qbittorrent("[Preferences]\nConnection\\GlobalDLLimit=-1\nBittorrent\\DHT=true\n");
+        assert_eq!(legacy, vec![ImportedSetting::MaxDownloadSpeed(None), ImportedSetting::Dht(true)]);
+    }
+
+    #[test]
+    fn test_parse_deluge_core_conf() -> Result<()> {
+        let conf = r#"{"file": 1, "format": 1}{
+            "download_location": "/home/me/Downloads",
+            "max_upload_speed": 250.0,
+            "max_download_speed": -1.0,
+            "listen_ports": [6881, 6891],
+            "dht": true,
+            "upnp": false
+        }"#;
+        let (settings, skipped) = parse_deluge(conf)?;
+        assert_eq!(settings, vec![
+            ImportedSetting::DownloadPath(PathBuf::from("/home/me/Downloads")),
+            ImportedSetting::MaxUploadSpeed(Some(250)),
+            ImportedSetting::MaxDownloadSpeed(None),
+            ImportedSetting::Dht(true),
+            ImportedSetting::Upnp(false),
+        ]);
+        assert_eq!(skipped.len(), 1);
+        assert!(parse_deluge("").is_err());
+        Ok(())
+    }
+}
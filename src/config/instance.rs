@@ -0,0 +1,55 @@
+// src/config/instance.rs
+
+//! `--instance <name>`: independent ModSyncs side by side. Each instance keeps its
+//! config, profiles, cache, session state and log under its own `instances/<name>`
+//! folders, so nothing is shared unless a setting points two of them at the same place.
+
+use anyhow::{Result, anyhow};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::profiles::is_valid_profile_name;
+
+/// Instance selected for this process with `--instance`, if any
+static ACTIVE_INSTANCE: OnceLock<String> = OnceLock::new();
+
+/// Select the instance used for the rest of this process. Can only be set once, before
+/// any config path is looked up.
+pub fn set_active_instance(name: &str) -> Result<()> {
+    // Instance names become folder names, like profile names become file names
+    if !is_valid_profile_name(name) {
+        return Err(anyhow!("Invalid instance name '{}': use letters, digits, '-' and '_' only", name));
+    }
+    ACTIVE_INSTANCE
+        .set(name.to_string())
+        .map_err(|_| anyhow!("An instance has already been selected"))
+}
+
+/// The instance selected with `--instance`, or None for the default one
+pub fn active_instance() -> Option<&'static str> {
+    ACTIVE_INSTANCE.get().map(String::as_str)
+}
+
+/// `base` for the default instance, `base/instances/<name>` for a named one
+pub(super) fn instance_dir(base: &Path) -> PathBuf {
+    instance_dir_for(base, active_instance())
+}
+
+fn instance_dir_for(base: &Path, instance: Option<&str>) -> PathBuf {
+    match instance {
+        Some(name) => base.join("instances").join(name),
+        None => base.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_named_instances_get_their_own_folder() {
+        let base = Path::new("/config/ModSync");
+        assert_eq!(instance_dir_for(base, None), PathBuf::from("/config/ModSync"));
+        assert_eq!(instance_dir_for(base, Some("ww2")), PathBuf::from("/config/ModSync/instances/ww2"));
+    }
+}
@@ -3,10 +3,28 @@
 use anyhow::{Context, Result};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 
+mod connection;
+mod import;
+mod instance;
+mod overrides;
+mod profiles;
+mod session_dir;
+mod store;
+pub use connection::{ConnectionPreset, ConnectionSettings, EncryptionMode, IpMode};
+pub use import::{ClientImport, ClientKind, ImportedSetting, import_from_client};
+pub use instance::{active_instance, set_active_instance};
+pub use overrides::set_cli_overrides;
+pub use profiles::{ProfileStore, active_profile, set_active_profile};
+pub use session_dir::{SessionInUse, clear_session_files, get_session_dir, init_session_dir};
+pub use store::{ConfigStore, FileConfigStore, FileStateStore, MemoryConfigStore, MemoryStateStore, STATE_KEYS, StateStore};
+use tracing::{error, info};
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AppConfig {
     pub torrent_url: String,
@@ -14,6 +32,207 @@ pub struct AppConfig {
     pub should_seed: bool,
     pub max_upload_speed: Option<u64>,  // in KB/s, None for unlimited
     pub max_download_speed: Option<u64>, // in KB/s, None for unlimited
+    pub upload_limit_while_downloading: Option<u64>, // in KB/s until full_upload_at_percent is downloaded, None to always allow max_upload_speed
+    pub full_upload_at_percent: u8, // Download progress at which the upload limit rises to max_upload_speed
+    pub allow_download: bool, // Fetch missing pieces; off pauses the torrent until it's allowed again, unless it's finished
+    pub allow_upload: bool, // Send pieces to peers; off pauses a finished torrent and slows uploading to one block a second while downloading
+    pub user_agent: Option<String>, // User-agent for fetching the .torrent, None for ModSync/<version>
+    pub tracker_passkey: Option<String>, // Private tracker passkey put into the announce URLs
+    pub idle_repaint_secs: u64, // UI repaint interval when idle and hidden
+    pub stats_poll_interval_secs: u64, // How often torrent stats are fetched for the UI
+    pub speed_average_window_secs: u64, // Averaging window of the headline speeds, 0 for the raw figures
+    pub max_memory_cache_mb: Option<usize>, // Memory for buffering piece writes, None to write straight to disk; read at startup
+    pub verify_cpu_limit: Option<CpuLimit>, // Cores piece hashing may use, e.g. 2 or 0.5 of them, None for all; read at startup
+    pub preallocate_files: bool, // Reserve each file's full size before downloading instead of sparse files
+    pub verify_after_complete: bool, // Hash every piece again once the torrent finishes, before reporting it complete
+    pub network_share_mode: NetworkShareMode, // Gentler I/O for a download folder on a NAS: auto (detect), on or off; read at startup
+    pub global_seed_goal: Option<SeedGoal>, // Pause all seeding once the session's total ratio or upload reaches this, None for no goal
+    pub min_free_space_mb: Option<u64>, // Pause seeding below this much free space, None to disable
+    pub torrent_label: Option<String>, // Friendly display name for the managed torrent
+    pub pause_while_process_running: Option<String>, // Executable name that pauses torrents while running
+    pub reload_config_on_change: bool, // Re-read config.toml whenever it's written outside ModSync
+    pub pause_on_battery_below: Option<u8>, // Battery percentage below which torrents pause while on battery, None to never pause
+    pub bind_interface: Option<String>, // Interface name or IP (e.g. a VPN) torrents need, paused while it's down
+    pub max_download_duration_minutes: Option<u64>, // Stop downloading after this long, None for no limit
+    pub stop_downloads_at: Option<String>, // "HH:MM" local time to stop downloading, None to disable
+    pub resume_downloads_at: Option<String>, // "HH:MM" local time to resume after a stop, None for manual
+    pub public_seeding_acknowledged: bool, // User agreed to seed public torrents without asking
+    pub require_notice_acknowledgement: bool, // Hold a torrent with a comment until the user accepts it as a notice
+    pub confirm_before_update: bool, // Show what an update would download and wait for the OK
+    pub update_grace_seconds: u64, // Wait this long before an update applies on its own, cancellable; 0 applies straight away
+    pub updates_snoozed_until: Option<chrono::DateTime<chrono::Local>>, // Automatic update checks wait until then, None when not snoozed
+    pub http_fallback_base_url: Option<String>, // Fetch unfinished files from here over HTTP when the swarm has no peers, None to only use peers
+    pub http_fallback_after_seconds: u64, // How long the torrent goes without any peer before the HTTP fallback
+    pub changelog_url: Option<String>, // Text/markdown release notes shown in the update prompt, None to disable
+    pub expected_version: Option<String>, // Game/mod version the synced torrent is, shown after sync and in /status
+    pub version_url: Option<String>, // File whose first line is the torrent's version, fetched with each check; expected_version wins
+    pub completion_marker_path: Option<PathBuf>, // File written while the torrent is complete, removed when a new sync starts; None to disable
+    pub post_download_chmod: Option<u32>, // Mode set on the finished torrent's files, e.g. 0o644; folders also get execute. Unix only
+    pub post_download_chown: Option<String>, // Owner set on the finished torrent's files: "user:group", "user" or ":group". Unix only
+    pub prevent_sleep_during_sync: bool, // Keep the machine awake while a download is progressing
+    pub safe_mode: bool, // Additive-only: never delete, overwrite or re-download files already on disk
+    pub connection: ConnectionSettings, // Peer connection tuning, see ConnectionPreset
+    pub ip_mode: IpMode, // IP stacks to connect over: ipv4, ipv6 or dual
+    pub encryption_mode: EncryptionMode, // Protocol encryption for peers: disabled, enabled or forced; read at startup
+    pub dns_overrides: HashMap<String, IpAddr>, // Host names resolved to fixed addresses, like a hosts file
+    pub force_private: bool, // Keep every torrent off the DHT, as if it were flagged private
+    pub update_strategy: UpdateStrategy, // How updates reach the download folder
+    pub on_missing_torrent: OnMissingTorrent, // What to do when the torrent URL answers 404 or 410
+    pub error_breaker_threshold: u32, // Failures within 30 minutes before automatic retries stop, 0 to never stop
+    pub failure_webhook_url: Option<String>, // Webhook posted a JSON alert when scheduled update checks keep failing, None to disable
+    pub failure_webhook_after: u32, // Scheduled update checks failing in a row before the webhook is posted
+    pub stats_baseline: StatsBaseline, // Session-wide counters at the last stats reset
+    pub torrent_stats_baselines: HashMap<String, StatsBaseline>, // Per-torrent reset points, keyed by info hash
+    pub language: Option<String>, // UI language code, None to follow the system locale
+    pub theme_file: Option<PathBuf>, // JSON theme (colors, rounding, spacing) applied at startup, None for the default look
+    pub progress_categories: Vec<ProgressCategory>, // Progress breakdown groups, empty for top-level folders
+    pub ignore_patterns: Vec<String>, // Globs for local files that are never reported as extra or deleted
+    pub paused_files: Vec<String>, // Torrent files paused from the Files tab, '/'-separated relative paths
+    pub expanded_folders: Vec<String>, // Folders left open in the Files tab, '/'-separated relative paths
+    pub route_rules: Vec<RouteRule>, // Extra destinations for matching files, first match wins; empty to keep one folder
+    pub extract_archives: Vec<String>, // Globs for zip or 7z archives unpacked next to themselves once the torrent completes, empty to disable
+    pub log_level: LogLevel, // Most detailed messages logged: error, warn, info, debug or trace
+    pub log_to_file: bool, // Also append the GUI's log to modsync.log, as headless mode always does; read at startup
+    pub session_dir: Option<PathBuf>, // Where the cached torrent is kept, None for the cache dir; read at startup
+    pub metrics_addr: Option<String>, // host:port serving Prometheus metrics at /metrics, None to disable; read at startup
+    pub control_addr: Option<String>, // host:port serving the per-torrent control API, None to disable; read at startup
+    pub control_token: Option<String>, // Bearer token the control API wants for changes, None to refuse them; read at startup
+    pub listen_port: Option<u16>, // First port tried for incoming peer connections, None for 4240; read at startup
+    pub port_check_url: Option<String>, // Opt-in outside service testing the listen port, "{port}" filled in; None to disable
+    pub bind_fallback_ports: u16, // Following ports tried when metrics_addr/control_addr is taken, 0 to only use the given one
+    pub http_compression: bool, // Gzip/deflate larger metrics and control API replies for clients that accept it; read at startup
+    pub publish_torrent_path: Option<PathBuf>, // Publisher mode: rebuild the torrent from download_path on edits and write it here, None to disable
+    pub publish_debounce_secs: u64, // Quiet time after the last edit before publishing
+    pub overwrite_policy: OverwritePolicy, // Whether locally edited torrent files may be overwritten
+    pub case_collisions: CaseCollisions, // Files differing only by case on a folder that ignores case: keep_first, refuse or ignore
+}
+
+/// A named group of files for the progress breakdown, matched by glob patterns
+/// such as "*.paa" or "**/sounds/**"
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProgressCategory {
+    pub name: String,
+    pub patterns: Vec<String>,
+}
+
+/// Copy files matching `pattern` into `destination` once downloaded, keeping their
+/// path relative to the download folder
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RouteRule {
+    pub pattern: String,
+    pub destination: PathBuf,
+}
+
+/// A CPU budget: a thread count (`2`) or a fraction of the cores (`0.5`)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(untagged)]
+pub enum CpuLimit {
+    Threads(usize),
+    Fraction(f64),
+}
+
+/// An upload target for the whole session: `{ ratio = 2.0 }` or `{ upload_gb = 500 }`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SeedGoal {
+    /// Uploaded bytes over completed bytes, summed over every torrent
+    Ratio(f64),
+    /// Total uploaded, in GB (1024³ bytes)
+    UploadGb(f64),
+}
+
+/// How an update is applied to the download folder
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateStrategy {
+    /// The new torrent downloads straight into the live folder
+    #[default]
+    InPlace,
+    /// The new torrent downloads into a sibling folder that replaces the live one once complete
+    AtomicSwap,
+}
+
+/// Whether a torrent file the user edited since the last sync is downloaded over
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OverwritePolicy {
+    /// librqbit restores every file to the torrent's content
+    #[default]
+    Always,
+    /// Edited files are kept unless the update changes them
+    IfDifferent,
+    /// Edited files are also kept when the update changes them, if the edit is newer than the torrent
+    PreserveNewerLocal,
+}
+
+/// Whether the download folder is treated as a network share
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkShareMode {
+    /// Detect it from the filesystem the folder is on
+    #[default]
+    Auto,
+    /// Always, for shares detection misses, e.g. through a FUSE driver
+    On,
+    /// Never, for fast shares that need no special care
+    Off,
+}
+
+/// What to do with torrent files whose paths differ only by case, when the download
+/// folder can't keep them apart
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseCollisions {
+    /// Download the first of each group in torrent order and leave the others out
+    #[default]
+    KeepFirst,
+    /// Don't add the torrent
+    Refuse,
+    /// Download them all, letting them overwrite each other
+    Ignore,
+}
+
+/// What happens when the server no longer has the torrent file
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OnMissingTorrent {
+    /// Keep seeding the last torrent that was fetched, with a warning
+    #[default]
+    KeepAndSeed,
+    /// Treat it like any other failed check
+    Error,
+}
+
+/// How much is logged, from errors only to everything
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+/// Transfer counters recorded when the user resets stats. This doesn't touch librqbit's
+/// own counters; displayed figures and ratios subtract the baseline instead.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct StatsBaseline {
+    pub uploaded_bytes: u64,
+    pub downloaded_bytes: u64,
+}
+
+impl StatsBaseline {
+    /// (uploaded, downloaded) since the reset. librqbit's upload counter starts from
+    /// zero each session, so a counter below its baseline means it has restarted since.
+    pub fn since_reset(&self, uploaded_bytes: u64, downloaded_bytes: u64) -> (u64, u64) {
+        let since = |current: u64, base: u64| if current >= base { current - base } else { current };
+        (
+            since(uploaded_bytes, self.uploaded_bytes),
+            since(downloaded_bytes, self.downloaded_bytes),
+        )
+    }
 }
 
 impl Default for AppConfig {
@@ -24,6 +243,79 @@ impl Default for AppConfig {
             should_seed: true,  // Default to seeding
             max_upload_speed: None,  // Default to unlimited
             max_download_speed: None,  // Default to unlimited
+            upload_limit_while_downloading: None,
+            full_upload_at_percent: 100,  // Default to raising it once the download is complete
+            allow_download: true,
+            allow_upload: true,
+            user_agent: None,  // Default to the ModSync-branded user-agent
+            tracker_passkey: None,
+            idle_repaint_secs: 30,  // Back off to a slow repaint when idle
+            stats_poll_interval_secs: 1,
+            speed_average_window_secs: 5,
+            max_memory_cache_mb: None,
+            verify_cpu_limit: None,
+            preallocate_files: false,
+            verify_after_complete: false,  // Default to trusting the checks made while downloading
+            network_share_mode: NetworkShareMode::Auto,
+            global_seed_goal: None,
+            min_free_space_mb: None,  // Default to no free space check
+            torrent_label: None,  // Default to the torrent's own name
+            pause_while_process_running: None,  // Default to never pausing for a process
+            reload_config_on_change: false,
+            pause_on_battery_below: None,
+            bind_interface: None,  // Default to using any interface
+            max_download_duration_minutes: None,  // Default to no time limit
+            stop_downloads_at: None,
+            resume_downloads_at: None,
+            public_seeding_acknowledged: false,  // Ask before seeding to a public swarm
+            require_notice_acknowledgement: false,
+            confirm_before_update: false,  // Keep the existing update flow
+            update_grace_seconds: 0, // Apply straight away, as before
+            updates_snoozed_until: None,
+            http_fallback_base_url: None,
+            http_fallback_after_seconds: 300,
+            changelog_url: None,
+            expected_version: None,
+            version_url: None,
+            completion_marker_path: None,
+            post_download_chmod: None,
+            post_download_chown: None,
+            prevent_sleep_during_sync: false,  // Let the OS manage sleep
+            safe_mode: false,
+            connection: ConnectionSettings::default(),  // Default to the Restricted preset
+            ip_mode: IpMode::Dual,
+            encryption_mode: EncryptionMode::Disabled,
+            dns_overrides: HashMap::new(),
+            force_private: false,
+            update_strategy: UpdateStrategy::InPlace,
+            on_missing_torrent: OnMissingTorrent::KeepAndSeed,
+            error_breaker_threshold: 5,
+            failure_webhook_url: None,
+            failure_webhook_after: 3,
+            stats_baseline: StatsBaseline::default(),  // Count from zero
+            torrent_stats_baselines: HashMap::new(),
+            language: None,  // Default to the system locale
+            theme_file: None,
+            progress_categories: Vec::new(),  // Default to grouping by top-level folder
+            ignore_patterns: Vec::new(),
+            paused_files: Vec::new(),
+            expanded_folders: Vec::new(),
+            route_rules: Vec::new(),
+            extract_archives: Vec::new(),
+            log_level: LogLevel::Info,
+            log_to_file: false,
+            session_dir: None,
+            metrics_addr: None,
+            control_addr: None,
+            control_token: None,
+            listen_port: None,
+            port_check_url: None,
+            bind_fallback_ports: 10,
+            http_compression: true,
+            publish_torrent_path: None,
+            publish_debounce_secs: 30,
+            overwrite_policy: OverwritePolicy::Always,
+            case_collisions: CaseCollisions::KeepFirst,
         }
     }
 }
@@ -36,31 +328,123 @@ struct ConfigLoader {
     should_seed: Option<bool>,
     max_upload_speed: Option<u64>,
     max_download_speed: Option<u64>,
+    upload_limit_while_downloading: Option<u64>,
+    full_upload_at_percent: Option<u8>,
+    allow_download: Option<bool>,
+    allow_upload: Option<bool>,
+    user_agent: Option<String>,
+    tracker_passkey: Option<String>,
+    idle_repaint_secs: Option<u64>,
+    stats_poll_interval_secs: Option<u64>,
+    speed_average_window_secs: Option<u64>,
+    max_memory_cache_mb: Option<usize>,
+    verify_cpu_limit: Option<CpuLimit>,
+    preallocate_files: Option<bool>,
+    verify_after_complete: Option<bool>,
+    network_share_mode: Option<NetworkShareMode>,
+    global_seed_goal: Option<SeedGoal>,
+    min_free_space_mb: Option<u64>,
+    torrent_label: Option<String>,
+    pause_while_process_running: Option<String>,
+    reload_config_on_change: Option<bool>,
+    pause_on_battery_below: Option<u8>,
+    bind_interface: Option<String>,
+    max_download_duration_minutes: Option<u64>,
+    stop_downloads_at: Option<String>,
+    resume_downloads_at: Option<String>,
+    public_seeding_acknowledged: Option<bool>,
+    require_notice_acknowledgement: Option<bool>,
+    confirm_before_update: Option<bool>,
+    update_grace_seconds: Option<u64>,
+    updates_snoozed_until: Option<chrono::DateTime<chrono::Local>>,
+    http_fallback_base_url: Option<String>,
+    http_fallback_after_seconds: Option<u64>,
+    changelog_url: Option<String>,
+    expected_version: Option<String>,
+    version_url: Option<String>,
+    completion_marker_path: Option<PathBuf>,
+    post_download_chmod: Option<u32>,
+    post_download_chown: Option<String>,
+    prevent_sleep_during_sync: Option<bool>,
+    safe_mode: Option<bool>,
+    connection: Option<ConnectionSettings>,
+    ip_mode: Option<IpMode>,
+    encryption_mode: Option<EncryptionMode>,
+    dns_overrides: Option<HashMap<String, IpAddr>>,
+    force_private: Option<bool>,
+    update_strategy: Option<UpdateStrategy>,
+    on_missing_torrent: Option<OnMissingTorrent>,
+    error_breaker_threshold: Option<u32>,
+    failure_webhook_url: Option<String>,
+    failure_webhook_after: Option<u32>,
+    stats_baseline: Option<StatsBaseline>,
+    torrent_stats_baselines: Option<HashMap<String, StatsBaseline>>,
+    language: Option<String>,
+    theme_file: Option<PathBuf>,
+    progress_categories: Option<Vec<ProgressCategory>>,
+    ignore_patterns: Option<Vec<String>>,
+    paused_files: Option<Vec<String>>,
+    expanded_folders: Option<Vec<String>>,
+    route_rules: Option<Vec<RouteRule>>,
+    extract_archives: Option<Vec<String>>,
+    log_level: Option<LogLevel>,
+    log_to_file: Option<bool>,
+    session_dir: Option<PathBuf>,
+    metrics_addr: Option<String>,
+    control_addr: Option<String>,
+    control_token: Option<String>,
+    listen_port: Option<u16>,
+    port_check_url: Option<String>,
+    bind_fallback_ports: Option<u16>,
+    http_compression: Option<bool>,
+    publish_torrent_path: Option<PathBuf>,
+    publish_debounce_secs: Option<u64>,
+    overwrite_policy: Option<OverwritePolicy>,
+    case_collisions: Option<CaseCollisions>,
+}
+
+fn project_dirs() -> Result<ProjectDirs> {
+    ProjectDirs::from("com", "ModSync", "ModSync").context("Failed to get project directories")
 }
 
 pub fn get_config_path() -> Result<PathBuf> {
-    let proj_dirs = ProjectDirs::from("com", "ModSync", "ModSync")
-        .context("Failed to get project directories")?;
-    let config_dir = proj_dirs.config_dir();
-    fs::create_dir_all(config_dir)?;
+    // A profile selected on the command line has its own config file
+    if let Some(profile) = active_profile() {
+        return ProfileStore::open()?.path_for(profile);
+    }
+    let config_dir = instance::instance_dir(project_dirs()?.config_dir());
+    fs::create_dir_all(&config_dir)?;
     Ok(config_dir.join("config.toml"))
 }
 
 // Helper to get the application cache directory
 pub fn get_cache_dir() -> Result<PathBuf> {
-    let proj_dirs = ProjectDirs::from("com", "ModSync", "ModSync")
-        .context("Failed to get project directories")?;
-    let cache_dir = proj_dirs.cache_dir();
-    fs::create_dir_all(cache_dir)?;
-    Ok(cache_dir.to_path_buf())
+    let cache_dir = instance::instance_dir(project_dirs()?.cache_dir());
+    fs::create_dir_all(&cache_dir)?;
+    Ok(cache_dir)
 }
 
 // Helper to get the full path for the cached torrent file
 pub fn get_cached_torrent_path() -> Result<PathBuf> {
-    Ok(get_cache_dir()?.join("cached.torrent"))
+    // Keep each profile's torrent separate so they don't trigger each other's updates
+    let [torrent_name, _] = session_dir::session_file_names();
+    Ok(get_session_dir()?.join(torrent_name))
+}
+
+// Helper to get the log file path used by headless/service mode and `log_to_file`
+pub fn get_log_path() -> Result<PathBuf> {
+    let data_dir = instance::instance_dir(project_dirs()?.data_dir());
+    fs::create_dir_all(&data_dir)?;
+    Ok(data_dir.join("modsync.log"))
 }
 
 pub fn load_config(config_path: &Path) -> Result<AppConfig> {
+    let config = load_config_file(config_path)?;
+    // MODSYNC_* environment variables and --set values win over the file
+    overrides::apply_overrides(config, &overrides::active_overrides()?)
+}
+
+fn load_config_file(config_path: &Path) -> Result<AppConfig> {
     if config_path.exists() {
         let mut file = File::open(config_path)
             .with_context(|| format!("Failed to open config file: {}", config_path.display()))?;
@@ -68,27 +452,14 @@ pub fn load_config(config_path: &Path) -> Result<AppConfig> {
         file.read_to_string(&mut contents)
             .with_context(|| format!("Failed to read config file: {}", config_path.display()))?;
         
-        // Try to parse with our loader struct that has all fields optional
-        match toml::from_str::<ConfigLoader>(&contents) {
-            Ok(loader) => {
-                // Create AppConfig with defaults and override with values from file
-                let default_config = AppConfig::default();
-                let config = AppConfig {
-                    torrent_url: loader.torrent_url.unwrap_or(default_config.torrent_url),
-                    download_path: loader.download_path.unwrap_or(default_config.download_path),
-                    should_seed: loader.should_seed.unwrap_or(default_config.should_seed),
-                    max_upload_speed: loader.max_upload_speed.or(default_config.max_upload_speed),
-                    max_download_speed: loader.max_download_speed.or(default_config.max_download_speed),
-                };
-                
-                // Optional: Save config back if it was modified (i.e., defaults were applied)
+        match parse_config(&contents) {
+            Ok((config, needs_upgrade)) => {
+                // Save config back if it was modified (i.e., defaults were applied)
                 // This will update the config file with the new fields
-                if loader.should_seed.is_none() || 
-                   loader.max_upload_speed.is_none() || 
-                   loader.max_download_speed.is_none() {
-                    println!("Upgrading config file with new profile settings fields");
+                if needs_upgrade {
+                    info!("Upgrading config file with new profile settings fields");
                     if let Err(e) = save_config(&config, config_path) {
-                        eprintln!("Failed to upgrade config file: {}", e);
+                        error!("Failed to upgrade config file: {}", e);
                         // Continue anyway, not a fatal error
                     }
                 }
@@ -97,7 +468,7 @@ pub fn load_config(config_path: &Path) -> Result<AppConfig> {
             },
             Err(e) => {
                 // Fallback to more informative error for troubleshooting
-                return Err(anyhow::anyhow!("Failed to parse config file: {} - {}", config_path.display(), e));
+                Err(anyhow::anyhow!("Failed to parse config file: {} - {}", config_path.display(), e))
             }
         }
     } else {
@@ -106,16 +477,142 @@ pub fn load_config(config_path: &Path) -> Result<AppConfig> {
     }
 }
 
+/// The config in the text of a config file, and whether the file lacks the profile
+/// settings fields and should be written back with them
+pub(crate) fn parse_config(contents: &str) -> Result<(AppConfig, bool), toml::de::Error> {
+    // Parse with our loader struct that has all fields optional
+    let loader = toml::from_str::<ConfigLoader>(contents)?;
+    // Create AppConfig with defaults and override with values from file
+    let default_config = AppConfig::default();
+    let config = AppConfig {
+        torrent_url: loader.torrent_url.unwrap_or(default_config.torrent_url),
+        download_path: loader.download_path.unwrap_or(default_config.download_path),
+        should_seed: loader.should_seed.unwrap_or(default_config.should_seed),
+        max_upload_speed: loader.max_upload_speed.or(default_config.max_upload_speed),
+        max_download_speed: loader.max_download_speed.or(default_config.max_download_speed),
+        upload_limit_while_downloading: loader.upload_limit_while_downloading.or(default_config.upload_limit_while_downloading),
+        full_upload_at_percent: loader.full_upload_at_percent.unwrap_or(default_config.full_upload_at_percent),
+        allow_download: loader.allow_download.unwrap_or(default_config.allow_download),
+        allow_upload: loader.allow_upload.unwrap_or(default_config.allow_upload),
+        user_agent: loader.user_agent.or(default_config.user_agent),
+        tracker_passkey: loader.tracker_passkey.or(default_config.tracker_passkey),
+        idle_repaint_secs: loader.idle_repaint_secs.unwrap_or(default_config.idle_repaint_secs),
+        stats_poll_interval_secs: loader.stats_poll_interval_secs.unwrap_or(default_config.stats_poll_interval_secs),
+        speed_average_window_secs: loader.speed_average_window_secs.unwrap_or(default_config.speed_average_window_secs),
+        max_memory_cache_mb: loader.max_memory_cache_mb.or(default_config.max_memory_cache_mb),
+        verify_cpu_limit: loader.verify_cpu_limit.or(default_config.verify_cpu_limit),
+        preallocate_files: loader.preallocate_files.unwrap_or(default_config.preallocate_files),
+        verify_after_complete: loader.verify_after_complete.unwrap_or(default_config.verify_after_complete),
+        network_share_mode: loader.network_share_mode.unwrap_or(default_config.network_share_mode),
+        global_seed_goal: loader.global_seed_goal.or(default_config.global_seed_goal),
+        min_free_space_mb: loader.min_free_space_mb.or(default_config.min_free_space_mb),
+        torrent_label: loader.torrent_label.or(default_config.torrent_label),
+        pause_while_process_running: loader.pause_while_process_running.or(default_config.pause_while_process_running),
+        reload_config_on_change: loader.reload_config_on_change.unwrap_or(default_config.reload_config_on_change),
+        pause_on_battery_below: loader.pause_on_battery_below.or(default_config.pause_on_battery_below),
+        bind_interface: loader.bind_interface.or(default_config.bind_interface),
+        max_download_duration_minutes: loader.max_download_duration_minutes.or(default_config.max_download_duration_minutes),
+        stop_downloads_at: loader.stop_downloads_at.or(default_config.stop_downloads_at),
+        resume_downloads_at: loader.resume_downloads_at.or(default_config.resume_downloads_at),
+        public_seeding_acknowledged: loader.public_seeding_acknowledged.unwrap_or(default_config.public_seeding_acknowledged),
+        require_notice_acknowledgement: loader.require_notice_acknowledgement.unwrap_or(default_config.require_notice_acknowledgement),
+        confirm_before_update: loader.confirm_before_update.unwrap_or(default_config.confirm_before_update),
+        update_grace_seconds: loader.update_grace_seconds.unwrap_or(default_config.update_grace_seconds),
+        updates_snoozed_until: loader.updates_snoozed_until.or(default_config.updates_snoozed_until),
+        http_fallback_base_url: loader.http_fallback_base_url.or(default_config.http_fallback_base_url),
+        http_fallback_after_seconds: loader.http_fallback_after_seconds.unwrap_or(default_config.http_fallback_after_seconds),
+        changelog_url: loader.changelog_url.or(default_config.changelog_url),
+        expected_version: loader.expected_version.or(default_config.expected_version),
+        version_url: loader.version_url.or(default_config.version_url),
+        completion_marker_path: loader.completion_marker_path.or(default_config.completion_marker_path),
+        post_download_chmod: loader.post_download_chmod.or(default_config.post_download_chmod),
+        post_download_chown: loader.post_download_chown.or(default_config.post_download_chown),
+        prevent_sleep_during_sync: loader.prevent_sleep_during_sync.unwrap_or(default_config.prevent_sleep_during_sync),
+        safe_mode: loader.safe_mode.unwrap_or(default_config.safe_mode),
+        connection: loader.connection.unwrap_or(default_config.connection),
+        ip_mode: loader.ip_mode.unwrap_or(default_config.ip_mode),
+        encryption_mode: loader.encryption_mode.unwrap_or(default_config.encryption_mode),
+        dns_overrides: loader.dns_overrides.unwrap_or(default_config.dns_overrides),
+        force_private: loader.force_private.unwrap_or(default_config.force_private),
+        update_strategy: loader.update_strategy.unwrap_or(default_config.update_strategy),
+        on_missing_torrent: loader.on_missing_torrent.unwrap_or(default_config.on_missing_torrent),
+        error_breaker_threshold: loader.error_breaker_threshold.unwrap_or(default_config.error_breaker_threshold),
+        failure_webhook_url: loader.failure_webhook_url.or(default_config.failure_webhook_url),
+        failure_webhook_after: loader.failure_webhook_after.unwrap_or(default_config.failure_webhook_after),
+        stats_baseline: loader.stats_baseline.unwrap_or(default_config.stats_baseline),
+        torrent_stats_baselines: loader.torrent_stats_baselines.unwrap_or(default_config.torrent_stats_baselines),
+        language: loader.language.or(default_config.language),
+        theme_file: loader.theme_file.or(default_config.theme_file),
+        progress_categories: loader.progress_categories.unwrap_or(default_config.progress_categories),
+        ignore_patterns: loader.ignore_patterns.unwrap_or(default_config.ignore_patterns),
+        paused_files: loader.paused_files.unwrap_or(default_config.paused_files),
+        expanded_folders: loader.expanded_folders.unwrap_or(default_config.expanded_folders),
+        route_rules: loader.route_rules.unwrap_or(default_config.route_rules),
+        extract_archives: loader.extract_archives.unwrap_or(default_config.extract_archives),
+        log_level: loader.log_level.unwrap_or(default_config.log_level),
+        log_to_file: loader.log_to_file.unwrap_or(default_config.log_to_file),
+        session_dir: loader.session_dir.or(default_config.session_dir),
+        metrics_addr: loader.metrics_addr.or(default_config.metrics_addr),
+        control_addr: loader.control_addr.or(default_config.control_addr),
+        control_token: loader.control_token.or(default_config.control_token),
+        listen_port: loader.listen_port.or(default_config.listen_port),
+        port_check_url: loader.port_check_url.or(default_config.port_check_url),
+        bind_fallback_ports: loader.bind_fallback_ports.unwrap_or(default_config.bind_fallback_ports),
+        http_compression: loader.http_compression.unwrap_or(default_config.http_compression),
+        publish_torrent_path: loader.publish_torrent_path.or(default_config.publish_torrent_path),
+        publish_debounce_secs: loader.publish_debounce_secs.unwrap_or(default_config.publish_debounce_secs),
+        overwrite_policy: loader.overwrite_policy.unwrap_or(default_config.overwrite_policy),
+        case_collisions: loader.case_collisions.unwrap_or(default_config.case_collisions),
+    };
+
+    // Files from before the profile settings fields get them written in
+    let needs_upgrade = loader.should_seed.is_none()
+        || loader.max_upload_speed.is_none()
+        || loader.max_download_speed.is_none()
+        || loader.idle_repaint_secs.is_none();
+
+    Ok((config, needs_upgrade))
+}
+
 pub fn save_config(config: &AppConfig, config_path: &Path) -> Result<()> {
-    let contents = toml::to_string_pretty(config)
-        .context("Failed to serialize config")?;
-    let mut file = File::create(config_path)
-        .with_context(|| format!("Failed to create config file: {}", config_path.display()))?;
+    let on_disk = fs::read_to_string(config_path).unwrap_or_default();
+    let contents = render_config(config, &on_disk)?;
+    // Written next to it and moved over it, so a reader never sees half a file
+    let mut temp_name = config_path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = config_path.with_file_name(temp_name);
+    let mut file = File::create(&temp_path)
+        .with_context(|| format!("Failed to create config file: {}", temp_path.display()))?;
     file.write_all(contents.as_bytes())
-        .with_context(|| format!("Failed to write config file: {}", config_path.display()))?;
+        .with_context(|| format!("Failed to write config file: {}", temp_path.display()))?;
+    drop(file);
+    fs::rename(&temp_path, config_path)
+        .with_context(|| format!("Failed to replace config file: {}", config_path.display()))?;
     Ok(())
 }
 
+/// The text of a config file holding `config`, keeping what `on_disk`, the file's text
+/// so far, has beyond it
+pub(crate) fn render_config(config: &AppConfig, on_disk: &str) -> Result<String> {
+    let on_disk = on_disk.parse::<toml::Table>().unwrap_or_default();
+    let table = toml::Table::try_from(config).context("Failed to serialize config")?;
+    let table = keep_unknown_keys(table, &on_disk, overrides::config_keys());
+    // Settings given in the environment or with --set stay out of the file
+    let table = overrides::restore_keys(table, &on_disk, &overrides::overridden_keys());
+    toml::to_string_pretty(&table).context("Failed to serialize config")
+}
+
+/// `saved`, plus the keys of `on_disk` that aren't `managed`, e.g. from a newer ModSync or
+/// added by another tool. Managed keys missing from `saved` are left out, as before.
+fn keep_unknown_keys(saved: toml::Table, on_disk: &toml::Table, managed: &[&str]) -> toml::Table {
+    let mut merged: toml::Table = on_disk
+        .iter()
+        .filter(|(key, _)| !managed.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    merged.extend(saved);
+    merged
+}
 
 #[cfg(test)]
 mod tests {
@@ -134,6 +631,7 @@ mod tests {
             should_seed: true,
             max_upload_speed: Some(100),
             max_download_speed: Some(500),
+            ..AppConfig::default()
         };
 
         // Test saving
@@ -152,6 +650,28 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_unknown_keys_survive_a_save() -> Result<()> {
+        let dir = tempdir()?;
+        let config_path = dir.path().join("config.toml");
+        std::fs::write(
+            &config_path,
+            "torrent_url = \"http://old/a.torrent\"\nmax_upload_speed = 50\nfuture_setting = 3\n\n[third_party]\nnote = \"kept\"\n",
+        )?;
+
+        let config = AppConfig { torrent_url: "http://new/a.torrent".to_string(), ..load_config(&config_path)? };
+        save_config(&AppConfig { max_upload_speed: None, ..config }, &config_path)?;
+
+        let saved: toml::Table = std::fs::read_to_string(&config_path)?.parse()?;
+        assert_eq!(saved["torrent_url"].as_str(), Some("http://new/a.torrent"));
+        assert_eq!(saved["future_setting"].as_integer(), Some(3));
+        assert_eq!(saved["third_party"]["note"].as_str(), Some("kept"));
+        // A managed setting that was cleared stays cleared
+        assert!(!saved.contains_key("max_upload_speed"));
+        assert_eq!(load_config(&config_path)?.torrent_url, "http://new/a.torrent");
+        Ok(())
+    }
+
     #[test]
     fn test_load_default_config_if_not_exists() -> Result<()> {
         let dir = tempdir()?;
@@ -160,7 +680,7 @@ mod tests {
         let loaded_config = load_config(&config_path)?;
         assert_eq!(loaded_config.torrent_url, "");
         assert_eq!(loaded_config.download_path, PathBuf::from(""));
-        assert_eq!(loaded_config.should_seed, true);
+        assert!(loaded_config.should_seed);
         assert_eq!(loaded_config.max_upload_speed, None);
         assert_eq!(loaded_config.max_download_speed, None);
 
@@ -185,7 +705,7 @@ mod tests {
         let loaded_config = load_config(&config_path)?;
         assert_eq!(loaded_config.torrent_url, "http://example.com/test.torrent");
         assert_eq!(loaded_config.download_path, PathBuf::from("/tmp/test_download"));
-        assert_eq!(loaded_config.should_seed, true); // Default value
+        assert!(loaded_config.should_seed); // Default value
         assert_eq!(loaded_config.max_upload_speed, None); // Default value
         assert_eq!(loaded_config.max_download_speed, None); // Default value
         
@@ -196,4 +716,12 @@ mod tests {
     // Note: Testing get_config_path() directly is tricky as ProjectDirs
     // might behave differently in test environments or across OSes.
     // Relying on load/save tests implicitly covers its basic usage.
+
+    #[test]
+    fn test_stats_baseline_since_reset() {
+        let baseline = StatsBaseline { uploaded_bytes: 1_000, downloaded_bytes: 5_000 };
+        assert_eq!(baseline.since_reset(1_500, 5_000), (500, 0));
+        // Upload counter restarted with a new session: count it from zero
+        assert_eq!(baseline.since_reset(200, 6_000), (200, 1_000));
+    }
 } 
\ No newline at end of file
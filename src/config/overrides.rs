@@ -0,0 +1,246 @@
+// src/config/overrides.rs
+
+//! Settings given outside config.toml, for containers and seed boxes: `--set KEY=VALUE`
+//! on the command line and `MODSYNC_<KEY>` environment variables. The environment wins
+//! over the command line, which wins over the config file, which wins over the defaults.
+//! Overridden keys keep the config file's value when the config is saved, so a passkey
+//! passed in the environment never ends up on disk.
+
+use anyhow::{Context, Result, anyhow, bail};
+use serde::de::{self, Deserialize, Visitor};
+use std::path::Path;
+use std::sync::OnceLock;
+use tracing::info;
+
+use super::AppConfig;
+
+const ENV_PREFIX: &str = "MODSYNC_";
+
+/// `--set` values given for this process, already split into key and value
+static CLI_OVERRIDES: OnceLock<Vec<(String, String)>> = OnceLock::new();
+
+/// One setting given outside the config file
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Override {
+    pub source: String, // Where it came from, for error messages
+    pub key: String,
+    pub value: String,
+}
+
+/// Record the `--set KEY=VALUE` arguments for every later `load_config`
+pub fn set_cli_overrides(pairs: &[String]) -> Result<()> {
+    let keys = config_keys();
+    let overrides = pairs
+        .iter()
+        .map(|pair| {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid --set '{}': expected KEY=VALUE", pair))?;
+            let key = key.trim();
+            if !keys.contains(&key) {
+                bail!("Unknown setting '{}' in --set", key);
+            }
+            Ok((key.to_string(), value.to_string()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    CLI_OVERRIDES
+        .set(overrides)
+        .map_err(|_| anyhow!("Command-line settings have already been set"))
+}
+
+/// Every AppConfig key, as written in config.toml
+pub(super) fn config_keys() -> &'static [&'static str] {
+    let mut recorder = FieldRecorder::default();
+    let _ = AppConfig::deserialize(&mut recorder);
+    recorder.fields
+}
+
+fn env_name(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.to_uppercase())
+}
+
+/// Command-line overrides followed by environment ones, so the environment is applied last.
+/// `MODSYNC_<KEY>_FILE` names a file holding the value, as Docker and Kubernetes secrets are mounted.
+pub(super) fn active_overrides() -> Result<Vec<Override>> {
+    let mut overrides: Vec<Override> = CLI_OVERRIDES
+        .get()
+        .into_iter()
+        .flatten()
+        .map(|(key, value)| Override { source: format!("--set {}", key), key: key.clone(), value: value.clone() })
+        .collect();
+    for key in config_keys() {
+        let name = env_name(key);
+        let file_name = format!("{}_FILE", name);
+        let value = match std::env::var(&name) {
+            Ok(value) => value,
+            Err(std::env::VarError::NotUnicode(_)) => bail!("{} is not valid UTF-8", name),
+            Err(std::env::VarError::NotPresent) => match std::env::var_os(&file_name) {
+                Some(path) => std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {} from {}", file_name, Path::new(&path).display()))?
+                    .trim_end_matches(['\r', '\n'])
+                    .to_string(),
+                None => continue,
+            },
+        };
+        overrides.push(Override { source: name, key: key.to_string(), value });
+    }
+    Ok(overrides)
+}
+
+/// Keys set by `--set` or the environment, which `save_config` leaves as they are on disk
+pub(super) fn overridden_keys() -> Vec<String> {
+    let mut keys: Vec<String> = CLI_OVERRIDES.get().into_iter().flatten().map(|(key, _)| key.clone()).collect();
+    keys.extend(
+        config_keys()
+            .iter()
+            .filter(|key| {
+                let name = env_name(key);
+                std::env::var_os(&name).is_some() || std::env::var_os(format!("{}_FILE", name)).is_some()
+            })
+            .map(|key| key.to_string()),
+    );
+    keys
+}
+
+/// Apply `overrides` in order. Values are read as TOML (`500`, `true`, `[...]`, `{ ... }`),
+/// falling back to plain text; an empty value unsets an optional setting.
+pub(super) fn apply_overrides(config: AppConfig, overrides: &[Override]) -> Result<AppConfig> {
+    if overrides.is_empty() {
+        return Ok(config);
+    }
+    let mut table = toml::Table::try_from(&config).context("Failed to serialize config")?;
+    for o in overrides {
+        let text = toml::Value::String(o.value.clone());
+        let candidates = if o.value.trim().is_empty() {
+            vec![None, Some(text)]
+        } else {
+            let parsed = format!("value = {}", o.value).parse::<toml::Table>().ok().and_then(|mut t| t.remove("value"));
+            parsed.into_iter().map(Some).chain([Some(text)]).collect()
+        };
+        let mut first_error = None;
+        let accepted = candidates.into_iter().find_map(|candidate| {
+            let mut attempt = table.clone();
+            match candidate {
+                Some(value) => attempt.insert(o.key.clone(), value),
+                None => attempt.remove(&o.key),
+            };
+            match toml::Value::Table(attempt.clone()).try_into::<AppConfig>() {
+                Ok(_) => Some(attempt),
+                Err(e) => {
+                    first_error.get_or_insert(e);
+                    None
+                }
+            }
+        });
+        match accepted {
+            Some(attempt) => table = attempt,
+            None => bail!(
+                "Invalid value for {}: {}",
+                o.source,
+                first_error.map_or_else(String::new, |e| e.message().to_string())
+            ),
+        }
+    }
+    let keys: Vec<&str> = overrides.iter().map(|o| o.key.as_str()).collect();
+    info!("Main: Settings overridden outside the config file: {}", keys.join(", "));
+    toml::Value::Table(table).try_into().context("Failed to apply setting overrides")
+}
+
+/// Put the `keys` of `saved` back to their value in `on_disk`, or remove them if the file
+/// didn't have them
+pub(super) fn restore_keys(mut saved: toml::Table, on_disk: &toml::Table, keys: &[String]) -> toml::Table {
+    for key in keys {
+        match on_disk.get(key) {
+            Some(value) => saved.insert(key.clone(), value.clone()),
+            None => saved.remove(key),
+        };
+    }
+    saved
+}
+
+/// Collects the field names serde hands to `deserialize_struct`, then bails out
+#[derive(Default)]
+struct FieldRecorder {
+    fields: &'static [&'static str],
+}
+
+impl<'de> de::Deserializer<'de> for &mut FieldRecorder {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> std::result::Result<V::Value, Self::Error> {
+        Err(de::Error::custom("only field names are recorded"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        self.fields = fields;
+        Err(de::Error::custom("only field names are recorded"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::UpdateStrategy;
+
+    fn set(source: &str, key: &str, value: &str) -> Override {
+        Override { source: source.to_string(), key: key.to_string(), value: value.to_string() }
+    }
+
+    #[test]
+    fn test_overrides_are_typed_and_applied_in_order() -> Result<()> {
+        let file = AppConfig { max_upload_speed: Some(100), torrent_url: "http://file/a.torrent".to_string(), ..AppConfig::default() };
+        let config = apply_overrides(
+            file,
+            &[
+                set("--set torrent_url", "torrent_url", "http://cli/a.torrent"),
+                set("MODSYNC_TORRENT_URL", "torrent_url", "http://env/a.torrent"),
+                set("MODSYNC_MAX_DOWNLOAD_SPEED", "max_download_speed", "500"),
+                set("MODSYNC_MAX_UPLOAD_SPEED", "max_upload_speed", ""),
+                set("MODSYNC_SHOULD_SEED", "should_seed", "false"),
+                set("MODSYNC_UPDATE_STRATEGY", "update_strategy", "atomic_swap"),
+                set("MODSYNC_TRACKER_PASSKEY", "tracker_passkey", "123456"),
+                set("MODSYNC_IGNORE_PATTERNS", "ignore_patterns", r#"["*.log", "userconfig/**"]"#),
+            ],
+        )?;
+        assert_eq!(config.torrent_url, "http://env/a.torrent");
+        assert_eq!((config.max_download_speed, config.max_upload_speed), (Some(500), None));
+        assert!(!config.should_seed);
+        assert_eq!(config.update_strategy, UpdateStrategy::AtomicSwap);
+        // Looks like a number but the field is text
+        assert_eq!(config.tracker_passkey.as_deref(), Some("123456"));
+        assert_eq!(config.ignore_patterns, vec!["*.log", "userconfig/**"]);
+
+        let error = apply_overrides(AppConfig::default(), &[set("MODSYNC_MAX_DOWNLOAD_SPEED", "max_download_speed", "fast")])
+            .unwrap_err();
+        assert!(error.to_string().contains("MODSYNC_MAX_DOWNLOAD_SPEED"), "{}", error);
+        Ok(())
+    }
+
+    #[test]
+    fn test_overridden_keys_keep_their_file_value_on_save() -> Result<()> {
+        assert!(config_keys().contains(&"tracker_passkey") && config_keys().contains(&"overwrite_policy"));
+        let on_disk: toml::Table = "torrent_url = \"http://file/a.torrent\"\nshould_seed = true\n".parse()?;
+        let config = AppConfig {
+            torrent_url: "http://env/a.torrent".to_string(),
+            tracker_passkey: Some("secret".to_string()),
+            should_seed: false,
+            ..AppConfig::default()
+        };
+        let saved = restore_keys(toml::Table::try_from(&config)?, &on_disk, &["torrent_url".to_string(), "tracker_passkey".to_string()]);
+        assert_eq!(saved["torrent_url"].as_str(), Some("http://file/a.torrent"));
+        assert!(!saved.contains_key("tracker_passkey"));
+        // Keys that weren't overridden are saved as usual
+        assert_eq!(saved["should_seed"].as_bool(), Some(false));
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+// src/config/profiles.rs
+
+//! Named profiles: one config file per setup, stored under `<config dir>/profiles`
+//! (of the active instance)
+
+use anyhow::{Context, Result, anyhow};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use super::instance::instance_dir;
+use super::project_dirs;
+
+/// Profile selected for this process with `--profile`, if any
+static ACTIVE_PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Directory of profile config files, one `<name>.toml` per profile
+pub struct ProfileStore {
+    dir: PathBuf,
+}
+
+impl ProfileStore {
+    /// Open the profile directory in the standard config location, creating it if needed
+    pub fn open() -> Result<Self> {
+        let dir = instance_dir(project_dirs()?.config_dir()).join("profiles");
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create profile directory: {}", dir.display()))?;
+        Ok(Self::at(dir))
+    }
+
+    /// Use an explicit directory as the profile store
+    pub fn at(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Names of all profiles, sorted
+    pub fn list(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        let entries = fs::read_dir(&self.dir)
+            .with_context(|| format!("Failed to read profile directory: {}", self.dir.display()))?;
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "toml")
+                && let Some(name) = path.file_stem().and_then(|s| s.to_str())
+                && is_valid_profile_name(name)
+            {
+                names.push(name.to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Config file path for a profile (which may not exist yet)
+    pub fn path_for(&self, name: &str) -> Result<PathBuf> {
+        if !is_valid_profile_name(name) {
+            return Err(anyhow!(
+                "Invalid profile name '{}': use letters, digits, '-' and '_' only",
+                name
+            ));
+        }
+        Ok(self.dir.join(format!("{}.toml", name)))
+    }
+
+    /// Whether a profile with this name has a config file
+    pub fn contains(&self, name: &str) -> bool {
+        self.path_for(name).is_ok_and(|path| path.is_file())
+    }
+}
+
+/// Profile names double as file names, so keep them to a safe character set
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Select the profile used for the rest of this process. Can only be set once.
+pub fn set_active_profile(name: &str) -> Result<()> {
+    ACTIVE_PROFILE
+        .set(name.to_string())
+        .map_err(|_| anyhow!("A profile has already been selected"))
+}
+
+/// The profile selected with `--profile`, or None for the default config
+pub fn active_profile() -> Option<&'static str> {
+    ACTIVE_PROFILE.get().map(String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_profiles_sorted_toml_only() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        fs::write(dir.path().join("zeus.toml"), "")?;
+        fs::write(dir.path().join("alpha-server.toml"), "")?;
+        fs::write(dir.path().join("notes.txt"), "")?;
+        let store = ProfileStore::at(dir.path().to_path_buf());
+
+        assert_eq!(store.list()?, vec!["alpha-server".to_string(), "zeus".to_string()]);
+        assert!(store.contains("zeus"));
+        assert!(!store.contains("missing"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_profile_names_cannot_escape_directory() {
+        let store = ProfileStore::at(PathBuf::from("/profiles"));
+        assert!(store.path_for("../config").is_err());
+        assert!(store.path_for("a/b").is_err());
+        assert!(store.path_for("").is_err());
+        assert_eq!(store.path_for("main_1").unwrap(), PathBuf::from("/profiles/main_1.toml"));
+    }
+}
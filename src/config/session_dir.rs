@@ -0,0 +1,204 @@
+// src/config/session_dir.rs
+
+//! Where session state lives: the cached torrent ModSync re-adds on startup and its
+//! redirect/ETag metadata. Set once at startup from `session_dir`; when the setting
+//! changes, the files are moved so the next start doesn't have to re-fetch and re-verify.
+//! A lock file next to them stops a second process from using the same session state.
+
+use anyhow::{Context, Result};
+use std::fs::{self, File, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::{error, info, warn};
+
+use super::{STATE_KEYS, active_profile, get_cache_dir};
+
+static SESSION_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Held for the life of the process; the OS releases the lock when it exits
+static SESSION_LOCK: OnceLock<File> = OnceLock::new();
+
+/// Directory holding the session state, defaulting to the platform cache dir
+pub fn get_session_dir() -> Result<PathBuf> {
+    match SESSION_DIR.get() {
+        Some(dir) => Ok(dir.clone()),
+        None => get_cache_dir(),
+    }
+}
+
+/// File names of the active profile's session state
+pub(super) fn session_file_names() -> [String; 2] {
+    let stem = match active_profile() {
+        Some(profile) => format!("cached-{}", profile),
+        None => "cached".to_string(),
+    };
+    [format!("{}.torrent", stem), format!("{}.meta", stem)]
+}
+
+/// Another process holds the session lock
+#[derive(Debug)]
+pub struct SessionInUse {
+    pub lock_path: PathBuf,
+    pub holder: Option<String>, // PID the holder wrote into the lock file
+}
+
+impl std::fmt::Display for SessionInUse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Another ModSync{} is already using this session ({}). Start this one with --instance <name>, another --profile or a different session_dir.",
+            self.holder.as_ref().map(|pid| format!(" (PID {})", pid)).unwrap_or_default(),
+            self.lock_path.display()
+        )
+    }
+}
+
+impl std::error::Error for SessionInUse {}
+
+/// Lock the session state at `lock_path` for this process. Fails with `SessionInUse`
+/// when another process holds it.
+pub fn lock_session_file(lock_path: &Path) -> Result<File> {
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(lock_path)
+        .with_context(|| format!("Failed to open session lock: {}", lock_path.display()))?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            let holder = fs::read_to_string(lock_path).ok().map(|pid| pid.trim().to_string()).filter(|pid| !pid.is_empty());
+            return Err(SessionInUse { lock_path: lock_path.to_path_buf(), holder }.into());
+        }
+        Err(TryLockError::Error(e)) => {
+            return Err(e).with_context(|| format!("Failed to lock session: {}", lock_path.display()));
+        }
+    }
+    file.set_len(0)?;
+    write!(file, "{}", std::process::id())?;
+    Ok(file)
+}
+
+/// Delete this profile's session files (cached torrent, metadata, changelog marker, versions and accepted notice),
+/// for when they're damaged. Nothing else in the session dir is touched. Returns how
+/// many were deleted.
+pub fn clear_session_files() -> Result<usize> {
+    let dir = get_session_dir()?;
+    let [torrent_name, _] = session_file_names();
+    let torrent_path = dir.join(&torrent_name);
+    let mut removed = 0;
+    for path in std::iter::once(torrent_path.clone()).chain(STATE_KEYS.iter().map(|key| torrent_path.with_extension(key))) {
+        match fs::remove_file(&path) {
+            Ok(()) => removed += 1,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("Failed to delete {}", path.display())),
+        }
+    }
+    Ok(removed)
+}
+
+/// Remembers the session dir used last time, kept in the default location
+fn pointer_path() -> Result<PathBuf> {
+    let name = match active_profile() {
+        Some(profile) => format!("session-dir-{}", profile),
+        None => "session-dir".to_string(),
+    };
+    Ok(get_cache_dir()?.join(name))
+}
+
+/// Move the session files from `from` to `to`, leaving any already at `to` alone.
+/// Returns how many were moved.
+pub fn migrate_session_files(from: &Path, to: &Path, file_names: &[String]) -> Result<usize> {
+    let mut moved = 0;
+    for name in file_names {
+        let (source, target) = (from.join(name), to.join(name));
+        if !source.exists() || target.exists() {
+            continue;
+        }
+        // Copy then delete, since a rename can't cross drives
+        if fs::rename(&source, &target).is_err() {
+            fs::copy(&source, &target)
+                .with_context(|| format!("Failed to copy {} to {}", source.display(), target.display()))?;
+            fs::remove_file(&source).with_context(|| format!("Failed to remove {}", source.display()))?;
+        }
+        moved += 1;
+    }
+    Ok(moved)
+}
+
+/// Pick the session dir for this run from `configured`, moving the session files over
+/// if it changed since last time. Call before anything reads the cached torrent; later
+/// calls keep the first choice until restart.
+pub fn init_session_dir(configured: Option<&Path>) -> Result<PathBuf> {
+    if let Some(dir) = SESSION_DIR.get() {
+        return Ok(dir.clone());
+    }
+    let dir = match configured {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => get_cache_dir()?,
+    };
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create session directory: {}", dir.display()))?;
+
+    // Before moving files another process might be using
+    let [torrent_name, _] = session_file_names();
+    let lock = lock_session_file(&dir.join(Path::new(&torrent_name).with_extension("lock")))?;
+    let _ = SESSION_LOCK.set(lock);
+
+    let pointer = pointer_path()?;
+    let previous = fs::read_to_string(&pointer).ok().map(|s| PathBuf::from(s.trim())).unwrap_or(get_cache_dir()?);
+    if previous != dir {
+        match migrate_session_files(&previous, &dir, &session_file_names()) {
+            Ok(moved) => info!(
+                "Main: Session directory changed to {}, moved {} files from {}",
+                dir.display(),
+                moved,
+                previous.display()
+            ),
+            Err(e) => warn!(
+                "Main: Could not move session files to {}: {:#}. The torrent will be fetched and verified again.",
+                dir.display(),
+                e
+            ),
+        }
+    }
+    if let Err(e) = fs::write(&pointer, dir.to_string_lossy().as_bytes()) {
+        error!("Main: Failed to remember session directory: {}", e);
+    }
+
+    Ok(SESSION_DIR.get_or_init(|| dir).clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_moves_files_but_keeps_existing_targets() -> Result<()> {
+        let (from, to) = (tempfile::tempdir()?, tempfile::tempdir()?);
+        fs::write(from.path().join("cached.torrent"), b"old torrent")?;
+        fs::write(from.path().join("cached.meta"), b"old meta")?;
+        fs::write(to.path().join("cached.meta"), b"newer meta")?;
+
+        let names = ["cached.torrent".to_string(), "cached.meta".to_string(), "cached-missing.torrent".to_string()];
+        assert_eq!(migrate_session_files(from.path(), to.path(), &names)?, 1);
+        assert_eq!(fs::read(to.path().join("cached.torrent"))?, b"old torrent");
+        assert!(!from.path().join("cached.torrent").exists());
+        assert_eq!(fs::read(to.path().join("cached.meta"))?, b"newer meta");
+        Ok(())
+    }
+
+    #[test]
+    fn test_second_session_lock_is_refused() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let lock_path = dir.path().join("cached.lock");
+        let held = lock_session_file(&lock_path)?;
+        let error = lock_session_file(&lock_path).unwrap_err().to_string();
+        assert!(error.contains(&format!("PID {}", std::process::id())), "{}", error);
+
+        drop(held);
+        lock_session_file(&lock_path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+// src/config/store.rs
+
+//! Where the config and the small state records kept next to the cached torrent are
+//! persisted. The app goes through [`ConfigStore`] and [`StateStore`] rather than fixed
+//! paths, so tests and embedders can keep them in memory or in a backend of their own.
+//! The file stores are what ModSync itself uses; the memory stores are for tests.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::{AppConfig, get_cached_torrent_path, load_config, overrides, parse_config, render_config, save_config};
+
+/// Names of the records a [`StateStore`] holds
+pub const STATE_KEYS: &[&str] = &["meta", "changelog", "version", "notice"];
+
+/// Loads and saves the app config
+pub trait ConfigStore: Send + Sync {
+    /// The config as ModSync runs with it: the stored settings, defaults for the rest,
+    /// and the environment and `--set` overrides on top
+    fn load(&self) -> Result<AppConfig>;
+
+    /// Load again while running. Stores that can be caught mid-write refuse what looks
+    /// half-written, so the running config is kept.
+    fn reload(&self) -> Result<AppConfig> {
+        self.load()
+    }
+
+    /// Store `config`, keeping stored keys ModSync doesn't know
+    fn save(&self, config: &AppConfig) -> Result<()>;
+
+    /// Where the config is kept, for log and error messages
+    fn location(&self) -> String;
+}
+
+/// `config.toml` at a path, e.g. `get_config_path()`
+#[derive(Debug, Clone)]
+pub struct FileConfigStore {
+    path: PathBuf,
+}
+
+impl FileConfigStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileConfigStore { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl ConfigStore for FileConfigStore {
+    fn load(&self) -> Result<AppConfig> {
+        load_config(&self.path)
+    }
+
+    fn reload(&self) -> Result<AppConfig> {
+        // A file emptied mid-write would otherwise load as all defaults
+        match std::fs::read_to_string(&self.path) {
+            Ok(contents) if contents.trim().is_empty() => anyhow::bail!("{} is empty", self.path.display()),
+            _ => self.load(),
+        }
+    }
+
+    fn save(&self, config: &AppConfig) -> Result<()> {
+        save_config(config, &self.path)
+    }
+
+    fn location(&self) -> String {
+        self.path.display().to_string()
+    }
+}
+
+/// The text of a config file held in memory. Loading parses and upgrades it like a
+/// file on disk, so migrations and damaged configs can be tested without one.
+#[derive(Debug, Default)]
+pub struct MemoryConfigStore {
+    contents: Mutex<Option<String>>, // None until saved, like a missing file
+}
+
+impl MemoryConfigStore {
+    /// A store holding `contents` as if it were the config file
+    pub fn with_contents(contents: &str) -> Self {
+        MemoryConfigStore { contents: Mutex::new(Some(contents.to_string())) }
+    }
+
+    /// The stored text, None if nothing was stored yet
+    pub fn contents(&self) -> Option<String> {
+        self.contents.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl ConfigStore for MemoryConfigStore {
+    fn load(&self) -> Result<AppConfig> {
+        let config = match self.contents() {
+            Some(contents) => {
+                let (config, needs_upgrade) = parse_config(&contents).context("Failed to parse in-memory config")?;
+                if needs_upgrade {
+                    self.save(&config)?;
+                }
+                config
+            }
+            None => AppConfig::default(),
+        };
+        overrides::apply_overrides(config, &overrides::active_overrides()?)
+    }
+
+    fn save(&self, config: &AppConfig) -> Result<()> {
+        let mut contents = self.contents.lock().unwrap_or_else(|e| e.into_inner());
+        *contents = Some(render_config(config, contents.as_deref().unwrap_or_default())?);
+        Ok(())
+    }
+
+    fn location(&self) -> String {
+        "in-memory config".to_string()
+    }
+}
+
+/// Small text records ModSync keeps between runs, by one of [`STATE_KEYS`]: "meta" for
+/// the redirect and ETag metadata, "changelog" for the last shown release notes,
+/// "version" for the versions of recent torrents and "notice" for the accepted notice.
+pub trait StateStore: Send + Sync {
+    /// The record `key`, None if it was never written
+    fn read(&self, key: &str) -> Result<Option<String>>;
+
+    /// Replace the record `key` with `contents`
+    fn write(&self, key: &str, contents: &str) -> Result<()>;
+}
+
+/// Records kept as files next to the cached torrent, named after it with the key as
+/// the extension, e.g. `cached.notice`
+#[derive(Debug, Clone)]
+pub struct FileStateStore {
+    torrent_path: PathBuf,
+}
+
+impl FileStateStore {
+    /// Records next to the cached torrent at `torrent_path`
+    pub fn new(torrent_path: impl Into<PathBuf>) -> Self {
+        FileStateStore { torrent_path: torrent_path.into() }
+    }
+
+    /// Records of the active profile's session
+    pub fn session() -> Result<Self> {
+        Ok(FileStateStore::new(get_cached_torrent_path()?))
+    }
+
+    /// File holding the record `key`
+    pub fn path_of(&self, key: &str) -> PathBuf {
+        self.torrent_path.with_extension(key)
+    }
+}
+
+impl StateStore for FileStateStore {
+    fn read(&self, key: &str) -> Result<Option<String>> {
+        let path = self.path_of(key);
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    fn write(&self, key: &str, contents: &str) -> Result<()> {
+        let path = self.path_of(key);
+        std::fs::write(&path, contents).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Records held in memory, for tests
+#[derive(Debug, Default)]
+pub struct MemoryStateStore {
+    records: Mutex<HashMap<String, String>>,
+}
+
+impl StateStore for MemoryStateStore {
+    fn read(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.records.lock().unwrap_or_else(|e| e.into_inner()).get(key).cloned())
+    }
+
+    fn write(&self, key: &str, contents: &str) -> Result<()> {
+        self.records.lock().unwrap_or_else(|e| e.into_inner()).insert(key.to_string(), contents.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_config_is_upgraded_and_keeps_unknown_keys() -> Result<()> {
+        let store = MemoryConfigStore::with_contents("torrent_url = \"http://a/mods.torrent\"\nfuture_setting = 3\n");
+        let config = store.load()?;
+        assert_eq!(config.torrent_url, "http://a/mods.torrent");
+        // An old config is written back with the profile settings filled in
+        let upgraded: toml::Table = store.contents().unwrap_or_default().parse()?;
+        assert!(upgraded.contains_key("should_seed"));
+        assert_eq!(upgraded["future_setting"].as_integer(), Some(3));
+
+        store.save(&AppConfig { torrent_url: "http://b/mods.torrent".to_string(), ..config })?;
+        assert_eq!(store.load()?.torrent_url, "http://b/mods.torrent");
+
+        assert!(MemoryConfigStore::with_contents("torrent_url = [").load().is_err(), "a damaged config is an error");
+        assert_eq!(MemoryConfigStore::default().load()?.torrent_url, "", "nothing stored loads the defaults");
+        Ok(())
+    }
+
+    #[test]
+    fn test_state_records() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let file_store = FileStateStore::new(dir.path().join("cached.torrent"));
+        let memory_store = MemoryStateStore::default();
+        for store in [&file_store as &dyn StateStore, &memory_store] {
+            assert_eq!(store.read("notice")?, None);
+            store.write("notice", "abc")?;
+            assert_eq!(store.read("notice")?.as_deref(), Some("abc"));
+            assert_eq!(store.read("version")?, None);
+        }
+        assert!(dir.path().join("cached.notice").exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,339 @@
+// src/daemon.rs
+
+//! Headless mode: runs the sync engine without a GUI, for seed boxes and services.
+//! Prompts the GUI would show are answered automatically: remote updates are
+//! applied (unless `confirm_before_update` is set) and missing files re-fetched. Extra files are only reported, never deleted.
+
+use anyhow::Result;
+use librqbit::Api;
+use std::future::Future;
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use crate::config::AppConfig;
+use crate::sync::{self, SyncCommand, SyncEvent, TimestampedEvent};
+use crate::ui::utils::{SyncStatus, format_size};
+
+/// Logs the daemon's lines, tagged with the event being handled
+#[derive(Default)]
+pub struct DaemonLog {
+    pub event: Option<(u64, chrono::DateTime<chrono::Local>)>, // Event being logged: its number and when it was sent
+}
+
+impl DaemonLog {
+    pub fn line(&mut self, message: &str) {
+        match self.event {
+            Some((seq, at)) => info!(event = seq, sent = %at.format("%H:%M:%S%.3f"), "{}", message),
+            None => info!("{}", message),
+        }
+    }
+}
+
+/// Resolves when the process is asked to stop (Ctrl+C, or SIGTERM on Unix)
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {},
+                    _ = sigterm.recv() => {},
+                }
+            }
+            Err(e) => {
+                error!("Daemon: Failed to install SIGTERM handler: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Run the sync engine until `shutdown` resolves, then stop the session cleanly
+pub async fn run_headless(config: AppConfig, shutdown: impl Future<Output = ()>) -> Result<()> {
+    // Headless mode always keeps a log file
+    crate::logging::init(config.log_level, true);
+    let mut log = DaemonLog::default();
+    log.line(&format!(
+        "ModSync {} starting headless. URL: {}, path: {}",
+        env!("CARGO_PKG_VERSION"),
+        config.torrent_url,
+        config.download_path.display()
+    ));
+
+    let session = sync::session::create_session(&config).await?;
+    let api = Api::new(session.clone(), None);
+    let initial_torrent_id = sync::session::add_cached_torrent(&api, &config).await;
+
+    let (stamped_tx, mut ui_rx) = mpsc::unbounded_channel::<TimestampedEvent>();
+    let ui_tx = sync::stamp::stamp_events(stamped_tx);
+    let (sync_cmd_tx, sync_cmd_rx) = mpsc::unbounded_channel::<SyncCommand>();
+
+    let manager = tokio::spawn(sync::run_sync_manager(
+        config.clone(),
+        api.clone(),
+        ui_tx,
+        sync_cmd_rx,
+        initial_torrent_id,
+    ));
+
+    // Check the remote straight away rather than waiting for the periodic check
+    if !config.torrent_url.is_empty() {
+        let _ = sync_cmd_tx.send(SyncCommand::DownloadAndCompare(config.torrent_url.clone()));
+    }
+
+    let mut responder = EventResponder {
+        confirm_before_update: config.confirm_before_update,
+        ..EventResponder::default()
+    };
+    tokio::pin!(shutdown);
+    loop {
+        tokio::select! {
+            Some(TimestampedEvent { seq, at, event }) = ui_rx.recv() => {
+                if let Some(skipped) = responder.sequence.observe(seq) {
+                    log.line(&format!("Missed {} events before #{}", skipped, seq));
+                }
+                log.event = Some((seq, at));
+                responder.handle(event, &sync_cmd_tx, &mut log);
+                log.event = None;
+            }
+            _ = &mut shutdown => {
+                log.line("Shutdown requested, stopping sync manager");
+                break;
+            }
+        }
+    }
+
+    // Let the manager finish what it's doing, then flush the session
+    let _ = sync_cmd_tx.send(SyncCommand::Shutdown);
+    match manager.await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => log.line(&format!("Sync manager exited with error: {}", e)),
+        Err(e) => log.line(&format!("Sync manager task failed: {}", e)),
+    }
+    session.stop().await;
+    log.line("Session stopped, exiting");
+    Ok(())
+}
+
+/// Logs sync events and answers the prompts the GUI would otherwise show
+#[derive(Default)]
+struct EventResponder {
+    last_status: Option<SyncStatus>,
+    last_logged_percent: Option<u64>,
+    last_resolved_url: Option<String>,
+    confirm_before_update: bool,
+    sequence: sync::stamp::SequenceTracker,
+}
+
+impl EventResponder {
+    fn handle(&mut self, event: SyncEvent, sync_cmd_tx: &mpsc::UnboundedSender<SyncCommand>, log: &mut DaemonLog) {
+        match event {
+            SyncEvent::ManagedTorrentUpdate(Some((id, stats))) => {
+                let percent = (stats.progress_bytes * 100)
+                    .checked_div(stats.total_bytes)
+                    .unwrap_or(0);
+                // Only log whole-percent changes to keep the log readable
+                if self.last_logged_percent != Some(percent) {
+                    self.last_logged_percent = Some(percent);
+                    log.line(&format!("Torrent {}: {}% ({} of {} bytes)", id, percent, stats.progress_bytes, stats.total_bytes));
+                }
+            }
+            SyncEvent::ManagedTorrentUpdate(None) => {}
+            SyncEvent::TorrentAdded(id) => {
+                log.line(&format!("Torrent {} added", id));
+            }
+            SyncEvent::SyncComplete { info_hash, bytes } => {
+                log.line(&format!("Sync complete: {} ({})", info_hash, format_size(bytes)));
+            }
+            SyncEvent::Error(message) => {
+                log.line(&format!("Message: {}", message));
+            }
+            SyncEvent::Warning(message) => {
+                log.line(&format!("Warning: {}", message));
+            }
+            SyncEvent::PermissionDenied(path) => {
+                log.line(&format!("Permission denied writing to {}: set download_path to a folder this user can write to", path.display()));
+            }
+            SyncEvent::StatusUpdate(status) => {
+                if self.last_status.as_ref() != Some(&status) {
+                    log.line(&status.display_text());
+                    self.last_status = Some(status);
+                }
+            }
+            SyncEvent::ExtraFilesFound(files) => {
+                if !files.is_empty() {
+                    log.line(&format!("{} extra files found in download folder (not deleted in headless mode)", files.len()));
+                }
+            }
+            SyncEvent::PartialFilesCleaned(files) => {
+                for file in &files {
+                    log.line(&format!("Removed partial file no longer in the torrent: {}", file.display()));
+                }
+            }
+            SyncEvent::MissingFilesFound(files) => {
+                log.line(&format!("{} files missing, re-fetching", files.len()));
+                let _ = sync_cmd_tx.send(SyncCommand::FixMissingFiles);
+            }
+            SyncEvent::SeedingConfirmationNeeded(id) => {
+                log.line(&format!(
+                    "Torrent {} is public and stays paused: set public_seeding_acknowledged = true in the config to seed it",
+                    id
+                ));
+            }
+            SyncEvent::NoticeAcknowledgementNeeded { id, notice } => {
+                // Accepting is for a person to do; headless instances wait until they have
+                log.line(&format!(
+                    "Torrent {} stays paused until its notice is accepted in the GUI:\n{}",
+                    id, notice
+                ));
+            }
+            SyncEvent::FolderOwnerMismatch { owner, .. } => {
+                // Never take over another torrent's folder without a person confirming it
+                log.line(&format!(
+                    "Download folder belongs to {}, skipping (claim it from the GUI or change download_path)",
+                    owner.torrent_url
+                ));
+            }
+            SyncEvent::ServerListening { name, url } => log.line(&format!("{} listening at {}", name, url)),
+            SyncEvent::VersionInfo { version: Some(version) } => log.line(&format!("Synced folder is version {}", version)),
+            SyncEvent::VersionInfo { version: None } => {}
+            SyncEvent::FolderChanged(changes) => log.line(&format!("Download folder changed: {}", changes.describe())),
+            SyncEvent::TorrentPublished(published) => log.line(&format!(
+                "Published {} ({} files, {}) to {}",
+                published.info_hash,
+                published.file_count,
+                format_size(published.total_bytes),
+                published.path.display()
+            )),
+            SyncEvent::FilesPreserved(paths) => {
+                log.line(&format!("Kept {} locally edited files: {}", paths.len(), paths.join(", ")))
+            }
+            SyncEvent::GlobalSeedGoalMet { uploaded_bytes, ratio } => log.line(&format!(
+                "Seeding goal reached: {} uploaded{}, seeding paused",
+                format_size(uploaded_bytes),
+                ratio.map(|ratio| format!(", ratio {:.2}", ratio)).unwrap_or_default()
+            )),
+            // Low memory shows up as a Warning; the readings themselves are for the GUI
+            SyncEvent::MemoryUsage(_) => {}
+            SyncEvent::RemoteResolved(resolved_url) => {
+                // Only worth a line when the redirector starts pointing somewhere new
+                if self.last_resolved_url.as_ref() != Some(&resolved_url) {
+                    log.line(&format!("Torrent URL resolves to {}", resolved_url));
+                    self.last_resolved_url = Some(resolved_url);
+                }
+            }
+            SyncEvent::FilesRouted(summary) => {
+                for folder in &summary.folders {
+                    log.line(&format!("Routed {} files to {}", folder.files, folder.destination.display()));
+                }
+                for (path, error) in &summary.failed {
+                    log.line(&format!("Could not route {}: {}", path.display(), error));
+                }
+            }
+            SyncEvent::ArchivesExtracted(summary) => {
+                for archive in &summary.archives {
+                    log.line(&format!("Extracted {} files from {}", archive.files, archive.archive));
+                }
+                for (entry, why) in &summary.skipped {
+                    log.line(&format!("Skipped {}: {}", entry, why));
+                }
+                for (archive, error) in &summary.failed {
+                    log.line(&format!("Could not extract {}: {}", archive, error));
+                }
+            }
+            SyncEvent::FileModesApplied(summary) => {
+                log.line(&format!(
+                    "Set permissions on {} files and {} folders, {} failed",
+                    summary.files,
+                    summary.folders,
+                    summary.failed.len()
+                ));
+            }
+            SyncEvent::FilePrioritized(path) => log.line(&format!("Downloading {} first", path)),
+            SyncEvent::HttpFallbackFetched(summary) => {
+                log.line(&format!("No peers, fetched {} files ({}) over HTTP", summary.fetched.len(), format_size(summary.bytes)));
+                for (file, error) in &summary.failed {
+                    log.line(&format!("Could not fetch {} over HTTP: {}", file, error));
+                }
+            }
+            SyncEvent::SubsetVerified(result) => match result {
+                Ok(report) => log.line(&format!(
+                    "Spot check: {} pieces of {} files checked, {} bad: {:?}",
+                    report.pieces_checked,
+                    report.files_checked,
+                    report.bad_files.len(),
+                    report.bad_files
+                )),
+                Err(e) => log.line(&format!("Spot check failed: {}", e)),
+            },
+            SyncEvent::ConfigReloaded { changed, .. } if changed.is_empty() => log.line("Config reloaded, nothing changed"),
+            SyncEvent::ConfigReloaded { changed, .. } => log.line(&format!("Config reloaded, changed: {}", changed.join(", "))),
+            SyncEvent::ConfigApplied { changed } if !changed.is_empty() => log.line(&format!("Settings applied, changed: {}", changed.join(", "))),
+            SyncEvent::ConfigApplied { .. } => {}
+            SyncEvent::OfflineVerified(result) => match result {
+                Ok(report) => log.line(&format!(
+                    "Offline verify of {}: {} files, {} missing, {} changed",
+                    report.torrent_name,
+                    report.files,
+                    report.missing.len(),
+                    report.mismatched.len()
+                )),
+                Err(e) => log.line(&format!("Offline verify failed: {}", e)),
+            },
+            SyncEvent::TrackerTestResult(result) => match result {
+                Ok(reports) => {
+                    for report in reports {
+                        log.line(&format!("Tracker {} ({} ms): {:?}", report.url, report.latency_ms, report.result));
+                    }
+                }
+                Err(e) => log.line(&format!("Tracker test failed: {}", e)),
+            },
+            SyncEvent::PortCheckResult(result) => match result {
+                Ok(check) => log.line(&format!("Listen port {} is {}", check.port, if check.open { "open" } else { "closed" })),
+                Err(e) => log.line(&format!("Port check failed: {}", e)),
+            },
+            SyncEvent::ConfigTestResult { checks } => {
+                for check in checks {
+                    let result = if check.passed { "ok" } else { "FAILED" };
+                    log.line(&format!("Config test {:?}: {} ({})", check.kind, result, check.detail));
+                }
+            }
+            SyncEvent::UrlCheckResult { url, result } => {
+                log.line(&format!("URL check for {}: {:?}", url, result));
+            }
+            SyncEvent::RemoteUpdateSummary(summary) => {
+                log.line(&format!(
+                    "Update would change {} files, {} to download",
+                    summary.files_changed,
+                    format_size(summary.bytes_to_download)
+                ));
+            }
+            SyncEvent::Changelog(text) => {
+                log.line(&format!("Changelog for the update:\n{}", text.trim()));
+            }
+            SyncEvent::RemoteUpdateFound(torrent_data) => {
+                if self.confirm_before_update {
+                    // Nobody is here to confirm; stay on the current torrent
+                    log.line("Remote update found, not applied: confirm_before_update is set (apply it from the GUI)");
+                    return;
+                }
+                log.line(&format!("Remote update found ({} bytes), applying", torrent_data.len()));
+                // Waits out update_grace_seconds first, if set
+                let _ = sync_cmd_tx.send(SyncCommand::ScheduleUpdate(torrent_data));
+            }
+            SyncEvent::UpdateCountdown(applies_at) => {
+                log.line(&format!("Update applies at {} unless cancelled", applies_at.format("%H:%M:%S")));
+            }
+            SyncEvent::UpdateCountdownEnded { applied } => {
+                if !applied {
+                    log.line("Update countdown cancelled; the update was not applied");
+                }
+            }
+        }
+    }
+}
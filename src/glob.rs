@@ -0,0 +1,50 @@
+// src/glob.rs
+
+//! Glob patterns over the '/'-separated paths of torrent files, shared by the progress
+//! categories, `ignore_patterns`, route rules and archive extraction.
+
+/// Case-insensitive glob match on a '/'-separated path. `*` and `?` stay within one
+/// folder, `**/` spans any number of folders, and a pattern without a '/' is matched
+/// against the file name alone (so "*.paa" matches in every folder).
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<char> = pattern.trim().to_lowercase().chars().collect();
+    let path = path.to_lowercase();
+    let target = if pattern.contains(&'/') {
+        path.as_str()
+    } else {
+        path.rsplit('/').next().unwrap_or_default()
+    };
+    let target: Vec<char> = target.chars().collect();
+    matches(&pattern, &target)
+}
+
+fn matches(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', '/', rest @ ..] => {
+            (0..=text.len()).any(|i| (i == 0 || text[i - 1] == '/') && matches(rest, &text[i..]))
+        }
+        ['*', '*'] => true,
+        ['*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| matches(rest, &text[i..])),
+        ['?', rest @ ..] => text.first().is_some_and(|c| *c != '/') && matches(rest, &text[1..]),
+        [c, rest @ ..] => text.first() == Some(c) && matches(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.paa", "addons/textures/ground.PAA"));
+        assert!(glob_match("addons/*.pbo", "addons/core.pbo"));
+        assert!(!glob_match("addons/*.pbo", "addons/sub/core.pbo"));
+        assert!(glob_match("**/sounds/**", "mod/addons/sounds/shot.ogg"));
+        assert!(glob_match("**/*.ogg", "shot.ogg"));
+        assert!(glob_match("file?.bin", "file1.bin"));
+        assert!(!glob_match("*.paa", "addons/textures.pbo"));
+    }
+}
@@ -0,0 +1,154 @@
+// src/i18n.rs
+
+//! UI translations: flat key/text tables embedded from `locales/<code>.toml`.
+//! Keys missing from a translation fall back to English, then to the key itself.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::{OnceLock, RwLock};
+use tracing::{error, info};
+
+/// Fallback language, and the one every key must exist in
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Bundled languages as (code, native name)
+pub const LANGUAGES: &[(&str, &str)] = &[("en", "English"), ("de", "Deutsch"), ("ru", "Русский")];
+
+const LOCALE_SOURCES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.toml")),
+    ("de", include_str!("../locales/de.toml")),
+    ("ru", include_str!("../locales/ru.toml")),
+];
+
+static TABLES: OnceLock<HashMap<&'static str, HashMap<String, String>>> = OnceLock::new();
+static ACTIVE_LANGUAGE: RwLock<&'static str> = RwLock::new(DEFAULT_LANGUAGE);
+
+fn tables() -> &'static HashMap<&'static str, HashMap<String, String>> {
+    TABLES.get_or_init(|| {
+        LOCALE_SOURCES
+            .iter()
+            .map(|(code, source)| {
+                let table = toml::from_str(source).unwrap_or_else(|e| {
+                    error!("I18n: Failed to parse locale '{}': {}", code, e);
+                    HashMap::new()
+                });
+                (*code, table)
+            })
+            .collect()
+    })
+}
+
+/// Pick the UI language: the configured code if bundled, otherwise the system
+/// locale if bundled, otherwise English. Returns the code now in use.
+pub fn set_language(configured: Option<&str>) -> &'static str {
+    let code = configured
+        .and_then(bundled_language)
+        .or_else(|| system_locale().as_deref().and_then(bundled_language))
+        .unwrap_or(DEFAULT_LANGUAGE);
+    if let Ok(mut active) = ACTIVE_LANGUAGE.write() {
+        *active = code;
+    }
+    info!("I18n: Using language '{}'", code);
+    code
+}
+
+/// The language code currently used by `tr`
+pub fn language() -> &'static str {
+    ACTIVE_LANGUAGE.read().map_or(DEFAULT_LANGUAGE, |active| *active)
+}
+
+/// Match a language code or locale name ("de", "de_DE.UTF-8", "de-AT") to a bundled language
+pub fn bundled_language(locale: &str) -> Option<&'static str> {
+    let code = locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    LANGUAGES.iter().map(|(c, _)| *c).find(|c| *c == code)
+}
+
+/// Text for `key` in the active language
+pub fn tr(key: &'static str) -> &'static str {
+    lookup(language(), key)
+}
+
+/// Text for `key` with each `{name}` placeholder replaced by its argument
+pub fn tr_fmt(key: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    fill(tr(key), args)
+}
+
+fn lookup(language: &str, key: &'static str) -> &'static str {
+    let tables = tables();
+    tables
+        .get(language)
+        .and_then(|table| table.get(key))
+        .or_else(|| tables.get(DEFAULT_LANGUAGE)?.get(key))
+        .map_or(key, String::as_str)
+}
+
+fn fill(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    args.iter().fold(template.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), &value.to_string())
+    })
+}
+
+/// The user's locale name as reported by the OS, e.g. "de_DE.UTF-8" or "de-DE"
+#[cfg(not(windows))]
+fn system_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty() && value != "C" && value != "POSIX")
+}
+
+/// The user's locale name as reported by the OS, e.g. "de_DE.UTF-8" or "de-DE"
+#[cfg(windows)]
+fn system_locale() -> Option<String> {
+    use windows_sys::Win32::Globalization::GetUserDefaultLocaleName;
+    const LOCALE_NAME_MAX_LENGTH: usize = 85;
+
+    let mut buffer = [0u16; LOCALE_NAME_MAX_LENGTH];
+    // SAFETY: the buffer is valid for writes of the length we pass
+    let len = unsafe { GetUserDefaultLocaleName(buffer.as_mut_ptr(), buffer.len() as i32) };
+    // The returned length includes the terminating null
+    (len > 1).then(|| String::from_utf16_lossy(&buffer[..len as usize - 1]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translations_only_use_english_keys() {
+        let tables = tables();
+        let english = &tables[DEFAULT_LANGUAGE];
+        assert!(!english.is_empty());
+        for (code, _) in LANGUAGES {
+            let table = tables.get(code).expect("every listed language is bundled");
+            for key in table.keys() {
+                assert!(english.contains_key(key), "'{}' has unknown key '{}'", code, key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_missing_keys_fall_back_to_english_then_key() {
+        assert_eq!(lookup("de", "status.idle"), "Sync: Leerlauf");
+        assert_eq!(lookup("xx", "status.idle"), "Sync: Idle");
+        assert_eq!(lookup("de", "no.such.key"), "no.such.key");
+    }
+
+    #[test]
+    fn test_bundled_language_from_locale_names() {
+        assert_eq!(bundled_language("de_DE.UTF-8"), Some("de"));
+        assert_eq!(bundled_language("ru-RU"), Some("ru"));
+        assert_eq!(bundled_language("EN"), Some("en"));
+        assert_eq!(bundled_language("fr_FR"), None);
+    }
+
+    #[test]
+    fn test_fill_placeholders() {
+        assert_eq!(fill("Checking ({percent}%)", &[("percent", &42)]), "Checking (42%)");
+        assert_eq!(fill("No args", &[]), "No args");
+    }
+}
@@ -3,7 +3,16 @@
 
 pub mod actions;
 pub mod app;
+pub mod cli;
 pub mod config;
+pub mod daemon;
+pub mod diagnostics;
+pub mod glob;
+pub mod i18n;
+pub mod logging;
+#[cfg(windows)]
+pub mod service;
+pub mod status;
 pub mod sync;
 pub mod ui;
 
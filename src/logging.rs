@@ -0,0 +1,353 @@
+// src/logging.rs
+
+//! Logging through `tracing`. Messages keep their "Sync:"/"Action:" prefixes; spans such
+//! as `sync` and `add{info_hash=.. bytes=..}` are written in front of them, and each span
+//! logs how long it took when it closes. Lines go to stdout (warnings and errors to
+//! stderr), to the in-app log viewer, and to `modsync.log` when file logging is on.
+//! ModSync's own messages follow `log_level`; other crates only log warnings, unless
+//! `log_level` is `trace`.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::{Debug, Write as _};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata, Subscriber};
+
+use crate::config::{LogLevel, get_log_path};
+
+/// Lines kept for the in-app log viewer
+const MAX_RECENT_LINES: usize = 500;
+
+/// The logger installed by `init`
+static LOGGER: OnceLock<Arc<Logger>> = OnceLock::new();
+
+thread_local! {
+    /// Spans entered on this thread, innermost last
+    static ENTERED: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+impl LogLevel {
+    fn filter(self) -> LevelFilter {
+        match self {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// Install the logger, or just change its level if it's already installed.
+/// `to_file` also appends to `modsync.log`.
+pub fn init(level: LogLevel, to_file: bool) {
+    let logger = LOGGER.get_or_init(|| {
+        let logger = Arc::new(Logger::new(level, true));
+        if let Err(e) = tracing::subscriber::set_global_default(logger.clone()) {
+            eprintln!("Main: Logging not set up: {}", e);
+        }
+        logger
+    });
+    set_level(level);
+    if to_file {
+        logger.open_file();
+    }
+}
+
+/// Change the level of the installed logger
+pub fn set_level(level: LogLevel) {
+    if let Some(logger) = LOGGER.get() {
+        logger.set_level(level);
+    }
+}
+
+/// The newest lines logged, oldest first, for the in-app viewer and diagnostics bundle
+pub fn recent_lines() -> Vec<String> {
+    LOGGER.get().map(|logger| logger.recent()).unwrap_or_default()
+}
+
+struct SpanRecord {
+    name: &'static str,
+    level: Level,
+    fields: String,
+    parent: Option<u64>,
+    started: Instant,
+    refs: usize,
+}
+
+/// Writes formatted lines to its sinks; see the module docs
+pub struct Logger {
+    level: AtomicUsize, // Index into LogLevel order
+    echo: bool, // Print to stdout/stderr
+    file: Mutex<Option<File>>,
+    recent: Mutex<VecDeque<String>>,
+    spans: Mutex<HashMap<u64, SpanRecord>>,
+    next_id: AtomicU64,
+}
+
+const LEVELS: [LogLevel; 5] = [LogLevel::Error, LogLevel::Warn, LogLevel::Info, LogLevel::Debug, LogLevel::Trace];
+
+impl Logger {
+    pub fn new(level: LogLevel, echo: bool) -> Self {
+        Self {
+            level: AtomicUsize::new(LEVELS.iter().position(|l| *l == level).unwrap_or(2)),
+            echo,
+            file: Mutex::new(None),
+            recent: Mutex::new(VecDeque::new()),
+            spans: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    fn set_level(&self, level: LogLevel) {
+        let index = LEVELS.iter().position(|l| *l == level).unwrap_or(2);
+        if self.level.swap(index, Ordering::Relaxed) != index {
+            tracing::callsite::rebuild_interest_cache();
+        }
+    }
+
+    fn level(&self) -> LogLevel {
+        LEVELS[self.level.load(Ordering::Relaxed).min(LEVELS.len() - 1)]
+    }
+
+    fn open_file(&self) {
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if file.is_some() {
+            return;
+        }
+        match get_log_path().and_then(|path| Ok(OpenOptions::new().create(true).append(true).open(path)?)) {
+            Ok(opened) => *file = Some(opened),
+            Err(e) => eprintln!("Main: Failed to open log file: {}. Logging without a file.", e),
+        }
+    }
+
+    pub fn recent(&self) -> Vec<String> {
+        self.recent.lock().unwrap_or_else(|e| e.into_inner()).iter().cloned().collect()
+    }
+
+    /// `name{fields}` of `id` and its parents, outermost first
+    fn span_path(&self, id: Option<u64>) -> String {
+        let spans = self.spans.lock().unwrap_or_else(|e| e.into_inner());
+        let mut names = Vec::new();
+        let mut next = id;
+        while let Some(span) = next.and_then(|id| spans.get(&id)) {
+            names.push(if span.fields.is_empty() { span.name.to_string() } else { format!("{}{{{}}}", span.name, span.fields) });
+            next = span.parent;
+        }
+        names.reverse();
+        names.join(":")
+    }
+
+    fn write_line(&self, level: Level, span: Option<u64>, message: &str) {
+        let path = self.span_path(span);
+        let context = if path.is_empty() { String::new() } else { format!("{}: ", path) };
+        let line = format!("{} {:>5} {}{}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), level.as_str(), context, message);
+        if self.echo {
+            if level <= Level::WARN {
+                eprintln!("{}", line);
+            } else {
+                println!("{}", line);
+            }
+        }
+        if let Some(file) = self.file.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            let _ = writeln!(file, "{}", line);
+        }
+        let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+        if recent.len() == MAX_RECENT_LINES {
+            recent.pop_front();
+        }
+        recent.push_back(line);
+    }
+
+    fn current_span() -> Option<u64> {
+        ENTERED.with(|entered| entered.borrow().last().copied())
+    }
+
+    /// Drop a reference to span `id`. The last one logs its time and releases its parent.
+    fn release(&self, id: u64) -> bool {
+        let (level, elapsed, parent) = {
+            let mut spans = self.spans.lock().unwrap_or_else(|e| e.into_inner());
+            let Some(span) = spans.get_mut(&id) else { return false };
+            span.refs -= 1;
+            if span.refs > 0 {
+                return false;
+            }
+            (span.level, span.started.elapsed(), span.parent)
+        };
+        self.write_line(level, Some(id), &format!("done in {:.2?}", elapsed));
+        self.spans.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+        if let Some(parent) = parent {
+            self.release(parent);
+        }
+        true
+    }
+}
+
+/// Formats the message and other fields of an event or span
+#[derive(Default)]
+struct FieldWriter {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldWriter {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.push_field(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            self.push_field(field, &format_args!("{:?}", value));
+        }
+    }
+}
+
+impl FieldWriter {
+    fn push_field(&mut self, field: &Field, value: &dyn std::fmt::Display) {
+        if !self.fields.is_empty() {
+            self.fields.push(' ');
+        }
+        let _ = write!(self.fields, "{}={}", field.name(), value);
+    }
+}
+
+impl Subscriber for Logger {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        // Asked again after set_level rebuilds the cache
+        if self.enabled(metadata) { Interest::always() } else { Interest::never() }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        let level = self.level();
+        let filter = if metadata.target().starts_with("modsync") || level == LogLevel::Trace {
+            level.filter()
+        } else {
+            level.filter().min(LevelFilter::WARN)
+        };
+        *metadata.level() <= filter
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(self.level().filter())
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+        let mut writer = FieldWriter::default();
+        attrs.record(&mut writer);
+        let parent = if attrs.is_contextual() { Self::current_span() } else { attrs.parent().map(Id::into_u64) };
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut spans = self.spans.lock().unwrap_or_else(|e| e.into_inner());
+        // A child keeps its parent open, so its path can still be written
+        let parent = parent.filter(|parent| spans.get_mut(parent).map(|span| span.refs += 1).is_some());
+        spans.insert(
+            id,
+            SpanRecord {
+                name: attrs.metadata().name(),
+                level: *attrs.metadata().level(),
+                fields: writer.fields,
+                parent,
+                started: Instant::now(),
+                refs: 1,
+            },
+        );
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut writer = FieldWriter::default();
+        values.record(&mut writer);
+        if let Some(span) = self.spans.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&span.into_u64()) {
+            if !span.fields.is_empty() && !writer.fields.is_empty() {
+                span.fields.push(' ');
+            }
+            span.fields.push_str(&writer.fields);
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut writer = FieldWriter::default();
+        event.record(&mut writer);
+        let span = if event.is_contextual() { Self::current_span() } else { event.parent().map(Id::into_u64) };
+        let message = if writer.fields.is_empty() {
+            writer.message
+        } else {
+            format!("{} {}", writer.message, writer.fields)
+        };
+        self.write_line(*event.metadata().level(), span, &message);
+    }
+
+    fn enter(&self, span: &Id) {
+        ENTERED.with(|entered| entered.borrow_mut().push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &Id) {
+        let id = span.into_u64();
+        ENTERED.with(|entered| {
+            let mut entered = entered.borrow_mut();
+            if let Some(index) = entered.iter().rposition(|entered| *entered == id) {
+                entered.remove(index);
+            }
+        });
+    }
+
+    fn clone_span(&self, span: &Id) -> Id {
+        if let Some(span) = self.spans.lock().unwrap_or_else(|e| e.into_inner()).get_mut(&span.into_u64()) {
+            span.refs += 1;
+        }
+        span.clone()
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        self.release(span.into_u64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing::{debug, info, info_span, warn};
+
+    #[test]
+    fn test_lines_carry_spans_fields_and_timing() {
+        let logger = Arc::new(Logger::new(LogLevel::Info, false));
+        tracing::subscriber::with_default(logger.clone(), || {
+            let sync = info_span!("sync").entered();
+            info_span!("add", info_hash = "abc123", bytes = 2048).in_scope(|| {
+                info!(id = 3, "Sync: Torrent added");
+                debug!("Sync: Not shown at info");
+            });
+            drop(sync);
+            warn!(target: "librqbit::session", "Peer warning");
+            info!(target: "librqbit::session", "Peer chatter");
+        });
+
+        let lines = logger.recent();
+        let messages: Vec<&str> = lines.iter().map(|line| line.split_once(' ').unwrap().1.split_once(' ').unwrap().1).collect();
+        assert_eq!(messages[0], " INFO sync:add{info_hash=abc123 bytes=2048}: Sync: Torrent added id=3");
+        assert!(messages[1].starts_with(" INFO sync:add{info_hash=abc123 bytes=2048}: done in "), "{}", messages[1]);
+        assert!(messages[2].starts_with(" INFO sync: done in "), "{}", messages[2]);
+        assert_eq!(messages[3], " WARN Peer warning");
+        assert_eq!(messages.len(), 4);
+
+        logger.set_level(LogLevel::Debug);
+        tracing::subscriber::with_default(logger.clone(), || debug!("Sync: Shown at debug"));
+        assert!(logger.recent().last().unwrap().ends_with("DEBUG Sync: Shown at debug"));
+    }
+}
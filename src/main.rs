@@ -1,138 +1,150 @@
-mod actions;
-mod app;
-mod config;
-mod sync;
-mod ui;
-
 use anyhow::Context;
-use app::MyApp;
-use config::{load_config, get_config_path, get_cached_torrent_path};
-use librqbit::{Api, Session, SessionOptions, AddTorrent, AddTorrentOptions};
+use clap::Parser;
+use modsync::app::{MyApp, repaint_on_events};
+use modsync::cli::{Cli, Command};
+use modsync::config::{ConfigStore, FileConfigStore, get_config_path, LogLevel, set_active_instance, set_active_profile, set_cli_overrides, ProfileStore};
+use modsync::daemon;
+use modsync::sync::{self, SyncCommand};
 use tokio::sync::mpsc;
-use sync::{SyncCommand, SyncEvent};
+use tracing::warn;
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    // Get config path and load initial configuration
-    let config_path = get_config_path().context("Failed to determine config path")?;
-    let initial_config = load_config(&config_path).context("Failed to load initial configuration")?;
+fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+    // Until the config says otherwise, log at the default level
+    modsync::logging::init(LogLevel::default(), false);
 
-    let options = eframe::NativeOptions::default();
+    // Windows service control doesn't need the config loaded here
+    #[cfg(windows)]
+    if let Some(Command::Service { action }) = &cli.command {
+        use modsync::cli::ServiceAction;
+        return match action {
+            ServiceAction::Install => modsync::service::install(),
+            ServiceAction::Uninstall => modsync::service::uninstall(),
+            ServiceAction::Run => modsync::service::run(),
+        };
+    }
 
-    // Ensure download path exists
-    let download_path = initial_config.download_path.clone(); // Renamed field
-    if !download_path.as_os_str().is_empty() {
-        tokio::fs::create_dir_all(&download_path)
-            .await
-            .with_context(|| format!("Failed to create download directory: {:?}", download_path))?;
+    // Every config, cache and log path depends on the instance, so pick it first
+    if let Some(name) = &cli.instance {
+        set_active_instance(name)?;
     }
+    set_cli_overrides(&cli.set)?;
 
-    // Setup librqbit session
-    let session_download_path = download_path.clone(); // Clone for session
-    let session = Session::new_with_opts(
-        session_download_path, // Pass the clone
-        SessionOptions {
-            disable_dht: true, // Keep DHT disabled for simplicity/focus
-            disable_dht_persistence: true,
-            persistence: None,
-            fastresume: true, // Enable fastresume to speed up checking existing files
-            ..Default::default()
+    if cli.list_profiles {
+        let profiles = ProfileStore::open()?.list()?;
+        if profiles.is_empty() {
+            println!("No profiles found.");
         }
-    ).await.context("Failed to initialize librqbit session")?;
-
-    let api = Api::new(session.clone(), None);
-
-    // --- Load cached torrent --- 
-    let mut initial_torrent_id = None;
-    match get_cached_torrent_path() {
-        Ok(cached_path) => {
-            if cached_path.exists() {
-                println!("Main: Found cached torrent at {}", cached_path.display());
-                match tokio::fs::read(&cached_path).await {
-                    Ok(torrent_bytes) => {
-                         println!("Main: Read {} bytes from cached torrent.", torrent_bytes.len());
-                        // Add the cached torrent, not paused, ensuring overwrite checks
-                        let add_request = AddTorrent::from_bytes(torrent_bytes);
-                        let add_options = AddTorrentOptions {
-                            output_folder: Some(download_path.to_string_lossy().into_owned()), // Use original download_path here
-                            paused: false, // Start unpaused to trigger immediate check/sync
-                            overwrite: true, // Ensure files are checked against cache
-                            ..Default::default()
-                        };
-                        match api.api_add_torrent(add_request, Some(add_options)).await {
-                            Ok(response) => {
-                                if let Some(id) = response.id {
-                                    println!("Main: Successfully added cached torrent with ID: {}", id);
-                                    initial_torrent_id = Some(id);
-                                } else {
-                                    eprintln!("Main: Added cached torrent but API returned no ID.");
-                                    // Delete potentially broken cache file?
-                                    let _ = tokio::fs::remove_file(&cached_path).await;
-                                }
-                            }
-                            Err(e) => {
-                                eprintln!("Main: Error adding cached torrent: {}. Deleting cache.", e);
-                                let _ = tokio::fs::remove_file(&cached_path).await;
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Main: Error reading cached torrent file {}: {}. Deleting cache.", cached_path.display(), e);
-                        let _ = tokio::fs::remove_file(cached_path).await;
-                    }
-                }
-            } else {
-                println!("Main: No cached torrent file found at {}", cached_path.display());
-            }
+        for name in profiles {
+            println!("{}", name);
+        }
+        return Ok(());
+    }
+    
+    if let Some(name) = &cli.profile {
+        if !ProfileStore::open()?.contains(name) {
+            anyhow::bail!("Profile '{}' not found (see --list-profiles)", name);
+        }
+        set_active_profile(name)?;
+    }
+
+    // Get config path and load initial configuration
+    let config_path = get_config_path().context("Failed to determine config path")?;
+    let first_run = !config_path.exists();
+    let config_store = std::sync::Arc::new(FileConfigStore::new(config_path.clone()));
+    let initial_config = config_store.load().context("Failed to load initial configuration")?;
+    modsync::logging::init(initial_config.log_level, initial_config.log_to_file);
+
+    if let Some(Command::VerifyTorrent { torrent, folder }) = &cli.command {
+        let folder = folder.as_ref().unwrap_or(&initial_config.download_path);
+        let report = sync::offline_verify::verify_offline(torrent, folder)?;
+        println!("{}: {} files checked in {}", report.torrent_name, report.files, folder.display());
+        for file in &report.missing {
+            println!("missing  {}", file.display());
         }
-        Err(e) => {
-            eprintln!("Main: Error getting cached torrent path: {}", e);
-            // Proceed without cache
+        for file in &report.mismatched {
+            println!("changed  {}", file.display());
         }
+        if !report.is_complete() {
+            anyhow::bail!("{} missing and {} changed files", report.missing.len(), report.mismatched.len());
+        }
+        println!("All files match");
+        return Ok(());
+    }
+
+    if let Some(Command::Status { json, fail_on }) = &cli.command {
+        let code = modsync::status::run(&initial_config, *json, *fail_on)?;
+        std::process::exit(code);
     }
-    // --------------------------
+
+    // Built by hand rather than with #[tokio::main], since its size comes from the config
+    let runtime = sync::cpu_limit::build_runtime(&initial_config)?;
+
+    if let Some(Command::Daemon) = cli.command {
+        return runtime.block_on(daemon::run_headless(initial_config, daemon::shutdown_signal()));
+    }
+    // Lets the UI thread spawn tasks onto the runtime
+    let _runtime_guard = runtime.enter();
+
+    // The daemon's log stays in English; the GUI follows the configured language
+    modsync::i18n::set_language(initial_config.language.as_deref());
+
+    let options = eframe::NativeOptions::default();
 
     // Create channels for communication using our new message types
-    let (ui_tx, ui_rx) = mpsc::unbounded_channel::<SyncEvent>();
+    // Events from every sender are numbered and timestamped on their way to the UI
+    let (stamped_tx, ui_rx) = mpsc::unbounded_channel::<sync::TimestampedEvent>();
+    let ui_tx = sync::stamp::stamp_events(stamped_tx);
     // Create channel for commands from UI to sync manager
     let (sync_cmd_tx, sync_cmd_rx) = mpsc::unbounded_channel::<SyncCommand>();
 
-    // Spawn the sync manager task
-    let sync_api = api.clone();
-    let sync_config = initial_config.clone();
-    let sync_ui_tx = ui_tx.clone();
-    tokio::spawn(async move {
-        println!("Main: Sync manager started in background task");
-
-        if let Err(e) = sync::run_sync_manager(
-            sync_config,
-            sync_api,
-            sync_ui_tx,
-            sync_cmd_rx,      // Pass commands receiver
-            initial_torrent_id, // Pass the initial ID
-        )
-        .await
-        {
-            eprintln!("Sync manager task exited with error: {}", e);
+    // Setup librqbit session, load the cached torrent and spawn the sync manager.
+    // If the session can't start, the UI still opens so the user can fix things.
+    let (api, failed_start) = runtime.block_on(async {
+        match sync::startup::start_engine(&initial_config, ui_tx.clone(), sync_cmd_rx).await {
+            Ok(api) => anyhow::Ok((api, None)),
+            Err(failed) => Ok((sync::startup::standby_api().await?, Some(failed))),
         }
-        println!("Sync manager task finished.");
-    });
+    })?;
 
-    // Run the eframe UI
+    // Run the eframe UI, titled so side-by-side instances can be told apart
+    let title = match &cli.instance {
+        Some(name) => format!("ModSync ({})", name),
+        None => "ModSync".to_string(),
+    };
     eframe::run_native(
-        "ModSync",
+        &title,
         options,
-        Box::new(move |_cc| {
+        Box::new(move |cc| {
+            // Community branding; a broken theme file leaves the default look
+            if let Some(theme_path) = &initial_config.theme_file
+                && let Err(e) = modsync::ui::theme::apply_theme_file(&cc.egui_ctx, theme_path)
+            {
+                warn!("UI: {:#}. Using the default theme.", e);
+                let _ = ui_tx.send(sync::SyncEvent::Error(format!("Theme not applied: {:#}", e)));
+            }
+
+            // Wake the UI whenever the sync manager sends an event
+            let ui_rx = repaint_on_events(cc.egui_ctx.clone(), ui_rx);
+            
             // Create MyApp instance
-            let app_box = Box::new(MyApp::new(
+            let mut app = MyApp::new(
                 api,
                 ui_tx.clone(), // UI event sender for the UI thread
                 ui_rx,        // UI event receiver
                 sync_cmd_tx.clone(), // Command sender
                 initial_config,
-            )) as Box<dyn eframe::App>;
+                config_store,
+            );
+            if let Some(failed) = failed_start {
+                app.show_failed_start(failed);
+            }
+            // Nothing configured yet: walk the user through it
+            if first_run {
+                app.start_setup_wizard();
+            }
             
-            Ok(app_box)
+            Ok(Box::new(app) as Box<dyn eframe::App>)
         }),
     )
     .map_err(|e| anyhow::anyhow!("eframe error: {}", e))?;
@@ -0,0 +1,136 @@
+// src/service.rs
+
+//! Windows service integration: registers ModSync with the service control
+//! manager and runs the headless sync engine under it.
+
+use anyhow::{Context, Result};
+use std::ffi::OsString;
+use std::sync::Mutex;
+use std::time::Duration;
+use windows_service::service::{
+    ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
+    ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+use windows_service::{define_windows_service, service_dispatcher};
+
+use crate::config::{ConfigStore, FileConfigStore, get_config_path};
+use crate::daemon::{DaemonLog, run_headless};
+
+const SERVICE_NAME: &str = "ModSync";
+const SERVICE_DISPLAY_NAME: &str = "ModSync";
+const SERVICE_TYPE: ServiceType = ServiceType::OWN_PROCESS;
+
+define_windows_service!(ffi_service_main, service_main);
+
+/// Hand control to the service dispatcher. Only works when started by the SCM.
+pub fn run() -> Result<()> {
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+        .context("Failed to start service dispatcher (is this running as a service?)")
+}
+
+/// Register ModSync as an auto-start service running `modsync service run`
+pub fn install() -> Result<()> {
+    let manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )
+    .context("Failed to connect to the service manager")?;
+
+    let service_info = ServiceInfo {
+        name: OsString::from(SERVICE_NAME),
+        display_name: OsString::from(SERVICE_DISPLAY_NAME),
+        service_type: SERVICE_TYPE,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path: std::env::current_exe().context("Failed to locate executable")?,
+        launch_arguments: vec![OsString::from("service"), OsString::from("run")],
+        dependencies: vec![],
+        account_name: None, // Run as LocalSystem
+        account_password: None,
+    };
+    let service = manager
+        .create_service(&service_info, ServiceAccess::CHANGE_CONFIG)
+        .context("Failed to create service")?;
+    service
+        .set_description("Keeps a local mod folder in sync with a remote torrent")
+        .context("Failed to set service description")?;
+    println!("Service '{}' installed.", SERVICE_NAME);
+    Ok(())
+}
+
+/// Stop (if running) and remove the ModSync service
+pub fn uninstall() -> Result<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+        .context("Failed to connect to the service manager")?;
+    let service = manager
+        .open_service(
+            SERVICE_NAME,
+            ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+        )
+        .context("Failed to open service")?;
+
+    service.delete().context("Failed to delete service")?;
+    if service.query_status()?.current_state != ServiceState::Stopped {
+        service.stop().context("Failed to stop service")?;
+    }
+    println!("Service '{}' marked for removal.", SERVICE_NAME);
+    Ok(())
+}
+
+fn service_main(_arguments: Vec<OsString>) {
+    if let Err(e) = run_service() {
+        crate::logging::init(crate::config::LogLevel::default(), true);
+        DaemonLog::default().line(&format!("Service failed: {:#}", e));
+    }
+}
+
+fn run_service() -> Result<()> {
+    let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel::<()>();
+    let shutdown_tx = Mutex::new(Some(shutdown_tx));
+
+    let event_handler = move |control_event| -> ServiceControlHandlerResult {
+        match control_event {
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            ServiceControl::Stop | ServiceControl::Shutdown => {
+                if let Some(tx) = shutdown_tx.lock().ok().and_then(|mut tx| tx.take()) {
+                    let _ = tx.send(());
+                }
+                ServiceControlHandlerResult::NoError
+            }
+            _ => ServiceControlHandlerResult::NotImplemented,
+        }
+    };
+    let status_handle = service_control_handler::register(SERVICE_NAME, event_handler)
+        .context("Failed to register service control handler")?;
+
+    let set_state = |state: ServiceState, accept: ServiceControlAccept| {
+        status_handle.set_service_status(ServiceStatus {
+            service_type: SERVICE_TYPE,
+            current_state: state,
+            controls_accepted: accept,
+            exit_code: ServiceExitCode::Win32(0),
+            checkpoint: 0,
+            wait_hint: Duration::default(),
+            process_id: None,
+        })
+    };
+    set_state(
+        ServiceState::Running,
+        ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+    )?;
+
+    // Service mode always reads the config from its fixed location
+    let result = get_config_path()
+        .and_then(|path| FileConfigStore::new(path).load())
+        .and_then(|config| {
+            let runtime = crate::sync::cpu_limit::build_runtime(&config)?;
+            runtime.block_on(run_headless(config, async {
+                let _ = shutdown_rx.await;
+            }))
+        });
+
+    set_state(ServiceState::Stopped, ServiceControlAccept::empty())?;
+    result
+}
@@ -0,0 +1,184 @@
+// src/status.rs
+
+//! `modsync status`: the sync state for shell scripts, e.g. to hold a game launch until
+//! the mods are synced. A running instance is asked through its control API (`GET
+//! /status` on `control_addr`, or a fallback port after it). Without one, the completion
+//! marker tells whether the folder was complete when ModSync last ran.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::cli::FailOn;
+use crate::config::AppConfig;
+use crate::sync::completion::CompletionMarker;
+use crate::ui::utils::format_size;
+
+/// How long a running instance gets to answer
+const QUERY_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Exit code when the status is an error
+pub const EXIT_ERROR: i32 = 1;
+
+/// Exit code when the folder isn't synced, or nothing tells whether it is
+pub const EXIT_NOT_SYNCED: i32 = 2;
+
+/// What `modsync status` found
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StatusReport {
+    pub running: bool, // Answered by a running instance; otherwise read from the completion marker
+    pub status: String, // Label as in /status and /metrics, e.g. "local_active"; "not_running" without an instance
+    pub status_text: String,
+    pub synced: bool, // Every wanted piece is on disk
+    pub progress: f64,
+    pub total_bytes: u64,
+    pub last_synced_at: Option<String>, // RFC 3339
+}
+
+impl StatusReport {
+    /// Built from a running instance's `/status` reply
+    pub fn from_status_json(status: &serde_json::Value) -> Option<Self> {
+        Some(Self {
+            running: true,
+            status: status["status"].as_str()?.to_string(),
+            status_text: status["status_text"].as_str().unwrap_or_default().to_string(),
+            synced: status["finished"].as_bool().unwrap_or(false),
+            progress: status["progress"].as_f64().unwrap_or(0.0),
+            total_bytes: status["total_bytes"].as_u64().unwrap_or(0),
+            last_synced_at: status["last_synced_at"].as_str().map(str::to_string),
+        })
+    }
+
+    /// Built from the completion marker's contents, None when there's no marker
+    pub fn from_marker(marker: Option<&str>) -> Self {
+        let marker = marker.and_then(|contents| toml::from_str::<CompletionMarker>(contents).ok());
+        Self {
+            running: false,
+            status: "not_running".to_string(),
+            status_text: match marker {
+                Some(_) => "ModSync isn't running; the folder was complete when it last ran".to_string(),
+                None => "ModSync isn't running".to_string(),
+            },
+            synced: marker.is_some(),
+            progress: if marker.is_some() { 1.0 } else { 0.0 },
+            total_bytes: marker.as_ref().map_or(0, |marker| marker.bytes),
+            last_synced_at: marker.map(|marker| marker.completed_at.to_rfc3339()),
+        }
+    }
+
+    /// Process exit code under `fail_on`
+    pub fn exit_code(&self, fail_on: FailOn) -> i32 {
+        match fail_on {
+            FailOn::Never => 0,
+            FailOn::Error | FailOn::NotSynced if self.status == "error" => EXIT_ERROR,
+            FailOn::NotSynced if !self.synced => EXIT_NOT_SYNCED,
+            _ => 0,
+        }
+    }
+
+    /// Lines printed without `--json`
+    pub fn text(&self) -> String {
+        let mut lines = vec![format!("Status: {}", self.status_text)];
+        if self.total_bytes > 0 {
+            lines.push(format!("Progress: {:.1}% of {}", self.progress * 100.0, format_size(self.total_bytes)));
+        }
+        lines.push(format!("Synced: {}", if self.synced { "yes" } else { "no" }));
+        if let Some(at) = &self.last_synced_at {
+            lines.push(format!("Last synced: {}", at));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Addresses the control API may be on: `control_addr` and the fallback ports after it.
+/// A server bound to every interface is asked on the loopback address.
+fn control_candidates(config: &AppConfig) -> Vec<SocketAddr> {
+    let Some(mut addr) = config.control_addr.as_deref().and_then(|addr| addr.parse::<SocketAddr>().ok()) else {
+        return Vec::new();
+    };
+    if addr.ip().is_unspecified() {
+        addr.set_ip(if addr.is_ipv4() { std::net::Ipv4Addr::LOCALHOST.into() } else { std::net::Ipv6Addr::LOCALHOST.into() });
+    }
+    let tries = if addr.port() == 0 { 0 } else { config.bind_fallback_ports };
+    (0..=tries)
+        .filter_map(|offset| addr.port().checked_add(offset))
+        .map(|port| SocketAddr::new(addr.ip(), port))
+        .collect()
+}
+
+async fn query_instance(config: &AppConfig) -> Option<StatusReport> {
+    let client = reqwest::Client::builder().timeout(QUERY_TIMEOUT).no_proxy().build().ok()?;
+    for addr in control_candidates(config) {
+        let Ok(response) = client.get(format!("http://{}/status", addr)).send().await else {
+            continue;
+        };
+        // Something else may hold a fallback port; only a /status reply counts
+        if let Ok(status) = response.json::<serde_json::Value>().await
+            && let Some(report) = StatusReport::from_status_json(&status)
+        {
+            return Some(report);
+        }
+    }
+    None
+}
+
+/// The status of this instance, from the running one or the completion marker
+pub fn query_status(config: &AppConfig) -> Result<StatusReport> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("Failed to start the runtime for the status query")?;
+    if let Some(report) = runtime.block_on(query_instance(config)) {
+        return Ok(report);
+    }
+    let marker = config.completion_marker_path.as_deref().and_then(|path| std::fs::read_to_string(path).ok());
+    Ok(StatusReport::from_marker(marker.as_deref()))
+}
+
+/// Print the status and return the exit code
+pub fn run(config: &AppConfig, json: bool, fail_on: FailOn) -> Result<i32> {
+    let report = query_status(config)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("{}", report.text());
+    }
+    Ok(report.exit_code(fail_on))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_and_exit_codes() {
+        let seeding = serde_json::json!({
+            "status": "local_active", "status_text": "Local: Active", "finished": true, "progress": 1.0,
+            "total_bytes": 4096, "last_synced_at": "2026-10-14T03:20:00+02:00",
+        });
+        let report = StatusReport::from_status_json(&seeding).unwrap();
+        assert!(report.running && report.synced);
+        assert_eq!(report.exit_code(FailOn::NotSynced), 0);
+        assert!(report.text().contains("Last synced: 2026-10-14T03:20:00+02:00"));
+        assert!(StatusReport::from_status_json(&serde_json::json!({ "other": "server" })).is_none());
+
+        let downloading = StatusReport { synced: false, status: "checking".to_string(), ..report.clone() };
+        assert_eq!(downloading.exit_code(FailOn::NotSynced), EXIT_NOT_SYNCED);
+        assert_eq!(downloading.exit_code(FailOn::Error), 0);
+        let failed = StatusReport { status: "error".to_string(), ..downloading };
+        assert_eq!(failed.exit_code(FailOn::Error), EXIT_ERROR);
+        assert_eq!(failed.exit_code(FailOn::Never), 0);
+
+        let marker = "info_hash = \"ab\"\ncompleted_at = \"2026-03-14T21:30:00+01:00\"\nbytes = 4096\n";
+        let offline = StatusReport::from_marker(Some(marker));
+        assert!(!offline.running && offline.synced);
+        // Written back in the local time zone, so compare the instant
+        let synced_at = chrono::DateTime::parse_from_rfc3339(offline.last_synced_at.as_deref().unwrap()).unwrap();
+        assert_eq!(synced_at, chrono::DateTime::parse_from_rfc3339("2026-03-14T21:30:00+01:00").unwrap());
+        assert_eq!(StatusReport::from_marker(None).exit_code(FailOn::NotSynced), EXIT_NOT_SYNCED);
+
+        let config = AppConfig { control_addr: Some("0.0.0.0:9185".to_string()), bind_fallback_ports: 1, ..AppConfig::default() };
+        assert_eq!(control_candidates(&config), ["127.0.0.1:9185".parse().unwrap(), "127.0.0.1:9186".parse().unwrap()]);
+    }
+}
@@ -0,0 +1,275 @@
+// src/sync/autostop.rs
+
+//! Stops downloading after a time budget or at a clock time, for metered connections.
+//! Clock times are matched against the wall clock on every tick, so DST changes and
+//! NTP corrections move them along with the clock; each boundary fires at most once.
+
+use chrono::{Duration as ChronoDuration, Local, NaiveDateTime, NaiveTime};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use crate::config::AppConfig;
+use crate::ui::utils::SyncStatus;
+
+use super::messages::SyncEvent;
+use super::types::{LocalTorrentState, SyncState};
+use super::utils::send_sync_status_event;
+
+/// Parse a "HH:MM" clock time from the config
+pub fn parse_clock_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+/// Wall clock changes smaller than this are ordinary drift and tick jitter
+const CLOCK_JUMP_THRESHOLD_SECS: i64 = 120;
+
+/// The most recent occurrence of the daily clock time `at` on or before `now`
+fn latest_boundary(now: NaiveDateTime, at: NaiveTime) -> NaiveDateTime {
+    let candidate = now.date().and_time(at);
+    if candidate > now { candidate - ChronoDuration::days(1) } else { candidate }
+}
+
+/// Whether the daily clock time `at` occurred in the interval (prev, now]
+pub fn crossed(prev: NaiveDateTime, now: NaiveDateTime, at: NaiveTime) -> bool {
+    latest_boundary(now, at) > prev
+}
+
+/// How far the wall clock moved beyond the time that really passed between two checks,
+/// if that's more than ordinary drift. Negative when the clock was set back.
+pub fn clock_jump(prev_wall: NaiveDateTime, now_wall: NaiveDateTime, elapsed: Duration) -> Option<ChronoDuration> {
+    let elapsed = ChronoDuration::from_std(elapsed).unwrap_or(ChronoDuration::MAX);
+    let jump = (now_wall - prev_wall) - elapsed;
+    (jump.num_seconds().abs() >= CLOCK_JUMP_THRESHOLD_SECS).then_some(jump)
+}
+
+/// Tracks how long the managed torrent has been downloading and when we last looked at the clock
+#[derive(Default)]
+pub struct AutoStop {
+    downloading_since: Option<Instant>, // Monotonic, so the budget ignores clock changes
+    last_check: Option<(NaiveDateTime, Instant)>,
+    last_stop: Option<NaiveDateTime>, // Boundary that last stopped downloads
+    last_resume: Option<NaiveDateTime>, // Boundary that last resumed them
+}
+
+impl AutoStop {
+    /// Start a fresh download budget, e.g. after the user resumes
+    pub fn reset(&mut self) {
+        self.downloading_since = None;
+    }
+
+    /// Record this check, returning the previous wall clock time and logging a clock jump
+    fn observe(&mut self, now: NaiveDateTime, instant: Instant) -> Option<NaiveDateTime> {
+        let (prev, prev_instant) = self.last_check.replace((now, instant))?;
+        if let Some(jump) = clock_jump(prev, now, instant.saturating_duration_since(prev_instant)) {
+            info!(
+                "Sync: System clock changed by {} minutes (from {} to {}), scheduled times follow the new clock",
+                jump.num_minutes(),
+                prev.format("%Y-%m-%d %H:%M"),
+                now.format("%Y-%m-%d %H:%M")
+            );
+        }
+        Some(prev)
+    }
+
+    /// The occurrence of `at` passed since the last check, unless it already fired.
+    /// A clock set back can pass the same boundary twice; it only counts once.
+    fn due(prev: Option<NaiveDateTime>, now: NaiveDateTime, at: Option<NaiveTime>, last_fired: &mut Option<NaiveDateTime>) -> bool {
+        let (Some(prev), Some(at)) = (prev, at) else {
+            return false;
+        };
+        let boundary = latest_boundary(now, at);
+        if boundary <= prev || *last_fired == Some(boundary) {
+            return false;
+        }
+        *last_fired = Some(boundary);
+        true
+    }
+
+    /// Decide whether to stop now, returning the reason shown to the user
+    fn stop_reason(&mut self, config: &AppConfig, downloading: bool, now: NaiveDateTime) -> Option<String> {
+        self.stop_reason_at(config, downloading, now, Instant::now())
+    }
+
+    fn stop_reason_at(&mut self, config: &AppConfig, downloading: bool, now: NaiveDateTime, instant: Instant) -> Option<String> {
+        let prev = self.observe(now, instant);
+        if !downloading {
+            self.downloading_since = None;
+            return None;
+        }
+        let since = *self.downloading_since.get_or_insert_with(Instant::now);
+
+        if let Some(minutes) = config.max_download_duration_minutes
+            && since.elapsed() >= Duration::from_secs(minutes * 60)
+        {
+            return Some(format!("Download stopped after {} minutes", minutes));
+        }
+
+        let stop_at = config.stop_downloads_at.as_deref().and_then(parse_clock_time);
+        if Self::due(prev, now, stop_at, &mut self.last_stop) {
+            return stop_at.map(|at| format!("Download stopped at {}", at.format("%H:%M")));
+        }
+        None
+    }
+
+    /// Whether the scheduled resume time has come round since the last check
+    fn should_resume(&mut self, config: &AppConfig, now: NaiveDateTime) -> bool {
+        self.should_resume_at(config, now, Instant::now())
+    }
+
+    fn should_resume_at(&mut self, config: &AppConfig, now: NaiveDateTime, instant: Instant) -> bool {
+        let prev = self.observe(now, instant);
+        let resume_at = config.resume_downloads_at.as_deref().and_then(parse_clock_time);
+        Self::due(prev, now, resume_at, &mut self.last_resume)
+    }
+}
+
+/// Pause the managed torrent once the download budget or stop time is reached,
+/// and resume it at the configured resume time
+pub async fn check_auto_stop(
+    config: &AppConfig,
+    state: &mut SyncState,
+    auto_stop: &mut AutoStop,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+) {
+    let LocalTorrentState::Active { id } = state.local else {
+        return;
+    };
+    let now = Local::now().naive_local();
+
+    if state.auto_stopped {
+        if auto_stop.should_resume(config, now) {
+            info!("Sync: Scheduled resume time reached");
+            resume_downloads(state, auto_stop, api, ui_tx).await;
+        } else {
+            send_sync_status_event(ui_tx, SyncStatus::Stopped);
+        }
+        return;
+    }
+
+    let downloading = api
+        .api_stats_v1(id.into())
+        .map(|stats| stats.live.is_some() && !stats.finished)
+        .unwrap_or(false);
+    if let Some(reason) = auto_stop.stop_reason(config, downloading, now) {
+        info!("Sync: {}, pausing torrent {}", reason, id);
+        if let Err(e) = api.api_torrent_action_pause(id.into()).await {
+            error!("Sync: Failed to pause torrent {}: {}", id, e);
+            return;
+        }
+        state.auto_stopped = true;
+        let _ = ui_tx.send(SyncEvent::Error(reason));
+        send_sync_status_event(ui_tx, SyncStatus::Stopped);
+    }
+}
+
+/// Undo an automatic stop and start a new download budget
+pub async fn resume_downloads(
+    state: &mut SyncState,
+    auto_stop: &mut AutoStop,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+) {
+    state.auto_stopped = false;
+    auto_stop.reset();
+    if let LocalTorrentState::Active { id } = state.local {
+        match api.api_torrent_action_start(id.into()).await {
+            Ok(_) => info!("Sync: Resumed torrent {} after automatic stop", id),
+            Err(e) => error!("Sync: Failed to resume torrent {}: {}", id, e),
+        }
+    }
+    send_sync_status_event(ui_tx, SyncStatus::Idle);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_clock_time() {
+        assert_eq!(parse_clock_time("07:30"), NaiveTime::from_hms_opt(7, 30, 0));
+        assert_eq!(parse_clock_time("25:00"), None);
+        assert_eq!(parse_clock_time("soon"), None);
+    }
+
+    #[test]
+    fn test_crossed_within_day_and_over_midnight() {
+        let stop = NaiveTime::from_hms_opt(22, 0, 0).unwrap();
+        assert!(crossed(at(1, 21, 59), at(1, 22, 0), stop));
+        assert!(!crossed(at(1, 22, 0), at(1, 22, 1), stop));
+        assert!(!crossed(at(1, 20, 0), at(1, 21, 0), stop));
+
+        let early = NaiveTime::from_hms_opt(0, 5, 0).unwrap();
+        assert!(crossed(at(1, 23, 59), at(2, 0, 10), early));
+    }
+
+    #[test]
+    fn test_stop_at_needs_a_previous_check() {
+        let config = AppConfig {
+            stop_downloads_at: Some("22:00".to_string()),
+            ..AppConfig::default()
+        };
+        let mut auto_stop = AutoStop::default();
+        // Starting up after the stop time doesn't stop straight away
+        assert_eq!(auto_stop.stop_reason(&config, true, at(1, 22, 30)), None);
+        assert!(auto_stop.stop_reason(&config, true, at(2, 22, 0)).is_some());
+    }
+
+    #[test]
+    fn test_duration_budget() {
+        let config = AppConfig {
+            max_download_duration_minutes: Some(0),
+            ..AppConfig::default()
+        };
+        let mut auto_stop = AutoStop::default();
+        assert_eq!(auto_stop.stop_reason(&config, false, at(1, 12, 0)), None);
+        assert!(auto_stop.stop_reason(&config, true, at(1, 12, 1)).is_some());
+    }
+
+    #[test]
+    fn test_clock_set_back_does_not_stop_twice() {
+        let config = AppConfig {
+            stop_downloads_at: Some("22:00".to_string()),
+            ..AppConfig::default()
+        };
+        let start = Instant::now();
+        let tick = |minutes: u64| start + Duration::from_secs(minutes * 60);
+        let mut auto_stop = AutoStop::default();
+        assert_eq!(auto_stop.stop_reason_at(&config, true, at(1, 21, 55), tick(0)), None);
+        assert!(auto_stop.stop_reason_at(&config, true, at(1, 22, 0), tick(5)).is_some());
+        // The user resumes, then NTP sets the clock back across 22:00
+        auto_stop.reset();
+        assert_eq!(auto_stop.stop_reason_at(&config, true, at(1, 21, 50), tick(6)), None);
+        assert_eq!(auto_stop.stop_reason_at(&config, true, at(1, 22, 1), tick(17)), None);
+        // The next evening stops again
+        assert!(auto_stop.stop_reason_at(&config, true, at(2, 22, 0), tick(24 * 60 + 16)).is_some());
+    }
+
+    #[test]
+    fn test_clock_jumping_forward_over_resume_time_resumes_once() {
+        let config = AppConfig {
+            resume_downloads_at: Some("06:00".to_string()),
+            ..AppConfig::default()
+        };
+        let start = Instant::now();
+        let mut auto_stop = AutoStop::default();
+        assert!(!auto_stop.should_resume_at(&config, at(1, 5, 0), start));
+        // Clock corrected forward by two hours within a ten second tick
+        assert!(auto_stop.should_resume_at(&config, at(1, 7, 0), start + Duration::from_secs(10)));
+        assert!(!auto_stop.should_resume_at(&config, at(1, 7, 0), start + Duration::from_secs(20)));
+    }
+
+    #[test]
+    fn test_clock_jump_detection() {
+        let tick = Duration::from_secs(10);
+        assert_eq!(clock_jump(at(1, 12, 0), at(1, 12, 0) + ChronoDuration::seconds(10), tick), None);
+        assert_eq!(clock_jump(at(1, 12, 0), at(1, 11, 0), tick).map(|j| j.num_minutes()), Some(-60));
+        assert_eq!(clock_jump(at(1, 1, 59), at(1, 3, 0), Duration::from_secs(60)).map(|j| j.num_minutes()), Some(60));
+    }
+}
@@ -0,0 +1,163 @@
+// src/sync/awake.rs
+
+//! Keeps the machine from sleeping while a download is making progress
+
+use tracing::{info, warn};
+use crate::config::AppConfig;
+
+use super::types::{LocalTorrentState, SyncState};
+
+/// Holds the platform sleep inhibitor while engaged. Dropping it releases the inhibitor.
+#[derive(Default)]
+pub struct SleepInhibitor {
+    guard: Option<platform::Guard>,
+    last_progress_bytes: Option<u64>,
+    warned_unsupported: bool,
+}
+
+impl SleepInhibitor {
+    /// Engage or release the inhibitor depending on whether `active` is true
+    fn set_active(&mut self, active: bool) {
+        match (active, self.guard.is_some()) {
+            (true, false) => match platform::Guard::engage() {
+                Ok(guard) => {
+                    info!("Sync: Preventing sleep while downloading");
+                    self.guard = Some(guard);
+                }
+                Err(e) => {
+                    // Retried every tick otherwise, so only say it once
+                    if !self.warned_unsupported {
+                        warn!("Sync: Cannot prevent sleep on this system: {}", e);
+                        self.warned_unsupported = true;
+                    }
+                }
+            },
+            (false, true) => {
+                self.guard = None;
+                info!("Sync: Allowing sleep again");
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Hold the inhibitor only while the managed torrent is downloading and moving:
+/// released when paused, idle, seeding or complete, or when the setting is off
+pub fn check_sleep_inhibit(config: &AppConfig, state: &SyncState, inhibitor: &mut SleepInhibitor, api: &librqbit::Api) {
+    let stats = match state.local {
+        LocalTorrentState::Active { id } => api.api_stats_v1(id.into()).ok(),
+        LocalTorrentState::NotLoaded => None,
+    };
+    let progress_bytes = stats.as_ref().map(|s| s.progress_bytes);
+    let progressing = stats.as_ref().is_some_and(|s| {
+        let moved = matches!((inhibitor.last_progress_bytes, progress_bytes), (Some(last), Some(now)) if now > last);
+        let speed = s.live.as_ref().is_some_and(|live| live.download_speed.mbps > 0.0);
+        !s.finished && s.live.is_some() && (moved || speed)
+    });
+    inhibitor.last_progress_bytes = progress_bytes;
+    inhibitor.set_active(config.prevent_sleep_during_sync && progressing);
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::sync::mpsc;
+    use std::thread;
+    use windows_sys::Win32::System::Power::{ES_CONTINUOUS, ES_SYSTEM_REQUIRED, SetThreadExecutionState};
+
+    /// The execution state belongs to the thread that set it, so a dedicated thread
+    /// holds it until the guard is dropped
+    pub struct Guard {
+        release: Option<mpsc::Sender<()>>,
+        thread: Option<thread::JoinHandle<()>>,
+    }
+
+    impl Guard {
+        pub fn engage() -> anyhow::Result<Self> {
+            let (release, released) = mpsc::channel::<()>();
+            let (ready_tx, ready_rx) = mpsc::channel::<bool>();
+            let thread = thread::spawn(move || {
+                // SAFETY: plain Win32 call with valid flags
+                let ok = unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) } != 0;
+                let _ = ready_tx.send(ok);
+                if ok {
+                    let _ = released.recv();
+                    // SAFETY: as above; clears the requirement for this thread
+                    unsafe { SetThreadExecutionState(ES_CONTINUOUS) };
+                }
+            });
+            if ready_rx.recv().unwrap_or(false) {
+                Ok(Self { release: Some(release), thread: Some(thread) })
+            } else {
+                Err(anyhow::anyhow!("SetThreadExecutionState failed"))
+            }
+        }
+    }
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            drop(self.release.take());
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+mod platform {
+    use anyhow::Context;
+    use std::process::{Child, Command, Stdio};
+
+    /// A helper process that holds the inhibitor for as long as it runs
+    pub struct Guard {
+        child: Child,
+    }
+
+    impl Guard {
+        pub fn engage() -> anyhow::Result<Self> {
+            let mut command = inhibit_command();
+            let child = command
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .with_context(|| format!("failed to run {:?}", command.get_program()))?;
+            Ok(Self { child })
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn inhibit_command() -> Command {
+        let mut command = Command::new("systemd-inhibit");
+        // `cat` exits when its stdin closes, so the lock goes away with us even if we crash
+        command.args(["--what=sleep:idle", "--who=ModSync", "--why=Downloading mods", "--mode=block", "cat"]);
+        command
+    }
+
+    #[cfg(target_os = "macos")]
+    fn inhibit_command() -> Command {
+        // -w ends the assertion if we die without cleaning up
+        let mut command = Command::new("caffeinate");
+        command.args(["-i", "-w", &std::process::id().to_string()]);
+        command
+    }
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            drop(self.child.stdin.take());
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+mod platform {
+    pub struct Guard;
+
+    impl Guard {
+        pub fn engage() -> anyhow::Result<Self> {
+            Err(anyhow::anyhow!("not supported on this platform"))
+        }
+    }
+}
@@ -0,0 +1,213 @@
+// src/sync/battery.rs
+
+//! `pause_on_battery_below`: pauses every torrent once a laptop running on battery drops
+//! below that charge, and resumes them when it's plugged in or charged above it again.
+//! The charge is read from the OS directly: `/sys/class/power_supply` on Linux,
+//! `GetSystemPowerStatus` on Windows and `pmset` on macOS. A machine without a battery
+//! never pauses.
+
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use crate::config::AppConfig;
+use crate::ui::utils::SyncStatus;
+
+use super::messages::SyncEvent;
+use super::types::SyncState;
+use super::utils::send_sync_status_event;
+
+/// How often the charge is read; it changes slowly, and `pmset` is a process per read
+const READ_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Charge and power source, as the OS reports them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryReading {
+    pub percent: u8,
+    pub on_battery: bool, // Not plugged in, or plugged in but not charging
+}
+
+/// Whether torrents should be paused for this reading. None (no battery) never pauses.
+pub fn should_pause(threshold: u8, reading: Option<BatteryReading>) -> bool {
+    reading.is_some_and(|battery| battery.on_battery && battery.percent < threshold)
+}
+
+/// Reads the battery between ticks, no more than every `READ_INTERVAL`
+#[derive(Debug, Default)]
+pub struct BatteryWatch {
+    last: Option<(Instant, Option<BatteryReading>)>,
+}
+
+impl BatteryWatch {
+    fn reading(&mut self, now: Instant) -> Option<BatteryReading> {
+        match self.last {
+            Some((at, reading)) if now.duration_since(at) < READ_INTERVAL => reading,
+            _ => {
+                let reading = platform::read_battery();
+                self.last = Some((now, reading));
+                reading
+            }
+        }
+    }
+}
+
+/// Pause every live torrent while the battery is below `pause_on_battery_below`, and
+/// resume the ones we paused once it's charging or back above it
+pub async fn check_battery(
+    config: &AppConfig,
+    state: &mut SyncState,
+    watch: &mut BatteryWatch,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+) {
+    let reading = config
+        .pause_on_battery_below
+        .and_then(|threshold| Some((threshold, watch.reading(Instant::now())?)));
+    let low = reading.is_some_and(|(threshold, battery)| should_pause(threshold, Some(battery)));
+
+    if low {
+        let paused_ids = state.paused_for_battery.get_or_insert_with(|| {
+            if let Some((threshold, battery)) = reading {
+                info!("Sync: Battery at {}%, below {}%, pausing torrents", battery.percent, threshold);
+            }
+            Vec::new()
+        });
+        // Also catches torrents added while the battery was already low
+        let torrent_list = api.api_torrent_list_ext(librqbit::api::ApiTorrentListOpts { with_stats: true });
+        for torrent in torrent_list.torrents {
+            let (Some(id), Some(stats)) = (torrent.id, torrent.stats.as_ref()) else { continue };
+            if stats.live.is_none() || paused_ids.contains(&id) {
+                continue;
+            }
+            match api.api_torrent_action_pause(id.into()).await {
+                Ok(_) => {
+                    info!("Sync: Paused torrent {} on low battery", id);
+                    paused_ids.push(id);
+                }
+                Err(e) => error!("Sync: Failed to pause torrent {} on low battery: {}", id, e),
+            }
+        }
+        send_sync_status_event(ui_tx, SyncStatus::PausedLowBattery);
+    } else if let Some(paused_ids) = state.paused_for_battery.take() {
+        info!("Sync: Battery charging or recharged, resuming {} torrents", paused_ids.len());
+        if let Some(held_ids) = state.paused_for_game.as_mut().or(state.paused_for_interface.as_mut()) {
+            // The game or interface pause is still on; it resumes these when it ends
+            for id in paused_ids {
+                if !held_ids.contains(&id) {
+                    held_ids.push(id);
+                }
+            }
+            return;
+        }
+        for id in paused_ids {
+            // Leave it paused if low disk space would pause it anyway
+            if state.low_disk_space {
+                break;
+            }
+            if state.paused_for_seed_goal.as_ref().is_some_and(|ids| ids.contains(&id)) {
+                continue; // Still held by the seed goal
+            }
+            match api.api_torrent_action_start(id.into()).await {
+                Ok(_) => info!("Sync: Resumed torrent {} after low battery", id),
+                Err(e) => error!("Sync: Failed to resume torrent {}: {}", id, e),
+            }
+        }
+        send_sync_status_event(ui_tx, SyncStatus::Idle);
+    }
+}
+
+/// Parse one `/sys/class/power_supply` entry from its `type`, `capacity` and `status` files
+pub fn parse_sysfs(kind: &str, capacity: &str, status: &str) -> Option<BatteryReading> {
+    if kind.trim() != "Battery" {
+        return None;
+    }
+    let percent = capacity.trim().parse::<u8>().ok()?.min(100);
+    Some(BatteryReading { percent, on_battery: status.trim() == "Discharging" })
+}
+
+/// Parse the output of `pmset -g batt`, e.g. "-InternalBattery-0 (id=…)  85%; discharging; 3:10 remaining"
+pub fn parse_pmset(output: &str) -> Option<BatteryReading> {
+    let line = output.lines().find(|line| line.contains("InternalBattery"))?;
+    let (before, _) = line.split_once('%')?;
+    let percent = before.rsplit(|c: char| !c.is_ascii_digit()).next()?.parse::<u8>().ok()?.min(100);
+    let on_battery = line.contains("discharging") || output.contains("'Battery Power'");
+    Some(BatteryReading { percent, on_battery })
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{BatteryReading, parse_sysfs};
+
+    pub fn read_battery() -> Option<BatteryReading> {
+        let entries = std::fs::read_dir("/sys/class/power_supply").ok()?;
+        entries.flatten().find_map(|entry| {
+            let read = |name: &str| std::fs::read_to_string(entry.path().join(name)).unwrap_or_default();
+            parse_sysfs(&read("type"), &read("capacity"), &read("status"))
+        })
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{BatteryReading, parse_pmset};
+
+    pub fn read_battery() -> Option<BatteryReading> {
+        let output = std::process::Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+        parse_pmset(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::BatteryReading;
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    /// BatteryFlag bits for "no system battery" and "status unknown"
+    const NO_BATTERY: u8 = 128;
+    const UNKNOWN: u8 = 255;
+
+    pub fn read_battery() -> Option<BatteryReading> {
+        // SAFETY: plain Win32 call filling a struct we own
+        let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+        if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+            return None;
+        }
+        if status.BatteryFlag == UNKNOWN || status.BatteryFlag & NO_BATTERY != 0 || status.BatteryLifePercent > 100 {
+            return None;
+        }
+        // ACLineStatus: 0 offline, 1 online, 255 unknown
+        Some(BatteryReading { percent: status.BatteryLifePercent, on_battery: status.ACLineStatus == 0 })
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod platform {
+    use super::BatteryReading;
+
+    pub fn read_battery() -> Option<BatteryReading> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_battery_readings_and_pause_threshold() {
+        let laptop = parse_sysfs("Battery\n", "14\n", "Discharging\n");
+        assert_eq!(laptop, Some(BatteryReading { percent: 14, on_battery: true }));
+        assert!(should_pause(20, laptop));
+        assert!(!should_pause(10, laptop));
+        // Plugged in, or no battery at all, never pauses
+        assert!(!should_pause(20, parse_sysfs("Battery", "14", "Charging")));
+        assert_eq!(parse_sysfs("Mains", "", ""), None);
+        assert!(!should_pause(20, None));
+
+        let pmset = "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=4653155)\t85%; discharging; 3:10 remaining present: true\n";
+        assert_eq!(parse_pmset(pmset), Some(BatteryReading { percent: 85, on_battery: true }));
+        let plugged = "Now drawing from 'AC Power'\n -InternalBattery-0 (id=4653155)\t9%; charging; 1:20 remaining present: true\n";
+        assert_eq!(parse_pmset(plugged), Some(BatteryReading { percent: 9, on_battery: false }));
+        assert_eq!(parse_pmset("Now drawing from 'AC Power'\n"), None);
+    }
+}
@@ -0,0 +1,117 @@
+// src/sync/bencode.rs
+
+//! Just enough bencode to walk a dict and re-emit it with some values replaced.
+//! librqbit_bencode decodes into maps, which re-encode with sorted keys; a torrent's
+//! info dict has to be copied byte for byte to keep its info hash.
+
+use anyhow::{Context, Result, bail};
+
+/// Deepest nesting of lists and dicts walked. A torrent needs a handful (info, files, a
+/// file, its path); this keeps hostile input from exhausting the stack.
+const MAX_DEPTH: usize = 32;
+
+/// The encoding of a byte string
+pub fn encode_string(bytes: &[u8]) -> Vec<u8> {
+    let mut encoded = format!("{}:", bytes.len()).into_bytes();
+    encoded.extend_from_slice(bytes);
+    encoded
+}
+
+/// A byte string at the start of `data`, and the length of its encoding
+pub fn parse_string(data: &[u8]) -> Result<(&[u8], usize)> {
+    let colon = data.iter().position(|&b| b == b':').context("Malformed bencode string")?;
+    let len: usize = std::str::from_utf8(&data[..colon])?.parse().context("Malformed bencode string length")?;
+    let end = (colon + 1).checked_add(len).filter(|end| *end <= data.len()).context("Truncated bencode string")?;
+    Ok((&data[colon + 1..end], end))
+}
+
+/// Length of the encoding of the value at the start of `data`
+fn value_len(data: &[u8]) -> Result<usize> {
+    nested_value_len(data, 0)
+}
+
+/// [`value_len`] of a value inside `depth` containers
+fn nested_value_len(data: &[u8], depth: usize) -> Result<usize> {
+    match data.first() {
+        Some(b'i') => Ok(data.iter().position(|&b| b == b'e').context("Malformed bencode integer")? + 1),
+        Some(b'l') | Some(b'd') => {
+            if depth >= MAX_DEPTH {
+                bail!("Bencode nested too deeply");
+            }
+            let mut offset = 1;
+            while data.get(offset) != Some(&b'e') {
+                if offset >= data.len() {
+                    bail!("Truncated bencode container");
+                }
+                offset += nested_value_len(&data[offset..], depth + 1)?;
+            }
+            Ok(offset + 1)
+        }
+        Some(b'0'..=b'9') => Ok(parse_string(data)?.1),
+        _ => bail!("Malformed bencode value"),
+    }
+}
+
+/// The raw encoded items of a list
+pub fn list_items(data: &[u8]) -> Result<Vec<&[u8]>> {
+    if data.first() != Some(&b'l') {
+        bail!("Expected a bencode list");
+    }
+    let mut items = Vec::new();
+    let mut offset = 1;
+    while data.get(offset) != Some(&b'e') {
+        if offset >= data.len() {
+            bail!("Truncated bencode list");
+        }
+        let len = value_len(&data[offset..])?;
+        items.push(&data[offset..offset + len]);
+        offset += len;
+    }
+    Ok(items)
+}
+
+/// The keys of a dict with the raw encoding of each value
+pub fn dict_entries(data: &[u8]) -> Result<Vec<(&[u8], &[u8])>> {
+    if data.first() != Some(&b'd') {
+        bail!("Expected a bencode dict");
+    }
+    let mut entries = Vec::new();
+    let mut offset = 1;
+    while data.get(offset) != Some(&b'e') {
+        if offset >= data.len() {
+            bail!("Truncated bencode dict");
+        }
+        let (key, key_len) = parse_string(&data[offset..])?;
+        offset += key_len;
+        let value_len = value_len(&data[offset..])?;
+        entries.push((key, &data[offset..offset + value_len]));
+        offset += value_len;
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_walks_raw_values() -> Result<()> {
+        let entries = dict_entries(b"d1:ai-3e1:bl1:xd1:yi0eee1:c3:abce")?;
+        let keys: Vec<&[u8]> = entries.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, [&b"a"[..], b"b", b"c"]);
+        assert_eq!(entries[1].1, b"l1:xd1:yi0eee");
+        assert_eq!(list_items(entries[1].1)?, [&b"1:x"[..], b"d1:yi0ee"]);
+        assert_eq!(parse_string(entries[2].1)?, (&b"abc"[..], 5));
+        assert_eq!(encode_string(b"abc"), b"3:abc");
+
+        assert!(dict_entries(b"d1:ai1e").is_err());
+        assert!(parse_string(b"5:abc").is_err());
+        // A length that would overflow is truncated input, not a panic
+        assert!(parse_string(format!("{}:abc", usize::MAX).as_bytes()).is_err());
+        let deep = format!("d1:a{}e", "l".repeat(100_000));
+        assert!(dict_entries(deep.as_bytes()).is_err());
+        let nested = format!("d1:a{}{}e", "l".repeat(MAX_DEPTH), "e".repeat(MAX_DEPTH));
+        assert!(dict_entries(nested.as_bytes()).is_ok());
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+// src/sync/breaker.rs
+
+//! Circuit breaker for automatic retries: after too many failures in a short time the
+//! periodic update checks and torrent restarts stop until the user retries by hand,
+//! instead of repeating the same error every few seconds.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+use crate::config::AppConfig;
+use crate::ui::utils::SyncStatus;
+
+use super::messages::SyncEvent;
+use super::network_share::{network_share, share_disconnect_in_text};
+use super::permissions::{permission_error_in_text, report_permission_error};
+use super::types::{LocalTorrentState, SyncState};
+use super::utils::send_sync_status_event;
+
+/// Failures older than this no longer count towards the threshold
+const FAILURE_WINDOW: Duration = Duration::from_secs(30 * 60);
+
+/// Counts recent failures of automatic sync operations
+#[derive(Debug, Default)]
+pub struct ErrorBreaker {
+    failures: VecDeque<Instant>,
+    last_error: String,
+    tripped: bool,
+}
+
+impl ErrorBreaker {
+    /// Whether automatic retries are disabled
+    pub fn is_tripped(&self) -> bool {
+        self.tripped
+    }
+
+    /// Record a failure, returning true if it tripped the breaker. A threshold of 0
+    /// never trips.
+    pub fn record_failure(&mut self, error: &str, threshold: u32, now: Instant) -> bool {
+        self.failures.retain(|at| now.saturating_duration_since(*at) < FAILURE_WINDOW);
+        self.failures.push_back(now);
+        self.last_error = error.to_string();
+        if self.tripped || threshold == 0 || self.failures.len() < threshold as usize {
+            return false;
+        }
+        self.tripped = true;
+        true
+    }
+
+    /// A successful operation clears the count
+    pub fn record_success(&mut self) {
+        self.failures.clear();
+    }
+
+    /// Re-enable automatic retries after the user asked to retry
+    pub fn reset(&mut self) {
+        self.failures.clear();
+        self.tripped = false;
+    }
+
+    /// Status text while tripped
+    pub fn message(&self) -> String {
+        format!("Disabled after repeated failures ({}). Retry to re-enable.", self.last_error)
+    }
+}
+
+/// Count a failed automatic operation, reporting it if that trips the breaker
+pub fn note_failure(breaker: &mut ErrorBreaker, config: &AppConfig, error: &str, ui_tx: &mpsc::UnboundedSender<SyncEvent>) {
+    if breaker.record_failure(error, config.error_breaker_threshold, Instant::now()) {
+        let message = breaker.message();
+        warn!("Sync: {}", message);
+        let _ = ui_tx.send(SyncEvent::Error(message.clone()));
+        send_sync_status_event(ui_tx, SyncStatus::Error(message));
+    }
+}
+
+/// Restart the managed torrent when librqbit has put it in its error state, until the
+/// breaker trips. While tripped, keep the status on the error so other checks don't
+/// replace it with Idle.
+pub async fn check_torrent_error(
+    config: &AppConfig,
+    state: &SyncState,
+    breaker: &mut ErrorBreaker,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+) {
+    if breaker.is_tripped() {
+        send_sync_status_event(ui_tx, SyncStatus::Error(breaker.message()));
+        return;
+    }
+    let LocalTorrentState::Active { id } = state.local else {
+        return;
+    };
+    let Ok(stats) = api.api_stats_v1(id.into()) else {
+        return;
+    };
+    if !matches!(stats.state, librqbit::TorrentStatsState::Error) {
+        return;
+    }
+    let error = stats.error.unwrap_or_else(|| "unknown torrent error".to_string());
+    info!("Sync: Torrent {} is in error state: {}", id, error);
+    if let Some(permission) = permission_error_in_text(&error) {
+        report_permission_error(&config.download_path, &error, permission, ui_tx);
+    }
+    if share_disconnect_in_text(&error, network_share(config).is_some()) {
+        // The share comes back on its own; retrying until it does isn't a repeated failure
+        warn!("Sync: Network share of the download folder is unreachable, retrying: {}", error);
+        send_sync_status_event(ui_tx, SyncStatus::Error(format!("Network share unreachable, retrying: {}", error)));
+    } else {
+        note_failure(breaker, config, &error, ui_tx);
+    }
+    if breaker.is_tripped() {
+        return;
+    }
+    match api.api_torrent_action_start(id.into()).await {
+        Ok(_) => info!("Sync: Restarted torrent {} after an error", id),
+        Err(e) => error!("Sync: Failed to restart torrent {}: {}", id, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trips_after_threshold_within_window() {
+        let start = Instant::now();
+        let mut breaker = ErrorBreaker::default();
+        assert!(!breaker.record_failure("disk full", 3, start));
+        assert!(!breaker.record_failure("disk full", 3, start + Duration::from_secs(10)));
+        assert!(breaker.record_failure("disk full", 3, start + Duration::from_secs(20)));
+        assert!(breaker.is_tripped());
+        assert!(breaker.message().contains("disk full"));
+        // Reported once, not on every later failure
+        assert!(!breaker.record_failure("disk full", 3, start + Duration::from_secs(30)));
+
+        breaker.reset();
+        assert!(!breaker.is_tripped());
+    }
+
+    #[test]
+    fn test_success_and_old_failures_dont_count() {
+        let start = Instant::now();
+        let mut breaker = ErrorBreaker::default();
+        breaker.record_failure("a", 2, start);
+        breaker.record_success();
+        assert!(!breaker.record_failure("b", 2, start + Duration::from_secs(1)));
+
+        // The first of these is outside the window by the time of the second
+        assert!(!breaker.record_failure("c", 2, start + FAILURE_WINDOW + Duration::from_secs(2)));
+        assert!(!breaker.is_tripped());
+        assert!(!breaker.record_failure("d", 0, start + FAILURE_WINDOW + Duration::from_secs(3)));
+    }
+}
@@ -0,0 +1,139 @@
+// src/sync/case_collisions.rs
+
+//! Files whose paths differ only by case, such as `Readme.txt` and `README.TXT`. A
+//! torrent made on Linux can have both, but on Windows and macOS they're the same file,
+//! so one silently overwrites the other and the torrent never verifies. When the
+//! download folder ignores case, `case_collisions` decides: `keep_first` downloads only
+//! the first of each group in torrent order and leaves the rest out, `refuse` doesn't
+//! add the torrent at all. librqbit writes every file at its path in the torrent, so
+//! the others can't be renamed instead.
+
+use anyhow::{Context, Result, bail};
+use librqbit::AddTorrentOptions;
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::warn;
+
+use crate::config::{AppConfig, CaseCollisions};
+
+/// Hidden file written briefly to find out whether a folder ignores case
+const PROBE_FILE: &str = ".modsync_case_probe";
+
+/// Groups of files whose '/'-separated paths match when case is ignored, as (index, path)
+/// in torrent order. Groups are ordered by their first file.
+pub fn find_collisions(paths: &[String]) -> Vec<Vec<(usize, String)>> {
+    let mut groups: HashMap<String, Vec<(usize, String)>> = HashMap::new();
+    for (index, path) in paths.iter().enumerate() {
+        groups.entry(path.to_lowercase()).or_default().push((index, path.clone()));
+    }
+    let mut collisions: Vec<Vec<(usize, String)>> = groups.into_values().filter(|group| group.len() > 1).collect();
+    collisions.sort_by_key(|group| group[0].0);
+    collisions
+}
+
+/// Whether files in `dir` (or its nearest existing parent) are looked up ignoring case.
+/// Falls back to the platform's usual answer when the folder can't be written.
+pub fn ignores_case(dir: &Path) -> bool {
+    let Some(existing) = dir.ancestors().find(|dir| dir.is_dir()) else {
+        return cfg!(any(windows, target_os = "macos"));
+    };
+    let probe = existing.join(PROBE_FILE);
+    if std::fs::write(&probe, b"").is_err() {
+        return cfg!(any(windows, target_os = "macos"));
+    }
+    let ignores = existing.join(PROBE_FILE.to_uppercase()).exists();
+    let _ = std::fs::remove_file(&probe);
+    ignores
+}
+
+/// The selection of `file_count` files without the second and later file of each group
+pub fn keep_first(collisions: &[Vec<(usize, String)>], file_count: usize, only_files: Option<&[usize]>) -> Vec<usize> {
+    let left_out: Vec<usize> = collisions.iter().flat_map(|group| group[1..].iter().map(|(index, _)| *index)).collect();
+    (0..file_count)
+        .filter(|index| only_files.is_none_or(|only| only.contains(index)))
+        .filter(|index| !left_out.contains(index))
+        .collect()
+}
+
+/// The collisions, each group's paths joined for a message
+fn describe(collisions: &[Vec<(usize, String)>]) -> String {
+    collisions
+        .iter()
+        .map(|group| group.iter().map(|(_, path)| path.as_str()).collect::<Vec<_>>().join(" / "))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Deal with case collisions in a torrent about to be added, per `case_collisions`.
+/// Runs after the other file selections. Returns a warning for the user when files were
+/// left out, and fails under `refuse`.
+pub fn apply_case_collisions(config: &AppConfig, torrent_bytes: &[u8], options: &mut AddTorrentOptions) -> Result<Option<String>> {
+    if config.case_collisions == CaseCollisions::Ignore {
+        return Ok(None);
+    }
+    let torrent = librqbit::torrent_from_bytes::<librqbit::ByteBuf>(torrent_bytes)
+        .context("Failed to parse torrent for case collisions")?;
+    let mut paths = Vec::new();
+    for file in torrent.info.iter_file_details()? {
+        paths.push(file.filename.to_pathbuf()?.to_string_lossy().replace('\\', "/"));
+    }
+    let collisions = find_collisions(&paths);
+    if collisions.is_empty() || !ignores_case(&config.download_path) {
+        return Ok(None);
+    }
+    let listed = describe(&collisions);
+    if config.case_collisions == CaseCollisions::Refuse {
+        bail!("The torrent has files that differ only by case, which this folder can't keep apart: {}", listed);
+    }
+    options.only_files = Some(keep_first(&collisions, paths.len(), options.only_files.as_deref()));
+    let message = format!("Files that differ only by case would overwrite each other here; downloading only the first of each: {}", listed);
+    warn!("Sync: {}", message);
+    Ok(Some(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 4-byte files under docs/, in one piece; the hash doesn't matter here
+    fn torrent_with(names: &[&str]) -> Vec<u8> {
+        let mut torrent = b"d4:infod5:filesl".to_vec();
+        for name in names {
+            torrent.extend_from_slice(format!("d6:lengthi4e4:pathl4:docs{}:{}ee", name.len(), name).as_bytes());
+        }
+        torrent.extend_from_slice(b"e4:name4:mods12:piece lengthi16384e6:pieces20:");
+        torrent.extend_from_slice(&[0; 20]);
+        torrent.extend_from_slice(b"ee");
+        torrent
+    }
+
+    #[test]
+    fn test_readme_case_collision() -> Result<()> {
+        let torrent = torrent_with(&["Readme.txt", "notes.txt", "README.TXT"]);
+        let paths = ["docs/Readme.txt", "docs/notes.txt", "docs/README.TXT"].map(String::from);
+        assert_eq!(find_collisions(&paths), vec![vec![(0, paths[0].clone()), (2, paths[2].clone())]]);
+        assert!(find_collisions(&paths[..2]).is_empty());
+        let collisions = find_collisions(&paths);
+        assert_eq!(keep_first(&collisions, 3, None), vec![0, 1]);
+        assert_eq!(keep_first(&collisions, 3, Some(&[1, 2])), vec![1]);
+
+        let dir = tempfile::tempdir()?;
+        let folder_ignores_case = ignores_case(dir.path());
+        let mut options = AddTorrentOptions::default();
+        let keep_first = AppConfig { download_path: dir.path().to_path_buf(), ..AppConfig::default() };
+        let warning = apply_case_collisions(&keep_first, &torrent, &mut options)?;
+        if folder_ignores_case {
+            assert!(warning.is_some_and(|w| w.contains("docs/Readme.txt / docs/README.TXT")));
+            assert_eq!(options.only_files, Some(vec![0, 1]));
+        } else {
+            // Both fit side by side
+            assert_eq!(warning, None);
+            assert_eq!(options.only_files, None);
+        }
+
+        let refuse = AppConfig { case_collisions: CaseCollisions::Refuse, ..keep_first };
+        assert_eq!(apply_case_collisions(&refuse, &torrent, &mut AddTorrentOptions::default()).is_err(), folder_ignores_case);
+        assert!(!dir.path().join(PROBE_FILE).exists(), "probe file left behind");
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+// src/sync/changelog.rs
+
+//! Release notes for the update prompt, fetched from `changelog_url` when an update is
+//! found. A hash of the last notes sent is kept per profile, so the same notes don't
+//! pop up again for an update that is only found again, e.g. after a restart.
+
+use anyhow::{Context, Result, anyhow};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+use crate::config::{AppConfig, FileStateStore, StateStore};
+
+use super::messages::SyncEvent;
+use super::utils::calculate_torrent_hash;
+
+/// State record holding the hash of the notes last shown
+const CHANGELOG_KEY: &str = "changelog";
+
+/// Notes longer than this are cut off; it's meant to be a short "what's new"
+const MAX_CHANGELOG_BYTES: usize = 64 * 1024;
+
+/// Download the changelog text
+pub async fn fetch_changelog(url: &str, client: &reqwest::Client) -> Result<String> {
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("Failed to send request to {}", url))?;
+    if !response.status().is_success() {
+        return Err(anyhow!("HTTP error: {}", response.status()));
+    }
+    let mut text = response
+        .text()
+        .await
+        .with_context(|| format!("Failed to read response body from {}", url))?;
+    if text.len() > MAX_CHANGELOG_BYTES {
+        let cut = (0..=MAX_CHANGELOG_BYTES).rev().find(|i| text.is_char_boundary(*i)).unwrap_or(0);
+        text.truncate(cut);
+        text.push_str("\n\n...");
+    }
+    Ok(text)
+}
+
+/// Whether `text` differs from the notes last recorded in `store`. New notes are
+/// recorded, so the next call with the same text returns false.
+pub fn take_unseen(text: &str, store: &dyn StateStore) -> bool {
+    let hash = calculate_torrent_hash(text.trim().as_bytes());
+    if store.read(CHANGELOG_KEY).ok().flatten().is_some_and(|seen| seen.trim() == hash) {
+        return false;
+    }
+    if let Err(e) = store.write(CHANGELOG_KEY, &hash) {
+        error!("Sync: Failed to record shown changelog: {:#}", e);
+    }
+    true
+}
+
+/// Fetch the changelog for a found update and pass it on if it hasn't been shown yet.
+/// A failed fetch only leaves the prompt without notes.
+pub async fn send_changelog(config: &AppConfig, client: &reqwest::Client, ui_tx: &mpsc::UnboundedSender<SyncEvent>) {
+    let Some(url) = config.changelog_url.as_deref().map(str::trim).filter(|url| !url.is_empty()) else {
+        return;
+    };
+    let text = match fetch_changelog(url, client).await {
+        Ok(text) if !text.trim().is_empty() => text,
+        Ok(_) => return,
+        Err(e) => {
+            warn!("Sync: Could not fetch changelog: {:#}", e);
+            return;
+        }
+    };
+    let Ok(store) = FileStateStore::session() else {
+        return;
+    };
+    if take_unseen(&text, &store) {
+        info!("Sync: Fetched changelog from {} ({} bytes)", url, text.len());
+        let _ = ui_tx.send(SyncEvent::Changelog(text));
+    } else {
+        info!("Sync: Changelog at {} was already shown", url);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changelog_is_only_new_once() -> Result<()> {
+        let seen = crate::config::MemoryStateStore::default();
+        assert!(take_unseen("## 1.2\n- New map", &seen));
+        assert!(!take_unseen("## 1.2\n- New map\n", &seen), "trailing whitespace isn't a new version");
+        assert!(take_unseen("## 1.3\n- Fixes", &seen));
+        Ok(())
+    }
+}
@@ -4,15 +4,31 @@ use anyhow::Result;
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
+use tracing::{error, info, warn};
+
+use super::extract::{EXTRACTED_MARKER_FILE, extracted_files};
+use super::ignore::is_ignored;
+use super::overwrite::SYNCED_MARKER_FILE;
+use super::owner::OWNER_MARKER_FILE;
+use super::safe_mode::SAFE_MODE_MARKER_FILE;
+
+/// Whether a download-relative path is one of ModSync's own marker files
+pub fn is_marker_file(relative_path: &Path) -> bool {
+    [OWNER_MARKER_FILE, SAFE_MODE_MARKER_FILE, SYNCED_MARKER_FILE, EXTRACTED_MARKER_FILE]
+        .iter()
+        .any(|marker| relative_path == Path::new(marker))
+}
 
 /// Scans the download directory and returns a list of files
 /// that are present locally but not in the expected set.
 /// Expected files should be relative to the download_path.
+/// Files matching `ignore_patterns` or unpacked from the torrent's archives are never extra.
 pub fn find_extra_files(
     download_path: &Path,
     expected_files: &HashSet<PathBuf>,
+    ignore_patterns: &[String],
 ) -> Result<Vec<PathBuf>> {
-    println!(
+    info!(
         "Cleaner: Scanning dir '{}' for extra files...",
         download_path.display()
     );
@@ -20,10 +36,11 @@ pub fn find_extra_files(
     let mut local_files = HashSet::new();
 
     if !download_path.exists() {
-        println!("Cleaner: Download path does not exist, nothing to scan.");
+        info!("Cleaner: Download path does not exist, nothing to scan.");
         return Ok(extra_files); // No directory, no extra files
     }
 
+    let extracted = extracted_files(download_path);
     for entry in WalkDir::new(download_path).into_iter().filter_map(|e| e.ok()) {
         let local_path = entry.path();
         // Only consider files, skip directories
@@ -31,17 +48,29 @@ pub fn find_extra_files(
             // Get the path relative to the download directory
             if let Ok(relative_path) = local_path.strip_prefix(download_path) {
                 let relative_path_buf = relative_path.to_path_buf();
+                // Our own marker files are never extra
+                if is_marker_file(&relative_path_buf) {
+                    continue;
+                }
                 local_files.insert(relative_path_buf.clone());
                 // If this local file is not in the expected set, it's extra
                 if !expected_files.contains(&relative_path_buf) {
-                    println!(
+                    if is_ignored(&relative_path_buf, ignore_patterns) {
+                        info!("Cleaner: Keeping ignored file: {}", relative_path.display());
+                        continue;
+                    }
+                    if extracted.contains(&relative_path_buf) {
+                        info!("Cleaner: Keeping extracted file: {}", relative_path.display());
+                        continue;
+                    }
+                    info!(
                         "Cleaner: Found extra file: {}",
                         relative_path.display()
                     );
                     extra_files.push(local_path.to_path_buf()); // Store the full path for deletion
                 }
             } else {
-                eprintln!(
+                warn!(
                     "Cleaner: Warning - could not strip prefix from {}",
                     local_path.display()
                 );
@@ -49,7 +78,7 @@ pub fn find_extra_files(
         }
     }
 
-    println!(
+    info!(
         "Cleaner: Scan complete. Found {} local files, {} expected files, {} extra files.",
         local_files.len(),
         expected_files.len(),
@@ -65,7 +94,7 @@ pub fn find_missing_files(
     download_path: &Path,
     expected_files: &HashSet<PathBuf>,
 ) -> Result<HashSet<PathBuf>> {
-    println!(
+    info!(
         "Cleaner: Checking for missing files in '{}'...",
         download_path.display()
     );
@@ -73,7 +102,7 @@ pub fn find_missing_files(
     let mut missing_files = expected_files.clone();
     
     if !download_path.exists() {
-        println!("Cleaner: Download path does not exist, all files are missing.");
+        info!("Cleaner: Download path does not exist, all files are missing.");
         return Ok(missing_files); // All files are missing
     }
     
@@ -93,7 +122,7 @@ pub fn find_missing_files(
         }
     }
     
-    println!(
+    info!(
         "Cleaner: Missing files check complete. {} out of {} expected files are missing.",
         missing_files.len(),
         expected_files.len()
@@ -101,7 +130,7 @@ pub fn find_missing_files(
     
     // List the missing files for debugging
     for missing in &missing_files {
-        println!("Cleaner: Missing file: {}", missing.display());
+        info!("Cleaner: Missing file: {}", missing.display());
     }
     
     Ok(missing_files)
@@ -123,7 +152,7 @@ pub fn get_expected_files_from_details(
                 for component in &file_detail.components {
                     current_path.push(component);
                 }
-                println!("Cleaner: Adding expected relative path: {}", current_path.display());
+                info!("Cleaner: Adding expected relative path: {}", current_path.display());
                 expected.insert(current_path);
             }
         }
@@ -131,6 +160,63 @@ pub fn get_expected_files_from_details(
     expected
 }
 
+/// Every file path in the torrent, whether or not it's selected for download
+pub fn get_all_files_from_details(details: &TorrentDetailsResponse) -> HashSet<PathBuf> {
+    details
+        .files
+        .iter()
+        .flatten()
+        .map(|file_detail| file_detail.components.iter().collect())
+        .collect()
+}
+
+/// Selected files that aren't fully downloaded yet, going by librqbit's per-file
+/// progress (in the same order as `details.files`). Empty if the two don't line up.
+pub fn find_partial_files(details: &TorrentDetailsResponse, file_progress: &[u64]) -> HashSet<PathBuf> {
+    let Some(files) = &details.files else {
+        return HashSet::new();
+    };
+    if files.len() != file_progress.len() {
+        return HashSet::new();
+    }
+    files
+        .iter()
+        .zip(file_progress)
+        .filter(|(file_detail, have)| file_detail.included && **have < file_detail.length)
+        .map(|(file_detail, _)| file_detail.components.iter().collect())
+        .collect()
+}
+
+/// After switching torrents, delete the old torrent's partial files that the new one
+/// no longer has. Anything the new torrent lists or `ignore_patterns` matches is left
+/// alone. Returns the relative paths that were removed.
+pub fn remove_orphaned_partial_files(
+    download_path: &Path,
+    old_partial_files: &HashSet<PathBuf>,
+    new_files: &HashSet<PathBuf>,
+    ignore_patterns: &[String],
+) -> Vec<PathBuf> {
+    let mut removed = Vec::new();
+    for relative_path in old_partial_files.difference(new_files) {
+        if is_marker_file(relative_path) || is_ignored(relative_path, ignore_patterns) {
+            continue;
+        }
+        let full_path = download_path.join(relative_path);
+        if !full_path.is_file() {
+            continue;
+        }
+        match std::fs::remove_file(&full_path) {
+            Ok(()) => {
+                info!("Cleaner: Removed orphaned partial file: {}", relative_path.display());
+                removed.push(relative_path.clone());
+            }
+            Err(e) => error!("Cleaner: Failed to remove {}: {}", full_path.display(), e),
+        }
+    }
+    removed.sort();
+    removed
+}
+
 #[cfg(test)]
 mod tests {
     use super::*; // Import functions from outer module
@@ -251,7 +337,7 @@ mod tests {
         .collect();
 
         let dir = setup_test_dir(&["file1.txt", "subdir/file2.dat"])?;
-        let extra = find_extra_files(dir.path(), &expected_files_rel)?;
+        let extra = find_extra_files(dir.path(), &expected_files_rel, &[])?;
         assert!(extra.is_empty());
         Ok(())
     }
@@ -266,7 +352,7 @@ mod tests {
         .collect();
 
         let dir = setup_test_dir(&["file1.txt", "extra_file.log"])?;
-        let extra = find_extra_files(dir.path(), &expected_files_rel)?;
+        let extra = find_extra_files(dir.path(), &expected_files_rel, &[])?;
         
         assert_eq!(extra.len(), 1);
         assert!(extra[0].ends_with("extra_file.log"));
@@ -284,7 +370,7 @@ mod tests {
         .collect();
 
         let dir = setup_test_dir(&["file1.txt", "subdir/extra.tmp"])?;
-        let extra = find_extra_files(dir.path(), &expected_files_rel)?;
+        let extra = find_extra_files(dir.path(), &expected_files_rel, &[])?;
         
         assert_eq!(extra.len(), 1);
         assert!(extra[0].ends_with("subdir/extra.tmp"));
@@ -302,7 +388,7 @@ mod tests {
         .collect();
 
         let dir = setup_test_dir(&["data/file.dat", "extra1.txt", "other/extra2.log"])?;
-        let mut extra = find_extra_files(dir.path(), &expected_files_rel)?;
+        let mut extra = find_extra_files(dir.path(), &expected_files_rel, &[])?;
         extra.sort(); // Sort for consistent assertion
         
         assert_eq!(extra.len(), 2);
@@ -324,7 +410,7 @@ mod tests {
 
         // Only create file1.txt locally, NO extra files
         let dir = setup_test_dir(&["file1.txt"])?;
-        let extra = find_extra_files(dir.path(), &expected_files_rel)?;
+        let extra = find_extra_files(dir.path(), &expected_files_rel, &[])?;
         
         // Should find no *extra* files
         assert!(extra.is_empty());
@@ -335,7 +421,7 @@ mod tests {
     fn test_find_extra_files_empty_dir() -> Result<()> {
         let expected_files_rel: HashSet<PathBuf> = HashSet::new();
         let dir = setup_test_dir(&[])?; // Empty dir
-        let extra = find_extra_files(dir.path(), &expected_files_rel)?;
+        let extra = find_extra_files(dir.path(), &expected_files_rel, &[])?;
         assert!(extra.is_empty());
         Ok(())
     }
@@ -344,8 +430,68 @@ mod tests {
     fn test_find_extra_files_non_existent_dir() -> Result<()> {
         let expected_files_rel: HashSet<PathBuf> = HashSet::new();
         let non_existent_path = PathBuf::from("surely_this_does_not_exist_12345");
-        let extra = find_extra_files(&non_existent_path, &expected_files_rel)?;
+        let extra = find_extra_files(&non_existent_path, &expected_files_rel, &[])?;
         assert!(extra.is_empty());
         Ok(())
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_find_extra_files_keeps_ignored() -> Result<()> {
+        let expected_files_rel: HashSet<PathBuf> = [PathBuf::from("file1.txt")].into_iter().collect();
+        let dir = setup_test_dir(&["file1.txt", "userconfig/mine.hpp", "stray.log"])?;
+        let extra = find_extra_files(dir.path(), &expected_files_rel, &["userconfig/**".to_string()])?;
+        assert_eq!(extra, vec![dir.path().join("stray.log")]);
+        Ok(())
+    }
+
+    // --- Tests for orphaned partial file cleanup ---
+
+    fn paths(relative_paths: &[&str]) -> HashSet<PathBuf> {
+        relative_paths.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn test_find_partial_files() {
+        let details = TorrentDetailsResponse {
+            id: Some(1),
+            info_hash: "dummy_hash".to_string(),
+            name: None,
+            output_folder: "/downloads".to_string(),
+            files: Some(vec![
+                create_dummy_file_detail(vec!["done.pbo"], 100, true),
+                create_dummy_file_detail(vec!["addons", "half.pbo"], 100, true),
+                create_dummy_file_detail(vec!["skipped.pbo"], 100, false),
+            ]),
+            stats: None,
+        };
+
+        assert_eq!(find_partial_files(&details, &[100, 50, 0]), paths(&["addons/half.pbo"]));
+        // Progress that doesn't match the file list is ignored rather than guessed at
+        assert!(find_partial_files(&details, &[0]).is_empty());
+        assert_eq!(get_all_files_from_details(&details).len(), 3);
+    }
+
+    #[test]
+    fn test_switching_torrents_removes_only_orphaned_partials() -> Result<()> {
+        let dir = setup_test_dir(&["shared/common.pbo", "old/only_old.pbo", "old/finished.pbo", "new/only_new.pbo"])?;
+
+        // Old torrent: the shared file and one old-only file were still downloading
+        let old_partial = paths(&["shared/common.pbo", "old/only_old.pbo"]);
+        // New torrent overlaps on the shared file and adds its own
+        let new_files = paths(&["shared/common.pbo", "new/only_new.pbo"]);
+
+        let removed = remove_orphaned_partial_files(dir.path(), &old_partial, &new_files, &[]);
+        assert_eq!(removed, vec![PathBuf::from("old/only_old.pbo")]);
+        assert!(!dir.path().join("old/only_old.pbo").exists());
+        assert!(dir.path().join("shared/common.pbo").exists());
+        // Complete old-only files are left for the extra files prompt
+        assert!(dir.path().join("old/finished.pbo").exists());
+        assert!(dir.path().join("new/only_new.pbo").exists());
+
+        // Then on to a torrent with a disjoint file set, mid-way through the new-only file
+        let removed = remove_orphaned_partial_files(dir.path(), &paths(&["new/only_new.pbo"]), &paths(&["other/x.pbo"]), &[]);
+        assert_eq!(removed, vec![PathBuf::from("new/only_new.pbo")]);
+        assert!(dir.path().join("shared/common.pbo").exists());
+        Ok(())
+    }
+}
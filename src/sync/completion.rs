@@ -0,0 +1,186 @@
+// src/sync/completion.rs
+
+//! `completion_marker_path`: a file that exists exactly while the synced torrent is
+//! complete, for launchers and scripts that would rather poll a file than talk to the
+//! control API. It's written when the torrent finishes, holding the info hash, the time
+//! and the size, and removed as soon as a new sync starts. Writes go through a temporary
+//! file and a rename, so a reader never sees half a marker.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::config::AppConfig;
+use crate::ui::utils::SyncStatus;
+
+use super::local::{fix_missing_files, loaded_torrent_bytes};
+use super::messages::SyncEvent;
+use super::types::{LocalTorrentState, SyncState};
+use super::utils::send_sync_status_event;
+use super::verify_complete::verify_downloaded;
+use super::version::{send_version, synced_version};
+
+/// Contents of the completion marker
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CompletionMarker {
+    pub info_hash: String,
+    pub completed_at: DateTime<Local>,
+    pub bytes: u64,
+}
+
+/// Write `marker` to `path` atomically, replacing any earlier one
+pub fn write_marker(path: &Path, marker: &CompletionMarker) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp = path.with_file_name(temp_name);
+    let contents = toml::to_string(marker).context("Failed to serialize completion marker")?;
+    std::fs::write(&temp, contents).with_context(|| format!("Failed to write {}", temp.display()))?;
+    std::fs::rename(&temp, path).with_context(|| format!("Failed to move the completion marker to {}", path.display()))
+}
+
+/// Remove the completion marker, if there is one
+pub fn clear_marker(config: &AppConfig) {
+    let Some(path) = config.completion_marker_path.as_deref() else {
+        return;
+    };
+    match std::fs::remove_file(path) {
+        Ok(()) => info!("Sync: Removed completion marker {}", path.display()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => warn!("Sync: Failed to remove completion marker {}: {}", path.display(), e),
+    }
+}
+
+/// Bytes of a finished torrent of `total_bytes` fetched from peers since it was added,
+/// and those its initial check found on disk already. librqbit only counts pieces it
+/// downloaded, so an update that shares most files with the old one fetches little.
+pub fn fetched_and_reused(total_bytes: u64, downloaded_bytes: u64) -> (u64, u64) {
+    let fetched = downloaded_bytes.min(total_bytes);
+    (fetched, total_bytes - fetched)
+}
+
+/// Wait for librqbit to finish checking the files of torrent `id`
+async fn wait_checked(api: &librqbit::Api, id: usize) -> Option<librqbit::TorrentStats> {
+    loop {
+        let stats = api.api_stats_v1(id.into()).ok()?;
+        if !matches!(stats.state, librqbit::TorrentStatsState::Initializing) {
+            return Some(stats);
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+}
+
+/// Send `SyncComplete` and write the marker once the synced torrent finishes, after the
+/// full check of `verify_after_complete` passes; remove the marker while it isn't finished
+pub async fn check_completion(config: &AppConfig, state: &mut SyncState, api: &librqbit::Api, ui_tx: &mpsc::UnboundedSender<SyncEvent>) {
+    let LocalTorrentState::Active { id } = state.local else {
+        return;
+    };
+    let Ok(stats) = api.api_stats_v1(id.into()) else {
+        return;
+    };
+    if !stats.finished {
+        if state.completed.take().is_some() {
+            send_version(ui_tx, None);
+            clear_marker(config);
+        } else if config.completion_marker_path.as_deref().is_some_and(Path::exists) {
+            clear_marker(config);
+        }
+        return;
+    }
+    let Ok(details) = api.api_torrent_details(id.into()) else {
+        return;
+    };
+    if state.completed.as_ref() == Some(&details.info_hash) {
+        return;
+    }
+    if config.verify_after_complete && state.verified.as_ref() != Some(&details.info_hash) {
+        info!("Sync: Torrent {} finished, checking every piece before reporting it complete", details.info_hash);
+        let checked = match loaded_torrent_bytes(api, id).await {
+            Ok(torrent_bytes) => verify_downloaded(config, torrent_bytes, &details, ui_tx).await,
+            Err(e) => Err(e),
+        };
+        match checked {
+            Ok(report) if report.bad_files.is_empty() => {
+                info!("Sync: Full check passed, {} pieces of {} files", report.pieces_checked, report.files_checked);
+                state.verified = Some(details.info_hash.clone());
+                send_sync_status_event(ui_tx, SyncStatus::LocalActive);
+            }
+            Ok(report) => {
+                warn!("Sync: Full check found {} damaged files, downloading them again: {:?}", report.bad_files.len(), report.bad_files);
+                let _ = ui_tx.send(SyncEvent::Error(format!(
+                    "Full check after download found {} damaged files, downloading them again",
+                    report.bad_files.len()
+                )));
+                // Re-added, librqbit hashes every piece again and fetches the bad ones
+                if !fix_missing_files(config, state, api, ui_tx).await {
+                    return;
+                }
+                let LocalTorrentState::Active { id: new_id } = state.local else {
+                    return;
+                };
+                // Its check passing counts as the full check; otherwise the torrent downloads the
+                // damaged pieces and is checked again once it finishes
+                if wait_checked(api, new_id).await.is_some_and(|stats| stats.finished) {
+                    info!("Sync: Re-check passed after the damaged files were found");
+                    state.verified = Some(details.info_hash.clone());
+                }
+                return;
+            }
+            Err(e) => {
+                // Tried again on the next tick
+                warn!("Sync: Couldn't check the finished torrent: {:#}", e);
+                return;
+            }
+        }
+    }
+    info!("Sync: Torrent {} is complete", details.info_hash);
+    // How much of an update was reused from the files already on disk rather than fetched again
+    if let Some(live) = &stats.live {
+        let (fetched, reused) = fetched_and_reused(stats.total_bytes, live.snapshot.downloaded_and_checked_bytes);
+        info!("Sync: Fetched {} bytes from peers, reused {} bytes already on disk", fetched, reused);
+    }
+    state.completed = Some(details.info_hash.clone());
+    let _ = ui_tx.send(SyncEvent::SyncComplete { info_hash: details.info_hash.clone(), bytes: stats.total_bytes });
+    if let Some(version) = synced_version(config, &details.info_hash) {
+        info!("Sync: Synced folder is version {}", version);
+        send_version(ui_tx, Some(version));
+    }
+
+    if let Some(path) = config.completion_marker_path.as_deref() {
+        let marker = CompletionMarker { info_hash: details.info_hash, completed_at: Local::now(), bytes: stats.total_bytes };
+        match write_marker(path, &marker) {
+            Ok(()) => info!("Sync: Wrote completion marker {}", path.display()),
+            Err(e) => warn!("Sync: {:#}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marker_is_written_and_cleared() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("state/modsync.done");
+        let marker = CompletionMarker { info_hash: "ab".repeat(20), completed_at: Local::now(), bytes: 4096 };
+        write_marker(&path, &marker)?;
+        let read: CompletionMarker = toml::from_str(&std::fs::read_to_string(&path)?)?;
+        assert_eq!(read.info_hash, marker.info_hash);
+        assert_eq!(read.bytes, 4096);
+        assert!(!dir.path().join("state/modsync.done.tmp").exists());
+
+        let config = AppConfig { completion_marker_path: Some(path.clone()), ..AppConfig::default() };
+        clear_marker(&config);
+        assert!(!path.exists());
+        clear_marker(&config); // Already gone is fine
+        Ok(())
+    }
+}
@@ -0,0 +1,411 @@
+// src/sync/control.rs
+
+//! Control API for external tools: pause, resume or remove a single torrent of the
+//! session by info hash, or cancel an update counting down. Served on its own `control_addr`, so graphing metrics never
+//! needs write access. Like `/metrics`, it is hand-rolled. Reading `/` and `/status` is
+//! open, but every request that changes something needs `control_token` as a bearer token.
+//! `/` serves a small web page on top of it, showing `/status` and offering its actions,
+//! for running a headless instance from a browser.
+
+use anyhow::Result;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use crate::config::get_cached_torrent_path;
+use crate::ui::utils::{SyncStatus, progress_fraction, speed_bytes_per_sec};
+
+use super::grace::UpdateGrace;
+use super::http_reply::build_reply;
+use super::listen::bind_with_fallback;
+use super::messages::{SyncCommand, SyncEvent};
+use super::metrics::{MetricsSnapshot, status_label};
+use super::torrent::{find_torrent_by_info_hash, info_hash_of};
+
+/// What a control request asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TorrentAction {
+    Pause,
+    Resume,
+    Remove, // Forget the torrent, keeping its files
+}
+
+/// What the control API acts on
+#[derive(Clone)]
+pub struct ControlContext {
+    pub api: librqbit::Api,
+    pub update_grace: UpdateGrace,
+    pub ui_tx: mpsc::UnboundedSender<SyncEvent>,
+    pub cmd_tx: mpsc::WeakUnboundedSender<SyncCommand>,
+    pub snapshot: Arc<Mutex<MetricsSnapshot>>,
+    pub token: Option<String>, // `control_token`; None refuses every change
+}
+
+/// The web page served at `/`, self-contained
+const WEB_UI: &str = include_str!("web_ui.html");
+
+/// Longest request line and headers read. Control requests carry no body, and a head
+/// cut short could hide the `Origin` header from the check.
+pub const MAX_REQUEST_HEAD: usize = 8 * 1024;
+
+/// A control API reply: HTTP status and a JSON body
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlResponse {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+impl ControlResponse {
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self { status, body: serde_json::json!({ "error": message.into() }) }
+    }
+}
+
+/// Normalise a hex info hash: 40 characters for v1 (SHA-1) or 64 for v2 (SHA-256),
+/// either case. Returns it lowercased, or None if it's neither.
+pub fn parse_info_hash(text: &str) -> Option<String> {
+    let valid = matches!(text.len(), 40 | 64) && text.bytes().all(|b| b.is_ascii_hexdigit());
+    valid.then(|| text.to_ascii_lowercase())
+}
+
+/// Match the request line to an action and info hash, or the error to reply with
+pub fn parse_request(method: &str, path: &str) -> Result<(TorrentAction, String), ControlResponse> {
+    let segments: Vec<&str> = path.trim_end_matches('/').split('/').skip(1).collect();
+    let (hash, action) = match (method, segments.as_slice()) {
+        ("POST", ["torrents", hash, "pause"]) => (hash, TorrentAction::Pause),
+        ("POST", ["torrents", hash, "resume"]) => (hash, TorrentAction::Resume),
+        ("DELETE", ["torrents", hash]) => (hash, TorrentAction::Remove),
+        (_, ["torrents", _, "pause" | "resume"] | ["torrents", _]) => {
+            return Err(ControlResponse::error(405, "Method not allowed"));
+        }
+        _ => return Err(ControlResponse::error(404, "Not found")),
+    };
+    match parse_info_hash(hash) {
+        Some(info_hash) => Ok((action, info_hash)),
+        None => Err(ControlResponse::error(400, "Info hash must be 40 (v1) or 64 (v2) hex characters")),
+    }
+}
+
+/// Info hash of the torrent ModSync keeps in sync with the torrent URL
+fn managed_info_hash() -> Option<String> {
+    let bytes = std::fs::read(get_cached_torrent_path().ok()?).ok()?;
+    info_hash_of(&bytes)
+}
+
+/// Apply `action` to the torrent with `info_hash`
+pub async fn run_action(api: &librqbit::Api, action: TorrentAction, info_hash: &str) -> ControlResponse {
+    let Some(id) = find_torrent_by_info_hash(api, info_hash) else {
+        return ControlResponse::error(404, format!("No torrent with info hash {}", info_hash));
+    };
+    let result = match action {
+        TorrentAction::Pause => api.api_torrent_action_pause(id.into()).await.map(|_| "paused"),
+        TorrentAction::Resume => api.api_torrent_action_start(id.into()).await.map(|_| "resumed"),
+        TorrentAction::Remove => {
+            // The sync manager would just add it back on the next check
+            if managed_info_hash().is_some_and(|managed| managed.eq_ignore_ascii_case(info_hash)) {
+                return ControlResponse::error(409, "This is the synced torrent; change or clear the torrent URL instead");
+            }
+            api.api_torrent_action_forget(id.into()).await.map(|_| "removed")
+        }
+    };
+    match result {
+        Ok(done) => {
+            info!("Sync: Control API {} torrent {} ({})", done, id, info_hash);
+            ControlResponse { status: 200, body: serde_json::json!({ "id": id, "info_hash": info_hash, "result": done }) }
+        }
+        Err(e) => ControlResponse::error(500, format!("{:#}", e)),
+    }
+}
+
+/// `POST /update/cancel`: stop the update counting down under `update_grace_seconds`
+fn cancel_update(method: &str, control: &ControlContext) -> ControlResponse {
+    if method != "POST" {
+        return ControlResponse::error(405, "Method not allowed");
+    }
+    if control.update_grace.cancel(&control.ui_tx) {
+        info!("Sync: Control API cancelled the pending update");
+        ControlResponse { status: 200, body: serde_json::json!({ "result": "cancelled" }) }
+    } else {
+        ControlResponse::error(409, "No update is counting down")
+    }
+}
+
+/// `POST /update/check`: check the torrent URL for an update now
+fn check_for_updates(method: &str, control: &ControlContext) -> ControlResponse {
+    if method != "POST" {
+        return ControlResponse::error(405, "Method not allowed");
+    }
+    match control.cmd_tx.upgrade().map(|cmd_tx| cmd_tx.send(SyncCommand::CheckForUpdates)) {
+        Some(Ok(())) => {
+            info!("Sync: Control API requested an update check");
+            ControlResponse { status: 200, body: serde_json::json!({ "result": "checking" }) }
+        }
+        _ => ControlResponse::error(500, "The sync manager isn't running"),
+    }
+}
+
+/// `POST /config/reload`: read config.toml again and apply what changed
+fn reload_config(method: &str, control: &ControlContext) -> ControlResponse {
+    if method != "POST" {
+        return ControlResponse::error(405, "Method not allowed");
+    }
+    match control.cmd_tx.upgrade().map(|cmd_tx| cmd_tx.send(SyncCommand::ReloadConfig)) {
+        Some(Ok(())) => {
+            info!("Sync: Control API requested a config reload");
+            ControlResponse { status: 200, body: serde_json::json!({ "result": "reloading" }) }
+        }
+        _ => ControlResponse::error(500, "The sync manager isn't running"),
+    }
+}
+
+/// `GET /status`: the synced torrent's progress and the sync status, from `snapshot`
+pub fn render_status(snapshot: &MetricsSnapshot, info_hash: Option<String>) -> serde_json::Value {
+    let stats = snapshot.stats.as_deref();
+    let live = stats.and_then(|s| s.live.as_ref());
+    let status = snapshot.status.clone().unwrap_or(SyncStatus::Idle);
+    serde_json::json!({
+        "status": status_label(&status),
+        "status_text": status.display_text(),
+        "torrent": stats.is_some(),
+        "info_hash": info_hash,
+        "paused": stats.is_some_and(|s| matches!(s.state, librqbit::TorrentStatsState::Paused)),
+        "finished": stats.is_some_and(|s| s.finished),
+        "progress": stats.map_or(0.0, |s| progress_fraction(s.progress_bytes, s.total_bytes, s.finished)),
+        "progress_bytes": stats.map_or(0, |s| s.progress_bytes),
+        "total_bytes": stats.map_or(0, |s| s.total_bytes),
+        "uploaded_bytes": stats.map_or(0, |s| s.uploaded_bytes),
+        "download_speed": live.map_or(0.0, |live| speed_bytes_per_sec(live.download_speed.mbps)),
+        "upload_speed": live.map_or(0.0, |live| speed_bytes_per_sec(live.upload_speed.mbps)),
+        "peers": live.map_or(0, |live| live.snapshot.peer_stats.live),
+        "update_applies_at": snapshot.update_applies_at.map(|at| at.to_rfc3339()),
+        "version": snapshot.version,
+        "last_synced_at": snapshot.last_synced_at.map(|at| at.to_rfc3339()),
+    })
+}
+
+/// The value of header `wanted` in `request`, whose first line is the request line
+fn header_value<'a>(request: &'a str, wanted: &str) -> Option<&'a str> {
+    request.lines().skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim().eq_ignore_ascii_case(wanted).then(|| value.trim())
+    })
+}
+
+/// Whether `request` carries `Authorization: Bearer <token>`. Without a configured token
+/// nothing is authorized. Compares every byte, so the reply time doesn't give away how much
+/// of a guess was right.
+pub fn authorized(request: &str, token: Option<&str>) -> bool {
+    let Some(token) = token.filter(|token| !token.is_empty()) else {
+        return false;
+    };
+    let Some(sent) = header_value(request, "authorization").and_then(|value| {
+        let (scheme, credentials) = value.split_once(' ')?;
+        scheme.eq_ignore_ascii_case("bearer").then(|| credentials.trim())
+    }) else {
+        return false;
+    };
+    sent.len() == token.len() && sent.bytes().zip(token.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Whether a request that changes something may go ahead. Browsers name the page that
+/// sent it in `Origin`; anything but our own page is refused, so another website open in
+/// the same browser can't press the buttons. Tools like curl send no `Origin`.
+pub fn origin_allowed(request: &str) -> bool {
+    let Some(origin) = header_value(request, "origin") else {
+        return true;
+    };
+    let origin_host = origin.strip_prefix("http://").unwrap_or("");
+    header_value(request, "host").is_some_and(|host| !origin_host.is_empty() && origin_host.eq_ignore_ascii_case(host))
+}
+
+/// The request line and headers, read up to the blank line that ends them. None when
+/// they run past [`MAX_REQUEST_HEAD`].
+pub async fn read_request_head(stream: &mut (impl AsyncRead + Unpin)) -> Result<Option<String>> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        if let Some(end) = head.windows(4).position(|window| window == b"\r\n\r\n") {
+            let fits = end + 4 <= MAX_REQUEST_HEAD;
+            return Ok(fits.then(|| String::from_utf8_lossy(&head[..end + 4]).into_owned()));
+        }
+        if head.len() > MAX_REQUEST_HEAD {
+            return Ok(None);
+        }
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            anyhow::bail!("Connection closed before the request headers ended");
+        }
+        head.extend_from_slice(&buf[..read]);
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream, control: &ControlContext, compression: bool) -> Result<()> {
+    let Some(request) = read_request_head(&mut stream).await? else {
+        let body = ControlResponse::error(431, "Request headers too large").body.to_string();
+        let reply = build_reply(&format!("431 {}", reason_phrase(431)), "application/json", body.as_bytes(), "", compression);
+        stream.write_all(&reply).await?;
+        stream.shutdown().await?;
+        return Ok(());
+    };
+    let mut parts = request.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    if method == "GET" && path == "/" {
+        let reply = build_reply("200 OK", "text/html; charset=utf-8", WEB_UI.as_bytes(), &request, compression);
+        stream.write_all(&reply).await?;
+        stream.shutdown().await?;
+        return Ok(());
+    }
+    let response = if method != "GET" && !origin_allowed(&request) {
+        ControlResponse::error(403, "Requests from other websites aren't allowed")
+    } else if method != "GET" && !authorized(&request, control.token.as_deref()) {
+        match control.token {
+            Some(_) => ControlResponse::error(401, "Missing or wrong control token"),
+            None => ControlResponse::error(401, "Set control_token in config.toml to allow changes"),
+        }
+    } else if method == "GET" && path.trim_end_matches('/') == "/status" {
+        let body = match control.snapshot.lock() {
+            Ok(snapshot) => render_status(&snapshot, managed_info_hash()),
+            Err(_) => serde_json::json!({}),
+        };
+        ControlResponse { status: 200, body }
+    } else if path.trim_end_matches('/') == "/update/check" {
+        check_for_updates(method, control)
+    } else if path.trim_end_matches('/') == "/update/cancel" {
+        cancel_update(method, control)
+    } else if path.trim_end_matches('/') == "/config/reload" {
+        reload_config(method, control)
+    } else {
+        match parse_request(method, path) {
+            Ok((action, info_hash)) => run_action(&control.api, action, &info_hash).await,
+            Err(response) => response,
+        }
+    };
+    let status_line = format!("{} {}", response.status, reason_phrase(response.status));
+    let body = response.body.to_string();
+    let reply = build_reply(&status_line, "application/json", body.as_bytes(), &request, compression);
+    stream.write_all(&reply).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Bind `addr` and serve the control API in the background
+pub async fn serve_control(addr: &str, fallback_ports: u16, compression: bool, control: ControlContext) -> Result<SocketAddr> {
+    let listener = bind_with_fallback(addr, fallback_ports, "Control API").await?;
+    let bound = listener.local_addr()?;
+    info!("Sync: Serving the control API and web page at http://{}/", bound);
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                continue;
+            };
+            let control = control.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(stream, &control, compression).await {
+                    error!("Sync: Control request failed: {}", e);
+                }
+            });
+        }
+    });
+    Ok(bound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1: &str = "0123456789ABCDEF0123456789abcdef01234567";
+
+    #[test]
+    fn test_parse_info_hash_formats() {
+        assert_eq!(parse_info_hash(V1), Some(V1.to_ascii_lowercase()));
+        assert!(parse_info_hash(&"a".repeat(64)).is_some());
+        assert_eq!(parse_info_hash(&V1[..39]), None);
+        assert_eq!(parse_info_hash(&"g".repeat(40)), None);
+    }
+
+    #[test]
+    fn test_parse_request_routes() {
+        let hash = V1.to_ascii_lowercase();
+        assert_eq!(parse_request("POST", &format!("/torrents/{}/pause", V1)), Ok((TorrentAction::Pause, hash.clone())));
+        assert_eq!(parse_request("POST", &format!("/torrents/{}/resume/", V1)), Ok((TorrentAction::Resume, hash.clone())));
+        assert_eq!(parse_request("DELETE", &format!("/torrents/{}", V1)), Ok((TorrentAction::Remove, hash)));
+        assert_eq!(parse_request("GET", &format!("/torrents/{}/pause", V1)).unwrap_err().status, 405);
+        assert_eq!(parse_request("POST", "/torrents/xyz/pause").unwrap_err().status, 400);
+        assert_eq!(parse_request("POST", "/metrics").unwrap_err().status, 404);
+    }
+
+    #[test]
+    fn test_only_our_own_page_may_post() {
+        let request = |headers: &str| format!("POST /update/check HTTP/1.1\r\nHost: 127.0.0.1:9185\r\n{}\r\n", headers);
+        assert!(origin_allowed(&request("")));
+        assert!(origin_allowed(&request("Origin: http://127.0.0.1:9185\r\n")));
+        assert!(!origin_allowed(&request("Origin: https://evil.example\r\n")));
+        assert!(!origin_allowed(&request("Origin: null\r\n")));
+
+        let snapshot = MetricsSnapshot { stats: None, status: Some(SyncStatus::RemoteChanged), ..Default::default() };
+        let status = render_status(&snapshot, None);
+        assert_eq!(status["status"], "remote_changed");
+        assert_eq!(status["torrent"], false);
+        assert_eq!(status["update_applies_at"], serde_json::Value::Null);
+        assert_eq!(status["version"], serde_json::Value::Null);
+        assert_eq!(status["last_synced_at"], serde_json::Value::Null);
+        let synced = MetricsSnapshot { version: Some("2.18".to_string()), ..Default::default() };
+        assert_eq!(render_status(&synced, None)["version"], "2.18");
+    }
+
+    #[test]
+    fn test_changes_need_the_control_token() {
+        let request = |headers: &str| format!("POST /update/check HTTP/1.1\r\nHost: 127.0.0.1:9185\r\n{}\r\n", headers);
+        assert!(authorized(&request("Authorization: Bearer s3cret\r\n"), Some("s3cret")));
+        assert!(authorized(&request("authorization: bearer  s3cret \r\n"), Some("s3cret")));
+        assert!(!authorized(&request(""), Some("s3cret")));
+        assert!(!authorized(&request("Authorization: Bearer s3cre\r\n"), Some("s3cret")));
+        assert!(!authorized(&request("Authorization: Basic s3cret\r\n"), Some("s3cret")));
+        // Without a configured token, nothing gets through
+        assert!(!authorized(&request("Authorization: Bearer \r\n"), Some("")));
+        assert!(!authorized(&request("Authorization: Bearer s3cret\r\n"), None));
+    }
+
+    #[tokio::test]
+    async fn test_request_head_is_read_to_the_blank_line() -> Result<()> {
+        // An Origin header well past the first read still reaches the check
+        let padding = format!("X-Padding: {}\r\n", "a".repeat(2000));
+        let request = format!("POST /update/check HTTP/1.1\r\nHost: 127.0.0.1:9185\r\n{}Origin: https://evil.example\r\n\r\n", padding);
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let sent = request.clone();
+        tokio::spawn(async move { client.write_all(sent.as_bytes()).await });
+        let head = read_request_head(&mut server).await?.expect("fits the cap");
+        assert_eq!(head, request);
+        assert!(!origin_allowed(&head));
+
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        tokio::spawn(async move {
+            let huge = format!("GET /status HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "a".repeat(MAX_REQUEST_HEAD));
+            client.write_all(huge.as_bytes()).await
+        });
+        assert!(read_request_head(&mut server).await?.is_none());
+
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client.write_all(b"GET /status HTTP/1.1\r\n").await?;
+        drop(client);
+        assert!(read_request_head(&mut server).await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+// src/sync/cpu_limit.rs
+
+//! `verify_cpu_limit`: how many cores piece hashing may keep busy. librqbit hashes on
+//! the tokio runtime's own threads (the initial check and every downloaded piece), so
+//! the limit sizes the runtime: half the threads run async work, the rest blocking
+//! work such as hashing and file I/O. Without a limit tokio uses one worker per core.
+
+use anyhow::{Context, Result};
+use tokio::runtime::{Builder, Runtime};
+use tracing::info;
+
+use crate::config::{AppConfig, CpuLimit};
+
+/// Threads allowed for `limit` on a machine with `cores` cores, at least 1
+pub fn thread_budget(limit: CpuLimit, cores: usize) -> usize {
+    let cores = cores.max(1);
+    match limit {
+        CpuLimit::Threads(threads) => threads.clamp(1, cores),
+        CpuLimit::Fraction(fraction) => ((cores as f64 * fraction).round() as usize).clamp(1, cores),
+    }
+}
+
+/// (worker, blocking) thread counts for a budget. Both need at least one.
+fn split_threads(budget: usize) -> (usize, usize) {
+    let workers = budget.div_ceil(2);
+    (workers, (budget - workers).max(1))
+}
+
+/// The runtime ModSync runs on, sized by `verify_cpu_limit`
+pub fn build_runtime(config: &AppConfig) -> Result<Runtime> {
+    let mut builder = Builder::new_multi_thread();
+    builder.enable_all();
+    if let Some(limit) = config.verify_cpu_limit {
+        let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+        let (workers, blocking) = split_threads(thread_budget(limit, cores));
+        info!(
+            "Main: Limiting hashing to {} of {} cores ({} async, {} blocking threads)",
+            workers + blocking,
+            cores,
+            workers,
+            blocking
+        );
+        builder.worker_threads(workers).max_blocking_threads(blocking);
+    }
+    builder.build().context("Failed to create tokio runtime")
+}
+
+/// How many torrents librqbit may check at once: one under a limit, its default otherwise
+pub fn concurrent_checks(config: &AppConfig) -> Option<usize> {
+    config.verify_cpu_limit.map(|_| 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thread_budget() {
+        assert_eq!(thread_budget(CpuLimit::Threads(2), 8), 2);
+        assert_eq!(thread_budget(CpuLimit::Threads(0), 8), 1);
+        assert_eq!(thread_budget(CpuLimit::Threads(32), 8), 8);
+        assert_eq!(thread_budget(CpuLimit::Fraction(0.5), 8), 4);
+        assert_eq!(thread_budget(CpuLimit::Fraction(0.01), 8), 1);
+        assert_eq!(thread_budget(CpuLimit::Fraction(1.5), 8), 8);
+
+        assert_eq!(split_threads(1), (1, 1));
+        assert_eq!(split_threads(4), (2, 2));
+        assert_eq!(split_threads(5), (3, 2));
+    }
+
+    #[test]
+    fn test_limit_reads_as_count_or_fraction() {
+        #[derive(serde::Deserialize)]
+        struct Setting {
+            verify_cpu_limit: CpuLimit,
+        }
+        let read = |toml_text: &str| toml::from_str::<Setting>(toml_text).map(|s| s.verify_cpu_limit).ok();
+        assert_eq!(read("verify_cpu_limit = 2"), Some(CpuLimit::Threads(2)));
+        assert_eq!(read("verify_cpu_limit = 0.5"), Some(CpuLimit::Fraction(0.5)));
+        assert_eq!(read("verify_cpu_limit = \"half\""), None);
+    }
+}
@@ -0,0 +1,120 @@
+// src/sync/disk.rs
+
+//! Free disk space monitoring for the download volume
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+use crate::config::AppConfig;
+use crate::ui::utils::SyncStatus;
+
+use super::messages::SyncEvent;
+use super::types::{LocalTorrentState, SyncState};
+use super::utils::send_sync_status_event;
+
+/// Extra headroom (in MB) required above the minimum before resuming,
+/// so we don't flap between paused and seeding around the threshold
+const RESUME_HYSTERESIS_MB: u64 = 256;
+
+/// Returns the space available to us (in MB) on the volume containing `path`
+pub fn available_space_mb(path: &Path) -> Result<u64> {
+    let bytes = fs4::available_space(path)
+        .with_context(|| format!("Failed to query free space for {}", path.display()))?;
+    Ok(bytes / (1024 * 1024))
+}
+
+/// Decide whether the volume counts as low on space, taking hysteresis into account
+pub fn is_low_on_space(available_mb: u64, min_free_mb: u64, currently_low: bool) -> bool {
+    if currently_low {
+        available_mb < min_free_mb.saturating_add(RESUME_HYSTERESIS_MB)
+    } else {
+        available_mb < min_free_mb
+    }
+}
+
+/// Check free space on the download volume, pausing or resuming the managed torrent
+/// when the configured minimum is crossed
+pub async fn check_free_space(
+    config: &AppConfig,
+    state: &mut SyncState,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+) {
+    let low = match config.min_free_space_mb {
+        Some(min_free_mb) if !config.download_path.as_os_str().is_empty() => {
+            match available_space_mb(&config.download_path) {
+                Ok(available_mb) => {
+                    let low = is_low_on_space(available_mb, min_free_mb, state.low_disk_space);
+                    if low != state.low_disk_space {
+                        info!(
+                            "Sync: Free space on download volume is {} MB (minimum {} MB), {}",
+                            available_mb,
+                            min_free_mb,
+                            if low { "pausing" } else { "resuming" }
+                        );
+                    }
+                    low
+                }
+                Err(e) => {
+                    warn!("Sync: {}", e);
+                    return; // Leave the current state untouched
+                }
+            }
+        }
+        // Check disabled; make sure we don't stay paused because of it
+        _ => false,
+    };
+
+    if low && !state.low_disk_space {
+        state.low_disk_space = true;
+        if let LocalTorrentState::Active { id } = state.local {
+            match api.api_torrent_action_pause(id.into()).await {
+                Ok(_) => info!("Sync: Paused torrent {} due to low disk space", id),
+                Err(e) => error!("Sync: Failed to pause torrent {} for low disk space: {}", id, e),
+            }
+        }
+        let _ = ui_tx.send(SyncEvent::Error("Low disk space: seeding paused".to_string()));
+    } else if !low && state.low_disk_space {
+        state.low_disk_space = false;
+        if let LocalTorrentState::Active { id } = state.local {
+            match api.api_torrent_action_start(id.into()).await {
+                Ok(_) => info!("Sync: Resumed torrent {} after disk space was freed", id),
+                Err(e) => error!("Sync: Failed to resume torrent {}: {}", id, e),
+            }
+        }
+        send_sync_status_event(ui_tx, SyncStatus::Idle);
+    }
+
+    if state.low_disk_space {
+        send_sync_status_event(ui_tx, SyncStatus::LowDiskSpace);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_low_space_enters_below_minimum() {
+        assert!(!is_low_on_space(1_000, 500, false));
+        assert!(is_low_on_space(499, 500, false));
+    }
+
+    #[test]
+    fn test_low_space_resumes_only_above_hysteresis() {
+        // Just above the minimum is not enough once we're paused
+        assert!(is_low_on_space(600, 500, true));
+        assert!(is_low_on_space(500 + RESUME_HYSTERESIS_MB - 1, 500, true));
+        assert!(!is_low_on_space(500 + RESUME_HYSTERESIS_MB, 500, true));
+    }
+
+    #[test]
+    fn test_available_space_for_temp_dir() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        // Any real volume reports something; mostly checks the call succeeds
+        let _ = available_space_mb(dir.path())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,422 @@
+// src/sync/extract.rs
+
+//! `extract_archives`: once the torrent is complete, zip and 7z archives among its files
+//! that match one of the patterns are unpacked next to themselves, for mod sets
+//! distributed as archives. The archives stay for seeding. What was extracted is recorded
+//! in a marker, so the files aren't reported as extra and aren't unpacked again until the
+//! torrent or the patterns change. Entries that would land outside the archive's folder
+//! (zip-slip) or on top of one of the torrent's own files are skipped.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+use crate::config::AppConfig;
+use crate::glob::glob_match;
+use crate::ui::utils::SyncStatus;
+
+use super::cleaner::get_all_files_from_details;
+use super::ignore::validate_pattern;
+use super::messages::SyncEvent;
+use super::types::{LocalTorrentState, SyncState};
+use super::utils::send_sync_status_event;
+
+/// Hidden file in the download folder listing the files unpacked from archives
+pub const EXTRACTED_MARKER_FILE: &str = ".modsync_extracted";
+
+/// Files unpacked from one archive
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractedArchive {
+    pub archive: String, // '/'-separated, relative to the download folder
+    pub files: usize,
+}
+
+/// What unpacking the torrent's archives did
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExtractSummary {
+    pub archives: Vec<ExtractedArchive>,
+    pub skipped: Vec<(String, String)>, // Archive entry, and why it wasn't written
+    pub failed: Vec<(String, String)>,  // Archive or pattern, and why it couldn't be unpacked
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+struct ExtractRecord {
+    info_hash: String,
+    patterns: Vec<String>,
+    files: Vec<String>, // '/'-separated, relative to the download folder
+}
+
+fn read_record(download_path: &Path) -> Option<ExtractRecord> {
+    let contents = fs::read_to_string(download_path.join(EXTRACTED_MARKER_FILE)).ok()?;
+    toml::from_str(&contents).ok()
+}
+
+/// Files unpacked from the torrent's archives, relative to the download folder
+pub fn extracted_files(download_path: &Path) -> HashSet<PathBuf> {
+    read_record(download_path)
+        .map(|record| record.files.iter().map(|file| file.split('/').collect()).collect())
+        .unwrap_or_default()
+}
+
+/// `name` as a path relative to the extraction folder, or None when it's absolute or
+/// climbs out of it
+pub fn safe_entry_path(name: &str) -> Option<PathBuf> {
+    if name.starts_with(['/', '\\']) {
+        return None;
+    }
+    let mut path = PathBuf::new();
+    for part in name.split(['/', '\\']) {
+        match part {
+            "" | "." => continue,
+            ".." => return None,
+            // A drive letter or an NTFS stream
+            part if part.contains(':') => return None,
+            part => path.push(part),
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+/// Entries of one archive that were written, relative to its folder, or skipped with why
+#[derive(Default)]
+struct Unpacked {
+    written: Vec<PathBuf>,
+    skipped: Vec<(String, String)>,
+}
+
+/// Copy `data` to `target`, creating its folder. A partly written file is removed.
+fn write_entry(data: &mut dyn Read, target: &Path) -> Result<()> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let written = File::create(target).map_err(anyhow::Error::from).and_then(|file| {
+        let mut output = BufWriter::new(file);
+        std::io::copy(data, &mut output)?;
+        output.flush()?;
+        Ok(())
+    });
+    if written.is_err() {
+        let _ = fs::remove_file(target);
+    }
+    written
+}
+
+impl Unpacked {
+    /// Write the entry `name` from `data` under `destination`, unless it would land outside
+    /// it or `keep` turns it down. Returns whether it was written.
+    fn entry(&mut self, name: &str, data: &mut dyn Read, destination: &Path, keep: &impl Fn(&Path) -> bool) -> bool {
+        let Some(relative) = safe_entry_path(name) else {
+            self.skipped.push((name.to_string(), "would be written outside the archive's folder".to_string()));
+            return false;
+        };
+        if !keep(&relative) {
+            self.skipped.push((name.to_string(), "would overwrite a file of the torrent".to_string()));
+            return false;
+        }
+        match write_entry(data, &destination.join(&relative)) {
+            Ok(()) => {
+                self.written.push(relative);
+                true
+            }
+            Err(e) => {
+                // The zip and 7z readers check each entry's CRC as it ends
+                self.skipped.push((name.to_string(), format!("{:#}", e)));
+                false
+            }
+        }
+    }
+}
+
+/// Unpack the zip `archive_path` into `destination`. `keep` says whether a relative path
+/// may be written; `progress` gets the archive bytes read so far.
+fn extract_zip(
+    archive_path: &Path,
+    destination: &Path,
+    keep: impl Fn(&Path) -> bool,
+    mut progress: impl FnMut(u64),
+) -> Result<Unpacked> {
+    let mut archive = zip::ZipArchive::new(File::open(archive_path)?).context("Not a zip archive")?;
+    let mut unpacked = Unpacked::default();
+    let mut read = 0;
+    for index in 0..archive.len() {
+        let name = match archive.name_for_index(index) {
+            Some(Ok(name)) => name.into_owned(),
+            _ => format!("entry {}", index + 1),
+        };
+        let mut entry = match archive.by_index(index) {
+            Ok(entry) => entry,
+            Err(e) => {
+                // Encrypted entries and unsupported compression methods end up here
+                unpacked.skipped.push((name, e.to_string()));
+                continue;
+            }
+        };
+        read += entry.compressed_size();
+        if entry.is_dir() {
+            continue; // Folders are created along with their files
+        }
+        unpacked.entry(&name, &mut entry, destination, &keep);
+        progress(read);
+    }
+    Ok(unpacked)
+}
+
+/// `extract_zip` for a 7z archive. Solid archives decode as one stream, so skipped
+/// entries are still read through, and progress is estimated from the unpacked sizes.
+fn extract_7z(
+    archive_path: &Path,
+    destination: &Path,
+    keep: impl Fn(&Path) -> bool,
+    mut progress: impl FnMut(u64),
+) -> Result<Unpacked> {
+    let mut archive = sevenz_rust::SevenZReader::open(archive_path, sevenz_rust::Password::empty()).context("Not a 7z archive")?;
+    let archive_len = fs::metadata(archive_path)?.len();
+    let unpacked_len: u64 = archive.archive().files.iter().map(|file| file.size()).sum();
+    let mut unpacked = Unpacked::default();
+    let mut done = 0;
+    archive.for_each_entries(|entry, data| {
+        done += entry.size();
+        if !entry.is_directory() && !unpacked.entry(entry.name(), data, destination, &keep) {
+            std::io::copy(data, &mut std::io::sink())?;
+        }
+        progress((u128::from(archive_len) * u128::from(done) / u128::from(unpacked_len.max(1))) as u64);
+        Ok(true)
+    })?;
+    Ok(unpacked)
+}
+
+/// Unpack each of `archives` ('/'-separated, relative to `download_path`) into its own
+/// folder, never over one of `torrent_files`. Returns the summary and the written files.
+/// `progress` gets whole percentages of the archive bytes read.
+pub fn extract_archives(
+    download_path: &Path,
+    archives: &[String],
+    torrent_files: &HashSet<PathBuf>,
+    mut progress: impl FnMut(u8),
+) -> (ExtractSummary, Vec<String>) {
+    let mut summary = ExtractSummary::default();
+    let mut files = Vec::new();
+    let total: u64 = archives.iter().filter_map(|a| fs::metadata(download_path.join(a)).ok()).map(|m| m.len()).sum();
+    let mut done = 0;
+    for archive in archives {
+        let relative_folder: PathBuf = archive.split('/').collect::<PathBuf>().parent().map(Path::to_path_buf).unwrap_or_default();
+        let archive_path = download_path.join(archive);
+        let destination = download_path.join(&relative_folder);
+        let keep = |relative: &Path| !torrent_files.contains(&relative_folder.join(relative));
+        let report = |read: u64| progress(((done + read) * 100 / total.max(1)).min(100) as u8);
+        let result = if archive.to_ascii_lowercase().ends_with(".7z") {
+            extract_7z(&archive_path, &destination, keep, report)
+        } else {
+            extract_zip(&archive_path, &destination, keep, report)
+        };
+        done += fs::metadata(&archive_path).map_or(0, |m| m.len());
+        match result {
+            Ok(Unpacked { written, skipped }) => {
+                summary.archives.push(ExtractedArchive { archive: archive.clone(), files: written.len() });
+                summary.skipped.extend(skipped.into_iter().map(|(entry, why)| (format!("{}: {}", archive, entry), why)));
+                files.extend(written.iter().map(|file| {
+                    relative_folder.join(file).components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+                }));
+            }
+            Err(e) => summary.failed.push((archive.clone(), format!("{:#}", e))),
+        }
+    }
+    (summary, files)
+}
+
+/// Unpack the managed torrent's matching archives once it has finished downloading.
+/// Runs again after an update or when the patterns change.
+pub async fn check_extractions(
+    config: &AppConfig,
+    state: &mut SyncState,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+) {
+    if config.extract_archives.is_empty() {
+        return;
+    }
+    let LocalTorrentState::Active { id } = state.local else {
+        return;
+    };
+    if !api.api_stats_v1(id.into()).is_ok_and(|stats| stats.finished) {
+        return;
+    }
+    let Ok(details) = api.api_torrent_details(id.into()) else {
+        return;
+    };
+    let extracted = (details.info_hash.clone(), config.extract_archives.clone());
+    if state.extracted.as_ref() == Some(&extracted) {
+        return;
+    }
+    // Unpacked by an earlier run
+    if read_record(&config.download_path).is_some_and(|record| record.info_hash == extracted.0 && record.patterns == extracted.1) {
+        state.extracted = Some(extracted);
+        return;
+    }
+
+    let mut failed = Vec::new();
+    let mut patterns = Vec::new();
+    for pattern in &config.extract_archives {
+        match validate_pattern(pattern) {
+            Ok(()) => patterns.push(pattern.trim().to_string()),
+            Err(e) => failed.push((pattern.clone(), e)),
+        }
+    }
+    let archives: Vec<String> = details
+        .files
+        .iter()
+        .flatten()
+        .filter(|file| file.included)
+        .map(|file| file.components.join("/"))
+        .filter(|file| patterns.iter().any(|pattern| glob_match(pattern, file)))
+        .collect();
+    if archives.is_empty() && failed.is_empty() {
+        state.extracted = Some(extracted);
+        return;
+    }
+
+    info!("Sync: Extracting {} archives", archives.len());
+    send_sync_status_event(ui_tx, SyncStatus::Extracting { percent: 0 });
+    let download_path = config.download_path.clone();
+    let torrent_files = get_all_files_from_details(&details);
+    let progress_tx = ui_tx.clone();
+    let task = tokio::task::spawn_blocking(move || {
+        let mut last = 0;
+        extract_archives(&download_path, &archives, &torrent_files, |percent| {
+            if percent != last {
+                last = percent;
+                send_sync_status_event(&progress_tx, SyncStatus::Extracting { percent });
+            }
+        })
+    });
+    let (mut summary, files) = match task.await {
+        Ok(result) => result,
+        Err(e) => {
+            error!("Sync: Extracting archives failed: {}", e);
+            return;
+        }
+    };
+    summary.failed.splice(0..0, failed);
+
+    for archive in &summary.archives {
+        info!("Sync: Extracted {} files from {}", archive.files, archive.archive);
+    }
+    for (entry, why) in &summary.skipped {
+        warn!("Sync: Skipped {}: {}", entry, why);
+    }
+    for (archive, error) in &summary.failed {
+        warn!("Sync: Could not extract {}: {}", archive, error);
+    }
+    // Failed archives are tried again on the next start
+    if summary.failed.is_empty() {
+        let record = ExtractRecord { info_hash: extracted.0.clone(), patterns: extracted.1.clone(), files };
+        let written = toml::to_string(&record)
+            .map_err(anyhow::Error::from)
+            .and_then(|contents| Ok(fs::write(config.download_path.join(EXTRACTED_MARKER_FILE), contents)?));
+        if let Err(e) = written {
+            warn!("Sync: Could not record extracted files: {}", e);
+        }
+    }
+    state.extracted = Some(extracted);
+    send_sync_status_event(ui_tx, SyncStatus::LocalActive);
+    let _ = ui_tx.send(SyncEvent::ArchivesExtracted(summary));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A zip holding `entries` (name, data, deflated?)
+    fn build_zip(entries: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        for (name, data, deflate) in entries {
+            let method = if *deflate { zip::CompressionMethod::Deflated } else { zip::CompressionMethod::Stored };
+            zip.start_file(*name, zip::write::SimpleFileOptions::default().compression_method(method)).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    /// A 7z holding `entries` (name, data), compressed as one solid stream
+    fn build_7z(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut archive = sevenz_rust::SevenZWriter::new(std::io::Cursor::new(Vec::new())).unwrap();
+        let entries = entries.iter().map(|(name, data)| {
+            let mut entry = sevenz_rust::SevenZArchiveEntry::new();
+            entry.name = name.to_string();
+            entry.has_stream = true;
+            (entry, *data)
+        });
+        let (entries, readers): (Vec<_>, Vec<_>) = entries.unzip();
+        let readers: Vec<_> = readers.into_iter().map(sevenz_rust::SourceReader::new).collect();
+        archive.push_archive_entries(entries, readers.into()).unwrap();
+        archive.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_entry_paths_stay_inside_the_folder() {
+        assert_eq!(safe_entry_path("addons/a.pbo"), Some(PathBuf::from("addons").join("a.pbo")));
+        assert_eq!(safe_entry_path("./keys\\a.bikey"), Some(PathBuf::from("keys").join("a.bikey")));
+        for escaping in ["../evil.dll", "addons/../../evil.dll", "/etc/passwd", "\\Windows\\evil.dll", "C:/evil.dll", "a.pbo:stream", ""] {
+            assert_eq!(safe_entry_path(escaping), None, "{}", escaping);
+        }
+    }
+
+    #[test]
+    fn test_archives_extract_next_to_themselves() -> Result<()> {
+        let download = tempfile::tempdir()?;
+        fs::create_dir_all(download.path().join("@mod"))?;
+        let pbo = vec![7u8; 10_000];
+        let zip = build_zip(&[
+            ("addons/a.pbo", &pbo, true),
+            ("readme.txt", b"hello", false),
+            ("../escape.txt", b"nope", false),
+            ("mod.cpp", b"name = \"new\";", false),
+        ]);
+        fs::write(download.path().join("@mod/pack.zip"), zip)?;
+        fs::write(download.path().join("@mod/mod.cpp"), b"name = \"seeded\";")?;
+        fs::write(download.path().join("@mod/broken.zip"), b"not a zip at all, just text")?;
+        let torrent_files: HashSet<PathBuf> =
+            ["@mod/pack.zip", "@mod/mod.cpp", "@mod/broken.zip"].iter().map(|f| f.split('/').collect()).collect();
+
+        let mut reported = Vec::new();
+        let archives = vec!["@mod/pack.zip".to_string(), "@mod/broken.zip".to_string()];
+        let (summary, mut files) = extract_archives(download.path(), &archives, &torrent_files, |p| reported.push(p));
+        files.sort();
+        assert_eq!(files, vec!["@mod/addons/a.pbo", "@mod/readme.txt"]);
+        assert_eq!(summary.archives, vec![ExtractedArchive { archive: "@mod/pack.zip".to_string(), files: 2 }]);
+        assert_eq!(fs::read(download.path().join("@mod/addons/a.pbo"))?, pbo);
+        assert_eq!(fs::read(download.path().join("@mod/readme.txt"))?, b"hello");
+        assert!(!download.path().join("escape.txt").exists());
+        assert_eq!(fs::read(download.path().join("@mod/mod.cpp"))?, b"name = \"seeded\";", "torrent files are never overwritten");
+        let skipped: Vec<&str> = summary.skipped.iter().map(|(entry, _)| entry.as_str()).collect();
+        assert_eq!(skipped, vec!["@mod/pack.zip: ../escape.txt", "@mod/pack.zip: mod.cpp"]);
+        assert_eq!(summary.failed.len(), 1);
+        assert!(reported.iter().all(|&p| p <= 100) && !reported.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_7z_archives_extract_too() -> Result<()> {
+        let download = tempfile::tempdir()?;
+        let pbo = vec![3u8; 20_000];
+        let archive = build_7z(&[("../escape.txt", b"nope"), ("mod.cpp", b"name = \"new\";"), ("addons/b.pbo", &pbo)]);
+        fs::create_dir_all(download.path().join("@mod"))?;
+        fs::write(download.path().join("@mod/pack.7z"), archive)?;
+        fs::write(download.path().join("@mod/mod.cpp"), b"name = \"seeded\";")?;
+        let torrent_files: HashSet<PathBuf> = ["@mod/pack.7z", "@mod/mod.cpp"].iter().map(|f| f.split('/').collect()).collect();
+
+        let (summary, files) = extract_archives(download.path(), &["@mod/pack.7z".to_string()], &torrent_files, |_| {});
+        // Skipped entries are read through, so the one after them still unpacks
+        assert_eq!(files, vec!["@mod/addons/b.pbo"]);
+        assert_eq!(fs::read(download.path().join("@mod/addons/b.pbo"))?, pbo);
+        assert_eq!(fs::read(download.path().join("@mod/mod.cpp"))?, b"name = \"seeded\";");
+        assert_eq!(summary.skipped.len(), 2);
+        assert!(summary.failed.is_empty(), "{:?}", summary.failed);
+        Ok(())
+    }
+}
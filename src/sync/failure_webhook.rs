@@ -0,0 +1,115 @@
+// src/sync/failure_webhook.rs
+
+//! `failure_webhook_url`: pages someone when an unattended instance stops syncing. After
+//! `failure_webhook_after` scheduled update checks fail in a row, a JSON payload with the
+//! error is posted to the URL, once per run of failures; a check that succeeds starts the
+//! count again. The payload carries `text` and `content` next to the structured fields,
+//! so Slack and Discord incoming webhooks show it as a message without an adapter.
+
+use anyhow::{Context, Result, anyhow};
+use chrono::Local;
+use tracing::{info, warn};
+
+use crate::config::AppConfig;
+
+use super::http::FailureKind;
+
+/// Counts scheduled update checks failing in a row
+#[derive(Debug, Default)]
+pub struct FailureStreak {
+    failures: u32,
+    alerted: bool,
+}
+
+impl FailureStreak {
+    /// Record a failed check, returning the number failed in a row when that reaches
+    /// `after` for the first time in this run
+    pub fn record_failure(&mut self, after: u32) -> Option<u32> {
+        self.failures = self.failures.saturating_add(1);
+        if self.alerted || self.failures < after.max(1) {
+            return None;
+        }
+        self.alerted = true;
+        Some(self.failures)
+    }
+
+    /// A successful check ends the run
+    pub fn record_success(&mut self) {
+        self.failures = 0;
+        self.alerted = false;
+    }
+}
+
+/// JSON posted to the webhook. `error` names the kind of failure, so alerting rules can
+/// tell a DNS outage from a server answering 502; `http_status` is the code, if any.
+pub fn failure_payload(message: &str, kind: FailureKind, failures: u32) -> serde_json::Value {
+    let text = format!("ModSync: {} update checks failed in a row. Last error: {}", failures, message);
+    let http_status = match kind {
+        FailureKind::HttpStatus(status) => Some(status),
+        _ => None,
+    };
+    serde_json::json!({
+        "error": kind.label(),
+        "http_status": http_status,
+        "message": message,
+        "consecutive_failures": failures,
+        "timestamp": Local::now().to_rfc3339(),
+        "text": text,
+        "content": text,
+    })
+}
+
+async fn post_failure(url: &str, client: &reqwest::Client, payload: &serde_json::Value) -> Result<()> {
+    let response = client.post(url).json(payload).send().await.context("Failed to send the webhook request")?;
+    if !response.status().is_success() {
+        return Err(anyhow!("HTTP error: {}", response.status()));
+    }
+    Ok(())
+}
+
+/// Count a failed scheduled check, posting to `failure_webhook_url` once enough failed in
+/// a row. The post runs in the background, so a slow webhook doesn't hold up syncing.
+pub fn note_check_failure(streak: &mut FailureStreak, config: &AppConfig, client: &reqwest::Client, message: &str, kind: FailureKind) {
+    let Some(url) = config.failure_webhook_url.clone().filter(|url| !url.trim().is_empty()) else {
+        return;
+    };
+    let Some(failures) = streak.record_failure(config.failure_webhook_after) else {
+        return;
+    };
+    let payload = failure_payload(message, kind, failures);
+    let client = client.clone();
+    tokio::spawn(async move {
+        match post_failure(&url, &client, &payload).await {
+            Ok(()) => info!("Sync: Reported {} failed update checks to the failure webhook", failures),
+            Err(e) => warn!("Sync: Failure webhook not reached: {:#}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alerts_once_per_run_of_failures() {
+        let mut streak = FailureStreak::default();
+        assert_eq!(streak.record_failure(3), None);
+        assert_eq!(streak.record_failure(3), None);
+        assert_eq!(streak.record_failure(3), Some(3));
+        assert_eq!(streak.record_failure(3), None, "not again until a check succeeds");
+        streak.record_success();
+        assert_eq!(streak.record_failure(3), None);
+        // 0 alerts on the first failure
+        assert_eq!(FailureStreak::default().record_failure(0), Some(1));
+
+        let payload = failure_payload("Failed to download remote torrent: HTTP error: 502", FailureKind::HttpStatus(502), 3);
+        assert_eq!(payload["error"], "http_status");
+        assert_eq!(payload["http_status"], 502);
+        assert_eq!(payload["consecutive_failures"], 3);
+        assert!(payload["content"].as_str().is_some_and(|text| text.contains("HTTP error: 502")));
+        assert!(chrono::DateTime::parse_from_rfc3339(payload["timestamp"].as_str().unwrap()).is_ok());
+        let payload = failure_payload("Can't resolve host mods.example", FailureKind::Dns, 3);
+        assert_eq!(payload["error"], "dns");
+        assert_eq!(payload["http_status"], serde_json::Value::Null);
+    }
+}
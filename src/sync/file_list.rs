@@ -0,0 +1,144 @@
+// src/sync/file_list.rs
+
+//! Exports the file list of a torrent (paths, sizes and any per-file hashes) as CSV or
+//! JSON, so admins can document or diff what a distributed torrent contains
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::Path;
+
+/// Output format of an exported file list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// One file of the torrent
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FileListEntry {
+    pub path: String, // '/'-separated, relative to the download folder
+    pub size: u64,
+    pub sha1: Option<String>, // Only present if the torrent creator added BEP 47 file hashes
+}
+
+#[derive(Serialize)]
+struct JsonFileList<'a> {
+    info_hash: &'a str,
+    files: &'a [FileListEntry],
+    total_count: usize,
+    total_size: u64,
+}
+
+/// The torrent's files in torrent order, leaving out BEP 47 padding files
+pub fn file_list_from_torrent(torrent_bytes: &[u8]) -> Result<(String, Vec<FileListEntry>)> {
+    let torrent = librqbit::torrent_from_bytes::<librqbit::ByteBuf>(torrent_bytes)
+        .context("Failed to parse torrent for file list")?;
+    let mut entries = Vec::new();
+    for file in torrent.info.iter_file_details()? {
+        if file.attrs().padding {
+            continue;
+        }
+        entries.push(FileListEntry {
+            path: file.filename.to_pathbuf()?.to_string_lossy().replace('\\', "/"),
+            size: file.len,
+            sha1: file.sha1.map(|hash| hash.iter().map(|b| format!("{:02x}", b)).collect()),
+        });
+    }
+    Ok((torrent.info_hash.as_string(), entries))
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Render the list in `format`, ending with the total count and size
+pub fn render_file_list(info_hash: &str, entries: &[FileListEntry], format: ExportFormat) -> Result<String> {
+    let total_size: u64 = entries.iter().map(|e| e.size).sum();
+    match format {
+        ExportFormat::Csv => {
+            let mut csv = String::from("path,size,sha1\n");
+            for entry in entries {
+                csv.push_str(&format!(
+                    "{},{},{}\n",
+                    csv_field(&entry.path),
+                    entry.size,
+                    entry.sha1.as_deref().unwrap_or_default()
+                ));
+            }
+            csv.push_str(&format!("{},{},\n", csv_field(&format!("TOTAL ({} files)", entries.len())), total_size));
+            Ok(csv)
+        }
+        ExportFormat::Json => {
+            let list = JsonFileList { info_hash, files: entries, total_count: entries.len(), total_size };
+            serde_json::to_string_pretty(&list).context("Failed to serialize file list")
+        }
+    }
+}
+
+/// Write the file list of `torrent_bytes` to `output_path`, returning how many files it lists
+pub fn export_file_list(torrent_bytes: &[u8], output_path: &Path, format: ExportFormat) -> Result<usize> {
+    let (info_hash, entries) = file_list_from_torrent(torrent_bytes)?;
+    let contents = render_file_list(&info_hash, &entries, format)?;
+    std::fs::write(output_path, contents)
+        .with_context(|| format!("Failed to write file list: {}", output_path.display()))?;
+    Ok(entries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use librqbit::CreateTorrentOptions;
+
+    fn entry(path: &str, size: u64) -> FileListEntry {
+        FileListEntry { path: path.to_string(), size, sha1: None }
+    }
+
+    #[test]
+    fn test_csv_has_summary_row_and_quotes_fields() -> Result<()> {
+        let entries = [entry("addons/a.pbo", 100), entry("odd, \"name\".txt", 5)];
+        let csv = render_file_list("abc", &entries, ExportFormat::Csv)?;
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "path,size,sha1");
+        assert_eq!(lines[1], "addons/a.pbo,100,");
+        assert_eq!(lines[2], "\"odd, \"\"name\"\".txt\",5,");
+        assert_eq!(lines[3], "TOTAL (2 files),105,");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_json_export_from_torrent() -> Result<()> {
+        let source = tempfile::tempdir()?;
+        std::fs::create_dir(source.path().join("addons"))?;
+        std::fs::write(source.path().join("addons/a.pbo"), vec![1u8; 300])?;
+        std::fs::write(source.path().join("mod.cpp"), vec![2u8; 20])?;
+        let torrent = librqbit::create_torrent(source.path(), CreateTorrentOptions::default()).await?;
+
+        let output = tempfile::tempdir()?;
+        let output_path = output.path().join("files.json");
+        assert_eq!(export_file_list(&torrent.as_bytes()?, &output_path, ExportFormat::Json)?, 2);
+
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&output_path)?)?;
+        assert_eq!(json["total_count"], 2);
+        assert_eq!(json["total_size"], 320);
+        let mut paths: Vec<&str> = json["files"].as_array().unwrap().iter().map(|f| f["path"].as_str().unwrap()).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["addons/a.pbo", "mod.cpp"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+// src/sync/file_mode.rs
+
+//! `post_download_chmod` and `post_download_chown`: once the torrent is complete, its
+//! files and the folders holding them get the configured mode and owner, for game
+//! servers running as another user that have to read them. Folders get the mode with
+//! execute added wherever it allows reading, so they stay listable. Unix only; a file
+//! that can't be changed, such as chown without root, is reported and the rest carry on.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+use crate::config::AppConfig;
+
+use super::messages::SyncEvent;
+use super::types::{LocalTorrentState, SyncState};
+use super::verify_complete::downloaded_files;
+
+/// Failures logged one by one; the rest are only counted
+const MAX_LOGGED_FAILURES: usize = 10;
+
+/// What applying the mode and owner did
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileModeSummary {
+    pub files: usize,
+    pub folders: usize,
+    pub failed: Vec<(PathBuf, String)>, // Path, and why it couldn't be changed
+}
+
+/// The mode for folders: `mode` with execute wherever it allows reading, e.g. 0o644 gives 0o755
+pub fn folder_mode(mode: u32) -> u32 {
+    mode | ((mode & 0o444) >> 2)
+}
+
+/// The ID of `name` in an `/etc/passwd` or `/etc/group` style table, or `name` itself when it's a number
+pub fn lookup_id(table: &str, name: &str) -> Option<u32> {
+    if let Ok(id) = name.parse() {
+        return Some(id);
+    }
+    table.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        (fields.next()? == name).then_some(())?;
+        fields.nth(1)?.parse().ok()
+    })
+}
+
+/// (uid, gid) of a `post_download_chown` spec: "user:group", "user" or ":group", by name
+/// or number. Whichever is left out stays as it is.
+pub fn parse_owner(spec: &str, passwd: &str, group: &str) -> Result<(Option<u32>, Option<u32>), String> {
+    let (user, group_name) = spec.trim().split_once(':').unwrap_or((spec.trim(), ""));
+    let uid = match user {
+        "" => None,
+        user => Some(lookup_id(passwd, user).ok_or_else(|| format!("Unknown user '{}'", user))?),
+    };
+    let gid = match group_name {
+        "" => None,
+        group_name => Some(lookup_id(group, group_name).ok_or_else(|| format!("Unknown group '{}'", group_name))?),
+    };
+    if uid.is_none() && gid.is_none() {
+        return Err(format!("'{}' names neither a user nor a group", spec));
+    }
+    Ok((uid, gid))
+}
+
+/// Folders holding `files` ('/'-separated, relative to `download_path`), not counting
+/// the download folder itself
+pub fn folders_of(files: &[PathBuf]) -> BTreeSet<PathBuf> {
+    files
+        .iter()
+        .flat_map(|file| file.ancestors().skip(1))
+        .filter(|folder| !folder.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .collect()
+}
+
+/// Set `mode` and `owner` on `files` under `download_path` and the folders holding them
+pub fn apply_file_modes(download_path: &Path, files: &[PathBuf], mode: Option<u32>, owner: (Option<u32>, Option<u32>)) -> FileModeSummary {
+    let mut summary = FileModeSummary::default();
+    let folders = folders_of(files);
+    let targets = files.iter().map(|file| (file, mode)).chain(folders.iter().map(|folder| (folder, mode.map(folder_mode))));
+    for (index, (path, mode)) in targets.enumerate() {
+        let full_path = download_path.join(path);
+        match platform::set_mode_and_owner(&full_path, mode, owner) {
+            Ok(()) if index < files.len() => summary.files += 1,
+            Ok(()) => summary.folders += 1,
+            Err(e) => summary.failed.push((path.clone(), e.to_string())),
+        }
+    }
+    summary
+}
+
+/// Apply `post_download_chmod` and `post_download_chown` once the managed torrent has
+/// finished. Runs again after an update or when the settings change.
+pub async fn check_file_modes(config: &AppConfig, state: &mut SyncState, api: &librqbit::Api, ui_tx: &mpsc::UnboundedSender<SyncEvent>) {
+    let owner_spec = config.post_download_chown.clone().filter(|spec| !spec.trim().is_empty());
+    if config.post_download_chmod.is_none() && owner_spec.is_none() {
+        return;
+    }
+    let LocalTorrentState::Active { id } = state.local else {
+        return;
+    };
+    if !api.api_stats_v1(id.into()).is_ok_and(|stats| stats.finished) {
+        return;
+    }
+    let Ok(details) = api.api_torrent_details(id.into()) else {
+        return;
+    };
+    let applied = (details.info_hash.clone(), config.post_download_chmod, owner_spec.clone());
+    if state.file_modes_applied.as_ref() == Some(&applied) {
+        return;
+    }
+    // Only tried once per torrent and settings, so a bad setting isn't reported every tick
+    state.file_modes_applied = Some(applied);
+    if !cfg!(unix) {
+        warn!("Sync: post_download_chmod and post_download_chown only apply on Unix, ignoring them");
+        return;
+    }
+    let owner = match owner_spec {
+        Some(spec) => {
+            let passwd = std::fs::read_to_string("/etc/passwd").unwrap_or_default();
+            let group = std::fs::read_to_string("/etc/group").unwrap_or_default();
+            match parse_owner(&spec, &passwd, &group) {
+                Ok(owner) => owner,
+                Err(e) => {
+                    error!("Sync: Invalid post_download_chown: {}", e);
+                    let _ = ui_tx.send(SyncEvent::Error(format!("Invalid post_download_chown: {}", e)));
+                    return;
+                }
+            }
+        }
+        None => (None, None),
+    };
+
+    let download_path = config.download_path.clone();
+    let files = downloaded_files(&details);
+    let mode = config.post_download_chmod;
+    let summary = match tokio::task::spawn_blocking(move || apply_file_modes(&download_path, &files, mode, owner)).await {
+        Ok(summary) => summary,
+        Err(e) => {
+            error!("Sync: Setting file permissions failed: {}", e);
+            return;
+        }
+    };
+    info!(
+        "Sync: Set permissions on {} files and {} folders, {} failed",
+        summary.files,
+        summary.folders,
+        summary.failed.len()
+    );
+    for (path, error) in summary.failed.iter().take(MAX_LOGGED_FAILURES) {
+        warn!("Sync: Could not set permissions on {}: {}", path.display(), error);
+    }
+    if summary.failed.len() > MAX_LOGGED_FAILURES {
+        warn!("Sync: ...and {} more", summary.failed.len() - MAX_LOGGED_FAILURES);
+    }
+    let _ = ui_tx.send(SyncEvent::FileModesApplied(summary));
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    pub fn set_mode_and_owner(path: &Path, mode: Option<u32>, (uid, gid): (Option<u32>, Option<u32>)) -> std::io::Result<()> {
+        if uid.is_some() || gid.is_some() {
+            std::os::unix::fs::chown(path, uid, gid)?;
+        }
+        if let Some(mode) = mode {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode & 0o7777))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(unix))]
+mod platform {
+    use std::path::Path;
+
+    pub fn set_mode_and_owner(_path: &Path, _mode: Option<u32>, _owner: (Option<u32>, Option<u32>)) -> std::io::Result<()> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "file modes are Unix only"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owner_specs_and_folder_mode() {
+        let passwd = "root:x:0:0:root:/root:/bin/bash\narma:x:1001:1001::/home/arma:/bin/sh\n";
+        let group = "root:x:0:\ngameserver:x:2000:arma\n";
+        assert_eq!(parse_owner("arma:gameserver", passwd, group), Ok((Some(1001), Some(2000))));
+        assert_eq!(parse_owner("arma", passwd, group), Ok((Some(1001), None)));
+        assert_eq!(parse_owner(":2001", passwd, group), Ok((None, Some(2001))));
+        assert!(parse_owner("nobody-here", passwd, group).is_err());
+        assert!(parse_owner(":", passwd, group).is_err());
+
+        assert_eq!(folder_mode(0o644), 0o755);
+        assert_eq!(folder_mode(0o640), 0o750);
+        assert_eq!(folder_mode(0o600), 0o700);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_modes_are_applied_to_files_and_folders() -> anyhow::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir()?;
+        std::fs::create_dir_all(dir.path().join("@mod/addons"))?;
+        std::fs::write(dir.path().join("@mod/addons/a.pbo"), b"pbo")?;
+        let files = vec![PathBuf::from("@mod/addons/a.pbo"), PathBuf::from("@mod/missing.pbo")];
+
+        let summary = apply_file_modes(dir.path(), &files, Some(0o640), (None, None));
+        assert_eq!((summary.files, summary.folders), (1, 2));
+        let failed: Vec<&Path> = summary.failed.iter().map(|(path, _)| path.as_path()).collect();
+        assert_eq!(failed, vec![Path::new("@mod/missing.pbo")]);
+        let mode_of = |path: &str| std::fs::metadata(dir.path().join(path)).map(|meta| meta.permissions().mode() & 0o7777);
+        assert_eq!(mode_of("@mod/addons/a.pbo")?, 0o640);
+        assert_eq!(mode_of("@mod/addons")?, 0o750);
+        assert_eq!(mode_of("@mod")?, 0o750);
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+// src/sync/file_pause.rs
+
+//! Pausing single files: a paused file is deselected in librqbit so nothing more of
+//! it is downloaded, while the rest of the torrent carries on. Paused files are kept
+//! by path in the config, so they stay paused across restarts and torrent updates.
+
+use anyhow::{Context, Result};
+use librqbit::AddTorrentOptions;
+use std::collections::HashSet;
+use tracing::info;
+
+use crate::config::AppConfig;
+
+/// The files librqbit should download: everything it currently selects plus files just
+/// resumed, minus paused ones. `files` is `(relative path, selected)` in torrent order.
+/// Starting from the current selection keeps safe mode's exclusions intact.
+pub fn desired_only_files(files: &[(String, bool)], paused: &[String], resumed: &[String]) -> HashSet<usize> {
+    files
+        .iter()
+        .enumerate()
+        .filter(|(_, (path, selected))| (*selected || resumed.contains(path)) && !paused.contains(path))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Leave paused files out of the selection of a torrent about to be added. Runs after
+/// safe mode, which may already have narrowed `only_files`.
+pub fn apply_paused_files(config: &AppConfig, torrent_bytes: &[u8], options: &mut AddTorrentOptions) -> Result<()> {
+    if config.paused_files.is_empty() {
+        return Ok(());
+    }
+    let torrent = librqbit::torrent_from_bytes::<librqbit::ByteBuf>(torrent_bytes)
+        .context("Failed to parse torrent for paused files")?;
+    let mut files = Vec::new();
+    for (index, file) in torrent.info.iter_file_details()?.enumerate() {
+        let path = file.filename.to_pathbuf()?.to_string_lossy().replace('\\', "/");
+        let selected = options.only_files.as_ref().is_none_or(|only| only.contains(&index));
+        files.push((path, selected));
+    }
+    let mut only_files: Vec<usize> = desired_only_files(&files, &config.paused_files, &[]).into_iter().collect();
+    only_files.sort_unstable();
+    info!("Sync: Leaving {} paused files out of the download", files.len() - only_files.len());
+    options.only_files = Some(only_files);
+    Ok(())
+}
+
+/// Apply a change to the paused files to the live torrent `id`
+pub async fn update_paused_files(old: &AppConfig, new: &AppConfig, api: &librqbit::Api, id: usize) -> Result<()> {
+    let paused: Vec<String> = new.paused_files.iter().filter(|p| !old.paused_files.contains(p)).cloned().collect();
+    let resumed: Vec<String> = old.paused_files.iter().filter(|p| !new.paused_files.contains(p)).cloned().collect();
+    if paused.is_empty() && resumed.is_empty() {
+        return Ok(());
+    }
+
+    let details = api.api_torrent_details(id.into())?;
+    let files: Vec<(String, bool)> = details
+        .files
+        .iter()
+        .flatten()
+        .map(|file| (file.components.join("/"), file.included))
+        .collect();
+    let only_files = desired_only_files(&files, &paused, &resumed);
+    api.api_torrent_action_update_only_files(id.into(), &only_files).await?;
+    info!(
+        "Sync: Paused {} and resumed {} files, {} of {} now selected",
+        paused.len(),
+        resumed.len(),
+        only_files.len(),
+        files.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files() -> Vec<(String, bool)> {
+        vec![
+            ("addons/core.pbo".to_string(), true),
+            ("addons/huge_optional.pbo".to_string(), true),
+            ("addons/users_own.pbo".to_string(), false), // Excluded by safe mode
+        ]
+    }
+
+    #[test]
+    fn test_pausing_and_resuming_a_file() {
+        let paused = vec!["addons/huge_optional.pbo".to_string()];
+        assert_eq!(desired_only_files(&files(), &paused, &[]), HashSet::from([0]));
+
+        let mut after_pause = files();
+        after_pause[1].1 = false;
+        assert_eq!(desired_only_files(&after_pause, &[], &paused), HashSet::from([0, 1]));
+    }
+
+    #[test]
+    fn test_other_exclusions_are_kept() {
+        assert_eq!(desired_only_files(&files(), &[], &[]), HashSet::from([0, 1]));
+    }
+}
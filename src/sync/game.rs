@@ -0,0 +1,133 @@
+// src/sync/game.rs
+
+//! Pauses torrents while a configured process (usually the game) is running
+
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use crate::config::AppConfig;
+use crate::ui::utils::SyncStatus;
+
+use super::messages::SyncEvent;
+use super::types::SyncState;
+use super::utils::send_sync_status_event;
+
+/// Keeps a process table between polls so refreshes stay cheap
+pub struct ProcessWatcher {
+    system: System,
+}
+
+impl Default for ProcessWatcher {
+    fn default() -> Self {
+        Self { system: System::new() }
+    }
+}
+
+impl ProcessWatcher {
+    /// Refresh the process list and check for a process with the given executable name
+    pub fn is_running(&mut self, executable: &str) -> bool {
+        self.system.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::nothing());
+        self.system
+            .processes()
+            .values()
+            .any(|process| matches_executable(&process.name().to_string_lossy(), executable))
+    }
+}
+
+/// Compare a process name with the configured executable, ignoring case and a
+/// trailing ".exe" so the same config works across platforms
+pub fn matches_executable(process_name: &str, executable: &str) -> bool {
+    fn normalize(name: &str) -> String {
+        let lower = name.trim().to_lowercase();
+        lower.strip_suffix(".exe").map(str::to_string).unwrap_or(lower)
+    }
+    let executable = normalize(executable);
+    !executable.is_empty() && normalize(process_name) == executable
+}
+
+/// Pause every live torrent while the configured process runs, and resume the
+/// ones we paused once it exits
+pub async fn check_game_process(
+    config: &AppConfig,
+    state: &mut SyncState,
+    watcher: &mut ProcessWatcher,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+) {
+    let running = match config.pause_while_process_running.as_deref() {
+        Some(executable) if !executable.trim().is_empty() => watcher.is_running(executable),
+        // Watching disabled; make sure we don't stay paused because of it
+        _ => false,
+    };
+
+    if running {
+        let paused_ids = state.paused_for_game.get_or_insert_with(|| {
+            info!("Sync: Watched process is running, pausing torrents");
+            Vec::new()
+        });
+        // Also catches torrents added while the game was already running
+        let torrent_list = api.api_torrent_list_ext(librqbit::api::ApiTorrentListOpts { with_stats: true });
+        for torrent in torrent_list.torrents {
+            let (Some(id), Some(stats)) = (torrent.id, torrent.stats.as_ref()) else { continue };
+            if stats.live.is_none() || paused_ids.contains(&id) {
+                continue;
+            }
+            match api.api_torrent_action_pause(id.into()).await {
+                Ok(_) => {
+                    info!("Sync: Paused torrent {} while game is running", id);
+                    paused_ids.push(id);
+                }
+                Err(e) => error!("Sync: Failed to pause torrent {} for game: {}", id, e),
+            }
+        }
+        send_sync_status_event(ui_tx, SyncStatus::PausedForGame);
+    } else if let Some(paused_ids) = state.paused_for_game.take() {
+        info!("Sync: Watched process exited, resuming {} torrents", paused_ids.len());
+        if let Some(interface_ids) = state.paused_for_interface.as_mut() {
+            // The bound interface is still down; it resumes these when it's back
+            for id in paused_ids {
+                if !interface_ids.contains(&id) {
+                    interface_ids.push(id);
+                }
+            }
+            return;
+        }
+        for id in paused_ids {
+            // Leave it paused if low disk space would pause it anyway
+            if state.low_disk_space {
+                break;
+            }
+            if state.paused_for_seed_goal.as_ref().is_some_and(|ids| ids.contains(&id)) {
+                continue; // Still held by the seed goal
+            }
+            if state.paused_for_battery.as_ref().is_some_and(|ids| ids.contains(&id)) {
+                continue; // Still held for the low battery
+            }
+            match api.api_torrent_action_start(id.into()).await {
+                Ok(_) => info!("Sync: Resumed torrent {} after game exited", id),
+                Err(e) => error!("Sync: Failed to resume torrent {}: {}", id, e),
+            }
+        }
+        send_sync_status_event(ui_tx, SyncStatus::Idle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_executable_ignores_case_and_exe_suffix() {
+        assert!(matches_executable("arma3_x64.exe", "Arma3_x64.exe"));
+        assert!(matches_executable("arma3_x64", "arma3_x64.exe"));
+        assert!(matches_executable("ARMA3_X64.EXE", "arma3_x64"));
+        assert!(!matches_executable("arma3launcher.exe", "arma3_x64.exe"));
+    }
+
+    #[test]
+    fn test_empty_executable_never_matches() {
+        assert!(!matches_executable("", ""));
+        assert!(!matches_executable("anything", "  "));
+    }
+}
@@ -0,0 +1,115 @@
+// src/sync/grace.rs
+
+//! `update_grace_seconds`: a detected update that would apply on its own waits this long
+//! first, so a person or a script can stop a bad one. The countdown is cancelled from the
+//! GUI or with `POST /update/cancel` on the control API; a cancelled update is left for
+//! the user to apply by hand. 0 applies straight away, as before.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+use crate::config::AppConfig;
+
+use super::messages::{SyncCommand, SyncEvent};
+
+/// The update counting down, shared by the sync manager and the control API
+#[derive(Debug, Clone, Default)]
+pub struct UpdateGrace {
+    pending: Arc<Mutex<Option<CancellationToken>>>,
+}
+
+impl UpdateGrace {
+    /// Apply `torrent_content` once the grace period has passed, through `cmd_tx`.
+    /// Replaces an update that was already counting down.
+    pub fn schedule(
+        &self,
+        config: &AppConfig,
+        torrent_content: Vec<u8>,
+        cmd_tx: mpsc::WeakUnboundedSender<SyncCommand>,
+        ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+    ) {
+        let grace = config.update_grace_seconds;
+        if grace == 0 {
+            if let Some(cmd_tx) = cmd_tx.upgrade() {
+                let _ = cmd_tx.send(SyncCommand::ApplyUpdate(torrent_content));
+            }
+            return;
+        }
+        let token = CancellationToken::new();
+        if let Ok(mut pending) = self.pending.lock()
+            && let Some(previous) = pending.replace(token.clone())
+        {
+            previous.cancel();
+        }
+        let cancel_hint = if config.control_addr.is_some() { "; POST /update/cancel on the control API stops it" } else { "" };
+        info!("Sync: Applying update in {} seconds{}", grace, cancel_hint);
+        let applies_at = chrono::Local::now() + chrono::Duration::seconds(grace as i64);
+        let _ = ui_tx.send(SyncEvent::UpdateCountdown(applies_at));
+
+        let pending = self.pending.clone();
+        let ui_tx = ui_tx.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(grace)) => {}
+                _ = token.cancelled() => return,
+            }
+            // Checked under the lock, since a cancel may have just missed the select
+            match pending.lock() {
+                Ok(mut pending) if !token.is_cancelled() => *pending = None,
+                _ => return,
+            }
+            info!("Sync: Grace period over, applying update");
+            let _ = ui_tx.send(SyncEvent::UpdateCountdownEnded { applied: true });
+            if let Some(cmd_tx) = cmd_tx.upgrade() {
+                let _ = cmd_tx.send(SyncCommand::ApplyUpdate(torrent_content));
+            }
+        });
+    }
+
+    /// Stop the update counting down. Returns false when there was none.
+    pub fn cancel(&self, ui_tx: &mpsc::UnboundedSender<SyncEvent>) -> bool {
+        let Some(token) = self.pending.lock().ok().and_then(|mut pending| pending.take()) else {
+            return false;
+        };
+        token.cancel();
+        info!("Sync: Pending update cancelled; it can still be applied by hand");
+        let _ = ui_tx.send(SyncEvent::UpdateCountdownEnded { applied: false });
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_update_applies_after_grace_unless_cancelled() {
+        let config = AppConfig { update_grace_seconds: 30, ..AppConfig::default() };
+        let (cmd_tx, mut cmd_rx) = mpsc::unbounded_channel();
+        let (ui_tx, mut ui_rx) = mpsc::unbounded_channel();
+        let grace = UpdateGrace::default();
+
+        grace.schedule(&config, vec![1], cmd_tx.downgrade(), &ui_tx);
+        assert!(matches!(ui_rx.recv().await, Some(SyncEvent::UpdateCountdown(_))));
+        tokio::time::sleep(Duration::from_secs(29)).await;
+        assert!(cmd_rx.try_recv().is_err(), "applied before the grace period was over");
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert!(matches!(cmd_rx.recv().await, Some(SyncCommand::ApplyUpdate(data)) if data == [1]));
+        assert!(matches!(ui_rx.recv().await, Some(SyncEvent::UpdateCountdownEnded { applied: true })));
+        assert!(!grace.cancel(&ui_tx), "nothing left to cancel");
+
+        grace.schedule(&config, vec![2], cmd_tx.downgrade(), &ui_tx);
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert!(grace.cancel(&ui_tx));
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        assert!(cmd_rx.try_recv().is_err(), "a cancelled update was applied");
+
+        // Without a grace period it applies straight away
+        grace.schedule(&AppConfig::default(), vec![3], cmd_tx.downgrade(), &ui_tx);
+        assert!(matches!(cmd_rx.try_recv(), Ok(SyncCommand::ApplyUpdate(data)) if data == [3]));
+    }
+}
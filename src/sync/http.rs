@@ -2,11 +2,329 @@
 
 // This module handles HTTP client creation for downloading torrent files
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
 
-// Helper to create a client (called once in sync_manager)
-pub fn create_http_client() -> Result<reqwest::Client> {
+use crate::config::IpMode;
+
+/// Most redirects followed when resolving the torrent URL
+pub const MAX_REDIRECTS: usize = 10;
+
+/// User-agent sent when `user_agent` isn't configured
+pub fn default_user_agent() -> String {
+    format!("ModSync/{}", env!("CARGO_PKG_VERSION"))
+}
+
+// Helper to create a client (called in sync_manager, and again when the user-agent, IP mode or DNS overrides change)
+pub fn create_http_client(user_agent: Option<&str>, ip_mode: IpMode, overrides: &HashMap<String, IpAddr>) -> Result<reqwest::Client> {
+    let user_agent = match user_agent.map(str::trim) {
+        Some("") => bail!("Configured user_agent is empty; remove it to use the default"),
+        Some(user_agent) => user_agent.to_string(),
+        None => default_user_agent(),
+    };
+    // Always our resolver, so a lookup that fails can be told apart from a refused connection
+    let overrides = overrides.iter().map(|(host, ip)| (host.to_ascii_lowercase(), *ip)).collect();
     reqwest::Client::builder()
+        .user_agent(&user_agent)
+        .redirect(redirect_policy())
+        .dns_resolver(Arc::new(StackResolver { ip_mode, overrides }))
         .build()
-        .context("Failed to build HTTP client")
-} 
\ No newline at end of file
+        .with_context(|| format!("Failed to build HTTP client with user-agent {:?}", user_agent))
+}
+
+/// A host name that couldn't be resolved, as opposed to a host that was reached and
+/// refused the connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsFailure {
+    pub host: String,
+    pub reason: String,
+}
+
+impl fmt::Display for DnsFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Can't resolve host {} ({}). Check the address, and your DNS or network connection.", self.host, self.reason)
+    }
+}
+
+impl std::error::Error for DnsFailure {}
+
+/// The DNS failure somewhere in the causes of `e`, if that's what stopped it
+pub fn dns_failure<'a>(e: &'a (dyn std::error::Error + 'static)) -> Option<&'a DnsFailure> {
+    let mut cause = Some(e);
+    while let Some(error) = cause {
+        if let Some(dns) = error.downcast_ref::<DnsFailure>() {
+            return Some(dns);
+        }
+        cause = error.source();
+    }
+    None
+}
+
+/// Whether the server's host was found but turned the connection away
+pub fn connection_refused(e: &(dyn std::error::Error + 'static)) -> bool {
+    let mut cause = Some(e);
+    while let Some(error) = cause {
+        if error.downcast_ref::<std::io::Error>().is_some_and(|io| io.kind() == std::io::ErrorKind::ConnectionRefused) {
+            return true;
+        }
+        cause = error.source();
+    }
+    false
+}
+
+/// A reply that wasn't a success, as an error callers can tell apart from the rest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpStatusError(pub reqwest::StatusCode);
+
+impl fmt::Display for HttpStatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HTTP error: {}", self.0)
+    }
+}
+
+impl std::error::Error for HttpStatusError {}
+
+/// What stopped a request, for alerts that sort failures by cause
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    Dns,
+    ConnectionRefused,
+    Timeout,
+    Connection, // Any other failure to reach the server
+    HttpStatus(u16),
+    Other, // Not a network failure, such as a broken cache
+}
+
+impl FailureKind {
+    /// Name used in JSON payloads
+    pub fn label(self) -> &'static str {
+        match self {
+            FailureKind::Dns => "dns",
+            FailureKind::ConnectionRefused => "connection_refused",
+            FailureKind::Timeout => "timeout",
+            FailureKind::Connection => "connection",
+            FailureKind::HttpStatus(_) => "http_status",
+            FailureKind::Other => "other",
+        }
+    }
+}
+
+/// The kind of failure behind `e`
+pub fn failure_kind(e: &anyhow::Error) -> FailureKind {
+    if e.downcast_ref::<DnsFailure>().is_some() {
+        return FailureKind::Dns;
+    }
+    if let Some(HttpStatusError(status)) = e.downcast_ref::<HttpStatusError>() {
+        return FailureKind::HttpStatus(status.as_u16());
+    }
+    for cause in e.chain() {
+        if dns_failure(cause).is_some() {
+            return FailureKind::Dns;
+        }
+        if connection_refused(cause) {
+            return FailureKind::ConnectionRefused;
+        }
+        if let Some(request) = cause.downcast_ref::<reqwest::Error>() {
+            if request.is_timeout() {
+                return FailureKind::Timeout;
+            }
+            if request.is_connect() {
+                return FailureKind::Connection;
+            }
+        }
+        if cause.downcast_ref::<std::io::Error>().is_some_and(|io| io.kind() == std::io::ErrorKind::TimedOut) {
+            return FailureKind::Timeout;
+        }
+    }
+    FailureKind::Other
+}
+
+/// Resolves host names through `dns_overrides` first, then the system, to the addresses
+/// of the allowed stack only, so a broken stack isn't tried first and left to time out
+struct StackResolver {
+    ip_mode: IpMode,
+    overrides: HashMap<String, IpAddr>,
+}
+
+impl Resolve for StackResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let ip_mode = self.ip_mode;
+        let fixed = self.overrides.get(&name.as_str().to_ascii_lowercase()).copied();
+        Box::pin(async move {
+            let failure = |reason: String| DnsFailure { host: name.as_str().to_string(), reason };
+            if let Some(ip) = fixed {
+                let addrs: Addrs = Box::new(std::iter::once(SocketAddr::new(ip, 0)));
+                return Ok(addrs);
+            }
+            let addrs: Vec<_> = tokio::net::lookup_host((name.as_str(), 0))
+                .await
+                .map_err(|e| failure(e.to_string()))?
+                .filter(|addr| ip_mode.allows(addr))
+                .collect();
+            if addrs.is_empty() {
+                return Err(failure(format!("no {} address", ip_mode.http_stacks())).into());
+            }
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// Follow redirects (the torrent URL is often a stable redirector), but stop
+/// with a clear reason on a loop or an overly long chain
+fn redirect_policy() -> Policy {
+    Policy::custom(|attempt| {
+        if attempt.previous().contains(attempt.url()) {
+            let reason = format!("Redirect loop: {} was already visited", attempt.url());
+            attempt.error(reason)
+        } else if attempt.previous().len() > MAX_REDIRECTS {
+            let reason = format!("Too many redirects (more than {})", MAX_REDIRECTS);
+            attempt.error(reason)
+        } else {
+            attempt.follow()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::utils::download_torrent;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve redirects forever: `/N` points to `/N+1`, or back to `/0` when `looping`
+    async fn redirect_server(looping: bool) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let location_base = base.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0u8; 1024];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let step: usize = request
+                    .split_whitespace()
+                    .nth(1)
+                    .and_then(|path| path.trim_start_matches('/').parse().ok())
+                    .unwrap_or(0);
+                let next = if looping { (step + 1) % 2 } else { step + 1 };
+                let response = format!(
+                    "HTTP/1.1 302 Found\r\nLocation: {}/{}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    location_base, next
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        base
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_is_sent() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/file.torrent", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let n = socket.read(&mut buf).await.unwrap();
+            let _ = socket.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+            String::from_utf8_lossy(&buf[..n]).to_lowercase()
+        });
+        let client = create_http_client(Some(" Mozilla/5.0 Test "), IpMode::Dual, &HashMap::new()).unwrap();
+        let _ = download_torrent(&url, &client).await;
+        assert!(server.await.unwrap().contains("user-agent: mozilla/5.0 test\r\n"));
+    }
+
+    #[test]
+    fn test_empty_user_agent_is_rejected() {
+        assert!(create_http_client(Some("  "), IpMode::Dual, &HashMap::new()).is_err());
+        assert!(default_user_agent().starts_with("ModSync/"));
+    }
+
+    #[tokio::test]
+    async fn test_redirect_loop_is_reported() {
+        let base = redirect_server(true).await;
+        let client = create_http_client(None, IpMode::Dual, &HashMap::new()).unwrap();
+        let err = download_torrent(&format!("{}/0", base), &client).await.unwrap_err();
+        assert!(err.to_string().contains("Redirect loop"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_long_redirect_chain_is_reported() {
+        let base = redirect_server(false).await;
+        let client = create_http_client(None, IpMode::Dual, &HashMap::new()).unwrap();
+        let err = download_torrent(&format!("{}/0", base), &client).await.unwrap_err();
+        assert!(err.to_string().contains("Too many redirects"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_ip_mode_limits_resolved_stack() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://localhost:{}/file.torrent", listener.local_addr().unwrap().port());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let _ = socket.read(&mut [0u8; 1024]).await;
+                let _ = socket.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+            }
+        });
+        let ipv4 = create_http_client(None, IpMode::Ipv4, &HashMap::new()).unwrap();
+        let err = download_torrent(&url, &ipv4).await.unwrap_err();
+        assert!(err.to_string().contains("404"), "{}", err);
+
+        // The server only listens on IPv4, so IPv6-only can't reach it
+        let ipv6 = create_http_client(None, IpMode::Ipv6, &HashMap::new()).unwrap();
+        let err = download_torrent(&url, &ipv6).await.unwrap_err();
+        assert!(!err.to_string().contains("404"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_dns_failures_and_refusals_are_told_apart() {
+        let client = create_http_client(None, IpMode::Dual, &HashMap::new()).unwrap();
+        let err = download_torrent("http://modsync-test.invalid/file.torrent", &client).await.unwrap_err();
+        assert!(err.downcast_ref::<DnsFailure>().is_some_and(|dns| dns.host == "modsync-test.invalid"), "{:#}", err);
+        assert_eq!(failure_kind(&err), FailureKind::Dns);
+        assert!(err.to_string().starts_with("Can't resolve host modsync-test.invalid"), "{}", err);
+
+        let port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+        let err = download_torrent(&format!("http://127.0.0.1:{}/file.torrent", port), &client).await.unwrap_err();
+        assert!(err.downcast_ref::<DnsFailure>().is_none());
+        assert!(err.to_string().contains("refused the connection"), "{}", err);
+        assert_eq!(failure_kind(&err), FailureKind::ConnectionRefused);
+
+        // An override answers for the host without asking DNS
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://Mods.Example.Invalid:{}/file.torrent", listener.local_addr().unwrap().port());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let _ = socket.read(&mut [0u8; 1024]).await;
+                let _ = socket.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+            }
+        });
+        let overrides = HashMap::from([("mods.example.invalid".to_string(), IpAddr::from([127, 0, 0, 1]))]);
+        let client = create_http_client(None, IpMode::Dual, &overrides).unwrap();
+        let err = download_torrent(&url, &client).await.unwrap_err();
+        assert!(err.to_string().contains("404"), "{}", err);
+        assert_eq!(failure_kind(&err), FailureKind::HttpStatus(404));
+        assert_eq!(failure_kind(&anyhow::anyhow!("Failed to read the cache")), FailureKind::Other);
+    }
+
+    #[tokio::test]
+    async fn test_removed_torrent_is_reported_as_missing() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/file.torrent", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let _ = socket.read(&mut [0u8; 1024]).await;
+                let _ = socket.write_all(b"HTTP/1.1 410 Gone\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").await;
+            }
+        });
+        let client = create_http_client(None, IpMode::Dual, &HashMap::new()).unwrap();
+        let fetched = crate::sync::utils::fetch_torrent(&url, &client, None).await.unwrap();
+        assert!(matches!(fetched, crate::sync::utils::FetchedTorrent::Missing(reqwest::StatusCode::GONE)));
+    }
+}
@@ -0,0 +1,451 @@
+// src/sync/http_fallback.rs
+
+//! `http_fallback_base_url`: a way out when a private swarm has no seeds online. Once the
+//! torrent has gone `http_fallback_after_seconds` without a single peer, the files it
+//! hasn't finished are fetched one after another from the base URL, at the same relative
+//! path as in the torrent. The fetch runs in the background, so commands aren't held up,
+//! and CancelSync stops it. Each file is then hash-checked against the torrent, and the
+//! torrent is restarted so librqbit picks them up and carries on seeding. Only files the
+//! torrent was added to download are fetched, so paused files and ones `overwrite_policy`
+//! keeps are left alone, and in safe mode nothing is. A file's partial data stays on disk
+//! until its replacement passes the check; one that fails is put back and left to the
+//! swarm. One attempt per torrent version, so a broken mirror isn't hammered every tick.
+
+use anyhow::{Context, Result, bail};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+use crate::config::AppConfig;
+use crate::ui::utils::SyncStatus;
+
+use super::local::{fix_missing_files, loaded_torrent_bytes};
+use super::messages::SyncEvent;
+use super::overwrite::preserved_files;
+use super::owner::foreign_owner;
+use super::spot_check::spot_check;
+use super::types::{LocalTorrentState, SyncState};
+use super::utils::send_sync_status_event;
+
+/// Added to a file's name while its download is in progress, next to the file
+const FETCHED_SUFFIX: &str = ".modsync-http";
+
+/// Added to the name of a file's partial data while the fetched copy is checked
+const ORIGINAL_SUFFIX: &str = ".modsync-partial";
+
+/// What the HTTP fallback fetched
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FallbackSummary {
+    pub fetched: Vec<String>, // '/'-separated, relative to the download folder
+    pub failed: Vec<(String, String)>, // File, and why it couldn't be fetched or didn't check out
+    pub bytes: u64,
+}
+
+/// A fallback fetch running in the background
+#[derive(Debug)]
+struct FallbackFetch {
+    id: usize, // The torrent paused for it
+    token: CancellationToken,
+    percent: Arc<AtomicU8>,
+    handle: JoinHandle<(FallbackSummary, bool)>, // And whether it was cancelled
+}
+
+/// Follows how long the torrent has been without peers, between ticks
+#[derive(Debug, Default)]
+pub struct FallbackWatch {
+    no_peers_since: Option<Instant>,
+    tried: Option<String>, // Info hash of the torrent the fallback last ran for
+    fetch: Option<FallbackFetch>,
+}
+
+impl FallbackWatch {
+    /// Stop a fetch that's running; what it already replaced is put back
+    pub fn cancel(&self) {
+        if let Some(fetch) = &self.fetch {
+            fetch.token.cancel();
+        }
+    }
+
+    /// Whether the torrent has now been without peers for `after`. Any peer starts the wait over.
+    pub fn no_peers_for(&mut self, peers: usize, now: Instant, after: Duration) -> bool {
+        if peers > 0 {
+            self.no_peers_since = None;
+            return false;
+        }
+        now.duration_since(*self.no_peers_since.get_or_insert(now)) >= after
+    }
+}
+
+/// URL of `relative` under `base`, each path segment escaped
+pub fn file_url(base: &str, relative: &str) -> Result<reqwest::Url> {
+    let mut url = reqwest::Url::parse(base).context("http_fallback_base_url isn't a valid URL")?;
+    if url.cannot_be_a_base() {
+        bail!("http_fallback_base_url can't have paths added to it");
+    }
+    if let Ok(mut segments) = url.path_segments_mut() {
+        segments.pop_if_empty().extend(relative.split('/'));
+    }
+    Ok(url)
+}
+
+/// `target` with `suffix` added to its file name
+fn sibling(target: &Path, suffix: &str) -> PathBuf {
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    target.with_file_name(name)
+}
+
+/// Download `url` to a temporary file next to `target`, leaving `target` as it is
+async fn fetch_file(client: &reqwest::Client, url: reqwest::Url, target: &Path, received: &mut impl FnMut(u64)) -> Result<u64> {
+    let mut response = client.get(url).send().await.context("Server didn't answer")?;
+    if !response.status().is_success() {
+        bail!("Server answered {}", response.status());
+    }
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(parent).await.context("Failed to create the folder")?;
+    }
+    let temp = sibling(target, FETCHED_SUFFIX);
+    let mut file = tokio::fs::File::create(&temp).await.context("Failed to create the file")?;
+    let mut bytes = 0;
+    let written: Result<()> = async {
+        while let Some(chunk) = response.chunk().await.context("Download broke off")? {
+            file.write_all(&chunk).await.context("Failed to write the file")?;
+            bytes += chunk.len() as u64;
+            received(chunk.len() as u64);
+        }
+        file.flush().await?;
+        Ok(())
+    }
+    .await;
+    drop(file);
+    if let Err(e) = written {
+        let _ = tokio::fs::remove_file(&temp).await;
+        return Err(e);
+    }
+    Ok(bytes)
+}
+
+/// Put the fetched copy of `target` in its place, moving what was there aside
+fn swap_in(target: &Path) -> Result<()> {
+    let fetched = sibling(target, FETCHED_SUFFIX);
+    if target.exists()
+        && let Err(e) = std::fs::rename(target, sibling(target, ORIGINAL_SUFFIX))
+    {
+        let _ = std::fs::remove_file(&fetched);
+        return Err(e).context("Failed to move the partial file aside");
+    }
+    if let Err(e) = std::fs::rename(&fetched, target) {
+        let _ = std::fs::remove_file(&fetched);
+        settle(target, false);
+        return Err(e).context("Failed to move the file into place");
+    }
+    Ok(())
+}
+
+/// After the check: drop the file moved aside by `swap_in`, or put it back if the fetched one is bad
+fn settle(target: &Path, passed: bool) {
+    let original = sibling(target, ORIGINAL_SUFFIX);
+    if passed {
+        let _ = std::fs::remove_file(original);
+        return;
+    }
+    let _ = std::fs::remove_file(target);
+    if original.exists()
+        && let Err(e) = std::fs::rename(&original, target)
+    {
+        error!("Sync: Failed to put {} back: {}", target.display(), e);
+    }
+}
+
+/// What a fallback fetch downloads, and where to
+struct FetchJob {
+    base_url: String,
+    download_path: PathBuf,
+    missing: Vec<(String, u64)>, // '/'-separated path and length of each file
+    torrent_bytes: Vec<u8>, // To check the files against
+}
+
+/// Fetch `missing` from `base_url`, then check what arrived against the torrent and put
+/// back anything that didn't pass. Returns whether `token` stopped it first, in which
+/// case nothing fetched is kept.
+async fn fetch_missing(
+    job: FetchJob,
+    client: reqwest::Client,
+    ui_tx: mpsc::UnboundedSender<SyncEvent>,
+    percent: Arc<AtomicU8>,
+    token: CancellationToken,
+) -> (FallbackSummary, bool) {
+    let FetchJob { base_url, download_path, missing, torrent_bytes } = job;
+    let total: u64 = missing.iter().map(|(_, len)| len).sum();
+    let mut summary = FallbackSummary::default();
+    let mut done = 0u64;
+    for (relative, _) in &missing {
+        let mut received = |bytes: u64| {
+            let before = done * 100 / total.max(1);
+            done += bytes;
+            let now = (done * 100 / total.max(1)).min(100) as u8;
+            if u64::from(now) != before {
+                percent.store(now, Ordering::Relaxed);
+                send_sync_status_event(&ui_tx, SyncStatus::FetchingHttp { percent: now });
+            }
+        };
+        let target = download_path.join(relative);
+        let fetched = match file_url(&base_url, relative) {
+            Ok(url) => tokio::select! {
+                fetched = fetch_file(&client, url, &target, &mut received) => fetched,
+                _ = token.cancelled() => {
+                    let _ = tokio::fs::remove_file(sibling(&target, FETCHED_SUFFIX)).await;
+                    break;
+                }
+            },
+            Err(e) => Err(e),
+        };
+        match fetched.and_then(|bytes| swap_in(&target).map(|_| bytes)) {
+            Ok(bytes) => {
+                info!("Sync: Fetched {} over HTTP ({} bytes)", relative, bytes);
+                summary.fetched.push(relative.clone());
+                summary.bytes += bytes;
+            }
+            Err(e) => {
+                warn!("Sync: HTTP fallback couldn't fetch {}: {:#}", relative, e);
+                summary.failed.push((relative.clone(), format!("{:#}", e)));
+            }
+        }
+    }
+    if token.is_cancelled() {
+        for relative in summary.fetched.drain(..) {
+            settle(&download_path.join(relative), false);
+        }
+        return (summary, true);
+    }
+
+    if !summary.fetched.is_empty() {
+        let check_path = download_path.clone();
+        let fetched: Vec<PathBuf> = summary.fetched.iter().map(PathBuf::from).collect();
+        let checked = tokio::task::spawn_blocking(move || spot_check(&torrent_bytes, &check_path, &fetched))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result);
+        match checked {
+            Ok(report) => {
+                let bad: Vec<String> = report.bad_files.iter().map(|bad| bad.to_string_lossy().replace('\\', "/")).collect();
+                for relative in &summary.fetched {
+                    settle(&download_path.join(relative), !bad.contains(relative));
+                }
+                for bad in bad {
+                    warn!("Sync: {} from the HTTP fallback doesn't match the torrent, keeping what was there", bad);
+                    summary.fetched.retain(|file| *file != bad);
+                    summary.failed.push((bad, "Doesn't match the torrent's hashes".to_string()));
+                }
+            }
+            Err(e) => {
+                // Unchecked files aren't trusted
+                warn!("Sync: Couldn't check the files from the HTTP fallback, keeping what was there: {:#}", e);
+                for relative in summary.fetched.drain(..) {
+                    settle(&download_path.join(&relative), false);
+                    summary.failed.push((relative, format!("Couldn't be checked: {:#}", e)));
+                }
+            }
+        }
+    }
+    (summary, false)
+}
+
+/// Pick up a fetch that finished since the last tick: report it and restart the torrent
+async fn finish_fallback(
+    config: &AppConfig,
+    state: &mut SyncState,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+    fetch: FallbackFetch,
+) {
+    let id = fetch.id;
+    let (summary, cancelled) = match fetch.handle.await {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            error!("Sync: HTTP fallback stopped: {}", e);
+            (FallbackSummary::default(), false)
+        }
+    };
+    if cancelled {
+        // Left paused, like any download stopped by a cancel
+        info!("Sync: HTTP fallback cancelled, the files are as they were");
+        return;
+    }
+    info!("Sync: HTTP fallback fetched {} files, {} failed", summary.fetched.len(), summary.failed.len());
+    let fetched_any = !summary.fetched.is_empty();
+    let _ = ui_tx.send(SyncEvent::HttpFallbackFetched(summary));
+
+    // An update that replaced the torrent meanwhile has it re-check the folder anyway
+    if !matches!(state.local, LocalTorrentState::Active { id: current } if current == id) {
+        return;
+    }
+    // Re-adding makes librqbit hash the new files and count them as done, and the new
+    // torrent starts running in place of the paused one
+    if fetched_any && fix_missing_files(config, state, api, ui_tx).await {
+        return;
+    }
+    // Nothing to pick up, or the re-add was refused and the paused torrent is still there
+    if matches!(state.local, LocalTorrentState::Active { id: current } if current == id) {
+        if let Err(e) = api.api_torrent_action_start(id.into()).await {
+            error!("Sync: Failed to resume torrent {} after the HTTP fallback: {}", id, e);
+        }
+        if !fetched_any {
+            send_sync_status_event(ui_tx, SyncStatus::LocalActive);
+        }
+    }
+}
+
+/// Fetch the torrent's unfinished files over HTTP once it has gone long enough without
+/// peers. The download runs in the background, under a token from `begin_cancellable` so
+/// CancelSync stops it, and a later tick picks up the result.
+pub async fn check_http_fallback(
+    config: &AppConfig,
+    state: &mut SyncState,
+    watch: &mut FallbackWatch,
+    api: &librqbit::Api,
+    client: &reqwest::Client,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+    begin_cancellable: impl FnOnce() -> CancellationToken,
+) {
+    if watch.fetch.as_ref().is_some_and(|fetch| fetch.handle.is_finished())
+        && let Some(fetch) = watch.fetch.take()
+    {
+        finish_fallback(config, state, api, ui_tx, fetch).await;
+        return;
+    }
+    if let Some(fetch) = &watch.fetch {
+        // The periodic refresh just showed the paused torrent
+        send_sync_status_event(ui_tx, SyncStatus::FetchingHttp { percent: fetch.percent.load(Ordering::Relaxed) });
+        return;
+    }
+
+    let Some(base_url) = config.http_fallback_base_url.as_deref() else {
+        return;
+    };
+    // Replacing files is what safe mode promises not to do
+    if config.safe_mode {
+        return;
+    }
+    let LocalTorrentState::Active { id } = state.local else {
+        return;
+    };
+    let Ok(stats) = api.api_stats_v1(id.into()) else {
+        return;
+    };
+    // Paused torrents have no peers either, but nobody is waiting on them
+    let Some(live) = stats.live.as_ref().filter(|_| !stats.finished) else {
+        watch.no_peers_since = None;
+        return;
+    };
+    let after = Duration::from_secs(config.http_fallback_after_seconds);
+    if !watch.no_peers_for(live.snapshot.peer_stats.live, Instant::now(), after) {
+        return;
+    }
+    let Ok(details) = api.api_torrent_details(id.into()) else {
+        return;
+    };
+    if watch.tried.as_ref() == Some(&details.info_hash) || foreign_owner(config).is_some() {
+        return;
+    }
+    watch.tried = Some(details.info_hash.clone());
+
+    let torrent_bytes = match loaded_torrent_bytes(api, id).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Sync: No torrent to check the HTTP fallback's files against: {:#}", e);
+            return;
+        }
+    };
+    // Files edited since the add could be preserved now, even if they weren't then
+    let preserved = match preserved_files(&config.download_path, &torrent_bytes, config.overwrite_policy) {
+        Ok(preserved) => preserved,
+        Err(e) => {
+            warn!("Sync: Not running the HTTP fallback, can't tell which files to keep: {:#}", e);
+            return;
+        }
+    };
+
+    // `included` is the add's only_files selection: no paused files or ones kept aside
+    let missing: Vec<(String, u64)> = details
+        .files
+        .iter()
+        .flatten()
+        .zip(&stats.file_progress)
+        .filter(|(file, progress)| file.included && !file.attributes.padding && **progress < file.length)
+        .map(|(file, _)| (file.components.join("/"), file.length))
+        .filter(|(relative, _)| !preserved.iter().any(|(_, path)| path == relative))
+        .collect();
+    if missing.is_empty() {
+        return;
+    }
+    info!("Sync: No peers for {} seconds, fetching {} files over HTTP", after.as_secs(), missing.len());
+
+    // librqbit mustn't write to the files while they're replaced
+    if let Err(e) = api.api_torrent_action_pause(id.into()).await {
+        error!("Sync: Failed to pause torrent {} for the HTTP fallback: {}", id, e);
+        return;
+    }
+    send_sync_status_event(ui_tx, SyncStatus::FetchingHttp { percent: 0 });
+    let token = begin_cancellable();
+    let percent = Arc::new(AtomicU8::new(0));
+    let job = FetchJob { base_url: base_url.to_string(), download_path: config.download_path.clone(), missing, torrent_bytes };
+    let handle = tokio::spawn(fetch_missing(job, client.clone(), ui_tx.clone(), percent.clone(), token.clone()));
+    watch.fetch = Some(FallbackFetch { id, token, percent, handle });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_urls_and_peer_wait() {
+        let url = file_url("https://mirror.example/mods", "@mod/addons/a b.pbo").unwrap();
+        assert_eq!(url.as_str(), "https://mirror.example/mods/@mod/addons/a%20b.pbo");
+        let url = file_url("https://mirror.example/mods/", "@mod/mod.cpp").unwrap();
+        assert_eq!(url.as_str(), "https://mirror.example/mods/@mod/mod.cpp");
+        assert!(file_url("not a url", "a.pbo").is_err());
+
+        let mut watch = FallbackWatch::default();
+        let start = Instant::now();
+        let after = Duration::from_secs(300);
+        assert!(!watch.no_peers_for(0, start, after));
+        assert!(!watch.no_peers_for(0, start + Duration::from_secs(299), after));
+        assert!(watch.no_peers_for(0, start + Duration::from_secs(300), after));
+        // A peer turning up starts the wait over
+        assert!(!watch.no_peers_for(1, start + Duration::from_secs(301), after));
+        assert!(!watch.no_peers_for(0, start + Duration::from_secs(400), after));
+    }
+
+    #[test]
+    fn test_partial_data_kept_until_the_check() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let target = dir.path().join("a.pbo");
+        std::fs::write(&target, b"partial")?;
+        std::fs::write(sibling(&target, FETCHED_SUFFIX), b"bad copy")?;
+        swap_in(&target)?;
+        assert_eq!(std::fs::read(&target)?, b"bad copy");
+        settle(&target, false);
+        assert_eq!(std::fs::read(&target)?, b"partial");
+
+        std::fs::write(sibling(&target, FETCHED_SUFFIX), b"good copy")?;
+        swap_in(&target)?;
+        settle(&target, true);
+        assert_eq!(std::fs::read(&target)?, b"good copy");
+        assert!(!sibling(&target, ORIGINAL_SUFFIX).exists());
+
+        // A file that wasn't there at all is simply removed again
+        let new = dir.path().join("b.pbo");
+        std::fs::write(sibling(&new, FETCHED_SUFFIX), b"bad copy")?;
+        swap_in(&new)?;
+        settle(&new, false);
+        assert!(!new.exists());
+        Ok(())
+    }
+}
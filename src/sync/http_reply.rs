@@ -0,0 +1,123 @@
+// src/sync/http_reply.rs
+
+//! Replies of the embedded HTTP servers (`/metrics` and the control API). Bodies of
+//! `MIN_COMPRESS_BYTES` or more are gzip- or deflate-compressed when `http_compression`
+//! is on and the client's `Accept-Encoding` allows it; small ones aren't worth it.
+
+use anyhow::Result;
+use flate2::Compression;
+use flate2::write::{GzEncoder, ZlibEncoder};
+use std::io::Write;
+
+/// Smallest body that gets compressed
+pub const MIN_COMPRESS_BYTES: usize = 1024;
+
+/// Content codings the servers can produce
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Gzip,
+    Deflate,
+}
+
+impl Encoding {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+        }
+    }
+}
+
+/// The coding to use for `request` (the raw request head), preferring gzip.
+/// Codings the client marks `q=0` are refused; `*` counts for both.
+pub fn accepted_encoding(request: &str) -> Option<Encoding> {
+    let header = request.lines().skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim().eq_ignore_ascii_case("accept-encoding").then_some(value)
+    })?;
+    let accepts = |wanted: &str| {
+        header.split(',').any(|coding| {
+            let mut params = coding.split(';');
+            let name = params.next().unwrap_or_default().trim();
+            let refused = params.any(|param| {
+                param.trim().strip_prefix("q=").and_then(|q| q.trim().parse::<f32>().ok()) == Some(0.0)
+            });
+            (name.eq_ignore_ascii_case(wanted) || name == "*") && !refused
+        })
+    };
+    [Encoding::Gzip, Encoding::Deflate].into_iter().find(|encoding| accepts(encoding.name()))
+}
+
+fn compress(body: &[u8], encoding: Encoding) -> Result<Vec<u8>> {
+    Ok(match encoding {
+        Encoding::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(body)?;
+            encoder.finish()?
+        }
+        // HTTP's "deflate" is the zlib format
+        Encoding::Deflate => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(body)?;
+            encoder.finish()?
+        }
+    })
+}
+
+/// A complete reply, compressed for `request` when `compression` is on and it pays off
+pub fn build_reply(status_line: &str, content_type: &str, body: &[u8], request: &str, compression: bool) -> Vec<u8> {
+    let encoding = accepted_encoding(request).filter(|_| compression && body.len() >= MIN_COMPRESS_BYTES);
+    let compressed = encoding.and_then(|encoding| Some((encoding, compress(body, encoding).ok()?)));
+    let (body, encoding_header) = match &compressed {
+        Some((encoding, compressed)) => (compressed.as_slice(), format!("Content-Encoding: {}\r\n", encoding.name())),
+        None => (body, String::new()),
+    };
+    let mut reply = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\n{}Vary: Accept-Encoding\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status_line,
+        content_type,
+        encoding_header,
+        body.len()
+    )
+    .into_bytes();
+    reply.extend_from_slice(body);
+    reply
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_accepted_encoding() {
+        let request = |header: &str| format!("GET /metrics HTTP/1.1\r\nHost: x\r\n{}\r\n\r\n", header);
+        assert_eq!(accepted_encoding(&request("Accept-Encoding: gzip, deflate")), Some(Encoding::Gzip));
+        assert_eq!(accepted_encoding(&request("accept-encoding: deflate;q=0.5, gzip;q=0")), Some(Encoding::Deflate));
+        assert_eq!(accepted_encoding(&request("Accept-Encoding: *")), Some(Encoding::Gzip));
+        assert_eq!(accepted_encoding(&request("Accept-Encoding: br")), None);
+        assert_eq!(accepted_encoding(&request("User-Agent: curl")), None);
+    }
+
+    #[test]
+    fn test_only_large_bodies_are_compressed() -> Result<()> {
+        let request = "GET /metrics HTTP/1.1\r\nAccept-Encoding: gzip\r\n\r\n";
+        let small = build_reply("200 OK", "text/plain", b"up 1\n", request, true);
+        assert!(!String::from_utf8_lossy(&small).contains("Content-Encoding"));
+
+        let body = "modsync_progress_ratio 1\n".repeat(100);
+        let off = build_reply("200 OK", "text/plain", body.as_bytes(), request, false);
+        assert!(!String::from_utf8_lossy(&off).contains("Content-Encoding"));
+
+        let reply = build_reply("200 OK", "text/plain", body.as_bytes(), request, true);
+        let split = reply.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+        let head = String::from_utf8_lossy(&reply[..split]);
+        assert!(head.contains("Content-Encoding: gzip\r\n"), "{}", head);
+        assert!(head.contains(&format!("Content-Length: {}\r\n", reply.len() - split)));
+        let mut decoded = String::new();
+        GzDecoder::new(&reply[split..]).read_to_string(&mut decoded)?;
+        assert_eq!(decoded, body);
+        Ok(())
+    }
+}
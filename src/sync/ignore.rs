@@ -0,0 +1,123 @@
+// src/sync/ignore.rs
+
+//! Ignore patterns: local files matching them are never reported as extra or
+//! deleted by ModSync, even when the torrent doesn't list them
+
+use std::path::Path;
+use walkdir::WalkDir;
+
+use crate::glob::glob_match;
+
+use super::cleaner::is_marker_file;
+
+/// Matched paths shown per pattern in the editor preview
+const PREVIEW_SAMPLE_SIZE: usize = 5;
+
+/// Why `pattern` can't be used, or Ok if the matcher understands it
+pub fn validate_pattern(pattern: &str) -> Result<(), String> {
+    let pattern = pattern.trim();
+    if pattern.is_empty() {
+        return Err("Pattern is empty".to_string());
+    }
+    if pattern.starts_with('/') || pattern.starts_with('\\') || pattern.contains(':') {
+        return Err("Use a path relative to the download folder".to_string());
+    }
+    if pattern.contains(['[', ']', '{', '}']) {
+        return Err("[...] and {...} aren't supported, only * ? and **".to_string());
+    }
+    for segment in pattern.split(['/', '\\']) {
+        if segment == ".." {
+            return Err("'..' can't be used".to_string());
+        }
+        if segment.contains("**") && segment != "**" {
+            return Err("** must be a whole folder, as in **/name or name/**".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Whether the download-relative `relative_path` matches any valid pattern
+pub fn is_ignored(relative_path: &Path, patterns: &[String]) -> bool {
+    let path = relative_path.to_string_lossy().replace('\\', "/");
+    patterns
+        .iter()
+        .filter(|pattern| validate_pattern(pattern).is_ok())
+        .any(|pattern| glob_match(pattern, &path))
+}
+
+/// Every file in the download folder, relative and '/'-separated, for previewing patterns
+pub fn list_local_files(download_path: &Path) -> Vec<String> {
+    let mut files: Vec<String> = WalkDir::new(download_path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let relative_path = entry.path().strip_prefix(download_path).ok()?;
+            (!is_marker_file(relative_path))
+                .then(|| relative_path.to_string_lossy().replace('\\', "/"))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// What one pattern matches among the cached local files
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternPreview {
+    pub pattern: String,
+    pub error: Option<String>, // Set when the pattern is invalid; it then matches nothing
+    pub match_count: usize,
+    pub sample: Vec<String>, // First few matches, in file list order
+}
+
+/// Match every pattern against `files` for the editor's live preview
+pub fn preview_patterns(patterns: &[String], files: &[String]) -> Vec<PatternPreview> {
+    patterns
+        .iter()
+        .map(|pattern| match validate_pattern(pattern) {
+            Err(error) => PatternPreview { pattern: pattern.clone(), error: Some(error), match_count: 0, sample: Vec::new() },
+            Ok(()) => {
+                let mut matches = files.iter().filter(|file| glob_match(pattern, file));
+                let sample: Vec<String> = matches.by_ref().take(PREVIEW_SAMPLE_SIZE).cloned().collect();
+                let match_count = sample.len() + matches.count();
+                PatternPreview { pattern: pattern.clone(), error: None, match_count, sample }
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_validate_pattern() {
+        assert!(validate_pattern("userconfig/**").is_ok());
+        assert!(validate_pattern("**/*.log").is_ok());
+        assert!(validate_pattern("  ").is_err());
+        assert!(validate_pattern("/etc/passwd").is_err());
+        assert!(validate_pattern("C:/mods").is_err());
+        assert!(validate_pattern("../outside").is_err());
+        assert!(validate_pattern("a**b").is_err());
+        assert!(validate_pattern("*.[ch]").is_err());
+    }
+
+    #[test]
+    fn test_is_ignored_skips_invalid_patterns() {
+        let patterns = vec!["userconfig/**".to_string(), "../**".to_string()];
+        assert!(is_ignored(&PathBuf::from("userconfig/settings.hpp"), &patterns));
+        assert!(!is_ignored(&PathBuf::from("addons/core.pbo"), &patterns));
+    }
+
+    #[test]
+    fn test_preview_counts_and_samples() {
+        let files: Vec<String> = (0..8).map(|i| format!("logs/run{}.log", i)).chain(["addons/a.pbo".to_string()]).collect();
+        let previews = preview_patterns(&["*.log".to_string(), "a**".to_string()], &files);
+        assert_eq!(previews[0].match_count, 8);
+        assert_eq!(previews[0].sample.len(), PREVIEW_SAMPLE_SIZE);
+        assert_eq!(previews[0].sample[0], "logs/run0.log");
+        assert!(previews[1].error.is_some());
+        assert_eq!(previews[1].match_count, 0);
+    }
+}
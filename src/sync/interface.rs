@@ -0,0 +1,150 @@
+// src/sync/interface.rs
+
+//! Kill switch for a bound network interface (usually a VPN): torrents are paused
+//! whenever the configured interface is missing, down or has no address. librqbit binds
+//! its sockets to all interfaces, so this can't route traffic; it only stops it.
+
+use std::net::IpAddr;
+use sysinfo::{InterfaceOperationalState, Networks};
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use crate::config::AppConfig;
+use crate::ui::utils::SyncStatus;
+
+use super::messages::SyncEvent;
+use super::types::SyncState;
+use super::utils::send_sync_status_event;
+
+/// Keeps the interface list between polls so refreshes stay cheap
+pub struct InterfaceWatcher {
+    networks: Networks,
+}
+
+impl Default for InterfaceWatcher {
+    fn default() -> Self {
+        Self { networks: Networks::new() }
+    }
+}
+
+impl InterfaceWatcher {
+    /// Refresh the interface list and check whether `name_or_ip` is usable
+    pub fn is_up(&mut self, name_or_ip: &str) -> bool {
+        self.networks.refresh(true);
+        self.networks.list().iter().any(|(name, data)| {
+            let addresses: Vec<IpAddr> = data.ip_networks().iter().map(|network| network.addr).collect();
+            interface_is_usable(name_or_ip, name, &addresses, data.operational_state())
+        })
+    }
+}
+
+/// Whether an interface called `name` is the configured one (by name, ignoring case, or
+/// by one of its addresses) and can pass traffic. Tunnel devices often report Unknown,
+/// so only the explicit "can't pass packets" states count as down.
+pub fn interface_is_usable(configured: &str, name: &str, addresses: &[IpAddr], state: InterfaceOperationalState) -> bool {
+    let configured = configured.trim();
+    let matches = match configured.parse::<IpAddr>() {
+        Ok(ip) => addresses.contains(&ip),
+        Err(_) => !configured.is_empty() && name.eq_ignore_ascii_case(configured),
+    };
+    let down = matches!(
+        state,
+        InterfaceOperationalState::Down
+            | InterfaceOperationalState::Dormant
+            | InterfaceOperationalState::NotPresent
+            | InterfaceOperationalState::LowerLayerDown
+    );
+    matches && !addresses.is_empty() && !down
+}
+
+/// Pause every live torrent while the bound interface is down, and resume the ones we
+/// paused once it's back. Torrents the game check still wants paused are handed to it.
+pub async fn check_bind_interface(
+    config: &AppConfig,
+    state: &mut SyncState,
+    watcher: &mut InterfaceWatcher,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+) {
+    let down = match config.bind_interface.as_deref() {
+        Some(interface) if !interface.trim().is_empty() => !watcher.is_up(interface),
+        // Binding disabled; make sure we don't stay paused because of it
+        _ => false,
+    };
+
+    if down {
+        let paused_ids = state.paused_for_interface.get_or_insert_with(|| {
+            info!("Sync: Bound interface is down, pausing torrents");
+            Vec::new()
+        });
+        // Also catches torrents added or resumed while the interface was down
+        let torrent_list = api.api_torrent_list_ext(librqbit::api::ApiTorrentListOpts { with_stats: true });
+        for torrent in torrent_list.torrents {
+            let (Some(id), Some(stats)) = (torrent.id, torrent.stats.as_ref()) else { continue };
+            if stats.live.is_none() || paused_ids.contains(&id) {
+                continue;
+            }
+            match api.api_torrent_action_pause(id.into()).await {
+                Ok(_) => {
+                    info!("Sync: Paused torrent {} while bound interface is down", id);
+                    paused_ids.push(id);
+                }
+                Err(e) => error!("Sync: Failed to pause torrent {} for bound interface: {}", id, e),
+            }
+        }
+        send_sync_status_event(ui_tx, SyncStatus::BindInterfaceDown);
+    } else if let Some(paused_ids) = state.paused_for_interface.take() {
+        info!("Sync: Bound interface is back, resuming {} torrents", paused_ids.len());
+        if let Some(game_ids) = state.paused_for_game.as_mut() {
+            // The game is still running; it resumes these when it exits
+            for id in paused_ids {
+                if !game_ids.contains(&id) {
+                    game_ids.push(id);
+                }
+            }
+            return;
+        }
+        for id in paused_ids {
+            // Leave it paused if low disk space would pause it anyway
+            if state.low_disk_space {
+                break;
+            }
+            if state.paused_for_seed_goal.as_ref().is_some_and(|ids| ids.contains(&id)) {
+                continue; // Still held by the seed goal
+            }
+            if state.paused_for_battery.as_ref().is_some_and(|ids| ids.contains(&id)) {
+                continue; // Still held for the low battery
+            }
+            match api.api_torrent_action_start(id.into()).await {
+                Ok(_) => info!("Sync: Resumed torrent {} after bound interface came back", id),
+                Err(e) => error!("Sync: Failed to resume torrent {}: {}", id, e),
+            }
+        }
+        send_sync_status_event(ui_tx, SyncStatus::Idle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VPN_ADDRESS: [IpAddr; 1] = [IpAddr::V4(std::net::Ipv4Addr::new(10, 8, 0, 2))];
+
+    #[test]
+    fn test_matches_by_name_or_address() {
+        assert!(interface_is_usable("WG0", "wg0", &VPN_ADDRESS, InterfaceOperationalState::Up));
+        assert!(interface_is_usable("10.8.0.2", "tun0", &VPN_ADDRESS, InterfaceOperationalState::Up));
+        assert!(!interface_is_usable("10.8.0.3", "tun0", &VPN_ADDRESS, InterfaceOperationalState::Up));
+        assert!(!interface_is_usable("wg0", "eth0", &VPN_ADDRESS, InterfaceOperationalState::Up));
+        assert!(!interface_is_usable("  ", "", &VPN_ADDRESS, InterfaceOperationalState::Up));
+    }
+
+    #[test]
+    fn test_down_or_addressless_interface_is_unusable() {
+        // Tunnels commonly report Unknown while working fine
+        assert!(interface_is_usable("tun0", "tun0", &VPN_ADDRESS, InterfaceOperationalState::Unknown));
+        assert!(!interface_is_usable("tun0", "tun0", &VPN_ADDRESS, InterfaceOperationalState::Down));
+        assert!(!interface_is_usable("tun0", "tun0", &VPN_ADDRESS, InterfaceOperationalState::LowerLayerDown));
+        assert!(!interface_is_usable("tun0", "tun0", &[], InterfaceOperationalState::Up));
+    }
+}
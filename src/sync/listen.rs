@@ -0,0 +1,83 @@
+// src/sync/listen.rs
+
+//! Binding the embedded HTTP servers (`/metrics` and the control API). When the
+//! configured port is taken, the next few ports are tried, so another program on the
+//! port doesn't leave the server off; the port actually used is reported.
+
+use anyhow::{Context, Result, anyhow};
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+use tracing::info;
+
+/// Bind `addr`, or one of the `fallback_ports` ports after it if that port is in use
+pub async fn bind_with_fallback(addr: &str, fallback_ports: u16, what: &str) -> Result<TcpListener> {
+    let wanted: SocketAddr = tokio::net::lookup_host(addr)
+        .await
+        .with_context(|| format!("Invalid {} address {}", what, addr))?
+        .next()
+        .ok_or_else(|| anyhow!("{} address {} resolves to nothing", what, addr))?;
+    // Port 0 lets the system pick, so there's nothing to fall back from
+    let tries = if wanted.port() == 0 { 0 } else { fallback_ports };
+
+    let mut last_error = None;
+    for offset in 0..=tries {
+        let Some(port) = wanted.port().checked_add(offset) else { break };
+        let candidate = SocketAddr::new(wanted.ip(), port);
+        match TcpListener::bind(candidate).await {
+            Ok(listener) => return Ok(listener),
+            // Windows reports ports reserved by other services as access denied
+            Err(e) if matches!(e.kind(), ErrorKind::AddrInUse | ErrorKind::PermissionDenied) => {
+                info!("Sync: {} port {} is not available: {}", what, port, e);
+                last_error = Some(e);
+            }
+            Err(e) => return Err(e).with_context(|| format!("Failed to bind {} address {}", what, candidate)),
+        }
+    }
+    let tried = match tries {
+        0 => String::new(),
+        n => format!(" (also tried the next {} ports)", n),
+    };
+    Err(anyhow!(
+        "Failed to bind {} address {}{}: {}",
+        what,
+        addr,
+        tried,
+        last_error.map_or("no port left".to_string(), |e| e.to_string())
+    ))
+}
+
+/// Why the server isn't on the port that was asked for, if it isn't
+pub fn moved_port_warning(what: &str, configured: &str, bound: SocketAddr) -> Option<String> {
+    let configured_port = configured.rsplit(':').next().and_then(|port| port.parse::<u16>().ok())?;
+    (configured_port != 0 && configured_port != bound.port()).then(|| {
+        format!("{} port {} is in use, serving on port {} instead", what, configured_port, bound.port())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_taken_port_falls_back() -> Result<()> {
+        let taken = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = taken.local_addr()?.to_string();
+
+        let listener = bind_with_fallback(&addr, 5, "Test").await?;
+        let bound = listener.local_addr()?;
+        assert_ne!(bound.port(), taken.local_addr()?.port());
+        assert!(moved_port_warning("Test", &addr, bound).is_some());
+
+        let error = bind_with_fallback(&addr, 0, "Test").await.unwrap_err();
+        assert!(error.to_string().starts_with("Failed to bind Test address"), "{}", error);
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_warning_on_configured_or_any_port() {
+        let bound: SocketAddr = "127.0.0.1:9100".parse().unwrap();
+        assert_eq!(moved_port_warning("Metrics", "127.0.0.1:9100", bound), None);
+        assert_eq!(moved_port_warning("Metrics", "127.0.0.1:0", bound), None);
+    }
+}
@@ -4,16 +4,17 @@
 
 use crate::config::AppConfig;
 use crate::ui::utils::SyncStatus;
-use librqbit::TorrentStatsState;
+use librqbit::{TorrentStats, TorrentStatsState};
+use tracing::{error, info, warn};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 
-use super::cleaner::{find_extra_files, find_missing_files, get_expected_files_from_details};
+use super::cleaner::{find_extra_files, find_missing_files, get_all_files_from_details, get_expected_files_from_details};
 use super::messages::SyncEvent;
 use super::types::{LocalTorrentState, SyncState};
 use super::utils::send_sync_status_event;
-use super::torrent::manage_torrent_task;
+use super::torrent::recheck_torrent_task;
 use crate::config::get_cached_torrent_path;
 
 /// Function to verify local folder contents
@@ -27,13 +28,13 @@ pub async fn verify_folder_contents(
     if let LocalTorrentState::Active { id } = state.local {
         if config.download_path.as_os_str().is_empty() {
             let err_msg = "Download path not configured".to_string();
-            eprintln!("Sync: {}", err_msg);
+            warn!("Sync: {}", err_msg);
             let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
             send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
             return;
         }
 
-        println!(
+        info!(
             "Sync: Verifying folder contents at {}",
             config.download_path.display()
         );
@@ -44,7 +45,7 @@ pub async fn verify_folder_contents(
                 // Get the expected files list from torrent
                 let expected_files = get_expected_files_from_details(&details);
 
-                let mut has_missing_files = false;
+                let has_missing_files;
                 
                 // Check for missing files
                 match find_missing_files(&config.download_path, &expected_files) {
@@ -52,11 +53,11 @@ pub async fn verify_folder_contents(
                         has_missing_files = !missing_files.is_empty();
                         
                         if has_missing_files {
-                            println!("Sync: Found {} missing files.", missing_files.len());
+                            info!("Sync: Found {} missing files.", missing_files.len());
                             
                             // Notify UI of missing files for user decision
                             if let Err(e) = ui_tx.send(SyncEvent::MissingFilesFound(missing_files.clone())) {
-                                eprintln!("Sync: Failed to send missing files list to UI: {}", e);
+                                error!("Sync: Failed to send missing files list to UI: {}", e);
                                 send_sync_status_event(ui_tx, SyncStatus::Error(format!("Failed to send missing files notification: {}", e)));
                                 return;
                             }
@@ -64,29 +65,33 @@ pub async fn verify_folder_contents(
                             // Set status to indicate missing files
                             send_sync_status_event(ui_tx, SyncStatus::LocalActive);
                         } else {
-                            println!("Sync: No missing files found. All expected files are present.");
+                            info!("Sync: No missing files found. All expected files are present.");
                         }
                     },
                     Err(e) => {
                         let err_msg = format!("Failed to check for missing files: {}", e);
-                        eprintln!("Sync: {}", err_msg);
+                        warn!("Sync: {}", err_msg);
                         let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
                         send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
                         return;
                     }
                 }
 
-                // Proceed with checking for extra files
-                match find_extra_files(&config.download_path, &expected_files) {
+                // Proceed with checking for extra files. Paused files are deselected
+                // but still belong to the torrent.
+                let torrent_files = get_all_files_from_details(&details);
+                match find_extra_files(&config.download_path, &torrent_files, &config.ignore_patterns) {
                     Ok(extra_files) => {
-                        println!("Sync: Found {} extra files in directory", extra_files.len());
+                        info!("Sync: Found {} extra files in directory", extra_files.len());
                         
                         // Check if there are extra files before sending
                         let has_extra_files = !extra_files.is_empty();
                         
                         // Notify UI of extra files for potential deletion
-                        if let Err(e) = ui_tx.send(SyncEvent::ExtraFilesFound(extra_files)) {
-                            eprintln!("Sync: Failed to send extra files list to UI: {}", e);
+                        if config.safe_mode {
+                            info!("Sync: Safe mode is on, not offering to delete extra files");
+                        } else if let Err(e) = ui_tx.send(SyncEvent::ExtraFilesFound(extra_files)) {
+                            error!("Sync: Failed to send extra files list to UI: {}", e);
                         }
                         
                         // Set the status appropriately based on whether files were found
@@ -98,13 +103,13 @@ pub async fn verify_folder_contents(
                             send_sync_status_event(ui_tx, SyncStatus::LocalActive);
                         } else {
                             // If both checks passed with no issues, reset to Idle
-                            println!("Sync: Verification completed with no issues. Resetting to Idle state.");
+                            info!("Sync: Verification completed with no issues. Resetting to Idle state.");
                             send_sync_status_event(ui_tx, SyncStatus::Idle);
                         }
                     }
                     Err(e) => {
                         let err_msg = format!("Failed to find extra files: {}", e);
-                        eprintln!("Sync: {}", err_msg);
+                        warn!("Sync: {}", err_msg);
                         let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
                         send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
                     }
@@ -112,109 +117,102 @@ pub async fn verify_folder_contents(
             }
             Err(e) => {
                 let err_msg = format!("Failed to get torrent details: {}", e);
-                eprintln!("Sync: {}", err_msg);
+                warn!("Sync: {}", err_msg);
                 let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
                 send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
             }
         }
     } else {
         let err_msg = "No active torrent to verify against".to_string();
-        eprintln!("Sync: {}", err_msg);
+        warn!("Sync: {}", err_msg);
         let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
         send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
     }
 }
 
-/// Function to fix missing files by restarting the torrent
+/// The torrent file the session loaded as `id`, or the cached one if the session has no metadata for it
+pub async fn loaded_torrent_bytes(api: &librqbit::Api, id: usize) -> anyhow::Result<Vec<u8>> {
+    let loaded = api
+        .session()
+        .get(id.into())
+        .and_then(|torrent| torrent.metadata.load().as_ref().map(|m| m.torrent_bytes.to_vec()));
+    match loaded {
+        Some(bytes) => Ok(bytes),
+        None => Ok(tokio::fs::read(get_cached_torrent_path()?).await?),
+    }
+}
+
+/// Function to fix missing files by restarting the torrent. Forgetting it and adding it
+/// again makes librqbit check every file and download what's missing or damaged.
+/// Returns whether the torrent was added again.
 pub async fn fix_missing_files(
     config: &AppConfig,
     state: &mut SyncState,
     api: &librqbit::Api,
     ui_tx: &mpsc::UnboundedSender<SyncEvent>,
-) {
+) -> bool {
     // Only proceed if we have an active torrent
-    if let LocalTorrentState::Active { id } = state.local {
-        println!("Sync: Attempting to fix missing files by restarting torrent ID {}", id);
-        send_sync_status_event(ui_tx, SyncStatus::UpdatingTorrent);
-        
-        // Get cached torrent file for restarting
-        match get_cached_torrent_path() {
-            Ok(cached_path) => {
-                match tokio::fs::read(&cached_path).await {
-                    Ok(torrent_content) => {
-                        // Restart the torrent with manage_torrent_task
-                        let restart_result = manage_torrent_task(
-                            config,
-                            api,
-                            ui_tx,
-                            Some(id), // Current ID to forget
-                            torrent_content,
-                        ).await;
-                        
-                        match restart_result {
-                            Ok(new_id) => {
-                                println!("Sync: Torrent restarted successfully to download missing files. New ID: {:?}", new_id);
-                                
-                                // Update the state with the new torrent ID
-                                state.local = match new_id {
-                                    Some(new_torrent_id) => {
-                                        // Send torrent added event with the new ID
-                                        let _ = ui_tx.send(SyncEvent::TorrentAdded(new_torrent_id));
-                                        
-                                        // Update status for the new torrent
-                                        refresh_managed_torrent_status_event(api, ui_tx, new_torrent_id);
-                                        
-                                        LocalTorrentState::Active { id: new_torrent_id }
-                                    },
-                                    None => LocalTorrentState::NotLoaded,
-                                };
-                            },
-                            Err(e) => {
-                                let err_msg = format!("Failed to restart torrent to download missing files: {}", e);
-                                eprintln!("Sync: {}", err_msg);
-                                let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
-                                send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
-                                
-                                // The old torrent was removed but we failed to add a new one
-                                state.local = LocalTorrentState::NotLoaded;
-                            }
-                        }
-                    },
-                    Err(e) => {
-                        let err_msg = format!("Failed to read cached torrent file: {}", e);
-                        eprintln!("Sync: {}", err_msg);
-                        let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
-                        send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
-                    }
-                }
-            },
-            Err(e) => {
-                let err_msg = format!("Failed to get cached torrent path: {}", e);
-                eprintln!("Sync: {}", err_msg);
-                let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
-                send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
-            }
-        }
-    } else {
+    let LocalTorrentState::Active { id } = state.local else {
         let err_msg = "No active torrent to fix missing files".to_string();
-        eprintln!("Sync: {}", err_msg);
+        warn!("Sync: {}", err_msg);
         let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
         send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
+        return false;
+    };
+    info!("Sync: Attempting to fix missing files by restarting torrent ID {}", id);
+    send_sync_status_event(ui_tx, SyncStatus::UpdatingTorrent);
+
+    let torrent_content = match loaded_torrent_bytes(api, id).await {
+        Ok(torrent_content) => torrent_content,
+        Err(e) => {
+            let err_msg = format!("Failed to read the torrent file to restart: {}", e);
+            warn!("Sync: {}", err_msg);
+            let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
+            send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
+            return false;
+        }
+    };
+
+    match recheck_torrent_task(config, api, ui_tx, id, torrent_content).await {
+        Ok(Some(new_id)) => {
+            info!("Sync: Torrent restarted successfully to download missing files. New ID: {}", new_id);
+            // Send torrent added event with the new ID and update its status
+            let _ = ui_tx.send(SyncEvent::TorrentAdded(new_id));
+            refresh_managed_torrent_status_event(api, ui_tx, new_id);
+            state.local = LocalTorrentState::Active { id: new_id };
+            true
+        }
+        Ok(None) => {
+            state.local = LocalTorrentState::NotLoaded;
+            false
+        }
+        Err(e) => {
+            let err_msg = format!("Failed to restart torrent to download missing files: {}", e);
+            warn!("Sync: {}", err_msg);
+            let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
+            send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
+
+            // A refused re-add leaves the old torrent; otherwise it was removed and no new one was added
+            if api.api_stats_v1(id.into()).is_err() {
+                state.local = LocalTorrentState::NotLoaded;
+            }
+            false
+        }
     }
 }
 
 /// Function to delete extra files
 pub async fn delete_files(files_to_delete: &[PathBuf], ui_tx: &mpsc::UnboundedSender<SyncEvent>) {
-    println!("Sync: Deleting {} files", files_to_delete.len());
+    info!("Sync: Deleting {} files", files_to_delete.len());
     send_sync_status_event(ui_tx, SyncStatus::CheckingLocal); // Re-use the CheckingLocal status
 
     let mut errors = Vec::new();
 
     for file_path in files_to_delete {
-        println!("Sync: Deleting file: {}", file_path.display());
+        info!("Sync: Deleting file: {}", file_path.display());
         if let Err(e) = tokio::fs::remove_file(file_path).await {
             let err_msg = format!("Failed to delete {}: {}", file_path.display(), e);
-            eprintln!("Sync: {}", err_msg);
+            warn!("Sync: {}", err_msg);
             errors.push(err_msg);
         }
     }
@@ -224,29 +222,42 @@ pub async fn delete_files(files_to_delete: &[PathBuf], ui_tx: &mpsc::UnboundedSe
         let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
         send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
     } else {
-        println!("Sync: All files deleted successfully");
+        info!("Sync: All files deleted successfully");
         // Clear any existing error and set status back to idle
         send_sync_status_event(ui_tx, SyncStatus::Idle);
     }
 
     // Let UI know that deletion is complete (empty list = no more files to delete)
     if let Err(e) = ui_tx.send(SyncEvent::ExtraFilesFound(Vec::new())) {
-        eprintln!("Sync: Failed to send empty extra files list to UI: {}", e);
+        error!("Sync: Failed to send empty extra files list to UI: {}", e);
     }
 }
 
+/// Percentage of the initial hash check done, or None once the torrent has left
+/// the initializing state
+pub fn initial_check_percent(stats: &TorrentStats) -> Option<u8> {
+    if !matches!(stats.state, TorrentStatsState::Initializing) {
+        return None;
+    }
+    // While initializing, librqbit reports checked bytes as progress
+    let percent = (stats.progress_bytes * 100)
+        .checked_div(stats.total_bytes)
+        .unwrap_or(0);
+    Some(percent.min(100) as u8)
+}
+
 /// Helper function to refresh the status of the managed torrent
 pub fn refresh_managed_torrent_status_event(
     api: &librqbit::Api,
     tx: &mpsc::UnboundedSender<SyncEvent>,
     managed_id: usize,
 ) {
-    println!("Sync: Fetching stats for torrent ID {}", managed_id);
+    info!("Sync: Fetching stats for torrent ID {}", managed_id);
     match api.api_stats_v1(managed_id.into()) {
         Ok(stats) => {
             // Send the torrent stats update - wrap in Arc
             if let Err(e) = tx.send(SyncEvent::ManagedTorrentUpdate(Some((managed_id, Arc::new(stats))))) {
-                eprintln!(
+                error!(
                     "Sync: Failed to send managed torrent stats update to UI (ID {}): {}",
                     managed_id, e
                 );
@@ -259,8 +270,9 @@ pub fn refresh_managed_torrent_status_event(
                 // Only do this if the torrent is in a "normal" state (not checking, etc.)
                 match refreshed_stats.state {
                     TorrentStatsState::Initializing => {
-                        // Torrent is still checking files
-                        send_sync_status_event(tx, SyncStatus::CheckingLocal);
+                        // Torrent is still hashing existing files
+                        let percent = initial_check_percent(&refreshed_stats).unwrap_or(0);
+                        send_sync_status_event(tx, SyncStatus::Checking { percent });
                     }
                     TorrentStatsState::Live => {
                         // Torrent is active (downloading or seeding)
@@ -282,7 +294,7 @@ pub fn refresh_managed_torrent_status_event(
             }
         }
         Err(e) => {
-            eprintln!(
+            error!(
                 "Sync: Error fetching torrent stats for ID {}: {}. Sending None to UI.",
                 managed_id, e
             );
@@ -293,4 +305,39 @@ pub fn refresh_managed_torrent_status_event(
             let _ = tx.send(SyncEvent::Error(err_msg));
         }
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats_with(state: TorrentStatsState, progress_bytes: u64, total_bytes: u64) -> TorrentStats {
+        TorrentStats {
+            state,
+            file_progress: Vec::new(),
+            error: None,
+            progress_bytes,
+            uploaded_bytes: 0,
+            total_bytes,
+            finished: false,
+            live: None,
+        }
+    }
+
+    #[test]
+    fn test_initial_check_percent_while_initializing() {
+        let stats = stats_with(TorrentStatsState::Initializing, 250, 1000);
+        assert_eq!(initial_check_percent(&stats), Some(25));
+    }
+
+    #[test]
+    fn test_initial_check_percent_unknown_total() {
+        let stats = stats_with(TorrentStatsState::Initializing, 250, 0);
+        assert_eq!(initial_check_percent(&stats), Some(0));
+    }
+
+    #[test]
+    fn test_initial_check_percent_after_check() {
+        let stats = stats_with(TorrentStatsState::Live, 1000, 1000);
+        assert_eq!(initial_check_percent(&stats), None);
+    }
+}
@@ -3,57 +3,180 @@
 //! Main manager for the synchronization process
 
 use anyhow::{Context, Result};
+use librqbit::TorrentStatsState;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, FileConfigStore, get_cached_torrent_path, get_config_path};
 use crate::ui::utils::SyncStatus;
 
-use super::cleaner::{find_extra_files, get_expected_files_from_details};
+use super::autostop::{AutoStop, check_auto_stop, resume_downloads};
+use super::breaker::{ErrorBreaker, check_torrent_error, note_failure};
+use super::metrics::{MetricsSnapshot, serve_metrics, tap_events};
+use super::control::{ControlContext, serve_control};
+use super::listen::moved_port_warning;
+use super::seed_goal::check_seed_goal;
+use super::seeding::check_seeding;
+use super::completion::{check_completion, clear_marker};
+use super::offline_verify::verify_offline;
+use super::priority::PriorityFile;
+use super::reload::{ConfigFileWatch, changed_keys, reload_config};
+use super::http_fallback::{FallbackWatch, check_http_fallback};
+use super::staging::check_staged_update;
+use super::spot_check::spot_check;
+use super::tracker_check::check_trackers;
+use super::routing::check_routes;
+use super::grace::UpdateGrace;
+use super::snooze::{SnoozeState, SnoozeWatch};
+use super::port_check::check_port;
+use super::extract::check_extractions;
+use super::file_mode::check_file_modes;
+use super::failure_webhook::{FailureStreak, note_check_failure};
+use super::awake::{SleepInhibitor, check_sleep_inhibit};
+use super::disk::check_free_space;
+use super::game::{ProcessWatcher, check_game_process};
+use super::battery::{BatteryWatch, check_battery};
+use super::memory::{MemoryWatcher, check_memory};
+use super::publish::{PublishWatcher, check_publish, publish_path};
+use super::interface::{InterfaceWatcher, check_bind_interface};
+use super::passkey::{PasskeyCheck, maybe_check_tracker_passkey};
+use super::http::create_http_client;
+use super::cleaner::{find_extra_files, get_all_files_from_details};
+use super::file_pause::update_paused_files;
 use super::local::{delete_files, refresh_managed_torrent_status_event, verify_folder_contents, fix_missing_files};
 use super::messages::{SyncCommand, SyncEvent};
+use super::torrent::{find_torrent_by_info_hash, info_hash_of, needs_seeding_confirmation};
+use super::notice::{acknowledge_notice, pending_notice};
+use super::overwrite::{check_synced_record, preserved_files};
+use super::upload_ramp::check_upload_ramp;
+use super::owner::{FolderOwner, foreign_owner, write_owner};
+use super::preflight::{check_torrent_url, test_config};
 use super::remote::{apply_remote_update, direct_download_and_compare};
 use super::types::{LocalTorrentState, RemoteTorrentState, SyncState};
-use super::utils::send_sync_status_event;
+use super::utils::{load_remote_meta, send_sync_event, send_sync_status_event};
 
 /// Main loop for the synchronization manager task
+#[tracing::instrument(name = "sync", skip_all)]
 pub async fn run_sync_manager(
     initial_config: AppConfig,
     api: librqbit::Api,
     ui_tx: mpsc::UnboundedSender<SyncEvent>,
-    mut sync_cmd_rx: mpsc::UnboundedReceiver<SyncCommand>,
+    sync_cmd_rx: mpsc::UnboundedReceiver<SyncCommand>,
     initial_torrent_id: Option<usize>, // Accept initial ID
 ) -> Result<()> {
+    // Commands go through a forwarder so CancelSync can interrupt whatever we're busy with
+    let cancel_slot = Arc::new(Mutex::new(CancellationToken::new()));
+    let (own_cmd_tx, mut sync_cmd_rx) = spawn_command_forwarder(sync_cmd_rx, cancel_slot.clone());
+    // Shared with the control API, which can cancel the countdown
+    let update_grace = UpdateGrace::default();
+
     let mut state = SyncState {
         local: match initial_torrent_id {
             Some(id) => LocalTorrentState::Active { id },
             None => LocalTorrentState::NotLoaded,
         },
         remote: RemoteTorrentState::Unknown,
+        ..SyncState::default()
     };
     let mut current_config = initial_config;
 
-    // Create HTTP client once
-    let http_client = super::http::create_http_client().context("Failed to create HTTP client")?;
+    // Record what we report for /metrics and /status; the addresses are only read at startup
+    let metrics_addr = current_config.metrics_addr.clone().filter(|addr| !addr.is_empty());
+    let control_addr = current_config.control_addr.clone().filter(|addr| !addr.is_empty());
+    let snapshot = Arc::new(Mutex::new(MetricsSnapshot::default()));
+    let ui_tx = if metrics_addr.is_some() || control_addr.is_some() { tap_events(ui_tx, snapshot.clone()) } else { ui_tx };
+    if let Some(addr) = metrics_addr.as_deref() {
+        let bound = serve_metrics(addr, current_config.bind_fallback_ports, current_config.http_compression, snapshot.clone()).await;
+        report_server(&ui_tx, "Metrics", addr, bound, "/metrics");
+    }
+    if let Some(addr) = control_addr.as_deref() {
+        let control = ControlContext {
+            api: api.clone(),
+            update_grace: update_grace.clone(),
+            ui_tx: ui_tx.clone(),
+            cmd_tx: own_cmd_tx.clone(),
+            snapshot,
+            token: current_config.control_token.clone(),
+        };
+        let bound = serve_control(addr, current_config.bind_fallback_ports, current_config.http_compression, control).await;
+        report_server(&ui_tx, "Control API", addr, bound, "/");
+    }
+
+    // Create HTTP client once; rebuilt only when the user-agent, IP mode or DNS overrides change
+    let mut http_client = match create_http_client(current_config.user_agent.as_deref(), current_config.ip_mode, &current_config.dns_overrides) {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Sync: {:#}. Using the default user-agent.", e);
+            let _ = ui_tx.send(SyncEvent::Error(format!("{:#}", e)));
+            create_http_client(None, current_config.ip_mode, &current_config.dns_overrides).context("Failed to create HTTP client")?
+        }
+    };
     
     // Track the last time we checked for updates
     let mut last_update_check: Option<std::time::Instant> = None;
+    
+    // Reused between polls of the process list
+    let mut process_watcher = ProcessWatcher::default();
+    let mut battery_watch = BatteryWatch::default();
+    let mut config_watch = ConfigFileWatch::default();
+    let mut memory_watcher = MemoryWatcher::default();
+    let mut publish_watcher = PublishWatcher::default();
+    let mut interface_watcher = InterfaceWatcher::default();
+    let mut fallback_watch = FallbackWatch::default();
+    let mut priority_file = PriorityFile::default();
+    let mut passkey_check = PasskeyCheck::default();
+    let mut error_breaker = ErrorBreaker::default();
+    let mut failure_streak = FailureStreak::default();
+    let mut auto_stop = AutoStop::default();
+    let mut snooze_watch = SnoozeWatch::default();
+    // Released on drop when the manager returns
+    let mut sleep_inhibitor = SleepInhibitor::default();
 
     // Send initial status based on whether a cached torrent was loaded
     if let LocalTorrentState::Active { id } = state.local {
         // If we started with a cached torrent, immediately check its status
-        println!(
+        info!(
             "Sync: Refreshing status for initially loaded torrent ID: {}",
             id
         );
         refresh_managed_torrent_status_event(&api, &ui_tx, id);
+        // The cached torrent was added paused if it still needs seeding confirmation or its notice accepted
+        if let Ok(cached_path) = get_cached_torrent_path()
+            && let Ok(bytes) = tokio::fs::read(&cached_path).await
+        {
+            if let Some(notice) = pending_notice(&current_config, &bytes) {
+                let _ = ui_tx.send(SyncEvent::NoticeAcknowledgementNeeded { id, notice });
+            }
+            if needs_seeding_confirmation(&current_config, &bytes) {
+                let _ = ui_tx.send(SyncEvent::SeedingConfirmationNeeded(id));
+            }
+            // Files the overwrite policy kept out when the cached torrent was added
+            if let Ok(preserved) = preserved_files(&current_config.download_path, &bytes, current_config.overwrite_policy)
+                && !preserved.is_empty()
+            {
+                let _ = ui_tx.send(SyncEvent::FilesPreserved(preserved.into_iter().map(|(_, path)| path).collect()));
+            }
+        }
         // Set overall sync status to Idle, actual torrent status comes from refresh
         send_sync_status_event(&ui_tx, SyncStatus::Idle);
     } else {
         send_sync_status_event(&ui_tx, SyncStatus::Idle);
     }
 
-    println!("Sync: Manager started. Initial State: {:?}", state);
+    // Show where the URL led last time until the next check refreshes it
+    if let Some(meta) = load_remote_meta().filter(|meta| meta.source_url == current_config.torrent_url) {
+        let _ = ui_tx.send(SyncEvent::RemoteResolved(meta.resolved_url));
+    }
+
+    // Don't wait for the first tick to stop traffic if the VPN is already down
+    check_bind_interface(&current_config, &mut state, &mut interface_watcher, &api, &ui_tx).await;
+    maybe_check_tracker_passkey(&current_config, &state, &mut passkey_check, &api, &http_client, &ui_tx);
+
+    info!("Sync: Manager started. Initial State: {:?}", state);
 
     loop {
         tokio::select! {
@@ -61,65 +184,204 @@ pub async fn run_sync_manager(
             Some(cmd_message) = sync_cmd_rx.recv() => {
                 match cmd_message {
                     SyncCommand::UpdateConfig(new_config) => {
-                        println!("Sync: Received configuration update.");
+                        info!("Sync: Received configuration update.");
+                        if current_config.log_level != new_config.log_level {
+                            crate::logging::set_level(new_config.log_level);
+                        }
                         
-                        // Check if the URL changed
+                        let changed = changed_keys(&current_config, &new_config);
                         let url_changed = current_config.torrent_url != new_config.torrent_url;
                         let path_changed = current_config.download_path != new_config.download_path;
                         
+                        if current_config.user_agent != new_config.user_agent
+                            || current_config.ip_mode != new_config.ip_mode
+                            || current_config.dns_overrides != new_config.dns_overrides
+                        {
+                            match create_http_client(new_config.user_agent.as_deref(), new_config.ip_mode, &new_config.dns_overrides) {
+                                Ok(client) => http_client = client,
+                                Err(e) => {
+                                    warn!("Sync: {:#}. Keeping the previous user-agent.", e);
+                                    let _ = ui_tx.send(SyncEvent::Error(format!("{:#}", e)));
+                                }
+                            }
+                        }
+                        
+                        if let LocalTorrentState::Active { id } = state.local
+                            && let Err(e) = update_paused_files(&current_config, &new_config, &api, id).await
+                        {
+                            error!("Sync: Failed to update paused files: {:#}", e);
+                            let _ = ui_tx.send(SyncEvent::Error(format!("Failed to pause or resume file: {:#}", e)));
+                        }
+                        
+                        let snooze_changed = current_config.updates_snoozed_until != new_config.updates_snoozed_until;
+                        
                         // Update the current config
-                        current_config = new_config;
+                        current_config = *new_config;
                         
-                        // If URL changed, we might want to trigger a download and compare
-                        if url_changed {
-                            println!("Sync: Torrent URL changed, will trigger a comparison on next periodic check.");
-                            let _ = ui_tx.send(SyncEvent::Error("Configuration updated. URL changes will be checked on next refresh.".to_string()));
-                        } else if path_changed {
-                            println!("Sync: Download path changed to {}", current_config.download_path.display());
-                            let _ = ui_tx.send(SyncEvent::Error("Configuration updated. Download path changed.".to_string()));
-                        } else {
-                            println!("Sync: Configuration updated but no relevant changes detected.");
-                            let _ = ui_tx.send(SyncEvent::Error("Configuration updated.".to_string()));
+                        // Show a new snooze, or check straight away when it was cancelled
+                        if snooze_changed && snooze_watch.check(&current_config, true, &ui_tx) == SnoozeState::Ended {
+                            last_update_check = None;
                         }
+                        
+                        // The torrent goes to the new folder: add it again there, checking what's already in it
+                        if path_changed && let LocalTorrentState::Active { .. } = state.local {
+                            info!("Sync: Download path changed to {}, adding the torrent there", current_config.download_path.display());
+                            match foreign_owner(&current_config) {
+                                Some(owner) => refuse_foreign_folder(&ui_tx, owner, SyncCommand::FixMissingFiles),
+                                None => {
+                                    fix_missing_files(&current_config, &mut state, &api, &ui_tx).await;
+                                }
+                            }
+                        }
+                        
+                        // A new URL may serve a different torrent; check it now rather than at the next periodic check
+                        if url_changed && let Some(cmd_tx) = own_cmd_tx.upgrade() {
+                            info!("Sync: Torrent URL changed, checking it now");
+                            let _ = cmd_tx.send(SyncCommand::CheckForUpdates);
+                        }
+                        
+                        info!("Sync: Configuration updated, changed: {:?}", changed);
+                        let _ = ui_tx.send(SyncEvent::ConfigApplied { changed });
                     }
+                    SyncCommand::ReloadConfig => {
+                        info!("Sync: Config reload requested");
+                        // Applied like a save from the UI, which follows the ConfigReloaded event
+                        let reloaded = match get_config_path() {
+                            Ok(path) => reload_config(&FileConfigStore::new(path), &current_config, true, &ui_tx),
+                            Err(e) => {
+                                warn!("Sync: Config not reloaded: {:#}", e);
+                                None
+                            }
+                        };
+                        if let Some(new_config) = reloaded
+                            && let Some(cmd_tx) = own_cmd_tx.upgrade()
+                        {
+                            let _ = cmd_tx.send(SyncCommand::UpdateConfig(Box::new(new_config)));
+                        }
+                    },
                     SyncCommand::VerifyFolder => {
-                        println!("Sync: Folder verification requested");
+                        info!("Sync: Folder verification requested");
                         verify_folder_contents(&current_config, &mut state, &api, &ui_tx).await;
                     },
+                    SyncCommand::VerifySubset(files) => {
+                        info!("Sync: Spot check of {} files requested", files.len());
+                        // Reading the pieces can take a while; other commands carry on meanwhile
+                        let download_path = current_config.download_path.clone();
+                        let tx = ui_tx.clone();
+                        tokio::spawn(async move {
+                            let result = tokio::task::spawn_blocking(move || {
+                                let bytes = std::fs::read(get_cached_torrent_path()?)
+                                    .context("No downloaded torrent to check against")?;
+                                spot_check(&bytes, &download_path, &files)
+                            })
+                            .await
+                            .map_err(anyhow::Error::from)
+                            .and_then(|result| result);
+                            match &result {
+                                Ok(report) => info!(
+                                    "Sync: Spot check done, {} pieces of {} files checked, {} bad files",
+                                    report.pieces_checked,
+                                    report.files_checked,
+                                    report.bad_files.len()
+                                ),
+                                Err(e) => error!("Sync: Spot check failed: {:#}", e),
+                            }
+                            send_sync_event(&tx, SyncEvent::SubsetVerified(result.map_err(|e| format!("{:#}", e))));
+                        });
+                    },
+                    SyncCommand::PrioritizeFile(path) => {
+                        info!("Sync: Download {} first requested", path);
+                        let LocalTorrentState::Active { id } = state.local else {
+                            let _ = ui_tx.send(SyncEvent::Error("No torrent is loaded".to_string()));
+                            continue;
+                        };
+                        if let Err(e) = priority_file.prioritize(&api, id, &path, &ui_tx) {
+                            warn!("Sync: Can't download {} first: {:#}", path, e);
+                            let _ = ui_tx.send(SyncEvent::Error(format!("{:#}", e)));
+                        }
+                    },
                     SyncCommand::FixMissingFiles => {
-                        println!("Sync: Fix missing files requested");
+                        info!("Sync: Fix missing files requested");
                         fix_missing_files(&current_config, &mut state, &api, &ui_tx).await;
                     },
                     SyncCommand::DeleteFiles(files_to_delete) => {
-                        println!("Sync: Deletion requested for {} files", files_to_delete.len());
+                        info!("Sync: Deletion requested for {} files", files_to_delete.len());
+                        if current_config.safe_mode {
+                            info!("Sync: Safe mode is on, refusing to delete files");
+                            let _ = ui_tx.send(SyncEvent::Error("Safe mode is on: ModSync never deletes files".to_string()));
+                            continue;
+                        }
+                        if let Some(owner) = foreign_owner(&current_config) {
+                            refuse_foreign_folder(&ui_tx, owner, SyncCommand::DeleteFiles(files_to_delete));
+                            continue;
+                        }
                         delete_files(&files_to_delete, &ui_tx).await;
                     },
                     SyncCommand::ApplyUpdate(torrent_content) => {
-                        println!("Sync: Apply remote update requested ({} bytes)", torrent_content.len());
+                        info!("Sync: Apply remote update requested ({} bytes)", torrent_content.len());
                         
-                        match apply_remote_update(&current_config, &mut state, &api, &ui_tx, torrent_content).await {
-                            true => {
+                        // Don't take on new downloads while the volume is nearly full
+                        if state.low_disk_space {
+                            info!("Sync: Refusing to apply update while low on disk space");
+                            let _ = ui_tx.send(SyncEvent::Error("Not enough free disk space to apply update".to_string()));
+                            send_sync_status_event(&ui_tx, SyncStatus::LowDiskSpace);
+                            continue;
+                        }
+                        
+                        if let Some(owner) = foreign_owner(&current_config) {
+                            refuse_foreign_folder(&ui_tx, owner, SyncCommand::ApplyUpdate(torrent_content));
+                            continue;
+                        }
+                        
+                        // A new sync starts; the folder is no longer complete
+                        state.completed = None;
+                        clear_marker(&current_config);
+                        priority_file.clear();
+                        
+                        let new_info_hash = info_hash_of(&torrent_content);
+                        let token = begin_cancellable(&cancel_slot);
+                        let applied = tokio::select! {
+                            applied = apply_remote_update(&current_config, &mut state, &api, &ui_tx, torrent_content) => Some(applied),
+                            _ = token.cancelled() => None,
+                        };
+                        match applied {
+                            Some(true) => error_breaker.record_success(),
+                            Some(false) => note_failure(&mut error_breaker, &current_config, "applying the update failed", &ui_tx),
+                            None => {}
+                        }
+                        match applied {
+                            None => {
+                                info!("Sync: Update cancelled");
+                                clean_up_cancelled_add(&mut state, &api, &ui_tx, new_info_hash.as_deref()).await;
+                            },
+                            Some(true) if state.staged.is_some() => {
+                                info!("Sync: Update is downloading into the staging folder");
+                            },
+                            Some(true) => {
                                 state.remote = RemoteTorrentState::Checked; // Update state on success
                                 
                                 // Verification logic after successful update
                                 if let LocalTorrentState::Active { id } = state.local {
-                                    println!("Sync: Checking for extra files after update");
+                                    info!("Sync: Checking for extra files after update");
                                     send_sync_status_event(&ui_tx, SyncStatus::CheckingLocal);
                                     // ... (rest of verification logic remains the same)
                                     match api.api_torrent_details(id.into()) {
                                         Ok(details) => {
-                                            let expected_files = get_expected_files_from_details(&details);
-                                            match find_extra_files(&current_config.download_path, &expected_files) {
+                                            // Paused files are deselected but still belong to the torrent
+                                            let expected_files = get_all_files_from_details(&details);
+                                            match find_extra_files(&current_config.download_path, &expected_files, &current_config.ignore_patterns) {
                                                 Ok(extra_files) => {
-                                                    println!("Sync: Found {} extra files after update", extra_files.len());
-                                                    if let Err(e) = ui_tx.send(SyncEvent::ExtraFilesFound(extra_files)) {
-                                                        eprintln!("Sync: Failed to send extra files list to UI: {}", e);
+                                                    info!("Sync: Found {} extra files after update", extra_files.len());
+                                                    if current_config.safe_mode {
+                                                        info!("Sync: Safe mode is on, not offering to delete them");
+                                                    } else if let Err(e) = ui_tx.send(SyncEvent::ExtraFilesFound(extra_files)) {
+                                                        error!("Sync: Failed to send extra files list to UI: {}", e);
                                                     }
                                                     send_sync_status_event(&ui_tx, SyncStatus::Idle);
                                                 },
                                                 Err(e) => {
                                                     let err_msg = format!("Failed to find extra files after update: {}", e);
-                                                    eprintln!("Sync: {}", err_msg);
+                                                    warn!("Sync: {}", err_msg);
                                                     let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
                                                     send_sync_status_event(&ui_tx, SyncStatus::Error(err_msg));
                                                 }
@@ -127,7 +389,7 @@ pub async fn run_sync_manager(
                                         },
                                         Err(e) => {
                                             let err_msg = format!("Failed to get torrent details after update: {}", e);
-                                            eprintln!("Sync: {}", err_msg);
+                                            warn!("Sync: {}", err_msg);
                                             let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
                                             send_sync_status_event(&ui_tx, SyncStatus::Error(err_msg));
                                         }
@@ -136,7 +398,7 @@ pub async fn run_sync_manager(
                                      send_sync_status_event(&ui_tx, SyncStatus::Idle); // No active torrent to verify against
                                 }
                             },
-                            false => {
+                            Some(false) => {
                                 // If update failed, set remote state back to Unknown
                                 // Maybe also set local state to NotLoaded if appropriate?
                                 state.remote = RemoteTorrentState::Unknown;
@@ -144,10 +406,165 @@ pub async fn run_sync_manager(
                             }
                         }
                     },
-                    SyncCommand::DownloadAndCompare(url) => {
-                        println!("Sync: Force download and compare requested for URL: {}", url);
-                        current_config.torrent_url = url.clone(); // Update config internally
-                        direct_download_and_compare(&current_config, &mut state, &api, &ui_tx, &http_client).await;
+                    SyncCommand::ScheduleUpdate(torrent_content) => {
+                        update_grace.schedule(&current_config, torrent_content, own_cmd_tx.clone(), &ui_tx);
+                    },
+                    SyncCommand::CancelScheduledUpdate => {
+                        if !update_grace.cancel(&ui_tx) {
+                            info!("Sync: No update is counting down");
+                        }
+                    },
+                    command @ (SyncCommand::DownloadAndCompare(_) | SyncCommand::CheckForUpdates) => {
+                        if let SyncCommand::DownloadAndCompare(url) = command {
+                            info!("Sync: Force download and compare requested for URL: {}", url);
+                            current_config.torrent_url = url; // Update config internally
+                        } else {
+                            info!("Sync: Update check requested");
+                        }
+                        let token = begin_cancellable(&cancel_slot);
+                        tokio::select! {
+                            _ = direct_download_and_compare(&current_config, &mut state, &api, &ui_tx, &http_client) => {},
+                            _ = token.cancelled() => {
+                                info!("Sync: Remote check cancelled");
+                                send_sync_status_event(&ui_tx, SyncStatus::Idle);
+                            }
+                        }
+                    },
+                    SyncCommand::CancelSync => {
+                        // Anything running has already been interrupted; also stop an unfinished download
+                        fallback_watch.cancel();
+                        if let LocalTorrentState::Active { id } = state.local
+                            && let Ok(stats) = api.api_stats_v1(id.into())
+                            && stats.live.is_some()
+                            && !stats.finished
+                        {
+                            match api.api_torrent_action_pause(id.into()).await {
+                                Ok(_) => info!("Sync: Paused torrent {} on cancel", id),
+                                Err(e) => error!("Sync: Failed to pause torrent {} on cancel: {}", id, e),
+                            }
+                        }
+                        let _ = ui_tx.send(SyncEvent::Error("Sync cancelled".to_string()));
+                        send_sync_status_event(&ui_tx, SyncStatus::Idle);
+                    },
+                    SyncCommand::ConfirmSeeding(id) => {
+                        info!("Sync: Seeding confirmed for torrent {}", id);
+                        let cached = get_cached_torrent_path().ok().and_then(|path| std::fs::read(path).ok());
+                        if cached.is_some_and(|bytes| pending_notice(&current_config, &bytes).is_some()) {
+                            info!("Sync: Torrent {} starts once its notice is accepted", id);
+                            continue;
+                        }
+                        match api.api_torrent_action_start(id.into()).await {
+                            Ok(_) => refresh_managed_torrent_status_event(&api, &ui_tx, id),
+                            Err(e) => error!("Sync: Failed to start torrent {}: {}", id, e),
+                        }
+                    },
+                    SyncCommand::AcknowledgeNotice(id) => {
+                        let Ok(details) = api.api_torrent_details(id.into()) else {
+                            continue;
+                        };
+                        acknowledge_notice(&details.info_hash);
+                        let cached = get_cached_torrent_path().ok().and_then(|path| std::fs::read(path).ok());
+                        if cached.is_some_and(|bytes| needs_seeding_confirmation(&current_config, &bytes)) {
+                            info!("Sync: Torrent {} starts once seeding is confirmed", id);
+                            continue;
+                        }
+                        match api.api_torrent_action_start(id.into()).await {
+                            Ok(_) => refresh_managed_torrent_status_event(&api, &ui_tx, id),
+                            Err(e) => error!("Sync: Failed to start torrent {}: {}", id, e),
+                        }
+                    },
+                    SyncCommand::TestConfig(config_to_test) => {
+                        info!("Sync: Testing configuration for {}", config_to_test.torrent_url);
+                        // Runs on its own so a slow server doesn't hold up other commands
+                        let client = http_client.clone();
+                        let tx = ui_tx.clone();
+                        tokio::spawn(async move {
+                            let checks = test_config(&config_to_test, &client).await;
+                            send_sync_event(&tx, SyncEvent::ConfigTestResult { checks });
+                        });
+                    },
+                    SyncCommand::VerifyAgainstTorrent { torrent, folder } => {
+                        info!("Sync: Offline verify of {} against {} requested", folder.display(), torrent.display());
+                        let tx = ui_tx.clone();
+                        tokio::spawn(async move {
+                            let result = tokio::task::spawn_blocking(move || verify_offline(&torrent, &folder))
+                                .await
+                                .map_err(anyhow::Error::from)
+                                .and_then(|result| result)
+                                .map_err(|e| format!("{:#}", e));
+                            match &result {
+                                Ok(report) => info!(
+                                    "Sync: Offline verify done, {} files, {} missing, {} changed",
+                                    report.files,
+                                    report.missing.len(),
+                                    report.mismatched.len()
+                                ),
+                                Err(e) => error!("Sync: Offline verify failed: {}", e),
+                            }
+                            send_sync_event(&tx, SyncEvent::OfflineVerified(result));
+                        });
+                    },
+                    SyncCommand::TestTrackers => {
+                        info!("Sync: Testing trackers");
+                        let config = current_config.clone();
+                        let client = http_client.clone();
+                        let tx = ui_tx.clone();
+                        tokio::spawn(async move {
+                            let result = check_trackers(&config, &client).await.map_err(|e| format!("{:#}", e));
+                            if let Err(e) = &result {
+                                error!("Sync: Tracker test failed: {}", e);
+                            }
+                            send_sync_event(&tx, SyncEvent::TrackerTestResult(result));
+                        });
+                    },
+                    SyncCommand::CheckPort => {
+                        let config = current_config.clone();
+                        let client = http_client.clone();
+                        let port = api.session().tcp_listen_port();
+                        let tx = ui_tx.clone();
+                        tokio::spawn(async move {
+                            let result = check_port(&config, &client, port).await.map_err(|e| format!("{:#}", e));
+                            info!("Sync: Port check for {:?}: {:?}", port, result);
+                            send_sync_event(&tx, SyncEvent::PortCheckResult(result));
+                        });
+                    },
+                    SyncCommand::CheckUrl(url) => {
+                        let client = http_client.clone();
+                        let tx = ui_tx.clone();
+                        tokio::spawn(async move {
+                            let result = check_torrent_url(&url, &client).await;
+                            info!("Sync: URL check for {}: {:?}", url, result);
+                            send_sync_event(&tx, SyncEvent::UrlCheckResult { url, result });
+                        });
+                    },
+                    SyncCommand::RetryAfterFailures => {
+                        info!("Sync: Retry requested, re-enabling automatic retries");
+                        error_breaker.reset();
+                        send_sync_status_event(&ui_tx, SyncStatus::Idle);
+                        check_torrent_error(&current_config, &state, &mut error_breaker, &api, &ui_tx).await;
+                        last_update_check = None; // Check for updates on the next tick
+                    },
+                    SyncCommand::ResumeDownloads => {
+                        info!("Sync: Resume after automatic stop requested");
+                        resume_downloads(&mut state, &mut auto_stop, &api, &ui_tx).await;
+                    },
+                    SyncCommand::ClaimFolder => {
+                        let info_hash = match state.local {
+                            LocalTorrentState::Active { id } => api.api_torrent_details(id.into()).ok().map(|d| d.info_hash),
+                            LocalTorrentState::NotLoaded => None,
+                        };
+                        let owner = FolderOwner { torrent_url: current_config.torrent_url.clone(), info_hash };
+                        match write_owner(&current_config.download_path, &owner) {
+                            Ok(()) => info!("Sync: Claimed {} for {}", current_config.download_path.display(), owner.torrent_url),
+                            Err(e) => {
+                                warn!("Sync: {}", e);
+                                let _ = ui_tx.send(SyncEvent::Error(format!("Failed to claim download folder: {}", e)));
+                            }
+                        }
+                    },
+                    SyncCommand::Shutdown => {
+                        info!("Sync: Shutdown requested, stopping manager.");
+                        return Ok(());
                     },
                     // No need for a catch-all since all variants are explicitly handled
                 }
@@ -158,6 +575,14 @@ pub async fn run_sync_manager(
                 if let LocalTorrentState::Active { id } = state.local {
                     refresh_managed_torrent_status_event(&api, &ui_tx, id);
 
+                    // The snooze replaces the active status, but not checking or errors
+                    let shows_active = api.api_stats_v1(id.into())
+                        .is_ok_and(|stats| matches!(stats.state, TorrentStatsState::Live | TorrentStatsState::Paused));
+                    let snooze = snooze_watch.check(&current_config, shows_active, &ui_tx);
+                    if snooze == SnoozeState::Ended {
+                        last_update_check = None; // Run the check the snooze held back
+                    }
+
                     // Every 10 minutes, also check for remote updates
                     let now = Instant::now();
                     let should_check = match last_update_check {
@@ -165,13 +590,195 @@ pub async fn run_sync_manager(
                         None => true
                     };
 
-                    if should_check {
+                    // A publisher's folder is the source; the server's copy is older until uploaded
+                    if should_check && snooze != SnoozeState::Snoozed && !error_breaker.is_tripped() && publish_path(&current_config).is_none() {
                         last_update_check = Some(now);
-                        println!("Sync: Periodic remote check triggered");
-                        direct_download_and_compare(&current_config, &mut state, &api, &ui_tx, &http_client).await;
+                        info!("Sync: Periodic remote check triggered");
+                        let token = begin_cancellable(&cancel_slot);
+                        tokio::select! {
+                            checked = direct_download_and_compare(&current_config, &mut state, &api, &ui_tx, &http_client) => {
+                                match checked {
+                                    Ok(()) => {
+                                        error_breaker.record_success();
+                                        failure_streak.record_success();
+                                    }
+                                    Err(e) => {
+                                        note_failure(&mut error_breaker, &current_config, "checking for updates failed", &ui_tx);
+                                        note_check_failure(&mut failure_streak, &current_config, &http_client, &e.message, e.kind);
+                                    }
+                                }
+                            },
+                            _ = token.cancelled() => info!("Sync: Periodic remote check cancelled"),
+                        }
                     }
                 }
+                
+                // Restart an errored torrent, or hold the error once retries are disabled
+                check_torrent_error(&current_config, &state, &mut error_breaker, &api, &ui_tx).await;
+                
+                // Swap in a staged update once it has finished downloading
+                check_staged_update(&current_config, &mut state, &api, &ui_tx).await;
+                
+                // Enforce the download time budget / stop time
+                check_auto_stop(&current_config, &mut state, &mut auto_stop, &api, &ui_tx).await;
+                
+                // Copy finished files to their route rule destinations
+                check_routes(&current_config, &mut state, &api, &ui_tx).await;
+                
+                // Fetch over HTTP when the swarm has had no peers for a while
+                check_http_fallback(&current_config, &mut state, &mut fallback_watch, &api, &http_client, &ui_tx, || begin_cancellable(&cancel_slot)).await;
+                
+                // Unpack finished archives that match extract_archives
+                check_extractions(&current_config, &mut state, &api, &ui_tx).await;
+                
+                // Give finished files the mode and owner a game server needs
+                check_file_modes(&current_config, &mut state, &api, &ui_tx).await;
+                
+                // Remember finished files, so later edits are recognised by the overwrite policy
+                check_synced_record(&current_config, &mut state, &api);
+                
+                // Lift the upload limit once enough of the download is done
+                check_upload_ramp(&current_config, &mut state, &api, &ui_tx).await;
+                
+                // Tell launchers and scripts the torrent is complete
+                check_completion(&current_config, &mut state, &api, &ui_tx).await;
+
+                // Stop uploading a finished torrent when seeding is off
+                check_seeding(&current_config, &mut state, &api, &ui_tx).await;
+                
+                // Pause or resume depending on free space on the download volume
+                check_free_space(&current_config, &mut state, &api, &ui_tx).await;
+                
+                // Stop seeding once the whole session has uploaded enough
+                check_seed_goal(&current_config, &mut state, &api, &ui_tx).await;
+                
+                // Publisher mode: rebuild and seed the torrent once edits settle
+                check_publish(&current_config, &mut state, &mut publish_watcher, &api, &ui_tx).await;
+                
+                // Report memory use, warning when the machine runs low
+                check_memory(&current_config, &mut memory_watcher, &ui_tx);
+                
+                // Stop all traffic while the bound interface (VPN) is down
+                check_bind_interface(&current_config, &mut state, &mut interface_watcher, &api, &ui_tx).await;
+                
+                // Stay out of the way while the game is running
+                check_game_process(&current_config, &mut state, &mut process_watcher, &api, &ui_tx).await;
+                
+                // Don't drain a laptop's battery syncing
+                check_battery(&current_config, &mut state, &mut battery_watch, &api, &ui_tx).await;
+                
+                // Pick up edits made to config.toml outside ModSync
+                if current_config.reload_config_on_change
+                    && let Ok(config_path) = get_config_path()
+                    && config_watch.changed(&config_path)
+                    && let Some(new_config) = reload_config(&FileConfigStore::new(config_path), &current_config, false, &ui_tx)
+                    && let Some(cmd_tx) = own_cmd_tx.upgrade()
+                {
+                    let _ = cmd_tx.send(SyncCommand::UpdateConfig(Box::new(new_config)));
+                }
+                
+                // Catches a new torrent or passkey, however it got there
+                maybe_check_tracker_passkey(&current_config, &state, &mut passkey_check, &api, &http_client, &ui_tx);
+                
+                // Runs after the checks above so a pause they made releases it straight away
+                check_sleep_inhibit(&current_config, &state, &mut sleep_inhibitor, &api);
+            }
+        }
+    }
+} 
+
+/// Tell the UI where an embedded server ended up, warning when it had to move to
+/// another port. A server that couldn't start leaves the rest of the app running.
+fn report_server(
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+    name: &str,
+    configured: &str,
+    bound: Result<SocketAddr>,
+    path: &str,
+) {
+    match bound {
+        Ok(bound) => {
+            if let Some(warning) = moved_port_warning(name, configured, bound) {
+                warn!("Sync: {}", warning);
+                let _ = ui_tx.send(SyncEvent::Warning(warning));
             }
+            let url = format!("http://{}{}", bound, path);
+            let _ = ui_tx.send(SyncEvent::ServerListening { name: name.to_string(), url });
+        }
+        Err(e) => {
+            warn!("Sync: {:#}", e);
+            let _ = ui_tx.send(SyncEvent::Warning(format!("{:#}. {} is off; everything else keeps working.", e, name)));
         }
     }
-} 
\ No newline at end of file
+}
+
+/// Tell the UI a command was refused because the download folder belongs to another torrent
+fn refuse_foreign_folder(ui_tx: &mpsc::UnboundedSender<SyncEvent>, owner: FolderOwner, retry: SyncCommand) {
+    info!("Sync: Download folder belongs to {}, asking for confirmation", owner.torrent_url);
+    let _ = ui_tx.send(SyncEvent::FolderOwnerMismatch { owner, retry: Box::new(retry) });
+    send_sync_status_event(ui_tx, SyncStatus::Idle);
+}
+
+/// Forward commands to the manager, cancelling the current operation's token when
+/// CancelSync arrives (the command itself is still forwarded). Also returns a sender
+/// for commands the manager queues for itself.
+fn spawn_command_forwarder(
+    mut rx: mpsc::UnboundedReceiver<SyncCommand>,
+    cancel_slot: Arc<Mutex<CancellationToken>>,
+) -> (mpsc::WeakUnboundedSender<SyncCommand>, mpsc::UnboundedReceiver<SyncCommand>) {
+    let (tx, forwarded_rx) = mpsc::unbounded_channel();
+    let own_tx = tx.downgrade();
+    tokio::spawn(async move {
+        while let Some(cmd) = rx.recv().await {
+            if matches!(cmd, SyncCommand::CancelSync)
+                && let Ok(token) = cancel_slot.lock()
+            {
+                token.cancel();
+            }
+            if tx.send(cmd).is_err() {
+                break; // Manager has stopped
+            }
+        }
+    });
+    (own_tx, forwarded_rx)
+}
+
+/// Give the operation about to start a fresh token for CancelSync to trigger
+fn begin_cancellable(cancel_slot: &Mutex<CancellationToken>) -> CancellationToken {
+    let token = CancellationToken::new();
+    if let Ok(mut slot) = cancel_slot.lock() {
+        *slot = token.clone();
+    }
+    token
+}
+
+/// After an update was cancelled part way, drop the new torrent if it made it into the
+/// session and make sure our state only points at a torrent that still exists
+async fn clean_up_cancelled_add(
+    state: &mut SyncState,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+    new_info_hash: Option<&str>,
+) {
+    let previous_id = match state.local {
+        LocalTorrentState::Active { id } => Some(id),
+        LocalTorrentState::NotLoaded => None,
+    };
+    if let Some(new_id) = new_info_hash.and_then(|hash| find_torrent_by_info_hash(api, hash))
+        && Some(new_id) != previous_id
+    {
+        match api.api_torrent_action_forget(new_id.into()).await {
+            Ok(_) => info!("Sync: Removed partially added torrent {}", new_id),
+            Err(e) => error!("Sync: Failed to remove partially added torrent {}: {}", new_id, e),
+        }
+    }
+    // The previous torrent may already have been forgotten before the cancel
+    if let Some(id) = previous_id
+        && api.api_stats_v1(id.into()).is_err()
+    {
+        info!("Sync: Previous torrent {} is gone after cancel", id);
+        state.local = LocalTorrentState::NotLoaded;
+        let _ = ui_tx.send(SyncEvent::ManagedTorrentUpdate(None));
+    }
+    state.remote = RemoteTorrentState::Unknown;
+}
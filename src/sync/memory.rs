@@ -0,0 +1,120 @@
+// src/sync/memory.rs
+
+//! Memory use of ModSync and the machine, for small seed boxes. librqbit doesn't report
+//! how full its write buffer (`max_memory_cache_mb`) is, so the stats show the buffer's
+//! cap next to the memory the whole process uses.
+
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::config::AppConfig;
+
+use super::messages::SyncEvent;
+
+/// Below this much available memory (in MB) the machine counts as low on memory
+const LOW_MEMORY_MB: u64 = 256;
+
+/// Extra headroom (in MB) before the warning can be given again
+const LOW_MEMORY_HYSTERESIS_MB: u64 = 128;
+
+/// One reading of memory use
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+    pub process_bytes: u64, // Resident memory of ModSync, librqbit included
+    pub available_bytes: u64, // Memory the system can still hand out
+    pub total_bytes: u64,
+    pub write_buffer_mb: Option<usize>, // The configured cap, None when writes aren't buffered
+}
+
+/// Keeps the system handle between polls so refreshes stay cheap
+pub struct MemoryWatcher {
+    system: System,
+    pid: Option<Pid>,
+    low: bool,
+}
+
+impl Default for MemoryWatcher {
+    fn default() -> Self {
+        Self { system: System::new(), pid: sysinfo::get_current_pid().ok(), low: false }
+    }
+}
+
+impl MemoryWatcher {
+    fn sample(&mut self, config: &AppConfig) -> MemoryUsage {
+        self.system.refresh_memory();
+        let process_bytes = match self.pid {
+            Some(pid) => {
+                self.system.refresh_processes_specifics(
+                    ProcessesToUpdate::Some(&[pid]),
+                    true,
+                    ProcessRefreshKind::nothing().with_memory(),
+                );
+                self.system.process(pid).map_or(0, |process| process.memory())
+            }
+            None => 0,
+        };
+        MemoryUsage {
+            process_bytes,
+            available_bytes: self.system.available_memory(),
+            total_bytes: self.system.total_memory(),
+            write_buffer_mb: write_buffer_mb(config),
+        }
+    }
+}
+
+/// The write buffer librqbit gets for `config`; zero counts as off
+pub fn write_buffer_mb(config: &AppConfig) -> Option<usize> {
+    config.max_memory_cache_mb.filter(|mb| *mb > 0)
+}
+
+/// Whether the machine counts as low on memory, with hysteresis so the warning doesn't flap
+pub fn is_low_on_memory(available_mb: u64, currently_low: bool) -> bool {
+    if currently_low {
+        available_mb < LOW_MEMORY_MB + LOW_MEMORY_HYSTERESIS_MB
+    } else {
+        available_mb < LOW_MEMORY_MB
+    }
+}
+
+/// Report memory use to the UI, warning once when the system runs low
+pub fn check_memory(config: &AppConfig, watcher: &mut MemoryWatcher, ui_tx: &mpsc::UnboundedSender<SyncEvent>) {
+    let usage = watcher.sample(config);
+    // Some platforms can't tell; don't warn on a zero reading
+    if usage.total_bytes > 0 {
+        let available_mb = usage.available_bytes / (1024 * 1024);
+        let low = is_low_on_memory(available_mb, watcher.low);
+        if low && !watcher.low {
+            let message = format!(
+                "System is low on memory: {} MB available, ModSync uses {} MB",
+                available_mb,
+                usage.process_bytes / (1024 * 1024)
+            );
+            warn!("Sync: {}", message);
+            let _ = ui_tx.send(SyncEvent::Warning(message));
+        }
+        watcher.low = low;
+    }
+    let _ = ui_tx.send(SyncEvent::MemoryUsage(usage));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_low_memory_hysteresis() {
+        assert!(!is_low_on_memory(LOW_MEMORY_MB, false));
+        assert!(is_low_on_memory(LOW_MEMORY_MB - 1, false));
+        assert!(is_low_on_memory(LOW_MEMORY_MB + 1, true), "stays low until there's headroom");
+        assert!(!is_low_on_memory(LOW_MEMORY_MB + LOW_MEMORY_HYSTERESIS_MB, true));
+    }
+
+    #[test]
+    fn test_zero_write_buffer_is_off() {
+        let mut config = AppConfig { max_memory_cache_mb: Some(0), ..AppConfig::default() };
+        assert_eq!(write_buffer_mb(&config), None);
+        config.max_memory_cache_mb = Some(64);
+        assert_eq!(write_buffer_mb(&config), Some(64));
+    }
+}
@@ -8,26 +8,103 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use std::collections::HashSet;
 
+use super::memory::MemoryUsage;
+use super::owner::FolderOwner;
+use super::preflight::{ConfigCheck, UrlCheck};
+use super::publish::{FolderChanges, PublishedTorrent};
+use super::extract::ExtractSummary;
+use super::file_mode::FileModeSummary;
+use super::http_fallback::FallbackSummary;
+use super::routing::RouteSummary;
+use super::offline_verify::OfflineVerifyReport;
+use super::spot_check::SpotCheckReport;
+use super::summary::UpdateSummary;
+use super::port_check::PortCheck;
+use super::tracker_check::TrackerReport;
+
 /// Commands that can be sent from the UI to the Sync Manager
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum SyncCommand {
     /// Update the configuration used by the sync manager
-    UpdateConfig(AppConfig),
+    UpdateConfig(Box<AppConfig>),
+    
+    /// Read config.toml again and apply what changed, answered with ConfigReloaded
+    ReloadConfig,
     
     /// Verify local folder contents against the torrent manifest
     VerifyFolder,
     
+    /// Hash check only the pieces of these files ('/'-separated, relative to the download folder)
+    VerifySubset(Vec<PathBuf>),
+    
+    /// Hash check a folder against a `.torrent` file, offline, answered with OfflineVerified
+    VerifyAgainstTorrent { torrent: PathBuf, folder: PathBuf },
+    
     /// Delete specified files that are not part of the torrent
     DeleteFiles(Vec<PathBuf>),
     
     /// Apply a remote torrent update with the provided torrent data
     ApplyUpdate(Vec<u8>),
     
+    /// Apply a remote update once `update_grace_seconds` pass, unless cancelled first
+    ScheduleUpdate(Vec<u8>),
+    
+    /// Stop the update counting down after ScheduleUpdate
+    CancelScheduledUpdate,
+    
     /// Download and compare a torrent from the specified URL
     DownloadAndCompare(String),
     
+    /// Download this file ('/'-separated, relative to the download folder) before the rest
+    PrioritizeFile(String),
+    
+    /// Check the configured torrent URL for an update now
+    CheckForUpdates,
+    
     /// Fix missing files by restarting the torrent
     FixMissingFiles,
+    
+    /// The user agreed to seed this public torrent; start it
+    ConfirmSeeding(usize),
+    
+    /// The user accepted this torrent's notice; remember it and start the torrent
+    AcknowledgeNotice(usize),
+    
+    /// Interrupt the running sync step (remote check or update) and pause an unfinished download
+    CancelSync,
+    
+    /// Resume downloading after an automatic stop
+    ResumeDownloads,
+    
+    /// Re-enable automatic retries after repeated failures, and try again now
+    RetryAfterFailures,
+    
+    /// Take ownership of the download folder for the current torrent URL
+    ClaimFolder,
+    
+    /// Dry-check an unsaved config (torrent URL and download path) without syncing
+    TestConfig(Box<AppConfig>),
+    
+    /// Quickly check that the URL serves a torrent, answered with UrlCheckResult
+    CheckUrl(String),
+    
+    /// Ask each tracker of the cached torrent for its swarm counts, answered with TrackerTestResult
+    TestTrackers,
+    
+    /// Test the listen port through `port_check_url`, answered with PortCheckResult
+    CheckPort,
+    
+    /// Stop the sync manager loop so the session can be shut down cleanly
+    Shutdown,
+}
+
+/// A SyncEvent with its place in the session's event order and when it was sent,
+/// so consumers can order events and notice ones they missed
+#[derive(Debug, Clone)]
+pub struct TimestampedEvent {
+    pub seq: u64, // Starts at 1 and goes up by one per event
+    pub at: chrono::DateTime<chrono::Local>,
+    pub event: SyncEvent,
 }
 
 /// Events that can be sent from the Sync Manager to the UI
@@ -40,18 +117,121 @@ pub enum SyncEvent {
     /// Notification that a torrent was added with the given ID
     TorrentAdded(usize),
     
+    /// The synced torrent finished downloading, `bytes` in all
+    SyncComplete { info_hash: String, bytes: u64 },
+    
     /// Error message from the sync manager
     Error(String),
     
+    /// Something the user should know about that doesn't stop syncing
+    Warning(String),
+    
+    /// Writing to this folder was refused by the OS; the UI suggests another location
+    PermissionDenied(PathBuf),
+    
     /// Update about the overall sync status
     StatusUpdate(SyncStatus),
     
     /// Notification about extra files found in the download directory
     ExtraFilesFound(Vec<PathBuf>),
     
+    /// Partial downloads of the previous torrent removed after an update, relative
+    /// to the download path
+    PartialFilesCleaned(Vec<PathBuf>),
+    
     /// Notification that a remote update is available
     RemoteUpdateFound(Vec<u8>),
     
+    /// A scheduled update applies at this time unless cancelled
+    UpdateCountdown(chrono::DateTime<chrono::Local>),
+    
+    /// The countdown is over: the update is being applied, or was cancelled
+    UpdateCountdownEnded { applied: bool },
+    
+    /// What the pending update would change; sent just before RemoteUpdateFound
+    /// when `confirm_before_update` is set
+    RemoteUpdateSummary(UpdateSummary),
+
+    /// Release notes from `changelog_url` not shown before; sent just before
+    /// RemoteUpdateSummary
+    Changelog(String),
+    
     /// Notification about missing files found in the download directory
     MissingFilesFound(HashSet<PathBuf>),
+    
+    /// A public torrent was added paused and needs the user's OK before seeding
+    SeedingConfirmationNeeded(usize),
+    
+    /// A torrent was added paused until the user accepts the notice in its comment,
+    /// see `require_notice_acknowledgement`
+    NoticeAcknowledgementNeeded { id: usize, notice: String },
+    
+    /// The download folder belongs to another torrent; `retry` is the command
+    /// that was refused and can be re-sent after a ClaimFolder
+    FolderOwnerMismatch { owner: FolderOwner, retry: Box<SyncCommand> },
+    
+    /// Final URL the torrent URL resolved to after redirects
+    RemoteResolved(String),
+    
+    /// Where route rules placed the finished torrent's files
+    FilesRouted(RouteSummary),
+    
+    /// What unpacking the finished torrent's archives did, see `extract_archives`
+    ArchivesExtracted(ExtractSummary),
+    
+    /// What setting `post_download_chmod` and `post_download_chown` on the finished torrent's files did
+    FileModesApplied(FileModeSummary),
+    
+    /// This file is now downloaded before the rest of the torrent
+    FilePrioritized(String),
+    
+    /// Files the HTTP fallback fetched because the swarm had no peers
+    HttpFallbackFetched(FallbackSummary),
+    
+    /// Result of a VerifySubset, or why it couldn't run
+    SubsetVerified(Result<SpotCheckReport, String>),
+    
+    /// config.toml was read again; `changed` lists the keys that differ from the running config
+    ConfigReloaded { config: Box<AppConfig>, changed: Vec<String> },
+    
+    /// The sync manager runs on an updated config now; `changed` lists the keys that differ
+    /// from the one before
+    ConfigApplied { changed: Vec<String> },
+    
+    /// Result of a VerifyAgainstTorrent, or why it couldn't run
+    OfflineVerified(Result<OfflineVerifyReport, String>),
+    
+    /// Version the folder is on after a sync (`expected_version` / `version_url`), None while it isn't synced
+    VersionInfo { version: Option<String> },
+    
+    /// An embedded HTTP server (metrics or control API) is up at `url`
+    ServerListening { name: String, url: String },
+    
+    /// Publisher mode saw edits in the download folder since the last published torrent
+    FolderChanged(FolderChanges),
+    
+    /// Publisher mode wrote a new torrent of the download folder and is seeding it
+    TorrentPublished(PublishedTorrent),
+    
+    /// Locally edited files left out of the download by `overwrite_policy`, '/'-separated relative paths
+    FilesPreserved(Vec<String>),
+    
+    /// The session reached `global_seed_goal` and seeding was paused. `ratio` is None
+    /// when nothing was downloaded this session.
+    GlobalSeedGoalMet { uploaded_bytes: u64, ratio: Option<f64> },
+    
+    /// Memory use of ModSync and the system, sent every tick
+    MemoryUsage(MemoryUsage),
+    
+    /// Result of a TestTrackers, one report per tracker, or why it couldn't run
+    TrackerTestResult(Result<Vec<TrackerReport>, String>),
+    
+    /// Result of a CheckPort, or why it couldn't run
+    PortCheckResult(Result<PortCheck, String>),
+    
+    /// Results of a TestConfig, one entry per check
+    ConfigTestResult { checks: Vec<ConfigCheck> },
+    
+    /// Result of a CheckUrl for `url`
+    UrlCheckResult { url: String, result: UrlCheck },
 } 
\ No newline at end of file
@@ -0,0 +1,209 @@
+// src/sync/metrics.rs
+
+//! Prometheus metrics for the managed torrent at `/metrics`, for graphing seed boxes
+//! next to other services. Built from the same stats snapshot the main window shows,
+//! formatted by hand to keep dependencies down. Only served when `metrics_addr` is set.
+
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use crate::ui::utils::{SyncStatus, progress_fraction};
+
+use super::http_reply::build_reply;
+use super::listen::bind_with_fallback;
+use super::messages::SyncEvent;
+
+/// Every status label, so the status gauge always has the full set of series
+const STATUS_LABELS: [&str; 13] = [
+    "idle",
+    "checking_remote",
+    "updating_torrent",
+    "checking_local",
+    "checking",
+    "local_active",
+    "remote_changed",
+    "low_disk_space",
+    "paused_for_game",
+    "bind_interface_down",
+    "stopped",
+    "staging",
+    "error",
+];
+
+/// Latest figures seen on the event channel
+#[derive(Debug, Default)]
+pub struct MetricsSnapshot {
+    pub stats: Option<Arc<librqbit::TorrentStats>>,
+    pub status: Option<SyncStatus>,
+    pub update_applies_at: Option<chrono::DateTime<chrono::Local>>, // Update counting down under update_grace_seconds
+    pub version: Option<String>, // Version the folder is on after a sync, see expected_version
+    pub last_synced_at: Option<chrono::DateTime<chrono::Local>>, // When the torrent was last reported complete
+}
+
+impl MetricsSnapshot {
+    fn observe(&mut self, event: &SyncEvent) {
+        match event {
+            SyncEvent::ManagedTorrentUpdate(torrent) => self.stats = torrent.as_ref().map(|(_, stats)| stats.clone()),
+            SyncEvent::StatusUpdate(status) => self.status = Some(status.clone()),
+            SyncEvent::Error(message) => self.status = Some(SyncStatus::Error(message.clone())),
+            SyncEvent::UpdateCountdown(applies_at) => self.update_applies_at = Some(*applies_at),
+            SyncEvent::UpdateCountdownEnded { .. } => self.update_applies_at = None,
+            SyncEvent::VersionInfo { version } => self.version = version.clone(),
+            SyncEvent::SyncComplete { .. } => self.last_synced_at = Some(chrono::Local::now()),
+            _ => {}
+        }
+    }
+}
+
+/// Label value of `status` in `modsync_sync_status`
+pub fn status_label(status: &SyncStatus) -> &'static str {
+    match status {
+        SyncStatus::Idle => "idle",
+        SyncStatus::CheckingRemote => "checking_remote",
+        SyncStatus::UpdatingTorrent => "updating_torrent",
+        SyncStatus::CheckingLocal => "checking_local",
+        SyncStatus::Checking { .. } => "checking",
+        SyncStatus::Verifying { .. } => "verifying",
+        SyncStatus::LocalActive => "local_active",
+        SyncStatus::RemoteChanged => "remote_changed",
+        SyncStatus::LowDiskSpace => "low_disk_space",
+        SyncStatus::PausedForGame => "paused_for_game",
+        SyncStatus::PausedLowBattery => "paused_low_battery",
+        SyncStatus::BindInterfaceDown => "bind_interface_down",
+        SyncStatus::SeedGoalMet => "seed_goal_met",
+        SyncStatus::Stopped => "stopped",
+        SyncStatus::Staging { .. } => "staging",
+        SyncStatus::Allocating { .. } => "allocating",
+        SyncStatus::FetchingHttp { .. } => "fetching_http",
+        SyncStatus::Extracting { .. } => "extracting",
+        SyncStatus::Snoozed { .. } => "snoozed",
+        SyncStatus::Error(_) => "error",
+    }
+}
+
+fn write_metric(out: &mut String, name: &str, kind: &str, help: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+/// The snapshot in the Prometheus text exposition format
+pub fn render_metrics(snapshot: &MetricsSnapshot) -> String {
+    let stats = snapshot.stats.as_deref();
+    let downloaded = stats.and_then(|s| s.live.as_ref()).map_or(0, |live| live.snapshot.fetched_bytes);
+    let uploaded = stats.map_or(0, |s| s.uploaded_bytes);
+    let progress = stats.map_or(0.0, |s| progress_fraction(s.progress_bytes, s.total_bytes, s.finished));
+    let peers = stats.and_then(|s| s.live.as_ref()).map_or(0, |live| live.snapshot.peer_stats.live);
+
+    let mut out = String::new();
+    write_metric(&mut out, "modsync_download_bytes_total", "counter", "Bytes downloaded from peers since the torrent was loaded.", downloaded);
+    write_metric(&mut out, "modsync_upload_bytes_total", "counter", "Bytes uploaded to peers.", uploaded);
+    write_metric(&mut out, "modsync_progress_ratio", "gauge", "Share of the torrent downloaded, from 0 to 1.", progress);
+    write_metric(&mut out, "modsync_peers", "gauge", "Connected peers.", peers);
+
+    let current = snapshot.status.as_ref().map(status_label);
+    let _ = writeln!(out, "# HELP modsync_sync_status Current sync status, 1 for the active status.");
+    let _ = writeln!(out, "# TYPE modsync_sync_status gauge");
+    for label in STATUS_LABELS {
+        let _ = writeln!(out, "modsync_sync_status{{status=\"{}\"}} {}", label, u8::from(current == Some(label)));
+    }
+    out
+}
+
+/// Pass events from the returned sender on to `ui_tx`, recording them in `snapshot` on the way
+pub fn tap_events(
+    ui_tx: mpsc::UnboundedSender<SyncEvent>,
+    snapshot: Arc<Mutex<MetricsSnapshot>>,
+) -> mpsc::UnboundedSender<SyncEvent> {
+    let (tap_tx, mut tap_rx) = mpsc::unbounded_channel::<SyncEvent>();
+    tokio::spawn(async move {
+        while let Some(event) = tap_rx.recv().await {
+            if let Ok(mut snapshot) = snapshot.lock() {
+                snapshot.observe(&event);
+            }
+            if ui_tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+    tap_tx
+}
+
+async fn handle_connection(mut stream: TcpStream, snapshot: &Mutex<MetricsSnapshot>, compression: bool) -> Result<()> {
+    let mut request = [0u8; 1024];
+    let read = stream.read(&mut request).await?;
+    let request = String::from_utf8_lossy(&request[..read]);
+    let mut parts = request.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            let body = match snapshot.lock() {
+                Ok(snapshot) => render_metrics(&snapshot),
+                Err(_) => String::new(),
+            };
+            build_reply("200 OK", "text/plain; version=0.0.4", body.as_bytes(), &request, compression)
+        }
+        _ => build_reply("404 Not Found", "text/plain", b"", &request, compression),
+    };
+    stream.write_all(&response).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// Bind `addr` and serve `/metrics` from `snapshot` in the background
+pub async fn serve_metrics(
+    addr: &str,
+    fallback_ports: u16,
+    compression: bool,
+    snapshot: Arc<Mutex<MetricsSnapshot>>,
+) -> Result<SocketAddr> {
+    let listener = bind_with_fallback(addr, fallback_ports, "Metrics").await?;
+    let bound = listener.local_addr()?;
+    info!("Sync: Serving Prometheus metrics at http://{}/metrics", bound);
+    tokio::spawn(async move {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                continue;
+            };
+            let snapshot = snapshot.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(stream, &snapshot, compression).await {
+                    error!("Sync: Metrics request failed: {}", e);
+                }
+            });
+        }
+    });
+    Ok(bound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_without_torrent() {
+        let snapshot = MetricsSnapshot { stats: None, status: Some(SyncStatus::Error("disk".to_string())), ..Default::default() };
+        let text = render_metrics(&snapshot);
+        assert!(text.contains("# TYPE modsync_upload_bytes_total counter\nmodsync_upload_bytes_total 0\n"));
+        assert!(text.contains("modsync_progress_ratio 0\n"));
+        assert!(text.contains("modsync_sync_status{status=\"error\"} 1\n"));
+        assert!(text.contains("modsync_sync_status{status=\"idle\"} 0\n"));
+        assert_eq!(text.matches("modsync_sync_status{").count(), STATUS_LABELS.len());
+    }
+
+    #[tokio::test]
+    async fn test_tap_records_and_forwards() {
+        let snapshot = Arc::new(Mutex::new(MetricsSnapshot::default()));
+        let (ui_tx, mut ui_rx) = mpsc::unbounded_channel();
+        let tap = tap_events(ui_tx, snapshot.clone());
+        tap.send(SyncEvent::StatusUpdate(SyncStatus::LocalActive)).unwrap();
+        assert!(matches!(ui_rx.recv().await, Some(SyncEvent::StatusUpdate(SyncStatus::LocalActive))));
+        assert_eq!(snapshot.lock().unwrap().status, Some(SyncStatus::LocalActive));
+    }
+}
@@ -2,20 +2,76 @@
 
 // Declare sub-modules for sync logic
 pub mod http;
+pub mod http_fallback;
+pub mod http_reply;
 pub mod cleaner;
+pub mod autostop;
+pub mod awake;
+pub mod bencode;
+pub mod battery;
+pub mod breaker;
+pub mod case_collisions;
+pub mod completion;
+pub mod changelog;
+pub mod control;
+pub mod cpu_limit;
+pub mod disk;
+pub mod extract;
+pub mod failure_webhook;
+pub mod file_list;
+pub mod file_mode;
+pub mod file_pause;
+pub mod game;
+pub mod grace;
+pub mod ignore;
+pub mod interface;
+pub mod listen;
+pub mod memory;
+pub mod metrics;
+pub mod network_share;
+pub mod notice;
+pub mod offline_verify;
+pub mod overwrite;
+pub mod owner;
+pub mod passkey;
+pub mod permissions;
+pub mod port_check;
+pub mod preallocate;
+pub mod preflight;
+pub mod priority;
+pub mod private;
+pub mod publish;
+pub mod routing;
+pub mod safe_mode;
+pub mod seed_goal;
+pub mod seeding;
+pub mod snooze;
+pub mod staging;
+pub mod startup;
+pub mod spot_check;
+pub mod stamp;
+pub mod summary;
 pub mod torrent;
+pub mod tracker_check;
+pub mod upload_ramp;
+pub mod version;
+pub mod verify_complete;
 
 // New modular structure
 pub mod messages;
 pub mod types;
 pub mod utils;
 pub mod local;
+pub mod reload;
 pub mod remote;
 pub mod manager;
+pub mod session;
+pub mod progress;
 
 // Re-export key types and functions for external use
-pub use messages::{SyncCommand, SyncEvent};
+pub use messages::{SyncCommand, SyncEvent, TimestampedEvent};
 pub use manager::run_sync_manager;
+pub use progress::{NoopSink, ProgressSink, SyncError, run_sync_manager_with_sink};
 
 // Potentially re-export key functions or structs if needed elsewhere
 pub use torrent::manage_torrent_task; 
\ No newline at end of file
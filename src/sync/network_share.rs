@@ -0,0 +1,194 @@
+// src/sync/network_share.rs
+
+//! `network_share_mode`: mod folders on a NAS behave differently from local disks. Every
+//! read and write waits on the network, and a share that drops for a moment fails
+//! operations that work again seconds later. When the download folder is on SMB, NFS or
+//! another network filesystem, torrents are checked one at a time, peers get longer to
+//! answer while writes wait on the share, and a dropped share is retried instead of being
+//! counted as a disk error.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::config::{AppConfig, NetworkShareMode};
+
+/// Peer read/write timeout on a share, unless `peer_read_write_timeout_secs` is set.
+/// Writes wait on the share, and a peer handler blocked on one must not time out.
+pub const SHARE_PEER_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Filesystem types of network mounts, as Linux and macOS name them
+const NETWORK_FILESYSTEMS: &[&str] = &[
+    "nfs", "nfs4", "cifs", "smb3", "smbfs", "afpfs", "webdav", "davfs", "9p", "afs", "ncpfs", "ceph", "glusterfs", "sshfs", "rclone",
+];
+
+/// Whether a filesystem type names a network filesystem. FUSE types count by the
+/// driver after `fuse.`, e.g. `fuse.sshfs`.
+pub fn is_network_filesystem(kind: &str) -> bool {
+    let kind = kind.strip_prefix("fuse.").unwrap_or(kind).to_ascii_lowercase();
+    NETWORK_FILESYSTEMS.contains(&kind.as_str())
+}
+
+/// (mount point, filesystem type) of each line of `/proc/self/mounts`, e.g.
+/// "nas:/export/mods /mnt/mods nfs4 rw,relatime 0 0". Spaces in mount points are `\040`.
+pub fn parse_proc_mounts(text: &str) -> Vec<(PathBuf, String)> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (_, mount_point, kind) = (fields.next()?, fields.next()?, fields.next()?);
+            Some((PathBuf::from(mount_point.replace("\\040", " ")), kind.to_string()))
+        })
+        .collect()
+}
+
+/// (mount point, filesystem type) of each line of macOS's `mount`, e.g.
+/// "//user@nas/mods on /Volumes/mods (smbfs, nodev, nosuid, mounted by user)"
+pub fn parse_mount_output(text: &str) -> Vec<(PathBuf, String)> {
+    text.lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once(" on ")?;
+            let (mount_point, options) = rest.rsplit_once(" (")?;
+            let kind = options.split([',', ')']).next()?.trim();
+            Some((PathBuf::from(mount_point), kind.to_string()))
+        })
+        .collect()
+}
+
+/// Filesystem type of the mount `path` is on: the longest mount point it's under
+pub fn filesystem_at<'a>(mounts: &'a [(PathBuf, String)], path: &Path) -> Option<&'a str> {
+    mounts
+        .iter()
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.components().count())
+        .map(|(_, kind)| kind.as_str())
+}
+
+/// The kind of network share `path` is on, None for a local disk or when it can't be told.
+/// A folder that doesn't exist yet is judged by the nearest one above it that does.
+pub fn detect_network_share(path: &Path) -> Option<String> {
+    let existing = path.ancestors().find_map(|ancestor| ancestor.canonicalize().ok())?;
+    platform::share_kind(&existing)
+}
+
+/// The kind of network share the download folder is on under `network_share_mode`,
+/// None when it's treated as a local disk
+pub fn network_share(config: &AppConfig) -> Option<String> {
+    if config.download_path.as_os_str().is_empty() {
+        return None;
+    }
+    match config.network_share_mode {
+        NetworkShareMode::Off => None,
+        NetworkShareMode::On => Some(detect_network_share(&config.download_path).unwrap_or_else(|| "network share".to_string())),
+        NetworkShareMode::Auto => detect_network_share(&config.download_path),
+    }
+}
+
+/// Whether an error message is a network share going away rather than a disk failing.
+/// librqbit often only keeps the io::Error's message, so this checks text. I/O errors
+/// and timeouts only count `on_share`, where they're the share's doing; on a local disk
+/// they're real disk errors.
+pub fn share_disconnect_in_text(text: &str, on_share: bool) -> bool {
+    let lower = text.to_ascii_lowercase();
+    let network = [
+        "stale file handle",
+        "stale nfs file handle",
+        "host is down",
+        "no route to host",
+        "network is unreachable",
+        "network path was not found",
+        "network name is no longer available",
+        "network name cannot be found",
+        "unexpected network error",
+        "network location cannot be reached",
+    ]
+    .iter()
+    .any(|phrase| lower.contains(phrase));
+    network || (on_share && ["input/output error", "timed out"].iter().any(|phrase| lower.contains(phrase)))
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{filesystem_at, is_network_filesystem, parse_proc_mounts};
+    use std::path::Path;
+
+    pub fn share_kind(path: &Path) -> Option<String> {
+        let mounts = parse_proc_mounts(&std::fs::read_to_string("/proc/self/mounts").ok()?);
+        filesystem_at(&mounts, path).filter(|kind| is_network_filesystem(kind)).map(str::to_string)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{filesystem_at, is_network_filesystem, parse_mount_output};
+    use std::path::Path;
+
+    pub fn share_kind(path: &Path) -> Option<String> {
+        let output = std::process::Command::new("mount").output().ok()?;
+        let mounts = parse_mount_output(&String::from_utf8_lossy(&output.stdout));
+        filesystem_at(&mounts, path).filter(|kind| is_network_filesystem(kind)).map(str::to_string)
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::{Component, Path, Prefix};
+    use windows_sys::Win32::Storage::FileSystem::GetDriveTypeW;
+
+    /// GetDriveTypeW's answer for a mapped network drive
+    const DRIVE_REMOTE: u32 = 4;
+
+    pub fn share_kind(path: &Path) -> Option<String> {
+        let Some(Component::Prefix(prefix)) = path.components().next() else {
+            return None;
+        };
+        match prefix.kind() {
+            Prefix::UNC(..) | Prefix::VerbatimUNC(..) => Some("smb".to_string()),
+            Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => {
+                let root: Vec<u16> = std::ffi::OsStr::new(&format!("{}:\\", letter as char)).encode_wide().chain([0]).collect();
+                // SAFETY: plain Win32 call on a null-terminated string we own
+                (unsafe { GetDriveTypeW(root.as_ptr()) } == DRIVE_REMOTE).then(|| "network drive".to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod platform {
+    use std::path::Path;
+
+    pub fn share_kind(_path: &Path) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mount_tables_and_disconnect_errors() {
+        let proc_mounts = "/dev/nvme0n1p2 / ext4 rw,relatime 0 0\n\
+                           nas:/export/mods /mnt/arma\\040mods nfs4 rw,relatime 0 0\n\
+                           //nas/share /mnt/smb cifs rw 0 0\n";
+        let mounts = parse_proc_mounts(proc_mounts);
+        assert_eq!(filesystem_at(&mounts, Path::new("/mnt/arma mods/@ace")), Some("nfs4"));
+        assert_eq!(filesystem_at(&mounts, Path::new("/home/me/mods")), Some("ext4"));
+        // Only whole path components count, /mnt/smbx isn't under /mnt/smb
+        assert_eq!(filesystem_at(&mounts, Path::new("/mnt/smbx")), Some("ext4"));
+
+        let mac = "/dev/disk3s1s1 on / (apfs, sealed, local, read-only, journaled)\n\
+                   //me@nas/mods on /Volumes/mods (smbfs, nodev, nosuid, mounted by me)\n";
+        assert_eq!(filesystem_at(&parse_mount_output(mac), Path::new("/Volumes/mods/@cba")), Some("smbfs"));
+
+        assert!(is_network_filesystem("nfs4") && is_network_filesystem("fuse.sshfs") && is_network_filesystem("CIFS"));
+        assert!(!is_network_filesystem("ext4") && !is_network_filesystem("fuse.ntfs-3g"));
+
+        assert!(share_disconnect_in_text("error writing piece: Stale file handle (os error 116)", false));
+        assert!(share_disconnect_in_text("The specified network name is no longer available. (os error 64)", false));
+        // An I/O error is the share's doing only on a share
+        assert!(share_disconnect_in_text("Input/output error (os error 5)", true));
+        assert!(!share_disconnect_in_text("Input/output error (os error 5)", false));
+        assert!(!share_disconnect_in_text("No space left on device (os error 28)", true));
+    }
+}
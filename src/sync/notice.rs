@@ -0,0 +1,73 @@
+// src/sync/notice.rs
+
+//! `require_notice_acknowledgement`: some mod sets carry a licence or notice in the
+//! torrent's comment that players must accept before downloading. With the setting on, a
+//! torrent with a comment is added paused and the comment is shown until the user
+//! accepts it. The acceptance is remembered per info hash, so a new version of the
+//! torrent, whose notice may have changed, asks again.
+
+use tracing::{info, warn};
+
+use crate::config::{AppConfig, FileStateStore, StateStore};
+
+use super::torrent::info_hash_of;
+
+/// State record holding the info hash whose notice was accepted
+const NOTICE_KEY: &str = "notice";
+
+/// Longest notice shown; a comment beyond this is cut off
+const MAX_NOTICE_CHARS: usize = 20_000;
+
+/// The comment of a .torrent file, None when it has none or it's blank
+pub fn torrent_comment(torrent_content: &[u8]) -> Option<String> {
+    let meta = librqbit::torrent_from_bytes::<librqbit::ByteBuf>(torrent_content).ok()?;
+    let comment = String::from_utf8_lossy(meta.comment?.as_ref()).trim().to_string();
+    (!comment.is_empty()).then(|| comment.chars().take(MAX_NOTICE_CHARS).collect())
+}
+
+/// Whether the notice of the torrent `info_hash` was accepted, per `store`
+pub fn is_acknowledged(store: &dyn StateStore, info_hash: &str) -> bool {
+    store.read(NOTICE_KEY).ok().flatten().is_some_and(|recorded| recorded.trim().eq_ignore_ascii_case(info_hash))
+}
+
+/// The notice the user still has to accept before this torrent may download, if any
+pub fn pending_notice(config: &AppConfig, torrent_content: &[u8]) -> Option<String> {
+    if !config.require_notice_acknowledgement {
+        return None;
+    }
+    let notice = torrent_comment(torrent_content)?;
+    let info_hash = info_hash_of(torrent_content)?;
+    let acknowledged = FileStateStore::session().is_ok_and(|store| is_acknowledged(&store, &info_hash));
+    (!acknowledged).then_some(notice)
+}
+
+/// Remember that the notice of the torrent `info_hash` was accepted
+pub fn acknowledge_notice(info_hash: &str) {
+    let written = FileStateStore::session().and_then(|store| store.write(NOTICE_KEY, info_hash));
+    match written {
+        Ok(()) => info!("Sync: Notice of torrent {} accepted", info_hash),
+        Err(e) => warn!("Sync: Failed to record the accepted notice: {:#}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comment_and_acknowledgement() -> anyhow::Result<()> {
+        let with_comment = b"d7:comment24:  Licence: no reuploads 4:infod6:lengthi10e4:name3:abc12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+        assert_eq!(torrent_comment(with_comment).as_deref(), Some("Licence: no reuploads"));
+        let blank = b"d7:comment2:  4:infod6:lengthi10e4:name3:abc12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaaee";
+        assert_eq!(torrent_comment(blank), None);
+        assert_eq!(pending_notice(&AppConfig::default(), with_comment), None, "off unless required");
+
+        let store = crate::config::MemoryStateStore::default();
+        let info_hash = info_hash_of(with_comment).unwrap();
+        assert!(!is_acknowledged(&store, &info_hash));
+        store.write(NOTICE_KEY, &info_hash)?;
+        assert!(is_acknowledged(&store, &info_hash));
+        assert!(!is_acknowledged(&store, &"0".repeat(40)), "a new version asks again");
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+// src/sync/offline_verify.rs
+
+//! "Verify against torrent file": checks a folder against any `.torrent`, for auditing
+//! an archived mod set. Everything is read from disk and hashed here, the same way as the
+//! spot check, so the torrent never reaches librqbit and nothing touches the network.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use super::spot_check::spot_check;
+
+/// How a folder compares to a torrent file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OfflineVerifyReport {
+    pub torrent_name: String,
+    pub files: usize,
+    pub pieces_checked: usize,
+    pub total_bytes: u64,
+    pub missing: Vec<PathBuf>,    // Not in the folder, or the wrong size
+    pub mismatched: Vec<PathBuf>, // There, but with a piece that doesn't match the torrent
+}
+
+impl OfflineVerifyReport {
+    /// Whether the folder holds every file of the torrent, intact
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+/// Hash-check `folder` against the torrent file at `torrent_path`. Paths in the report are
+/// '/'-separated and relative to `folder`.
+pub fn verify_offline(torrent_path: &Path, folder: &Path) -> Result<OfflineVerifyReport> {
+    let bytes = std::fs::read(torrent_path).with_context(|| format!("Failed to read {}", torrent_path.display()))?;
+    let torrent = librqbit::torrent_from_bytes::<librqbit::ByteBuf>(&bytes)
+        .with_context(|| format!("{} isn't a valid torrent file", torrent_path.display()))?;
+
+    let mut files = Vec::new();
+    for file in torrent.info.iter_file_details()? {
+        files.push((PathBuf::from(file.filename.to_pathbuf()?.to_string_lossy().replace('\\', "/")), file.len));
+    }
+    let selected: Vec<PathBuf> = files.iter().map(|(path, _)| path.clone()).collect();
+    let checked = spot_check(&bytes, folder, &selected)?;
+
+    let mut report = OfflineVerifyReport {
+        torrent_name: torrent.info.name.as_ref().map(|name| String::from_utf8_lossy(name.as_ref()).into_owned()).unwrap_or_default(),
+        files: files.len(),
+        pieces_checked: checked.pieces_checked,
+        total_bytes: files.iter().map(|(_, len)| len).sum(),
+        ..Default::default()
+    };
+    for bad in checked.bad_files {
+        let len = files.iter().find(|(path, _)| *path == bad).map(|(_, len)| *len);
+        let on_disk = std::fs::metadata(folder.join(&bad)).ok().map(|meta| meta.len());
+        if on_disk.is_none() || on_disk != len {
+            report.missing.push(bad);
+        } else {
+            report.mismatched.push(bad);
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha1::{Digest, Sha1};
+    use std::fs;
+
+    #[test]
+    fn test_missing_and_changed_files_are_told_apart() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let folder = dir.path().join("archive");
+        fs::create_dir_all(folder.join("@mod"))?;
+        fs::write(folder.join("@mod/a.pbo"), [1u8; 8])?;
+        fs::write(folder.join("@mod/b.pbo"), [2u8; 8])?;
+
+        // Two 8-byte files in 8-byte pieces
+        let mut torrent = b"d4:infod5:filesl".to_vec();
+        for name in ["a.pbo", "b.pbo"] {
+            torrent.extend_from_slice(format!("d6:lengthi8e4:pathl4:@mod5:{}ee", name).as_bytes());
+        }
+        torrent.extend_from_slice(b"e4:name4:mods12:piece lengthi8e6:pieces40:");
+        torrent.extend_from_slice(&Sha1::digest([1u8; 8]));
+        torrent.extend_from_slice(&Sha1::digest([2u8; 8]));
+        torrent.extend_from_slice(b"ee");
+        let torrent_path = dir.path().join("mods.torrent");
+        fs::write(&torrent_path, &torrent)?;
+
+        let report = verify_offline(&torrent_path, &folder)?;
+        assert!(report.is_complete(), "{:?}", report);
+        assert_eq!((report.torrent_name.as_str(), report.files, report.total_bytes), ("mods", 2, 16));
+
+        fs::write(folder.join("@mod/a.pbo"), [9u8; 8])?;
+        fs::remove_file(folder.join("@mod/b.pbo"))?;
+        let report = verify_offline(&torrent_path, &folder)?;
+        assert_eq!(report.mismatched, [PathBuf::from("@mod/a.pbo")]);
+        assert_eq!(report.missing, [PathBuf::from("@mod/b.pbo")]);
+
+        assert!(verify_offline(&dir.path().join("none.torrent"), &folder).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+// src/sync/overwrite.rs
+
+//! `overwrite_policy`: whether librqbit may overwrite local files the user edited.
+//! When a torrent finishes, the size and modification time of each of its files is
+//! recorded in a marker, with a fingerprint of the torrent's version of the file. A file
+//! whose size or time no longer match was edited since; the policy decides whether the
+//! next add or update leaves it out of the download so librqbit never touches it.
+
+use anyhow::{Context, Result};
+use librqbit::AddTorrentOptions;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use tracing::{info, warn};
+
+use crate::config::{AppConfig, OverwritePolicy};
+
+use super::types::{LocalTorrentState, SyncState};
+
+/// Hidden file in the download folder recording each file as ModSync last left it
+pub const SYNCED_MARKER_FILE: &str = ".modsync_synced";
+
+/// One file as it was when the torrent last finished
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct SyncedFile {
+    size: u64,
+    modified_ms: i64, // Milliseconds since the Unix epoch
+    fingerprint: String, // The torrent's version of the file, see `fingerprints`
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct SyncedMarker {
+    files: BTreeMap<String, SyncedFile>, // '/'-separated, relative to the download folder
+}
+
+fn read_marker(download_path: &Path) -> SyncedMarker {
+    let marker_path = download_path.join(SYNCED_MARKER_FILE);
+    let Ok(contents) = std::fs::read_to_string(&marker_path) else {
+        return SyncedMarker::default();
+    };
+    toml::from_str(&contents).unwrap_or_else(|e| {
+        // Without a record no file counts as edited, as before the setting existed
+        warn!("Sync: Ignoring unreadable sync record {}: {}", marker_path.display(), e);
+        SyncedMarker::default()
+    })
+}
+
+fn write_marker(download_path: &Path, marker: &SyncedMarker) -> Result<()> {
+    let marker_path = download_path.join(SYNCED_MARKER_FILE);
+    let contents = toml::to_string(marker).context("Failed to serialize sync record")?;
+    std::fs::write(&marker_path, contents)
+        .with_context(|| format!("Failed to write sync record: {}", marker_path.display()))
+}
+
+/// Size and modification time of a local file, None if it's missing
+fn local_stamp(path: &Path) -> Option<(u64, i64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((metadata.len(), modified.as_millis() as i64))
+}
+
+/// A file of the torrent with a fingerprint of its content
+struct TorrentFile {
+    index: usize,
+    path: String,
+    fingerprint: String,
+}
+
+/// Files of the torrent in torrent order, padding files left out, and its creation date
+/// in milliseconds. The fingerprint is the BEP 47 file hash when the torrent has one,
+/// otherwise a hash of the piece hashes covering the file, so it changes whenever the
+/// file's content does (or a neighbour sharing one of its pieces).
+fn fingerprints(torrent_bytes: &[u8]) -> Result<(Vec<TorrentFile>, Option<i64>)> {
+    let torrent = librqbit::torrent_from_bytes::<librqbit::ByteBuf>(torrent_bytes)
+        .context("Failed to parse torrent for the overwrite policy")?;
+    let piece_length = u64::from(torrent.info.piece_length.max(1));
+    let pieces = torrent.info.pieces.as_ref();
+    let mut files = Vec::new();
+    let mut offset = 0u64;
+    for (index, file) in torrent.info.iter_file_details()?.enumerate() {
+        let start = offset;
+        offset += file.len;
+        if file.attrs().padding {
+            continue;
+        }
+        let fingerprint = match (file.sha1, file.len) {
+            (Some(hash), _) => format!("sha1:{}", hash.as_ref().iter().map(|b| format!("{:02x}", b)).collect::<String>()),
+            (None, 0) => "empty".to_string(),
+            (None, len) => {
+                let first = (start / piece_length) as usize;
+                let last = ((start + len - 1) / piece_length) as usize;
+                let covering = pieces.get(first * 20..(last + 1) * 20).unwrap_or_default();
+                let mut hasher = Sha1::new();
+                hasher.update(covering);
+                hasher.update((start % piece_length).to_le_bytes());
+                hasher.update(len.to_le_bytes());
+                format!("pieces:{:x}", hasher.finalize())
+            }
+        };
+        let path = file.filename.to_pathbuf()?.to_string_lossy().replace('\\', "/");
+        files.push(TorrentFile { index, path, fingerprint });
+    }
+    Ok((files, torrent.creation_date.map(|secs| secs as i64 * 1000)))
+}
+
+/// Files of the torrent to leave alone under `policy`: (index, path) of each local file
+/// edited since the last sync that the update either doesn't change (`if_different`) or
+/// changes but the edit is newer than the torrent (`preserve_newer_local`)
+pub fn preserved_files(download_path: &Path, torrent_bytes: &[u8], policy: OverwritePolicy) -> Result<Vec<(usize, String)>> {
+    if policy == OverwritePolicy::Always {
+        return Ok(Vec::new());
+    }
+    let marker = read_marker(download_path);
+    if marker.files.is_empty() {
+        return Ok(Vec::new());
+    }
+    let (files, created_ms) = fingerprints(torrent_bytes)?;
+
+    let mut preserved = Vec::new();
+    for TorrentFile { index, path, fingerprint } in files {
+        let (Some(synced), Some((size, modified_ms))) = (marker.files.get(&path), local_stamp(&download_path.join(&path))) else {
+            continue;
+        };
+        let edited = synced.size != size || synced.modified_ms != modified_ms;
+        if !edited {
+            continue;
+        }
+        let torrent_changed = synced.fingerprint != fingerprint;
+        let keep = match policy {
+            OverwritePolicy::Always => false,
+            OverwritePolicy::IfDifferent => !torrent_changed,
+            // Without a creation date there's nothing to compare with, so the edit wins
+            OverwritePolicy::PreserveNewerLocal => !torrent_changed || created_ms.is_none_or(|created| modified_ms > created),
+        };
+        if keep {
+            preserved.push((index, path));
+        }
+    }
+    Ok(preserved)
+}
+
+/// Leave preserved files out of the selection of a torrent about to be added. Runs after
+/// safe mode and before paused files. Returns the preserved paths for reporting.
+pub fn apply_overwrite_policy(config: &AppConfig, torrent_bytes: &[u8], options: &mut AddTorrentOptions) -> Result<Vec<String>> {
+    let preserved = preserved_files(&config.download_path, torrent_bytes, config.overwrite_policy)?;
+    if preserved.is_empty() {
+        return Ok(Vec::new());
+    }
+    let file_count = librqbit::torrent_from_bytes::<librqbit::ByteBuf>(torrent_bytes)?.info.iter_file_details()?.count();
+    let only_files: Vec<usize> = (0..file_count)
+        .filter(|index| options.only_files.as_ref().is_none_or(|only| only.contains(index)))
+        .filter(|index| !preserved.iter().any(|(preserved, _)| preserved == index))
+        .collect();
+    info!("Sync: Keeping {} locally edited files out of the download", preserved.len());
+    options.only_files = Some(only_files);
+    Ok(preserved.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Record the files of a finished torrent. Files it doesn't download (preserved, paused
+/// or kept by safe mode) keep their earlier record, so an edit stays recognised.
+pub fn record_synced_files(download_path: &Path, torrent_bytes: &[u8], included: &[String]) -> Result<()> {
+    let mut marker = read_marker(download_path);
+    let (files, _) = fingerprints(torrent_bytes)?;
+    let mut recorded = BTreeMap::new();
+    for TorrentFile { path, fingerprint, .. } in files {
+        let entry = if included.contains(&path) {
+            local_stamp(&download_path.join(&path)).map(|(size, modified_ms)| SyncedFile { size, modified_ms, fingerprint })
+        } else {
+            marker.files.remove(&path)
+        };
+        if let Some(entry) = entry {
+            recorded.insert(path, entry);
+        }
+    }
+    marker.files = recorded;
+    write_marker(download_path, &marker)
+}
+
+/// Record the managed torrent's files each time it finishes, while a policy other than
+/// `always` is set
+pub fn check_synced_record(config: &AppConfig, state: &mut SyncState, api: &librqbit::Api) {
+    if config.overwrite_policy == OverwritePolicy::Always {
+        return;
+    }
+    let LocalTorrentState::Active { id } = state.local else {
+        return;
+    };
+    if !api.api_stats_v1(id.into()).is_ok_and(|stats| stats.finished) {
+        state.synced_recorded = None;
+        return;
+    }
+    let Ok(details) = api.api_torrent_details(id.into()) else {
+        return;
+    };
+    if state.synced_recorded.as_ref() == Some(&details.info_hash) {
+        return;
+    }
+    let included: Vec<String> = details
+        .files
+        .iter()
+        .flatten()
+        .filter(|file| file.included)
+        .map(|file| file.components.join("/"))
+        .collect();
+    let torrent = crate::config::get_cached_torrent_path().and_then(|path| std::fs::read(&path).context("Failed to read cached torrent"));
+    match torrent.and_then(|bytes| record_synced_files(&config.download_path, &bytes, &included)) {
+        Ok(()) => info!("Sync: Recorded {} synced files for the overwrite policy", included.len()),
+        Err(e) => warn!("Sync: {:#}", e),
+    }
+    state.synced_recorded = Some(details.info_hash);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use librqbit::CreateTorrentOptions;
+    use std::fs;
+    use std::time::{Duration, SystemTime};
+
+    #[tokio::test]
+    async fn test_edited_files_are_preserved_by_policy() -> Result<()> {
+        let source = tempfile::tempdir()?;
+        fs::write(source.path().join("a_config.hpp"), vec![1u8; 16384])?;
+        fs::write(source.path().join("b_mod.pbo"), vec![2u8; 16384])?;
+        let v1 = librqbit::create_torrent(source.path(), CreateTorrentOptions { piece_length: Some(16384), ..Default::default() }).await?;
+        let v1 = v1.as_bytes()?;
+
+        let download = tempfile::tempdir()?;
+        fs::write(download.path().join("a_config.hpp"), vec![1u8; 16384])?;
+        fs::write(download.path().join("b_mod.pbo"), vec![2u8; 16384])?;
+        record_synced_files(download.path(), &v1, &["a_config.hpp".to_string(), "b_mod.pbo".to_string()])?;
+        assert!(preserved_files(download.path(), &v1, OverwritePolicy::IfDifferent)?.is_empty());
+
+        // The user edits both files
+        let later = SystemTime::now() + Duration::from_secs(5);
+        for name in ["a_config.hpp", "b_mod.pbo"] {
+            fs::write(download.path().join(name), b"edited")?;
+            fs::File::options().write(true).open(download.path().join(name))?.set_modified(later)?;
+        }
+        // The update only changes b_mod.pbo; one piece per file keeps the fingerprints apart
+        fs::write(source.path().join("b_mod.pbo"), vec![3u8; 16384])?;
+        let v2 = librqbit::create_torrent(source.path(), CreateTorrentOptions { piece_length: Some(16384), ..Default::default() }).await?;
+        let v2 = v2.as_bytes()?;
+
+        let names = |preserved: Vec<(usize, String)>| {
+            let mut names: Vec<String> = preserved.into_iter().map(|(_, path)| path).collect();
+            names.sort();
+            names
+        };
+        assert!(preserved_files(download.path(), &v2, OverwritePolicy::Always)?.is_empty());
+        assert_eq!(names(preserved_files(download.path(), &v2, OverwritePolicy::IfDifferent)?), vec!["a_config.hpp"]);
+        assert_eq!(
+            names(preserved_files(download.path(), &v2, OverwritePolicy::PreserveNewerLocal)?),
+            vec!["a_config.hpp", "b_mod.pbo"],
+            "edits made after the torrent was created are kept even when it changes them"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+// src/sync/owner.rs
+
+//! Marker file recording which torrent a download folder belongs to, so that
+//! two configs pointing at the same folder can't silently clobber each other
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::warn;
+
+use crate::config::AppConfig;
+
+/// Hidden file written into the download folder
+pub const OWNER_MARKER_FILE: &str = ".modsync_owner";
+
+/// Contents of the owner marker
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FolderOwner {
+    pub torrent_url: String,
+    pub info_hash: Option<String>,
+}
+
+/// Read the owner marker from `download_path`, or None if the folder is unclaimed
+pub fn read_owner(download_path: &Path) -> Result<Option<FolderOwner>> {
+    let marker_path = download_path.join(OWNER_MARKER_FILE);
+    if !marker_path.exists() {
+        return Ok(None);
+    }
+    let contents = std::fs::read_to_string(&marker_path)
+        .with_context(|| format!("Failed to read owner marker: {}", marker_path.display()))?;
+    let owner = toml::from_str(&contents)
+        .with_context(|| format!("Failed to parse owner marker: {}", marker_path.display()))?;
+    Ok(Some(owner))
+}
+
+/// Claim `download_path` for `owner`, replacing any existing marker
+pub fn write_owner(download_path: &Path, owner: &FolderOwner) -> Result<()> {
+    let marker_path = download_path.join(OWNER_MARKER_FILE);
+    let contents = toml::to_string(owner).context("Failed to serialize owner marker")?;
+    std::fs::write(&marker_path, contents)
+        .with_context(|| format!("Failed to write owner marker: {}", marker_path.display()))
+}
+
+/// Returns the other owner if the download folder has been claimed by a different torrent URL.
+/// An unreadable marker is treated as foreign so we err on the side of asking.
+pub fn foreign_owner(config: &AppConfig) -> Option<FolderOwner> {
+    match read_owner(&config.download_path) {
+        Ok(Some(owner)) if owner.torrent_url != config.torrent_url => Some(owner),
+        Ok(_) => None,
+        Err(e) => {
+            warn!("Sync: {}", e);
+            Some(FolderOwner {
+                torrent_url: "(unreadable owner marker)".to_string(),
+                info_hash: None,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_for(url: &str, path: &Path) -> AppConfig {
+        AppConfig {
+            torrent_url: url.to_string(),
+            download_path: path.to_path_buf(),
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_unclaimed_folder_has_no_foreign_owner() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        assert_eq!(read_owner(dir.path())?, None);
+        assert_eq!(foreign_owner(&config_for("http://a/mods.torrent", dir.path())), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_foreign_owner_detected() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let owner = FolderOwner {
+            torrent_url: "http://a/mods.torrent".to_string(),
+            info_hash: Some("abc123".to_string()),
+        };
+        write_owner(dir.path(), &owner)?;
+        assert_eq!(read_owner(dir.path())?, Some(owner.clone()));
+
+        assert_eq!(foreign_owner(&config_for("http://a/mods.torrent", dir.path())), None);
+        assert_eq!(foreign_owner(&config_for("http://b/other.torrent", dir.path())), Some(owner));
+        Ok(())
+    }
+}
@@ -0,0 +1,255 @@
+// src/sync/passkey.rs
+
+//! Per-user passkeys for private trackers. Admins share one .torrent; each user's
+//! passkey is put into its announce URLs when the torrent is added, and never written
+//! to the cached torrent.
+
+use anyhow::{Context, Result, bail};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::config::{AppConfig, get_cached_torrent_path};
+
+use super::bencode::{dict_entries, encode_string, list_items, parse_string};
+use super::messages::SyncEvent;
+use super::types::{LocalTorrentState, SyncState};
+
+/// Token in an announce URL that is replaced by the configured passkey
+pub const PASSKEY_PLACEHOLDER: &str = "{passkey}";
+
+/// Put `passkey` into an announce URL, percent-encoded: the placeholder is replaced wherever it appears.
+/// Without one, the primary tracker (the torrent's `announce`) gets a `passkey` query
+/// parameter unless it already carries one; other trackers are left alone so the key
+/// isn't sent to public trackers.
+pub fn inject_passkey(url: &str, passkey: &str, is_primary: bool) -> String {
+    if url.contains(PASSKEY_PLACEHOLDER) {
+        return url.replace(PASSKEY_PLACEHOLDER, &percent_encode(passkey.as_bytes()));
+    }
+    let is_http = url.starts_with("http://") || url.starts_with("https://");
+    if !is_primary || !is_http || url.contains(passkey) || url.contains("passkey=") {
+        return url.to_string();
+    }
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!("{}{}passkey={}", url, separator, percent_encode(passkey.as_bytes()))
+}
+
+/// Hide the passkey in a URL before logging or showing it
+pub fn mask_passkey(url: &str, passkey: &str) -> String {
+    let mut masked = url.to_string();
+    for form in [passkey.to_string(), percent_encode(passkey.as_bytes())] {
+        if !form.is_empty() {
+            masked = masked.replace(&form, "***");
+        }
+    }
+    masked
+}
+
+/// The configured passkey, or None when unset or blank
+pub(crate) fn configured_passkey(config: &AppConfig) -> Option<&str> {
+    config.tracker_passkey.as_deref().map(str::trim).filter(|key| !key.is_empty())
+}
+
+/// Torrent bytes to hand to librqbit, with the passkey in the announce URLs. Only the
+/// announce keys are rewritten; the info dict is copied byte for byte, so the info hash
+/// (and so the folder owner, resume and reuse checks) is unchanged.
+pub fn with_tracker_passkey(config: &AppConfig, torrent_bytes: Vec<u8>) -> Result<Vec<u8>> {
+    let Some(passkey) = configured_passkey(config) else {
+        return Ok(torrent_bytes);
+    };
+    rewrite_announce_urls(&torrent_bytes, |url, is_primary| inject_passkey(url, passkey, is_primary))
+}
+
+/// Re-encode the top-level torrent dict with `rewrite` applied to every announce URL,
+/// telling it whether the URL is the primary tracker
+fn rewrite_announce_urls(torrent_bytes: &[u8], rewrite: impl Fn(&str, bool) -> String) -> Result<Vec<u8>> {
+    let entries = dict_entries(torrent_bytes).context("Failed to read torrent for the tracker passkey")?;
+    let primary = match entries.iter().find(|(key, _)| *key == b"announce") {
+        Some((_, value)) => Some(parse_string(value)?.0),
+        None => None,
+    };
+    let rewrite_bytes = |raw: &[u8]| -> Result<Vec<u8>> {
+        let (url, _) = parse_string(raw)?;
+        let is_primary = primary == Some(url);
+        Ok(encode_string(rewrite(&String::from_utf8_lossy(url), is_primary).as_bytes()))
+    };
+
+    let mut output = vec![b'd'];
+    for (key, value) in entries {
+        output.extend(encode_string(key));
+        match key {
+            b"announce" => output.extend(rewrite_bytes(value)?),
+            b"announce-list" => {
+                output.push(b'l');
+                for tier in list_items(value)? {
+                    output.push(b'l');
+                    for url in list_items(tier)? {
+                        output.extend(rewrite_bytes(url)?);
+                    }
+                    output.push(b'e');
+                }
+                output.push(b'e');
+            }
+            _ => output.extend_from_slice(value),
+        }
+    }
+    output.push(b'e');
+    Ok(output)
+}
+
+/// Announce to the passkey-carrying trackers of the cached torrent once, and report a
+/// tracker that turns the passkey down. librqbit only logs tracker failures internally,
+/// so without this a wrong passkey just looks like a torrent with no peers.
+pub async fn check_tracker_passkey(config: AppConfig, http_client: reqwest::Client, ui_tx: mpsc::UnboundedSender<SyncEvent>) {
+    let Some(passkey) = configured_passkey(&config) else { return };
+    let original = match get_cached_torrent_path().and_then(|path| std::fs::read(&path).context("Failed to read cached torrent")) {
+        Ok(bytes) => bytes,
+        Err(_) => return, // Nothing loaded yet
+    };
+    let torrent = match librqbit::torrent_from_bytes::<librqbit::ByteBuf>(&original) {
+        Ok(torrent) => torrent,
+        Err(e) => {
+            warn!("Sync: Cannot check tracker passkey, torrent unreadable: {}", e);
+            return;
+        }
+    };
+
+    let primary = torrent.announce.as_ref().map(|url| url.as_ref().to_vec());
+    let announce_urls: Vec<(String, String)> = torrent
+        .iter_announce()
+        .filter_map(|url| {
+            let is_primary = primary.as_deref() == Some(url.as_ref());
+            let url = std::str::from_utf8(url.as_ref()).ok()?;
+            let injected = inject_passkey(url, passkey, is_primary);
+            (injected != url && injected.starts_with("http")).then(|| (url.to_string(), injected))
+        })
+        .collect();
+    let info_hash = percent_encode(&torrent.info_hash.0);
+
+    for (original_url, url) in announce_urls {
+        match announce_once(&http_client, &url, &info_hash).await {
+            Ok(()) => info!("Sync: Tracker accepted the passkey: {}", mask_passkey(&url, passkey)),
+            Err(e) => {
+                let message = format!("Tracker {} rejected the passkey: {}", original_url, mask_passkey(&format!("{:#}", e), passkey));
+                warn!("Sync: {}", message);
+                let _ = ui_tx.send(SyncEvent::Error(message));
+            }
+        }
+    }
+}
+
+/// Remembers the torrent and passkey last checked, so each pair is announced only once
+#[derive(Default)]
+pub struct PasskeyCheck {
+    last_checked: Option<(String, String)>, // (info hash, passkey)
+}
+
+/// Check the passkey in the background whenever the managed torrent or the passkey changed
+pub fn maybe_check_tracker_passkey(
+    config: &AppConfig,
+    state: &SyncState,
+    check: &mut PasskeyCheck,
+    api: &librqbit::Api,
+    http_client: &reqwest::Client,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+) {
+    let Some(passkey) = configured_passkey(config) else {
+        check.last_checked = None;
+        return;
+    };
+    let LocalTorrentState::Active { id } = state.local else { return };
+    let Ok(details) = api.api_torrent_details(id.into()) else { return };
+    let current = Some((details.info_hash, passkey.to_string()));
+    if check.last_checked != current {
+        check.last_checked = current;
+        tokio::spawn(check_tracker_passkey(config.clone(), http_client.clone(), ui_tx.clone()));
+    }
+}
+
+/// A throwaway 20-byte peer ID for one-off tracker requests
+pub(super) fn probe_peer_id() -> String {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default();
+    format!("-MS0100-{:012}", nanos % 1_000_000_000_000)
+}
+
+/// One `stopped` announce that asks for no peers, failing on an HTTP error or a "failure
+/// reason". A stop only takes the probe's peer ID off the swarm, so the tracker never
+/// lists the user as a seeder it can't reach, nor counts the probe toward their ratio.
+async fn announce_once(http_client: &reqwest::Client, url: &str, info_hash: &str) -> Result<()> {
+    let peer_id = probe_peer_id();
+    let separator = if url.contains('?') { '&' } else { '?' };
+    let announce_url = format!(
+        "{}{}info_hash={}&peer_id={}&port=6881&uploaded=0&downloaded=0&left=0&event=stopped&compact=1&numwant=0",
+        url, separator, info_hash, peer_id
+    );
+
+    let response = http_client.get(&announce_url).send().await.context("Announce failed")?;
+    let status = response.status();
+    let body = response.bytes().await.context("Failed to read announce response")?;
+    if !status.is_success() {
+        bail!("HTTP {}", status);
+    }
+    for (key, value) in dict_entries(&body).context("Unreadable announce response")? {
+        if key == b"failure reason" {
+            let (reason, _) = parse_string(value)?;
+            bail!("{}", String::from_utf8_lossy(reason));
+        }
+    }
+    Ok(())
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters
+pub(crate) fn percent_encode(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inject_passkey() {
+        assert_eq!(inject_passkey("https://t.example/{passkey}/announce", "abc", false), "https://t.example/abc/announce");
+        assert_eq!(inject_passkey("https://t.example/announce", "abc", true), "https://t.example/announce?passkey=abc");
+        assert_eq!(inject_passkey("https://t.example/a.php?x=1", "abc", true), "https://t.example/a.php?x=1&passkey=abc");
+        // Backup and UDP trackers only get it through the placeholder
+        assert_eq!(inject_passkey("https://public.example/announce", "abc", false), "https://public.example/announce");
+        assert_eq!(inject_passkey("udp://t.example:1337", "abc", true), "udp://t.example:1337");
+        assert_eq!(mask_passkey("https://t.example/abc/announce", "abc"), "https://t.example/***/announce");
+        // Both forms encode a key that isn't URL-safe
+        assert_eq!(inject_passkey("https://t.example/{passkey}/announce", "a/b c", false), "https://t.example/a%2Fb%20c/announce");
+        assert_eq!(inject_passkey("https://t.example/announce", "a/b c", true), "https://t.example/announce?passkey=a%2Fb%20c");
+    }
+
+    #[test]
+    fn test_rewrite_keeps_info_dict_and_hash() -> Result<()> {
+        let info = b"d6:lengthi3e4:name1:a12:piece lengthi16384e6:pieces20:aaaaaaaaaaaaaaaaaaaae";
+        let mut torrent = b"d8:announce31:https://t.example/{passkey}/ann13:announce-listll31:https://t.example/{passkey}/ann".to_vec();
+        torrent.extend_from_slice(b"ee4:info");
+        torrent.extend_from_slice(info);
+        torrent.push(b'e');
+        let before = librqbit::torrent_from_bytes::<librqbit::ByteBuf>(&torrent)?.info_hash;
+
+        let config = AppConfig { tracker_passkey: Some("s3cret".to_string()), ..AppConfig::default() };
+        let rewritten = with_tracker_passkey(&config, torrent.clone())?;
+        let parsed = librqbit::torrent_from_bytes::<librqbit::ByteBuf>(&rewritten)?;
+        assert_eq!(parsed.info_hash, before);
+        let urls: Vec<String> = parsed.iter_announce().map(|u| String::from_utf8_lossy(u.as_ref()).into_owned()).collect();
+        assert!(urls.iter().all(|u| u == "https://t.example/s3cret/ann"), "{:?}", urls);
+
+        // The primary tracker also gets it when listed again in announce-list
+        let plain = b"d8:announce21:https://t.example/ann13:announce-listll21:https://t.example/ann21:https://p.example/anneee";
+        let rewritten = String::from_utf8(rewrite_announce_urls(plain, |url, primary| inject_passkey(url, "k", primary))?)?;
+        assert_eq!(rewritten, "d8:announce31:https://t.example/ann?passkey=k13:announce-listll31:https://t.example/ann?passkey=k21:https://p.example/anneee");
+
+        // Without a passkey the bytes pass through untouched
+        assert_eq!(with_tracker_passkey(&AppConfig::default(), torrent.clone())?, torrent);
+        Ok(())
+    }
+}
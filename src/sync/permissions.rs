@@ -0,0 +1,82 @@
+// src/sync/permissions.rs
+
+//! Spotting access-denied errors, such as a download folder under Program Files, so
+//! the user is told to pick another folder instead of seeing a bare error mid-sync.
+
+use std::path::Path;
+use tokio::sync::mpsc;
+use tracing::error;
+
+use super::messages::SyncEvent;
+
+/// A permission error, with the OS error code when one is known
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PermissionError {
+    pub os_error: Option<i32>,
+}
+
+/// Whether `text` describes a permission error. librqbit often only keeps the
+/// io::Error's message, so this is what torrent errors can be checked with.
+pub fn permission_error_in_text(text: &str) -> Option<PermissionError> {
+    let lower = text.to_ascii_lowercase();
+    let denied = ["permission denied", "access is denied", "operation not permitted"]
+        .iter()
+        .any(|phrase| lower.contains(phrase));
+    if !denied {
+        return None;
+    }
+    let os_error = lower.split("os error ").nth(1).and_then(|rest| {
+        let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+        digits.parse().ok()
+    });
+    Some(PermissionError { os_error })
+}
+
+/// Whether `error` or any of its causes is a permission error
+pub fn permission_error(error: &anyhow::Error) -> Option<PermissionError> {
+    let io_error = error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<std::io::Error>())
+        .find(|e| e.kind() == std::io::ErrorKind::PermissionDenied);
+    match io_error {
+        Some(e) => Some(PermissionError { os_error: e.raw_os_error() }),
+        None => permission_error_in_text(&format!("{:#}", error)),
+    }
+}
+
+/// Log the error with its OS code and tell the UI which folder can't be written
+pub fn report_permission_error(
+    path: &Path,
+    error: &str,
+    permission: PermissionError,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+) {
+    let code = permission.os_error.map_or("unknown".to_string(), |code| code.to_string());
+    error!("Sync: Permission denied writing to {} (OS error {}): {}", path.display(), code, error);
+    let _ = ui_tx.send(SyncEvent::PermissionDenied(path.to_path_buf()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_permission_error_from_text() {
+        assert_eq!(
+            permission_error_in_text("error opening C:\\Program Files\\mods\\a.pbo: Access is denied. (os error 5)"),
+            Some(PermissionError { os_error: Some(5) })
+        );
+        assert_eq!(permission_error_in_text("Permission denied"), Some(PermissionError { os_error: None }));
+        assert_eq!(permission_error_in_text("No space left on device (os error 28)"), None);
+    }
+
+    #[test]
+    fn test_permission_error_from_cause() {
+        let io_error = std::io::Error::from_raw_os_error(13);
+        let error = Err::<(), _>(io_error).context("Failed to add torrent").unwrap_err();
+        let permission = permission_error(&error).expect("permission error");
+        assert_eq!(permission.os_error, Some(13));
+        assert_eq!(permission_error(&anyhow::anyhow!("tracker unreachable")), None);
+    }
+}
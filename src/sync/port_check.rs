@@ -0,0 +1,85 @@
+// src/sync/port_check.rs
+
+//! Whether peers can reach the listen port, to tell a firewalled setup apart from a slow
+//! swarm. The port librqbit actually bound comes from the session. A peer that connected
+//! in proves the port open without asking anyone; no incoming peers proves nothing, since
+//! the swarm may simply be quiet. For a definite answer, `port_check_url` can name an
+//! outside service to test the port. It's off by default, since that service learns
+//! the machine's address.
+
+use anyhow::{Context, Result, bail};
+use librqbit::Api;
+
+use crate::config::AppConfig;
+
+/// The listen port and what the torrent's peers say about it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListenStatus {
+    pub port: Option<u16>, // None when incoming connections are off
+    pub incoming_peers: u32, // Connected peers that dialled in
+}
+
+/// What an outside check of the listen port found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortCheck {
+    pub port: u16,
+    pub open: bool,
+}
+
+/// The bound listen port, and how many of the torrent's live peers connected in
+pub fn listen_status(api: &Api, torrent_id: Option<usize>) -> ListenStatus {
+    let incoming_peers = torrent_id
+        .and_then(|id| api.api_peer_stats(id.into(), Default::default()).ok())
+        .map_or(0, |stats| stats.peers.values().map(|peer| peer.counters.incoming_connections.min(1)).sum());
+    ListenStatus { port: api.session().tcp_listen_port(), incoming_peers }
+}
+
+/// `port_check_url` with `{port}` filled in
+pub fn check_url(template: &str, port: u16) -> Result<String> {
+    if !template.contains("{port}") {
+        bail!("port_check_url needs a {{port}} placeholder");
+    }
+    Ok(template.replace("{port}", &port.to_string()))
+}
+
+/// Read a check service's reply: a body starting with open/true/yes/1 or closed/false/no/0
+pub fn parse_reply(body: &str) -> Result<bool> {
+    let answer = body.trim().to_ascii_lowercase();
+    let starts = |words: &[&str]| words.iter().any(|word| answer.starts_with(word));
+    if starts(&["open", "true", "yes", "1"]) {
+        Ok(true)
+    } else if starts(&["closed", "false", "no", "0"]) {
+        Ok(false)
+    } else {
+        bail!("Unrecognised reply from the port check service: {:.80}", body.trim())
+    }
+}
+
+/// Ask the `port_check_url` service whether `port` accepts connections from outside
+pub async fn check_port(config: &AppConfig, client: &reqwest::Client, port: Option<u16>) -> Result<PortCheck> {
+    let template = config.port_check_url.as_deref().context("No port_check_url is configured")?;
+    let port = port.context("Not listening for incoming connections")?;
+    let url = check_url(template, port)?;
+    let response = client.get(&url).send().await.context("Port check service didn't answer")?;
+    if !response.status().is_success() {
+        bail!("Port check service answered {}", response.status());
+    }
+    let body = response.text().await.context("Failed to read the port check reply")?;
+    Ok(PortCheck { port, open: parse_reply(&body)? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_url_and_replies() {
+        assert_eq!(check_url("https://check.example/?port={port}", 4240).unwrap(), "https://check.example/?port=4240");
+        assert!(check_url("https://check.example/", 4240).is_err());
+        assert!(parse_reply("OPEN\n").unwrap());
+        assert!(parse_reply("true").unwrap());
+        assert!(!parse_reply(" closed: connection refused").unwrap());
+        assert!(!parse_reply("0").unwrap());
+        assert!(parse_reply("<html>rate limited</html>").is_err());
+    }
+}
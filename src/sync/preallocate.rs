@@ -0,0 +1,151 @@
+// src/sync/preallocate.rs
+
+//! `preallocate_files`: reserve the full size of each file to download before librqbit
+//! starts, instead of its sparse files that grow as pieces arrive. A disk that's too
+//! small fails the add straight away, and files end up less fragmented, which helps
+//! sequential reads on HDDs. Existing files are only ever extended, never truncated.
+
+use anyhow::{Context, Result, bail};
+use fs4::FileExt;
+use librqbit::AddTorrentOptions;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use tracing::info;
+
+use crate::config::AppConfig;
+use crate::ui::utils::{SyncStatus, format_size};
+
+use super::messages::SyncEvent;
+use super::utils::send_sync_status_event;
+
+/// Selected files of the torrent that are shorter on disk than in the torrent, with
+/// their full length. Padding files are never written, so they're left out.
+pub fn files_to_allocate(download_path: &Path, torrent_bytes: &[u8], only_files: Option<&[usize]>) -> Result<Vec<(PathBuf, u64)>> {
+    let torrent = librqbit::torrent_from_bytes::<librqbit::ByteBuf>(torrent_bytes)
+        .context("Failed to parse torrent for preallocation")?;
+    let mut files = Vec::new();
+    for (index, file) in torrent.info.iter_file_details()?.enumerate() {
+        if file.attrs().padding || file.len == 0 || only_files.is_some_and(|only| !only.contains(&index)) {
+            continue;
+        }
+        let path = download_path.join(file.filename.to_pathbuf()?);
+        let current = std::fs::metadata(&path).map_or(0, |metadata| metadata.len());
+        if current < file.len {
+            files.push((path, file.len));
+        }
+    }
+    Ok(files)
+}
+
+/// Allocate `files`, reporting whole percentages of the bytes done. Returns the bytes reserved.
+pub fn allocate_files(files: &[(PathBuf, u64)], mut progress: impl FnMut(u8)) -> Result<u64> {
+    let total: u64 = files.iter().map(|(_, len)| len).sum();
+    let mut done = 0u64;
+    for (path, len) in files {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).with_context(|| format!("Failed to create folder {}", parent.display()))?;
+        }
+        let file = File::options()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .with_context(|| format!("Failed to open {} for preallocation", path.display()))?;
+        file.allocate(*len)
+            .with_context(|| format!("Failed to allocate {} for {}", format_size(*len), path.display()))?;
+        // fs4 skips files whose blocks already cover `len`, leaving them short
+        if file.metadata()?.len() < *len {
+            file.set_len(*len).with_context(|| format!("Failed to extend {}", path.display()))?;
+        }
+        done += len;
+        progress((done * 100 / total.max(1)) as u8);
+    }
+    Ok(total)
+}
+
+/// With `preallocate_files` on, allocate the files `options` selects before the torrent is
+/// added. Fails without allocating anything when the volume doesn't have the room.
+pub async fn preallocate(
+    config: &AppConfig,
+    torrent_bytes: &[u8],
+    options: &AddTorrentOptions,
+    ui_tx: Option<&mpsc::UnboundedSender<SyncEvent>>,
+) -> Result<()> {
+    if !config.preallocate_files {
+        return Ok(());
+    }
+    let download_path = config.download_path.clone();
+    let files = files_to_allocate(&download_path, torrent_bytes, options.only_files.as_deref())?;
+    if files.is_empty() {
+        return Ok(());
+    }
+    let needed: u64 = files.iter().map(|(path, len)| len - std::fs::metadata(path).map_or(0, |m| m.len())).sum();
+    let available = fs4::available_space(&download_path)
+        .with_context(|| format!("Failed to query free space for {}", download_path.display()))?;
+    if needed > available {
+        bail!(
+            "Not enough disk space to download the torrent: {} more needed, {} free on {}",
+            format_size(needed),
+            format_size(available),
+            download_path.display()
+        );
+    }
+
+    info!("Sync: Preallocating {} files ({} more)", files.len(), format_size(needed));
+    if let Some(ui_tx) = ui_tx {
+        send_sync_status_event(ui_tx, SyncStatus::Allocating { percent: 0 });
+    }
+    let progress_tx = ui_tx.cloned();
+    let allocated = tokio::task::spawn_blocking(move || {
+        let mut last = 0;
+        allocate_files(&files, |percent| {
+            if let Some(tx) = progress_tx.as_ref().filter(|_| percent != last) {
+                last = percent;
+                send_sync_status_event(tx, SyncStatus::Allocating { percent });
+            }
+        })
+    })
+    .await
+    .context("Preallocation task failed")??;
+    info!("Sync: Preallocated {}", format_size(allocated));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use librqbit::CreateTorrentOptions;
+    use std::fs;
+
+    #[tokio::test]
+    async fn test_selected_short_files_are_allocated_to_full_size() -> Result<()> {
+        let source = tempfile::tempdir()?;
+        fs::create_dir(source.path().join("addons"))?;
+        fs::write(source.path().join("addons/a.pbo"), vec![1u8; 5000])?;
+        fs::write(source.path().join("b.pbo"), vec![2u8; 3000])?;
+        fs::write(source.path().join("c.pbo"), vec![3u8; 100])?;
+        let torrent = librqbit::create_torrent(source.path(), CreateTorrentOptions::default()).await?;
+        let torrent_bytes = torrent.as_bytes()?;
+        let index_of = |name: &str| {
+            torrent.as_info().info.iter_file_details().unwrap().position(|file| file.filename.to_string().unwrap() == name).unwrap()
+        };
+
+        let download = tempfile::tempdir()?;
+        fs::write(download.path().join("b.pbo"), vec![9u8; 1000])?;
+        fs::write(download.path().join("c.pbo"), vec![3u8; 100])?;
+        let selected = [index_of("addons/a.pbo"), index_of("b.pbo"), index_of("c.pbo")];
+        let mut files = files_to_allocate(download.path(), &torrent_bytes, Some(&selected))?;
+        files.sort();
+        assert_eq!(files, vec![(download.path().join("addons/a.pbo"), 5000), (download.path().join("b.pbo"), 3000)]);
+        assert!(files_to_allocate(download.path(), &torrent_bytes, Some(&[index_of("c.pbo")]))?.is_empty());
+
+        let mut reported = Vec::new();
+        assert_eq!(allocate_files(&files, |percent| reported.push(percent))?, 8000);
+        assert_eq!(reported.last(), Some(&100));
+        assert_eq!(fs::metadata(download.path().join("addons/a.pbo"))?.len(), 5000);
+        let b = fs::read(download.path().join("b.pbo"))?;
+        assert_eq!((b.len(), &b[..1000]), (3000, &vec![9u8; 1000][..]), "existing bytes are kept");
+        Ok(())
+    }
+}
@@ -0,0 +1,277 @@
+// src/sync/preflight.rs
+
+//! Dry-run checks of an unsaved config: fetch and parse the torrent, and make
+//! sure the download folder is usable. Nothing is added to the session.
+
+use std::fs;
+use std::path::Path;
+
+use crate::config::AppConfig;
+
+use super::disk::available_space_mb;
+use super::utils::download_torrent;
+
+/// What a single config test check looked at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigCheckKind {
+    TorrentUrl,
+    TorrentParses,
+    PathWritable,
+    FreeSpace,
+}
+
+/// Outcome of one check, with the reason it passed or failed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigCheck {
+    pub kind: ConfigCheckKind,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl ConfigCheck {
+    fn pass(kind: ConfigCheckKind, detail: impl Into<String>) -> Self {
+        Self { kind, passed: true, detail: detail.into() }
+    }
+
+    fn fail(kind: ConfigCheckKind, detail: impl Into<String>) -> Self {
+        Self { kind, passed: false, detail: detail.into() }
+    }
+}
+
+/// Outcome of the quick check run when the torrent URL is saved or edited
+#[derive(Debug, Clone, PartialEq)]
+pub enum UrlCheck {
+    Torrent,
+    NotATorrent(String), // The server answered, but not with a torrent
+    Unreachable(String),
+}
+
+/// Bencoded torrents are a dictionary, so they start with `d` and a key length.
+/// Servers often send them as octet-stream, so the content type alone can't decide.
+pub fn looks_like_torrent(content_type: Option<&str>, body_start: &[u8]) -> bool {
+    let bencoded = body_start.first() == Some(&b'd') && body_start.get(1).is_some_and(u8::is_ascii_digit);
+    let torrent_type = content_type.is_some_and(|t| t.contains("bittorrent"));
+    bencoded || (torrent_type && body_start.is_empty())
+}
+
+/// Fetch just the first chunk of `url` and check it looks like a torrent
+pub async fn check_torrent_url(url: &str, client: &reqwest::Client) -> UrlCheck {
+    let mut response = match client.get(url).send().await {
+        Ok(response) => response,
+        Err(e) => return UrlCheck::Unreachable(e.without_url().to_string()),
+    };
+    if !response.status().is_success() {
+        return UrlCheck::NotATorrent(format!("HTTP {}", response.status()));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body_start = match response.chunk().await {
+        Ok(chunk) => chunk.unwrap_or_default(),
+        Err(e) => return UrlCheck::Unreachable(e.without_url().to_string()),
+    };
+    if looks_like_torrent(content_type.as_deref(), &body_start) {
+        UrlCheck::Torrent
+    } else {
+        UrlCheck::NotATorrent(content_type.unwrap_or_else(|| "no content type".to_string()))
+    }
+}
+
+/// Run every check against `config`, in order. Checks that depend on an earlier
+/// failed one still report, explaining why they were skipped.
+pub async fn test_config(config: &AppConfig, client: &reqwest::Client) -> Vec<ConfigCheck> {
+    let mut checks = Vec::new();
+
+    let torrent_bytes = if config.torrent_url.trim().is_empty() {
+        checks.push(ConfigCheck::fail(ConfigCheckKind::TorrentUrl, "No torrent URL set"));
+        None
+    } else {
+        match download_torrent(config.torrent_url.trim(), client).await {
+            Ok(bytes) => {
+                checks.push(ConfigCheck::pass(
+                    ConfigCheckKind::TorrentUrl,
+                    format!("Fetched {} bytes", bytes.len()),
+                ));
+                Some(bytes)
+            }
+            Err(e) => {
+                checks.push(ConfigCheck::fail(ConfigCheckKind::TorrentUrl, format!("{:#}", e)));
+                None
+            }
+        }
+    };
+
+    let torrent_size = match torrent_bytes {
+        Some(bytes) => {
+            let (check, size) = check_parses(&bytes);
+            checks.push(check);
+            size
+        }
+        None => {
+            checks.push(ConfigCheck::fail(ConfigCheckKind::TorrentParses, "Skipped: torrent could not be fetched"));
+            None
+        }
+    };
+
+    let path_check = check_writable(&config.download_path);
+    let path_ok = path_check.passed;
+    checks.push(path_check);
+
+    checks.push(if path_ok {
+        check_free_space(&config.download_path, torrent_size, config.min_free_space_mb)
+    } else {
+        ConfigCheck::fail(ConfigCheckKind::FreeSpace, "Skipped: download path is not usable")
+    });
+
+    checks
+}
+
+/// Parse the torrent file, returning its total content size if it parses
+fn check_parses(bytes: &[u8]) -> (ConfigCheck, Option<u64>) {
+    let torrent = match librqbit::torrent_from_bytes::<librqbit::ByteBuf>(bytes) {
+        Ok(torrent) => torrent,
+        Err(e) => return (ConfigCheck::fail(ConfigCheckKind::TorrentParses, format!("Not a valid torrent: {}", e)), None),
+    };
+    match torrent.info.iter_file_lengths() {
+        Ok(lengths) => {
+            let size: u64 = lengths.sum();
+            let detail = format!("{} ({})", torrent.info_hash.as_string(), crate::ui::utils::format_size(size));
+            (ConfigCheck::pass(ConfigCheckKind::TorrentParses, detail), Some(size))
+        }
+        Err(e) => (ConfigCheck::fail(ConfigCheckKind::TorrentParses, format!("Invalid file list: {}", e)), None),
+    }
+}
+
+/// Write and remove a probe file in `dir`
+fn probe_write(dir: &Path) -> std::io::Result<()> {
+    let probe = dir.join(".modsync_write_test");
+    fs::write(&probe, b"modsync")?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+/// The folder must exist (or be creatable) and accept a new file
+pub fn check_writable(path: &Path) -> ConfigCheck {
+    if path.as_os_str().is_empty() {
+        return ConfigCheck::fail(ConfigCheckKind::PathWritable, "No download path set");
+    }
+    if !path.exists() {
+        // The real sync creates the folder; only check that it could
+        let parent = path.ancestors().skip(1).find(|p| p.exists());
+        return match parent {
+            Some(parent) if parent.is_dir() => match probe_write(parent) {
+                Ok(()) => ConfigCheck::pass(
+                    ConfigCheckKind::PathWritable,
+                    format!("Will be created inside {}", parent.display()),
+                ),
+                Err(e) => ConfigCheck::fail(
+                    ConfigCheckKind::PathWritable,
+                    format!("Cannot create folder inside {}: {}", parent.display(), e),
+                ),
+            },
+            _ => ConfigCheck::fail(ConfigCheckKind::PathWritable, "Folder does not exist and has no parent to create it in"),
+        };
+    }
+    if !path.is_dir() {
+        return ConfigCheck::fail(ConfigCheckKind::PathWritable, "Path is a file, not a folder");
+    }
+
+    match probe_write(path) {
+        Ok(()) => ConfigCheck::pass(ConfigCheckKind::PathWritable, "Folder is writable"),
+        Err(e) => ConfigCheck::fail(ConfigCheckKind::PathWritable, format!("Cannot write to folder: {}", e)),
+    }
+}
+
+/// Enough space for the whole torrent plus the configured minimum. Space already used by
+/// existing files isn't counted, so this is pessimistic for a folder that's partly synced.
+fn check_free_space(path: &Path, torrent_size: Option<u64>, min_free_space_mb: Option<u64>) -> ConfigCheck {
+    let existing = path.ancestors().find(|p| p.exists()).unwrap_or(path);
+    let available_mb = match available_space_mb(existing) {
+        Ok(mb) => mb,
+        Err(e) => return ConfigCheck::fail(ConfigCheckKind::FreeSpace, format!("{:#}", e)),
+    };
+    // A torrent that reports no size yet can't be checked; the tick's free-space check still guards the volume
+    let Some(torrent_size) = torrent_size.filter(|size| *size > 0) else {
+        return ConfigCheck::pass(ConfigCheckKind::FreeSpace, format!("{} MB free; torrent size not known yet, so not checked", available_mb));
+    };
+    let needed_mb = torrent_size.div_ceil(1024 * 1024) + min_free_space_mb.unwrap_or(0);
+    let detail = format!("{} MB free, {} MB needed", available_mb, needed_mb);
+    if available_mb >= needed_mb {
+        ConfigCheck::pass(ConfigCheckKind::FreeSpace, detail)
+    } else {
+        ConfigCheck::fail(ConfigCheckKind::FreeSpace, detail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_writable() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        assert!(check_writable(dir.path()).passed);
+        assert!(check_writable(&dir.path().join("new/sub")).passed, "missing folders can be created");
+        assert!(!dir.path().join(".modsync_write_test").exists(), "probe file is cleaned up");
+
+        let file = dir.path().join("file.txt");
+        fs::write(&file, "x")?;
+        assert!(!check_writable(&file).passed);
+        assert!(!check_writable(Path::new("")).passed);
+        Ok(())
+    }
+
+    #[test]
+    fn test_looks_like_torrent() {
+        assert!(looks_like_torrent(Some("application/octet-stream"), b"d8:announce35:http://"));
+        assert!(looks_like_torrent(Some("application/x-bittorrent"), b""));
+        assert!(!looks_like_torrent(Some("text/html; charset=utf-8"), b"<!DOCTYPE html>"));
+        assert!(!looks_like_torrent(Some("application/x-bittorrent"), b"<html>"));
+        assert!(!looks_like_torrent(None, b"don't"));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_url() {
+        let client = reqwest::Client::new();
+        let check = check_torrent_url("http://127.0.0.1:1/mods.torrent", &client).await;
+        assert!(matches!(check, UrlCheck::Unreachable(_)), "{:?}", check);
+    }
+
+    #[test]
+    fn test_free_space_waits_for_a_known_size() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        for size in [None, Some(0)] {
+            let check = check_free_space(dir.path(), size, Some(u64::MAX / 2));
+            assert!(check.passed, "{}", check.detail);
+            assert!(check.detail.contains("not known yet"));
+        }
+        assert!(!check_free_space(dir.path(), Some(1), Some(u64::MAX / 2)).passed);
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_parses_rejects_garbage() {
+        let (check, size) = check_parses(b"not a torrent");
+        assert!(!check.passed);
+        assert_eq!(size, None);
+    }
+
+    #[tokio::test]
+    async fn test_empty_config_fails_every_check_with_reasons() {
+        let client = reqwest::Client::new();
+        let checks = test_config(&AppConfig::default(), &client).await;
+        let kinds: Vec<_> = checks.iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ConfigCheckKind::TorrentUrl,
+                ConfigCheckKind::TorrentParses,
+                ConfigCheckKind::PathWritable,
+                ConfigCheckKind::FreeSpace,
+            ]
+        );
+        assert!(checks.iter().all(|c| !c.passed && !c.detail.is_empty()));
+    }
+}
@@ -0,0 +1,98 @@
+// src/sync/priority.rs
+
+//! "Download this first" for one file of the live torrent. librqbit's API has no file
+//! priorities, but it fetches the pieces just ahead of an open file stream before any
+//! others. Reading the file through a stream, and throwing the bytes away, walks that
+//! window from its start to its end, so the file arrives roughly in order while the rest
+//! of the torrent carries on with the bandwidth left over. One file at a time; choosing
+//! another replaces it.
+
+use anyhow::{Context, Result, bail};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use super::messages::SyncEvent;
+
+/// Index of `path` among `files`, given as (relative path, selected, complete) in torrent
+/// order, if it can be put first
+pub fn choose_file(files: &[(String, bool, bool)], path: &str) -> Result<usize> {
+    let index = files.iter().position(|(file, _, _)| file == path).with_context(|| format!("No file {} in the torrent", path))?;
+    let (_, selected, complete) = &files[index];
+    if !selected {
+        bail!("{} is paused or left out of the download; resume it first", path);
+    }
+    if *complete {
+        bail!("{} is already downloaded", path);
+    }
+    Ok(index)
+}
+
+/// The file being downloaded first, if any
+#[derive(Debug, Default)]
+pub struct PriorityFile {
+    current: Option<CancellationToken>,
+}
+
+impl PriorityFile {
+    /// Download `path` of torrent `id` before the rest, replacing the file put first before
+    pub fn prioritize(&mut self, api: &librqbit::Api, id: usize, path: &str, ui_tx: &mpsc::UnboundedSender<SyncEvent>) -> Result<()> {
+        let details = api.api_torrent_details(id.into())?;
+        let stats = api.api_stats_v1(id.into())?;
+        let files: Vec<(String, bool, bool)> = details
+            .files
+            .iter()
+            .flatten()
+            .enumerate()
+            .map(|(index, file)| {
+                let complete = stats.file_progress.get(index).is_some_and(|done| *done >= file.length);
+                (file.components.join("/"), file.included, complete)
+            })
+            .collect();
+        let index = choose_file(&files, path)?;
+        let mut stream = api.api_stream(id.into(), index).context("Failed to open the file for prioritising")?;
+
+        self.clear();
+        let token = CancellationToken::new();
+        self.current = Some(token.clone());
+        let path = path.to_string();
+        info!("Sync: Downloading {} first", path);
+        let _ = ui_tx.send(SyncEvent::FilePrioritized(path.clone()));
+        tokio::spawn(async move {
+            let mut sink = tokio::io::sink();
+            tokio::select! {
+                read = tokio::io::copy(&mut stream, &mut sink) => match read {
+                    Ok(_) => info!("Sync: Prioritised file {} is downloaded", path),
+                    Err(e) => warn!("Sync: Stopped prioritising {}: {}", path, e),
+                },
+                _ = token.cancelled() => info!("Sync: No longer prioritising {}", path),
+            }
+        });
+        Ok(())
+    }
+
+    /// Stop putting a file first, e.g. when the torrent is replaced
+    pub fn clear(&mut self) {
+        if let Some(token) = self.current.take() {
+            token.cancel();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_unfinished_selected_files_go_first() {
+        let files = [
+            ("@mod/addons/a.pbo".to_string(), true, false),
+            ("@mod/addons/b.pbo".to_string(), false, false),
+            ("@mod/mod.cpp".to_string(), true, true),
+        ];
+        assert_eq!(choose_file(&files, "@mod/addons/a.pbo").unwrap(), 0);
+        assert!(choose_file(&files, "@mod/addons/b.pbo").unwrap_err().to_string().contains("resume it first"));
+        assert!(choose_file(&files, "@mod/mod.cpp").unwrap_err().to_string().contains("already downloaded"));
+        assert!(choose_file(&files, "@mod/missing.pbo").is_err());
+    }
+}
@@ -0,0 +1,72 @@
+// src/sync/private.rs
+
+//! Keeps private torrents off the DHT. librqbit ignores the BEP 27 private flag and
+//! shares one DHT between every torrent in the session, so a private torrent is only
+//! safe in a session started without DHT. librqbit has no LSD, and no way to turn off
+//! PEX, which only swaps addresses between peers that are already connected.
+
+use anyhow::{Result, bail};
+
+use crate::config::AppConfig;
+
+use super::torrent::is_private_torrent;
+
+/// Whether `torrent_content` has to be kept off the DHT
+pub fn treat_as_private(config: &AppConfig, torrent_content: &[u8]) -> bool {
+    config.force_private || is_private_torrent(torrent_content)
+}
+
+/// Whether the session may run the DHT, given the torrent it will start with
+pub fn session_dht_allowed(config: &AppConfig, cached_torrent: Option<&[u8]>) -> bool {
+    config.connection.enable_dht && !config.force_private && !cached_torrent.is_some_and(is_private_torrent)
+}
+
+/// Refuse to add a private torrent to a session that's running the DHT. The torrent is
+/// already cached by then, so the next start picks a session without DHT for it.
+pub fn ensure_private_safe(config: &AppConfig, api: &librqbit::Api, torrent_content: &[u8]) -> Result<()> {
+    if treat_as_private(config, torrent_content) && api.session().get_dht().is_some() {
+        bail!("This torrent is private, but DHT is running. Restart ModSync to load it without DHT.");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConnectionSettings;
+    use crate::sync::session::session_options;
+
+    const PRIVATE: &[u8] = b"d4:infod6:lengthi10e4:name3:abc12:piece lengthi16384e7:privatei1eee";
+    const PUBLIC: &[u8] = b"d4:infod6:lengthi10e4:name3:abc12:piece lengthi16384eee";
+
+    fn dht_config() -> AppConfig {
+        AppConfig {
+            connection: ConnectionSettings { enable_dht: true, ..ConnectionSettings::default() },
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_dht_stays_off_for_private_torrents() {
+        let mut config = dht_config();
+        assert!(!session_options(&config, Some(PUBLIC)).disable_dht);
+        assert!(!session_options(&config, None).disable_dht);
+        assert!(session_options(&config, Some(PRIVATE)).disable_dht);
+
+        config.force_private = true;
+        assert!(session_options(&config, Some(PUBLIC)).disable_dht);
+        assert!(treat_as_private(&config, PUBLIC));
+    }
+
+    #[tokio::test]
+    async fn test_private_torrent_is_refused_while_dht_runs() -> Result<()> {
+        let download = tempfile::tempdir()?;
+        let config = dht_config();
+        let options = librqbit::SessionOptions { listen_port_range: None, ..session_options(&config, None) };
+        let session = librqbit::Session::new_with_opts(download.path().to_path_buf(), options).await?;
+        let api = librqbit::Api::new(session, None);
+        assert!(ensure_private_safe(&config, &api, PUBLIC).is_ok());
+        assert!(ensure_private_safe(&config, &api, PRIVATE).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,434 @@
+// src/sync/progress.rs
+
+//! Callback-style alternative to the `SyncEvent` channel for embedders.
+//!
+//! Implement [`ProgressSink`] and pass it to [`run_sync_manager_with_sink`], or drive
+//! one from an existing channel with [`forward_events`].
+//!
+//! Call ordering guarantees:
+//! - Callbacks are made one at a time from a single task, never concurrently.
+//! - They are made in the order the sync manager produced the events.
+//! - `on_torrent_added(id)` is called before any `on_progress` for that `id`, except for a
+//!   torrent passed in as `initial_torrent_id`, which may report progress straight away.
+//! - `on_error` does not stop the manager; further callbacks may follow.
+//! - With `run_sync_manager_with_sink`, `on_event_stamp` comes just before the callback for
+//!   each event, with the event's sequence number and the time it was sent.
+//! - Once `SyncCommand::Shutdown` is handled the manager returns, and
+//!   `run_sync_manager_with_sink` returns only after every earlier callback was made.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use tokio::sync::mpsc;
+
+use crate::config::AppConfig;
+use crate::ui::utils::SyncStatus;
+
+use super::messages::{SyncCommand, SyncEvent, TimestampedEvent};
+use super::memory::MemoryUsage;
+use super::publish::{FolderChanges, PublishedTorrent};
+use super::owner::FolderOwner;
+use super::preflight::{ConfigCheck, UrlCheck};
+use super::extract::ExtractSummary;
+use super::file_mode::FileModeSummary;
+use super::http_fallback::FallbackSummary;
+use super::routing::RouteSummary;
+use super::offline_verify::OfflineVerifyReport;
+use super::spot_check::SpotCheckReport;
+use super::summary::UpdateSummary;
+use super::port_check::PortCheck;
+use super::tracker_check::TrackerReport;
+
+/// An error or notice reported by the sync manager
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncError {
+    pub message: String,
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for SyncError {}
+
+/// Receives sync progress. Every method defaults to doing nothing, so implement only what you need.
+pub trait ProgressSink: Send {
+    /// Sequence number (from 1, no gaps unless events were lost) and send time of the
+    /// event whose callback follows
+    fn on_event_stamp(&mut self, _seq: u64, _at: chrono::DateTime<chrono::Local>) {}
+
+    /// Overall sync status changed (may repeat the same status)
+    fn on_status(&mut self, _status: SyncStatus) {}
+
+    /// Latest stats for the managed torrent, or None if there is no torrent
+    fn on_progress(&mut self, _torrent: Option<(usize, Arc<librqbit::TorrentStats>)>) {}
+
+    /// A torrent was added to the session and is now managed
+    fn on_torrent_added(&mut self, _id: usize) {}
+
+    /// The synced torrent finished downloading
+    fn on_sync_complete(&mut self, _info_hash: String, _bytes: u64) {}
+
+    fn on_error(&mut self, _error: SyncError) {}
+
+    /// A problem that doesn't stop syncing, such as a removed torrent URL while seeding
+    fn on_warning(&mut self, _message: String) {}
+
+    /// The OS refused writes to this folder, so syncing can't work until it changes
+    fn on_permission_denied(&mut self, _path: PathBuf) {}
+
+    /// The remote torrent changed; send `SyncCommand::ApplyUpdate` with this data to apply it
+    fn on_remote_update(&mut self, _torrent_data: Vec<u8>) {}
+
+    /// A `SyncCommand::ScheduleUpdate` applies at this time unless cancelled
+    fn on_update_countdown(&mut self, _applies_at: chrono::DateTime<chrono::Local>) {}
+
+    /// The countdown ended: `applied` when the update is going ahead, false when cancelled
+    fn on_update_countdown_ended(&mut self, _applied: bool) {}
+
+    /// What the pending remote update would change, when `confirm_before_update` is set
+    fn on_update_summary(&mut self, _summary: UpdateSummary) {}
+
+    /// Release notes for the pending update, only when they haven't been shown before
+    fn on_changelog(&mut self, _text: String) {}
+
+    /// Files in the download folder that aren't part of the torrent
+    fn on_extra_files(&mut self, _files: Vec<PathBuf>) {}
+
+    /// Incomplete files of the previous torrent that were deleted after an update
+    fn on_partial_files_cleaned(&mut self, _files: Vec<PathBuf>) {}
+
+    /// Torrent files missing from the download folder
+    fn on_missing_files(&mut self, _files: HashSet<PathBuf>) {}
+
+    /// A public torrent is paused until `SyncCommand::ConfirmSeeding(id)` is sent
+    fn on_seeding_confirmation_needed(&mut self, _id: usize) {}
+
+    /// A torrent is paused until its notice is accepted with `SyncCommand::AcknowledgeNotice(id)`
+    fn on_notice_acknowledgement_needed(&mut self, _id: usize, _notice: String) {}
+
+    /// A command was refused because another torrent owns the download folder
+    fn on_folder_owner_mismatch(&mut self, _owner: FolderOwner, _retry: SyncCommand) {}
+
+    /// Final URL the torrent URL resolved to after redirects
+    fn on_remote_resolved(&mut self, _resolved_url: String) {}
+
+    /// Files of the finished torrent were copied to route rule destinations
+    fn on_files_routed(&mut self, _summary: RouteSummary) {}
+
+    /// Archives of the finished torrent were unpacked
+    fn on_archives_extracted(&mut self, _summary: ExtractSummary) {}
+
+    /// The finished torrent's files were given `post_download_chmod` and `post_download_chown`
+    fn on_file_modes_applied(&mut self, _summary: FileModeSummary) {}
+
+    /// A file is now downloaded before the rest
+    fn on_file_prioritized(&mut self, _path: String) {}
+
+    /// Files fetched over HTTP while the swarm had no peers
+    fn on_http_fallback_fetched(&mut self, _summary: FallbackSummary) {}
+
+    /// Result of a `SyncCommand::VerifySubset`
+    fn on_subset_verified(&mut self, _result: Result<SpotCheckReport, String>) {}
+
+    /// config.toml was read again, with the keys that changed
+    fn on_config_reloaded(&mut self, _config: AppConfig, _changed: Vec<String>) {}
+
+    /// The manager runs on an updated config now, with the keys that changed
+    fn on_config_applied(&mut self, _changed: Vec<String>) {}
+
+    /// Result of a `SyncCommand::VerifyAgainstTorrent`
+    fn on_offline_verified(&mut self, _result: Result<OfflineVerifyReport, String>) {}
+
+    /// The version the folder is on after a sync, None while it isn't synced
+    fn on_version_info(&mut self, _version: Option<String>) {}
+
+    /// The metrics or control API server started, on the port shown in `url`
+    fn on_server_listening(&mut self, _name: String, _url: String) {}
+
+    /// Publisher mode saw edits since the last published torrent
+    fn on_folder_changed(&mut self, _changes: FolderChanges) {}
+
+    /// Publisher mode wrote and started seeding a new torrent
+    fn on_torrent_published(&mut self, _published: PublishedTorrent) {}
+
+    /// Locally edited files the overwrite policy keeps out of the download
+    fn on_files_preserved(&mut self, _paths: Vec<String>) {}
+
+    /// The session reached `global_seed_goal` and seeding was paused
+    fn on_global_seed_goal_met(&mut self, _uploaded_bytes: u64, _ratio: Option<f64>) {}
+
+    /// Latest memory reading, made every tick
+    fn on_memory_usage(&mut self, _usage: MemoryUsage) {}
+
+    /// Result of a `SyncCommand::TestTrackers`
+    fn on_tracker_test_result(&mut self, _result: Result<Vec<TrackerReport>, String>) {}
+
+    /// Result of a `SyncCommand::CheckPort`
+    fn on_port_check_result(&mut self, _result: Result<PortCheck, String>) {}
+
+    /// Results of a `SyncCommand::TestConfig`
+    fn on_config_test_result(&mut self, _checks: Vec<ConfigCheck>) {}
+
+    /// Result of a `SyncCommand::CheckUrl`
+    fn on_url_check_result(&mut self, _url: String, _result: UrlCheck) {}
+}
+
+/// Sink that ignores everything
+pub struct NoopSink;
+
+impl ProgressSink for NoopSink {}
+
+/// The channel the GUI consumes is itself a sink: each callback becomes a `SyncEvent`
+impl ProgressSink for mpsc::UnboundedSender<SyncEvent> {
+    fn on_status(&mut self, status: SyncStatus) {
+        let _ = self.send(SyncEvent::StatusUpdate(status));
+    }
+    fn on_progress(&mut self, torrent: Option<(usize, Arc<librqbit::TorrentStats>)>) {
+        let _ = self.send(SyncEvent::ManagedTorrentUpdate(torrent));
+    }
+    fn on_torrent_added(&mut self, id: usize) {
+        let _ = self.send(SyncEvent::TorrentAdded(id));
+    }
+    fn on_sync_complete(&mut self, info_hash: String, bytes: u64) {
+        let _ = self.send(SyncEvent::SyncComplete { info_hash, bytes });
+    }
+    fn on_error(&mut self, error: SyncError) {
+        let _ = self.send(SyncEvent::Error(error.message));
+    }
+    fn on_warning(&mut self, message: String) {
+        let _ = self.send(SyncEvent::Warning(message));
+    }
+    fn on_permission_denied(&mut self, path: PathBuf) {
+        let _ = self.send(SyncEvent::PermissionDenied(path));
+    }
+    fn on_remote_update(&mut self, torrent_data: Vec<u8>) {
+        let _ = self.send(SyncEvent::RemoteUpdateFound(torrent_data));
+    }
+    fn on_update_countdown(&mut self, applies_at: chrono::DateTime<chrono::Local>) {
+        let _ = self.send(SyncEvent::UpdateCountdown(applies_at));
+    }
+    fn on_update_countdown_ended(&mut self, applied: bool) {
+        let _ = self.send(SyncEvent::UpdateCountdownEnded { applied });
+    }
+    fn on_update_summary(&mut self, summary: UpdateSummary) {
+        let _ = self.send(SyncEvent::RemoteUpdateSummary(summary));
+    }
+    fn on_changelog(&mut self, text: String) {
+        let _ = self.send(SyncEvent::Changelog(text));
+    }
+    fn on_extra_files(&mut self, files: Vec<PathBuf>) {
+        let _ = self.send(SyncEvent::ExtraFilesFound(files));
+    }
+    fn on_partial_files_cleaned(&mut self, files: Vec<PathBuf>) {
+        let _ = self.send(SyncEvent::PartialFilesCleaned(files));
+    }
+    fn on_missing_files(&mut self, files: HashSet<PathBuf>) {
+        let _ = self.send(SyncEvent::MissingFilesFound(files));
+    }
+    fn on_seeding_confirmation_needed(&mut self, id: usize) {
+        let _ = self.send(SyncEvent::SeedingConfirmationNeeded(id));
+    }
+    fn on_notice_acknowledgement_needed(&mut self, id: usize, notice: String) {
+        let _ = self.send(SyncEvent::NoticeAcknowledgementNeeded { id, notice });
+    }
+    fn on_folder_owner_mismatch(&mut self, owner: FolderOwner, retry: SyncCommand) {
+        let _ = self.send(SyncEvent::FolderOwnerMismatch { owner, retry: Box::new(retry) });
+    }
+    fn on_remote_resolved(&mut self, resolved_url: String) {
+        let _ = self.send(SyncEvent::RemoteResolved(resolved_url));
+    }
+    fn on_files_routed(&mut self, summary: RouteSummary) {
+        let _ = self.send(SyncEvent::FilesRouted(summary));
+    }
+    fn on_archives_extracted(&mut self, summary: ExtractSummary) {
+        let _ = self.send(SyncEvent::ArchivesExtracted(summary));
+    }
+    fn on_file_modes_applied(&mut self, summary: FileModeSummary) {
+        let _ = self.send(SyncEvent::FileModesApplied(summary));
+    }
+    fn on_file_prioritized(&mut self, path: String) {
+        let _ = self.send(SyncEvent::FilePrioritized(path));
+    }
+    fn on_http_fallback_fetched(&mut self, summary: FallbackSummary) {
+        let _ = self.send(SyncEvent::HttpFallbackFetched(summary));
+    }
+    fn on_subset_verified(&mut self, result: Result<SpotCheckReport, String>) {
+        let _ = self.send(SyncEvent::SubsetVerified(result));
+    }
+    fn on_config_reloaded(&mut self, config: AppConfig, changed: Vec<String>) {
+        let _ = self.send(SyncEvent::ConfigReloaded { config: Box::new(config), changed });
+    }
+    fn on_config_applied(&mut self, changed: Vec<String>) {
+        let _ = self.send(SyncEvent::ConfigApplied { changed });
+    }
+    fn on_offline_verified(&mut self, result: Result<OfflineVerifyReport, String>) {
+        let _ = self.send(SyncEvent::OfflineVerified(result));
+    }
+    fn on_version_info(&mut self, version: Option<String>) {
+        let _ = self.send(SyncEvent::VersionInfo { version });
+    }
+    fn on_server_listening(&mut self, name: String, url: String) {
+        let _ = self.send(SyncEvent::ServerListening { name, url });
+    }
+    fn on_folder_changed(&mut self, changes: FolderChanges) {
+        let _ = self.send(SyncEvent::FolderChanged(changes));
+    }
+    fn on_torrent_published(&mut self, published: PublishedTorrent) {
+        let _ = self.send(SyncEvent::TorrentPublished(published));
+    }
+    fn on_files_preserved(&mut self, paths: Vec<String>) {
+        let _ = self.send(SyncEvent::FilesPreserved(paths));
+    }
+    fn on_global_seed_goal_met(&mut self, uploaded_bytes: u64, ratio: Option<f64>) {
+        let _ = self.send(SyncEvent::GlobalSeedGoalMet { uploaded_bytes, ratio });
+    }
+    fn on_memory_usage(&mut self, usage: MemoryUsage) {
+        let _ = self.send(SyncEvent::MemoryUsage(usage));
+    }
+    fn on_tracker_test_result(&mut self, result: Result<Vec<TrackerReport>, String>) {
+        let _ = self.send(SyncEvent::TrackerTestResult(result));
+    }
+    fn on_port_check_result(&mut self, result: Result<PortCheck, String>) {
+        let _ = self.send(SyncEvent::PortCheckResult(result));
+    }
+    fn on_config_test_result(&mut self, checks: Vec<ConfigCheck>) {
+        let _ = self.send(SyncEvent::ConfigTestResult { checks });
+    }
+    fn on_url_check_result(&mut self, url: String, result: UrlCheck) {
+        let _ = self.send(SyncEvent::UrlCheckResult { url, result });
+    }
+}
+
+/// Deliver a single event to the matching sink callback
+pub fn dispatch(sink: &mut dyn ProgressSink, event: SyncEvent) {
+    match event {
+        SyncEvent::StatusUpdate(status) => sink.on_status(status),
+        SyncEvent::ManagedTorrentUpdate(torrent) => sink.on_progress(torrent),
+        SyncEvent::TorrentAdded(id) => sink.on_torrent_added(id),
+        SyncEvent::SyncComplete { info_hash, bytes } => sink.on_sync_complete(info_hash, bytes),
+        SyncEvent::Error(message) => sink.on_error(SyncError { message }),
+        SyncEvent::Warning(message) => sink.on_warning(message),
+        SyncEvent::PermissionDenied(path) => sink.on_permission_denied(path),
+        SyncEvent::RemoteUpdateFound(torrent_data) => sink.on_remote_update(torrent_data),
+        SyncEvent::UpdateCountdown(applies_at) => sink.on_update_countdown(applies_at),
+        SyncEvent::UpdateCountdownEnded { applied } => sink.on_update_countdown_ended(applied),
+        SyncEvent::RemoteUpdateSummary(summary) => sink.on_update_summary(summary),
+        SyncEvent::Changelog(text) => sink.on_changelog(text),
+        SyncEvent::ExtraFilesFound(files) => sink.on_extra_files(files),
+        SyncEvent::PartialFilesCleaned(files) => sink.on_partial_files_cleaned(files),
+        SyncEvent::MissingFilesFound(files) => sink.on_missing_files(files),
+        SyncEvent::SeedingConfirmationNeeded(id) => sink.on_seeding_confirmation_needed(id),
+        SyncEvent::NoticeAcknowledgementNeeded { id, notice } => sink.on_notice_acknowledgement_needed(id, notice),
+        SyncEvent::FolderOwnerMismatch { owner, retry } => sink.on_folder_owner_mismatch(owner, *retry),
+        SyncEvent::RemoteResolved(resolved_url) => sink.on_remote_resolved(resolved_url),
+        SyncEvent::FilesRouted(summary) => sink.on_files_routed(summary),
+        SyncEvent::ArchivesExtracted(summary) => sink.on_archives_extracted(summary),
+        SyncEvent::FileModesApplied(summary) => sink.on_file_modes_applied(summary),
+        SyncEvent::FilePrioritized(path) => sink.on_file_prioritized(path),
+        SyncEvent::HttpFallbackFetched(summary) => sink.on_http_fallback_fetched(summary),
+        SyncEvent::SubsetVerified(result) => sink.on_subset_verified(result),
+        SyncEvent::ConfigReloaded { config, changed } => sink.on_config_reloaded(*config, changed),
+        SyncEvent::ConfigApplied { changed } => sink.on_config_applied(changed),
+        SyncEvent::OfflineVerified(result) => sink.on_offline_verified(result),
+        SyncEvent::VersionInfo { version } => sink.on_version_info(version),
+        SyncEvent::ServerListening { name, url } => sink.on_server_listening(name, url),
+        SyncEvent::FolderChanged(changes) => sink.on_folder_changed(changes),
+        SyncEvent::TorrentPublished(published) => sink.on_torrent_published(published),
+        SyncEvent::FilesPreserved(paths) => sink.on_files_preserved(paths),
+        SyncEvent::GlobalSeedGoalMet { uploaded_bytes, ratio } => sink.on_global_seed_goal_met(uploaded_bytes, ratio),
+        SyncEvent::MemoryUsage(usage) => sink.on_memory_usage(usage),
+        SyncEvent::TrackerTestResult(result) => sink.on_tracker_test_result(result),
+        SyncEvent::PortCheckResult(result) => sink.on_port_check_result(result),
+        SyncEvent::ConfigTestResult { checks } => sink.on_config_test_result(checks),
+        SyncEvent::UrlCheckResult { url, result } => sink.on_url_check_result(url, result),
+    }
+}
+
+/// Drive `sink` from an event channel until every sender is dropped
+pub async fn forward_events<S: ProgressSink>(mut rx: mpsc::UnboundedReceiver<SyncEvent>, mut sink: S) -> S {
+    while let Some(event) = rx.recv().await {
+        dispatch(&mut sink, event);
+    }
+    sink
+}
+
+/// Like [`forward_events`], for a channel of stamped events
+pub async fn forward_stamped_events<S: ProgressSink>(mut rx: mpsc::UnboundedReceiver<TimestampedEvent>, mut sink: S) -> S {
+    while let Some(TimestampedEvent { seq, at, event }) = rx.recv().await {
+        sink.on_event_stamp(seq, at);
+        dispatch(&mut sink, event);
+    }
+    sink
+}
+
+/// Run the sync manager, reporting to `sink` instead of a channel.
+/// Returns the manager's result once it stops and all its events have been delivered.
+pub async fn run_sync_manager_with_sink<S: ProgressSink>(
+    config: AppConfig,
+    api: librqbit::Api,
+    sink: S,
+    sync_cmd_rx: mpsc::UnboundedReceiver<SyncCommand>,
+    initial_torrent_id: Option<usize>,
+) -> Result<()> {
+    let (stamped_tx, rx) = mpsc::unbounded_channel();
+    let tx = super::stamp::stamp_events(stamped_tx);
+    let forwarder = forward_stamped_events(rx, sink);
+    // The manager owns the only sender, so the forwarder drains and ends after it returns
+    let (result, _sink) = tokio::join!(
+        super::manager::run_sync_manager(config, api, tx, sync_cmd_rx, initial_torrent_id),
+        forwarder
+    );
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        calls: Vec<String>,
+    }
+
+    impl ProgressSink for Recorder {
+        fn on_status(&mut self, status: SyncStatus) {
+            self.calls.push(format!("status:{}", status.display_text()));
+        }
+        fn on_torrent_added(&mut self, id: usize) {
+            self.calls.push(format!("added:{}", id));
+        }
+        fn on_error(&mut self, error: SyncError) {
+            self.calls.push(format!("error:{}", error));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forward_events_preserves_order_and_skips_defaults() {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tx.send(SyncEvent::TorrentAdded(3)).unwrap();
+        tx.send(SyncEvent::ExtraFilesFound(Vec::new())).unwrap(); // default no-op
+        tx.send(SyncEvent::Error("boom".to_string())).unwrap();
+        tx.send(SyncEvent::StatusUpdate(SyncStatus::Idle)).unwrap();
+        drop(tx);
+
+        let recorder = forward_events(rx, Recorder::default()).await;
+        assert_eq!(
+            recorder.calls,
+            vec!["added:3".to_string(), "error:boom".to_string(), format!("status:{}", SyncStatus::Idle.display_text())]
+        );
+    }
+
+    #[test]
+    fn test_channel_sink_round_trips_events() {
+        let (mut tx, mut rx) = mpsc::unbounded_channel::<SyncEvent>();
+        dispatch(&mut tx, SyncEvent::TorrentAdded(7));
+        assert!(matches!(rx.try_recv(), Ok(SyncEvent::TorrentAdded(7))));
+        dispatch(&mut NoopSink, SyncEvent::TorrentAdded(7));
+    }
+}
@@ -0,0 +1,400 @@
+// src/sync/publish.rs
+
+//! Publisher mode, for mod authors: watch the download folder and, once edits have
+//! settled for `publish_debounce_secs`, build a new .torrent from it, write it to
+//! `publish_torrent_path` for upload to the server and seed it from here. The folder is
+//! polled on the manager tick by size and modification time rather than through file
+//! system notifications, so only the hashing is expensive.
+
+use anyhow::{Context, Result, bail};
+use sha1::{Digest, Sha1};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use walkdir::WalkDir;
+use tracing::{info, warn};
+
+use crate::config::{AppConfig, get_cached_torrent_path};
+
+use super::bencode::{dict_entries, encode_string, parse_string};
+use super::cleaner::is_marker_file;
+use super::ignore::is_ignored;
+use super::messages::SyncEvent;
+use super::torrent::{info_hash_of, manage_torrent_task};
+use super::types::{LocalTorrentState, SyncState};
+
+/// Size and modification time of one file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    pub size: u64,
+    pub modified: Option<SystemTime>, // None when only the size is known (from a torrent)
+}
+
+/// Every file of the folder, keyed by '/'-separated relative path
+pub type FolderSnapshot = BTreeMap<String, FileStamp>;
+
+/// What changed in the folder since the last published torrent
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FolderChanges {
+    pub added: Vec<String>,
+    pub modified: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl FolderChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.removed.is_empty()
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{} added, {} modified, {} removed", self.added.len(), self.modified.len(), self.removed.len())
+    }
+}
+
+/// A torrent built from the folder and written out for upload
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublishedTorrent {
+    pub path: PathBuf,
+    pub info_hash: String,
+    pub file_count: usize,
+    pub total_bytes: u64,
+}
+
+/// Publishing state kept between ticks
+#[derive(Default)]
+pub struct PublishWatcher {
+    published: Option<FolderSnapshot>, // The folder as of the last published torrent
+    last_scan: Option<FolderSnapshot>,
+    changed_at: Option<Instant>, // Last scan that saw an edit, reset by every new edit
+    build: Option<(FolderSnapshot, JoinHandle<Result<Vec<u8>>>)>,
+}
+
+/// Where publisher mode writes the torrent, None when it's off
+pub fn publish_path(config: &AppConfig) -> Option<&Path> {
+    config.publish_torrent_path.as_deref().filter(|path| !path.as_os_str().is_empty())
+}
+
+/// The files a published torrent would hold: everything but marker files and ignored paths
+pub fn scan_folder(root: &Path, ignore_patterns: &[String]) -> FolderSnapshot {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let relative_path = entry.path().strip_prefix(root).ok()?;
+            if is_marker_file(relative_path) || is_ignored(relative_path, ignore_patterns) {
+                return None;
+            }
+            let metadata = entry.metadata().ok()?;
+            let stamp = FileStamp { size: metadata.len(), modified: metadata.modified().ok() };
+            Some((relative_path.to_string_lossy().replace('\\', "/"), stamp))
+        })
+        .collect()
+}
+
+/// Files added, removed, or changed in size or (where both are known) modification time
+pub fn diff_snapshots(old: &FolderSnapshot, new: &FolderSnapshot) -> FolderChanges {
+    let mut changes = FolderChanges::default();
+    for (path, stamp) in new {
+        match old.get(path) {
+            None => changes.added.push(path.clone()),
+            Some(previous) => {
+                let touched = matches!((previous.modified, stamp.modified), (Some(a), Some(b)) if a != b);
+                if previous.size != stamp.size || touched {
+                    changes.modified.push(path.clone());
+                }
+            }
+        }
+    }
+    changes.removed = old.keys().filter(|path| !new.contains_key(*path)).cloned().collect();
+    changes
+}
+
+/// The cached torrent's files as a snapshot with sizes only
+fn torrent_snapshot(torrent_bytes: &[u8]) -> Option<FolderSnapshot> {
+    let (_, entries) = super::file_list::file_list_from_torrent(torrent_bytes).ok()?;
+    Some(entries.into_iter().map(|entry| (entry.path, FileStamp { size: entry.size, modified: None })).collect())
+}
+
+/// Aim for about 1500 pieces, between 256 KiB and 16 MiB each
+pub fn choose_piece_length(total_bytes: u64) -> u64 {
+    (total_bytes / 1500).clamp(256 * 1024, 16 * 1024 * 1024).next_power_of_two().min(16 * 1024 * 1024)
+}
+
+fn encode_int(value: u64) -> Vec<u8> {
+    format!("i{}e", value).into_bytes()
+}
+
+/// A bencode dict from entries whose values are already encoded; keys come out sorted
+fn encode_dict(entries: BTreeMap<&[u8], Vec<u8>>) -> Vec<u8> {
+    let mut output = vec![b'd'];
+    for (key, value) in entries {
+        output.extend(encode_string(key));
+        output.extend(value);
+    }
+    output.push(b'e');
+    output
+}
+
+/// Build a torrent of `files` under `root`. Trackers, comment, name, piece length and the
+/// private flag are taken from `template` (the torrent being replaced) when there is one.
+pub fn build_torrent(root: &Path, files: &FolderSnapshot, template: Option<&[u8]>, created: u64) -> Result<Vec<u8>> {
+    if files.is_empty() {
+        bail!("Nothing to publish: {} has no files", root.display());
+    }
+    let template_entries = template.map(dict_entries).transpose().context("Failed to read the current torrent")?;
+    let template_value = |key: &[u8]| template_entries.as_ref()?.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+    let template_info = template_value(b"info").map(dict_entries).transpose()?;
+    let info_value = |key: &[u8]| template_info.as_ref()?.iter().find(|(k, _)| *k == key).map(|(_, v)| *v);
+
+    let name = match info_value(b"name") {
+        Some(raw) => parse_string(raw)?.0.to_vec(),
+        None => root.file_name().map_or("mods".into(), |name| name.to_string_lossy().into_owned()).into_bytes(),
+    };
+    let total_bytes: u64 = files.values().map(|stamp| stamp.size).sum();
+    let piece_length = match info_value(b"piece length") {
+        Some(raw) => std::str::from_utf8(raw)?.trim_start_matches('i').trim_end_matches('e').parse()?,
+        None => choose_piece_length(total_bytes),
+    };
+
+    // Hash the files back to back, as if they were one stream
+    let mut pieces = Vec::new();
+    let mut piece = Vec::with_capacity(piece_length as usize);
+    let mut file_entries = vec![b'l'];
+    for (path, stamp) in files {
+        let full_path = root.join(path);
+        let mut file = std::fs::File::open(&full_path).with_context(|| format!("Failed to open {}", full_path.display()))?;
+        let mut read = 0u64;
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            let n = file.read(&mut buffer).with_context(|| format!("Failed to read {}", full_path.display()))?;
+            if n == 0 {
+                break;
+            }
+            read += n as u64;
+            let mut chunk = &buffer[..n];
+            while !chunk.is_empty() {
+                let take = chunk.len().min(piece_length as usize - piece.len());
+                piece.extend_from_slice(&chunk[..take]);
+                chunk = &chunk[take..];
+                if piece.len() == piece_length as usize {
+                    pieces.extend_from_slice(&Sha1::digest(&piece));
+                    piece.clear();
+                }
+            }
+        }
+        if read != stamp.size {
+            bail!("{} changed while it was being hashed", path);
+        }
+
+        let mut path_list = vec![b'l'];
+        for segment in path.split('/') {
+            path_list.extend(encode_string(segment.as_bytes()));
+        }
+        path_list.push(b'e');
+        file_entries.extend(encode_dict(BTreeMap::from([
+            (&b"length"[..], encode_int(stamp.size)),
+            (&b"path"[..], path_list),
+        ])));
+    }
+    if !piece.is_empty() {
+        pieces.extend_from_slice(&Sha1::digest(&piece));
+    }
+    file_entries.push(b'e');
+
+    let mut info = BTreeMap::from([
+        (&b"files"[..], file_entries),
+        (&b"name"[..], encode_string(&name)),
+        (&b"piece length"[..], encode_int(piece_length)),
+        (&b"pieces"[..], encode_string(&pieces)),
+    ]);
+    if let Some(private) = info_value(b"private") {
+        info.insert(b"private", private.to_vec());
+    }
+
+    let mut torrent = BTreeMap::from([
+        (&b"created by"[..], encode_string(format!("ModSync/{}", env!("CARGO_PKG_VERSION")).as_bytes())),
+        (&b"creation date"[..], encode_int(created)),
+        (&b"info"[..], encode_dict(info)),
+    ]);
+    for key in [&b"announce"[..], b"announce-list", b"comment"] {
+        if let Some(value) = template_value(key) {
+            torrent.insert(key, value.to_vec());
+        }
+    }
+    Ok(encode_dict(torrent))
+}
+
+/// Write the torrent next to its destination first, so the server never serves half a file
+fn write_published(path: &Path, torrent: &[u8]) -> Result<()> {
+    let temp_path = path.with_extension("torrent.tmp");
+    std::fs::write(&temp_path, torrent).with_context(|| format!("Failed to write {}", temp_path.display()))?;
+    std::fs::rename(&temp_path, path).with_context(|| format!("Failed to move the torrent to {}", path.display()))
+}
+
+/// Write out a finished build and seed it in place of the current torrent
+async fn finish_publish(
+    config: &AppConfig,
+    state: &mut SyncState,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+    output: &Path,
+    snapshot: &FolderSnapshot,
+    torrent: Vec<u8>,
+) -> Result<()> {
+    let info_hash = info_hash_of(&torrent).context("Built torrent is unreadable")?;
+    let cache_path = get_cached_torrent_path()?;
+    let current = std::fs::read(&cache_path).ok();
+    if current.as_deref().and_then(info_hash_of).as_deref() == Some(info_hash.as_str()) && output.exists() {
+        info!("Sync: Folder content is unchanged, not publishing");
+        return Ok(());
+    }
+
+    write_published(output, &torrent)?;
+    std::fs::write(&cache_path, &torrent).with_context(|| format!("Failed to write {}", cache_path.display()))?;
+    let published = PublishedTorrent {
+        path: output.to_path_buf(),
+        info_hash,
+        file_count: snapshot.len(),
+        total_bytes: snapshot.values().map(|stamp| stamp.size).sum(),
+    };
+    info!("Sync: Published {} ({} files) to {}", published.info_hash, published.file_count, output.display());
+    let _ = ui_tx.send(SyncEvent::TorrentPublished(published));
+
+    let current_id = match state.local {
+        LocalTorrentState::Active { id } => Some(id),
+        LocalTorrentState::NotLoaded => None,
+    };
+    let new_id = manage_torrent_task(config, api, ui_tx, current_id, torrent).await?;
+    state.local = match new_id {
+        Some(id) => LocalTorrentState::Active { id },
+        None => LocalTorrentState::NotLoaded,
+    };
+    Ok(())
+}
+
+/// Scan the folder, report edits, and publish once they have settled
+pub async fn check_publish(
+    config: &AppConfig,
+    state: &mut SyncState,
+    watcher: &mut PublishWatcher,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+) {
+    let Some(output) = publish_path(config) else {
+        *watcher = PublishWatcher::default();
+        return;
+    };
+
+    // Finish a build started on an earlier tick
+    if watcher.build.as_ref().is_some_and(|(_, handle)| handle.is_finished())
+        && let Some((snapshot, handle)) = watcher.build.take()
+    {
+        let result = match handle.await {
+            Ok(Ok(torrent)) => finish_publish(config, state, api, ui_tx, output, &snapshot, torrent).await,
+            Ok(Err(e)) => Err(e),
+            Err(e) => Err(anyhow::anyhow!("Torrent build stopped: {}", e)),
+        };
+        if let Err(e) = result {
+            let message = format!("Failed to publish torrent: {:#}", e);
+            warn!("Sync: {}", message);
+            let _ = ui_tx.send(SyncEvent::Error(message));
+        }
+        // Either way, wait for the next edit before building again
+        watcher.published = Some(snapshot);
+    }
+    if watcher.build.is_some() {
+        return;
+    }
+
+    let (root, patterns) = (config.download_path.clone(), config.ignore_patterns.clone());
+    let Ok(scan) = tokio::task::spawn_blocking(move || scan_folder(&root, &patterns)).await else { return };
+
+    let published = match &watcher.published {
+        Some(published) => published.clone(),
+        None => {
+            // Edits made while ModSync was closed show up against the cached torrent
+            let cached = get_cached_torrent_path().ok().and_then(|path| std::fs::read(path).ok());
+            let baseline = cached.as_deref().and_then(torrent_snapshot).unwrap_or_else(|| scan.clone());
+            watcher.published = Some(baseline.clone());
+            baseline
+        }
+    };
+    if watcher.last_scan.as_ref() != Some(&scan) {
+        let changes = diff_snapshots(&published, &scan);
+        if changes.is_empty() {
+            watcher.changed_at = None;
+        } else {
+            info!("Sync: Folder changed: {}", changes.describe());
+            let _ = ui_tx.send(SyncEvent::FolderChanged(changes));
+            watcher.changed_at = Some(Instant::now());
+        }
+        watcher.last_scan = Some(scan);
+        return;
+    }
+
+    let settled = watcher.changed_at.is_some_and(|at| at.elapsed().as_secs() >= config.publish_debounce_secs);
+    if settled {
+        watcher.changed_at = None;
+        info!("Sync: Edits have settled, building a new torrent");
+        let root = config.download_path.clone();
+        let template = get_cached_torrent_path().ok().and_then(|path| std::fs::read(path).ok());
+        let created = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let files = scan.clone();
+        let handle = tokio::task::spawn_blocking(move || build_torrent(&root, &files, template.as_deref(), created));
+        watcher.build = Some((scan, handle));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_snapshots() {
+        let stamp = |size, secs| FileStamp { size, modified: Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs)) };
+        let old = FolderSnapshot::from([
+            ("a.pbo".to_string(), stamp(10, 1)),
+            ("b.pbo".to_string(), stamp(10, 1)),
+            ("c.pbo".to_string(), FileStamp { size: 10, modified: None }),
+        ]);
+        let new = FolderSnapshot::from([
+            ("a.pbo".to_string(), stamp(10, 2)),
+            ("c.pbo".to_string(), stamp(10, 5)),
+            ("d.pbo".to_string(), stamp(1, 1)),
+        ]);
+        let changes = diff_snapshots(&old, &new);
+        assert_eq!(changes.added, vec!["d.pbo"]);
+        assert_eq!(changes.modified, vec!["a.pbo"], "unknown times only compare sizes");
+        assert_eq!(changes.removed, vec!["b.pbo"]);
+    }
+
+    #[test]
+    fn test_built_torrent_keeps_trackers_and_hashes_folder() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        std::fs::create_dir_all(dir.path().join("@mod/addons"))?;
+        std::fs::write(dir.path().join("@mod/addons/a.pbo"), vec![7u8; 300 * 1024])?;
+        std::fs::write(dir.path().join("@mod/mod.cpp"), b"name = \"Mod\";")?;
+        std::fs::write(dir.path().join(".modsync_owner"), b"owner")?;
+        std::fs::write(dir.path().join("notes.txt"), b"local")?;
+
+        let files = scan_folder(dir.path(), &["notes.txt".to_string()]);
+        assert_eq!(files.keys().collect::<Vec<_>>(), vec!["@mod/addons/a.pbo", "@mod/mod.cpp"]);
+
+        let template = b"d8:announce21:http://t.example/anno4:infod4:name4:Mods12:piece lengthi262144e6:pieces0:7:privatei1eee";
+        let torrent = build_torrent(dir.path(), &files, Some(template), 1_700_000_000)?;
+        let parsed = librqbit::torrent_from_bytes::<librqbit::ByteBuf>(&torrent)?;
+        assert_eq!(parsed.announce.as_ref().map(|url| url.as_ref().to_vec()), Some(b"http://t.example/anno".to_vec()));
+        assert_eq!(parsed.info.name.as_ref().map(|name| name.as_ref().to_vec()), Some(b"Mods".to_vec()));
+        assert!(super::super::torrent::is_private_torrent(&torrent));
+        assert_eq!(parsed.info.piece_length, 262144);
+        assert_eq!(parsed.info.pieces.as_ref().len(), 2 * 20, "300 KiB + 13 bytes is two pieces");
+
+        let listed = super::super::file_list::file_list_from_torrent(&torrent)?.1;
+        assert_eq!(listed.iter().map(|entry| entry.path.as_str()).collect::<Vec<_>>(), vec!["@mod/addons/a.pbo", "@mod/mod.cpp"]);
+        Ok(())
+    }
+}
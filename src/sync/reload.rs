@@ -0,0 +1,126 @@
+// src/sync/reload.rs
+
+//! Picks up edits made to `config.toml` outside ModSync, by hand or by config management
+//! tooling, without a restart. A reload reads the file again (with the environment and
+//! `--set` overrides on top, as at startup) and applies it like **Save Configuration**:
+//! the torrent is only re-checked when the URL or path changed. With
+//! `reload_config_on_change` the file is watched and reloaded whenever it's written.
+
+use std::path::Path;
+use std::time::SystemTime;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::config::{AppConfig, ConfigStore};
+
+use super::messages::SyncEvent;
+
+/// Settings that differ between `old` and `new`, by their config.toml key
+pub fn changed_keys(old: &AppConfig, new: &AppConfig) -> Vec<String> {
+    let (Ok(old), Ok(new)) = (toml::Table::try_from(old), toml::Table::try_from(new)) else {
+        return Vec::new();
+    };
+    let mut keys: Vec<String> = old
+        .keys()
+        .chain(new.keys())
+        .filter(|key| old.get(*key) != new.get(*key))
+        .cloned()
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+/// Follows the config file's modification time between ticks
+#[derive(Debug, Default)]
+pub struct ConfigFileWatch {
+    modified: Option<SystemTime>,
+    settling: bool, // Written since; waiting a tick in case the writer isn't done
+}
+
+impl ConfigFileWatch {
+    /// Whether the file at `path` was written and has since been left alone for a call.
+    /// The first call only takes note of it.
+    pub fn changed(&mut self, path: &Path) -> bool {
+        let Ok(modified) = std::fs::metadata(path).and_then(|meta| meta.modified()) else {
+            return false;
+        };
+        match self.modified.replace(modified) {
+            Some(before) if before != modified => {
+                self.settling = true;
+                false
+            }
+            Some(_) => std::mem::take(&mut self.settling),
+            None => false,
+        }
+    }
+}
+
+/// Read the config from `store` again. Returns the new config when anything changed, or
+/// None when nothing did or it couldn't be read, which keeps the running config.
+/// `report_unchanged` also tells the UI when nothing changed.
+pub fn reload_config(
+    store: &dyn ConfigStore,
+    current: &AppConfig,
+    report_unchanged: bool,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+) -> Option<AppConfig> {
+    let new_config = match store.reload() {
+        Ok(config) => config,
+        Err(e) => {
+            warn!("Sync: Config not reloaded, keeping the running one: {:#}", e);
+            let _ = ui_tx.send(SyncEvent::Error(format!("Config not reloaded: {:#}", e)));
+            return None;
+        }
+    };
+    let changed = changed_keys(current, &new_config);
+    if changed.is_empty() && !report_unchanged {
+        return None;
+    }
+    info!("Sync: Config reloaded from disk, changed: {:?}", changed);
+    let _ = ui_tx.send(SyncEvent::ConfigReloaded { config: Box::new(new_config.clone()), changed: changed.clone() });
+    (!changed.is_empty()).then_some(new_config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changed_keys_and_file_watch() -> anyhow::Result<()> {
+        let old = AppConfig::default();
+        let new = AppConfig { max_upload_speed: Some(100), torrent_url: "http://new/a.torrent".to_string(), ..AppConfig::default() };
+        assert_eq!(changed_keys(&old, &new), ["max_upload_speed", "torrent_url"]);
+        // A setting that was cleared counts too
+        assert_eq!(changed_keys(&new, &AppConfig { max_upload_speed: None, ..new.clone() }), ["max_upload_speed"]);
+        assert!(changed_keys(&old, &old).is_empty());
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("config.toml");
+        let mut watch = ConfigFileWatch::default();
+        assert!(!watch.changed(&path), "no file yet");
+        std::fs::write(&path, "should_seed = true\n")?;
+        assert!(!watch.changed(&path), "first sight only takes note");
+        let file = std::fs::File::options().write(true).open(&path)?;
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(5))?;
+        assert!(!watch.changed(&path), "waits a call for the write to settle");
+        assert!(watch.changed(&path));
+        assert!(!watch.changed(&path));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reload_from_a_store() {
+        let (ui_tx, mut ui_rx) = mpsc::unbounded_channel();
+        let current = AppConfig::default();
+        let store = crate::config::MemoryConfigStore::with_contents("torrent_url = \"http://new/a.torrent\"\n");
+        let reloaded = reload_config(&store, &current, false, &ui_tx).map(|config| config.torrent_url);
+        assert_eq!(reloaded.as_deref(), Some("http://new/a.torrent"));
+        assert!(matches!(ui_rx.try_recv(), Ok(SyncEvent::ConfigReloaded { .. })));
+
+        // A damaged config keeps the running one and says why
+        let damaged = crate::config::MemoryConfigStore::with_contents("torrent_url = [");
+        assert!(reload_config(&damaged, &current, false, &ui_tx).is_none());
+        assert!(matches!(ui_rx.try_recv(), Ok(SyncEvent::Error(_))));
+    }
+}
@@ -2,17 +2,30 @@
 
 //! Operations related to the remote torrent state
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, OnMissingTorrent, UpdateStrategy};
 use reqwest;
+use tracing::{error, info, warn};
+use std::collections::HashSet;
 use tokio::sync::mpsc;
 
 use crate::config::get_cached_torrent_path;
 use crate::ui::utils::SyncStatus;
 
+use super::changelog::send_changelog;
+use super::http::{FailureKind, failure_kind};
+use super::version::record_version;
+use super::cleaner::{
+    find_partial_files, get_all_files_from_details, get_expected_files_from_details, remove_orphaned_partial_files,
+};
 use super::local::refresh_managed_torrent_status_event;
+use super::staging::start_staged_update;
 use super::messages::SyncEvent;
+use super::summary::summarize_update;
 use super::types::{LocalTorrentState, RemoteTorrentState, SyncState};
-use super::utils::{download_torrent, calculate_torrent_hash, get_local_torrent_hash, send_sync_status_event};
+use super::utils::{
+    FetchedTorrent, RemoteMeta, calculate_torrent_hash, fetch_torrent_retrying, get_local_torrent_hash, load_remote_meta,
+    save_remote_meta, send_sync_status_event,
+};
 use super::manage_torrent_task;
 
 /// Function to apply a remote update
@@ -23,6 +36,33 @@ pub async fn apply_remote_update(
     ui_tx: &mpsc::UnboundedSender<SyncEvent>,
     torrent_content: Vec<u8>,
 ) -> bool {
+    // Stage the update beside the live folder; a first download has nothing to protect
+    if config.update_strategy == UpdateStrategy::AtomicSwap && matches!(state.local, LocalTorrentState::Active { .. }) {
+        return match start_staged_update(config, state, api, ui_tx, torrent_content).await {
+            Ok(()) => true,
+            Err(e) => {
+                let err_msg = format!("Sync error staging update: {:#}", e);
+                warn!("Sync: {}", err_msg);
+                let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
+                send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
+                false
+            }
+        };
+    }
+    // The update becomes the cached torrent, with the metadata of where it came from
+    let pending = state.pending_update.take().filter(|(hash, _)| *hash == calculate_torrent_hash(&torrent_content));
+    if let Ok(cache_path) = get_cached_torrent_path() {
+        info!("Sync: Saving torrent to cache: {}", cache_path.display());
+        match tokio::fs::write(&cache_path, &torrent_content).await {
+            Ok(()) => {
+                if let Some((_, meta)) = pending {
+                    record_remote_meta(ui_tx, &meta);
+                }
+            }
+            Err(e) => error!("Sync: Failed to write to cache file {}: {}", cache_path.display(), e),
+        }
+    }
+
     send_sync_status_event(ui_tx, SyncStatus::UpdatingTorrent);
 
     // Get current torrent ID to forget if we have one
@@ -31,6 +71,11 @@ pub async fn apply_remote_update(
         LocalTorrentState::NotLoaded => None,
     };
 
+    // Note what the old torrent hadn't finished, while it's still in the session
+    let old_partial_files = current_id_to_forget
+        .map(|id| partial_files_of(api, id))
+        .unwrap_or_default();
+
     // Process the update with the torrent manager
     match manage_torrent_task(
         config,
@@ -42,7 +87,7 @@ pub async fn apply_remote_update(
     .await
     {
         Ok(new_id) => {
-            println!(
+            info!(
                 "Sync: Torrent task managed successfully. New ID: {:?}",
                 new_id
             );
@@ -54,6 +99,7 @@ pub async fn apply_remote_update(
             };
 
             if let LocalTorrentState::Active { id } = state.local {
+                clean_orphaned_partial_files(config, api, ui_tx, id, &old_partial_files);
                 refresh_managed_torrent_status_event(api, ui_tx, id);
             }
             // Let status be updated by refresh or next cycle
@@ -61,7 +107,7 @@ pub async fn apply_remote_update(
         }
         Err(e) => {
             let err_msg = format!("Sync error managing torrent: {}", e);
-            eprintln!("Sync: {}", err_msg);
+            warn!("Sync: {}", err_msg);
             let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
             send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
             false
@@ -69,126 +115,215 @@ pub async fn apply_remote_update(
     }
 }
 
-/// Function to directly download a remote torrent and compare with local
+/// Relative paths of the torrent's files that are still incomplete
+fn partial_files_of(api: &librqbit::Api, id: usize) -> HashSet<std::path::PathBuf> {
+    match (api.api_torrent_details(id.into()), api.api_stats_v1(id.into())) {
+        (Ok(details), Ok(stats)) => find_partial_files(&details, &stats.file_progress),
+        _ => HashSet::new(),
+    }
+}
+
+/// Remove partial downloads left by the previous torrent that the new torrent
+/// `id` no longer references, and report them
+fn clean_orphaned_partial_files(
+    config: &AppConfig,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+    id: usize,
+    old_partial_files: &HashSet<std::path::PathBuf>,
+) {
+    if old_partial_files.is_empty() || config.safe_mode {
+        return;
+    }
+    // Without the new file list we can't tell what's safe to delete
+    let new_files = match api.api_torrent_details(id.into()) {
+        Ok(details) => get_all_files_from_details(&details),
+        Err(e) => {
+            warn!("Sync: Skipping partial file cleanup, no details for torrent {}: {}", id, e);
+            return;
+        }
+    };
+    let removed = remove_orphaned_partial_files(&config.download_path, old_partial_files, &new_files, &config.ignore_patterns);
+    if !removed.is_empty() {
+        info!("Sync: Removed {} partial files the updated torrent no longer has", removed.len());
+        let _ = ui_tx.send(SyncEvent::PartialFilesCleaned(removed));
+    }
+}
+
+/// Why an update check failed: the message shown, and what kind of failure it was
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckFailure {
+    pub message: String,
+    pub kind: FailureKind,
+}
+
+/// Function to directly download a remote torrent and compare with local.
+/// Returns the error if the check failed (it has already been reported).
 pub async fn direct_download_and_compare(
     config: &AppConfig,
     state: &mut SyncState,
-    _api: &librqbit::Api,
+    api: &librqbit::Api,
     ui_tx: &mpsc::UnboundedSender<SyncEvent>,
     http_client: &reqwest::Client,
-) {
+) -> Result<(), CheckFailure> {
     if config.torrent_url.is_empty() {
-        println!("Sync: No remote URL configured, skipping direct download.");
+        info!("Sync: No remote URL configured, skipping direct download.");
         send_sync_status_event(ui_tx, SyncStatus::Idle);
-        return;
+        return Ok(());
     }
 
-    println!(
+    info!(
         "Sync: Directly downloading torrent from {}...",
         config.torrent_url
     );
     send_sync_status_event(ui_tx, SyncStatus::CheckingRemote);
 
+    // Only ask for a conditional response when the cache holds what that ETag describes
+    let known_etag = load_remote_meta()
+        .filter(|meta| meta.source_url == config.torrent_url)
+        .filter(|_| get_cached_torrent_path().is_ok_and(|path| path.exists()))
+        .and_then(|meta| meta.etag);
+
     // Download the remote torrent file
-    match download_torrent(&config.torrent_url, http_client).await {
-        Ok(remote_torrent) => {
-            println!(
-                "Sync: Downloaded remote torrent successfully ({} bytes)",
-                remote_torrent.len()
-            );
+    let (remote_torrent, remote_meta) = match fetch_torrent_retrying(&config.torrent_url, http_client, known_etag.as_deref()).await {
+        Ok(FetchedTorrent::Modified { data, meta }) => (data, meta),
+        Ok(FetchedTorrent::NotModified(meta)) => {
+            info!("Sync: Remote torrent not modified (ETag match), skipping download.");
+            record_remote_meta(ui_tx, &meta);
+            // The version file can change without the torrent
+            if let Ok(cached) = get_cached_torrent_path().and_then(|path| Ok(std::fs::read(path)?)) {
+                record_version(config, http_client, &cached).await;
+            }
+            send_sync_status_event(ui_tx, SyncStatus::Idle);
+            return Ok(());
+        }
+        // The files on disk are still good, so a removed torrent needn't stop seeding
+        Ok(FetchedTorrent::Missing(status))
+            if config.on_missing_torrent == OnMissingTorrent::KeepAndSeed
+                && matches!(state.local, LocalTorrentState::Active { .. }) =>
+        {
+            let warning = format!("The torrent URL answered {}. Seeding the last torrent fetched.", status);
+            info!("Sync: {}", warning);
+            let _ = ui_tx.send(SyncEvent::Warning(warning));
+            send_sync_status_event(ui_tx, SyncStatus::Idle);
+            return Ok(());
+        }
+        Ok(FetchedTorrent::Missing(status)) => {
+            let err_msg = format!("Failed to download remote torrent: HTTP error: {}", status);
+            warn!("Sync: {}", err_msg);
+            let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
+            send_sync_status_event(ui_tx, SyncStatus::Error(err_msg.clone()));
+            return Err(CheckFailure { message: err_msg, kind: FailureKind::HttpStatus(status.as_u16()) });
+        }
+        Err(e) => {
+            let err_msg = format!("Failed to download remote torrent: {}", e);
+            warn!("Sync: {}", err_msg);
+            let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
+            send_sync_status_event(ui_tx, SyncStatus::Error(err_msg.clone()));
+            return Err(CheckFailure { message: err_msg, kind: failure_kind(&e) });
+        }
+    };
+    info!(
+        "Sync: Downloaded remote torrent successfully ({} bytes)",
+        remote_torrent.len()
+    );
+    record_version(config, http_client, &remote_torrent).await;
 
-            // Calculate hash of remote torrent
-            let remote_hash = calculate_torrent_hash(&remote_torrent);
-            println!("Sync: Remote torrent hash: {}", remote_hash);
-
-            // Get local torrent hash (if exists)
-            let local_hash_result = get_local_torrent_hash().await;
-
-            match local_hash_result {
-                Ok(Some(local_hash)) => {
-                    println!("Sync: Local torrent hash: {}", local_hash);
-
-                    // Compare hashes
-                    if remote_hash != local_hash {
-                        println!(
-                            "Sync: Torrent has changed! Remote hash different from local hash."
-                        );
-
-                        // Save the new torrent to cache
-                        if let Ok(cache_path) = get_cached_torrent_path() {
-                            println!(
-                                "Sync: Saving updated torrent to cache: {}",
-                                cache_path.display()
-                            );
-                            if let Err(e) = tokio::fs::write(&cache_path, &remote_torrent).await {
-                                eprintln!(
-                                    "Sync: WARNING - Failed to write to cache file {}: {}",
-                                    cache_path.display(),
-                                    e
-                                );
-                            }
-                        }
-
-                        // Update the remote state
-                        state.remote = RemoteTorrentState::UpdateAvailable;
-
-                        // Send update message to UI
-                        if let Err(e) = ui_tx.send(SyncEvent::RemoteUpdateFound(remote_torrent)) {
-                            let err_msg =
-                                format!("Failed to send update notification to UI: {}", e);
-                            eprintln!("Sync: {}", err_msg);
-                            send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
-                        } else {
-                            send_sync_status_event(ui_tx, SyncStatus::RemoteChanged);
-                        }
-                    } else {
-                        println!("Sync: Torrent is unchanged. Local and remote hashes match.");
-                        send_sync_status_event(ui_tx, SyncStatus::Idle);
-                    }
-                }
-                Ok(None) => {
-                    println!("Sync: No local torrent found. This is a new torrent.");
-
-                    // Save the new torrent to cache
-                    if let Ok(cache_path) = get_cached_torrent_path() {
-                        println!(
-                            "Sync: Saving new torrent to cache: {}",
-                            cache_path.display()
-                        );
-                        if let Err(e) = tokio::fs::write(&cache_path, &remote_torrent).await {
-                            eprintln!(
-                                "Sync: WARNING - Failed to write to cache file {}: {}",
-                                cache_path.display(),
-                                e
-                            );
-                        }
-                    }
-
-                    // Update the remote state
-                    state.remote = RemoteTorrentState::UpdateAvailable;
-
-                    // Send update message to UI
-                    if let Err(e) = ui_tx.send(SyncEvent::RemoteUpdateFound(remote_torrent)) {
-                        let err_msg = format!("Failed to send update notification to UI: {}", e);
-                        eprintln!("Sync: {}", err_msg);
-                        send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
-                    } else {
-                        send_sync_status_event(ui_tx, SyncStatus::RemoteChanged);
-                    }
-                }
-                Err(e) => {
-                    let err_msg = format!("Failed to get local torrent hash: {}", e);
-                    eprintln!("Sync: {}", err_msg);
-                    let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
-                    send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
+    // Calculate hash of remote torrent
+    let remote_hash = calculate_torrent_hash(&remote_torrent);
+    info!("Sync: Remote torrent hash: {}", remote_hash);
+
+    // Get local torrent hash (if exists)
+    let local_hash_result = get_local_torrent_hash().await;
+
+    match local_hash_result {
+        _ if state.staged.as_ref().is_some_and(|staged| calculate_torrent_hash(&staged.torrent_content) == remote_hash) => {
+            info!("Sync: This update is already downloading into the staging folder.");
+            Ok(())
+        }
+        Ok(Some(local_hash)) if local_hash == remote_hash => {
+            info!("Sync: Torrent is unchanged. Local and remote hashes match.");
+            record_remote_meta(ui_tx, &remote_meta);
+            send_sync_status_event(ui_tx, SyncStatus::Idle);
+            Ok(())
+        }
+        Ok(local_hash) => {
+            match local_hash {
+                Some(local_hash) => {
+                    info!("Sync: Local torrent hash: {}", local_hash);
+                    info!("Sync: Torrent has changed! Remote hash different from local hash.");
                 }
+                None => info!("Sync: No local torrent found. This is a new torrent."),
+            }
+
+            // The cache (and the ETag) keep matching the loaded torrent until the update is
+            // applied, so one that wasn't confirmed is found again after a restart instead
+            // of being loaded without asking
+            if state.pending_update.as_ref().is_some_and(|(hash, _)| *hash == remote_hash) {
+                info!("Sync: This update is already waiting to be applied.");
+                send_sync_status_event(ui_tx, SyncStatus::RemoteChanged);
+                return Ok(());
+            }
+            let _ = ui_tx.send(SyncEvent::RemoteResolved(remote_meta.resolved_url.clone()));
+            state.pending_update = Some((remote_hash.clone(), remote_meta));
+
+            // Update the remote state
+            state.remote = RemoteTorrentState::UpdateAvailable;
+            
+            if config.confirm_before_update {
+                send_changelog(config, http_client, ui_tx).await;
+                send_update_summary(config, state, api, ui_tx, &remote_torrent);
             }
+
+            // Send update message to UI
+            if let Err(e) = ui_tx.send(SyncEvent::RemoteUpdateFound(remote_torrent)) {
+                let err_msg = format!("Failed to send update notification to UI: {}", e);
+                warn!("Sync: {}", err_msg);
+                send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
+            } else {
+                send_sync_status_event(ui_tx, SyncStatus::RemoteChanged);
+            }
+            Ok(())
         }
         Err(e) => {
-            let err_msg = format!("Failed to download remote torrent: {}", e);
-            eprintln!("Sync: {}", err_msg);
+            let err_msg = format!("Failed to get local torrent hash: {}", e);
+            warn!("Sync: {}", err_msg);
             let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
-            send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
+            send_sync_status_event(ui_tx, SyncStatus::Error(err_msg.clone()));
+            Err(CheckFailure { message: err_msg, kind: FailureKind::Other })
         }
     }
-}
\ No newline at end of file
+}
+
+/// Work out what applying the update would download, for the confirmation prompt
+fn send_update_summary(
+    config: &AppConfig,
+    state: &SyncState,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+    torrent_bytes: &[u8],
+) {
+    let current_files = match state.local {
+        LocalTorrentState::Active { id } => api
+            .api_torrent_details(id.into())
+            .map(|details| get_expected_files_from_details(&details))
+            .unwrap_or_default(),
+        LocalTorrentState::NotLoaded => HashSet::new(),
+    };
+    match summarize_update(&config.download_path, torrent_bytes, &current_files) {
+        Ok(summary) => {
+            info!("Sync: Update would change {} files ({} bytes)", summary.files_changed, summary.bytes_to_download);
+            let _ = ui_tx.send(SyncEvent::RemoteUpdateSummary(summary));
+        }
+        // The prompt still appears, just without the numbers
+        Err(e) => warn!("Sync: Could not summarize update: {}", e),
+    }
+}
+
+/// Persist what the URL resolved to for the cached torrent and tell the UI
+fn record_remote_meta(ui_tx: &mpsc::UnboundedSender<SyncEvent>, meta: &RemoteMeta) {
+    if let Err(e) = save_remote_meta(meta) {
+        warn!("Sync: {}", e);
+    }
+    let _ = ui_tx.send(SyncEvent::RemoteResolved(meta.resolved_url.clone()));
+}
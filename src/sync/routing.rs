@@ -0,0 +1,183 @@
+// src/sync/routing.rs
+
+//! Route rules: once the torrent is complete, files matching a rule's pattern are also
+//! placed in that rule's destination folder, such as configs into a game's profile dir.
+//! librqbit has no per-file output paths and moving files would break seeding, so the
+//! originals stay put and a copy goes to the destination. Copies rather than links, so
+//! editing a routed config doesn't change the seeded file.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+use crate::config::{AppConfig, RouteRule};
+use crate::glob::glob_match;
+
+use super::ignore::validate_pattern;
+use super::messages::SyncEvent;
+use super::types::{LocalTorrentState, SyncState};
+
+/// Files placed in one destination folder
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoutedFolder {
+    pub destination: PathBuf,
+    pub files: usize,
+}
+
+/// How the files of the torrent were routed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RouteSummary {
+    pub folders: Vec<RoutedFolder>,
+    pub failed: Vec<(PathBuf, String)>, // File or rule, and why it couldn't be routed
+}
+
+/// Why `rule` can't be used. Destinations must be absolute and outside the download
+/// folder, or the routed files would show up there as extra files.
+pub fn validate_rule(rule: &RouteRule, download_path: &Path) -> Result<(), String> {
+    validate_pattern(&rule.pattern)?;
+    if !rule.destination.is_absolute() {
+        return Err("Destination must be an absolute path".to_string());
+    }
+    if rule.destination.starts_with(download_path) {
+        return Err("Destination can't be inside the download folder".to_string());
+    }
+    Ok(())
+}
+
+/// Where each of `files` goes: under the first matching rule's destination, keeping its
+/// path relative to the download folder. Files no rule matches aren't listed.
+pub fn plan_routes<'a>(files: &'a [String], rules: &'a [RouteRule]) -> Vec<(&'a str, &'a RouteRule)> {
+    files
+        .iter()
+        .filter_map(|file| rules.iter().find(|rule| glob_match(&rule.pattern, file)).map(|rule| (file.as_str(), rule)))
+        .collect()
+}
+
+/// Copy `source` to `target`, unless a target of the same size that isn't older is
+/// already there from an earlier run
+fn place_file(source: &Path, target: &Path) -> std::io::Result<()> {
+    let source_meta = fs::metadata(source)?;
+    if let Ok(target_meta) = fs::metadata(target)
+        && target_meta.len() == source_meta.len()
+        && target_meta.modified()? >= source_meta.modified()?
+    {
+        return Ok(());
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(source, target)?;
+    Ok(())
+}
+
+/// Place the matching `files` ('/'-separated, relative to `download_path`) per `rules`
+pub fn route_files(download_path: &Path, files: &[String], rules: &[RouteRule]) -> RouteSummary {
+    let mut summary = RouteSummary::default();
+    let mut valid = Vec::new();
+    for rule in rules {
+        match validate_rule(rule, download_path) {
+            Ok(()) => valid.push(rule.clone()),
+            Err(e) => summary.failed.push((PathBuf::from(&rule.pattern), e)),
+        }
+    }
+    for (file, rule) in plan_routes(files, &valid) {
+        let target = rule.destination.join(file);
+        match place_file(&download_path.join(file), &target) {
+            Ok(()) => match summary.folders.iter_mut().find(|f| f.destination == rule.destination) {
+                Some(folder) => folder.files += 1,
+                None => summary.folders.push(RoutedFolder { destination: rule.destination.clone(), files: 1 }),
+            },
+            Err(e) => summary.failed.push((PathBuf::from(file), e.to_string())),
+        }
+    }
+    summary
+}
+
+/// Route the files of the managed torrent once it has finished downloading. Runs again
+/// after an update or when the rules change.
+pub async fn check_routes(
+    config: &AppConfig,
+    state: &mut SyncState,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+) {
+    if config.route_rules.is_empty() {
+        return;
+    }
+    let LocalTorrentState::Active { id } = state.local else {
+        return;
+    };
+    if !api.api_stats_v1(id.into()).is_ok_and(|stats| stats.finished) {
+        return;
+    }
+    let Ok(details) = api.api_torrent_details(id.into()) else {
+        return;
+    };
+    let routed = (details.info_hash.clone(), config.route_rules.clone());
+    if state.routed.as_ref() == Some(&routed) {
+        return;
+    }
+    let files: Vec<String> = details
+        .files
+        .iter()
+        .flatten()
+        .filter(|file| file.included)
+        .map(|file| file.components.join("/"))
+        .collect();
+
+    let download_path = config.download_path.clone();
+    let rules = config.route_rules.clone();
+    let summary = match tokio::task::spawn_blocking(move || route_files(&download_path, &files, &rules)).await {
+        Ok(summary) => summary,
+        Err(e) => {
+            error!("Sync: Routing files failed: {}", e);
+            return;
+        }
+    };
+    for folder in &summary.folders {
+        info!("Sync: Routed {} files to {}", folder.files, folder.destination.display());
+    }
+    for (path, error) in &summary.failed {
+        warn!("Sync: Could not route {}: {}", path.display(), error);
+    }
+    state.routed = Some(routed);
+    let _ = ui_tx.send(SyncEvent::FilesRouted(summary));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, destination: &Path) -> RouteRule {
+        RouteRule { pattern: pattern.to_string(), destination: destination.to_path_buf() }
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let files = vec!["@mod/userconfig/a.hpp".to_string(), "@mod/addons/a.pbo".to_string(), "@mod/keys/a.bikey".to_string()];
+        let rules = vec![rule("**/userconfig/**", Path::new("/cfg")), rule("*.hpp", Path::new("/other")), rule("*.pbo", Path::new("/game"))];
+        let plan: Vec<(&str, &Path)> = plan_routes(&files, &rules).into_iter().map(|(f, r)| (f, r.destination.as_path())).collect();
+        assert_eq!(plan, vec![("@mod/userconfig/a.hpp", Path::new("/cfg")), ("@mod/addons/a.pbo", Path::new("/game"))]);
+    }
+
+    #[test]
+    fn test_route_files_copies_and_reports() -> anyhow::Result<()> {
+        let (download, destination) = (tempfile::tempdir()?, tempfile::tempdir()?);
+        fs::create_dir_all(download.path().join("@mod/userconfig"))?;
+        fs::write(download.path().join("@mod/userconfig/a.hpp"), b"class cfg {};")?;
+        let files = vec!["@mod/userconfig/a.hpp".to_string(), "@mod/missing.hpp".to_string()];
+        let rules = vec![rule("*.hpp", destination.path()), rule("*.pbo", &download.path().join("inside"))];
+
+        let summary = route_files(download.path(), &files, &rules);
+        assert_eq!(summary.folders, vec![RoutedFolder { destination: destination.path().to_path_buf(), files: 1 }]);
+        assert_eq!(fs::read(destination.path().join("@mod/userconfig/a.hpp"))?, b"class cfg {};");
+        assert!(download.path().join("@mod/userconfig/a.hpp").exists(), "the original stays for seeding");
+        let failed: Vec<&Path> = summary.failed.iter().map(|(path, _)| path.as_path()).collect();
+        assert_eq!(failed, vec![Path::new("*.pbo"), Path::new("@mod/missing.hpp")]);
+
+        // Running again finds the file already in place
+        assert_eq!(route_files(download.path(), &files[..1], &rules[..1]).folders[0].files, 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+// src/sync/safe_mode.rs
+
+//! Safe mode: additive-only syncing. ModSync only writes files it created itself;
+//! anything that was already in the folder is left as it is, even if it doesn't match.
+
+use anyhow::{Context, Result};
+use librqbit::AddTorrentOptions;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+use tracing::{info, warn};
+
+use crate::config::AppConfig;
+
+/// Hidden file in the download folder listing the files safe mode let ModSync create
+pub const SAFE_MODE_MARKER_FILE: &str = ".modsync_safe_mode";
+
+/// Contents of the safe mode marker
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct SafeModeMarker {
+    created_files: BTreeSet<String>, // '/'-separated, relative to the download folder
+}
+
+fn read_marker(download_path: &Path) -> SafeModeMarker {
+    let marker_path = download_path.join(SAFE_MODE_MARKER_FILE);
+    let Ok(contents) = std::fs::read_to_string(&marker_path) else {
+        return SafeModeMarker::default();
+    };
+    toml::from_str(&contents).unwrap_or_else(|e| {
+        // Forgetting what we created only makes safe mode stricter
+        warn!("Sync: Ignoring unreadable safe mode marker {}: {}", marker_path.display(), e);
+        SafeModeMarker::default()
+    })
+}
+
+fn write_marker(download_path: &Path, marker: &SafeModeMarker) -> Result<()> {
+    let marker_path = download_path.join(SAFE_MODE_MARKER_FILE);
+    let contents = toml::to_string(marker).context("Failed to serialize safe mode marker")?;
+    std::fs::write(&marker_path, contents)
+        .with_context(|| format!("Failed to write safe mode marker: {}", marker_path.display()))
+}
+
+/// Indices of the torrent's files safe mode lets librqbit write: files missing or empty
+/// on disk, plus ones ModSync created on an earlier run. Newly claimed files are recorded
+/// so that after a restart our own partial downloads aren't mistaken for the user's files.
+pub fn writable_files(download_path: &Path, torrent_bytes: &[u8]) -> Result<(Vec<usize>, usize)> {
+    let torrent = librqbit::torrent_from_bytes::<librqbit::ByteBuf>(torrent_bytes)
+        .context("Failed to parse torrent for safe mode")?;
+
+    let mut marker = read_marker(download_path);
+    let mut writable = Vec::new();
+    let mut total = 0;
+    for (index, file) in torrent.info.iter_file_details()?.enumerate() {
+        total += 1;
+        let relative_path = file.filename.to_pathbuf()?;
+        let key = relative_path.to_string_lossy().replace('\\', "/");
+        let on_disk_len = std::fs::metadata(download_path.join(&relative_path)).ok().map(|m| m.len());
+        if marker.created_files.contains(&key) || matches!(on_disk_len, None | Some(0)) {
+            marker.created_files.insert(key);
+            writable.push(index);
+        }
+    }
+
+    if let Err(e) = write_marker(download_path, &marker) {
+        // Not fatal: on the next run these files just count as pre-existing
+        warn!("Sync: {}", e);
+    }
+    Ok((writable, total))
+}
+
+/// In safe mode, restrict `options` to the files ModSync may write. `overwrite` stays on
+/// because librqbit refuses to open a folder containing any existing file without it;
+/// deselected files are never resized, checked for re-download or fetched. A piece that
+/// straddles a new file and a kept one can still rewrite the edge of the kept file.
+pub fn apply_safe_mode(config: &AppConfig, torrent_bytes: &[u8], options: &mut AddTorrentOptions) -> Result<()> {
+    if !config.safe_mode {
+        return Ok(());
+    }
+    let (writable, total) = writable_files(&config.download_path, torrent_bytes)?;
+    info!(
+        "Sync: Safe mode - downloading {} of {} files, leaving existing files untouched",
+        writable.len(),
+        total
+    );
+    options.only_files = Some(writable);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use librqbit::CreateTorrentOptions;
+    use std::fs;
+
+    #[tokio::test]
+    async fn test_existing_files_are_protected_and_own_files_stay_writable() -> Result<()> {
+        let source = tempfile::tempdir()?;
+        fs::write(source.path().join("a_user.pbo"), vec![1u8; 100])?;
+        fs::write(source.path().join("b_new.pbo"), vec![2u8; 100])?;
+        fs::write(source.path().join("c_empty.pbo"), vec![3u8; 100])?;
+        let torrent = librqbit::create_torrent(source.path(), CreateTorrentOptions::default()).await?;
+        let torrent_bytes = torrent.as_bytes()?;
+        let name_of = |index: usize| {
+            torrent.as_info().info.iter_file_details().unwrap().nth(index).unwrap().filename.to_string().unwrap()
+        };
+
+        let download = tempfile::tempdir()?;
+        fs::write(download.path().join("a_user.pbo"), b"user's own, mismatched")?;
+        fs::write(download.path().join("c_empty.pbo"), b"")?;
+
+        let (writable, total) = writable_files(download.path(), &torrent_bytes)?;
+        assert_eq!(total, 3);
+        let mut names: Vec<String> = writable.iter().map(|&i| name_of(i)).collect();
+        names.sort();
+        assert_eq!(names, vec!["b_new.pbo", "c_empty.pbo"]);
+
+        // A partial download of ours survives a restart; the user's file is still kept
+        fs::write(download.path().join("b_new.pbo"), vec![2u8; 40])?;
+        let (writable_again, _) = writable_files(download.path(), &torrent_bytes)?;
+        assert_eq!(writable_again, writable);
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_safe_mode_only_when_enabled() -> Result<()> {
+        let mut options = AddTorrentOptions::default();
+        apply_safe_mode(&AppConfig::default(), b"not a torrent", &mut options)?;
+        assert!(options.only_files.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+// src/sync/seed_goal.rs
+
+//! `global_seed_goal`: one upload target for every torrent in the session, for seed boxes
+//! that think in totals. Uploads are counted like the ratio in the seeding summary: summed
+//! over all torrents, since the last stats reset or since ModSync started. Once the goal
+//! is met, every finished torrent is paused; downloads keep going. Seeding only resumes
+//! once the total drops well below the goal again (after an update, a stats reset or a
+//! restart), so it doesn't flap around the threshold.
+
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use crate::config::{AppConfig, SeedGoal, StatsBaseline};
+use crate::ui::utils::{SyncStatus, format_size};
+
+use super::messages::SyncEvent;
+use super::types::SyncState;
+use super::utils::send_sync_status_event;
+
+/// How far below the goal the total has to fall before seeding resumes
+const RESUME_MARGIN: f64 = 0.05;
+
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Uploaded and completed bytes over every torrent in the session, since the stats reset
+pub fn session_totals(api: &librqbit::Api, baseline: &StatsBaseline) -> (u64, u64) {
+    let torrent_list = api.api_torrent_list_ext(librqbit::api::ApiTorrentListOpts { with_stats: true });
+    let (uploaded, downloaded) = torrent_list
+        .torrents
+        .iter()
+        .filter_map(|t| t.stats.as_ref())
+        .fold((0, 0), |(up, down), stats| (up + stats.uploaded_bytes, down + stats.progress_bytes));
+    baseline.since_reset(uploaded, downloaded)
+}
+
+/// Whether `goal` is met by these totals. Once met, it stays met until the total falls
+/// `RESUME_MARGIN` below the goal.
+pub fn goal_met(goal: SeedGoal, uploaded: u64, downloaded: u64, currently_met: bool) -> bool {
+    let (progress, target) = match goal {
+        SeedGoal::Ratio(_) if downloaded == 0 => return false,
+        SeedGoal::Ratio(ratio) => (uploaded as f64 / downloaded as f64, ratio),
+        SeedGoal::UploadGb(gb) => (uploaded as f64, gb * GIB),
+    };
+    if currently_met {
+        progress >= target * (1.0 - RESUME_MARGIN)
+    } else {
+        progress >= target
+    }
+}
+
+/// Pause every seeding torrent while the session meets `global_seed_goal`, and resume
+/// the ones we paused once it no longer does
+pub async fn check_seed_goal(
+    config: &AppConfig,
+    state: &mut SyncState,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+) {
+    let (uploaded, downloaded) = session_totals(api, &config.stats_baseline);
+    let met = config
+        .global_seed_goal
+        .is_some_and(|goal| goal_met(goal, uploaded, downloaded, state.paused_for_seed_goal.is_some()));
+
+    if met {
+        if state.paused_for_seed_goal.is_none() {
+            info!("Sync: Seed goal met ({} uploaded), pausing seeding torrents", format_size(uploaded));
+            let ratio = (downloaded > 0).then(|| uploaded as f64 / downloaded as f64);
+            let _ = ui_tx.send(SyncEvent::GlobalSeedGoalMet { uploaded_bytes: uploaded, ratio });
+        }
+        let paused_ids = state.paused_for_seed_goal.get_or_insert_with(Vec::new);
+        // Also catches torrents that finished after the goal was met
+        let torrent_list = api.api_torrent_list_ext(librqbit::api::ApiTorrentListOpts { with_stats: true });
+        for torrent in torrent_list.torrents {
+            let (Some(id), Some(stats)) = (torrent.id, torrent.stats.as_ref()) else { continue };
+            if !stats.finished || stats.live.is_none() || paused_ids.contains(&id) {
+                continue;
+            }
+            match api.api_torrent_action_pause(id.into()).await {
+                Ok(_) => {
+                    info!("Sync: Paused torrent {}, the seed goal is met", id);
+                    paused_ids.push(id);
+                }
+                Err(e) => error!("Sync: Failed to pause torrent {} for the seed goal: {}", id, e),
+            }
+        }
+        send_sync_status_event(ui_tx, SyncStatus::SeedGoalMet);
+    } else if let Some(paused_ids) = state.paused_for_seed_goal.take() {
+        info!("Sync: Below the seed goal again, resuming {} torrents", paused_ids.len());
+        if let Some(held_ids) = state.paused_for_game.as_mut().or(state.paused_for_interface.as_mut()) {
+            // The game or interface pause is still on; it resumes these when it ends
+            for id in paused_ids {
+                if !held_ids.contains(&id) {
+                    held_ids.push(id);
+                }
+            }
+            return;
+        }
+        for id in paused_ids {
+            // Leave it paused if low disk space would pause it anyway
+            if state.low_disk_space {
+                break;
+            }
+            if state.paused_for_battery.as_ref().is_some_and(|ids| ids.contains(&id)) {
+                continue; // Still held for the low battery
+            }
+            match api.api_torrent_action_start(id.into()).await {
+                Ok(_) => info!("Sync: Resumed torrent {} below the seed goal", id),
+                Err(e) => error!("Sync: Failed to resume torrent {}: {}", id, e),
+            }
+        }
+        send_sync_status_event(ui_tx, SyncStatus::Idle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_goal_is_met_with_hysteresis() {
+        let ratio = SeedGoal::Ratio(2.0);
+        assert!(!goal_met(ratio, 1900, 1000, false));
+        assert!(goal_met(ratio, 2000, 1000, false));
+        // Just below the goal doesn't resume seeding
+        assert!(goal_met(ratio, 1950, 1000, true));
+        assert!(!goal_met(ratio, 1850, 1000, true));
+        // An update that adds data lowers the ratio
+        assert!(!goal_met(ratio, 2000, 1200, true));
+        assert!(!goal_met(ratio, 5000, 0, false), "nothing to measure a ratio against");
+
+        let amount = SeedGoal::UploadGb(10.0);
+        let gib = 1024 * 1024 * 1024;
+        assert!(!goal_met(amount, 9 * gib, 0, false));
+        assert!(goal_met(amount, 10 * gib, 0, false));
+        assert!(goal_met(amount, 10 * gib - gib / 4, 0, true));
+        // A stats reset starts counting again
+        assert!(!goal_met(amount, 0, 0, true));
+    }
+}
@@ -0,0 +1,63 @@
+// src/sync/seeding.rs
+
+//! `should_seed`: whether the torrent keeps uploading once the download is done. It never
+//! holds the download itself: torrents are always added running, and a non-seeder
+//! downloads like anyone else. Once the torrent finishes it's paused, so nothing is
+//! uploaded from then on, and resumed if seeding is switched back on. `allow_upload` off
+//! pauses a finished torrent the same way, and `allow_download` off an unfinished one:
+//! librqbit can only slow a direction down, not stop it.
+
+use librqbit::TorrentStatsState;
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use crate::config::AppConfig;
+
+use super::local::refresh_managed_torrent_status_event;
+use super::messages::SyncEvent;
+use super::types::{LocalTorrentState, SyncState};
+
+/// Pause the synced torrent while the direction it would move in is held: once it's
+/// finished while `should_seed` or `allow_upload` is off, before that while
+/// `allow_download` is off. Resume it when that's switched back on.
+pub async fn check_seeding(config: &AppConfig, state: &mut SyncState, api: &librqbit::Api, ui_tx: &mpsc::UnboundedSender<SyncEvent>) {
+    let LocalTorrentState::Active { id } = state.local else {
+        state.seeding_held = None;
+        return;
+    };
+    let Ok(stats) = api.api_stats_v1(id.into()) else {
+        return;
+    };
+    // Whether it's finished is only known once librqbit has checked the files
+    if matches!(stats.state, TorrentStatsState::Initializing) {
+        return;
+    }
+    let held = match stats.finished {
+        true if !config.should_seed => Some("finished and seeding is off"),
+        true if !config.allow_upload => Some("finished and uploading is off"),
+        false if !config.allow_download => Some("unfinished and downloading is off"),
+        _ => None,
+    };
+    let Some(reason) = held else {
+        if state.seeding_held.take() == Some(id) && !state.low_disk_space {
+            match api.api_torrent_action_start(id.into()).await {
+                Ok(_) => info!("Sync: Transfers switched back on, resumed torrent {}", id),
+                Err(e) => error!("Sync: Failed to resume torrent {}: {}", id, e),
+            }
+            refresh_managed_torrent_status_event(api, ui_tx, id);
+        }
+        return;
+    };
+    // Also catches a torrent resumed by hand or by another check
+    if stats.live.is_none() {
+        return;
+    }
+    match api.api_torrent_action_pause(id.into()).await {
+        Ok(_) => {
+            info!("Sync: Torrent {} is {}, pausing it", id, reason);
+            state.seeding_held = Some(id);
+            refresh_managed_torrent_status_event(api, ui_tx, id);
+        }
+        Err(e) => error!("Sync: Failed to pause torrent {}: {}", id, e),
+    }
+}
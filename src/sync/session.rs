@@ -0,0 +1,155 @@
+// src/sync/session.rs
+
+//! librqbit session setup shared by the GUI and headless modes
+
+use anyhow::{Context, Result};
+use librqbit::{Api, PeerConnectionOptions, Session, SessionOptions};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{Instrument, error, info, info_span, warn};
+
+use crate::config::{AppConfig, EncryptionMode, IpMode, get_cached_torrent_path, init_session_dir};
+
+use super::cpu_limit::concurrent_checks;
+use super::memory::write_buffer_mb;
+use super::network_share::{SHARE_PEER_TIMEOUT, network_share};
+use super::private::session_dht_allowed;
+use super::torrent::{build_add_options, info_hash_of};
+
+/// Ports tried for incoming connections when enabled (librqbit's usual range)
+const LISTEN_PORT_RANGE: std::ops::Range<u16> = 4240..4260;
+
+/// Ports tried for incoming connections: as many as the usual range, from `listen_port`
+fn listen_ports(config: &AppConfig) -> std::ops::Range<u16> {
+    match config.listen_port {
+        Some(port) => port..port.saturating_add(LISTEN_PORT_RANGE.len() as u16),
+        None => LISTEN_PORT_RANGE,
+    }
+}
+
+/// Create the download directory (if configured) and start a librqbit session for it
+pub async fn create_session(config: &AppConfig) -> Result<Arc<Session>> {
+    // Before anything reads the cached torrent
+    init_session_dir(config.session_dir.as_deref())?;
+
+    // Ensure download path exists
+    let download_path = config.download_path.clone();
+    if !download_path.as_os_str().is_empty() {
+        tokio::fs::create_dir_all(&download_path)
+            .await
+            .with_context(|| format!("Failed to create download directory: {:?}", download_path))?;
+    }
+
+    // librqbit has no MSE/PE, so forced encryption can only be kept by not connecting at all
+    if let Some(refusal) = config.encryption_mode.session_refusal() {
+        anyhow::bail!("{}", refusal);
+    }
+    if config.encryption_mode == EncryptionMode::Enabled {
+        warn!("Main: encryption_mode is enabled, but librqbit can't encrypt peer connections. They stay unencrypted.");
+    }
+
+    // librqbit has no setting for this; its listener, DHT and announces are IPv4 only
+    if config.ip_mode == IpMode::Ipv6 {
+        warn!("Main: ip_mode is ipv6, but librqbit only reaches peers over IPv4. Torrents won't find peers.");
+    }
+
+    // The DHT can only be left out when the session starts, so decide for the cached torrent now
+    let cached_torrent = get_cached_torrent_path().ok().and_then(|path| std::fs::read(path).ok());
+    let options = session_options(config, cached_torrent.as_deref());
+    if config.connection.enable_dht && options.disable_dht {
+        info!("Main: Starting without DHT, the torrent is private");
+    }
+    Session::new_with_opts(download_path, options).await.context("Failed to initialize librqbit session")
+}
+
+/// librqbit session settings for `config`, starting with `cached_torrent`
+pub fn session_options(config: &AppConfig, cached_torrent: Option<&[u8]>) -> SessionOptions {
+    let connection = &config.connection;
+    let share = network_share(config);
+    if let Some(kind) = &share {
+        warn!("Main: Download folder is on a network share ({}), checking one torrent at a time", kind);
+    }
+    SessionOptions {
+        disable_dht: !session_dht_allowed(config, cached_torrent),
+        disable_dht_persistence: true,
+        persistence: None,
+        fastresume: true, // Enable fastresume to speed up checking existing files
+        listen_port_range: connection.accept_incoming.then(|| listen_ports(config)),
+        enable_upnp_port_forwarding: connection.accept_incoming && connection.enable_upnp,
+        peer_opts: Some(PeerConnectionOptions {
+            connect_timeout: connection.peer_connect_timeout_secs.map(Duration::from_secs),
+            read_write_timeout: connection
+                .peer_read_write_timeout_secs
+                .map(Duration::from_secs)
+                .or(share.as_ref().map(|_| SHARE_PEER_TIMEOUT)),
+            ..Default::default()
+        }),
+        // Bounds the memory librqbit queues writes in; without it pieces go straight to disk
+        defer_writes_up_to: write_buffer_mb(config),
+        concurrent_init_limit: concurrent_checks(config).or(share.as_ref().map(|_| 1)),
+        ..Default::default()
+    }
+}
+
+/// Re-add the cached torrent from a previous run, returning its ID if it was added.
+/// A cache file that can't be read or added is deleted.
+pub async fn add_cached_torrent(api: &Api, config: &AppConfig) -> Option<usize> {
+    let cached_path = match get_cached_torrent_path() {
+        Ok(path) => path,
+        Err(e) => {
+            error!("Main: Error getting cached torrent path: {}", e);
+            return None; // Proceed without cache
+        }
+    };
+
+    if !cached_path.exists() {
+        info!("Main: No cached torrent file found at {}", cached_path.display());
+        return None;
+    }
+
+    info!("Main: Found cached torrent at {}", cached_path.display());
+    let torrent_bytes = match tokio::fs::read(&cached_path).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            error!("Main: Error reading cached torrent file {}: {}. Deleting cache.", cached_path.display(), e);
+            let _ = tokio::fs::remove_file(cached_path).await;
+            return None;
+        }
+    };
+    info!("Main: Read {} bytes from cached torrent.", torrent_bytes.len());
+
+    // Add the cached torrent, not paused, ensuring overwrite checks. A public torrent
+    // still awaiting seeding confirmation, or one whose notice isn't accepted yet, stays
+    // paused; the sync manager asks.
+    let add_span = info_span!(
+        "add",
+        info_hash = info_hash_of(&torrent_bytes).as_deref().unwrap_or("unknown"),
+        bytes = torrent_bytes.len()
+    );
+    let plan = match build_add_options(config, api, torrent_bytes, None).await {
+        Ok(plan) => plan,
+        Err(e) => {
+            // Adding without the file selection could touch the user's files
+            warn!("Main: Not adding cached torrent: {:#}", e);
+            return None;
+        }
+    };
+    match api.api_add_torrent(plan.request, Some(plan.options)).instrument(add_span).await {
+        Ok(response) => {
+            if let Some(id) = response.id {
+                info!("Main: Successfully added cached torrent with ID: {}", id);
+                Some(id)
+            } else {
+                warn!("Main: Added cached torrent but API returned no ID.");
+                // Delete potentially broken cache file?
+                let _ = tokio::fs::remove_file(&cached_path).await;
+                None
+            }
+        }
+        Err(e) => {
+            error!("Main: Error adding cached torrent: {}. Deleting cache.", e);
+            let _ = tokio::fs::remove_file(&cached_path).await;
+            None
+        }
+    }
+}
@@ -0,0 +1,102 @@
+// src/sync/snooze.rs
+
+//! Snoozing updates: `updates_snoozed_until` holds the periodic remote check until that
+//! time, for when a download mid-game or mid-call would get in the way. The torrent keeps
+//! seeding and downloading what it already has; only new updates wait. Checks resume on
+//! their own once the time passes, and the time is kept in the config so a restart within
+//! the window stays snoozed.
+
+use chrono::{DateTime, Days, Duration, Local, NaiveTime};
+use tokio::sync::mpsc;
+use tracing::info;
+
+use crate::config::AppConfig;
+use crate::ui::utils::SyncStatus;
+
+use super::messages::SyncEvent;
+use super::utils::send_sync_status_event;
+
+/// How long to snooze for, as offered in the UI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnoozeChoice {
+    Hours(u32),
+    UntilTomorrow, // 08:00 the next day
+}
+
+impl SnoozeChoice {
+    /// When a snooze chosen at `now` ends
+    pub fn until(self, now: DateTime<Local>) -> DateTime<Local> {
+        match self {
+            SnoozeChoice::Hours(hours) => now + Duration::hours(hours.into()),
+            SnoozeChoice::UntilTomorrow => (now.date_naive() + Days::new(1))
+                .and_time(NaiveTime::from_hms_opt(8, 0, 0).unwrap_or_default())
+                .and_local_timezone(Local)
+                .earliest()
+                .unwrap_or(now + Duration::days(1)),
+        }
+    }
+}
+
+/// The end of the snooze, while one is running at `now`
+pub fn active_snooze(config: &AppConfig, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    config.updates_snoozed_until.filter(|until| *until > now)
+}
+
+/// Whether the snooze is running, or just ran out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnoozeState {
+    Snoozed,
+    Ended, // Was snoozed until now; time for the check that was held
+    Off,
+}
+
+/// Follows the snooze between ticks, to notice it ending
+#[derive(Debug, Default)]
+pub struct SnoozeWatch {
+    snoozed: bool,
+}
+
+impl SnoozeWatch {
+    /// Whether automatic update checks are held. Shows the snooze in place of the
+    /// torrent's active status when `show` is set.
+    pub fn check(&mut self, config: &AppConfig, show: bool, ui_tx: &mpsc::UnboundedSender<SyncEvent>) -> SnoozeState {
+        let until = active_snooze(config, Local::now());
+        let state = match (until, self.snoozed) {
+            (Some(until), was_snoozed) => {
+                if !was_snoozed {
+                    info!("Sync: Automatic update checks snoozed until {}", until.format("%Y-%m-%d %H:%M"));
+                }
+                if show {
+                    send_sync_status_event(ui_tx, SyncStatus::Snoozed { until });
+                }
+                SnoozeState::Snoozed
+            }
+            (None, true) => {
+                info!("Sync: Update snooze over, resuming automatic checks");
+                send_sync_status_event(ui_tx, SyncStatus::Idle);
+                SnoozeState::Ended
+            }
+            (None, false) => SnoozeState::Off,
+        };
+        self.snoozed = until.is_some();
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_snooze_ends_and_expires() {
+        let now = Local.with_ymd_and_hms(2026, 3, 14, 21, 30, 0).unwrap();
+        assert_eq!(SnoozeChoice::Hours(1).until(now), Local.with_ymd_and_hms(2026, 3, 14, 22, 30, 0).unwrap());
+        assert_eq!(SnoozeChoice::UntilTomorrow.until(now), Local.with_ymd_and_hms(2026, 3, 15, 8, 0, 0).unwrap());
+
+        let config = AppConfig { updates_snoozed_until: Some(SnoozeChoice::Hours(1).until(now)), ..AppConfig::default() };
+        assert!(active_snooze(&config, now).is_some());
+        assert_eq!(active_snooze(&config, now + Duration::hours(2)), None);
+        assert_eq!(active_snooze(&AppConfig::default(), now), None);
+    }
+}
@@ -0,0 +1,178 @@
+// src/sync/spot_check.rs
+
+//! Hash check of just the pieces behind a few files, for a quick look at one suspect
+//! mod without re-verifying the whole torrent. librqbit can only re-check a torrent as
+//! a whole, so the pieces are read from disk and compared against the torrent here.
+
+use anyhow::{Context, Result};
+use sha1::{Digest, Sha1};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Outcome of checking the selected files
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpotCheckReport {
+    pub files_checked: usize,
+    pub pieces_checked: usize,
+    pub bad_files: Vec<PathBuf>, // Selected files with a piece that is missing or doesn't match
+}
+
+/// A file's place in the torrent's byte stream
+struct FileSpan {
+    relative: String, // '/'-separated, as in the Files tab
+    offset: u64,
+    len: u64,
+}
+
+/// Read `len` bytes at torrent offset `start`, across file boundaries.
+/// None if a file is missing or too short.
+fn read_span(download_path: &Path, files: &[FileSpan], start: u64, len: u64) -> Option<Vec<u8>> {
+    let end = start + len;
+    let mut buf = Vec::with_capacity(len as usize);
+    for file in files.iter().filter(|f| f.len > 0 && f.offset < end && f.offset + f.len > start) {
+        let from = start.max(file.offset);
+        let to = end.min(file.offset + file.len);
+        let mut handle = File::open(download_path.join(&file.relative)).ok()?;
+        handle.seek(SeekFrom::Start(from - file.offset)).ok()?;
+        let mut chunk = vec![0u8; (to - from) as usize];
+        handle.read_exact(&mut chunk).ok()?;
+        buf.extend_from_slice(&chunk);
+    }
+    (buf.len() as u64 == len).then_some(buf)
+}
+
+/// Check every piece touching `selected` ('/'-separated paths relative to the download
+/// folder). A piece shared with an unselected file also needs that file's bytes, so a
+/// missing or paused neighbour makes the selected file fail too.
+pub fn spot_check(torrent_bytes: &[u8], download_path: &Path, selected: &[PathBuf]) -> Result<SpotCheckReport> {
+    spot_check_with_progress(torrent_bytes, download_path, selected, |_| {})
+}
+
+/// `spot_check`, calling `progress` with the percentage of pieces checked so far
+pub fn spot_check_with_progress(
+    torrent_bytes: &[u8],
+    download_path: &Path,
+    selected: &[PathBuf],
+    mut progress: impl FnMut(u8),
+) -> Result<SpotCheckReport> {
+    let torrent = librqbit::torrent_from_bytes::<librqbit::ByteBuf>(torrent_bytes)
+        .context("Failed to parse torrent for spot check")?;
+    let piece_length = u64::from(torrent.info.piece_length);
+    let hashes: Vec<&[u8]> = torrent.info.pieces.as_ref().chunks(20).collect();
+
+    let mut files = Vec::new();
+    let mut offset = 0;
+    for file in torrent.info.iter_file_details()? {
+        let relative = file.filename.to_pathbuf()?.to_string_lossy().replace('\\', "/");
+        files.push(FileSpan { relative, offset, len: file.len });
+        offset += file.len;
+    }
+    let total = offset;
+
+    let wanted: HashSet<String> = selected.iter().map(|p| p.to_string_lossy().replace('\\', "/")).collect();
+    let chosen: Vec<&FileSpan> = files.iter().filter(|f| wanted.contains(&f.relative)).collect();
+
+    let mut report = SpotCheckReport { files_checked: chosen.len(), ..Default::default() };
+    let mut pieces: Vec<u64> = chosen
+        .iter()
+        .filter(|f| f.len > 0)
+        .flat_map(|f| f.offset / piece_length..=(f.offset + f.len - 1) / piece_length)
+        .collect();
+    pieces.sort_unstable();
+    pieces.dedup();
+
+    let mut bad = HashSet::new();
+    let piece_count = pieces.len();
+    for (done, piece) in pieces.into_iter().enumerate() {
+        progress((done * 100 / piece_count) as u8);
+        let start = piece * piece_length;
+        let len = piece_length.min(total - start);
+        let expected = hashes.get(piece as usize).context("Torrent has fewer piece hashes than pieces")?;
+        let matches = read_span(download_path, &files, start, len)
+            .is_some_and(|data| Sha1::digest(&data).as_slice() == *expected);
+        report.pieces_checked += 1;
+        if !matches {
+            for file in chosen.iter().filter(|f| f.offset < start + len && f.offset + f.len > start) {
+                bad.insert(file.relative.clone());
+            }
+        }
+    }
+    progress(100);
+    let mut bad_files: Vec<PathBuf> = bad.into_iter().map(PathBuf::from).collect();
+    bad_files.sort();
+    report.bad_files = bad_files;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Two 10-byte files in 8-byte pieces: a.pbo is 0..10, b.pbo is 10..20
+    fn fixture(dir: &Path) -> Vec<u8> {
+        let a: Vec<u8> = (0..10).collect();
+        let b: Vec<u8> = (10..20).collect();
+        fs::create_dir_all(dir.join("@mod/addons")).unwrap();
+        fs::write(dir.join("@mod/addons/a.pbo"), &a).unwrap();
+        fs::write(dir.join("@mod/addons/b.pbo"), &b).unwrap();
+
+        let data = [a, b].concat();
+        let pieces: Vec<u8> = data.chunks(8).flat_map(|chunk| Sha1::digest(chunk).to_vec()).collect();
+        let mut torrent = b"d4:infod5:filesl".to_vec();
+        for name in ["a.pbo", "b.pbo"] {
+            torrent.extend_from_slice(format!("d6:lengthi10e4:pathl4:@mod6:addons5:{}ee", name).as_bytes());
+        }
+        torrent.extend_from_slice(b"e4:name4:mods12:piece lengthi8e6:pieces");
+        torrent.extend_from_slice(format!("{}:", pieces.len()).as_bytes());
+        torrent.extend_from_slice(&pieces);
+        torrent.extend_from_slice(b"ee");
+        torrent
+    }
+
+    #[test]
+    fn test_only_pieces_of_the_selection_count() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let torrent = fixture(dir.path());
+        let selected = [PathBuf::from("@mod/addons/b.pbo")];
+
+        let report = spot_check(&torrent, dir.path(), &selected)?;
+        assert_eq!(report, SpotCheckReport { files_checked: 1, pieces_checked: 2, bad_files: Vec::new() });
+
+        // Piece 0 belongs to a.pbo alone, so b.pbo still checks out
+        let mut a = fs::read(dir.path().join("@mod/addons/a.pbo"))?;
+        a[0] = 0xff;
+        fs::write(dir.path().join("@mod/addons/a.pbo"), &a)?;
+        assert!(spot_check(&torrent, dir.path(), &selected)?.bad_files.is_empty());
+
+        // Piece 1 spans both files
+        a[9] = 0xff;
+        fs::write(dir.path().join("@mod/addons/a.pbo"), &a)?;
+        assert_eq!(spot_check(&torrent, dir.path(), &selected)?.bad_files, selected.to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn test_progress_covers_every_piece() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let torrent = fixture(dir.path());
+        let all = [PathBuf::from("@mod/addons/a.pbo"), PathBuf::from("@mod/addons/b.pbo")];
+        let mut seen = Vec::new();
+        let report = spot_check_with_progress(&torrent, dir.path(), &all, |percent| seen.push(percent))?;
+        assert_eq!(report.pieces_checked, 3);
+        assert_eq!(seen, [0, 33, 66, 100]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_file_fails() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let torrent = fixture(dir.path());
+        fs::remove_file(dir.path().join("@mod/addons/a.pbo"))?;
+        let report = spot_check(&torrent, dir.path(), &[PathBuf::from("@mod/addons/a.pbo")])?;
+        assert_eq!(report.bad_files, vec![PathBuf::from("@mod/addons/a.pbo")]);
+        Ok(())
+    }
+}
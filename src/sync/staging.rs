@@ -0,0 +1,309 @@
+// src/sync/staging.rs
+
+//! The atomic swap update strategy. An update downloads into a sibling staging folder
+//! while the current torrent keeps seeding the live one. Once the new torrent has every
+//! piece, both torrents are forgotten, the folders are swapped by renaming and the new
+//! torrent is added again on the live folder, so players never see a half-updated folder.
+
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+use walkdir::WalkDir;
+use tracing::{error, info, warn};
+
+use crate::config::{AppConfig, get_cached_torrent_path};
+use crate::ui::utils::SyncStatus;
+
+use super::cleaner::{find_extra_files, get_all_files_from_details};
+use super::messages::SyncEvent;
+use super::torrent::{info_hash_of, manage_torrent_task};
+use super::types::{LocalTorrentState, RemoteTorrentState, SyncState};
+use super::utils::send_sync_status_event;
+
+/// An update downloading into the staging folder
+#[derive(Debug)]
+pub struct StagedUpdate {
+    pub id: usize, // The new torrent, added on the staging folder
+    pub torrent_content: Vec<u8>,
+}
+
+fn sibling(live: &Path, suffix: &str) -> PathBuf {
+    let mut name = live.file_name().map(|name| name.to_os_string()).unwrap_or_default();
+    name.push(suffix);
+    live.with_file_name(name)
+}
+
+/// Where an update downloads before the swap, next to the live folder so renaming stays on one volume
+pub fn staging_dir(live: &Path) -> PathBuf {
+    sibling(live, ".modsync-staging")
+}
+
+/// Where the live folder goes during the swap, deleted once the swap succeeded
+pub fn backup_dir(live: &Path) -> PathBuf {
+    sibling(live, ".modsync-old")
+}
+
+/// Copy the live folder's files into the staging folder, so the new torrent only downloads
+/// what changed and files the user added survive the swap. Files already staged are kept,
+/// which resumes an interrupted update. Returns how many were copied.
+pub fn seed_staging(live: &Path, staging: &Path) -> Result<usize> {
+    fs::create_dir_all(staging).with_context(|| format!("Failed to create staging folder {}", staging.display()))?;
+    if !live.exists() {
+        return Ok(0);
+    }
+    let mut copied = 0;
+    for entry in WalkDir::new(live).into_iter().filter_map(|e| e.ok()).filter(|e| e.file_type().is_file()) {
+        let relative = entry.path().strip_prefix(live)?;
+        let target = staging.join(relative);
+        if target.exists() {
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(entry.path(), &target).with_context(|| format!("Failed to stage {}", relative.display()))?;
+        copied += 1;
+    }
+    Ok(copied)
+}
+
+/// Move `live` to `backup` and `staging` to `live`. If the second rename fails the live
+/// folder is put back, so either the update is in place or nothing changed.
+pub fn swap_into_place(live: &Path, staging: &Path, backup: &Path) -> Result<()> {
+    if backup.exists() {
+        fs::remove_dir_all(backup).with_context(|| format!("Failed to remove old backup {}", backup.display()))?;
+    }
+    let had_live = live.exists();
+    if had_live {
+        fs::rename(live, backup).with_context(|| format!("Failed to move {} aside", live.display()))?;
+    }
+    if let Err(e) = fs::rename(staging, live) {
+        if had_live && let Err(restore) = fs::rename(backup, live) {
+            bail!(
+                "Failed to move the staging folder into place ({}), and restoring {} from {} failed: {}",
+                e,
+                live.display(),
+                backup.display(),
+                restore
+            );
+        }
+        return Err(e).with_context(|| format!("Failed to move {} into place", staging.display()));
+    }
+    Ok(())
+}
+
+/// Start downloading `torrent_content` into the staging folder, leaving the current
+/// torrent seeding the live folder
+pub async fn start_staged_update(
+    config: &AppConfig,
+    state: &mut SyncState,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+    torrent_content: Vec<u8>,
+) -> Result<()> {
+    if let Some(staged) = state.staged.take() {
+        if info_hash_of(&staged.torrent_content) == info_hash_of(&torrent_content) {
+            info!("Sync: This update is already downloading into the staging folder");
+            state.staged = Some(staged);
+            return Ok(());
+        }
+        // A newer update replaces the one being staged; its files are reused
+        info!("Sync: Replacing the staged update with a newer one");
+        let _ = api.api_torrent_action_forget(staged.id.into()).await;
+    }
+
+    let live = config.download_path.clone();
+    let staging = staging_dir(&live);
+    send_sync_status_event(ui_tx, SyncStatus::Staging { percent: 0 });
+    let seed_target = staging.clone();
+    let copied = tokio::task::spawn_blocking(move || seed_staging(&live, &seed_target)).await??;
+    info!("Sync: Copied {} files into the staging folder {}", copied, staging.display());
+
+    let staging_config = AppConfig { download_path: staging, ..config.clone() };
+    let Some(id) = manage_torrent_task(&staging_config, api, ui_tx, None, torrent_content.clone()).await? else {
+        bail!("The update could not be added on the staging folder");
+    };
+    info!("Sync: Update downloading into the staging folder as torrent {}", id);
+    state.staged = Some(StagedUpdate { id, torrent_content });
+    send_sync_status_event(ui_tx, SyncStatus::Staging { percent: 0 });
+    Ok(())
+}
+
+/// Report staging progress, and swap the folders once the staged torrent is complete
+pub async fn check_staged_update(
+    config: &AppConfig,
+    state: &mut SyncState,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+) {
+    let Some(staged) = &state.staged else {
+        return;
+    };
+    let stats = match api.api_stats_v1(staged.id.into()) {
+        Ok(stats) => stats,
+        Err(_) => {
+            info!("Sync: Staged torrent {} is gone, abandoning the staged update", staged.id);
+            state.staged = None;
+            return;
+        }
+    };
+    if !stats.finished {
+        let percent = (stats.progress_bytes * 100).checked_div(stats.total_bytes).unwrap_or(0).min(99) as u8;
+        send_sync_status_event(ui_tx, SyncStatus::Staging { percent });
+        return;
+    }
+    if let Some(staged) = state.staged.take() {
+        swap_staged_update(config, state, api, ui_tx, staged).await;
+    }
+}
+
+async fn swap_staged_update(
+    config: &AppConfig,
+    state: &mut SyncState,
+    api: &librqbit::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+    staged: StagedUpdate,
+) {
+    info!("Sync: Staged update complete, swapping it into {}", config.download_path.display());
+    send_sync_status_event(ui_tx, SyncStatus::UpdatingTorrent);
+
+    let current_id = match state.local {
+        LocalTorrentState::Active { id } => Some(id),
+        LocalTorrentState::NotLoaded => None,
+    };
+    // The announce URLs already carry the passkey; kept for a rollback
+    let previous = current_id
+        .and_then(|id| api.session().get(id.into()))
+        .and_then(|torrent| torrent.metadata.load().as_ref().map(|m| m.torrent_bytes.to_vec()));
+
+    // Both torrents hold their files open, which would block renaming on Windows
+    for id in [Some(staged.id), current_id].into_iter().flatten() {
+        if let Err(e) = api.api_torrent_action_forget(id.into()).await {
+            error!("Sync: Failed to forget torrent {} before the swap: {}", id, e);
+        }
+    }
+    state.local = LocalTorrentState::NotLoaded;
+
+    let live = config.download_path.clone();
+    let (staging, backup) = (staging_dir(&live), backup_dir(&live));
+    let swap_paths = (live.clone(), staging.clone(), backup.clone());
+    let swapped = tokio::task::spawn_blocking(move || swap_into_place(&swap_paths.0, &swap_paths.1, &swap_paths.2))
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result);
+
+    let mut swap_error = None;
+    let (content, restore_config) = match swapped {
+        Ok(()) => {
+            info!("Sync: Swapped the update into place");
+            if let Ok(cache_path) = get_cached_torrent_path()
+                && let Err(e) = tokio::fs::write(&cache_path, &staged.torrent_content).await
+            {
+                error!("Sync: Failed to write to cache file {}: {}", cache_path.display(), e);
+            }
+            tokio::task::spawn_blocking(move || {
+                if let Err(e) = fs::remove_dir_all(&backup) {
+                    error!("Sync: Failed to remove the previous folder {}: {}", backup.display(), e);
+                }
+            });
+            (staged.torrent_content, config.clone())
+        }
+        Err(e) => {
+            let err_msg = format!("Update swap failed, keeping the current version: {:#}", e);
+            warn!("Sync: {}", err_msg);
+            state.remote = RemoteTorrentState::UpdateAvailable;
+            let Some(previous_content) = previous else {
+                let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
+                send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
+                return;
+            };
+            swap_error = Some(err_msg);
+            (previous_content, AppConfig { tracker_passkey: None, ..config.clone() })
+        }
+    };
+
+    // librqbit checks the files again on the live folder, then seeding carries on
+    match manage_torrent_task(&restore_config, api, ui_tx, None, content).await {
+        Ok(Some(id)) => {
+            state.local = LocalTorrentState::Active { id };
+            match swap_error {
+                // After the torrent's own status, so the error stays on screen
+                Some(err_msg) => {
+                    let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
+                    send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
+                }
+                None => {
+                    state.remote = RemoteTorrentState::Checked;
+                    report_extra_files(config, api, ui_tx, id);
+                }
+            }
+        }
+        Ok(None) => {}
+        Err(e) => {
+            let err_msg = format!("Failed to add the torrent after the swap: {:#}", e);
+            warn!("Sync: {}", err_msg);
+            let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
+            send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
+        }
+    }
+}
+
+/// Offer to delete files the new version no longer has, as an in-place update does
+fn report_extra_files(config: &AppConfig, api: &librqbit::Api, ui_tx: &mpsc::UnboundedSender<SyncEvent>, id: usize) {
+    if config.safe_mode {
+        return;
+    }
+    let Ok(details) = api.api_torrent_details(id.into()) else {
+        return;
+    };
+    match find_extra_files(&config.download_path, &get_all_files_from_details(&details), &config.ignore_patterns) {
+        Ok(extra_files) => {
+            let _ = ui_tx.send(SyncEvent::ExtraFilesFound(extra_files));
+        }
+        Err(e) => error!("Sync: Failed to find extra files after the swap: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_and_swap() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let live = root.path().join("@mods");
+        fs::create_dir_all(live.join("addons"))?;
+        fs::write(live.join("addons/a.pbo"), b"old")?;
+        fs::write(live.join("userconfig.hpp"), b"mine")?;
+        let (staging, backup) = (staging_dir(&live), backup_dir(&live));
+        assert_eq!(staging, root.path().join("@mods.modsync-staging"));
+
+        // An interrupted earlier attempt already staged the new a.pbo
+        fs::create_dir_all(staging.join("addons"))?;
+        fs::write(staging.join("addons/a.pbo"), b"new")?;
+        assert_eq!(seed_staging(&live, &staging)?, 1);
+
+        swap_into_place(&live, &staging, &backup)?;
+        assert_eq!(fs::read(live.join("addons/a.pbo"))?, b"new");
+        assert_eq!(fs::read(live.join("userconfig.hpp"))?, b"mine");
+        assert_eq!(fs::read(backup.join("addons/a.pbo"))?, b"old");
+        assert!(!staging.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_swap_restores_live_folder() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let live = root.path().join("@mods");
+        fs::create_dir_all(&live)?;
+        fs::write(live.join("a.pbo"), b"old")?;
+
+        // No staging folder to move in
+        let result = swap_into_place(&live, &staging_dir(&live), &backup_dir(&live));
+        assert!(result.is_err());
+        assert_eq!(fs::read(live.join("a.pbo"))?, b"old");
+        assert!(!backup_dir(&live).exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+// src/sync/stamp.rs
+
+//! Numbering and timestamping of sync events. Every producer (the manager, its
+//! background tasks and the UI) sends into one queue, and events are stamped in the
+//! order they come out of it, so numbers are unique and increasing for the session.
+
+use chrono::Local;
+use tokio::sync::mpsc;
+
+use super::messages::{SyncEvent, TimestampedEvent};
+
+/// A sender whose events reach `out` stamped with a sequence number (from 1) and the time
+pub fn stamp_events(out: mpsc::UnboundedSender<TimestampedEvent>) -> mpsc::UnboundedSender<SyncEvent> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<SyncEvent>();
+    tokio::spawn(async move {
+        let mut seq = 0;
+        while let Some(event) = rx.recv().await {
+            seq += 1;
+            if out.send(TimestampedEvent { seq, at: Local::now(), event }).is_err() {
+                break; // Consumer has shut down
+            }
+        }
+    });
+    tx
+}
+
+/// Follows the sequence numbers a consumer receives, to notice events it never got
+#[derive(Debug, Default)]
+pub struct SequenceTracker {
+    last: Option<u64>,
+    pub missed: u64, // Total events skipped over so far
+}
+
+impl SequenceTracker {
+    /// Record `seq`, returning how many events were skipped just before it
+    pub fn observe(&mut self, seq: u64) -> Option<u64> {
+        let expected = self.last.map_or(1, |last| last + 1);
+        self.last = Some(seq);
+        let skipped = seq.checked_sub(expected).filter(|skipped| *skipped > 0)?;
+        self.missed += skipped;
+        Some(skipped)
+    }
+
+    pub fn last(&self) -> Option<u64> {
+        self.last
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_events_are_numbered_in_send_order() {
+        let (out_tx, mut out_rx) = mpsc::unbounded_channel();
+        let tx = stamp_events(out_tx);
+        let other_producer = tx.clone();
+        tx.send(SyncEvent::TorrentAdded(1)).unwrap();
+        other_producer.send(SyncEvent::Error("boom".to_string())).unwrap();
+        tx.send(SyncEvent::TorrentAdded(2)).unwrap();
+        drop((tx, other_producer));
+
+        let mut stamped = Vec::new();
+        while let Some(event) = out_rx.recv().await {
+            stamped.push(event);
+        }
+        assert_eq!(stamped.iter().map(|event| event.seq).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert!(matches!(stamped[1].event, SyncEvent::Error(_)));
+        assert!(stamped.windows(2).all(|pair| pair[0].at <= pair[1].at));
+    }
+
+    #[test]
+    fn test_sequence_tracker_counts_gaps() {
+        let mut tracker = SequenceTracker::default();
+        assert_eq!(tracker.observe(1), None);
+        assert_eq!(tracker.observe(2), None);
+        assert_eq!(tracker.observe(5), Some(2));
+        assert_eq!(tracker.observe(6), None);
+        assert_eq!((tracker.last(), tracker.missed), (Some(6), 2));
+
+        // A consumer that starts late counts the events before its first
+        let mut late = SequenceTracker::default();
+        assert_eq!(late.observe(4), Some(3));
+    }
+}
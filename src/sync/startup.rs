@@ -0,0 +1,145 @@
+// src/sync/startup.rs
+
+//! Starting the sync engine for the GUI: the librqbit session, the cached torrent and the
+//! sync manager. When the session can't start, the GUI runs against an empty stand-in
+//! session so settings stay usable, and offers to retry from a recovery banner.
+
+use anyhow::{Context, Result};
+use librqbit::{Api, Session, SessionOptions};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{error, info};
+
+use crate::config::{AppConfig, SessionInUse};
+
+use super::manager::run_sync_manager;
+use super::messages::{SyncCommand, SyncEvent};
+use super::network_share::share_disconnect_in_text;
+use super::session::{add_cached_torrent, create_session};
+
+/// What stopped the session from starting, for the recovery hints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionProblem {
+    PortInUse,
+    PermissionDenied,
+    NetworkShare, // The download folder's network share is unreachable
+    InUse, // Another ModSync holds the session lock
+    Other,
+}
+
+/// Why the torrent session didn't start
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionFailure {
+    pub problem: SessionProblem,
+    pub message: String,
+}
+
+impl SessionFailure {
+    pub fn from_error(error: &anyhow::Error) -> Self {
+        let io_kind = error.chain().find_map(|cause| cause.downcast_ref::<std::io::Error>()).map(|e| e.kind());
+        let problem = if error.chain().any(|cause| cause.is::<SessionInUse>()) {
+            SessionProblem::InUse
+        } else if io_kind == Some(std::io::ErrorKind::AddrInUse) || format!("{:#}", error).contains("error listening on TCP") {
+            // librqbit reports a range with no free port as a plain message
+            SessionProblem::PortInUse
+        } else if io_kind == Some(std::io::ErrorKind::PermissionDenied) {
+            SessionProblem::PermissionDenied
+        } else if share_disconnect_in_text(&format!("{:#}", error), false) {
+            SessionProblem::NetworkShare
+        } else {
+            SessionProblem::Other
+        };
+        Self { problem, message: format!("{:#}", error) }
+    }
+}
+
+/// A start that failed, with the command queue to hand to the next attempt
+#[derive(Debug)]
+pub struct FailedStart {
+    pub failure: SessionFailure,
+    pub sync_cmd_rx: mpsc::UnboundedReceiver<SyncCommand>,
+}
+
+/// Start the session, re-add the cached torrent and spawn the sync manager
+pub async fn start_engine(
+    config: &AppConfig,
+    ui_tx: mpsc::UnboundedSender<SyncEvent>,
+    mut sync_cmd_rx: mpsc::UnboundedReceiver<SyncCommand>,
+) -> Result<Api, FailedStart> {
+    let session = match create_session(config).await {
+        Ok(session) => session,
+        Err(e) => {
+            error!("Main: Torrent session failed to start: {:#}", e);
+            return Err(FailedStart { failure: SessionFailure::from_error(&e), sync_cmd_rx });
+        }
+    };
+    let api = Api::new(session, None);
+    let initial_torrent_id = add_cached_torrent(&api, config).await;
+
+    // Clicks made while the engine was down are stale by now
+    while sync_cmd_rx.try_recv().is_ok() {}
+
+    let sync_api = api.clone();
+    let sync_config = config.clone();
+    tokio::spawn(async move {
+        info!("Main: Sync manager started in background task");
+        if let Err(e) = run_sync_manager(sync_config, sync_api, ui_tx, sync_cmd_rx, initial_torrent_id).await {
+            error!("Sync manager task exited with error: {}", e);
+        }
+        info!("Sync manager task finished.");
+    });
+    Ok(api)
+}
+
+/// Try `start_engine` again in the background, with the settings the user just fixed
+pub fn retry_engine(
+    config: AppConfig,
+    ui_tx: mpsc::UnboundedSender<SyncEvent>,
+    sync_cmd_rx: mpsc::UnboundedReceiver<SyncCommand>,
+) -> oneshot::Receiver<Result<Api, FailedStart>> {
+    let (result_tx, result_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        let _ = result_tx.send(start_engine(&config, ui_tx, sync_cmd_rx).await);
+    });
+    result_rx
+}
+
+/// An empty session with no listener, DHT or files of its own, for the UI to show while
+/// the real one is down
+pub async fn standby_api() -> Result<Api> {
+    let options = SessionOptions {
+        disable_dht: true,
+        disable_dht_persistence: true,
+        persistence: None,
+        listen_port_range: None,
+        enable_upnp_port_forwarding: false,
+        ..Default::default()
+    };
+    let session = Session::new_with_opts(std::env::temp_dir(), options)
+        .await
+        .context("Failed to start a stand-in torrent session")?;
+    Ok(Api::new(session, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_failures_are_classified() {
+        let failure = |error: anyhow::Error| SessionFailure::from_error(&error).problem;
+        let denied = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied");
+        assert_eq!(failure(anyhow::Error::new(denied).context("Failed to create download directory")), SessionProblem::PermissionDenied);
+        assert_eq!(
+            failure(anyhow::anyhow!("no free TCP ports in range 4240..4260").context("error listening on TCP")),
+            SessionProblem::PortInUse
+        );
+        let in_use = SessionInUse { lock_path: PathBuf::from("cached.lock"), holder: Some("42".to_string()) };
+        assert_eq!(failure(anyhow::Error::new(in_use)), SessionProblem::InUse);
+        assert_eq!(
+            failure(anyhow::anyhow!("Stale file handle (os error 116)").context("Failed to create download directory")),
+            SessionProblem::NetworkShare
+        );
+        assert_eq!(failure(anyhow::anyhow!("bad fastresume data")), SessionProblem::Other);
+    }
+}
@@ -0,0 +1,70 @@
+// src/sync/summary.rs
+
+//! What applying a remote update would change, for the confirmation prompt
+
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Size of a pending update, compared against what's on disk
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UpdateSummary {
+    pub files_changed: usize,   // New files, or existing ones whose size differs
+    pub files_removed: usize,   // In the current torrent but not the new one
+    pub bytes_to_download: u64, // Full size of every changed file
+}
+
+/// Compare the new torrent's files with the download folder and the current torrent's
+/// file list. Files are matched by size only, so this is an estimate: a file edited
+/// in place without changing size isn't counted until librqbit checks it.
+pub fn summarize_update(
+    download_path: &Path,
+    torrent_bytes: &[u8],
+    current_files: &HashSet<PathBuf>,
+) -> Result<UpdateSummary> {
+    let torrent = librqbit::torrent_from_bytes::<librqbit::ByteBuf>(torrent_bytes)
+        .context("Failed to parse updated torrent")?;
+
+    let mut summary = UpdateSummary::default();
+    let mut new_files = HashSet::new();
+    for file in torrent.info.iter_file_details()? {
+        let relative_path = file.filename.to_pathbuf()?;
+        let on_disk_len = std::fs::metadata(download_path.join(&relative_path)).ok().map(|m| m.len());
+        if on_disk_len != Some(file.len) {
+            summary.files_changed += 1;
+            summary.bytes_to_download += file.len;
+        }
+        new_files.insert(relative_path);
+    }
+    summary.files_removed = current_files.difference(&new_files).count();
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use librqbit::CreateTorrentOptions;
+    use std::fs;
+
+    #[tokio::test]
+    async fn test_summarize_update_counts_changed_and_removed() -> Result<()> {
+        let source = tempfile::tempdir()?;
+        fs::write(source.path().join("same.pbo"), vec![1u8; 1000])?;
+        fs::write(source.path().join("grown.pbo"), vec![2u8; 2000])?;
+        fs::write(source.path().join("new.pbo"), vec![3u8; 3000])?;
+        let torrent = librqbit::create_torrent(source.path(), CreateTorrentOptions::default()).await?;
+        let torrent_bytes = torrent.as_bytes()?;
+
+        let download = tempfile::tempdir()?;
+        fs::write(download.path().join("same.pbo"), vec![1u8; 1000])?;
+        fs::write(download.path().join("grown.pbo"), vec![2u8; 500])?;
+        let current: HashSet<PathBuf> = ["same.pbo", "grown.pbo", "old.pbo"].iter().map(PathBuf::from).collect();
+
+        let summary = summarize_update(download.path(), &torrent_bytes, &current)?;
+        assert_eq!(
+            summary,
+            UpdateSummary { files_changed: 2, files_removed: 1, bytes_to_download: 5000 }
+        );
+        Ok(())
+    }
+}
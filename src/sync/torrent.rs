@@ -5,6 +5,7 @@
 // - Forgetting the old torrent
 // - Monitoring torrent status for sync purposes (e.g., completion)
 
+use tracing::{Instrument, error, info, info_span, warn};
 use crate::config::AppConfig;
 use crate::ui::utils::SyncStatus;
 use crate::sync::messages::SyncEvent;
@@ -12,8 +13,20 @@ use anyhow::{Context, Result};
 use librqbit::{AddTorrent, AddTorrentOptions};
 use tokio::sync::mpsc;
 use librqbit::limits::LimitsConfig;
+use librqbit_bencode::BencodeValue;
 use std::num::NonZeroU32;
 
+use super::case_collisions::apply_case_collisions;
+use super::file_pause::apply_paused_files;
+use super::preallocate::preallocate;
+use super::notice::pending_notice;
+use super::overwrite::apply_overwrite_policy;
+use super::owner::{FolderOwner, foreign_owner, write_owner};
+use super::passkey::with_tracker_passkey;
+use super::permissions::{permission_error, report_permission_error};
+use super::private::ensure_private_safe;
+use super::safe_mode::apply_safe_mode;
+use super::upload_ramp::apply_upload_ramp;
 use super::utils::send_sync_status_event;
 
 // Function to manage the torrent task based on config
@@ -24,44 +37,56 @@ pub async fn manage_torrent_task(
     current_id_to_forget: Option<usize>,
     torrent_content: Vec<u8>,
 ) -> Result<Option<usize>> {
-    println!(
+    add_torrent_task(app_config, api, ui_tx, current_id_to_forget, torrent_content, false).await
+}
+
+/// Forget torrent `id` and add `torrent_content` in its place even when it's the same
+/// torrent, so librqbit checks every file again and fetches what's missing or damaged
+pub async fn recheck_torrent_task(
+    app_config: &AppConfig,
+    api: &librqbit::api::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+    id: usize,
+    torrent_content: Vec<u8>,
+) -> Result<Option<usize>> {
+    add_torrent_task(app_config, api, ui_tx, Some(id), torrent_content, true).await
+}
+
+async fn add_torrent_task(
+    app_config: &AppConfig,
+    api: &librqbit::api::Api,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+    current_id_to_forget: Option<usize>,
+    torrent_content: Vec<u8>,
+    recheck: bool,
+) -> Result<Option<usize>> {
+    info!(
         "Sync: Managing torrent task for URL: {}. Path: {}. Current ID to forget: {:?}",
         app_config.torrent_url,
         app_config.download_path.display(),
         current_id_to_forget
     );
 
-    // 1. Forget the old torrent if an ID was provided
-    if let Some(id_to_forget) = current_id_to_forget {
-        println!("Sync: Forgetting previous torrent ID: {}", id_to_forget);
-        send_sync_status_event(ui_tx, SyncStatus::UpdatingTorrent);
-        
-        match api
-            .api_torrent_action_forget(id_to_forget.into())
-            .await
-        {
-            Ok(_) => println!("Sync: Successfully forgot torrent {}", id_to_forget),
-            Err(e) => {
-                // Log error but proceed, maybe the torrent was already gone
-                eprintln!(
-                    "Sync: Error forgetting torrent {}: {}. Proceeding to add new one.",
-                    id_to_forget,
-                    e
-                );
-                 let _ = ui_tx.send(SyncEvent::Error(format!("Error forgetting old torrent {}: {}", id_to_forget, e)));
-            }
+    // 0. If the session already has this exact torrent (e.g. a double-triggered update),
+    // reuse it rather than forgetting and re-adding, which would re-check every file.
+    // A re-check of that same torrent wants exactly that, so it goes on to the re-add.
+    let info_hash = info_hash_of(&torrent_content);
+    if let Some(existing_id) = info_hash
+        .as_deref()
+        .and_then(|hash| find_torrent_by_info_hash(api, hash))
+        .filter(|existing_id| !recheck || current_id_to_forget != Some(*existing_id))
+    {
+        info!("Sync: Torrent already in session with ID {}, reusing it", existing_id);
+        if let Some(id_to_forget) = current_id_to_forget.filter(|id| *id != existing_id) {
+            forget_torrent(api, ui_tx, id_to_forget).await;
         }
+        let _ = ui_tx.send(SyncEvent::TorrentAdded(existing_id));
+        send_sync_status_event(ui_tx, SyncStatus::Idle);
+        return Ok(Some(existing_id));
     }
 
-    // 2. Add the new torrent
-    println!(
-        "Sync: Adding new torrent content ({} bytes) to path: {}",
-        torrent_content.len(),
-        app_config.download_path.display()
-    );
-
     if app_config.download_path.as_os_str().is_empty() {
-        println!("Sync: Download path is empty, cannot add torrent.");
+        info!("Sync: Download path is empty, cannot add torrent.");
         let err_msg = "Download path not configured".to_string();
         let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
         send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
@@ -69,58 +94,257 @@ pub async fn manage_torrent_task(
         return Ok(None);
     }
 
+    // Built before forgetting, so a refused update leaves the current torrent running
+    let add_span = info_span!("add", info_hash = info_hash.as_deref().unwrap_or("unknown"), bytes = torrent_content.len());
+    let plan = build_add_options(app_config, api, torrent_content, Some(ui_tx)).await?;
+
+    // 1. Forget the old torrent if an ID was provided
+    if let Some(id_to_forget) = current_id_to_forget {
+        send_sync_status_event(ui_tx, SyncStatus::UpdatingTorrent);
+        forget_torrent(api, ui_tx, id_to_forget).await;
+    }
+
+    // 2. Add the new torrent
+    info!(
+        "Sync: Adding new torrent content to path: {}",
+        app_config.download_path.display()
+    );
+
     // Notify that we're still updating - librqbit will do the checking internally
     send_sync_status_event(ui_tx, SyncStatus::UpdatingTorrent);
 
-    let add_request = AddTorrent::from_bytes(torrent_content);
-    
-    // Create a LimitsConfig based on app settings
-    let ratelimits = LimitsConfig {
-        // Convert KB/s to B/s (bytes per second) and to NonZeroU32
-        download_bps: app_config.max_download_speed.and_then(|s| {
-            let value = (s * 1024) as u32;
-            NonZeroU32::new(value)
-        }),
-        upload_bps: app_config.max_upload_speed.and_then(|s| {
-            let value = (s * 1024) as u32;
-            NonZeroU32::new(value)
-        }),
-    };
-    
-    let options = AddTorrentOptions {
-        output_folder: Some(app_config.download_path.to_string_lossy().into_owned()),
-        overwrite: true, // Important: ensures librqbit checks existing files
-        paused: !app_config.should_seed, // Opposite of should_seed
-        ratelimits,
-        ..Default::default()
-    };
-
-    println!(
-        "Sync: Applying settings - Seeding: {}, Upload limit: {:?} KB/s, Download limit: {:?} KB/s",
+    info!(
+        "Sync: Applying settings - Seeding after download: {}, Upload limit: {:?} KB/s, Download limit: {:?} KB/s, Upload allowed: {}, Download allowed: {}",
         app_config.should_seed,
         app_config.max_upload_speed,
-        app_config.max_download_speed
+        app_config.max_download_speed,
+        app_config.allow_upload,
+        app_config.allow_download
     );
 
-    let response = api
-        .api_add_torrent(add_request, Some(options))
+    let response = match api
+        .api_add_torrent(plan.request, Some(plan.options))
+        .instrument(add_span)
         .await
-        .context("Failed to add torrent via librqbit API")?;
+        .context("Failed to add torrent via librqbit API")
+    {
+        Ok(response) => response,
+        Err(e) => {
+            // A folder the user can't write to needs a different hint than other failures
+            if let Some(permission) = permission_error(&e) {
+                report_permission_error(&app_config.download_path, &format!("{:#}", e), permission, ui_tx);
+            }
+            return Err(e);
+        }
+    };
 
     if let Some(id) = response.id {
-        println!("Sync: Torrent added successfully with ID: {}", id);
+        info!("Sync: Torrent added successfully with ID: {}", id);
         let _ = ui_tx.send(SyncEvent::TorrentAdded(id));
+        if !plan.preserved.is_empty() {
+            let _ = ui_tx.send(SyncEvent::FilesPreserved(plan.preserved));
+        }
+        if let Some(warning) = plan.case_warning {
+            let _ = ui_tx.send(SyncEvent::Warning(warning));
+        }
+        if let Some(notice) = plan.notice {
+            info!("Sync: Torrent {} has a notice, waiting for it to be accepted", id);
+            let _ = ui_tx.send(SyncEvent::NoticeAcknowledgementNeeded { id, notice });
+        }
+        if plan.awaiting_confirmation {
+            info!("Sync: Torrent {} is public, waiting for seeding confirmation", id);
+            let _ = ui_tx.send(SyncEvent::SeedingConfirmationNeeded(id));
+        }
+        
+        // Claim the folder, unless another torrent already owns it
+        if foreign_owner(app_config).is_none() {
+            let owner = FolderOwner {
+                torrent_url: app_config.torrent_url.clone(),
+                info_hash: api.api_torrent_details(id.into()).ok().map(|d| d.info_hash),
+            };
+            if let Err(e) = write_owner(&app_config.download_path, &owner) {
+                warn!("Sync: {}", e);
+            }
+        }
         
         // Return to Idle after adding - state tracking is now separate from torrent state
         send_sync_status_event(ui_tx, SyncStatus::Idle);
         
         Ok(Some(id))
     } else {
-        println!("Sync: Torrent added but no ID returned by API.");
+        info!("Sync: Torrent added but no ID returned by API.");
         // Maybe send an error/warning? For now, return Ok(None)
         let err_msg = "Torrent added but API returned no ID".to_string();
         let _ = ui_tx.send(SyncEvent::Error(err_msg.clone()));
         send_sync_status_event(ui_tx, SyncStatus::Error(err_msg));
         Ok(None)
     }
-}
\ No newline at end of file
+}
+
+/// A torrent ready to hand to librqbit, and what to tell the user once it's added
+pub struct AddPlan {
+    pub request: AddTorrent<'static>,
+    pub options: AddTorrentOptions,
+    /// Files kept as they are on disk by the overwrite policy
+    pub preserved: Vec<String>,
+    pub case_warning: Option<String>,
+    /// Notice the torrent is held paused for until it's accepted
+    pub notice: Option<String>,
+    /// Public torrent held paused until seeding to the swarm is confirmed
+    pub awaiting_confirmation: bool,
+}
+
+/// Add options for `torrent_content` under every setting that shapes an add, shared by
+/// the sync and the start-up re-add of the cached torrent so the two can't drift
+pub async fn build_add_options(
+    app_config: &AppConfig,
+    api: &librqbit::api::Api,
+    torrent_content: Vec<u8>,
+    ui_tx: Option<&mpsc::UnboundedSender<SyncEvent>>,
+) -> Result<AddPlan> {
+    ensure_private_safe(app_config, api, &torrent_content)?;
+
+    // Hold public torrents paused until the user agrees to seed to the swarm
+    let awaiting_confirmation = needs_seeding_confirmation(app_config, &torrent_content);
+    // And any torrent until its notice is accepted
+    let notice = pending_notice(app_config, &torrent_content);
+
+    let mut options = AddTorrentOptions {
+        output_folder: Some(app_config.download_path.to_string_lossy().into_owned()),
+        overwrite: true, // Important: ensures librqbit checks existing files
+        paused: awaiting_confirmation || notice.is_some(), // A non-seeder still downloads; check_seeding pauses it once finished
+        ratelimits: torrent_ratelimits(app_config),
+        ..Default::default()
+    };
+    apply_safe_mode(app_config, &torrent_content, &mut options)?;
+    let preserved = apply_overwrite_policy(app_config, &torrent_content, &mut options)?;
+    apply_paused_files(app_config, &torrent_content, &mut options)?;
+    let case_warning = apply_case_collisions(app_config, &torrent_content, &mut options)?;
+    apply_upload_ramp(app_config, &torrent_content, &mut options);
+    preallocate(app_config, &torrent_content, &options, ui_tx).await?;
+    let request = AddTorrent::from_bytes(with_tracker_passkey(app_config, torrent_content)?);
+    Ok(AddPlan { request, options, preserved, case_warning, notice, awaiting_confirmation })
+}
+
+/// Per-torrent rate limits from the speed settings and direction toggles
+pub fn torrent_ratelimits(config: &AppConfig) -> LimitsConfig {
+    LimitsConfig {
+        download_bps: direction_limit(config.allow_download, config.max_download_speed),
+        upload_bps: direction_limit(config.allow_upload, config.max_upload_speed),
+    }
+}
+
+/// Lowest rate limit librqbit can run at, one 16 KiB block a second. Its limiter fails a
+/// transfer larger than the limit instead of waiting, which stops the torrent's peers.
+pub const MIN_RATE_LIMIT: NonZeroU32 = NonZeroU32::new(16 * 1024).unwrap();
+
+/// Rate limit for one direction in B/s, no lower than [`MIN_RATE_LIMIT`]. A disallowed
+/// direction is throttled to that until `check_seeding` pauses the torrent: an
+/// unfinished one while downloading is off, a finished one while uploading is off.
+fn direction_limit(allowed: bool, max_kbps: Option<u64>) -> Option<NonZeroU32> {
+    if !allowed {
+        return Some(MIN_RATE_LIMIT);
+    }
+    // Convert KB/s to B/s (bytes per second) and to NonZeroU32
+    max_kbps
+        .and_then(|s| NonZeroU32::new(s.saturating_mul(1024).min(u32::MAX as u64) as u32))
+        .map(|limit| limit.max(MIN_RATE_LIMIT))
+}
+
+/// Hex info hash of a .torrent file, or None if it can't be parsed
+pub fn info_hash_of(torrent_content: &[u8]) -> Option<String> {
+    librqbit::torrent_from_bytes::<librqbit::ByteBuf>(torrent_content)
+        .map(|meta| meta.info_hash.as_string())
+        .ok()
+}
+
+/// ID of the torrent in the session with the given hex info hash, if any
+pub fn find_torrent_by_info_hash(api: &librqbit::api::Api, info_hash: &str) -> Option<usize> {
+    api.api_torrent_list()
+        .torrents
+        .into_iter()
+        .find(|t| t.info_hash.eq_ignore_ascii_case(info_hash))
+        .and_then(|t| t.id)
+}
+
+// Remove a torrent from the session, keeping its files on disk
+#[tracing::instrument(name = "forget", skip(api, ui_tx))]
+async fn forget_torrent(api: &librqbit::api::Api, ui_tx: &mpsc::UnboundedSender<SyncEvent>, id_to_forget: usize) {
+    info!("Sync: Forgetting previous torrent ID: {}", id_to_forget);
+    match api
+        .api_torrent_action_forget(id_to_forget.into())
+        .await
+    {
+        Ok(_) => info!("Sync: Successfully forgot torrent {}", id_to_forget),
+        Err(e) => {
+            // Log error but proceed, maybe the torrent was already gone
+            error!(
+                "Sync: Error forgetting torrent {}: {}. Proceeding to add new one.",
+                id_to_forget,
+                e
+            );
+            let _ = ui_tx.send(SyncEvent::Error(format!("Error forgetting old torrent {}: {}", id_to_forget, e)));
+        }
+    }
+}
+
+/// Whether a torrent is marked private (seeded only to a private tracker's members).
+/// librqbit doesn't expose the flag, so decode the info dict and read its `private` key.
+pub fn is_private_torrent(torrent_content: &[u8]) -> bool {
+    let Ok(BencodeValue::Dict(torrent)) = librqbit_bencode::dyn_from_bytes::<&[u8]>(torrent_content) else {
+        return false;
+    };
+    match torrent.get(b"info".as_slice()) {
+        Some(BencodeValue::Dict(info)) => matches!(info.get(b"private".as_slice()), Some(BencodeValue::Integer(1))),
+        _ => false,
+    }
+}
+
+/// Seeding a public torrent exposes the user's IP to anyone in the swarm, so ask first
+/// unless they've already said not to
+pub fn needs_seeding_confirmation(config: &AppConfig, torrent_content: &[u8]) -> bool {
+    config.should_seed && !config.public_seeding_acknowledged && !is_private_torrent(torrent_content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_private_flag_detection() {
+        let public = b"d4:infod6:lengthi10e4:name3:abc12:piece lengthi16384eee";
+        let private = b"d4:infod6:lengthi10e4:name3:abc12:piece lengthi16384e7:privatei1eee";
+        assert!(!is_private_torrent(public));
+        assert!(is_private_torrent(private));
+        // Only the info dict's key counts, not the same bytes in a name or comment
+        let named = b"d7:comment12:7:privatei1e4:infod6:lengthi10e4:name12:7:privatei1e12:piece lengthi16384eee";
+        assert!(!is_private_torrent(named));
+        assert!(!is_private_torrent(b"d4:infod6:lengthi10e4:name3:abc7:privatei0eee"));
+        assert!(!is_private_torrent(b"not a torrent 7:privatei1e"));
+    }
+
+    #[test]
+    fn test_seeding_confirmation_only_for_unacknowledged_public_seeding() {
+        let public = b"d4:infod4:name3:abcee";
+        let mut config = AppConfig::default();
+        assert!(needs_seeding_confirmation(&config, public));
+
+        config.public_seeding_acknowledged = true;
+        assert!(!needs_seeding_confirmation(&config, public));
+
+        config.public_seeding_acknowledged = false;
+        config.should_seed = false;
+        assert!(!needs_seeding_confirmation(&config, public));
+    }
+
+    #[test]
+    fn test_direction_limit() {
+        assert_eq!(direction_limit(true, None), None);
+        assert_eq!(direction_limit(true, Some(500)), NonZeroU32::new(500 * 1024));
+        assert_eq!(direction_limit(true, Some(0)), None);
+        // librqbit can't limit below one block a second
+        assert_eq!(direction_limit(true, Some(2)), Some(MIN_RATE_LIMIT));
+        assert_eq!(direction_limit(false, None), Some(MIN_RATE_LIMIT));
+        assert_eq!(direction_limit(false, Some(500)), Some(MIN_RATE_LIMIT));
+    }
+}
@@ -0,0 +1,289 @@
+// src/sync/tracker_check.rs
+
+//! Reachability test for the trackers of the cached torrent, to tell a firewall or a
+//! tracker that is down apart from a sync problem. Each tracker is asked once for its
+//! seeder and leecher counts: HTTP trackers with a scrape where the URL allows one,
+//! otherwise a `stopped` announce that asks for no peers; UDP trackers with a BEP 15
+//! scrape. Nothing is announced to the swarm as downloading or seeding.
+
+use anyhow::{Context, Result, anyhow, bail};
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tracing::{error, info};
+
+use crate::config::{AppConfig, get_cached_torrent_path};
+
+use super::bencode::{dict_entries, parse_string};
+use super::passkey::{configured_passkey, inject_passkey, mask_passkey, percent_encode, probe_peer_id};
+
+/// How long one tracker gets to answer
+const TRACKER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Swarm counts a tracker reported; trackers may leave either out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrackerCounts {
+    pub seeders: Option<u64>,
+    pub leechers: Option<u64>,
+}
+
+/// Outcome of testing one tracker
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackerReport {
+    pub url: String, // As listed in the torrent, with any passkey masked
+    pub latency_ms: u64, // Until the answer or the failure
+    pub result: Result<TrackerCounts, String>,
+}
+
+/// The scrape URL for an HTTP announce URL, by the usual convention of replacing
+/// "announce" at the start of the last path segment. None when the tracker can't scrape.
+pub fn scrape_url(announce_url: &str) -> Option<String> {
+    let (path, query) = match announce_url.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (announce_url, None),
+    };
+    let slash = path.rfind('/')?;
+    let segment = path[slash + 1..].strip_prefix("announce")?;
+    let mut url = format!("{}scrape{}", &path[..=slash], segment);
+    if let Some(query) = query {
+        url.push('?');
+        url.push_str(query);
+    }
+    Some(url)
+}
+
+/// A bencoded integer such as `i42e`
+fn parse_int(value: &[u8]) -> Option<u64> {
+    std::str::from_utf8(value.strip_prefix(b"i")?.strip_suffix(b"e")?).ok()?.parse().ok()
+}
+
+/// The counts in a scrape file entry or announce response, failing on a "failure reason"
+fn parse_counts(dict: &[u8]) -> Result<TrackerCounts> {
+    let mut counts = TrackerCounts::default();
+    for (key, value) in dict_entries(dict).context("Unreadable tracker response")? {
+        match key {
+            b"failure reason" => bail!("{}", String::from_utf8_lossy(parse_string(value)?.0)),
+            b"complete" => counts.seeders = parse_int(value),
+            b"incomplete" => counts.leechers = parse_int(value),
+            _ => {}
+        }
+    }
+    Ok(counts)
+}
+
+/// The counts for the torrent in a scrape response
+fn parse_scrape(body: &[u8]) -> Result<TrackerCounts> {
+    for (key, value) in dict_entries(body).context("Unreadable scrape response")? {
+        match key {
+            b"failure reason" => bail!("{}", String::from_utf8_lossy(parse_string(value)?.0)),
+            b"files" => {
+                // Asked about one torrent, so any entry is it
+                if let Some((_, file)) = dict_entries(value)?.first() {
+                    return parse_counts(file);
+                }
+                bail!("Tracker doesn't know this torrent");
+            }
+            _ => {}
+        }
+    }
+    bail!("Scrape response has no files")
+}
+
+async fn check_http(client: &reqwest::Client, url: &str, info_hash: &[u8; 20]) -> Result<TrackerCounts> {
+    let separator = |url: &str| if url.contains('?') { '&' } else { '?' };
+    let encoded = percent_encode(info_hash);
+    let (request_url, is_scrape) = match scrape_url(url) {
+        Some(scrape) => (format!("{}{}info_hash={}", scrape, separator(&scrape), encoded), true),
+        None => (
+            format!(
+                "{}{}info_hash={}&peer_id={}&port=6881&uploaded=0&downloaded=0&left=0&event=stopped&compact=1&numwant=0",
+                url, separator(url), encoded, probe_peer_id()
+            ),
+            false,
+        ),
+    };
+    let response = client.get(&request_url).send().await.context("Request failed")?;
+    let status = response.status();
+    let body = response.bytes().await.context("Failed to read tracker response")?;
+    if !status.is_success() {
+        bail!("HTTP {}", status);
+    }
+    if is_scrape { parse_scrape(&body) } else { parse_counts(&body) }
+}
+
+/// Send `request` and wait for an answer to the same transaction, failing on a UDP tracker error
+async fn udp_round_trip(socket: &UdpSocket, request: &[u8], action: u32) -> Result<Vec<u8>> {
+    let transaction = &request[12..16];
+    socket.send(request).await.context("Failed to send to tracker")?;
+    let mut buf = [0u8; 1024];
+    loop {
+        let len = socket.recv(&mut buf).await.context("Failed to read from tracker")?;
+        let reply = &buf[..len];
+        if len < 8 || &reply[4..8] != transaction {
+            continue;
+        }
+        match u32::from_be_bytes(reply[..4].try_into()?) {
+            3 => bail!("{}", String::from_utf8_lossy(&reply[8..])),
+            got if got == action => return Ok(reply[8..].to_vec()),
+            got => bail!("Unexpected tracker action {}", got),
+        }
+    }
+}
+
+/// BEP 15: connect, then scrape
+async fn check_udp(url: &str, info_hash: &[u8; 20]) -> Result<TrackerCounts> {
+    let host = url
+        .strip_prefix("udp://")
+        .and_then(|rest| rest.split('/').next())
+        .filter(|host| !host.is_empty())
+        .context("Malformed UDP tracker URL")?;
+    let addr = tokio::net::lookup_host(host)
+        .await
+        .with_context(|| format!("Failed to resolve {}", host))?
+        .next()
+        .context("Tracker host has no address")?;
+    let socket = UdpSocket::bind(if addr.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" }).await?;
+    socket.connect(addr).await?;
+
+    let transaction: u32 = rand_u32();
+    let mut connect = 0x41727101980u64.to_be_bytes().to_vec();
+    connect.extend_from_slice(&0u32.to_be_bytes());
+    connect.extend_from_slice(&transaction.to_be_bytes());
+    let reply = udp_round_trip(&socket, &connect, 0).await?;
+    let connection_id = reply.get(..8).context("Short connect response")?;
+
+    let mut scrape = connection_id.to_vec();
+    scrape.extend_from_slice(&2u32.to_be_bytes());
+    scrape.extend_from_slice(&transaction.wrapping_add(1).to_be_bytes());
+    scrape.extend_from_slice(info_hash);
+    let reply = udp_round_trip(&socket, &scrape, 2).await?;
+    if reply.len() < 12 {
+        bail!("Short scrape response");
+    }
+    // Seeders, completed, leechers
+    let field = |i: usize| u64::from(u32::from_be_bytes([reply[i], reply[i + 1], reply[i + 2], reply[i + 3]]));
+    Ok(TrackerCounts { seeders: Some(field(0)), leechers: Some(field(8)) })
+}
+
+/// A transaction ID; it only has to tell our requests apart
+fn rand_u32() -> u32 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or_default();
+    nanos ^ std::process::id().rotate_left(16)
+}
+
+async fn check_tracker(client: &reqwest::Client, url: &str, info_hash: &[u8; 20]) -> Result<TrackerCounts> {
+    let check = async {
+        if url.starts_with("http://") || url.starts_with("https://") {
+            check_http(client, url, info_hash).await
+        } else if url.starts_with("udp://") {
+            check_udp(url, info_hash).await
+        } else {
+            Err(anyhow!("Unsupported tracker protocol"))
+        }
+    };
+    tokio::time::timeout(TRACKER_TIMEOUT, check)
+        .await
+        .unwrap_or_else(|_| Err(anyhow!("No answer within {} seconds", TRACKER_TIMEOUT.as_secs())))
+}
+
+/// Test every tracker of the cached torrent at once, using the announce URLs the session
+/// uses (passkey included). Reports keep the torrent's tracker order.
+pub async fn check_trackers(config: &AppConfig, client: &reqwest::Client) -> Result<Vec<TrackerReport>> {
+    let bytes = std::fs::read(get_cached_torrent_path()?).context("No downloaded torrent to test the trackers of")?;
+    let torrent = librqbit::torrent_from_bytes::<librqbit::ByteBuf>(&bytes).context("Failed to parse cached torrent")?;
+    let passkey = configured_passkey(config);
+    let primary = torrent.announce.as_ref().map(|url| url.as_ref().to_vec());
+    let mut urls: Vec<String> = Vec::new();
+    for url in torrent.iter_announce() {
+        let Ok(text) = std::str::from_utf8(url.as_ref()) else { continue };
+        let is_primary = primary.as_deref() == Some(url.as_ref());
+        let text = passkey.map_or(text.to_string(), |key| inject_passkey(text, key, is_primary));
+        if !urls.contains(&text) {
+            urls.push(text);
+        }
+    }
+    let info_hash = torrent.info_hash.0;
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, url) in urls.into_iter().enumerate() {
+        let client = client.clone();
+        tasks.spawn(async move {
+            let started = Instant::now();
+            let result = check_tracker(&client, &url, &info_hash).await;
+            (index, url, started.elapsed(), result)
+        });
+    }
+    let mut reports = Vec::new();
+    while let Some(done) = tasks.join_next().await {
+        let (index, url, elapsed, result) = done?;
+        let shown = passkey.map_or(url.clone(), |key| mask_passkey(&url, key));
+        let result = result.map_err(|e| passkey.map_or(format!("{:#}", e), |key| mask_passkey(&format!("{:#}", e), key)));
+        match &result {
+            Ok(counts) => info!("Sync: Tracker {} answered in {} ms: {:?}", shown, elapsed.as_millis(), counts),
+            Err(e) => error!("Sync: Tracker {} failed: {}", shown, e),
+        }
+        reports.push((index, TrackerReport { url: shown, latency_ms: elapsed.as_millis() as u64, result }));
+    }
+    reports.sort_by_key(|(index, _)| *index);
+    Ok(reports.into_iter().map(|(_, report)| report).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrape_url_convention() {
+        assert_eq!(scrape_url("http://t.example/announce").as_deref(), Some("http://t.example/scrape"));
+        assert_eq!(
+            scrape_url("https://t.example/abc/announce.php?passkey=k").as_deref(),
+            Some("https://t.example/abc/scrape.php?passkey=k")
+        );
+        assert_eq!(scrape_url("http://t.example/a/tracker"), None);
+        assert_eq!(scrape_url("http://t.example/announce/x"), None);
+    }
+
+    #[test]
+    fn test_parse_tracker_responses() -> Result<()> {
+        let scrape = b"d5:filesd20:aaaaaaaaaaaaaaaaaaaad8:completei12e10:downloadedi40e10:incompletei3eeee";
+        assert_eq!(parse_scrape(scrape)?, TrackerCounts { seeders: Some(12), leechers: Some(3) });
+        assert!(parse_scrape(b"d5:filesdee").is_err());
+
+        let announce = b"d8:completei5e8:intervali1800e5:peers0:e";
+        assert_eq!(parse_counts(announce)?, TrackerCounts { seeders: Some(5), leechers: None });
+        let failure = parse_counts(b"d14:failure reason12:unregisterede").unwrap_err();
+        assert_eq!(failure.to_string(), "unregistered");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_udp_scrape_round_trip() -> Result<()> {
+        let tracker = UdpSocket::bind("127.0.0.1:0").await?;
+        let url = format!("udp://{}/announce", tracker.local_addr()?);
+        let server = tokio::spawn(async move {
+            let mut buf = [0u8; 128];
+            let (_, peer) = tracker.recv_from(&mut buf).await?;
+            let mut reply = 0u32.to_be_bytes().to_vec();
+            reply.extend_from_slice(&buf[12..16]);
+            reply.extend_from_slice(&7u64.to_be_bytes());
+            tracker.send_to(&reply, peer).await?;
+
+            let (len, peer) = tracker.recv_from(&mut buf).await?;
+            assert_eq!(len, 36);
+            assert_eq!(&buf[..8], &7u64.to_be_bytes());
+            let mut reply = 2u32.to_be_bytes().to_vec();
+            reply.extend_from_slice(&buf[12..16]);
+            for count in [9u32, 20, 4] {
+                reply.extend_from_slice(&count.to_be_bytes());
+            }
+            tracker.send_to(&reply, peer).await?;
+            Ok::<_, std::io::Error>(())
+        });
+        let counts = check_udp(&url, &[1; 20]).await?;
+        server.await??;
+        assert_eq!(counts, TrackerCounts { seeders: Some(9), leechers: Some(4) });
+        Ok(())
+    }
+}
@@ -37,6 +37,55 @@ pub struct SyncState {
     
     /// The state of the remote torrent
     pub remote: RemoteTorrentState,
+    
+    /// Whether the download volume is below the configured free space minimum
+    pub low_disk_space: bool,
+    
+    /// IDs of torrents paused because the watched process is running, None when it isn't
+    pub paused_for_game: Option<Vec<usize>>,
+    
+    /// IDs of torrents paused because the bound interface is down, None when it's up
+    pub paused_for_interface: Option<Vec<usize>>,
+    
+    /// IDs of finished torrents paused because `global_seed_goal` is met, None when it isn't
+    pub paused_for_seed_goal: Option<Vec<usize>>,
+    
+    /// IDs of torrents paused because the battery is below `pause_on_battery_below`, None when it isn't
+    pub paused_for_battery: Option<Vec<usize>>,
+    
+    /// Whether downloading was stopped by the duration or clock-time limit
+    pub auto_stopped: bool,
+    
+    /// Hash and remote metadata of the update found but not applied yet; the cache keeps
+    /// the loaded torrent until it is
+    pub pending_update: Option<(String, super::utils::RemoteMeta)>,
+    
+    /// Update downloading into the staging folder, with the atomic swap strategy
+    pub staged: Option<super::staging::StagedUpdate>,
+    
+    /// Info hash and route rules the finished torrent's files were last routed with
+    pub routed: Option<(String, Vec<crate::config::RouteRule>)>,
+    
+    /// Info hash and patterns the finished torrent's archives were last extracted with
+    pub extracted: Option<(String, Vec<String>)>,
+    
+    /// Info hash, mode and owner the finished torrent's files were last given, see `post_download_chmod`
+    pub file_modes_applied: Option<(String, Option<u32>, Option<String>)>,
+    
+    /// Info hash the finished torrent's files were last recorded for, see `overwrite_policy`
+    pub synced_recorded: Option<String>,
+    
+    /// Info hash of the torrent last reported complete, None while it isn't
+    pub completed: Option<String>,
+    
+    /// Info hash of the torrent whose pieces `verify_after_complete` last checked in full
+    pub verified: Option<String>,
+    
+    /// ID of the torrent last re-added at the full upload limit, see `upload_limit_while_downloading`
+    pub upload_raised: Option<usize>,
+    
+    /// ID of the torrent paused because `should_seed`, `allow_upload` or `allow_download` holds it, None while none does
+    pub seeding_held: Option<usize>,
 }
 
 impl Default for SyncState {
@@ -44,6 +93,22 @@ impl Default for SyncState {
         SyncState {
             local: LocalTorrentState::NotLoaded,
             remote: RemoteTorrentState::Unknown,
+            low_disk_space: false,
+            paused_for_game: None,
+            paused_for_interface: None,
+            paused_for_seed_goal: None,
+            paused_for_battery: None,
+            auto_stopped: false,
+            pending_update: None,
+            staged: None,
+            routed: None,
+            extracted: None,
+            file_modes_applied: None,
+            synced_recorded: None,
+            completed: None,
+            verified: None,
+            upload_raised: None,
+            seeding_held: None,
         }
     }
 } 
\ No newline at end of file
@@ -0,0 +1,133 @@
+// src/sync/upload_ramp.rs
+
+//! `upload_limit_while_downloading`: keeps uploading to a trickle while the torrent is
+//! still downloading, so the bandwidth goes to fetching, and lifts it to
+//! `max_upload_speed` once `full_upload_at_percent` of the download is done. librqbit
+//! fixes a torrent's limits when it's added, so raising it means re-adding the torrent
+//! once, which re-checks its files. A record in the profile's session state remembers the
+//! torrent version that reached the threshold, so it's added at the full limit from then on.
+
+use std::num::NonZeroU32;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use librqbit::AddTorrentOptions;
+
+use crate::config::{AppConfig, FileStateStore, StateStore};
+
+use super::local::fix_missing_files;
+use super::messages::SyncEvent;
+use super::torrent::{MIN_RATE_LIMIT, info_hash_of};
+use super::types::{LocalTorrentState, SyncState};
+
+/// State record holding the info hash of the torrent that reached `full_upload_at_percent`
+const UPLOAD_RAMP_KEY: &str = "upload_ramp";
+
+/// Whether the torrent `info_hash` reached `full_upload_at_percent` before, per `store`
+pub fn threshold_recorded(store: &dyn StateStore, info_hash: &str) -> bool {
+    store.read(UPLOAD_RAMP_KEY).ok().flatten().is_some_and(|recorded| recorded.trim().eq_ignore_ascii_case(info_hash))
+}
+
+fn recorded_in_session(info_hash: &str) -> bool {
+    FileStateStore::session().is_ok_and(|store| threshold_recorded(&store, info_hash))
+}
+
+/// Upload limit in B/s while downloading: `low_kbps`, unless `current` is lower already.
+/// Like `allow_upload = false`, anything below [`MIN_RATE_LIMIT`] (0 KB/s too) gets that
+/// lowest rate librqbit can run at.
+pub fn limited_upload(current: Option<NonZeroU32>, low_kbps: u64) -> Option<NonZeroU32> {
+    let low = NonZeroU32::new(low_kbps.saturating_mul(1024).min(u32::MAX as u64) as u32)
+        .unwrap_or(MIN_RATE_LIMIT)
+        .max(MIN_RATE_LIMIT);
+    Some(current.map_or(low, |current| current.min(low)))
+}
+
+/// Whether a download of `total_bytes` with `progress_bytes` done has reached `percent`
+pub fn threshold_reached(progress_bytes: u64, total_bytes: u64, finished: bool, percent: u8) -> bool {
+    finished || progress_bytes.saturating_mul(100) >= total_bytes.saturating_mul(u64::from(percent.min(100)))
+}
+
+/// Lower the upload limit of a torrent about to be added, unless this version of it
+/// already reached the threshold
+pub fn apply_upload_ramp(config: &AppConfig, torrent_bytes: &[u8], options: &mut AddTorrentOptions) {
+    let Some(low_kbps) = config.upload_limit_while_downloading else {
+        return;
+    };
+    if info_hash_of(torrent_bytes).is_some_and(|info_hash| recorded_in_session(&info_hash)) {
+        return;
+    }
+    info!("Sync: Limiting upload to {} KB/s until {}% is downloaded", low_kbps, config.full_upload_at_percent);
+    options.ratelimits.upload_bps = limited_upload(options.ratelimits.upload_bps, low_kbps);
+}
+
+/// Re-add the torrent at the full upload limit once it reaches `full_upload_at_percent`
+pub async fn check_upload_ramp(config: &AppConfig, state: &mut SyncState, api: &librqbit::Api, ui_tx: &mpsc::UnboundedSender<SyncEvent>) {
+    if config.upload_limit_while_downloading.is_none() || state.staged.is_some() {
+        return;
+    }
+    let LocalTorrentState::Active { id } = state.local else {
+        return;
+    };
+    // Progress only counts once librqbit has checked the files and the torrent is live
+    let Ok(stats) = api.api_stats_v1(id.into()) else {
+        return;
+    };
+    if stats.live.is_none() || !threshold_reached(stats.progress_bytes, stats.total_bytes, stats.finished, config.full_upload_at_percent) {
+        return;
+    }
+    let Ok(details) = api.api_torrent_details(id.into()) else {
+        return;
+    };
+    if recorded_in_session(&details.info_hash) || state.upload_raised == Some(id) {
+        return;
+    }
+    info!("Sync: {}% downloaded, re-adding the torrent at the full upload limit", config.full_upload_at_percent);
+    let full_limit = AppConfig { upload_limit_while_downloading: None, ..config.clone() };
+    if !fix_missing_files(&full_limit, state, api, ui_tx).await {
+        return;
+    }
+    if let LocalTorrentState::Active { id: raised } = state.local {
+        state.upload_raised = Some(raised);
+    }
+    let written = FileStateStore::session().and_then(|store| store.write(UPLOAD_RAMP_KEY, &details.info_hash));
+    if let Err(e) = written {
+        // Without the record the next re-add comes back limited, and is raised again
+        warn!("Sync: Failed to record the raised upload limit: {:#}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_while_downloading_and_threshold() {
+        let kbps = |kbps: u32| NonZeroU32::new(kbps * 1024);
+        assert_eq!(limited_upload(None, 50), kbps(50));
+        assert_eq!(limited_upload(kbps(500), 50), kbps(50));
+        // A lower max_upload_speed, or uploads held off, stay as they are
+        assert_eq!(limited_upload(kbps(20), 50), kbps(20));
+        assert_eq!(limited_upload(Some(MIN_RATE_LIMIT), 50), Some(MIN_RATE_LIMIT));
+        // librqbit can't limit below one block a second
+        assert_eq!(limited_upload(None, 0), Some(MIN_RATE_LIMIT));
+        assert_eq!(limited_upload(None, 4), Some(MIN_RATE_LIMIT));
+
+        assert!(!threshold_reached(99, 100, false, 100));
+        assert!(threshold_reached(100, 100, false, 100));
+        assert!(threshold_reached(80, 100, false, 80));
+        assert!(!threshold_reached(79, 100, false, 80));
+        // A finished torrent with files left out counts as done
+        assert!(threshold_reached(60, 100, true, 100));
+    }
+
+    #[test]
+    fn test_threshold_recorded_per_version() -> anyhow::Result<()> {
+        let store = crate::config::MemoryStateStore::default();
+        let info_hash = "ab".repeat(20);
+        assert!(!threshold_recorded(&store, &info_hash));
+        store.write(UPLOAD_RAMP_KEY, &format!("{}\n", info_hash.to_uppercase()))?;
+        assert!(threshold_recorded(&store, &info_hash));
+        assert!(!threshold_recorded(&store, &"0".repeat(40)), "a new version starts limited again");
+        Ok(())
+    }
+}
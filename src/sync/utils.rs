@@ -3,17 +3,27 @@
 //! Utility functions for sync operations
 
 use anyhow::{Context, Result, anyhow};
+use reqwest::StatusCode;
+use reqwest::header::{ETAG, IF_NONE_MATCH};
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::time::Duration;
 use tokio::sync::mpsc;
+use tracing::{error, info, warn};
 
-use crate::config::get_cached_torrent_path;
+use crate::config::{FileStateStore, StateStore, get_cached_torrent_path};
 use crate::ui::utils::SyncStatus;
+use super::http::{DnsFailure, HttpStatusError, connection_refused, dns_failure};
 use super::messages::SyncEvent;
 
+/// Waits before each retry of a torrent URL whose host didn't resolve; flaky DNS often
+/// answers on a later try
+pub const DNS_RETRY_DELAYS: [Duration; 3] = [Duration::from_secs(1), Duration::from_secs(3), Duration::from_secs(8)];
+
 /// Helper function to send any event to UI
 pub fn send_sync_event(tx: &mpsc::UnboundedSender<SyncEvent>, event: SyncEvent) {
     if let Err(e) = tx.send(event) {
-        eprintln!("Sync: Failed to send event to UI: {}", e);
+        error!("Sync: Failed to send event to UI: {}", e);
     }
 }
 
@@ -22,18 +32,63 @@ pub fn send_sync_status_event(tx: &mpsc::UnboundedSender<SyncEvent>, status: Syn
     send_sync_event(tx, SyncEvent::StatusUpdate(status));
 }
 
+/// Where the torrent URL led on the last fetch, and the ETag the server gave
+/// for it, so the next check can ask "has this changed?" without re-downloading
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RemoteMeta {
+    pub source_url: String,   // The configured URL we requested
+    pub resolved_url: String, // Final URL after following redirects
+    pub etag: Option<String>,
+}
+
+/// Result of fetching the torrent file, possibly conditionally
+#[derive(Debug)]
+pub enum FetchedTorrent {
+    Modified { data: Vec<u8>, meta: RemoteMeta },
+    /// The server confirmed the ETag we sent still matches
+    NotModified(RemoteMeta),
+    /// The server answered 404 Not Found or 410 Gone
+    Missing(StatusCode),
+}
+
 /// Function to download a torrent file from a URL
 pub async fn download_torrent(url: &str, client: &reqwest::Client) -> Result<Vec<u8>> {
-    println!("Sync: Downloading torrent from: {}", url);
+    match fetch_torrent(url, client, None).await? {
+        FetchedTorrent::Modified { data, .. } => Ok(data),
+        FetchedTorrent::NotModified(_) => Err(anyhow!("Unexpected 304 Not Modified from {}", url)),
+        FetchedTorrent::Missing(status) => Err(HttpStatusError(status).into()),
+    }
+}
 
-    let response = client
-        .get(url)
-        .send()
-        .await
-        .with_context(|| format!("Failed to send request to {}", url))?;
+/// Fetch the torrent file, following redirects. With `known_etag`, the server can
+/// answer 304 Not Modified instead of sending the file again.
+pub async fn fetch_torrent(url: &str, client: &reqwest::Client, known_etag: Option<&str>) -> Result<FetchedTorrent> {
+    info!("Sync: Downloading torrent from: {}", url);
+
+    let mut request = client.get(url);
+    if let Some(etag) = known_etag {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    let response = request.send().await.map_err(|e| request_error(url, e))?;
+
+    let mut meta = RemoteMeta {
+        source_url: url.to_string(),
+        resolved_url: response.url().to_string(),
+        etag: response.headers().get(ETAG).and_then(|v| v.to_str().ok()).map(str::to_string),
+    };
+    if meta.resolved_url != url {
+        info!("Sync: Torrent URL resolved to: {}", meta.resolved_url);
+    }
 
+    if response.status() == StatusCode::NOT_MODIFIED && known_etag.is_some() {
+        meta.etag = meta.etag.or(known_etag.map(str::to_string));
+        return Ok(FetchedTorrent::NotModified(meta));
+    }
+    if matches!(response.status(), StatusCode::NOT_FOUND | StatusCode::GONE) {
+        return Ok(FetchedTorrent::Missing(response.status()));
+    }
     if !response.status().is_success() {
-        return Err(anyhow!("HTTP error: {}", response.status()));
+        return Err(HttpStatusError(response.status()).into());
     }
 
     let content = response
@@ -41,7 +96,51 @@ pub async fn download_torrent(url: &str, client: &reqwest::Client) -> Result<Vec
         .await
         .with_context(|| format!("Failed to read response body from {}", url))?;
 
-    Ok(content.to_vec())
+    Ok(FetchedTorrent::Modified { data: content.to_vec(), meta })
+}
+
+/// `fetch_torrent`, retried with backoff while the host doesn't resolve
+pub async fn fetch_torrent_retrying(url: &str, client: &reqwest::Client, known_etag: Option<&str>) -> Result<FetchedTorrent> {
+    for delay in DNS_RETRY_DELAYS {
+        match fetch_torrent(url, client, known_etag).await {
+            Err(e) if e.downcast_ref::<DnsFailure>().is_some() => {
+                warn!("Sync: {} Retrying in {} seconds.", e, delay.as_secs());
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+    fetch_torrent(url, client, known_etag).await
+}
+
+/// Surface redirect loop/limit errors from the redirect policy as the main message, and
+/// say whether the host didn't resolve or refused the connection
+fn request_error(url: &str, e: reqwest::Error) -> anyhow::Error {
+    if e.is_redirect()
+        && let Some(reason) = std::error::Error::source(&e)
+    {
+        return anyhow!("Failed to resolve {}: {}", url, reason);
+    }
+    if let Some(dns) = dns_failure(&e).cloned() {
+        return anyhow::Error::new(e).context(dns);
+    }
+    if connection_refused(&e) {
+        let host = e.url().and_then(|url| url.host_str()).unwrap_or(url).to_string();
+        return anyhow::Error::new(e).context(format!("{} refused the connection. The server may be down, or on another port.", host));
+    }
+    anyhow::Error::new(e).context(format!("Failed to send request to {}", url))
+}
+
+/// Read the remote metadata saved alongside the cached torrent, if any
+pub fn load_remote_meta() -> Option<RemoteMeta> {
+    let contents = FileStateStore::session().ok()?.read("meta").ok()??;
+    toml::from_str(&contents).ok()
+}
+
+/// Save the remote metadata for the torrent now in the cache
+pub fn save_remote_meta(meta: &RemoteMeta) -> Result<()> {
+    let contents = toml::to_string_pretty(meta).context("Failed to serialize remote metadata")?;
+    FileStateStore::session()?.write("meta", &contents).context("Failed to write remote metadata")
 }
 
 /// Function to calculate a hash for a torrent file
@@ -59,7 +158,7 @@ pub async fn get_local_torrent_hash() -> Result<Option<String>> {
 
     // Check if the file exists
     if !cache_path.exists() {
-        println!(
+        info!(
             "Sync: No local torrent cache file found at {}",
             cache_path.display()
         );
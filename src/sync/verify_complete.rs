@@ -0,0 +1,57 @@
+// src/sync/verify_complete.rs
+
+//! `verify_after_complete`: a full hash check of every downloaded piece once the torrent
+//! finishes, before it's reported complete. librqbit checks each piece as it arrives, but
+//! not what ends up on disk afterwards, so this catches a failing disk or a file changed
+//! behind its back. Damaged files are downloaded again by re-adding the torrent, whose own
+//! check then finds the bad pieces; the torrent is reported complete after that.
+
+use anyhow::Result;
+use librqbit::api::TorrentDetailsResponse;
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+
+use crate::config::AppConfig;
+use crate::ui::utils::SyncStatus;
+
+use super::messages::SyncEvent;
+use super::spot_check::{SpotCheckReport, spot_check_with_progress};
+use super::utils::send_sync_status_event;
+
+/// Files of the torrent that are downloaded, '/'-separated: padding and files left out
+/// of the download have nothing on disk to check
+pub fn downloaded_files(details: &TorrentDetailsResponse) -> Vec<PathBuf> {
+    details
+        .files
+        .iter()
+        .flatten()
+        .filter(|file| file.included && !file.attributes.padding)
+        .map(|file| PathBuf::from(file.components.join("/")))
+        .collect()
+}
+
+/// Hash every piece of the finished torrent's downloaded files against its torrent file
+/// `torrent_bytes`, showing `SyncStatus::Verifying` meanwhile
+pub async fn verify_downloaded(
+    config: &AppConfig,
+    torrent_bytes: Vec<u8>,
+    details: &TorrentDetailsResponse,
+    ui_tx: &mpsc::UnboundedSender<SyncEvent>,
+) -> Result<SpotCheckReport> {
+    let download_path = config.download_path.clone();
+    let files = downloaded_files(details);
+    let progress_tx = ui_tx.clone();
+    send_sync_status_event(ui_tx, SyncStatus::Verifying { percent: 0 });
+    tokio::task::spawn_blocking(move || {
+        let mut last = 0;
+        spot_check_with_progress(&torrent_bytes, &download_path, &files, |percent| {
+            if percent != last {
+                last = percent;
+                send_sync_status_event(&progress_tx, SyncStatus::Verifying { percent });
+            }
+        })
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|result| result)
+}
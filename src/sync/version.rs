@@ -0,0 +1,135 @@
+// src/sync/version.rs
+
+//! The game or mod version the synced folder is on, for launchers that must match a
+//! server exactly. It's declared with `expected_version`, or read from the first line of
+//! the file at `version_url`, fetched with each torrent check. Versions are remembered
+//! per torrent, so the one reported after a sync is the one that came with that torrent,
+//! not a newer one announced for an update still to come.
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+use crate::config::{AppConfig, FileStateStore, StateStore, get_cached_torrent_path};
+
+use super::messages::SyncEvent;
+use super::torrent::info_hash_of;
+
+/// State record holding the versions of recent torrents
+const VERSION_KEY: &str = "version";
+
+/// Longer text isn't a version string; it's cut off
+const MAX_VERSION_CHARS: usize = 100;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct VersionRecord {
+    versions: BTreeMap<String, String>, // Info hash of a torrent, and the version it came with
+}
+
+fn read_record(store: &dyn StateStore) -> VersionRecord {
+    store
+        .read(VERSION_KEY)
+        .ok()
+        .flatten()
+        .and_then(|contents| toml::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// The version in the text of a version file: its first non-empty line
+pub fn version_from_text(text: &str) -> Option<String> {
+    let line = text.lines().map(str::trim).find(|line| !line.is_empty())?;
+    Some(line.chars().take(MAX_VERSION_CHARS).collect())
+}
+
+/// Remember `version` for the torrent `info_hash`, forgetting every other torrent but those in `keep`
+pub fn remember_version(store: &dyn StateStore, info_hash: &str, version: &str, keep: &[String]) -> Result<()> {
+    let mut record = read_record(store);
+    record.versions.retain(|hash, _| keep.contains(hash));
+    record.versions.insert(info_hash.to_string(), version.to_string());
+    let contents = toml::to_string(&record).context("Failed to serialize versions")?;
+    store.write(VERSION_KEY, &contents)
+}
+
+/// The version remembered for the torrent `info_hash`
+pub fn remembered_version(store: &dyn StateStore, info_hash: &str) -> Option<String> {
+    read_record(store).versions.remove(info_hash)
+}
+
+/// Download the version file and read the version from it
+pub async fn fetch_version(url: &str, client: &reqwest::Client) -> Result<String> {
+    let response = client.get(url).send().await.with_context(|| format!("Failed to send request to {}", url))?;
+    if !response.status().is_success() {
+        return Err(anyhow!("HTTP error: {}", response.status()));
+    }
+    let text = response.text().await.with_context(|| format!("Failed to read response body from {}", url))?;
+    version_from_text(&text).ok_or_else(|| anyhow!("{} is empty", url))
+}
+
+/// Fetch `version_url` for the torrent just fetched and remember it. The version of the
+/// torrent in the cache is kept too, as that's what's synced until an update applies.
+/// A failed fetch keeps what was remembered before.
+pub async fn record_version(config: &AppConfig, client: &reqwest::Client, torrent_bytes: &[u8]) {
+    let Some(url) = config.version_url.as_deref().map(str::trim).filter(|url| !url.is_empty()) else {
+        return;
+    };
+    let Some(info_hash) = info_hash_of(torrent_bytes) else {
+        return;
+    };
+    let version = match fetch_version(url, client).await {
+        Ok(version) => version,
+        Err(e) => {
+            warn!("Sync: Could not fetch the version from {}: {:#}", url, e);
+            return;
+        }
+    };
+    let Ok(store) = FileStateStore::session() else {
+        return;
+    };
+    let cached = get_cached_torrent_path()
+        .ok()
+        .and_then(|cached| std::fs::read(cached).ok())
+        .and_then(|bytes| info_hash_of(&bytes));
+    match remember_version(&store, &info_hash, &version, cached.as_slice()) {
+        Ok(()) => info!("Sync: Torrent {} is version {}", info_hash, version),
+        Err(e) => error!("Sync: {:#}", e),
+    }
+}
+
+/// The version of the synced torrent `info_hash`: `expected_version`, or what `version_url` said for it
+pub fn synced_version(config: &AppConfig, info_hash: &str) -> Option<String> {
+    if let Some(version) = config.expected_version.as_deref().and_then(version_from_text) {
+        return Some(version);
+    }
+    config.version_url.as_ref()?;
+    remembered_version(&FileStateStore::session().ok()?, info_hash)
+}
+
+/// Tell the UI and the status API the version the folder is now on, or None while it isn't synced
+pub fn send_version(ui_tx: &mpsc::UnboundedSender<SyncEvent>, version: Option<String>) {
+    let _ = ui_tx.send(SyncEvent::VersionInfo { version });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_are_read_and_remembered_per_torrent() -> Result<()> {
+        assert_eq!(version_from_text("\n  2.18.1 (server 42)  \nnotes"), Some("2.18.1 (server 42)".to_string()));
+        assert_eq!(version_from_text(" \n"), None);
+        assert_eq!(version_from_text(&"9".repeat(500)).map(|v| v.len()), Some(MAX_VERSION_CHARS));
+
+        let store = crate::config::MemoryStateStore::default();
+        let (live, update, old) = ("a".repeat(40), "b".repeat(40), "c".repeat(40));
+        remember_version(&store, &old, "1.0", &[])?;
+        remember_version(&store, &live, "1.1", &[])?;
+        assert_eq!(remembered_version(&store, &old), None, "only torrents in keep are kept");
+        // An update announced before it's synced doesn't change the live torrent's version
+        remember_version(&store, &update, "1.2", std::slice::from_ref(&live))?;
+        assert_eq!(remembered_version(&store, &live).as_deref(), Some("1.1"));
+        assert_eq!(remembered_version(&store, &update).as_deref(), Some("1.2"));
+        Ok(())
+    }
+}
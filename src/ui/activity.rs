@@ -0,0 +1,249 @@
+// src/ui/activity.rs
+// Short feed of what ModSync did recently, built from sync events
+
+use chrono::{DateTime, Local};
+use eframe::egui::{self, Color32, RichText, Ui};
+use std::collections::VecDeque;
+
+use crate::sync::SyncEvent;
+use crate::ui::utils::{SyncStatus, format_size};
+
+/// Entries kept; older ones drop off the end
+const MAX_ENTRIES: usize = 20;
+
+/// Paths listed in the details of a file event
+const MAX_LISTED_FILES: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityEntry {
+    pub at: DateTime<Local>,
+    pub summary: String,
+    pub details: Option<String>, // Shown when the entry is clicked
+    pub is_error: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct ActivityFeed {
+    entries: VecDeque<ActivityEntry>, // Newest first
+    expanded: Option<DateTime<Local>>, // Entry showing its details
+    last_status: Option<SyncStatus>,
+    finished_torrent: Option<usize>, // Torrent last seen complete, so completion is logged once
+}
+
+fn file_details<'a>(files: impl ExactSizeIterator<Item = &'a std::path::Path>) -> Option<String> {
+    let count = files.len();
+    let mut lines: Vec<String> = files.take(MAX_LISTED_FILES).map(|path| path.display().to_string()).collect();
+    if count > MAX_LISTED_FILES {
+        lines.push(format!("... and {} more", count - MAX_LISTED_FILES));
+    }
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+impl ActivityFeed {
+    pub fn entries(&self) -> impl Iterator<Item = &ActivityEntry> {
+        self.entries.iter()
+    }
+
+    /// Add an entry for `event` if it's worth telling the user about
+    pub fn record(&mut self, event: &SyncEvent, now: DateTime<Local>) {
+        let (summary, details, is_error) = match event {
+            SyncEvent::TorrentAdded(id) => (format!("Torrent {} added", id), None, false),
+            SyncEvent::RemoteUpdateFound(_) => ("Update found".to_string(), None, false),
+            SyncEvent::UpdateCountdown(applies_at) => (format!("Update applies at {}", applies_at.format("%H:%M:%S")), None, false),
+            SyncEvent::UpdateCountdownEnded { applied: false } => ("Update countdown cancelled".to_string(), None, false),
+            SyncEvent::Changelog(text) => ("Release notes received".to_string(), Some(text.trim().to_string()), false),
+            SyncEvent::Error(message) => {
+                // "{:#}" errors chain their causes with ": "; the first part is the headline
+                let headline = message.split(": ").next().unwrap_or(message);
+                let details = (headline != message).then(|| message.replace(": ", "\n"));
+                (format!("Error: {}", headline), details, true)
+            }
+            SyncEvent::Warning(message) => (format!("Warning: {}", message), None, false),
+            SyncEvent::PermissionDenied(path) => (format!("Not allowed to write to {}", path.display()), None, true),
+            SyncEvent::ExtraFilesFound(files) if !files.is_empty() => (
+                format!("{} extra files found", files.len()),
+                file_details(files.iter().map(|path| path.as_path())),
+                false,
+            ),
+            SyncEvent::MissingFilesFound(files) if !files.is_empty() => (
+                format!("{} files missing", files.len()),
+                file_details(files.iter().map(|path| path.as_path())),
+                false,
+            ),
+            SyncEvent::PartialFilesCleaned(files) if !files.is_empty() => (
+                format!("Removed {} leftover partial files", files.len()),
+                file_details(files.iter().map(|path| path.as_path())),
+                false,
+            ),
+            SyncEvent::FilesRouted(summary) => {
+                let routed: usize = summary.folders.iter().map(|folder| folder.files).sum();
+                let mut lines: Vec<String> = summary.folders.iter()
+                    .map(|folder| format!("{} files to {}", folder.files, folder.destination.display()))
+                    .collect();
+                lines.extend(summary.failed.iter().map(|(path, error)| format!("Failed: {} ({})", path.display(), error)));
+                let text = format!("Routed {} files to {} folders", routed, summary.folders.len());
+                (text, (!lines.is_empty()).then(|| lines.join("\n")), !summary.failed.is_empty())
+            }
+            SyncEvent::ArchivesExtracted(summary) => {
+                let extracted: usize = summary.archives.iter().map(|archive| archive.files).sum();
+                let mut lines: Vec<String> = summary.archives.iter()
+                    .map(|archive| format!("{} files from {}", archive.files, archive.archive))
+                    .collect();
+                lines.extend(summary.skipped.iter().map(|(entry, why)| format!("Skipped: {} ({})", entry, why)));
+                lines.extend(summary.failed.iter().map(|(archive, error)| format!("Failed: {} ({})", archive, error)));
+                let text = format!("Extracted {} files from {} archives", extracted, summary.archives.len());
+                (text, (!lines.is_empty()).then(|| lines.join("\n")), !summary.failed.is_empty())
+            }
+            SyncEvent::FileModesApplied(summary) => {
+                let lines: Vec<String> = summary.failed.iter()
+                    .map(|(path, error)| format!("Failed: {} ({})", path.display(), error))
+                    .collect();
+                let text = format!("Set permissions on {} files and {} folders", summary.files, summary.folders);
+                (text, (!lines.is_empty()).then(|| lines.join("\n")), !summary.failed.is_empty())
+            }
+            SyncEvent::ConfigReloaded { changed, .. } if changed.is_empty() => ("Config reloaded, nothing changed".to_string(), None, false),
+            SyncEvent::ConfigReloaded { changed, .. } => {
+                (format!("Config reloaded, {} settings changed", changed.len()), Some(changed.join("\n")), false)
+            }
+            SyncEvent::ConfigApplied { changed } if !changed.is_empty() => {
+                (format!("Settings applied, {} changed", changed.len()), Some(changed.join("\n")), false)
+            }
+            SyncEvent::FilePrioritized(path) => (format!("Downloading {} first", path), None, false),
+            SyncEvent::VersionInfo { version: Some(version) } => (format!("Synced to version {}", version), None, false),
+            SyncEvent::HttpFallbackFetched(summary) => {
+                let mut lines = summary.fetched.clone();
+                lines.extend(summary.failed.iter().map(|(file, error)| format!("Failed: {} ({})", file, error)));
+                let text = format!("No peers, fetched {} files ({}) over HTTP", summary.fetched.len(), format_size(summary.bytes));
+                (text, (!lines.is_empty()).then(|| lines.join("\n")), !summary.failed.is_empty())
+            }
+            SyncEvent::GlobalSeedGoalMet { uploaded_bytes, ratio } => {
+                let ratio = ratio.map(|ratio| format!(", ratio {:.2}", ratio)).unwrap_or_default();
+                (format!("Seeding goal reached ({} uploaded{}), seeding paused", format_size(*uploaded_bytes), ratio), None, false)
+            }
+            SyncEvent::TorrentPublished(published) => (
+                format!("Published a new torrent ({} files)", published.file_count),
+                Some(format!("{}\nInfo hash {}", published.path.display(), published.info_hash)),
+                false,
+            ),
+            SyncEvent::FilesPreserved(paths) => (
+                format!("Kept {} locally edited files", paths.len()),
+                Some(paths.join("\n")),
+                false,
+            ),
+            SyncEvent::ManagedTorrentUpdate(Some((id, stats))) => {
+                if !stats.finished || self.finished_torrent == Some(*id) {
+                    if !stats.finished && self.finished_torrent == Some(*id) {
+                        self.finished_torrent = None;
+                    }
+                    return;
+                }
+                self.finished_torrent = Some(*id);
+                ("Download complete".to_string(), None, false)
+            }
+            SyncEvent::StatusUpdate(status) => {
+                let previous = self.last_status.replace(status.clone());
+                if previous.as_ref() == Some(status) {
+                    return;
+                }
+                let summary = match status {
+                    SyncStatus::Idle | SyncStatus::LocalActive if previous.as_ref().is_some_and(SyncStatus::is_in_progress) => {
+                        "Sync complete".to_string()
+                    }
+                    SyncStatus::RemoteChanged
+                    | SyncStatus::LowDiskSpace
+                    | SyncStatus::PausedForGame
+                    | SyncStatus::PausedLowBattery
+                    | SyncStatus::BindInterfaceDown
+                    | SyncStatus::Stopped => status.display_text(),
+                    _ => return,
+                };
+                (summary, None, false)
+            }
+            _ => return,
+        };
+
+        // The same error often repeats on every check
+        if self.entries.front().is_some_and(|last| last.summary == summary && last.details == details) {
+            return;
+        }
+        self.entries.push_front(ActivityEntry { at: now, summary, details, is_error });
+        self.entries.truncate(MAX_ENTRIES);
+    }
+
+    /// Draw the feed as one line per entry, newest first
+    pub fn ui(&mut self, ui: &mut Ui) {
+        if self.entries.is_empty() {
+            ui.label(RichText::new("Nothing yet").weak());
+            return;
+        }
+        egui::ScrollArea::vertical().max_height(120.0).id_salt("activity_feed").show(ui, |ui| {
+            for entry in &self.entries {
+                let color = if entry.is_error { Color32::LIGHT_RED } else { ui.visuals().text_color() };
+                let marker = match (&entry.details, self.expanded == Some(entry.at)) {
+                    (None, _) => " ",
+                    (Some(_), false) => "▶",
+                    (Some(_), true) => "▼",
+                };
+                let line = format!("{} {}  {}", marker, entry.at.format("%H:%M:%S"), entry.summary);
+                let response = ui.add(egui::Label::new(RichText::new(line).color(color)).sense(egui::Sense::click()));
+                if entry.details.is_some() && response.clicked() {
+                    self.expanded = if self.expanded == Some(entry.at) { None } else { Some(entry.at) };
+                }
+                if let (Some(details), true) = (&entry.details, self.expanded == Some(entry.at)) {
+                    ui.indent(entry.at.timestamp_nanos_opt(), |ui| {
+                        ui.label(RichText::new(details).small());
+                    });
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feed_keeps_notable_events_once() {
+        let mut feed = ActivityFeed::default();
+        let now = Local::now();
+        feed.record(&SyncEvent::StatusUpdate(SyncStatus::CheckingRemote), now);
+        feed.record(&SyncEvent::StatusUpdate(SyncStatus::Idle), now);
+        feed.record(&SyncEvent::StatusUpdate(SyncStatus::Idle), now);
+        feed.record(&SyncEvent::TorrentAdded(1), now);
+        feed.record(&SyncEvent::Error("Failed to fetch: connection refused".to_string()), now);
+        feed.record(&SyncEvent::Error("Failed to fetch: connection refused".to_string()), now);
+
+        let entries: Vec<&ActivityEntry> = feed.entries().collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].summary, "Error: Failed to fetch");
+        assert_eq!(entries[0].details.as_deref(), Some("Failed to fetch\nconnection refused"));
+        assert!(entries[0].is_error);
+        assert_eq!(entries[1].summary, "Torrent 1 added");
+        assert_eq!(entries[2].summary, "Sync complete");
+    }
+
+    #[test]
+    fn test_applied_settings_are_not_errors() {
+        let mut feed = ActivityFeed::default();
+        let now = Local::now();
+        feed.record(&SyncEvent::ConfigApplied { changed: Vec::new() }, now);
+        feed.record(&SyncEvent::ConfigApplied { changed: vec!["download_path".to_string(), "torrent_url".to_string()] }, now);
+
+        let entries: Vec<&ActivityEntry> = feed.entries().collect();
+        assert_eq!(entries.len(), 1, "nothing changed, nothing to show");
+        assert_eq!(entries[0].summary, "Settings applied, 2 changed");
+        assert_eq!(entries[0].details.as_deref(), Some("download_path\ntorrent_url"));
+        assert!(!entries[0].is_error);
+    }
+
+    #[test]
+    fn test_feed_is_capped() {
+        let mut feed = ActivityFeed::default();
+        for id in 0..(MAX_ENTRIES + 5) {
+            feed.record(&SyncEvent::TorrentAdded(id), Local::now());
+        }
+        assert_eq!(feed.entries().count(), MAX_ENTRIES);
+        assert_eq!(feed.entries().next().unwrap().summary, format!("Torrent {} added", MAX_ENTRIES + 4));
+    }
+}
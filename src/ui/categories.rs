@@ -0,0 +1,103 @@
+// src/ui/categories.rs
+// Groups per-file download progress into categories for the progress breakdown
+
+use crate::config::ProgressCategory;
+use crate::glob::glob_match;
+
+/// Label used for files no category matches
+const OTHER_CATEGORY: &str = "Other";
+
+/// Download progress summed over the files in one category
+#[derive(Debug, Clone, PartialEq)]
+pub struct CategoryProgress {
+    pub name: String,
+    pub total_bytes: u64,
+    pub progress_bytes: u64,
+}
+
+impl CategoryProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.total_bytes > 0 {
+            self.progress_bytes as f32 / self.total_bytes as f32
+        } else {
+            1.0
+        }
+    }
+}
+
+/// Sum `(path, length, downloaded)` per category. With no rules configured, files are
+/// grouped by their top-level folder. Configured categories keep their order (first
+/// matching rule wins); folder groups are sorted largest first.
+pub fn categorize(files: &[(String, u64, u64)], rules: &[ProgressCategory]) -> Vec<CategoryProgress> {
+    let mut groups: Vec<CategoryProgress> = rules
+        .iter()
+        .map(|rule| CategoryProgress { name: rule.name.clone(), total_bytes: 0, progress_bytes: 0 })
+        .collect();
+
+    for (path, length, downloaded) in files {
+        let path = path.replace('\\', "/");
+        let name = if rules.is_empty() {
+            match path.split_once('/') {
+                Some((top_level, _)) => top_level.to_string(),
+                None => OTHER_CATEGORY.to_string(),
+            }
+        } else {
+            rules
+                .iter()
+                .find(|rule| rule.patterns.iter().any(|pattern| glob_match(pattern, &path)))
+                .map_or_else(|| OTHER_CATEGORY.to_string(), |rule| rule.name.clone())
+        };
+
+        let index = match groups.iter().position(|g| g.name == name) {
+            Some(index) => index,
+            None => {
+                groups.push(CategoryProgress { name, total_bytes: 0, progress_bytes: 0 });
+                groups.len() - 1
+            }
+        };
+        groups[index].total_bytes += length;
+        groups[index].progress_bytes += (*downloaded).min(*length);
+    }
+
+    groups.retain(|g| g.total_bytes > 0);
+    if rules.is_empty() {
+        groups.sort_by(|a, b| b.total_bytes.cmp(&a.total_bytes).then_with(|| a.name.cmp(&b.name)));
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, length: u64, downloaded: u64) -> (String, u64, u64) {
+        (path.to_string(), length, downloaded)
+    }
+
+    #[test]
+    fn test_default_groups_by_top_level_folder() {
+        let files = [
+            file("@mod_a/addons/a.pbo", 100, 50),
+            file("@mod_a/keys/a.bikey", 10, 10),
+            file("@mod_b/addons/b.pbo", 300, 0),
+            file("readme.txt", 5, 5),
+        ];
+        let groups = categorize(&files, &[]);
+        let summary: Vec<_> = groups.iter().map(|g| (g.name.as_str(), g.total_bytes, g.progress_bytes)).collect();
+        assert_eq!(summary, vec![("@mod_b", 300, 0), ("@mod_a", 110, 60), ("Other", 5, 5)]);
+    }
+
+    #[test]
+    fn test_rules_first_match_wins_in_config_order() {
+        let rules = vec![
+            ProgressCategory { name: "Audio".to_string(), patterns: vec!["*.ogg".to_string(), "*.wss".to_string()] },
+            ProgressCategory { name: "Textures".to_string(), patterns: vec!["*.paa".to_string()] },
+            ProgressCategory { name: "Unused".to_string(), patterns: vec!["*.nothing".to_string()] },
+        ];
+        let files = [file("a/b.paa", 80, 40), file("c.ogg", 20, 20), file("d.pbo", 10, 0)];
+        let groups = categorize(&files, &rules);
+        let names: Vec<_> = groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["Audio", "Textures", "Other"]);
+        assert_eq!(groups[1].fraction(), 0.5);
+    }
+}
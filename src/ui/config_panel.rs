@@ -1,7 +1,13 @@
 // src/ui/config_panel.rs
 // Component for configuration UI
 
-use eframe::egui::{self, RichText};
+use eframe::egui::{self, ProgressBar, RichText};
+use std::time::{Duration, Instant};
+
+use crate::i18n::{tr, tr_fmt};
+
+/// How long typing has to pause before the URL is checked
+const URL_CHECK_DEBOUNCE: Duration = Duration::from_millis(800);
 
 /// Component for handling configuration settings
 pub struct ConfigPanel;
@@ -11,18 +17,42 @@ impl ConfigPanel {
     pub fn draw(ui: &mut egui::Ui, ui_state: &mut crate::ui::UiState) -> Option<crate::ui::UiAction> {
         let mut action = None;
         
-        ui.heading("ModSync Configuration");
+        ui.heading(tr("config.heading"));
+        if ui_state.session_failure.is_some() {
+            Self::draw_session_recovery(ui, ui_state, &mut action);
+        }
+        if ui_state.safe_mode_active {
+            Self::draw_safe_mode_banner(ui);
+        }
         ui.separator();
 
         // URL input
         ui.horizontal(|ui| {
-            ui.label("Remote Torrent URL:");
-            ui.text_edit_singleline(&mut ui_state.config_url);
+            ui.label(tr("config.remote_url"));
+            if ui.text_edit_singleline(&mut ui_state.config_url).changed() {
+                ui_state.url_check_due = Some(Instant::now() + URL_CHECK_DEBOUNCE);
+            }
+            Self::draw_url_check(ui, ui_state);
         });
+        if let Some(due) = ui_state.url_check_due {
+            let now = Instant::now();
+            if now >= due {
+                action = Some(crate::ui::UiAction::CheckUrl);
+            } else {
+                ui.ctx().request_repaint_after(due - now);
+            }
+        }
+        
+        // Shown when the URL is a redirector, so it's clear which file is actually used
+        if let Some(resolved_url) = &ui_state.resolved_url
+            && *resolved_url != ui_state.config_url.trim()
+        {
+            ui.label(RichText::new(tr_fmt("config.resolved_url", &[("url", resolved_url)])).weak());
+        }
         
         // Path input
         ui.horizontal(|ui| {
-            ui.label("Local Download Path:");
+            ui.label(tr("config.download_path"));
             ui.text_edit_singleline(&mut ui_state.config_path);
         });
 
@@ -31,14 +61,28 @@ impl ConfigPanel {
         // Buttons row 1 - Configuration
         ui.horizontal(|ui| {
             // Save config button
-            if ui.button("Save Configuration").clicked() {
+            if ui.button(tr("config.save")).clicked() {
                 action = Some(crate::ui::UiAction::SaveConfig);
             }
             
             // New button to update from remote URL
-            if ui.button("Update from Remote").clicked() {
+            if ui.button(tr("config.update_from_remote")).clicked() {
                 action = Some(crate::ui::UiAction::UpdateFromRemote);
             }
+            
+            // Dry-check the typed URL and path without saving
+            if ui.add_enabled(!ui_state.config_test_running, egui::Button::new(tr("config.test")))
+                .on_hover_text(tr("config.test_hover"))
+                .clicked()
+            {
+                action = Some(crate::ui::UiAction::TestConfig);
+            }
+            
+            // Cancel is only offered while something is actually running
+            let downloading = ui_state.torrent_stats.as_ref().is_some_and(|s| s.state == "Downloading");
+            if (ui_state.sync_status.is_in_progress() || downloading) && ui.button(tr("config.cancel")).clicked() {
+                action = Some(crate::ui::UiAction::CancelSync);
+            }
         });
         
         // Buttons row 2 - Operations
@@ -48,26 +92,208 @@ impl ConfigPanel {
 
             // Open folder button (only enabled if path is set)
             Self::draw_open_folder_button(ui, ui_state, &mut action);
+
+            if ui.button(tr("config.setup_wizard")).clicked() {
+                action = Some(crate::ui::UiAction::ShowSetupWizard);
+            }
+
+            if ui.button(tr("config.reload")).on_hover_text(tr("config.reload_hint")).clicked() {
+                action = Some(crate::ui::UiAction::ReloadConfig);
+            }
         });
 
+        Self::draw_config_test_results(ui, ui_state);
+
         ui.separator();
         
         // Sync status display
         Self::draw_sync_status(ui, ui_state);
         
+        // Downloads stopped by the time limit stay stopped until resumed
+        if ui_state.sync_status == crate::ui::utils::SyncStatus::Stopped
+            && ui.button(tr("config.resume_downloading")).clicked()
+        {
+            action = Some(crate::ui::UiAction::ResumeDownloads);
+        }
+        
+        // A notice put off with "Not Now" holds the torrent until it's accepted
+        if ui_state.notice_pending && ui_state.notice_dismissed {
+            ui.horizontal(|ui| {
+                ui.label(RichText::new(tr("notice.waiting")).color(ui.visuals().warn_fg_color));
+                if ui.button(tr("notice.read")).clicked() {
+                    action = Some(crate::ui::UiAction::ShowNotice);
+                }
+            });
+        }
+        
+        Self::draw_update_countdown(ui, ui_state, &mut action);
+        Self::draw_snooze_controls(ui, ui_state, &mut action);
+        
+        // Also re-enables automatic retries if they were stopped after repeated failures
+        if matches!(ui_state.sync_status, crate::ui::utils::SyncStatus::Error(_))
+            && ui.button(tr("config.retry")).clicked()
+        {
+            action = Some(crate::ui::UiAction::RetryAfterFailures);
+        }
+        
+        if let Some(path) = &ui_state.permission_denied_path {
+            let path = path.display().to_string();
+            ui.label(RichText::new(tr_fmt("config.permission_denied", &[("path", &path)])).color(ui.visuals().warn_fg_color).strong());
+            ui.label(RichText::new(tr("config.permission_hint")).weak());
+        }
+        
+        if let Some(kind) = &ui_state.network_share {
+            ui.label(RichText::new(tr_fmt("config.network_share", &[("kind", kind)])).color(ui.visuals().warn_fg_color));
+            ui.label(RichText::new(tr("config.network_share_hint")).weak());
+        }
+        
+        // Seeding summary, only relevant when seeding is enabled
+        if ui_state.should_seed {
+            Self::draw_seeding_summary(ui, ui_state, &mut action);
+        }
+        
         ui.separator();
         
         action
     }
     
+    /// Check mark next to the URL field, for the URL as currently typed
+    fn draw_url_check(ui: &mut egui::Ui, ui_state: &crate::ui::UiState) {
+        use crate::sync::preflight::UrlCheck;
+
+        let Some((url, result)) = &ui_state.url_check else {
+            return;
+        };
+        if url != ui_state.config_url.trim() {
+            return;
+        }
+        match result {
+            None => {
+                ui.spinner().on_hover_text(tr("config.url_checking"));
+            }
+            Some(UrlCheck::Torrent) => {
+                ui.label(RichText::new("✔").color(egui::Color32::GREEN).strong())
+                    .on_hover_text(tr("config.url_ok"));
+            }
+            Some(UrlCheck::NotATorrent(detail)) => {
+                ui.label(RichText::new("✖").color(egui::Color32::RED).strong());
+                ui.label(RichText::new(tr_fmt("config.url_not_torrent", &[("detail", detail)])).weak());
+            }
+            Some(UrlCheck::Unreachable(detail)) => {
+                ui.label(RichText::new("✖").color(egui::Color32::RED).strong());
+                ui.label(RichText::new(tr_fmt("config.url_unreachable", &[("detail", detail)])).weak());
+            }
+        }
+    }
+
     /// Draw the verify local files button
+    /// Reminder that safe mode can leave the folder out of date
+    /// Shown while the torrent session is down, with the error and ways to recover
+    fn draw_session_recovery(ui: &mut egui::Ui, ui_state: &mut crate::ui::UiState, action: &mut Option<crate::ui::UiAction>) {
+        use crate::sync::startup::SessionProblem;
+        let Some(failure) = ui_state.session_failure.as_ref() else {
+            return;
+        };
+        let problem = failure.problem;
+        let hint = match problem {
+            SessionProblem::PortInUse => tr("recovery.hint_port"),
+            SessionProblem::PermissionDenied => tr("recovery.hint_permission"),
+            SessionProblem::NetworkShare => tr("recovery.hint_network_share"),
+            SessionProblem::InUse => tr("recovery.hint_in_use"),
+            SessionProblem::Other => tr("recovery.hint_other"),
+        };
+        egui::Frame::NONE
+            .inner_margin(6.0)
+            .corner_radius(4.0)
+            .fill(ui.visuals().faint_bg_color)
+            .show(ui, |ui| {
+                ui.label(RichText::new(tr("recovery.heading")).strong().color(ui.visuals().error_fg_color));
+                ui.label(RichText::new(&failure.message).monospace());
+                ui.label(RichText::new(hint).weak());
+                if ui_state.session_retrying {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label(tr("recovery.retrying"));
+                    });
+                    return;
+                }
+                ui.horizontal(|ui| {
+                    if ui.button(tr("recovery.retry")).clicked() {
+                        *action = Some(crate::ui::UiAction::RetrySession);
+                    }
+                    if problem == SessionProblem::Other && ui.button(tr("recovery.clear_session")).clicked() {
+                        *action = Some(crate::ui::UiAction::ClearSessionAndRetry);
+                    }
+                });
+                if problem == SessionProblem::PortInUse {
+                    ui.horizontal(|ui| {
+                        ui.label(tr("recovery.port"));
+                        ui.add(egui::TextEdit::singleline(&mut ui_state.recovery_port_str).hint_text("4240").desired_width(60.0));
+                        if ui.button(tr("recovery.use_port")).clicked() {
+                            *action = Some(crate::ui::UiAction::UseListenPortAndRetry);
+                        }
+                    });
+                }
+            });
+    }
+
+    /// Seconds left before a scheduled update applies, with a way to stop it
+    fn draw_update_countdown(ui: &mut egui::Ui, ui_state: &crate::ui::UiState, action: &mut Option<crate::ui::UiAction>) {
+        let Some(applies_at) = ui_state.update_countdown else {
+            return;
+        };
+        let seconds = (applies_at - chrono::Local::now()).num_seconds().max(0);
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(tr_fmt("config.update_countdown", &[("seconds", &seconds)])).color(egui::Color32::YELLOW));
+            if ui.button(tr("config.cancel_update")).clicked() {
+                *action = Some(crate::ui::UiAction::CancelScheduledUpdate);
+            }
+        });
+        ui.ctx().request_repaint_after(Duration::from_secs(1));
+    }
+
+    /// Snooze automatic update checks for a while, or cancel a running snooze
+    fn draw_snooze_controls(ui: &mut egui::Ui, ui_state: &crate::ui::UiState, action: &mut Option<crate::ui::UiAction>) {
+        use crate::sync::snooze::SnoozeChoice;
+        if ui_state.updates_snoozed {
+            if ui.button(tr("config.resume_updates")).clicked() {
+                *action = Some(crate::ui::UiAction::SnoozeUpdates(None));
+            }
+            return;
+        }
+        ui.menu_button(tr("config.snooze_updates"), |ui| {
+            let choices = [
+                (tr("config.snooze_hour"), SnoozeChoice::Hours(1)),
+                (tr("config.snooze_hours"), SnoozeChoice::Hours(4)),
+                (tr("config.snooze_tomorrow"), SnoozeChoice::UntilTomorrow),
+            ];
+            for (label, choice) in choices {
+                if ui.button(label).clicked() {
+                    *action = Some(crate::ui::UiAction::SnoozeUpdates(Some(choice)));
+                    ui.close_menu();
+                }
+            }
+        });
+    }
+
+    fn draw_safe_mode_banner(ui: &mut egui::Ui) {
+        egui::Frame::NONE
+            .inner_margin(6.0)
+            .corner_radius(4.0)
+            .fill(ui.visuals().faint_bg_color)
+            .show(ui, |ui| {
+                ui.label(RichText::new(tr("config.safe_mode_banner")).strong().color(ui.visuals().warn_fg_color));
+                ui.label(RichText::new(tr("config.safe_mode_detail")).weak());
+            });
+    }
+
     fn draw_verify_button(ui: &mut egui::Ui, ui_state: &crate::ui::UiState, action: &mut Option<crate::ui::UiAction>) {
         // Enable button only when config is valid
         let is_config_valid = ui_state.is_config_valid();
 
         if ui.add_enabled(
             is_config_valid,
-            egui::Button::new("Verify Local Files")
+            egui::Button::new(tr("config.verify"))
         ).clicked() {
             *action = Some(crate::ui::UiAction::VerifyLocalFiles);
         }
@@ -80,21 +306,93 @@ impl ConfigPanel {
 
         if ui.add_enabled(
             is_path_set,
-            egui::Button::new("Open Folder")
+            egui::Button::new(tr("config.open_folder"))
         ).clicked() {
             *action = Some(crate::ui::UiAction::OpenDownloadFolder);
         }
     }
     
+    /// Draw one pass/fail line per config test check
+    fn draw_config_test_results(ui: &mut egui::Ui, ui_state: &crate::ui::UiState) {
+        if ui_state.config_test_running {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(tr("config.test_running"));
+            });
+            return;
+        }
+        let Some(checks) = &ui_state.config_test_results else {
+            return;
+        };
+        for check in checks {
+            draw_config_check(ui, check);
+        }
+    }
+    
     /// Draw the sync status display
     fn draw_sync_status(ui: &mut egui::Ui, ui_state: &crate::ui::UiState) {
         ui.horizontal(|ui| {
-            ui.label("Sync Status: ");
+            ui.label(tr("config.sync_status"));
             ui.label(
                 RichText::new(ui_state.sync_status.display_text())
                     .color(ui_state.sync_status.display_color())
                     .strong()
             );
+            if let Some(version) = &ui_state.synced_version {
+                ui.label(RichText::new(tr_fmt("config.version_badge", &[("version", version)])).color(egui::Color32::LIGHT_GREEN));
+            }
+        });
+        
+        // The initial check, and the full one after download, can take a while on large torrents; show that it's moving
+        if let crate::ui::utils::SyncStatus::Checking { percent } | crate::ui::utils::SyncStatus::Verifying { percent } = ui_state.sync_status {
+            ui.add(
+                ProgressBar::new(percent as f32 / 100.0)
+                    .text(tr_fmt("config.checking_files", &[("percent", &percent)]))
+                    .animate(true)
+            );
+        }
+    }
+    
+    /// Draw the compact "what's seeding" summary
+    fn draw_seeding_summary(ui: &mut egui::Ui, ui_state: &crate::ui::UiState, action: &mut Option<crate::ui::UiAction>) {
+        let summary = &ui_state.seeding_summary;
+        let ratio_text = summary
+            .ratio()
+            .map_or_else(|| "-".to_string(), |r| format!("{:.2}", r));
+        
+        ui.horizontal(|ui| {
+            ui.label(tr("config.seeding"));
+            ui.label(RichText::new(tr_fmt("config.seeding_active", &[("count", &summary.active_seeding)])).strong());
+            ui.separator();
+            ui.label(tr_fmt("config.upload", &[("speed", &crate::ui::utils::format_speed(summary.upload_speed))]));
+            ui.separator();
+            ui.label(tr_fmt("config.ratio", &[("ratio", &ratio_text)]));
+            if ui.small_button(tr("config.reset"))
+                .on_hover_text(tr("config.reset_hover"))
+                .clicked()
+            {
+                *action = Some(crate::ui::UiAction::ResetGlobalStats);
+            }
         });
     }
-} 
\ No newline at end of file
+} 
+/// One pass/fail line for a config test check
+pub(crate) fn draw_config_check(ui: &mut egui::Ui, check: &crate::sync::preflight::ConfigCheck) {
+    use crate::sync::preflight::ConfigCheckKind;
+
+    let name = match check.kind {
+        ConfigCheckKind::TorrentUrl => tr("config.check_torrent_url"),
+        ConfigCheckKind::TorrentParses => tr("config.check_torrent_parses"),
+        ConfigCheckKind::PathWritable => tr("config.check_path_writable"),
+        ConfigCheckKind::FreeSpace => tr("config.check_free_space"),
+    };
+    let (mark, color) = if check.passed {
+        ("✔", egui::Color32::GREEN)
+    } else {
+        ("✖", egui::Color32::RED)
+    };
+    ui.horizontal(|ui| {
+        ui.label(RichText::new(format!("{} {}", mark, name)).color(color).strong());
+        ui.label(RichText::new(&check.detail).weak());
+    });
+}
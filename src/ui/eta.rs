@@ -0,0 +1,165 @@
+// src/ui/eta.rs
+// Smoothed transfer speeds and the wall-clock time a download should finish
+
+use chrono::{DateTime, Duration, Local};
+use std::time::{Duration as StdDuration, Instant};
+
+/// Time constant of the speed average; samples older than this fade out quickly
+const SMOOTHING_SECS: f64 = 10.0;
+
+/// The average always spans at least this many stats polls, however slow they are
+const MIN_SAMPLES_SMOOTHED: f64 = 5.0;
+
+/// Below this speed (B/s) any finish time would be meaningless
+const MIN_ESTIMATE_SPEED: f64 = 1024.0;
+
+/// Exponential moving average of a transfer speed. Samples are weighted by the time
+/// since the previous one, so the result doesn't depend on how often the UI repaints.
+#[derive(Debug, Default)]
+pub struct SpeedSmoother {
+    average: Option<f64>,
+    last_sample: Option<Instant>,
+    poll_interval_secs: f64, // How often the speed behind the samples is refreshed
+    window_secs: Option<f64>, // Time constant chosen by the user, instead of one fitted to the poll interval
+}
+
+impl SpeedSmoother {
+    /// Average over a fixed `secs`, e.g. `speed_average_window_secs`; 0 passes samples through
+    pub fn set_window(&mut self, secs: u64) {
+        self.window_secs = Some(secs as f64);
+    }
+
+    /// Match the averaging window to how often stats are polled
+    pub fn set_poll_interval(&mut self, interval: StdDuration) {
+        self.poll_interval_secs = interval.as_secs_f64();
+    }
+
+    fn smoothing_secs(&self) -> f64 {
+        self.window_secs.unwrap_or_else(|| SMOOTHING_SECS.max(self.poll_interval_secs * MIN_SAMPLES_SMOOTHED))
+    }
+
+    /// Add a speed sample (B/s) taken at `now` and return the smoothed speed
+    pub fn sample(&mut self, speed: f64, now: Instant) -> f64 {
+        let average = match (self.average, self.last_sample) {
+            _ if self.smoothing_secs() <= 0.0 => speed,
+            (Some(average), Some(last)) => {
+                let elapsed = now.saturating_duration_since(last).as_secs_f64();
+                let weight = 1.0 - (-elapsed / self.smoothing_secs()).exp();
+                average + (speed - average) * weight
+            }
+            _ => speed,
+        };
+        self.average = Some(average);
+        self.last_sample = Some(now);
+        average
+    }
+
+    /// Forget the history, e.g. when the managed torrent changes
+    pub fn reset(&mut self) {
+        self.average = None;
+        self.last_sample = None;
+    }
+}
+
+/// When `remaining_bytes` will be done at `speed` B/s, or None if that's too slow to tell
+pub fn estimate_completion(remaining_bytes: u64, speed: f64, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    if speed.is_nan() || speed < MIN_ESTIMATE_SPEED {
+        return None;
+    }
+    let seconds = remaining_bytes as f64 / speed;
+    // Anything past a year is as good as unknown, and keeps chrono in range
+    if seconds > 365.0 * 24.0 * 3600.0 {
+        return None;
+    }
+    Some(now + Duration::seconds(seconds.ceil() as i64))
+}
+
+/// "14:35" today, "Tue 14:35" within the week, a full date after that, or "unknown"
+pub fn format_completion(done_by: Option<DateTime<Local>>, now: DateTime<Local>) -> String {
+    let Some(done_by) = done_by else {
+        return "unknown".to_string();
+    };
+    let days_ahead = (done_by.date_naive() - now.date_naive()).num_days();
+    if days_ahead == 0 {
+        done_by.format("%H:%M").to_string()
+    } else if days_ahead < 7 {
+        done_by.format("%a %H:%M").to_string()
+    } else {
+        done_by.format("%Y-%m-%d %H:%M").to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_smoother_damps_spikes_but_follows_sustained_change() {
+        let start = Instant::now();
+        let mut smoother = SpeedSmoother::default();
+        assert_eq!(smoother.sample(1000.0, start), 1000.0);
+
+        // A one-second spike moves the average only a little
+        let spiked = smoother.sample(11_000.0, start + StdDuration::from_secs(1));
+        assert!(spiked > 1000.0 && spiked < 2500.0, "{}", spiked);
+
+        // A change held for a minute is followed almost entirely
+        let mut average = spiked;
+        for second in 2..=60 {
+            average = smoother.sample(5000.0, start + StdDuration::from_secs(second));
+        }
+        assert!((average - 5000.0).abs() < 50.0, "{}", average);
+    }
+
+    #[test]
+    fn test_slow_polling_widens_the_window() {
+        let start = Instant::now();
+        let mut fast = SpeedSmoother::default();
+        let mut slow = SpeedSmoother::default();
+        slow.set_poll_interval(StdDuration::from_secs(10));
+        for smoother in [&mut fast, &mut slow] {
+            smoother.sample(1000.0, start);
+        }
+        // One 10s poll interval of a new speed is a single sample for the slow smoother
+        let fast_average = fast.sample(11_000.0, start + StdDuration::from_secs(10));
+        let slow_average = slow.sample(11_000.0, start + StdDuration::from_secs(10));
+        assert!(slow_average < fast_average, "{} vs {}", slow_average, fast_average);
+        assert!(slow_average < 3500.0, "{}", slow_average);
+
+        // Resetting forgets the speed but keeps the interval
+        slow.reset();
+        assert_eq!(slow.smoothing_secs(), 50.0);
+    }
+
+    #[test]
+    fn test_configured_window() {
+        let start = Instant::now();
+        let mut calm = SpeedSmoother::default();
+        calm.set_window(30);
+        calm.set_poll_interval(StdDuration::from_secs(10));
+        assert_eq!(calm.smoothing_secs(), 30.0, "the configured window wins over the poll interval");
+        calm.sample(1000.0, start);
+        let average = calm.sample(11_000.0, start + StdDuration::from_secs(1));
+        assert!(average > 1000.0 && average < 1500.0, "{}", average);
+
+        let mut raw = SpeedSmoother::default();
+        raw.set_window(0);
+        raw.sample(1000.0, start);
+        assert_eq!(raw.sample(11_000.0, start), 11_000.0);
+    }
+
+    #[test]
+    fn test_estimate_and_format() {
+        let now = Local.with_ymd_and_hms(2026, 3, 2, 14, 0, 0).unwrap();
+        let done_by = estimate_completion(35 * 60 * 10_000, 10_000.0, now);
+        assert_eq!(format_completion(done_by, now), "14:35");
+
+        let tomorrow = estimate_completion(24 * 3600 * 10_000, 10_000.0, now);
+        assert_eq!(format_completion(tomorrow, now), tomorrow.unwrap().format("%a 14:00").to_string());
+
+        assert_eq!(estimate_completion(1_000_000, 10.0, now), None);
+        assert_eq!(estimate_completion(1_000_000, f64::NAN, now), None);
+        assert_eq!(format_completion(None, now), "unknown");
+    }
+}
@@ -0,0 +1,136 @@
+// src/ui/markdown.rs
+// Just enough markdown for release notes: headings, bullets, **bold** and `code`
+
+use eframe::egui::{self, RichText};
+
+/// A run of text with one style
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub bold: bool,
+    pub code: bool,
+}
+
+/// One line of the document
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Heading(usize, Vec<Span>), // Level 1-6
+    Bullet(Vec<Span>),
+    Paragraph(Vec<Span>),
+    Blank,
+}
+
+/// Split `text` on `**` and backticks. Unclosed markers are kept as plain text.
+fn parse_inline(text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut rest = text;
+    let mut plain = String::new();
+    while !rest.is_empty() {
+        let styled = [("**", false), ("`", true)].into_iter().find_map(|(marker, code)| {
+            let inner = rest.strip_prefix(marker)?;
+            let end = inner.find(marker).filter(|end| *end > 0)?;
+            Some((code, &inner[..end], &inner[end + marker.len()..]))
+        });
+        match styled {
+            Some((code, inner, after)) => {
+                if !plain.is_empty() {
+                    spans.push(Span { text: std::mem::take(&mut plain), bold: false, code: false });
+                }
+                spans.push(Span { text: inner.to_string(), bold: !code, code });
+                rest = after;
+            }
+            None => {
+                let ch = rest.chars().next().unwrap_or_default();
+                plain.push(ch);
+                rest = &rest[ch.len_utf8()..];
+            }
+        }
+    }
+    if !plain.is_empty() {
+        spans.push(Span { text: plain, bold: false, code: false });
+    }
+    spans
+}
+
+/// Parse `text` line by line
+pub fn parse_markdown(text: &str) -> Vec<Block> {
+    text.lines()
+        .map(|line| {
+            let line = line.trim();
+            let level = line.chars().take_while(|c| *c == '#').count();
+            if line.is_empty() {
+                Block::Blank
+            } else if (1..=6).contains(&level) && line[level..].starts_with(' ') {
+                Block::Heading(level, parse_inline(line[level..].trim()))
+            } else if let Some(item) = line.strip_prefix("- ").or(line.strip_prefix("* ")) {
+                Block::Bullet(parse_inline(item.trim()))
+            } else {
+                Block::Paragraph(parse_inline(line))
+            }
+        })
+        .collect()
+}
+
+fn draw_spans(ui: &mut egui::Ui, spans: &[Span], size: Option<f32>, strong: bool) {
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        for span in spans {
+            let mut text = RichText::new(&span.text);
+            if let Some(size) = size {
+                text = text.size(size);
+            }
+            if span.bold || strong {
+                text = text.strong();
+            }
+            if span.code {
+                text = text.code();
+            }
+            ui.label(text);
+        }
+    });
+}
+
+/// Draw `text` as markdown
+pub fn draw_markdown(ui: &mut egui::Ui, text: &str) {
+    for block in parse_markdown(text) {
+        match block {
+            Block::Heading(level, spans) => {
+                let size = match level {
+                    1 => 20.0,
+                    2 => 17.0,
+                    _ => 15.0,
+                };
+                draw_spans(ui, &spans, Some(size), true);
+            }
+            Block::Bullet(spans) => {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("•");
+                    draw_spans(ui, &spans, None, false);
+                });
+            }
+            Block::Paragraph(spans) => draw_spans(ui, &spans, None, false),
+            Block::Blank => ui.add_space(4.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(text: &str) -> Span {
+        Span { text: text.to_string(), bold: false, code: false }
+    }
+
+    #[test]
+    fn test_parse_release_notes() {
+        let blocks = parse_markdown("## v1.2\n\n- **New** map\n* Fixed `init.sqf`\n#hashtag and **open");
+        assert_eq!(blocks, vec![
+            Block::Heading(2, vec![plain("v1.2")]),
+            Block::Blank,
+            Block::Bullet(vec![Span { text: "New".to_string(), bold: true, code: false }, plain(" map")]),
+            Block::Bullet(vec![plain("Fixed "), Span { text: "init.sqf".to_string(), bold: false, code: true }]),
+            Block::Paragraph(vec![plain("#hashtag and **open")]),
+        ]);
+    }
+}
@@ -1,18 +1,84 @@
 use crate::app::MyApp;
 use eframe::egui::{self, CentralPanel};
+use tracing::{error, info};
 use crate::actions; // Import actions module
-use crate::ui::state::{UiState, UiAction, TorrentStats, TorrentFileStats, ModalState};
+use crate::ui::state::{UiState, UiAction, TorrentStats, TorrentFileStats, TorrentFileEntry, ModalState, SeedingSummary};
 use crate::ui::utils::SyncStatus; // Import SyncStatus
 
 // Create sub-modules
 mod torrent_display;
+pub mod activity;
+pub mod categories;
+pub mod eta;
 mod config_panel;
+pub mod setup_wizard;
 pub mod torrent_file_tree;
 pub mod utils; // Make utils public
 pub mod state; // Make state module public
+pub mod theme;
+mod markdown;
 mod modals;
 
+/// Rebuild the Files tab list, progress categories and private flag from fresh stats
+fn rebuild_torrent_files(
+    app_config: &crate::config::AppConfig,
+    stats: &librqbit::TorrentStats,
+    torrent_id: usize,
+    api: &librqbit::Api,
+    ui_state: &mut UiState,
+) {
+    let Ok(details) = api.api_torrent_details(torrent_id.into()) else {
+        // Clear file details if API call fails
+        ui_state.torrent_files = None;
+        ui_state.category_progress.clear();
+        return;
+    };
+    let paused_files: std::collections::HashSet<&str> = app_config.paused_files.iter().map(String::as_str).collect();
+    
+    // Selected files, plus paused ones so they can be resumed
+    let file_data: Vec<TorrentFileEntry> = details.files.iter().flatten()
+        .zip(stats.file_progress.iter().map(Some).chain(std::iter::repeat(None)))
+        .filter_map(|(f, downloaded)| {
+            let path = f.components.join("/");
+            let paused = paused_files.contains(path.as_str());
+            (f.included || paused).then(|| TorrentFileEntry {
+                path,
+                size: f.length,
+                paused,
+                complete: downloaded.is_some_and(|d| *d >= f.length),
+            })
+        })
+        .collect();
+    
+    // file_progress is indexed like the torrent's file list
+    let file_progress: Vec<(String, u64, u64)> = details.files.iter().flatten()
+        .zip(stats.file_progress.iter().chain(std::iter::repeat(&0)))
+        .filter(|(f, _)| f.included)
+        .map(|(f, downloaded)| (f.components.join("/"), f.length, *downloaded))
+        .collect();
+    ui_state.category_progress = categories::categorize(&file_progress, &app_config.progress_categories);
+    // The flag isn't in the torrent details; read the cached torrent once per info hash
+    if ui_state.private_flag.as_ref().is_none_or(|(hash, _)| *hash != details.info_hash) {
+        let private = crate::config::get_cached_torrent_path()
+            .ok()
+            .and_then(|path| std::fs::read(path).ok())
+            .is_some_and(|bytes| crate::sync::torrent::is_private_torrent(&bytes));
+        ui_state.private_flag = Some((details.info_hash.clone(), private));
+    }
+    ui_state.torrent_is_private = app_config.force_private || ui_state.private_flag.as_ref().is_some_and(|(_, private)| *private);
+    
+    ui_state.torrent_files = Some(TorrentFileStats {
+        name: details.name,
+        info_hash: Some(details.info_hash),
+        output_folder: Some(details.output_folder),
+        files: std::sync::Arc::new(file_data),
+        files_complete: file_progress.iter().filter(|(_, length, downloaded)| downloaded >= length).count(),
+        files_total: file_progress.len(),
+    });
+}
+
 /// Update the mutable UI state based on the immutable App state
+#[allow(clippy::too_many_arguments)]
 fn update_persistent_ui_state(
     // Pass only the needed immutable fields from App
     app_config: &crate::config::AppConfig,
@@ -26,6 +92,9 @@ fn update_persistent_ui_state(
     missing_files_to_prompt: &Option<std::collections::HashSet<std::path::PathBuf>>,
     extra_files_to_prompt: &Option<Vec<std::path::PathBuf>>,
     remote_update: &Option<Vec<u8>>,
+    folder_owner_prompt: &Option<(crate::sync::owner::FolderOwner, crate::sync::SyncCommand)>,
+    seeding_confirmation: &Option<usize>,
+    notice_prompt: &Option<(usize, String)>,
     managed_torrent_stats: &Option<(usize, std::sync::Arc<librqbit::TorrentStats>)>, 
     api: &librqbit::Api, // Needed for file details call
     ui_state: &mut UiState, // The state to update
@@ -36,11 +105,18 @@ fn update_persistent_ui_state(
     ui_state.config_url = config_edit_url.to_string();
     ui_state.config_path = config_edit_path_str.to_string();
     ui_state.download_path = app_config.download_path.clone();
+    ui_state.torrent_label = app_config.torrent_label.clone();
+    ui_state.safe_mode_active = app_config.safe_mode;
+    ui_state.updates_snoozed = crate::sync::snooze::active_snooze(app_config, chrono::Local::now()).is_some();
+    ui_state.port_check_enabled = app_config.port_check_url.is_some();
+    ui_state.notice_pending = notice_prompt.is_some();
     
-    // Update profile settings
-    ui_state.should_seed = *config_edit_should_seed;
-    ui_state.max_upload_speed_str = config_edit_max_upload_speed_str.to_string();
-    ui_state.max_download_speed_str = config_edit_max_download_speed_str.to_string();
+    // Update profile settings, unless the settings modal is editing them
+    if !matches!(ui_state.modal_state, ModalState::Settings) {
+        ui_state.should_seed = *config_edit_should_seed;
+        ui_state.max_upload_speed_str = config_edit_max_upload_speed_str.to_string();
+        ui_state.max_download_speed_str = config_edit_max_download_speed_str.to_string();
+    }
     ui_state.max_upload_speed = ui_state.parse_speed_limit(&ui_state.max_upload_speed_str);
     ui_state.max_download_speed = ui_state.parse_speed_limit(&ui_state.max_download_speed_str);
     
@@ -48,34 +124,47 @@ fn update_persistent_ui_state(
     ui_state.last_error = last_error.clone();
     ui_state.sync_status = sync_status.clone();
     
-    // Update modal state based on app prompts, but preserve Settings if it's already set
-    // This is to prevent overriding the Settings modal state that was just set by a UI action
-    println!("Modal state before update: {:?}", std::mem::discriminant(&ui_state.modal_state));
+    // Update modal state based on app prompts, but preserve Settings and the setup wizard if set
+    // This is to prevent overriding the modal state that was just set by a UI action
+    info!("Modal state before update: {:?}", std::mem::discriminant(&ui_state.modal_state));
     match ui_state.modal_state {
         ModalState::Settings => {
-            println!("Preserving Settings modal state");
+            info!("Preserving Settings modal state");
             // Don't change the modal state if it's currently set to Settings
             // This preserves the settings modal when the gear icon is clicked
         },
+        ModalState::SetupWizard => {
+            // Prompts wait until the wizard is finished or closed
+        },
         _ => {
             // For any other modal state, update based on app prompts
-            println!("Updating modal state based on app prompts");
-            if let Some(files) = missing_files_to_prompt {
-                println!("Setting modal to MissingFiles");
+            info!("Updating modal state based on app prompts");
+            if let Some((owner, _)) = folder_owner_prompt {
+                info!("Setting modal to FolderOwnerMismatch");
+                ui_state.modal_state = ModalState::FolderOwnerMismatch(owner.torrent_url.clone());
+            } else if let Some((_, notice)) = notice_prompt.as_ref().filter(|_| !ui_state.notice_dismissed) {
+                // Asked before seeding: nothing downloads until it's accepted
+                info!("Setting modal to AcknowledgeNotice");
+                ui_state.modal_state = ModalState::AcknowledgeNotice(notice.clone());
+            } else if seeding_confirmation.is_some() {
+                info!("Setting modal to ConfirmPublicSeeding");
+                ui_state.modal_state = ModalState::ConfirmPublicSeeding;
+            } else if let Some(files) = missing_files_to_prompt {
+                info!("Setting modal to MissingFiles");
                 ui_state.modal_state = ModalState::MissingFiles(files.clone());
             } else if let Some(files) = extra_files_to_prompt {
-                println!("Setting modal to ExtraFiles");
+                info!("Setting modal to ExtraFiles");
                 ui_state.modal_state = ModalState::ExtraFiles(files.clone());
-            } else if remote_update.is_some() { 
-                println!("Setting modal to RemoteUpdateAvailable");
+            } else if remote_update.is_some() && ui_state.update_countdown.is_none() {
+                info!("Setting modal to RemoteUpdateAvailable");
                 ui_state.modal_state = ModalState::RemoteUpdateAvailable;
             } else {
-                println!("Setting modal to None");
+                info!("Setting modal to None");
                 ui_state.modal_state = ModalState::None;
             }
         }
     }
-    println!("Modal state after update: {:?}", std::mem::discriminant(&ui_state.modal_state));
+    info!("Modal state after update: {:?}", std::mem::discriminant(&ui_state.modal_state));
     
     // Update torrent stats if available from app state
     if let Some((id, stats)) = managed_torrent_stats {
@@ -87,21 +176,23 @@ fn update_persistent_ui_state(
             total_bytes: stats.total_bytes,
             progress_bytes: stats.progress_bytes,
             uploaded_bytes: stats.uploaded_bytes,
-            progress: if stats.total_bytes > 0 {
-                stats.progress_bytes as f64 / stats.total_bytes as f64
-            } else {
-                if stats.finished { 1.0 } else { 0.0 }
-            },
+            // Adjusted for the per-torrent reset point once the info hash is known below
+            uploaded_since_reset: stats.uploaded_bytes,
+            downloaded_since_reset: stats.progress_bytes,
+            progress: utils::progress_fraction(stats.progress_bytes, stats.total_bytes, stats.finished),
             download_speed: if let Some(live) = &stats.live {
-                live.download_speed.mbps * 125_000.0
+                utils::speed_bytes_per_sec(live.download_speed.mbps)
             } else {
                 0.0
             },
             upload_speed: if let Some(live) = &stats.live {
-                live.upload_speed.mbps * 125_000.0
+                utils::speed_bytes_per_sec(live.upload_speed.mbps)
             } else {
                 0.0
             },
+            // Filled in below
+            smoothed_download_speed: 0.0,
+            smoothed_upload_speed: 0.0,
             state: match stats.state {
                 librqbit::TorrentStatsState::Initializing => "Checking Files".to_string(),
                 librqbit::TorrentStatsState::Live => {
@@ -123,38 +214,89 @@ fn update_persistent_ui_state(
                 }
             },
             is_finished: stats.finished,
-            time_remaining: if let Some(live) = &stats.live {
-                live.time_remaining.as_ref().map(|t| t.to_string())
-            } else {
-                None
+            // Meaningless until the total size is known
+            time_remaining: match &stats.live {
+                Some(live) if stats.total_bytes > 0 => live.time_remaining.as_ref().map(|t| t.to_string()),
+                _ => None,
             },
+            done_by: None,
+            files_complete: 0,
+            files_total: 0,
         });
         
-        // Try to fetch file details
-        if let Ok(details) = api.api_torrent_details(torrent_id.into()) {
-            let file_data: Vec<(String, u64)> = if let Some(files) = &details.files {
-                files.iter()
-                    .filter(|f| f.included)
-                    .map(|f| (f.name.clone(), f.length))
-                    .collect()
-            } else {
-                Vec::new()
-            };
-            
-            ui_state.torrent_files = Some(TorrentFileStats {
-                name: details.name,
-                info_hash: Some(details.info_hash),
-                output_folder: Some(details.output_folder),
-                files: file_data,
-            });
-        } else {
-            // Clear file details if API call fails
-            ui_state.torrent_files = None; 
+        // Smooth the speed so the finish time doesn't jump around between samples
+        if ui_state.speed_smoother_torrent != Some(torrent_id) {
+            ui_state.speed_smoother.reset();
+            ui_state.display_speed_smoothers.0.reset();
+            ui_state.display_speed_smoothers.1.reset();
+            ui_state.speed_smoother_torrent = Some(torrent_id);
+        }
+        // The headline speeds are calmed separately; Details keeps the raw figures
+        let (down_smoother, up_smoother) = &mut ui_state.display_speed_smoothers;
+        if let Some(torrent_stats) = ui_state.torrent_stats.as_mut() {
+            let now = std::time::Instant::now();
+            down_smoother.set_window(app_config.speed_average_window_secs);
+            up_smoother.set_window(app_config.speed_average_window_secs);
+            torrent_stats.smoothed_download_speed = down_smoother.sample(torrent_stats.download_speed, now);
+            torrent_stats.smoothed_upload_speed = up_smoother.sample(torrent_stats.upload_speed, now);
+        }
+        ui_state.speed_smoother.set_poll_interval(std::time::Duration::from_secs(app_config.stats_poll_interval_secs.max(1)));
+        if let Some(torrent_stats) = ui_state.torrent_stats.as_mut()
+            && !torrent_stats.is_finished
+            && torrent_stats.total_bytes > 0
+        {
+            let speed = ui_state.speed_smoother.sample(torrent_stats.download_speed, std::time::Instant::now());
+            let now = chrono::Local::now();
+            let remaining = torrent_stats.total_bytes.saturating_sub(torrent_stats.progress_bytes);
+            torrent_stats.done_by = Some(eta::format_completion(eta::estimate_completion(remaining, speed, now), now));
+        }
+        
+        // The file list only changes with new stats or paused files, so with tens of
+        // thousands of files rebuild it once per poll rather than every frame
+        let files_stale = !ui_state.files_source.as_ref().is_some_and(|(source, paused)| {
+            std::sync::Arc::ptr_eq(source, stats) && *paused == app_config.paused_files
+        });
+        if files_stale {
+            ui_state.files_source = Some((stats.clone(), app_config.paused_files.clone()));
+            rebuild_torrent_files(app_config, stats, torrent_id, api, ui_state);
+        }
+        
+        if let Some(files) = &ui_state.torrent_files {
+            if let Some(ui_stats) = ui_state.torrent_stats.as_mut() {
+                ui_stats.files_total = files.files_total;
+                ui_stats.files_complete = files.files_complete;
+            }
+            if let (Some(ui_stats), Some(baseline)) = (
+                ui_state.torrent_stats.as_mut(),
+                files.info_hash.as_ref().and_then(|hash| app_config.torrent_stats_baselines.get(hash)),
+            ) {
+                (ui_stats.uploaded_since_reset, ui_stats.downloaded_since_reset) =
+                    baseline.since_reset(stats.uploaded_bytes, stats.progress_bytes);
+            }
         }
     } else {
         ui_state.torrent_stats = None;
         ui_state.torrent_files = None;
+        ui_state.files_source = None;
+        ui_state.category_progress.clear();
+    }
+    
+    // Update the seeding summary from every torrent in the session
+    let mut summary = SeedingSummary::default();
+    let torrent_list = api.api_torrent_list_ext(librqbit::api::ApiTorrentListOpts { with_stats: true });
+    for stats in torrent_list.torrents.iter().filter_map(|t| t.stats.as_ref()) {
+        summary.uploaded_bytes += stats.uploaded_bytes;
+        summary.downloaded_bytes += stats.progress_bytes;
+        if let Some(live) = &stats.live {
+            summary.upload_speed += utils::speed_bytes_per_sec(live.upload_speed.mbps);
+            if stats.finished {
+                summary.active_seeding += 1;
+            }
+        }
     }
+    (summary.uploaded_bytes, summary.downloaded_bytes) =
+        app_config.stats_baseline.since_reset(summary.uploaded_bytes, summary.downloaded_bytes);
+    ui_state.seeding_summary = summary;
     
     // Update last update time
     ui_state.last_update = Some(std::time::Instant::now());
@@ -175,14 +317,37 @@ fn process_ui_action(action: UiAction, app: &mut MyApp) {
                 return;
             }
             
-            let _ = actions::save_config_changes(app);
+            let path = std::path::PathBuf::from(app.config_edit_path_str.trim());
+            let path_check = crate::sync::preflight::check_writable(&path);
+            if !path_check.passed {
+                if crate::sync::permissions::permission_error_in_text(&path_check.detail).is_some() {
+                    app.ui_state.permission_denied_path = Some(path);
+                }
+                app.last_error = Some(format!("Download path is not usable: {}", path_check.detail));
+                return;
+            }
+            
+            if actions::save_config_changes(app).is_ok() {
+                actions::check_url(app);
+            }
         },
         UiAction::UpdateFromRemote => {
             actions::update_from_remote(app);
         },
+        UiAction::TestConfig => {
+            actions::test_config(app);
+        },
+        UiAction::CheckUrl => {
+            actions::check_url(app);
+        },
+        UiAction::CancelSync => {
+            if let Err(e) = app.sync_cmd_tx.send(crate::sync::SyncCommand::CancelSync) {
+                error!("UI: Failed to send cancel request: {}", e);
+            }
+        },
         UiAction::VerifyLocalFiles => {
             if let Err(e) = app.sync_cmd_tx.send(crate::sync::SyncCommand::VerifyFolder) {
-                eprintln!("UI: Failed to send folder verify request: {}", e);
+                error!("UI: Failed to send folder verify request: {}", e);
             }
         },
         UiAction::OpenDownloadFolder => {
@@ -190,7 +355,7 @@ fn process_ui_action(action: UiAction, app: &mut MyApp) {
         },
         UiAction::FixMissingFiles => {
             if let Err(e) = app.sync_cmd_tx.send(crate::sync::SyncCommand::FixMissingFiles) {
-                eprintln!("Action: Failed to send FixMissingFiles command: {}", e);
+                error!("Action: Failed to send FixMissingFiles command: {}", e);
                 let _ = app.ui_tx.send(crate::sync::SyncEvent::Error(format!("Failed to send fix command: {}", e)));
             }
             app.missing_files_to_prompt = None;
@@ -199,10 +364,132 @@ fn process_ui_action(action: UiAction, app: &mut MyApp) {
             actions::delete_extra_files(app);
         },
         UiAction::ApplyRemoteUpdate => {
+            app.ui_state.remote_update_summary = None;
+            app.ui_state.changelog = None;
             actions::apply_remote_update(app);
         },
+        UiAction::RetryAfterFailures => {
+            if let Err(e) = app.sync_cmd_tx.send(crate::sync::SyncCommand::RetryAfterFailures) {
+                error!("Action: Failed to send RetryAfterFailures command: {}", e);
+            }
+        },
+        UiAction::SnoozeUpdates(choice) => {
+            actions::snooze_updates(app, choice);
+        },
+        UiAction::CancelScheduledUpdate => {
+            if let Err(e) = app.sync_cmd_tx.send(crate::sync::SyncCommand::CancelScheduledUpdate) {
+                error!("Action: Failed to send CancelScheduledUpdate command: {}", e);
+            }
+        },
+        UiAction::ResumeDownloads => {
+            if let Err(e) = app.sync_cmd_tx.send(crate::sync::SyncCommand::ResumeDownloads) {
+                error!("Action: Failed to send ResumeDownloads command: {}", e);
+            }
+        },
+        UiAction::ConfirmPublicSeeding => {
+            actions::confirm_public_seeding(app);
+        },
+        UiAction::DeclinePublicSeeding => {
+            actions::decline_public_seeding(app);
+        },
+        UiAction::AcknowledgeNotice => {
+            actions::acknowledge_notice(app);
+        },
+        UiAction::DismissNotice => {
+            info!("Action: Notice put off, the torrent stays paused");
+            app.ui_state.notice_dismissed = true;
+        },
+        UiAction::ShowNotice => {
+            app.ui_state.notice_dismissed = false;
+        },
+        UiAction::ClaimFolderAndContinue => {
+            actions::claim_folder_and_continue(app);
+        },
+        UiAction::ResetTorrentStats => {
+            actions::reset_torrent_stats(app);
+        },
+        UiAction::ResetGlobalStats => {
+            actions::reset_global_stats(app);
+        },
+        UiAction::SetTorrentLabel(label) => {
+            actions::set_torrent_label(app, label);
+        },
+        UiAction::ExportFileList => {
+            actions::export_file_list(app);
+        },
+        UiAction::SaveDiagnosticsBundle => {
+            actions::save_diagnostics_bundle(app);
+        },
+        UiAction::VerifySubset(files) => {
+            if let Err(e) = app.sync_cmd_tx.send(crate::sync::SyncCommand::VerifySubset(files)) {
+                error!("UI: Failed to send spot check request: {}", e);
+                return;
+            }
+            app.ui_state.subset_verify_running = true;
+            app.ui_state.subset_verify = None;
+        },
+        UiAction::ReloadConfig => {
+            if let Err(e) = app.sync_cmd_tx.send(crate::sync::SyncCommand::ReloadConfig) {
+                error!("UI: Failed to send config reload request: {}", e);
+            }
+        },
+        UiAction::PrioritizeFile(path) => {
+            if let Err(e) = app.sync_cmd_tx.send(crate::sync::SyncCommand::PrioritizeFile(path)) {
+                error!("UI: Failed to send prioritise request: {}", e);
+            }
+        },
+        UiAction::VerifyAgainstTorrent { torrent, folder } => {
+            let folder = folder.unwrap_or_else(|| app.config.download_path.clone());
+            if let Err(e) = app.sync_cmd_tx.send(crate::sync::SyncCommand::VerifyAgainstTorrent { torrent, folder }) {
+                error!("UI: Failed to send offline verify request: {}", e);
+                return;
+            }
+            app.ui_state.offline_verify_running = true;
+            app.ui_state.offline_verify = None;
+        },
+        UiAction::TestTrackers => {
+            if let Err(e) = app.sync_cmd_tx.send(crate::sync::SyncCommand::TestTrackers) {
+                error!("UI: Failed to send tracker test request: {}", e);
+                return;
+            }
+            app.ui_state.tracker_test_running = true;
+            app.ui_state.tracker_test = None;
+        },
+        UiAction::CheckPort => {
+            if let Err(e) = app.sync_cmd_tx.send(crate::sync::SyncCommand::CheckPort) {
+                error!("UI: Failed to send port check request: {}", e);
+                return;
+            }
+            app.ui_state.port_check_running = true;
+            app.ui_state.port_check = None;
+        },
+        UiAction::SetExpandedFolders(folders) => {
+            actions::set_expanded_folders(app, folders);
+        },
+        UiAction::SetFilePaused { path, paused } => {
+            actions::set_file_paused(app, path, paused);
+        },
         UiAction::ShowSettingsModal => {
-            println!("Setting modal state to show settings");
+            info!("Setting modal state to show settings");
+            // Start editing from the saved connection settings
+            app.ui_state.connection = app.config.connection;
+            app.ui_state.ip_mode = app.config.ip_mode;
+            app.ui_state.encryption_mode = app.config.encryption_mode;
+            app.ui_state.force_private = app.config.force_private;
+            app.ui_state.pause_process_str = app.config.pause_while_process_running.clone().unwrap_or_default();
+            app.ui_state.bind_interface_str = app.config.bind_interface.clone().unwrap_or_default();
+            app.ui_state.tracker_passkey_str = app.config.tracker_passkey.clone().unwrap_or_default();
+            app.ui_state.show_passkey = false;
+            app.ui_state.language = app.config.language.clone();
+            app.ui_state.confirm_before_update = app.config.confirm_before_update;
+            app.ui_state.prevent_sleep_during_sync = app.config.prevent_sleep_during_sync;
+            app.ui_state.safe_mode = app.config.safe_mode;
+            app.ui_state.allow_download = app.config.allow_download;
+            app.ui_state.allow_upload = app.config.allow_upload;
+            app.ui_state.ignore_patterns = app.config.ignore_patterns.clone();
+            app.ui_state.ignore_new_pattern.clear();
+            app.ui_state.ignore_preview_files = crate::sync::ignore::list_local_files(&app.config.download_path);
+            app.ui_state.ignore_previews.clear();
             // Set the modal state to show the settings modal
             app.ui_state.modal_state = crate::ui::state::ModalState::Settings;
         },
@@ -217,15 +504,45 @@ fn process_ui_action(action: UiAction, app: &mut MyApp) {
             app.config.should_seed = app.ui_state.should_seed;
             app.config.max_upload_speed = app.ui_state.max_upload_speed;
             app.config.max_download_speed = app.ui_state.max_download_speed;
+            app.config.connection = app.ui_state.connection;
+            app.config.ip_mode = app.ui_state.ip_mode;
+            app.config.encryption_mode = app.ui_state.encryption_mode;
+            app.config.force_private = app.ui_state.force_private;
+            app.config.confirm_before_update = app.ui_state.confirm_before_update;
+            app.config.prevent_sleep_during_sync = app.ui_state.prevent_sleep_during_sync;
+            app.config.safe_mode = app.ui_state.safe_mode;
+            app.config.allow_download = app.ui_state.allow_download;
+            app.config.allow_upload = app.ui_state.allow_upload;
+            app.config.ignore_patterns = app.ui_state.ignore_patterns.iter()
+                .map(|pattern| pattern.trim().to_string())
+                .filter(|pattern| match crate::sync::ignore::validate_pattern(pattern) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        info!("Dropping invalid ignore pattern {:?}: {}", pattern, e);
+                        false
+                    }
+                })
+                .collect();
+            let pause_process = app.ui_state.pause_process_str.trim();
+            app.config.pause_while_process_running = (!pause_process.is_empty()).then(|| pause_process.to_string());
+            let bind_interface = app.ui_state.bind_interface_str.trim();
+            app.config.bind_interface = (!bind_interface.is_empty()).then(|| bind_interface.to_string());
+            let passkey = app.ui_state.tracker_passkey_str.trim();
+            app.config.tracker_passkey = (!passkey.is_empty()).then(|| passkey.to_string());
+            if app.config.language != app.ui_state.language {
+                app.config.language = app.ui_state.language.clone();
+                crate::i18n::set_language(app.config.language.as_deref());
+            }
             
             // Save the settings
             match actions::save_config_changes(app) {
-                Ok(_) => println!("Settings saved successfully"),
-                Err(e) => eprintln!("Failed to save settings: {}", e),
+                Ok(_) => info!("Settings saved successfully"),
+                Err(e) => error!("Failed to save settings: {}", e),
             }
             
             // Close the modal
             app.ui_state.modal_state = crate::ui::state::ModalState::None;
+            app.ui_state.ignore_preview_files.clear();
         },
         // UiAction::SetTorrentTab(_) => { /* No-op */ }
         UiAction::DismissMissingFilesModal => {
@@ -236,20 +553,69 @@ fn process_ui_action(action: UiAction, app: &mut MyApp) {
         },
         UiAction::DismissRemoteUpdateModal => {
             app.remote_update = None;
+            app.ui_state.remote_update_summary = None;
+            app.ui_state.changelog = None;
             // Reset status to idle if we were showing RemoteChanged
             if app.sync_status == SyncStatus::RemoteChanged {
                 app.sync_status = SyncStatus::Idle;
             }
         },
+        UiAction::DismissFolderOwnerModal => {
+            app.folder_owner_prompt = None;
+            app.last_error = Some("Sync cancelled: download folder belongs to another torrent".to_string());
+        },
+        UiAction::RetrySession => {
+            actions::retry_session(app);
+        },
+        UiAction::ClearSessionAndRetry => {
+            actions::clear_session_and_retry(app);
+        },
+        UiAction::UseListenPortAndRetry => {
+            actions::use_listen_port_and_retry(app);
+        },
+        UiAction::ShowSetupWizard => {
+            actions::start_setup_wizard(app);
+        },
+        UiAction::ImportClientSettings => {
+            actions::import_client_settings(app);
+        },
+        UiAction::TestSetupWizard => {
+            actions::test_setup_wizard(app);
+        },
+        UiAction::FinishSetupWizard => {
+            actions::finish_setup_wizard(app);
+        },
+        UiAction::DismissSetupWizard => {
+            app.ui_state.modal_state = crate::ui::state::ModalState::None;
+        },
         UiAction::DismissSettingsModal => {
             // Close the settings modal without saving
             app.ui_state.modal_state = crate::ui::state::ModalState::None;
+            app.ui_state.ignore_preview_files.clear();
         },
         UiAction::None => {},
     }
 }
 
 // Main function to draw the UI - Now takes &mut UiState from MyApp
+// The newest log lines, as they'd appear on the console
+fn draw_log_viewer(ui: &mut egui::Ui) {
+    let lines = crate::logging::recent_lines();
+    if lines.is_empty() {
+        ui.label("Nothing logged yet");
+        return;
+    }
+    egui::ScrollArea::vertical()
+        .id_salt("log_viewer")
+        .max_height(160.0)
+        .stick_to_bottom(true)
+        .show(ui, |ui| {
+            for line in &lines {
+                ui.label(egui::RichText::new(line).monospace().small());
+            }
+        });
+}
+
 pub fn draw_ui(app: &mut MyApp, ctx: &egui::Context) {
     // Update the persistent ui_state based on app state
     // Pass only the necessary immutable fields from app
@@ -265,6 +631,9 @@ pub fn draw_ui(app: &mut MyApp, ctx: &egui::Context) {
         &app.missing_files_to_prompt,
         &app.extra_files_to_prompt,
         &app.remote_update,
+        &app.folder_owner_prompt,
+        &app.seeding_confirmation,
+        &app.notice_prompt,
         &app.managed_torrent_stats,
         &app.api,
         &mut app.ui_state // Pass mutable ui_state
@@ -284,21 +653,34 @@ pub fn draw_ui(app: &mut MyApp, ctx: &egui::Context) {
                 // Use a larger font for the gear icon
                 let gear_icon = egui::RichText::new("⚙").size(24.0);
                 if ui.button(gear_icon).on_hover_text("Settings").clicked() {
-                    println!("Settings gear icon clicked! Setting action to ShowSettingsModal");
+                    info!("Settings gear icon clicked! Setting action to ShowSettingsModal");
                     ui_action = UiAction::ShowSettingsModal;
                 }
             });
         });
     });
     
+    // Recent events along the bottom, below the tabs that fill the central panel
+    egui::TopBottomPanel::bottom("activity_panel").show(ctx, |ui| {
+        egui::CollapsingHeader::new("Recent activity")
+            .default_open(true)
+            .show(ui, |ui| app.ui_state.activity.ui(ui));
+        egui::CollapsingHeader::new("Log")
+            .default_open(false)
+            .show(ui, draw_log_viewer);
+    });
+    
     // Draw the main UI using components, passing mutable ui_state
     CentralPanel::default().show(ctx, |ui| {
         // Use the ConfigPanel component - Use full path
-        if let Some(action) = config_panel::ConfigPanel::draw(ui, &mut app.ui_state) {
-            if matches!(ui_action, UiAction::None) {
-                ui_action = action;
-            }
+        if let Some(action) = config_panel::ConfigPanel::draw(ui, &mut app.ui_state)
+            && matches!(ui_action, UiAction::None)
+        {
+            ui_action = action;
         }
+        // Keep what was typed; the next frame's state update reads these back
+        app.config_edit_url = app.ui_state.config_url.clone();
+        app.config_edit_path_str = app.ui_state.config_path.clone();
         
         // Use the TorrentDisplay component - Use full path
         if let Some(action) = torrent_display::TorrentDisplay::draw(ui, &mut app.ui_state) {
@@ -311,15 +693,13 @@ pub fn draw_ui(app: &mut MyApp, ctx: &egui::Context) {
     
     // Process any action from the main UI components first
     if !matches!(ui_action, UiAction::None) {
-        println!("Processing UI action before modals: {:?}", ui_action);
+        info!("Processing UI action before modals: {:?}", ui_action);
         process_ui_action(ui_action, app);
-        // Reset action after processing
-        ui_action = UiAction::None;
     }
     
     // Draw modal dialogs if any - Use full path
     if let Some(action) = modals::draw_modals(ctx, &mut app.ui_state) {
-        println!("Got action from modal: {:?}", action);
+        info!("Got action from modal: {:?}", action);
         // Process modal action separately
         process_ui_action(action, app);
     }
@@ -2,31 +2,58 @@
 // Module for handling modal dialogs
 
 use eframe::egui::{self, Context, Window, RichText};
+use tracing::info;
 use crate::ui::state::{UiState, UiAction, ModalState};
+use crate::config::{ConnectionPreset, ConnectionSettings, EncryptionMode, IpMode};
+use crate::i18n::{tr, tr_fmt};
+use crate::sync::ignore::{preview_patterns, validate_pattern};
+use crate::sync::summary::UpdateSummary;
+use crate::ui::markdown::draw_markdown;
+use crate::ui::utils::{format_size, format_speed, limit_to_slider, slider_to_limit};
 
 /// Draw modal dialogs based on the current UI state
 pub fn draw_modals(ctx: &Context, ui_state: &mut UiState) -> Option<UiAction> {
-    println!("Drawing modals, current modal state: {:?}", std::mem::discriminant(&ui_state.modal_state));
+    info!("Drawing modals, current modal state: {:?}", std::mem::discriminant(&ui_state.modal_state));
     
     match &ui_state.modal_state {
         ModalState::MissingFiles(files) => {
-            println!("Drawing missing files modal");
+            info!("Drawing missing files modal");
             let files_copy = files.clone();
             draw_missing_files_modal(ctx, &files_copy)
         },
         ModalState::ExtraFiles(files) => {
-            println!("Drawing extra files modal");
+            info!("Drawing extra files modal");
             let files_copy = files.clone();
             draw_extra_files_modal(ctx, &files_copy)
         },
         ModalState::RemoteUpdateAvailable => {
-            println!("Drawing remote update modal");
-            draw_remote_update_modal(ctx)
+            info!("Drawing remote update modal");
+            let summary = ui_state.remote_update_summary.clone();
+            let changelog = ui_state.changelog.clone();
+            draw_remote_update_modal(ctx, summary.as_ref(), changelog.as_deref())
+        },
+        ModalState::FolderOwnerMismatch(owner_url) => {
+            info!("Drawing folder owner modal");
+            let owner_copy = owner_url.clone();
+            draw_folder_owner_modal(ctx, &owner_copy)
+        },
+        ModalState::ConfirmPublicSeeding => {
+            info!("Drawing public seeding confirmation modal");
+            draw_public_seeding_modal(ctx, ui_state)
+        },
+        ModalState::AcknowledgeNotice(notice) => {
+            info!("Drawing notice modal");
+            let notice_copy = notice.clone();
+            draw_notice_modal(ctx, &notice_copy)
         },
         ModalState::Settings => {
-            println!("Drawing settings modal");
+            info!("Drawing settings modal");
             draw_settings_modal(ctx, ui_state)
         },
+        ModalState::SetupWizard => {
+            info!("Drawing setup wizard");
+            crate::ui::setup_wizard::draw_setup_wizard(ctx, ui_state)
+        },
         ModalState::None => {
             None
         },
@@ -38,7 +65,7 @@ fn draw_settings_modal(ctx: &Context, ui_state: &mut UiState) -> Option<UiAction
     let mut action = None;
     let mut open = true;
     
-    Window::new("Settings")
+    Window::new(tr("settings.title"))
         .id(egui::Id::new("settings_modal"))
         .collapsible(false)
         .resizable(false)
@@ -46,58 +73,155 @@ fn draw_settings_modal(ctx: &Context, ui_state: &mut UiState) -> Option<UiAction
         .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
         .open(&mut open)
         .show(ctx, |ui| {
-            println!("Inside settings modal render callback");
-            ui.heading("Profile Settings");
+            info!("Inside settings modal render callback");
+            ui.heading(tr("settings.heading"));
             ui.add_space(4.0);
-            ui.label("Configure your download and sharing preferences.");
+            ui.label(tr("settings.intro"));
             ui.separator();
             ui.add_space(8.0);
             
             // Create a frame for the settings
-            egui::Frame::none()
+            egui::Frame::NONE
                 .inner_margin(10.0)
                 .fill(ui.style().visuals.extreme_bg_color)
-                .rounding(4.0)
+                .corner_radius(4.0)
                 .show(ui, |ui| {
                     // Should seed checkbox
                     ui.horizontal(|ui| {
-                        ui.label("Contribute to seeding:");
+                        ui.label(tr("settings.seeding"));
                         ui.checkbox(&mut ui_state.should_seed, "");
-                        ui.label(RichText::new("Share with others after downloading").weak());
+                        ui.label(RichText::new(tr("settings.seeding_hint")).weak());
                     });
                     
                     ui.add_space(8.0);
                     
-                    // Upload/download section
+                    // Upload/download section: a log-scale slider plus the exact value
+                    let (upload_now, download_now) = ui_state.torrent_stats.as_ref()
+                        .map_or((0.0, 0.0), |s| (s.upload_speed, s.download_speed));
+                    draw_speed_limit(ui, tr("settings.max_upload"), &mut ui_state.max_upload_speed_str, upload_now);
+                    draw_speed_limit(ui, tr("settings.max_download"), &mut ui_state.max_download_speed_str, download_now);
+                    ui.label(RichText::new(tr("settings.limits_hint")).weak());
+                    
+                    // Per-direction holds; unlike seeding off, the torrent stays active
                     ui.horizontal(|ui| {
-                        ui.vertical(|ui| {
-                            // Max upload speed input (KB/s)
-                            ui.horizontal(|ui| {
-                                ui.label("Max Upload Speed (KB/s):");
-                                ui.add_enabled(
-                                    ui_state.should_seed, 
-                                    egui::TextEdit::singleline(&mut ui_state.max_upload_speed_str)
-                                        .desired_width(80.0)
-                                ).on_hover_text("Leave empty for unlimited");
-                            });
-                            
-                            // Max download speed input (KB/s)
-                            ui.horizontal(|ui| {
-                                ui.label("Max Download Speed (KB/s):");
-                                ui.add(
-                                    egui::TextEdit::singleline(&mut ui_state.max_download_speed_str)
-                                        .desired_width(80.0)
-                                ).on_hover_text("Leave empty for unlimited");
+                        ui.checkbox(&mut ui_state.allow_download, tr("settings.allow_download"));
+                        ui.checkbox(&mut ui_state.allow_upload, tr("settings.allow_upload"))
+                            .on_hover_text(tr("settings.allow_upload_hint"));
+                    });
+                    ui.label(RichText::new(tr("settings.directions_hint")).weak());
+                    
+                    ui.add_space(8.0);
+                    
+                    // Process that pauses all transfers while it runs
+                    ui.horizontal(|ui| {
+                        ui.label(tr("settings.pause_process"));
+                        ui.add(
+                            egui::TextEdit::singleline(&mut ui_state.pause_process_str)
+                                .hint_text(tr("settings.pause_process_example"))
+                                .desired_width(160.0)
+                        ).on_hover_text(tr("settings.pause_process_hint"));
+                    });
+                    
+                    // Kill switch for a VPN or other required interface
+                    ui.horizontal(|ui| {
+                        ui.label(tr("settings.bind_interface"));
+                        ui.add(
+                            egui::TextEdit::singleline(&mut ui_state.bind_interface_str)
+                                .hint_text(tr("settings.bind_interface_example"))
+                                .desired_width(160.0)
+                        ).on_hover_text(tr("settings.bind_interface_hint"));
+                    });
+                    
+                    // Private tracker passkey, masked unless revealed
+                    ui.horizontal(|ui| {
+                        ui.label(tr("settings.passkey"));
+                        ui.add(
+                            egui::TextEdit::singleline(&mut ui_state.tracker_passkey_str)
+                                .password(!ui_state.show_passkey)
+                                .desired_width(160.0)
+                        ).on_hover_text(tr("settings.passkey_hint"));
+                        ui.checkbox(&mut ui_state.show_passkey, tr("settings.show"));
+                    });
+                    
+                    ui.add_space(8.0);
+                    
+                    ui.horizontal(|ui| {
+                        ui.label(tr("settings.confirm_update"));
+                        ui.checkbox(&mut ui_state.confirm_before_update, "");
+                        ui.label(RichText::new(tr("settings.confirm_update_hint")).weak());
+                    });
+                    
+                    ui.horizontal(|ui| {
+                        ui.label(tr("settings.keep_awake"));
+                        ui.checkbox(&mut ui_state.prevent_sleep_during_sync, "");
+                        ui.label(RichText::new(tr("settings.keep_awake_hint")).weak());
+                    });
+                    
+                    ui.horizontal(|ui| {
+                        ui.label(tr("settings.safe_mode"));
+                        ui.checkbox(&mut ui_state.safe_mode, "");
+                        ui.label(RichText::new(tr("settings.safe_mode_hint")).weak());
+                    });
+                    
+                    ui.add_space(8.0);
+                    
+                    // UI language, applied when the settings are saved
+                    ui.horizontal(|ui| {
+                        ui.label(tr("settings.language"));
+                        let selected_text = ui_state.language.as_deref()
+                            .and_then(|code| crate::i18n::LANGUAGES.iter().find(|(c, _)| *c == code))
+                            .map_or(tr("settings.language_system"), |(_, name)| *name);
+                        egui::ComboBox::from_id_salt("settings_language")
+                            .selected_text(selected_text)
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut ui_state.language, None, tr("settings.language_system"));
+                                for (code, name) in crate::i18n::LANGUAGES {
+                                    ui.selectable_value(&mut ui_state.language, Some(code.to_string()), *name);
+                                }
                             });
-                        });
-                        
-                        ui.vertical(|ui| {
-                            ui.label(RichText::new("Empty = Unlimited").weak());
-                            ui.label(RichText::new("1000 KB/s = 1 MB/s").weak());
-                        });
                     });
                 });
             
+            ui.add_space(8.0);
+            egui::CollapsingHeader::new(tr("settings.ignore_patterns"))
+                .id_salt("settings_ignore_patterns")
+                .show(ui, |ui| {
+                    draw_ignore_patterns(ui, ui_state);
+                });
+            
+            egui::CollapsingHeader::new(tr("settings.advanced"))
+                .id_salt("settings_advanced")
+                .show(ui, |ui| {
+                    draw_connection_settings(ui, &mut ui_state.connection);
+                    ui.add_space(4.0);
+                    draw_ip_mode(ui, &mut ui_state.ip_mode);
+                    ui.add_space(4.0);
+                    draw_encryption_mode(ui, &mut ui_state.encryption_mode, ui_state.active_encryption_mode);
+                    ui.add_space(4.0);
+                    ui.checkbox(&mut ui_state.force_private, tr("settings.force_private"))
+                        .on_hover_text(tr("settings.force_private_hint"));
+                });
+            
+            egui::CollapsingHeader::new(tr("settings.troubleshooting"))
+                .id_salt("settings_troubleshooting")
+                .show(ui, |ui| {
+                    ui.label(RichText::new(tr("settings.bundle_hint")).weak());
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut ui_state.bundle_path_str)
+                                .hint_text(tr("settings.bundle_path_example"))
+                                .desired_width(220.0)
+                        );
+                        let has_path = !ui_state.bundle_path_str.trim().is_empty();
+                        if ui.add_enabled(has_path, egui::Button::new(tr("settings.save_bundle"))).clicked() {
+                            action = Some(UiAction::SaveDiagnosticsBundle);
+                        }
+                    });
+                    if let Some(result) = &ui_state.bundle_result {
+                        ui.label(RichText::new(result).small().weak());
+                    }
+                });
+            
             // Update the Option<u64> values based on the string inputs
             ui_state.max_upload_speed = ui_state.parse_speed_limit(&ui_state.max_upload_speed_str);
             ui_state.max_download_speed = ui_state.parse_speed_limit(&ui_state.max_download_speed_str);
@@ -108,12 +232,12 @@ fn draw_settings_modal(ctx: &Context, ui_state: &mut UiState) -> Option<UiAction
             // Buttons at the bottom
             ui.add_space(4.0);
             ui.with_layout(egui::Layout::right_to_left(egui::Align::RIGHT), |ui| {
-                if ui.button("Save").clicked() {
-                    println!("Settings Save button clicked");
+                if ui.button(tr("settings.save")).clicked() {
+                    info!("Settings Save button clicked");
                     action = Some(UiAction::SaveSettingsAndDismiss);
                 }
-                if ui.button("Cancel").clicked() {
-                    println!("Settings Cancel button clicked");
+                if ui.button(tr("settings.cancel")).clicked() {
+                    info!("Settings Cancel button clicked");
                     action = Some(UiAction::DismissSettingsModal);
                 }
             });
@@ -122,6 +246,193 @@ fn draw_settings_modal(ctx: &Context, ui_state: &mut UiState) -> Option<UiAction
     action
 }
 
+/// Edit the ignore patterns, showing what each one matches among the local files
+/// listed when the settings were opened
+fn draw_ignore_patterns(ui: &mut egui::Ui, ui_state: &mut UiState) {
+    if !ui_state.ignore_previews.iter().map(|p| &p.pattern).eq(ui_state.ignore_patterns.iter()) {
+        ui_state.ignore_previews = preview_patterns(&ui_state.ignore_patterns, &ui_state.ignore_preview_files);
+    }
+    let total_files = ui_state.ignore_preview_files.len();
+    let error_color = ui.visuals().error_fg_color;
+    let warn_color = ui.visuals().warn_fg_color;
+    
+    ui.label(RichText::new(tr("settings.ignore_hint")).weak());
+    ui.add_space(4.0);
+    
+    let mut remove = None;
+    egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+        for index in 0..ui_state.ignore_patterns.len() {
+            let preview = ui_state.ignore_previews[index].clone();
+            ui.horizontal(|ui| {
+                let mut edit = egui::TextEdit::singleline(&mut ui_state.ignore_patterns[index])
+                    .font(egui::TextStyle::Monospace)
+                    .desired_width(200.0);
+                if preview.error.is_some() {
+                    edit = edit.text_color(error_color);
+                }
+                ui.add(edit);
+                match &preview.error {
+                    Some(error) => {
+                        ui.label(RichText::new(error).color(error_color));
+                    }
+                    None if total_files > 0 && preview.match_count == total_files => {
+                        ui.label(RichText::new(tr_fmt("settings.ignore_all", &[("count", &total_files)])).color(warn_color))
+                            .on_hover_text(tr("settings.ignore_all_hint"));
+                    }
+                    None => {
+                        ui.label(RichText::new(tr_fmt("settings.ignore_matches", &[("matched", &preview.match_count), ("total", &total_files)])).weak());
+                    }
+                }
+                if ui.small_button("✖").on_hover_text(tr("settings.ignore_remove")).clicked() {
+                    remove = Some(index);
+                }
+            });
+            for path in &preview.sample {
+                ui.label(RichText::new(format!("    {}", path)).small().weak());
+            }
+            if preview.match_count > preview.sample.len() {
+                ui.label(RichText::new(format!("    {}", tr_fmt("settings.ignore_more", &[("count", &(preview.match_count - preview.sample.len()))]))).small().weak());
+            }
+        }
+    });
+    if let Some(index) = remove {
+        ui_state.ignore_patterns.remove(index);
+    }
+    
+    // New pattern, validated and previewed as it's typed
+    let new_pattern = ui_state.ignore_new_pattern.trim().to_string();
+    let validation = validate_pattern(&new_pattern);
+    let duplicate = ui_state.ignore_patterns.iter().any(|p| p.trim() == new_pattern);
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut ui_state.ignore_new_pattern)
+                .hint_text(tr("settings.ignore_example"))
+                .font(egui::TextStyle::Monospace)
+                .desired_width(200.0)
+        );
+        if ui.add_enabled(validation.is_ok() && !duplicate, egui::Button::new(tr("settings.ignore_add"))).clicked() {
+            ui_state.ignore_patterns.push(new_pattern.clone());
+            ui_state.ignore_new_pattern.clear();
+        }
+    });
+    if !new_pattern.is_empty() {
+        match validation {
+            Err(error) => {
+                ui.label(RichText::new(error).color(error_color));
+            }
+            Ok(()) if duplicate => {
+                ui.label(RichText::new(tr("settings.ignore_duplicate")).weak());
+            }
+            Ok(()) => {
+                let count = preview_patterns(std::slice::from_ref(&new_pattern), &ui_state.ignore_preview_files)[0].match_count;
+                ui.label(RichText::new(tr_fmt("settings.ignore_would_match", &[("matched", &count), ("total", &total_files)])).weak());
+            }
+        }
+    }
+}
+
+/// Draw the connection preset picker and the individual settings it fills in
+fn draw_connection_settings(ui: &mut egui::Ui, connection: &mut ConnectionSettings) {
+    let current = ConnectionPreset::detect(connection);
+    ui.horizontal(|ui| {
+        ui.label(tr("settings.preset"));
+        egui::ComboBox::from_id_salt("connection_preset")
+            .selected_text(current.label())
+            .show_ui(ui, |ui| {
+                for preset in ConnectionPreset::SELECTABLE {
+                    let response = ui.selectable_label(current == preset, preset.label())
+                        .on_hover_text(preset.description());
+                    if response.clicked() && let Some(settings) = preset.settings() {
+                        *connection = settings;
+                    }
+                }
+                // Custom can't be picked; it's shown once a field is changed by hand
+                ui.add_enabled(false, egui::SelectableLabel::new(current == ConnectionPreset::Custom, ConnectionPreset::Custom.label()));
+            });
+    });
+    ui.label(RichText::new(current.description()).weak());
+    ui.add_space(4.0);
+    
+    ui.checkbox(&mut connection.enable_dht, tr("settings.dht"));
+    ui.checkbox(&mut connection.accept_incoming, tr("settings.accept_incoming"));
+    ui.add_enabled(
+        connection.accept_incoming,
+        egui::Checkbox::new(&mut connection.enable_upnp, tr("settings.upnp")),
+    );
+    draw_timeout_setting(ui, tr("settings.connect_timeout"), &mut connection.peer_connect_timeout_secs);
+    draw_timeout_setting(ui, tr("settings.rw_timeout"), &mut connection.peer_read_write_timeout_secs);
+    
+    ui.label(RichText::new(tr("settings.connection_restart")).weak());
+}
+
+/// Speed limit as a text field and a log-scale slider kept in step, with the effective
+/// limit and the current speed next to it
+fn draw_speed_limit(ui: &mut egui::Ui, label: &str, value: &mut String, current_speed: f64) {
+    let limit = value.trim().parse::<u64>().ok().filter(|kbps| *kbps > 0);
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(egui::TextEdit::singleline(value).desired_width(80.0))
+            .on_hover_text(tr("limit.empty_hint"));
+        let mut position = limit_to_slider(limit);
+        let slider = egui::Slider::new(&mut position, 0.0..=1.0).show_value(false);
+        if ui.add(slider).changed() {
+            *value = slider_to_limit(position).map_or(String::new(), |kbps| kbps.to_string());
+        }
+        let effective = limit.map_or(tr("limit.unlimited").to_string(), |kbps| format_speed(kbps as f64 * 1024.0));
+        ui.label(RichText::new(effective).strong());
+        ui.label(RichText::new(tr_fmt("settings.speed_now", &[("speed", &format_speed(current_speed))])).weak());
+    });
+}
+
+/// Pick the IP stacks to use and show which ones are active with that choice
+fn draw_ip_mode(ui: &mut egui::Ui, ip_mode: &mut IpMode) {
+    ui.horizontal(|ui| {
+        ui.label(tr("settings.ip_version"));
+        egui::ComboBox::from_id_salt("ip_mode")
+            .selected_text(ip_mode.label())
+            .show_ui(ui, |ui| {
+                for mode in IpMode::ALL {
+                    ui.selectable_value(ip_mode, mode, mode.label());
+                }
+            });
+    });
+    ui.label(RichText::new(tr_fmt("settings.ip_stacks", &[("http", &ip_mode.http_stacks()), ("peers", &ip_mode.peer_stacks())])).weak());
+    if *ip_mode == IpMode::Ipv6 {
+        ui.label(RichText::new(tr("settings.ipv6_warning")).color(ui.visuals().warn_fg_color));
+    }
+}
+
+/// Pick the protocol encryption mode and show what peer connections are with it
+fn draw_encryption_mode(ui: &mut egui::Ui, encryption_mode: &mut EncryptionMode, active: EncryptionMode) {
+    ui.horizontal(|ui| {
+        ui.label(tr("settings.encryption"));
+        egui::ComboBox::from_id_salt("encryption_mode")
+            .selected_text(encryption_mode.label())
+            .show_ui(ui, |ui| {
+                for mode in EncryptionMode::ALL {
+                    ui.selectable_value(encryption_mode, mode, mode.label());
+                }
+            });
+    })
+    .response
+    .on_hover_text(tr("settings.restart_hint"));
+    ui.label(RichText::new(tr_fmt("settings.encryption_active", &[("mode", &active.label()), ("peers", &active.peer_connections())])).weak());
+    if *encryption_mode != EncryptionMode::Disabled {
+        ui.label(RichText::new(tr_fmt("settings.encryption_warning", &[("mode", &encryption_mode.label()), ("peers", &encryption_mode.peer_connections())])).color(ui.visuals().warn_fg_color));
+    }
+}
+
+/// Optional timeout in seconds, where 0 means the librqbit default
+fn draw_timeout_setting(ui: &mut egui::Ui, label: &str, value: &mut Option<u64>) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        let mut secs = value.unwrap_or(0);
+        ui.add(egui::DragValue::new(&mut secs).range(0..=300))
+            .on_hover_text(tr("settings.timeout_default"));
+        *value = (secs > 0).then_some(secs);
+    });
+}
+
 /// Draw the missing files modal dialog
 fn draw_missing_files_modal(ctx: &Context, missing_files: &std::collections::HashSet<std::path::PathBuf>) -> Option<UiAction> {
     let mut action = None;
@@ -191,7 +502,7 @@ fn draw_extra_files_modal(ctx: &Context, extra_files: &[std::path::PathBuf]) ->
 }
 
 /// Draw the remote update modal dialog
-fn draw_remote_update_modal(ctx: &Context) -> Option<UiAction> {
+fn draw_remote_update_modal(ctx: &Context, summary: Option<&UpdateSummary>, changelog: Option<&str>) -> Option<UiAction> {
     let mut action = None;
     
     Window::new("Remote Update Available")
@@ -201,6 +512,25 @@ fn draw_remote_update_modal(ctx: &Context) -> Option<UiAction> {
         .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
         .show(ctx, |ui| {
             ui.label("A new version of the remote torrent is available.");
+            // Only present when confirm_before_update is on
+            if let Some(summary) = summary {
+                ui.label(RichText::new(format!(
+                    "New version: {} files changed, {} to download",
+                    summary.files_changed,
+                    format_size(summary.bytes_to_download)
+                )).strong());
+                if summary.files_removed > 0 {
+                    ui.label(format!("{} files are no longer part of the torrent", summary.files_removed));
+                }
+            }
+            if let Some(changelog) = changelog {
+                ui.separator();
+                ui.label(RichText::new("What's new").strong());
+                egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                    draw_markdown(ui, changelog);
+                });
+                ui.separator();
+            }
             ui.label("Do you want to update your local copy?");
             ui.label(RichText::new("Note: Files that are not part of the updated torrent will need to be reviewed.").italics().small());
             
@@ -209,11 +539,100 @@ fn draw_remote_update_modal(ctx: &Context) -> Option<UiAction> {
                 if ui.button("Update").clicked() {
                     action = Some(UiAction::ApplyRemoteUpdate);
                 }
-                if ui.button("Ignore").clicked() {
+                let ignore_label = if summary.is_some() { "Keep Current Version" } else { "Ignore" };
+                if ui.button(ignore_label).clicked() {
                     action = Some(UiAction::DismissRemoteUpdateModal);
                 }
             });
         });
     
     action
-} 
\ No newline at end of file
+} 
+
+/// Draw the confirmation shown when the download folder belongs to another torrent
+fn draw_folder_owner_modal(ctx: &Context, owner_url: &str) -> Option<UiAction> {
+    let mut action = None;
+    
+    Window::new("Download Folder In Use")
+        .id(egui::Id::new("folder_owner_prompt"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .show(ctx, |ui| {
+            ui.label("This download folder is already used by another torrent:");
+            ui.label(RichText::new(owner_url).monospace());
+            ui.label("Continuing may overwrite or delete that torrent's files.");
+            ui.label(RichText::new("Consider choosing a different download path instead.").italics().small());
+            
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button(RichText::new("Use This Folder Anyway").color(egui::Color32::RED)).clicked() {
+                    action = Some(UiAction::ClaimFolderAndContinue);
+                }
+                if ui.button("Cancel").clicked() {
+                    action = Some(UiAction::DismissFolderOwnerModal);
+                }
+            });
+        });
+    
+    action
+}
+
+/// Draw the one-time warning before seeding a torrent to a public swarm
+fn draw_public_seeding_modal(ctx: &Context, ui_state: &mut UiState) -> Option<UiAction> {
+    let mut action = None;
+    
+    Window::new("Seed To Public Swarm?")
+        .id(egui::Id::new("public_seeding_prompt"))
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .show(ctx, |ui| {
+            ui.label("This torrent is public: anyone who finds it can connect to you.");
+            ui.label("Seeding shares your files and reveals your IP address to every peer in the swarm.");
+            ui.checkbox(&mut ui_state.dont_ask_public_seeding, "Don't ask again");
+            
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Download and Seed").clicked() {
+                    action = Some(UiAction::ConfirmPublicSeeding);
+                }
+                if ui.button("Turn Off Seeding").clicked() {
+                    action = Some(UiAction::DeclinePublicSeeding);
+                }
+            });
+        });
+    
+    action
+}
+
+/// Draw the notice from the torrent's comment, which must be accepted before it downloads
+fn draw_notice_modal(ctx: &Context, notice: &str) -> Option<UiAction> {
+    let mut action = None;
+    
+    Window::new("Notice From the Mod Authors")
+        .id(egui::Id::new("notice_prompt"))
+        .collapsible(false)
+        .resizable(false)
+        .max_width(520.0)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .show(ctx, |ui| {
+            ui.label("This torrent comes with a notice that must be accepted before it downloads.");
+            ui.separator();
+            egui::ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                ui.label(notice);
+            });
+            
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Accept and Download").clicked() {
+                    action = Some(UiAction::AcknowledgeNotice);
+                }
+                if ui.button("Not Now").clicked() {
+                    action = Some(UiAction::DismissNotice);
+                }
+            });
+        });
+    
+    action
+}
@@ -0,0 +1,396 @@
+// src/ui/setup_wizard.rs
+// Guided first-run setup: URL, test, download folder, seeding and limits, confirm
+
+use eframe::egui::{self, Context, RichText, Window};
+
+use crate::config::{AppConfig, ClientImport, ConnectionSettings, ImportedSetting};
+use crate::i18n::{tr, tr_fmt};
+use crate::sync::preflight::{ConfigCheck, ConfigCheckKind};
+use crate::ui::config_panel::draw_config_check;
+use crate::ui::state::{UiAction, UiState};
+use crate::ui::utils::format_speed;
+
+/// Wizard pages, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WizardStep {
+    #[default]
+    Url,
+    TestUrl,
+    Path,
+    Seeding,
+    Confirm,
+}
+
+impl WizardStep {
+    const ALL: [WizardStep; 5] = [Self::Url, Self::TestUrl, Self::Path, Self::Seeding, Self::Confirm];
+
+    fn title(self) -> &'static str {
+        match self {
+            Self::Url => tr("wizard.step_url"),
+            Self::TestUrl => tr("wizard.step_test_url"),
+            Self::Path => tr("wizard.step_path"),
+            Self::Seeding => tr("wizard.step_seeding"),
+            Self::Confirm => tr("wizard.step_confirm"),
+        }
+    }
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|step| *step == self).unwrap_or(0)
+    }
+
+    fn next(self) -> Self {
+        Self::ALL.get(self.index() + 1).copied().unwrap_or(self)
+    }
+
+    fn previous(self) -> Self {
+        self.index().checked_sub(1).map_or(self, |i| Self::ALL[i])
+    }
+
+    /// Config test checks that must pass before leaving this step
+    fn required_checks(self) -> &'static [ConfigCheckKind] {
+        match self {
+            Self::TestUrl => &[ConfigCheckKind::TorrentUrl, ConfigCheckKind::TorrentParses],
+            Self::Path => &[ConfigCheckKind::PathWritable, ConfigCheckKind::FreeSpace],
+            _ => &[],
+        }
+    }
+}
+
+/// Answers collected by the wizard, written to the config on the last step
+#[derive(Debug, Clone, Default)]
+pub struct SetupWizard {
+    pub step: WizardStep,
+    pub url: String,
+    pub path: String,
+    pub should_seed: bool,
+    pub max_upload_speed_str: String,
+    pub max_download_speed_str: String,
+    pub tested: Option<(String, String)>, // URL and path the last config test ran with
+    pub connection: ConnectionSettings,
+    pub import_report: Option<Result<Vec<String>, String>>, // Lines saying what was imported, or why not
+}
+
+fn valid_limit(value: &str) -> bool {
+    value.trim().is_empty() || value.trim().parse::<u64>().is_ok()
+}
+
+impl SetupWizard {
+    /// Start from the current config, so re-running the wizard keeps what's there
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            step: WizardStep::Url,
+            url: config.torrent_url.clone(),
+            path: config.download_path.to_string_lossy().into_owned(),
+            should_seed: config.should_seed,
+            max_upload_speed_str: config.max_upload_speed.map_or(String::new(), |v| v.to_string()),
+            max_download_speed_str: config.max_download_speed.map_or(String::new(), |v| v.to_string()),
+            tested: None,
+            connection: config.connection,
+            import_report: None,
+        }
+    }
+
+    /// Pre-fill the answers from another client's settings, returning the report lines
+    pub fn apply_import(&mut self, import: &ClientImport) -> Vec<String> {
+        let limit = |value: &Option<u64>| value.map_or(String::new(), |kbps| kbps.to_string());
+        let mut report = vec![tr_fmt("wizard.import_from", &[("client", &import.client.name()), ("source", &import.source.display())])];
+        for setting in &import.settings {
+            match setting {
+                ImportedSetting::DownloadPath(path) => self.path = path.to_string_lossy().into_owned(),
+                ImportedSetting::MaxUploadSpeed(value) => self.max_upload_speed_str = limit(value),
+                ImportedSetting::MaxDownloadSpeed(value) => self.max_download_speed_str = limit(value),
+                ImportedSetting::Dht(on) => self.connection.enable_dht = *on,
+                ImportedSetting::Upnp(on) => {
+                    // Both clients accept incoming peers, which forwarding is for
+                    self.connection.enable_upnp = *on;
+                    self.connection.accept_incoming |= *on;
+                }
+            }
+            report.push(format!("✔ {}", setting.describe()));
+        }
+        if import.settings.is_empty() {
+            report.push(tr("wizard.import_nothing").to_string());
+        }
+        report.extend(import.skipped.iter().map(|skipped| tr_fmt("wizard.not_imported", &[("item", skipped)])));
+        report
+    }
+
+    /// Whether the last test ran with what's typed now. The URL step's checks don't
+    /// depend on the path, so only the URL has to match there.
+    fn test_is_current(&self) -> bool {
+        match (&self.tested, self.step) {
+            (Some((url, _)), WizardStep::TestUrl) => *url == self.url.trim(),
+            (Some((url, path)), _) => *url == self.url.trim() && *path == self.path.trim(),
+            (None, _) => false,
+        }
+    }
+
+    /// Why the current step can't be left yet, if it can't
+    pub fn blocker(&self, results: Option<&[ConfigCheck]>, running: bool) -> Option<String> {
+        match self.step {
+            WizardStep::Url if self.url.trim().is_empty() => return Some(tr("wizard.need_url").to_string()),
+            WizardStep::Path if self.path.trim().is_empty() => return Some(tr("wizard.need_path").to_string()),
+            WizardStep::Seeding if !valid_limit(&self.max_upload_speed_str) || !valid_limit(&self.max_download_speed_str) => {
+                return Some(tr("wizard.bad_limit").to_string());
+            }
+            _ => {}
+        }
+        let required = self.step.required_checks();
+        if required.is_empty() {
+            return None;
+        }
+        if running {
+            return Some(tr("wizard.waiting_test").to_string());
+        }
+        let Some(results) = results.filter(|_| self.test_is_current()) else {
+            return Some(tr("wizard.run_test").to_string());
+        };
+        results
+            .iter()
+            .find(|check| required.contains(&check.kind) && !check.passed)
+            .map(|check| check.detail.clone())
+    }
+}
+
+/// Draw the wizard window, returning the action picked on this frame
+pub fn draw_setup_wizard(ctx: &Context, ui_state: &mut UiState) -> Option<UiAction> {
+    let mut action = None;
+    let mut open = true;
+    let running = ui_state.config_test_running;
+    let results = ui_state.config_test_results.clone();
+    let wizard = &mut ui_state.wizard;
+
+    Window::new(tr("wizard.title"))
+        .id(egui::Id::new("setup_wizard"))
+        .collapsible(false)
+        .resizable(false)
+        .min_width(420.0)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.label(RichText::new(tr_fmt("wizard.step", &[
+                ("number", &(wizard.step.index() + 1)),
+                ("count", &WizardStep::ALL.len()),
+                ("title", &wizard.step.title()),
+            ])).strong());
+            ui.separator();
+
+            match wizard.step {
+                WizardStep::Url => {
+                    ui.label(tr("wizard.url_hint"));
+                    ui.text_edit_singleline(&mut wizard.url);
+                    ui.add_space(4.0);
+                    draw_import(ui, wizard, &mut action);
+                }
+                WizardStep::TestUrl => {
+                    ui.label(tr_fmt("wizard.fetching", &[("url", &wizard.url.trim())]));
+                    draw_step_checks(ui, wizard, results.as_deref(), running, &mut action);
+                }
+                WizardStep::Path => {
+                    ui.label(tr("wizard.path_hint"));
+                    ui.text_edit_singleline(&mut wizard.path);
+                    draw_step_checks(ui, wizard, results.as_deref(), running, &mut action);
+                }
+                WizardStep::Seeding => {
+                    ui.checkbox(&mut wizard.should_seed, tr("wizard.keep_seeding"));
+                    ui.label(RichText::new(tr("wizard.seeding_hint")).weak());
+                    ui.add_space(4.0);
+                    draw_limit(ui, tr("wizard.max_upload"), &mut wizard.max_upload_speed_str);
+                    draw_limit(ui, tr("wizard.max_download"), &mut wizard.max_download_speed_str);
+                }
+                WizardStep::Confirm => {
+                    egui::Grid::new("setup_wizard_summary").num_columns(2).show(ui, |ui| {
+                        ui.label(tr("wizard.summary_url"));
+                        ui.label(wizard.url.trim());
+                        ui.end_row();
+                        ui.label(tr("wizard.summary_path"));
+                        ui.label(wizard.path.trim());
+                        ui.end_row();
+                        ui.label(tr("wizard.summary_seeding"));
+                        ui.label(if wizard.should_seed { tr("wizard.yes") } else { tr("wizard.no") });
+                        ui.end_row();
+                        ui.label(tr("wizard.summary_upload"));
+                        ui.label(limit_text(&wizard.max_upload_speed_str));
+                        ui.end_row();
+                        ui.label(tr("wizard.summary_download"));
+                        ui.label(limit_text(&wizard.max_download_speed_str));
+                        ui.end_row();
+                        if wizard.import_report.as_ref().is_some_and(|report| report.is_ok()) {
+                            ui.label(tr("wizard.summary_dht_upnp"));
+                            let on_off = |on: bool| if on { tr("wizard.on") } else { tr("wizard.off") };
+                            ui.label(format!("{} / {}", on_off(wizard.connection.enable_dht), on_off(wizard.connection.enable_upnp)));
+                            ui.end_row();
+                        }
+                    });
+                    ui.label(RichText::new(tr("wizard.finish_hint")).weak());
+                }
+            }
+
+            ui.separator();
+            let blocker = wizard.blocker(results.as_deref(), running);
+            if let Some(reason) = &blocker
+                && !matches!(wizard.step, WizardStep::Url)
+            {
+                ui.label(RichText::new(reason).color(ui.visuals().warn_fg_color));
+            }
+            ui.horizontal(|ui| {
+                if wizard.step != WizardStep::Url && ui.button(tr("wizard.back")).clicked() {
+                    wizard.step = wizard.step.previous();
+                }
+                if wizard.step == WizardStep::Confirm {
+                    if ui.button(tr("wizard.finish")).clicked() {
+                        action = Some(UiAction::FinishSetupWizard);
+                    }
+                } else if ui.add_enabled(blocker.is_none(), egui::Button::new(tr("wizard.next"))).clicked() {
+                    wizard.step = wizard.step.next();
+                    // Test the URL as soon as its page opens
+                    if wizard.step == WizardStep::TestUrl && !wizard.test_is_current() {
+                        action = Some(UiAction::TestSetupWizard);
+                    }
+                }
+            });
+        });
+
+    if !open {
+        action = Some(UiAction::DismissSetupWizard);
+    }
+    action
+}
+
+/// The checks the current step needs, with a button to run the test again
+fn draw_step_checks(
+    ui: &mut egui::Ui,
+    wizard: &SetupWizard,
+    results: Option<&[ConfigCheck]>,
+    running: bool,
+    action: &mut Option<UiAction>,
+) {
+    if running {
+        ui.horizontal(|ui| {
+            ui.spinner();
+            ui.label(tr("wizard.testing"));
+        });
+        return;
+    }
+    if let Some(results) = results.filter(|_| wizard.test_is_current()) {
+        for check in results.iter().filter(|check| wizard.step.required_checks().contains(&check.kind)) {
+            draw_config_check(ui, check);
+        }
+    }
+    let label = if wizard.step == WizardStep::Path { tr("wizard.check_folder") } else { tr("wizard.test_again") };
+    if ui.button(label).clicked() {
+        *action = Some(UiAction::TestSetupWizard);
+    }
+}
+
+/// The one-time import button, or what the import did
+fn draw_import(ui: &mut egui::Ui, wizard: &SetupWizard, action: &mut Option<UiAction>) {
+    match &wizard.import_report {
+        Some(Ok(lines)) => {
+            for line in lines {
+                ui.label(RichText::new(line).small());
+            }
+        }
+        Some(Err(e)) => {
+            ui.label(RichText::new(e).small().color(ui.visuals().warn_fg_color));
+        }
+        None => {
+            if ui.button(tr("wizard.import"))
+                .on_hover_text(tr("wizard.import_hint"))
+                .clicked()
+            {
+                *action = Some(UiAction::ImportClientSettings);
+            }
+        }
+    }
+}
+
+fn draw_limit(ui: &mut egui::Ui, label: &str, value: &mut String) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(egui::TextEdit::singleline(value).desired_width(80.0))
+            .on_hover_text(tr("limit.empty_hint"));
+        ui.label(RichText::new(limit_text(value)).weak());
+    });
+}
+
+fn limit_text(value: &str) -> String {
+    match value.trim().parse::<u64>() {
+        Ok(kbps) if kbps > 0 => format_speed(kbps as f64 * 1024.0),
+        _ => tr("limit.unlimited").to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(kind: ConfigCheckKind, passed: bool) -> ConfigCheck {
+        ConfigCheck { kind, passed, detail: format!("{:?} {}", kind, passed) }
+    }
+
+    #[test]
+    fn test_steps_need_a_passing_test_for_current_input() {
+        let mut wizard = SetupWizard { step: WizardStep::TestUrl, url: "http://a/x.torrent".to_string(), ..Default::default() };
+        let results = vec![
+            check(ConfigCheckKind::TorrentUrl, true),
+            check(ConfigCheckKind::TorrentParses, true),
+            check(ConfigCheckKind::PathWritable, false),
+            check(ConfigCheckKind::FreeSpace, false),
+        ];
+        assert_eq!(wizard.blocker(Some(&results), false).as_deref(), Some("Run the test first"));
+
+        // The path doesn't matter for the URL checks
+        wizard.tested = Some((wizard.url.clone(), String::new()));
+        assert_eq!(wizard.blocker(Some(&results), false), None);
+        assert!(wizard.blocker(Some(&results), true).is_some());
+
+        wizard.step = WizardStep::Path;
+        wizard.path = "/mods".to_string();
+        assert_eq!(wizard.blocker(Some(&results), false).as_deref(), Some("Run the test first"));
+        wizard.tested = Some((wizard.url.clone(), wizard.path.clone()));
+        assert_eq!(wizard.blocker(Some(&results), false).as_deref(), Some("PathWritable false"));
+
+        // Editing the URL afterwards makes the result stale
+        wizard.step = WizardStep::TestUrl;
+        wizard.url.push('2');
+        assert!(wizard.blocker(Some(&results), false).is_some());
+    }
+
+    #[test]
+    fn test_import_fills_answers() {
+        let mut wizard = SetupWizard { max_upload_speed_str: "100".to_string(), ..Default::default() };
+        let import = ClientImport {
+            client: crate::config::ClientKind::Deluge,
+            source: std::path::PathBuf::from("/home/me/.config/deluge/core.conf"),
+            settings: vec![
+                ImportedSetting::DownloadPath("/home/me/Downloads".into()),
+                ImportedSetting::MaxUploadSpeed(None),
+                ImportedSetting::Upnp(true),
+            ],
+            skipped: vec!["Listen port 6881".to_string()],
+        };
+        let report = wizard.apply_import(&import);
+        assert_eq!(wizard.path, "/home/me/Downloads");
+        assert_eq!(wizard.max_upload_speed_str, "", "unlimited is an empty limit");
+        assert!(wizard.connection.enable_upnp && wizard.connection.accept_incoming);
+        assert_eq!(report.len(), 5);
+        assert_eq!(report[4], "✖ Not imported: Listen port 6881");
+    }
+
+    #[test]
+    fn test_input_steps_validate_fields() {
+        let mut wizard = SetupWizard::default();
+        assert!(wizard.blocker(None, false).is_some());
+        wizard.url = "http://a/x.torrent".to_string();
+        assert_eq!(wizard.blocker(None, false), None);
+
+        wizard.step = WizardStep::Seeding;
+        wizard.max_upload_speed_str = "fast".to_string();
+        assert!(wizard.blocker(None, false).is_some());
+        wizard.max_upload_speed_str = " 500 ".to_string();
+        assert_eq!(wizard.blocker(None, false), None);
+        assert_eq!(WizardStep::Confirm.next(), WizardStep::Confirm);
+        assert_eq!(WizardStep::Url.previous(), WizardStep::Url);
+    }
+}
@@ -4,8 +4,10 @@
 use std::path::PathBuf;
 use std::collections::HashSet;
 use std::time::Instant;
+use std::sync::Arc;
 use crate::ui::utils::SyncStatus;
 use crate::ui::torrent_file_tree::TorrentFileTree;
+use crate::config::{ConnectionSettings, EncryptionMode, IpMode};
 
 /// Represents a modal dialog state
 #[derive(Debug, Clone)]
@@ -13,7 +15,11 @@ pub enum ModalState {
     MissingFiles(HashSet<PathBuf>),
     ExtraFiles(Vec<PathBuf>),
     RemoteUpdateAvailable,
+    FolderOwnerMismatch(String), // URL of the torrent that owns the folder
+    ConfirmPublicSeeding,
+    AcknowledgeNotice(String), // The torrent's comment
     Settings,
+    SetupWizard,
     None,
 }
 
@@ -23,7 +29,18 @@ pub struct TorrentFileStats {
     pub name: Option<String>,
     pub info_hash: Option<String>,
     pub output_folder: Option<String>,
-    pub files: Vec<(String, u64)>, // (path, size)
+    pub files: Arc<Vec<TorrentFileEntry>>, // Shared so drawing doesn't copy the list every frame
+    pub files_complete: usize, // Selected files fully downloaded
+    pub files_total: usize, // Selected files
+}
+
+/// One file of the managed torrent in the Files tab
+#[derive(Debug, Clone, PartialEq)]
+pub struct TorrentFileEntry {
+    pub path: String, // '/'-separated, relative to the download folder
+    pub size: u64,
+    pub paused: bool, // Paused from the Files tab
+    pub complete: bool,
 }
 
 /// Torrent statistics for the UI
@@ -33,25 +50,48 @@ pub struct TorrentStats {
     pub total_bytes: u64,
     pub progress_bytes: u64,
     pub uploaded_bytes: u64,
+    pub uploaded_since_reset: u64,
+    pub downloaded_since_reset: u64,
     pub download_speed: f64,
     pub upload_speed: f64,
+    pub smoothed_download_speed: f64, // Averaged over speed_average_window_secs for the headline readout
+    pub smoothed_upload_speed: f64,
     pub progress: f64,
     pub state: String,
     pub is_finished: bool,
     pub time_remaining: Option<String>,
+    pub done_by: Option<String>, // Projected finish clock time while downloading, "unknown" if too slow
+    pub files_complete: usize, // Selected files fully downloaded
+    pub files_total: usize, // Selected files, 0 until file details are known
+}
+
+/// Aggregate seeding figures across every torrent in the session
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SeedingSummary {
+    pub active_seeding: usize,
+    pub upload_speed: f64,
+    pub uploaded_bytes: u64,
+    pub downloaded_bytes: u64,
+}
+
+impl SeedingSummary {
+    /// Upload/download ratio, or None if nothing has been downloaded yet
+    pub fn ratio(&self) -> Option<f64> {
+        if self.downloaded_bytes > 0 {
+            Some(self.uploaded_bytes as f64 / self.downloaded_bytes as f64)
+        } else {
+            None
+        }
+    }
 }
 
 /// Represents the available tabs in the torrent display
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum TorrentTab {
+    #[default]
     Details,
     Files,
-}
-
-impl Default for TorrentTab {
-    fn default() -> Self {
-        Self::Details
-    }
+    Diagnostics,
 }
 
 /// UI State contains all the data needed by the UI components
@@ -68,9 +108,57 @@ pub struct UiState {
     pub max_download_speed: Option<u64>,
     pub max_upload_speed_str: String,
     pub max_download_speed_str: String,
+    pub allow_download: bool, // Settings modal edit buffer
+    pub allow_upload: bool, // Settings modal edit buffer
+    
+    // Executable that pauses torrents while running, empty for none
+    pub pause_process_str: String,
+    pub bind_interface_str: String, // Settings modal edit buffer, empty for any interface
+    pub tracker_passkey_str: String, // Settings modal edit buffer, empty for none
+    pub show_passkey: bool, // Reveal the passkey instead of masking it
+    pub language: Option<String>, // Language picked in the settings modal, None for system default
+    pub remote_update_summary: Option<crate::sync::summary::UpdateSummary>, // Shown in the update prompt
+    pub changelog: Option<String>, // Release notes for the pending update, shown in the update prompt
+    pub confirm_before_update: bool, // Settings modal edit buffer
+    pub prevent_sleep_during_sync: bool, // Settings modal edit buffer
+    pub safe_mode: bool, // Settings modal edit buffer
+    pub safe_mode_active: bool, // Saved setting, for the banner
+    pub updates_snoozed: bool, // Saved snooze that hasn't run out yet
+    pub update_countdown: Option<chrono::DateTime<chrono::Local>>, // When a scheduled update applies
+    pub resolved_url: Option<String>, // Where the saved torrent URL redirected to on the last check
+    pub config_test_running: bool, // Waiting for a ConfigTestResult
+    pub config_test_results: Option<Vec<crate::sync::preflight::ConfigCheck>>, // Last "Test" button results
+    pub url_check: Option<(String, Option<crate::sync::preflight::UrlCheck>)>, // URL last checked, None result while running
+    pub url_check_due: Option<Instant>, // When to check the URL once typing pauses
+    
+    // Ignore pattern editor in the settings modal, previewed against the local files listed on open
+    pub ignore_patterns: Vec<String>,
+    pub ignore_new_pattern: String,
+    pub ignore_preview_files: Vec<String>,
+    pub ignore_previews: Vec<crate::sync::ignore::PatternPreview>,
+    
+    // "Don't ask again" checkbox in the public seeding confirmation
+    pub dont_ask_public_seeding: bool,
+    
+    // A torrent waits for its notice to be accepted, and whether the prompt was put off with "Not Now"
+    pub notice_pending: bool,
+    pub notice_dismissed: bool,
+    
+    // Advanced connection settings being edited in the settings modal
+    pub connection: ConnectionSettings,
+    pub ip_mode: IpMode,
+    pub encryption_mode: EncryptionMode,
+    pub active_encryption_mode: EncryptionMode, // What the session started with; changes apply on restart
+    pub force_private: bool, // Settings modal edit buffer
+    pub private_flag: Option<(String, bool)>, // Private flag of the cached torrent, by info hash
+    pub torrent_is_private: bool, // Flagged private or force_private, for the badge
     
     // Error state
     pub last_error: Option<String>,
+    pub last_warning: Option<String>, // Shown until the next remote check
+    pub permission_denied_path: Option<PathBuf>, // Folder the OS refused writes to, until the path is saved again
+    pub synced_version: Option<String>, // Version the folder is on after the last sync, see expected_version
+    pub network_share: Option<String>, // Kind of network share the saved download folder is on, see network_share_mode
     
     // Current sync status
     pub sync_status: SyncStatus,
@@ -78,6 +166,73 @@ pub struct UiState {
     // Torrent details
     pub torrent_stats: Option<TorrentStats>,
     pub torrent_files: Option<TorrentFileStats>,
+    pub files_source: Option<(Arc<librqbit::TorrentStats>, Vec<String>)>, // Stats and paused files torrent_files was built from
+    pub category_progress: Vec<crate::ui::categories::CategoryProgress>, // Progress breakdown for the managed torrent
+    pub speed_smoother: crate::ui::eta::SpeedSmoother, // Download speed average behind done_by
+    pub speed_smoother_torrent: Option<usize>, // Torrent the average belongs to
+    pub display_speed_smoothers: (crate::ui::eta::SpeedSmoother, crate::ui::eta::SpeedSmoother), // Download and upload readouts
+    
+    // User-assigned label for the managed torrent, and the edit buffer while renaming
+    pub torrent_label: Option<String>,
+    pub label_edit: Option<String>,
+    
+    // File list export from the Files tab
+    pub export_path_str: String,
+    pub export_format: crate::sync::file_list::ExportFormat,
+    pub export_result: Option<String>, // Summary of the last successful export
+    
+    // Spot check of files picked in the Files tab
+    pub subset_verify_running: bool,
+    pub subset_verify: Option<Result<crate::sync::spot_check::SpotCheckReport, String>>,
+    
+    // Publisher mode: edits not yet published, and the last torrent written
+    pub folder_changes: Option<crate::sync::publish::FolderChanges>,
+    pub last_published: Option<crate::sync::publish::PublishedTorrent>,
+    
+    // Latest memory reading, for the Details tab
+    pub memory_usage: Option<crate::sync::memory::MemoryUsage>,
+    
+    // Sequence numbers of the sync events received, and when the last one was sent
+    pub event_sequence: crate::sync::stamp::SequenceTracker,
+    pub last_event_at: Option<chrono::DateTime<chrono::Local>>,
+    
+    // Embedded HTTP servers that are up, as (name, URL), for the Diagnostics tab
+    pub servers: Vec<(String, String)>,
+    
+    // Tracker reachability test from the Diagnostics tab
+    pub tracker_test_running: bool,
+    pub tracker_test: Option<Result<Vec<crate::sync::tracker_check::TrackerReport>, String>>,
+    
+    // Offline check of a folder against a .torrent file, from the Diagnostics tab
+    pub offline_torrent_str: String,
+    pub offline_folder_str: String, // Empty for the download folder
+    pub offline_verify_running: bool,
+    pub offline_verify: Option<Result<crate::sync::offline_verify::OfflineVerifyReport, String>>,
+    
+    // Listen port and reachability, for the Diagnostics tab
+    pub listen_status: crate::sync::port_check::ListenStatus,
+    pub incoming_seen: bool, // A peer has connected in since the port was bound
+    pub port_check_enabled: bool, // Saved setting: port_check_url is configured
+    pub port_check_running: bool,
+    pub port_check: Option<Result<crate::sync::port_check::PortCheck, String>>,
+    
+    // Torrent session that didn't start, for the recovery banner
+    pub session_failure: Option<crate::sync::startup::SessionFailure>,
+    pub session_retrying: bool,
+    pub recovery_port_str: String,
+    
+    // Diagnostics bundle from the Settings modal
+    pub bundle_path_str: String,
+    pub bundle_result: Option<String>, // Where the last bundle was saved
+    
+    // Seeding summary across the session
+    pub seeding_summary: SeedingSummary,
+    
+    // Setup wizard answers while it's open
+    pub wizard: crate::ui::setup_wizard::SetupWizard,
+    
+    // Recent events, separate from the log
+    pub activity: crate::ui::activity::ActivityFeed,
     
     // UI components state
     pub file_tree: TorrentFileTree,
@@ -103,10 +258,85 @@ impl Default for UiState {
             max_download_speed: None,
             max_upload_speed_str: String::new(),
             max_download_speed_str: String::new(),
+            allow_download: true,
+            allow_upload: true,
+            pause_process_str: String::new(),
+            bind_interface_str: String::new(),
+            tracker_passkey_str: String::new(),
+            show_passkey: false,
+            language: None,
+            remote_update_summary: None,
+            changelog: None,
+            confirm_before_update: false,
+            prevent_sleep_during_sync: false,
+            safe_mode: false,
+            safe_mode_active: false,
+            updates_snoozed: false,
+            update_countdown: None,
+            resolved_url: None,
+            config_test_running: false,
+            config_test_results: None,
+            url_check: None,
+            url_check_due: None,
+            ignore_patterns: Vec::new(),
+            ignore_new_pattern: String::new(),
+            ignore_preview_files: Vec::new(),
+            ignore_previews: Vec::new(),
+            dont_ask_public_seeding: false,
+            notice_pending: false,
+            notice_dismissed: false,
+            connection: ConnectionSettings::default(),
+            ip_mode: IpMode::default(),
+            encryption_mode: EncryptionMode::default(),
+            active_encryption_mode: EncryptionMode::default(),
+            force_private: false,
+            private_flag: None,
+            torrent_is_private: false,
             last_error: None,
+            last_warning: None,
+            permission_denied_path: None,
+            network_share: None,
+            synced_version: None,
             sync_status: SyncStatus::Idle,
             torrent_stats: None,
             torrent_files: None,
+            files_source: None,
+            category_progress: Vec::new(),
+            speed_smoother: Default::default(),
+            speed_smoother_torrent: None,
+            display_speed_smoothers: Default::default(),
+            torrent_label: None,
+            label_edit: None,
+            export_path_str: String::new(),
+            export_format: Default::default(),
+            export_result: None,
+            subset_verify_running: false,
+            subset_verify: None,
+            folder_changes: None,
+            last_published: None,
+            memory_usage: None,
+            event_sequence: Default::default(),
+            last_event_at: None,
+            session_failure: None,
+            session_retrying: false,
+            recovery_port_str: String::new(),
+            bundle_path_str: String::new(),
+            bundle_result: None,
+            servers: Vec::new(),
+            tracker_test_running: false,
+            tracker_test: None,
+            offline_torrent_str: String::new(),
+            offline_folder_str: String::new(),
+            offline_verify_running: false,
+            offline_verify: None,
+            listen_status: Default::default(),
+            incoming_seen: false,
+            port_check_enabled: false,
+            port_check_running: false,
+            port_check: None,
+            seeding_summary: SeedingSummary::default(),
+            wizard: Default::default(),
+            activity: crate::ui::activity::ActivityFeed::default(),
             file_tree: TorrentFileTree::default(),
             modal_state: ModalState::None,
             last_update: None,
@@ -148,6 +378,20 @@ impl UiState {
         !self.download_path.as_os_str().is_empty()
     }
     
+    /// Name to show for the managed torrent: the user's label, else the torrent's own name
+    pub fn torrent_display_name(&self) -> String {
+        if let Some(label) = &self.torrent_label {
+            return label.clone();
+        }
+        if let Some(name) = self.torrent_files.as_ref().and_then(|f| f.name.clone()) {
+            return name;
+        }
+        match &self.torrent_stats {
+            Some(stats) => format!("Torrent {}", stats.id),
+            None => "No torrent".to_string(),
+        }
+    }
+    
     /// Get time since last update
     pub fn time_since_update(&self) -> Option<std::time::Duration> {
         self.last_update.map(|time| Instant::now().duration_since(time))
@@ -169,6 +413,9 @@ pub enum UiAction {
     // Configuration actions
     SaveConfig,
     UpdateFromRemote,
+    CancelSync,
+    TestConfig,
+    CheckUrl,
     VerifyLocalFiles,
     OpenDownloadFolder,
     
@@ -176,16 +423,48 @@ pub enum UiAction {
     FixMissingFiles,
     DeleteExtraFiles,
     ApplyRemoteUpdate,
+    SetTorrentLabel(String),
+    ExportFileList,
+    SaveDiagnosticsBundle,
+    VerifySubset(Vec<PathBuf>),
+    TestTrackers,
+    CheckPort,
+    SetFilePaused { path: String, paused: bool },
+    PrioritizeFile(String),
+    ReloadConfig,
+    VerifyAgainstTorrent { torrent: PathBuf, folder: Option<PathBuf> }, // No folder for the download folder
+    SetExpandedFolders(Vec<String>),
+    ResetTorrentStats,
+    ResetGlobalStats,
+    ResumeDownloads,
+    SnoozeUpdates(Option<crate::sync::snooze::SnoozeChoice>), // None resumes them
+    CancelScheduledUpdate,
+    RetryAfterFailures,
+    ClaimFolderAndContinue,
+    ConfirmPublicSeeding,
+    DeclinePublicSeeding,
+    AcknowledgeNotice,
+    DismissNotice,
+    ShowNotice,
+    RetrySession,
+    ClearSessionAndRetry,
+    UseListenPortAndRetry,
     
     // Modal actions
     ShowSettingsModal,
     SaveSettingsAndDismiss,
+    ShowSetupWizard,
+    TestSetupWizard,
+    ImportClientSettings,
+    FinishSetupWizard,
     
     // Modal dismissal actions
     DismissMissingFilesModal,
     DismissExtraFilesModal,
     DismissRemoteUpdateModal,
+    DismissFolderOwnerModal,
     DismissSettingsModal,
+    DismissSetupWizard,
     
     // No action
     None,
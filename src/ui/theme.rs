@@ -0,0 +1,273 @@
+// src/ui/theme.rs
+
+//! Theme files (`theme_file`): a JSON description of colors, rounding and spacing laid
+//! over egui's dark or light look at startup. Every field is optional, so a theme only
+//! lists what it changes. `themes/example.json` has every field at its default.
+
+use anyhow::{Context, Result};
+use eframe::egui::{self, Color32, CornerRadius, Margin, Style, Vec2, Visuals};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::info;
+
+/// The egui look a theme starts from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeBase {
+    #[default]
+    Dark,
+    Light,
+}
+
+/// A color as "#rrggbb" or "#rrggbbaa". As in egui, translucent colors are premultiplied:
+/// the color channels are already scaled by alpha, and alpha 00 with a color lightens
+/// what's behind instead of hiding it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(try_from = "String", into = "String")]
+pub struct HexColor(pub Color32);
+
+impl TryFrom<String> for HexColor {
+    type Error = String;
+    fn try_from(hex: String) -> Result<Self, String> {
+        let bytes: Option<Vec<u8>> = hex
+            .strip_prefix('#')
+            .filter(|digits| matches!(digits.len(), 6 | 8) && digits.is_ascii())
+            .and_then(|digits| (0..digits.len()).step_by(2).map(|i| u8::from_str_radix(&digits[i..i + 2], 16).ok()).collect());
+        match bytes.as_deref() {
+            Some(&[r, g, b]) => Ok(HexColor(Color32::from_rgb(r, g, b))),
+            Some(&[r, g, b, a]) => Ok(HexColor(Color32::from_rgba_premultiplied(r, g, b, a))),
+            _ => Err(format!("invalid color {:?}, expected #rrggbb or #rrggbbaa", hex)),
+        }
+    }
+}
+
+impl From<HexColor> for String {
+    fn from(HexColor(color): HexColor) -> String {
+        let [r, g, b, a] = color.to_array();
+        format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+    }
+}
+
+/// Look of one widget state (e.g. hovered buttons)
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WidgetTheme {
+    pub bg_fill: Option<HexColor>,
+    pub weak_bg_fill: Option<HexColor>, // Background of buttons and the like
+    pub bg_stroke_color: Option<HexColor>, // Outline
+    pub fg_stroke_color: Option<HexColor>, // Text and icons
+    pub corner_radius: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct WidgetThemes {
+    pub noninteractive: WidgetTheme, // Labels, separators and frames
+    pub inactive: WidgetTheme,
+    pub hovered: WidgetTheme,
+    pub active: WidgetTheme, // While clicked or dragged
+    pub open: WidgetTheme, // Open menus and combo boxes
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpacingTheme {
+    pub item_spacing: Option<[f32; 2]>, // Gap between widgets, [x, y]
+    pub button_padding: Option<[f32; 2]>,
+    pub window_margin: Option<i8>,
+    pub indent: Option<f32>,
+    pub interact_size: Option<[f32; 2]>, // Minimum size of buttons and fields
+}
+
+/// Contents of a theme file
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeFile {
+    pub base: ThemeBase,
+    pub text_color: Option<HexColor>, // Overrides every widget's text color
+    pub hyperlink_color: Option<HexColor>,
+    pub selection_color: Option<HexColor>,
+    pub faint_bg_color: Option<HexColor>, // Banners and striped rows
+    pub extreme_bg_color: Option<HexColor>, // Text fields and progress bar troughs
+    pub code_bg_color: Option<HexColor>,
+    pub warn_fg_color: Option<HexColor>,
+    pub error_fg_color: Option<HexColor>,
+    pub panel_fill: Option<HexColor>,
+    pub window_fill: Option<HexColor>,
+    pub window_stroke_color: Option<HexColor>,
+    pub window_corner_radius: Option<u8>,
+    pub menu_corner_radius: Option<u8>,
+    pub widgets: WidgetThemes,
+    pub spacing: SpacingTheme,
+}
+
+fn set_color(target: &mut Color32, color: Option<HexColor>) {
+    if let Some(HexColor(color)) = color {
+        *target = color;
+    }
+}
+
+fn set_radius(target: &mut CornerRadius, radius: Option<u8>) {
+    if let Some(radius) = radius {
+        *target = CornerRadius::same(radius);
+    }
+}
+
+fn set_vec2(target: &mut Vec2, value: Option<[f32; 2]>) {
+    if let Some([x, y]) = value {
+        *target = Vec2::new(x, y);
+    }
+}
+
+impl WidgetTheme {
+    fn apply(&self, widget: &mut egui::style::WidgetVisuals) {
+        set_color(&mut widget.bg_fill, self.bg_fill);
+        set_color(&mut widget.weak_bg_fill, self.weak_bg_fill);
+        set_color(&mut widget.bg_stroke.color, self.bg_stroke_color);
+        set_color(&mut widget.fg_stroke.color, self.fg_stroke_color);
+        set_radius(&mut widget.corner_radius, self.corner_radius);
+    }
+
+    fn from_widget(widget: &egui::style::WidgetVisuals) -> Self {
+        Self {
+            bg_fill: Some(HexColor(widget.bg_fill)),
+            weak_bg_fill: Some(HexColor(widget.weak_bg_fill)),
+            bg_stroke_color: Some(HexColor(widget.bg_stroke.color)),
+            fg_stroke_color: Some(HexColor(widget.fg_stroke.color)),
+            corner_radius: Some(widget.corner_radius.nw),
+        }
+    }
+}
+
+impl ThemeFile {
+    /// Replace `style`'s look with the theme's base, then lay the theme over it
+    pub fn apply(&self, style: &mut Style) {
+        let visuals = &mut style.visuals;
+        *visuals = match self.base {
+            ThemeBase::Dark => Visuals::dark(),
+            ThemeBase::Light => Visuals::light(),
+        };
+        if let Some(HexColor(color)) = self.text_color {
+            visuals.override_text_color = Some(color);
+        }
+        set_color(&mut visuals.hyperlink_color, self.hyperlink_color);
+        set_color(&mut visuals.selection.bg_fill, self.selection_color);
+        set_color(&mut visuals.faint_bg_color, self.faint_bg_color);
+        set_color(&mut visuals.extreme_bg_color, self.extreme_bg_color);
+        set_color(&mut visuals.code_bg_color, self.code_bg_color);
+        set_color(&mut visuals.warn_fg_color, self.warn_fg_color);
+        set_color(&mut visuals.error_fg_color, self.error_fg_color);
+        set_color(&mut visuals.panel_fill, self.panel_fill);
+        set_color(&mut visuals.window_fill, self.window_fill);
+        set_color(&mut visuals.window_stroke.color, self.window_stroke_color);
+        set_radius(&mut visuals.window_corner_radius, self.window_corner_radius);
+        set_radius(&mut visuals.menu_corner_radius, self.menu_corner_radius);
+        self.widgets.noninteractive.apply(&mut visuals.widgets.noninteractive);
+        self.widgets.inactive.apply(&mut visuals.widgets.inactive);
+        self.widgets.hovered.apply(&mut visuals.widgets.hovered);
+        self.widgets.active.apply(&mut visuals.widgets.active);
+        self.widgets.open.apply(&mut visuals.widgets.open);
+
+        let spacing = &mut style.spacing;
+        set_vec2(&mut spacing.item_spacing, self.spacing.item_spacing);
+        set_vec2(&mut spacing.button_padding, self.spacing.button_padding);
+        set_vec2(&mut spacing.interact_size, self.spacing.interact_size);
+        if let Some(margin) = self.spacing.window_margin {
+            spacing.window_margin = Margin::same(margin);
+        }
+        if let Some(indent) = self.spacing.indent {
+            spacing.indent = indent;
+        }
+    }
+
+    /// Every field of the theme set from `style`, e.g. to start a theme from the current look.
+    /// Per-corner radii are read from the top-left corner.
+    pub fn from_style(style: &Style) -> Self {
+        let visuals = &style.visuals;
+        let spacing = &style.spacing;
+        let vec2 = |v: Vec2| Some([v.x, v.y]);
+        Self {
+            base: if visuals.dark_mode { ThemeBase::Dark } else { ThemeBase::Light },
+            text_color: visuals.override_text_color.map(HexColor),
+            hyperlink_color: Some(HexColor(visuals.hyperlink_color)),
+            selection_color: Some(HexColor(visuals.selection.bg_fill)),
+            faint_bg_color: Some(HexColor(visuals.faint_bg_color)),
+            extreme_bg_color: Some(HexColor(visuals.extreme_bg_color)),
+            code_bg_color: Some(HexColor(visuals.code_bg_color)),
+            warn_fg_color: Some(HexColor(visuals.warn_fg_color)),
+            error_fg_color: Some(HexColor(visuals.error_fg_color)),
+            panel_fill: Some(HexColor(visuals.panel_fill)),
+            window_fill: Some(HexColor(visuals.window_fill)),
+            window_stroke_color: Some(HexColor(visuals.window_stroke.color)),
+            window_corner_radius: Some(visuals.window_corner_radius.nw),
+            menu_corner_radius: Some(visuals.menu_corner_radius.nw),
+            widgets: WidgetThemes {
+                noninteractive: WidgetTheme::from_widget(&visuals.widgets.noninteractive),
+                inactive: WidgetTheme::from_widget(&visuals.widgets.inactive),
+                hovered: WidgetTheme::from_widget(&visuals.widgets.hovered),
+                active: WidgetTheme::from_widget(&visuals.widgets.active),
+                open: WidgetTheme::from_widget(&visuals.widgets.open),
+            },
+            spacing: SpacingTheme {
+                item_spacing: vec2(spacing.item_spacing),
+                button_padding: vec2(spacing.button_padding),
+                window_margin: Some(spacing.window_margin.left),
+                indent: Some(spacing.indent),
+                interact_size: vec2(spacing.interact_size),
+            },
+        }
+    }
+}
+
+/// Read a theme file
+pub fn load_theme(path: &Path) -> Result<ThemeFile> {
+    let contents = std::fs::read_to_string(path).with_context(|| format!("Failed to read theme file: {}", path.display()))?;
+    serde_json::from_str(&contents).with_context(|| format!("Invalid theme file {}", path.display()))
+}
+
+/// Apply the theme at `path` and stick to its base look instead of following the system.
+/// On error the default look is kept.
+pub fn apply_theme_file(ctx: &egui::Context, path: &Path) -> Result<()> {
+    let theme = load_theme(path)?;
+    let egui_theme = match theme.base {
+        ThemeBase::Dark => egui::Theme::Dark,
+        ThemeBase::Light => egui::Theme::Light,
+    };
+    ctx.set_theme(egui_theme);
+    ctx.style_mut_of(egui_theme, |style| theme.apply(style));
+    info!("UI: Applied theme {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_theme_overrides_only_its_fields() -> Result<()> {
+        let theme: ThemeFile = serde_json::from_str(
+            r##"{ "base": "light", "panel_fill": "#102030", "widgets": { "hovered": { "corner_radius": 8 } }, "spacing": { "item_spacing": [10, 6] } }"##,
+        )?;
+        let mut style = Style::default();
+        theme.apply(&mut style);
+        assert!(!style.visuals.dark_mode);
+        assert_eq!(style.visuals.panel_fill, Color32::from_rgb(0x10, 0x20, 0x30));
+        assert_eq!(style.visuals.widgets.hovered.corner_radius, CornerRadius::same(8));
+        assert_eq!(style.visuals.hyperlink_color, Visuals::light().hyperlink_color);
+        assert_eq!(style.spacing.item_spacing, Vec2::new(10.0, 6.0));
+
+        assert!(serde_json::from_str::<ThemeFile>(r#"{ "panel_fill": "blue" }"#).is_err());
+        assert!(serde_json::from_str::<ThemeFile>(r##"{ "panel_colour": "#000" }"##).is_err(), "typos are reported");
+        Ok(())
+    }
+
+    #[test]
+    fn test_example_theme_is_the_default_look() -> Result<()> {
+        let example = load_theme(&Path::new(env!("CARGO_MANIFEST_DIR")).join("themes/example.json"))?;
+        let mut style = Style::default();
+        example.apply(&mut style);
+        assert_eq!(ThemeFile::from_style(&style), example, "the example round-trips");
+        assert_eq!(style.visuals, Visuals::dark());
+        Ok(())
+    }
+}
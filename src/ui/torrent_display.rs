@@ -10,11 +10,17 @@ pub struct TorrentDisplay;
 impl TorrentDisplay {
     /// Draw the torrent details
     pub fn draw(ui: &mut egui::Ui, ui_state: &mut crate::ui::UiState) -> Option<crate::ui::UiAction> {
+        let mut action = None;
+        
         // --- Error Display (if any) ---
         if let Some(error) = &ui_state.last_error {
              ui.label(RichText::new(format!("Error: {}", error)).color(Color32::RED));
              ui.add_space(8.0);
         }
+        if let Some(warning) = &ui_state.last_warning {
+            ui.label(RichText::new(format!("Warning: {}", warning)).color(Color32::YELLOW));
+            ui.add_space(8.0);
+        }
 
         // --- Use the torrent stats from UiState --- 
         if let Some(stats) = &ui_state.torrent_stats {
@@ -23,17 +29,20 @@ impl TorrentDisplay {
             let state_str = stats.state.clone();
             let down_speed = stats.download_speed;
             let up_speed = stats.upload_speed;
+            let (smoothed_down, smoothed_up) = (stats.smoothed_download_speed, stats.smoothed_upload_speed);
             let torrent_id = stats.id;
             let total_bytes = stats.total_bytes;
-            let progress_bytes = stats.progress_bytes;
-            let uploaded_bytes = stats.uploaded_bytes;
+            let uploaded_bytes = stats.uploaded_since_reset;
+            let downloaded_since_reset = stats.downloaded_since_reset;
             let eta = stats.time_remaining.clone();
+            let done_by = stats.done_by.clone();
+            let (files_complete, files_total) = (stats.files_complete, stats.files_total);
             
             // Extract file information if available
             let mut file_name = None;
             let mut info_hash = None;
             let mut output_folder = None;
-            let mut file_list = Vec::new();
+            let mut file_list = std::sync::Arc::new(Vec::new());
             
             if let Some(file_details) = &ui_state.torrent_files {
                 file_name = file_details.name.clone();
@@ -64,10 +73,19 @@ impl TorrentDisplay {
                 .corner_radius(8.0)
                 .show(ui, |ui| {
                     
+                    // 0. Torrent name / label with inline rename
+                    Self::draw_label_row(ui, ui_state, &mut action);
+                    ui.add_space(4.0);
+                    
                     // 1. Progress bar as window element
-                    ui.add(ProgressBar::new(progress as f32)
-                        .show_percentage()
-                        .animate(should_animate));
+                    let bar = ProgressBar::new(progress as f32).animate(should_animate);
+                    ui.add(if total_bytes == 0 && progress < 1.0 { bar.text("Size pending") } else { bar.show_percentage() });
+                    
+                    // Only worth showing when there's more than one group to compare
+                    if ui_state.category_progress.len() > 1 {
+                        ui.add_space(4.0);
+                        Self::draw_category_breakdown(ui, &ui_state.category_progress);
+                    }
                     
                     // 2. Status bar directly below progress
                     ui.add_space(4.0);
@@ -75,6 +93,11 @@ impl TorrentDisplay {
                         ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
                             ui.label("Status: ");
                             ui.label(RichText::new(&state_str).color(state_color).strong());
+                            // Counts files, for mod sets where a few big ones dominate the percentage
+                            if files_total > 0 {
+                                ui.separator();
+                                ui.label(crate::ui::utils::format_file_count(files_complete, files_total));
+                            }
                         });
                     });
                     
@@ -85,9 +108,9 @@ impl TorrentDisplay {
                         // Could be added in a future enhancement
                         ui.label("Peers: -");
                         ui.separator();
-                        ui.label(format!("Download: {}", crate::ui::utils::format_speed(down_speed)));
+                        ui.label(format!("Download: {}", crate::ui::utils::format_speed(smoothed_down)));
                         ui.separator();
-                        ui.label(format!("Upload: {}", crate::ui::utils::format_speed(up_speed)));
+                        ui.label(format!("Upload: {}", crate::ui::utils::format_speed(smoothed_up)));
                     });
                     
                     ui.add_space(8.0);
@@ -106,6 +129,11 @@ impl TorrentDisplay {
                         if ui.selectable_label(is_files_selected, "Files").clicked() {
                             ui_state.torrent_tab_state = TorrentTab::Files;
                         }
+                        
+                        let is_diagnostics_selected = matches!(ui_state.torrent_tab_state, TorrentTab::Diagnostics);
+                        if ui.selectable_label(is_diagnostics_selected, "Diagnostics").clicked() {
+                            ui_state.torrent_tab_state = TorrentTab::Diagnostics;
+                        }
                     });
                     
                     ui.add_space(4.0);
@@ -121,19 +149,44 @@ impl TorrentDisplay {
                                 torrent_id, 
                                 total_bytes, 
                                 progress, 
-                                progress_bytes, 
+                                downloaded_since_reset, 
                                 uploaded_bytes, 
                                 down_speed, 
                                 up_speed, 
                                 &eta, 
+                                &done_by, 
                                 &file_name, 
                                 &info_hash, 
                                 &output_folder
                             );
+                            if let Some(usage) = ui_state.memory_usage {
+                                ui.add_space(4.0);
+                                Self::draw_memory_usage(ui, usage);
+                            }
+                            if ui_state.folder_changes.is_some() || ui_state.last_published.is_some() {
+                                ui.add_space(4.0);
+                                Self::draw_publishing(ui, ui_state);
+                            }
+                            
+                            ui.add_space(4.0);
+                            if ui.small_button("Reset Stats")
+                                .on_hover_text("Count uploaded/downloaded and ratio from now. librqbit's own counters are untouched.")
+                                .clicked()
+                            {
+                                action = Some(crate::ui::UiAction::ResetTorrentStats);
+                            }
                         },
                         TorrentTab::Files => {
                             // Files tab content
-                            Self::draw_files_content(ui, ui_state, &file_list);
+                            ui.label(RichText::new(ui_state.torrent_display_name()).strong());
+                            Self::draw_files_content(ui, ui_state, &file_list, &mut action);
+                            ui.add_space(4.0);
+                            Self::draw_export_row(ui, ui_state, &mut action);
+                        }
+                        TorrentTab::Diagnostics => {
+                            Self::draw_diagnostics(ui, ui_state, &mut action);
+                            ui.add_space(8.0);
+                            Self::draw_offline_verify(ui, ui_state, &mut action);
                         }
                     }
                 });
@@ -157,20 +210,51 @@ impl TorrentDisplay {
             Self::draw_no_torrent_message(ui, ui_state);
         }
         
-        None // No longer need to return actions for tab changes
+        action
+    }
+    
+    /// Draw the torrent's display name, with an inline editor for the label
+    fn draw_label_row(ui: &mut Ui, ui_state: &mut crate::ui::UiState, action: &mut Option<crate::ui::UiAction>) {
+        ui.horizontal(|ui| {
+            if let Some(edit) = &mut ui_state.label_edit {
+                let response = ui.add(
+                    egui::TextEdit::singleline(edit)
+                        .hint_text("Label (empty to use torrent name)")
+                        .desired_width(240.0)
+                );
+                let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if ui.button("Save").clicked() || submitted {
+                    *action = Some(crate::ui::UiAction::SetTorrentLabel(edit.clone()));
+                    ui_state.label_edit = None;
+                } else if ui.button("Cancel").clicked() {
+                    ui_state.label_edit = None;
+                }
+            } else {
+                ui.label(RichText::new(ui_state.torrent_display_name()).heading());
+                if ui.small_button("✏").on_hover_text("Rename").clicked() {
+                    ui_state.label_edit = Some(ui_state.torrent_label.clone().unwrap_or_default());
+                }
+                if ui_state.torrent_is_private {
+                    ui.label(RichText::new("🔒 Private").small().strong().color(Color32::from_rgb(100, 149, 237)))
+                        .on_hover_text("Peers come only from the tracker; DHT is off");
+                }
+            }
+        });
     }
 
     /// Draw the Details tab content
+    #[allow(clippy::too_many_arguments)]
     fn draw_details_content(
         ui: &mut Ui,
         torrent_id: usize,
         total_bytes: u64,
         progress: f64,
-        progress_bytes: u64,
+        downloaded_bytes: u64,
         uploaded_bytes: u64,
         download_speed: f64,
         upload_speed: f64,
         eta: &Option<String>,
+        done_by: &Option<String>,
         file_name: &Option<String>,
         info_hash: &Option<String>,
         output_folder: &Option<String>,
@@ -186,7 +270,7 @@ impl TorrentDisplay {
                 if let Some(hash) = info_hash {
                     Self::info_row(ui, "Info Hash", hash);
                 }
-                Self::info_row(ui, "Size", &crate::ui::utils::format_size(total_bytes));
+                Self::info_row(ui, "Size", &crate::ui::utils::format_total_size(total_bytes));
                 Self::info_row(ui, "Progress", &format!("{:.2}%", progress * 100.0));
                 if let Some(folder) = output_folder {
                     Self::info_row(ui, "Output Folder", folder);
@@ -199,8 +283,11 @@ impl TorrentDisplay {
         CollapsingHeader::new("Transfer Information")
             .default_open(true)
             .show(ui, |ui| {
-                Self::info_row(ui, "Downloaded", &crate::ui::utils::format_size(progress_bytes));
+                Self::info_row(ui, "Downloaded", &crate::ui::utils::format_size(downloaded_bytes));
                 Self::info_row(ui, "Uploaded", &crate::ui::utils::format_size(uploaded_bytes));
+                if downloaded_bytes > 0 {
+                    Self::info_row(ui, "Ratio", &format!("{:.2}", uploaded_bytes as f64 / downloaded_bytes as f64));
+                }
                 Self::info_row(ui, "Download Speed", &crate::ui::utils::format_speed(download_speed));
                 Self::info_row(ui, "Upload Speed", &crate::ui::utils::format_speed(upload_speed));
                 
@@ -208,18 +295,350 @@ impl TorrentDisplay {
                 if let Some(eta_str) = eta {
                     Self::info_row(ui, "ETA", eta_str);
                 }
+                if let Some(done_by) = done_by {
+                    Self::info_row(ui, "Done By", done_by);
+                }
+            });
+    }
+    
+    /// Draw memory use of ModSync and the system, with the write buffer cap
+    fn draw_memory_usage(ui: &mut Ui, usage: crate::sync::memory::MemoryUsage) {
+        use crate::ui::utils::format_size;
+        CollapsingHeader::new("Memory")
+            .default_open(true)
+            .show(ui, |ui| {
+                Self::info_row(ui, "ModSync", &format_size(usage.process_bytes));
+                let buffer = match usage.write_buffer_mb {
+                    Some(mb) => format!("up to {} MB", mb),
+                    None => "off, writing straight to disk".to_string(),
+                };
+                Self::info_row(ui, "Write Buffer", &buffer);
+                if usage.total_bytes > 0 {
+                    Self::info_row(ui, "System Available", &format!(
+                        "{} of {}",
+                        format_size(usage.available_bytes),
+                        format_size(usage.total_bytes)
+                    ));
+                }
+            });
+    }
+    
+    /// Draw publisher mode's pending edits and the last torrent it wrote
+    fn draw_publishing(ui: &mut Ui, ui_state: &crate::ui::UiState) {
+        use crate::ui::utils::format_size;
+        CollapsingHeader::new("Publishing")
+            .default_open(true)
+            .show(ui, |ui| {
+                if let Some(changes) = &ui_state.folder_changes {
+                    Self::info_row(ui, "Unpublished Edits", &changes.describe());
+                }
+                if let Some(published) = &ui_state.last_published {
+                    Self::info_row(ui, "Last Published", &format!(
+                        "{} files, {}",
+                        published.file_count,
+                        format_size(published.total_bytes)
+                    ));
+                    Self::info_row(ui, "Torrent File", &published.path.display().to_string());
+                    Self::info_row(ui, "Info Hash", &published.info_hash);
+                    ui.label(RichText::new("Upload the torrent file to your server so players get the update").small().color(Color32::GRAY));
+                }
             });
     }
     
     /// Draw the Files tab content
-    fn draw_files_content(ui: &mut Ui, ui_state: &mut crate::ui::UiState, file_list: &[(String, u64)]) {
+    fn draw_files_content(
+        ui: &mut Ui,
+        ui_state: &mut crate::ui::UiState,
+        file_list: &std::sync::Arc<Vec<crate::ui::state::TorrentFileEntry>>,
+        action: &mut Option<crate::ui::UiAction>,
+    ) {
+        Self::draw_spot_check_result(ui, ui_state);
         if !file_list.is_empty() {
-            ui_state.file_tree.ui(ui, file_list);
+            if let Some(file_action) = ui_state.file_tree.ui(ui, file_list) {
+                *action = Some(file_action);
+            }
         } else {
             ui.label("No file information available.");
         }
     }
     
+    /// Outcome of the last "Verify this file/folder", or a spinner while it runs
+    fn draw_spot_check_result(ui: &mut Ui, ui_state: &crate::ui::UiState) {
+        if ui_state.subset_verify_running {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label("Verifying selected files...");
+            });
+            return;
+        }
+        match &ui_state.subset_verify {
+            Some(Ok(report)) if report.bad_files.is_empty() => {
+                ui.label(RichText::new(format!(
+                    "✔ {} files OK ({} pieces checked)",
+                    report.files_checked, report.pieces_checked
+                )).color(Color32::GREEN));
+            }
+            Some(Ok(report)) => {
+                ui.label(RichText::new(format!(
+                    "✖ {} of {} files failed the check, run Verify Local Files to repair:",
+                    report.bad_files.len(), report.files_checked
+                )).color(Color32::RED));
+                for file in report.bad_files.iter().take(10) {
+                    ui.label(RichText::new(file.display().to_string()).small());
+                }
+                if report.bad_files.len() > 10 {
+                    ui.label(RichText::new(format!("... and {} more", report.bad_files.len() - 10)).small());
+                }
+            }
+            Some(Err(e)) => {
+                ui.label(RichText::new(format!("Verify failed: {}", e)).color(Color32::RED));
+            }
+            None => {
+                ui.label(RichText::new("Right-click a file or folder to verify just those files").small().color(Color32::GRAY));
+            }
+        }
+    }
+    
+    /// Check of a folder against any .torrent file, without the network
+    fn draw_offline_verify(ui: &mut Ui, ui_state: &mut crate::ui::UiState, action: &mut Option<crate::ui::UiAction>) {
+        ui.label(RichText::new("Verify against torrent file").strong());
+        egui::Grid::new("offline_verify_paths").num_columns(2).show(ui, |ui| {
+            ui.label("Torrent file:");
+            ui.add(egui::TextEdit::singleline(&mut ui_state.offline_torrent_str).hint_text("Path, or drop a .torrent on the window"));
+            ui.end_row();
+            ui.label("Folder:");
+            ui.add(egui::TextEdit::singleline(&mut ui_state.offline_folder_str).hint_text("Download folder"));
+            ui.end_row();
+        });
+        ui.horizontal(|ui| {
+            let torrent = ui_state.offline_torrent_str.trim();
+            let ready = !torrent.is_empty() && !ui_state.offline_verify_running;
+            let button = ui.add_enabled(ready, egui::Button::new("Verify"))
+                .on_hover_text("Hash-check the folder against the torrent. Nothing is downloaded and no tracker or peer is contacted.");
+            if button.clicked() {
+                let folder = ui_state.offline_folder_str.trim();
+                *action = Some(crate::ui::UiAction::VerifyAgainstTorrent {
+                    torrent: std::path::PathBuf::from(torrent),
+                    folder: (!folder.is_empty()).then(|| std::path::PathBuf::from(folder)),
+                });
+            }
+            if ui_state.offline_verify_running {
+                ui.spinner();
+                ui.label("Hashing files...");
+            }
+        });
+        match &ui_state.offline_verify {
+            Some(Ok(report)) if report.is_complete() => {
+                ui.label(RichText::new(format!(
+                    "✔ {}: all {} files match ({})",
+                    report.torrent_name, report.files, crate::ui::utils::format_size(report.total_bytes)
+                )).color(Color32::GREEN));
+            }
+            Some(Ok(report)) => {
+                ui.label(RichText::new(format!(
+                    "✖ {}: {} missing and {} changed of {} files",
+                    report.torrent_name, report.missing.len(), report.mismatched.len(), report.files
+                )).color(Color32::RED));
+                let listed = report.missing.iter().map(|file| (file, "missing")).chain(report.mismatched.iter().map(|file| (file, "changed")));
+                for (file, problem) in listed.clone().take(10) {
+                    ui.label(RichText::new(format!("{} ({})", file.display(), problem)).small());
+                }
+                let count = listed.count();
+                if count > 10 {
+                    ui.label(RichText::new(format!("... and {} more", count - 10)).small());
+                }
+            }
+            Some(Err(e)) => {
+                ui.label(RichText::new(format!("Verify failed: {}", e)).color(Color32::RED));
+            }
+            None => {}
+        }
+    }
+    
+    /// Draw the running embedded servers and the tracker reachability test
+    fn draw_diagnostics(ui: &mut Ui, ui_state: &crate::ui::UiState, action: &mut Option<crate::ui::UiAction>) {
+        for (name, url) in &ui_state.servers {
+            Self::info_row(ui, name, url);
+        }
+        if let (Some(seq), Some(at)) = (ui_state.event_sequence.last(), ui_state.last_event_at) {
+            let mut events = format!("#{} at {}", seq, at.format("%H:%M:%S%.3f"));
+            if ui_state.event_sequence.missed > 0 {
+                events.push_str(&format!(", {} missed", ui_state.event_sequence.missed));
+            }
+            Self::info_row(ui, "Last Sync Event", &events);
+        }
+        Self::draw_listen_port(ui, ui_state, action);
+        ui.add_space(4.0);
+        ui.horizontal(|ui| {
+            let button = ui.add_enabled(!ui_state.tracker_test_running, egui::Button::new("Test Trackers"))
+                .on_hover_text("Ask each tracker for its seeder and leecher counts, without joining the swarm");
+            if button.clicked() {
+                *action = Some(crate::ui::UiAction::TestTrackers);
+            }
+            if ui_state.tracker_test_running {
+                ui.spinner();
+                ui.label("Contacting trackers...");
+            }
+        });
+        ui.add_space(4.0);
+        
+        match &ui_state.tracker_test {
+            Some(Ok(reports)) if reports.is_empty() => {
+                ui.label("This torrent lists no trackers; peers can only be found through DHT.");
+            }
+            Some(Ok(reports)) => {
+                egui::Grid::new("tracker_test_results").num_columns(4).striped(true).show(ui, |ui| {
+                    ui.label(RichText::new("Tracker").strong());
+                    ui.label(RichText::new("Seeders").strong());
+                    ui.label(RichText::new("Leechers").strong());
+                    ui.label(RichText::new("Latency").strong());
+                    ui.end_row();
+                    for report in reports {
+                        let count = |value: Option<u64>| value.map_or("-".to_string(), |v| v.to_string());
+                        match &report.result {
+                            Ok(counts) => {
+                                ui.label(RichText::new(format!("✔ {}", report.url)).color(Color32::GREEN));
+                                ui.label(count(counts.seeders));
+                                ui.label(count(counts.leechers));
+                            }
+                            Err(e) => {
+                                ui.label(RichText::new(format!("✖ {}", report.url)).color(Color32::RED));
+                                ui.label(RichText::new(e).small().color(Color32::RED));
+                                ui.label("");
+                            }
+                        }
+                        ui.label(format!("{} ms", report.latency_ms));
+                        ui.end_row();
+                    }
+                });
+                if reports.iter().all(|report| report.result.is_err()) {
+                    ui.add_space(4.0);
+                    ui.label(RichText::new(
+                        "No tracker answered. Check your firewall, VPN or proxy; other sites working points at the trackers being down."
+                    ).small().color(Color32::YELLOW));
+                }
+            }
+            Some(Err(e)) => {
+                ui.label(RichText::new(format!("Tracker test failed: {}", e)).color(Color32::RED));
+            }
+            None if !ui_state.tracker_test_running => {
+                ui.label(RichText::new("Checks whether this machine can reach the torrent's trackers").small().color(Color32::GRAY));
+            }
+            None => {}
+        }
+    }
+    
+    /// Draw the listen port with whether peers can reach it, and the opt-in outside check
+    fn draw_listen_port(ui: &mut Ui, ui_state: &crate::ui::UiState, action: &mut Option<crate::ui::UiAction>) {
+        let Some(port) = ui_state.listen_status.port else {
+            Self::info_row(ui, "Listen Port", "Not listening (incoming connections are off)");
+            return;
+        };
+        let checked = match &ui_state.port_check {
+            Some(Ok(check)) => Some(check.open),
+            _ => None,
+        };
+        let (indicator, color, hint) = if ui_state.incoming_seen || checked == Some(true) {
+            ("● Open", Color32::GREEN, "Peers can connect to this machine")
+        } else if checked == Some(false) {
+            ("● Closed", Color32::RED, "Peers can't connect in. Forward the port on your router or allow it through the firewall; downloads may be slower")
+        } else {
+            ("● Not confirmed", Color32::GRAY, "No peer has connected in yet. That's normal in a quiet swarm, but can also mean a firewall or router blocks the port")
+        };
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Listen Port:").strong());
+            ui.label(port.to_string());
+            ui.label(RichText::new(indicator).color(color)).on_hover_text(hint);
+            if ui_state.listen_status.incoming_peers > 0 {
+                ui.label(RichText::new(format!("{} peers connected in", ui_state.listen_status.incoming_peers)).small());
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui_state.port_check_running {
+                ui.spinner();
+                ui.label("Checking port...");
+            } else if ui_state.port_check_enabled {
+                let button = ui.button("Check Port")
+                    .on_hover_text("Ask the port_check_url service whether the port accepts connections from outside");
+                if button.clicked() {
+                    *action = Some(crate::ui::UiAction::CheckPort);
+                }
+            } else {
+                ui.label(RichText::new("Set port_check_url in config.toml to test the port from outside").small().color(Color32::GRAY));
+            }
+            if let Some(Err(e)) = &ui_state.port_check {
+                ui.label(RichText::new(format!("Port check failed: {}", e)).small().color(Color32::RED));
+            }
+        });
+    }
+    
+    /// Draw the path and format pickers for exporting the file list
+    fn draw_export_row(ui: &mut Ui, ui_state: &mut crate::ui::UiState, action: &mut Option<crate::ui::UiAction>) {
+        use crate::sync::file_list::ExportFormat;
+        ui.horizontal(|ui| {
+            ui.label("Export file list:");
+            ui.add(
+                egui::TextEdit::singleline(&mut ui_state.export_path_str)
+                    .hint_text("e.g. C:\\modsync-files.csv")
+                    .desired_width(200.0)
+            );
+            ui.radio_value(&mut ui_state.export_format, ExportFormat::Csv, "CSV");
+            ui.radio_value(&mut ui_state.export_format, ExportFormat::Json, "JSON");
+            let has_path = !ui_state.export_path_str.trim().is_empty();
+            if ui.add_enabled(has_path, egui::Button::new("Export")).clicked() {
+                *action = Some(crate::ui::UiAction::ExportFileList);
+            }
+        });
+        if let Some(result) = &ui_state.export_result {
+            ui.label(RichText::new(result).small().color(Color32::GRAY));
+        }
+    }
+    
+    /// Draw a stacked bar of each category's share of the torrent, with the downloaded
+    /// part of each segment solid, then a legend row per category
+    fn draw_category_breakdown(ui: &mut Ui, categories: &[crate::ui::categories::CategoryProgress]) {
+        const PALETTE: [Color32; 6] = [
+            Color32::from_rgb(66, 133, 244),
+            Color32::from_rgb(219, 68, 55),
+            Color32::from_rgb(244, 180, 0),
+            Color32::from_rgb(15, 157, 88),
+            Color32::from_rgb(171, 71, 188),
+            Color32::from_rgb(0, 172, 193),
+        ];
+        
+        CollapsingHeader::new("Progress by Category")
+            .default_open(true)
+            .show(ui, |ui| {
+                let total: u64 = categories.iter().map(|c| c.total_bytes).sum();
+                let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), 14.0), egui::Sense::hover());
+                let painter = ui.painter_at(rect);
+                let mut x = rect.left();
+                for (i, category) in categories.iter().enumerate() {
+                    let color = PALETTE[i % PALETTE.len()];
+                    let width = rect.width() * category.total_bytes as f32 / total.max(1) as f32;
+                    let segment = egui::Rect::from_min_size(egui::pos2(x, rect.top()), egui::vec2(width, rect.height()));
+                    let done = egui::Rect::from_min_size(segment.min, egui::vec2(width * category.fraction(), rect.height()));
+                    painter.rect_filled(segment, 0.0, color.gamma_multiply(0.25));
+                    painter.rect_filled(done, 0.0, color);
+                    x += width;
+                }
+                
+                ui.add_space(2.0);
+                for (i, category) in categories.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new("■").color(PALETTE[i % PALETTE.len()]));
+                        ui.label(&category.name);
+                        ui.label(RichText::new(format!(
+                            "{:.0}% ({} of {})",
+                            category.fraction() * 100.0,
+                            crate::ui::utils::format_size(category.progress_bytes),
+                            crate::ui::utils::format_size(category.total_bytes)
+                        )).weak());
+                    });
+                }
+            });
+    }
+    
     /// Helper to display a labeled info row
     fn info_row(ui: &mut Ui, label: &str, value: &str) {
         ui.horizontal(|ui| {
@@ -1,16 +1,21 @@
 // src/ui/torrent_file_tree.rs
-use eframe::egui::{self, Ui};
-use std::collections::BTreeMap;
-use std::path::{Component, Path};
+use eframe::egui::{self, RichText, Ui};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use tracing::warn;
 
-#[derive(Default)]
+use crate::ui::state::TorrentFileEntry;
+use crate::ui::UiAction;
+
+#[derive(Default, Debug, Clone)]
 struct TreeNode {
     children: BTreeMap<String, TreeNode>,
-    file_size: Option<u64>, // Store file size if it's a file node
+    file: Option<usize>, // Index into the file list if it's a file node
 }
 
 impl TreeNode {
-    fn insert(&mut self, path: &Path, size: u64) {
+    fn insert(&mut self, path: &Path, index: usize) {
         let mut current_node = self;
         for component in path.components() {
             if let Component::Normal(name_osstr) = component {
@@ -19,87 +24,183 @@ impl TreeNode {
             } else {
                 // Handle other Component types if necessary (RootDir, CurDir, ParentDir)
                 // For typical torrent paths, Normal should suffice.
-                eprintln!("Warning: Unexpected path component type: {:?}", component);
+                warn!("Warning: Unexpected path component type: {:?}", component);
                 return; // Skip this file if path is unusual
             }
         }
         // If we've traversed all components, this node represents the file itself
-        current_node.file_size = Some(size);
+        current_node.file = Some(index);
     }
 
-    fn build_tree(files: &[(String, u64)]) -> TreeNode {
+    /// Whether the '/'-separated `path` is a folder in this tree
+    fn has_dir(&self, path: &str) -> bool {
+        let mut node = self;
+        for name in path.split('/') {
+            match node.children.get(name) {
+                Some(child) => node = child,
+                None => return false,
+            }
+        }
+        !node.children.is_empty()
+    }
+
+    fn build_tree(files: &[TorrentFileEntry]) -> TreeNode {
         let mut root = TreeNode::default();
-        for (name, size) in files {
-            root.insert(Path::new(name), *size);
+        for (index, file) in files.iter().enumerate() {
+            root.insert(Path::new(&file.path), index);
         }
         root
     }
 }
 
+/// One line of the tree as currently expanded
+#[derive(Debug, Clone, PartialEq)]
+enum Row {
+    Dir { path: String, name: String, depth: usize, expanded: bool },
+    File { index: usize, name: String, depth: usize },
+}
+
+/// The rows shown with the directories in `expanded` open, in display order
+fn visible_rows(node: &TreeNode, prefix: &str, depth: usize, expanded: &HashSet<String>, rows: &mut Vec<Row>) {
+    // BTreeMap keeps children sorted alphabetically
+    for (name, child) in &node.children {
+        let path = if prefix.is_empty() { name.clone() } else { format!("{}/{}", prefix, name) };
+        if let (Some(index), true) = (child.file, child.children.is_empty()) {
+            rows.push(Row::File { index, name: name.clone(), depth });
+        } else if !child.children.is_empty() {
+            let is_expanded = expanded.contains(&path);
+            rows.push(Row::Dir { path: path.clone(), name: name.clone(), depth, expanded: is_expanded });
+            if is_expanded {
+                visible_rows(child, &path, depth + 1, expanded, rows);
+            }
+        }
+    }
+}
+
+/// Files tab tree. The tree is only rebuilt when the file list changes and only the rows
+/// on screen are drawn, so torrents with tens of thousands of files stay responsive.
 #[derive(Default, Debug, Clone)]
 pub struct TorrentFileTree {
-    // Potentially add state here if needed, e.g., expanded state cache
-    // For now, egui's collapsing header state should be sufficient.
+    tree: Option<(Arc<Vec<TorrentFileEntry>>, TreeNode)>, // Built from this file list
+    expanded: HashSet<String>, // Open directories, by '/'-separated path
+    rows: Option<Vec<Row>>, // Visible rows, None when the tree or expansion changed
 }
 
 impl TorrentFileTree {
-    pub fn ui(&mut self, ui: &mut Ui, files: &[(String, u64)]) {
-        let root_node = TreeNode::build_tree(files);
+    /// A tree that opens with the folders saved last time
+    pub fn with_expanded(expanded: &[String]) -> Self {
+        Self { expanded: expanded.iter().cloned().collect(), ..Default::default() }
+    }
 
-        // Add a scroll area
+    /// Open folders, sorted, as saved in the config
+    fn expanded_list(&self) -> Vec<String> {
+        let mut expanded: Vec<String> = self.expanded.iter().cloned().collect();
+        expanded.sort();
+        expanded
+    }
+
+    /// Draw the tree, returning the action of a clicked pause/resume button, or the
+    /// open folders to save when they changed
+    pub fn ui(&mut self, ui: &mut Ui, files: &Arc<Vec<TorrentFileEntry>>) -> Option<UiAction> {
+        let mut action = None;
+        if !self.tree.as_ref().is_some_and(|(source, _)| Arc::ptr_eq(source, files)) {
+            let root = TreeNode::build_tree(files);
+            // Folders an update removed are forgotten rather than kept forever
+            let before = self.expanded.len();
+            self.expanded.retain(|path| root.has_dir(path));
+            if self.expanded.len() != before {
+                action = Some(UiAction::SetExpandedFolders(self.expanded_list()));
+            }
+            self.tree = Some((files.clone(), root));
+            self.rows = None;
+        }
+        let Some((_, root_node)) = &self.tree else {
+            return None;
+        };
+        let rows = self.rows.get_or_insert_with(|| {
+            let mut rows = Vec::new();
+            match (root_node.children.is_empty(), root_node.file) {
+                // A single-file torrent
+                (true, Some(index)) => {
+                    let path = &files[index].path;
+                    let name = Path::new(path).file_name().map_or(path.clone(), |os| os.to_string_lossy().into_owned());
+                    rows.push(Row::File { index, name, depth: 0 });
+                }
+                _ => visible_rows(root_node, "", 0, &self.expanded, &mut rows),
+            }
+            rows
+        });
+
+        let mut toggled = None;
+        let row_height = ui.spacing().interact_size.y;
+        let indent = ui.spacing().indent;
         egui::ScrollArea::vertical()
             .auto_shrink([false, false]) // Prevent shrinking
-            .show(ui, |ui| {
-                // Iterate through the top-level children directly
-                // Sort top-level items alphabetically
-                let mut top_level_children: Vec<_> = root_node.children.iter().collect();
-                top_level_children.sort_by_key(|(k, _)| *k);
-
-                if top_level_children.is_empty() && root_node.file_size.is_some() {
-                    // Handle the case of a single-file torrent
-                     ui.label(format!(
-                        "{} ({})",
-                        // Extract filename from the first (and only) file entry if possible
-                        files.first().map_or("File", |(name, _)| Path::new(name).file_name().map_or(name.as_str(), |os| os.to_str().unwrap_or(name.as_str()))),
-                        format_bytes(root_node.file_size.unwrap_or(0))
-                    ));
-                } else {
-                    for (name, node) in top_level_children {
-                         self.render_tree_node(ui, node, name);
+            .show_rows(ui, row_height, rows.len(), |ui, range| {
+                for row in &rows[range] {
+                    match row {
+                        Row::Dir { path, name, depth, expanded } => {
+                            ui.horizontal(|ui| {
+                                ui.add_space(indent * *depth as f32);
+                                let arrow = if *expanded { "▼" } else { "▶" };
+                                let response = ui.selectable_label(false, format!("{} {}", arrow, name));
+                                if response.clicked() {
+                                    toggled = Some(path.clone());
+                                }
+                                response.context_menu(|ui| {
+                                    if ui.button("Verify this folder").clicked() {
+                                        let prefix = format!("{}/", path);
+                                        let files_in_dir = files.iter()
+                                            .filter(|file| file.path.starts_with(&prefix))
+                                            .map(|file| PathBuf::from(&file.path))
+                                            .collect();
+                                        action = Some(UiAction::VerifySubset(files_in_dir));
+                                        ui.close_menu();
+                                    }
+                                });
+                            });
+                        }
+                        Row::File { index, name, depth } => {
+                            Self::render_file_row(ui, &files[*index], name, *depth as f32 * indent, &mut action);
+                        }
                     }
                 }
             });
-    }
 
-    fn render_tree_node(&mut self, ui: &mut Ui, node: &TreeNode, name: &str) {
-        // Check if it's a file node (has size, no children)
-        if node.file_size.is_some() && node.children.is_empty() {
-            ui.label(format!(
-                "{} ({})",
-                name,
-                format_bytes(node.file_size.unwrap_or(0))
-            ));
-        } 
-        // Check if it's a directory node (has children)
-        else if !node.children.is_empty() {
-            // It's a directory
-            let default_open = false; // Keep directories closed by default
-            egui::CollapsingHeader::new(name)
-                .default_open(default_open)
-                .show(ui, |ui| {
-                    // Sort children alphabetically for consistent display
-                    let mut children: Vec<_> = node.children.iter().collect();
-                    children.sort_by_key(|(k, _)| *k);
-
-                    for (child_name, child_node) in children {
-                        // Render child node recursively
-                        self.render_tree_node(ui, child_node, child_name);
-                    }
-                });
+        if let Some(path) = toggled {
+            if !self.expanded.remove(&path) {
+                self.expanded.insert(path);
+            }
+            self.rows = None;
+            action = action.or(Some(UiAction::SetExpandedFolders(self.expanded_list())));
         }
-        // else: Node might be an intermediate path component without a size 
-        // and potentially no children listed explicitly if structure is weird.
-        // In standard torrents, this shouldn't happen for leaf nodes.
+        action
+    }
+
+    /// A file's name and size, with a button to pause or resume downloading it
+    fn render_file_row(ui: &mut Ui, file: &TorrentFileEntry, name: &str, indent: f32, action: &mut Option<UiAction>) {
+        ui.horizontal(|ui| {
+            ui.add_space(indent);
+            let text = format!("{} ({})", name, format_bytes(file.size));
+            let label = if file.paused { RichText::new(format!("{} - paused", text)).weak() } else { RichText::new(text) };
+            ui.add(egui::Label::new(label).sense(egui::Sense::click())).context_menu(|ui| {
+                if !file.complete && !file.paused && ui.button("Download this first").clicked() {
+                    *action = Some(UiAction::PrioritizeFile(file.path.clone()));
+                    ui.close_menu();
+                }
+                if ui.button("Verify this file").clicked() {
+                    *action = Some(UiAction::VerifySubset(vec![PathBuf::from(&file.path)]));
+                    ui.close_menu();
+                }
+            });
+            if file.paused {
+                if ui.small_button("▶").on_hover_text("Resume downloading this file").clicked() {
+                    *action = Some(UiAction::SetFilePaused { path: file.path.clone(), paused: false });
+                }
+            } else if !file.complete && ui.small_button("⏸").on_hover_text("Pause downloading this file").clicked() {
+                *action = Some(UiAction::SetFilePaused { path: file.path.clone(), paused: true });
+            }
+        });
     }
 }
 
@@ -115,4 +216,101 @@ fn format_bytes(bytes: u64) -> String {
     } else {
         format!("{:.1} {}", (bytes as f64) / (1024.0_f64.powi(i)), sizes[i as usize])
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A large mod pack: 50 mods of 10 folders with 100 files each
+    fn large_file_list() -> Vec<TorrentFileEntry> {
+        let mut files = Vec::new();
+        for m in 0..50 {
+            for d in 0..10 {
+                for f in 0..100 {
+                    files.push(TorrentFileEntry {
+                        path: format!("@mod{:02}/addons{}/file{:03}.pbo", m, d, f),
+                        size: 1024,
+                        paused: false,
+                        complete: f % 2 == 0,
+                    });
+                }
+            }
+        }
+        files
+    }
+
+    #[test]
+    fn test_rows_follow_expansion() {
+        let files = large_file_list();
+        let root = TreeNode::build_tree(&files);
+        let mut rows = Vec::new();
+        visible_rows(&root, "", 0, &HashSet::new(), &mut rows);
+        assert_eq!(rows.len(), 50); // Only the collapsed top-level folders
+
+        let expanded = HashSet::from(["@mod03".to_string(), "@mod03/addons7".to_string()]);
+        rows.clear();
+        visible_rows(&root, "", 0, &expanded, &mut rows);
+        assert_eq!(rows.len(), 50 + 10 + 100);
+        assert_eq!(rows[4], Row::Dir { path: "@mod03/addons0".to_string(), name: "addons0".to_string(), depth: 1, expanded: false });
+        assert!(matches!(&rows[12], Row::File { depth: 2, name, .. } if name == "file000.pbo"));
+    }
+
+    #[test]
+    fn test_saved_folders_that_disappeared_are_dropped() {
+        let files = large_file_list();
+        let root = TreeNode::build_tree(&files);
+        assert!(root.has_dir("@mod03/addons7"));
+        assert!(!root.has_dir("@mod03/addons7/file001.pbo"), "files aren't folders");
+        assert!(!root.has_dir("@gone"));
+
+        let mut tree = TorrentFileTree::with_expanded(&["@mod03".to_string(), "@gone/addons".to_string()]);
+        tree.expanded.retain(|path| root.has_dir(path));
+        assert_eq!(tree.expanded_list(), vec!["@mod03".to_string()]);
+    }
+
+    #[test]
+    fn test_fifty_thousand_files_fully_expanded() {
+        let files = large_file_list();
+        assert_eq!(files.len(), 50_000);
+        let root = TreeNode::build_tree(&files);
+        // Collapsed folders aren't visited at all
+        let mut rows = Vec::new();
+        visible_rows(&root, "", 0, &HashSet::new(), &mut rows);
+        assert_eq!(rows.len(), 50);
+
+        let mut expanded = HashSet::new();
+        for m in 0..50 {
+            expanded.insert(format!("@mod{:02}", m));
+            for d in 0..10 {
+                expanded.insert(format!("@mod{:02}/addons{}", m, d));
+            }
+        }
+        rows.clear();
+        visible_rows(&root, "", 0, &expanded, &mut rows);
+        assert_eq!(rows.len(), 50 + 500 + 50_000);
+        // Each file is visited once
+        let mut seen = HashSet::new();
+        for row in &rows {
+            if let Row::File { index, .. } = row {
+                assert!(seen.insert(*index), "file {} listed twice", index);
+            }
+        }
+        assert_eq!(seen.len(), 50_000);
+
+        // Done once per file list change or click, never per frame
+        let files = Arc::new(files);
+        let expanded: Vec<String> = expanded.into_iter().collect();
+        let mut tree = TorrentFileTree::with_expanded(&expanded);
+        let ctx = egui::Context::default();
+        let mut built = Vec::new();
+        for _ in 0..3 {
+            let _ = ctx.run(egui::RawInput::default(), |ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| tree.ui(ui, &files));
+            });
+            built.push(tree.rows.as_ref().map(|rows| (rows.as_ptr(), rows.len())));
+        }
+        assert_eq!(built[0].map(|(_, len)| len), Some(50 + 500 + 50_000));
+        assert!(built.iter().all(|rows| *rows == built[0]), "rows were rebuilt between frames");
+    }
+}
@@ -3,6 +3,8 @@
 
 use eframe::egui::Color32;
 
+use crate::i18n::{tr, tr_fmt};
+
 /// Helper function to format speed in bytes/sec to KB/s or MB/s
 pub fn format_speed(bytes_per_sec: f64) -> String {
     if bytes_per_sec < 1024.0 {
@@ -27,6 +29,70 @@ pub fn format_size(bytes: u64) -> String {
     }
 }
 
+/// A torrent's total size, or "size pending" while librqbit reports it as 0 (not known yet)
+pub fn format_total_size(total_bytes: u64) -> String {
+    if total_bytes == 0 {
+        tr("status.size_pending").to_string()
+    } else {
+        format_size(total_bytes)
+    }
+}
+
+/// Share of the torrent done, from 0 to 1. An unknown (zero) total counts as nothing
+/// done, unless librqbit says the torrent is finished.
+pub fn progress_fraction(progress_bytes: u64, total_bytes: u64, finished: bool) -> f64 {
+    if total_bytes > 0 {
+        (progress_bytes as f64 / total_bytes as f64).min(1.0)
+    } else if finished {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Bytes per second of a librqbit speed, whose `mbps` is in MiB/s
+pub fn speed_bytes_per_sec(mbps: f64) -> f64 {
+    mbps * 1_048_576.0
+}
+
+/// Speed limit range covered by the limit sliders, in KB/s
+const SLIDER_MIN_KBPS: f64 = 10.0;
+const SLIDER_MAX_KBPS: f64 = 100_000.0;
+/// Slider positions past this are the "Unlimited" end stop
+const SLIDER_LIMITED_END: f64 = 0.95;
+
+/// Slider position (0..=1) for a speed limit in KB/s, on a log scale so that both
+/// 50 KB/s and 50 MB/s are easy to reach. No limit sits at the right end stop.
+pub fn limit_to_slider(kbps: Option<u64>) -> f64 {
+    match kbps {
+        None | Some(0) => 1.0,
+        Some(kbps) => {
+            let kbps = (kbps as f64).clamp(SLIDER_MIN_KBPS, SLIDER_MAX_KBPS);
+            (kbps / SLIDER_MIN_KBPS).log10() / (SLIDER_MAX_KBPS / SLIDER_MIN_KBPS).log10() * SLIDER_LIMITED_END
+        }
+    }
+}
+
+/// Speed limit in KB/s for a slider position, rounded to two significant digits
+pub fn slider_to_limit(position: f64) -> Option<u64> {
+    if position > SLIDER_LIMITED_END {
+        return None;
+    }
+    let fraction = position.max(0.0) / SLIDER_LIMITED_END;
+    let kbps = SLIDER_MIN_KBPS * (SLIDER_MAX_KBPS / SLIDER_MIN_KBPS).powf(fraction);
+    let step = 10f64.powi(kbps.log10().floor() as i32 - 1);
+    Some(((kbps / step).round() * step) as u64)
+}
+
+/// File-count progress, e.g. "Downloading file 43 of 512" or "512 of 512 files"
+pub fn format_file_count(complete: usize, total: usize) -> String {
+    if complete >= total {
+        tr_fmt("status.files_complete", &[("total", &total)])
+    } else {
+        tr_fmt("status.file_progress", &[("current", &(complete + 1)), ("total", &total)])
+    }
+}
+
 /// Enum representing the overall sync task status
 #[derive(Debug, Clone, PartialEq)]
 pub enum SyncStatus {
@@ -34,33 +100,130 @@ pub enum SyncStatus {
     CheckingRemote,        // Checking the remote torrent for updates
     UpdatingTorrent,       // Updating/replacing the managed torrent
     CheckingLocal,         // Verifying local files against torrent manifest
+    Checking { percent: u8 }, // librqbit hashing existing files after (re)adding the torrent
+    Verifying { percent: u8 }, // Hashing every piece of the finished torrent (verify_after_complete)
     LocalActive,           // Local torrent is active and seeding/downloading
     RemoteChanged,         // Remote torrent has changed, update available
+    LowDiskSpace,          // Seeding paused, download volume below free space minimum
+    PausedForGame,         // Torrents paused while the watched game process runs
+    PausedLowBattery,      // Torrents paused, on battery below pause_on_battery_below
+    BindInterfaceDown,     // Torrents paused while the bound network interface is down
+    SeedGoalMet,           // Seeding paused, the session reached global_seed_goal
+    Stopped,               // Downloading stopped by the time limit until resumed
+    Staging { percent: u8 }, // Update downloading into the staging folder before the swap
+    Allocating { percent: u8 }, // Reserving file space before the download starts (preallocate_files)
+    FetchingHttp { percent: u8 }, // Unfinished files downloading over HTTP, the swarm has no peers (http_fallback_base_url)
+    Extracting { percent: u8 }, // Unpacking the finished torrent's archives (extract_archives)
+    Snoozed { until: chrono::DateTime<chrono::Local> }, // Automatic update checks held until then
     Error(String),         // Error in the sync process
 }
 
 impl SyncStatus {
+    /// Whether a sync step is running that CancelSync can interrupt
+    pub fn is_in_progress(&self) -> bool {
+        matches!(
+            self,
+            SyncStatus::CheckingRemote
+                | SyncStatus::UpdatingTorrent
+                | SyncStatus::CheckingLocal
+                | SyncStatus::Checking { .. }
+        )
+    }
+    
     pub fn display_color(&self) -> Color32 {
         match self {
             SyncStatus::Idle => Color32::GRAY,
             SyncStatus::CheckingRemote => Color32::YELLOW,
             SyncStatus::UpdatingTorrent => Color32::BLUE,
             SyncStatus::CheckingLocal => Color32::LIGHT_BLUE,
+            SyncStatus::Checking { .. } => Color32::YELLOW,
+            SyncStatus::Verifying { .. } => Color32::LIGHT_BLUE,
             SyncStatus::LocalActive => Color32::GREEN,
             SyncStatus::RemoteChanged => Color32::GOLD,
+            SyncStatus::LowDiskSpace => Color32::ORANGE,
+            SyncStatus::PausedForGame => Color32::LIGHT_BLUE,
+            SyncStatus::PausedLowBattery => Color32::YELLOW,
+            SyncStatus::BindInterfaceDown => Color32::ORANGE,
+            SyncStatus::SeedGoalMet => Color32::GREEN,
+            SyncStatus::Stopped => Color32::GRAY,
+            SyncStatus::Staging { .. } => Color32::BLUE,
+            SyncStatus::Allocating { .. } => Color32::YELLOW,
+            SyncStatus::FetchingHttp { .. } => Color32::BLUE,
+            SyncStatus::Extracting { .. } => Color32::LIGHT_BLUE,
+            SyncStatus::Snoozed { .. } => Color32::GRAY,
             SyncStatus::Error(_) => Color32::RED,
         }
     }
     
     pub fn display_text(&self) -> String {
         match self {
-            SyncStatus::Idle => "Sync: Idle".to_string(),
-            SyncStatus::CheckingRemote => "Sync: Checking Remote".to_string(),
-            SyncStatus::UpdatingTorrent => "Sync: Updating Torrent".to_string(),
-            SyncStatus::CheckingLocal => "Sync: Verifying Local Files".to_string(),
-            SyncStatus::LocalActive => "Local: Active & Seeding".to_string(),
-            SyncStatus::RemoteChanged => "Remote: Update Available".to_string(),
-            SyncStatus::Error(err) => format!("Sync Error: {}", err),
+            SyncStatus::Idle => tr("status.idle").to_string(),
+            SyncStatus::CheckingRemote => tr("status.checking_remote").to_string(),
+            SyncStatus::UpdatingTorrent => tr("status.updating_torrent").to_string(),
+            SyncStatus::CheckingLocal => tr("status.checking_local").to_string(),
+            SyncStatus::Checking { percent } => tr_fmt("status.checking", &[("percent", percent)]),
+            SyncStatus::Verifying { percent } => tr_fmt("status.verifying", &[("percent", percent)]),
+            SyncStatus::LocalActive => tr("status.local_active").to_string(),
+            SyncStatus::RemoteChanged => tr("status.remote_changed").to_string(),
+            SyncStatus::LowDiskSpace => tr("status.low_disk_space").to_string(),
+            SyncStatus::PausedForGame => tr("status.paused_for_game").to_string(),
+            SyncStatus::PausedLowBattery => tr("status.paused_low_battery").to_string(),
+            SyncStatus::BindInterfaceDown => tr("status.bind_interface_down").to_string(),
+            SyncStatus::SeedGoalMet => tr("status.seed_goal_met").to_string(),
+            SyncStatus::Stopped => tr("status.stopped").to_string(),
+            SyncStatus::Staging { percent } => tr_fmt("status.staging", &[("percent", percent)]),
+            SyncStatus::Allocating { percent } => tr_fmt("status.allocating", &[("percent", percent)]),
+            SyncStatus::FetchingHttp { percent } => tr_fmt("status.fetching_http", &[("percent", percent)]),
+            SyncStatus::Extracting { percent } => tr_fmt("status.extracting", &[("percent", percent)]),
+            SyncStatus::Snoozed { until } => {
+                // Only name the day when it isn't today
+                let format = if until.date_naive() == chrono::Local::now().date_naive() { "%H:%M" } else { "%a %H:%M" };
+                tr_fmt("status.snoozed", &[("until", &until.format(format))])
+            }
+            SyncStatus::Error(err) => tr_fmt("status.error", &[("error", err)]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_slider_scale() {
+        assert_eq!(slider_to_limit(0.0), Some(10));
+        assert_eq!(slider_to_limit(SLIDER_LIMITED_END), Some(100_000));
+        assert_eq!(slider_to_limit(1.0), None);
+        assert_eq!(limit_to_slider(None), 1.0);
+        assert_eq!(limit_to_slider(Some(0)), 1.0);
+        for kbps in [50, 500, 2_000, 50_000] {
+            assert_eq!(slider_to_limit(limit_to_slider(Some(kbps))), Some(kbps));
         }
+        // Halfway along the limited range is the geometric middle, 1 MB/s
+        assert_eq!(slider_to_limit(SLIDER_LIMITED_END / 2.0), Some(1_000));
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_unknown_total_size() {
+        assert_eq!(format_total_size(0), "size pending");
+        assert_eq!(format_total_size(2048), "2.0 KB");
+        assert_eq!(progress_fraction(0, 0, false), 0.0);
+        assert_eq!(progress_fraction(0, 0, true), 1.0);
+        assert_eq!(progress_fraction(512, 1024, false), 0.5);
+        // Progress reported past a total that was still growing
+        assert_eq!(progress_fraction(2048, 1024, false), 1.0);
+    }
+
+    #[test]
+    fn test_speed_is_mebibytes_per_second() {
+        assert_eq!(speed_bytes_per_sec(1.0), 1_048_576.0);
+        assert_eq!(speed_bytes_per_sec(0.5), 524_288.0);
+    }
+
+    #[test]
+    fn test_format_file_count() {
+        assert_eq!(format_file_count(42, 512), "Downloading file 43 of 512");
+        assert_eq!(format_file_count(0, 3), "Downloading file 1 of 3");
+        assert_eq!(format_file_count(512, 512), "512 of 512 files");
+    }
+}
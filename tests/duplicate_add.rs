@@ -0,0 +1,81 @@
+// tests/duplicate_add.rs
+
+//! Adding the same torrent twice through `manage_torrent_task` (as a double-triggered
+//! update would) must reuse the existing torrent instead of forgetting and re-adding it.
+
+use std::fs;
+
+use librqbit::{Api, CreateTorrentOptions, Session, SessionOptions};
+use modsync::config::AppConfig;
+use modsync::sync::{manage_torrent_task, SyncEvent};
+use tempfile::tempdir;
+use tokio::sync::mpsc;
+
+#[tokio::test]
+async fn test_duplicate_add_reuses_existing_torrent() {
+    // Build a small torrent from local content
+    let source_dir = tempdir().unwrap();
+    let content_dir = source_dir.path().join("mods");
+    fs::create_dir_all(&content_dir).unwrap();
+    fs::write(content_dir.join("mod.pbo"), vec![7u8; 64 * 1024]).unwrap();
+    let torrent_bytes = librqbit::create_torrent(
+        &content_dir,
+        CreateTorrentOptions {
+            name: Some("mods"),
+            piece_length: Some(16 * 1024),
+        },
+    )
+    .await
+    .unwrap()
+    .as_bytes()
+    .unwrap()
+    .to_vec();
+
+    // A session with no listener or DHT; nothing needs to download
+    let download_dir = tempdir().unwrap();
+    let session = Session::new_with_opts(
+        download_dir.path().to_path_buf(),
+        SessionOptions {
+            disable_dht: true,
+            disable_dht_persistence: true,
+            persistence: None,
+            listen_port_range: None,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let api = Api::new(session.clone(), None);
+    let (ui_tx, mut ui_rx) = mpsc::unbounded_channel::<SyncEvent>();
+
+    let config = AppConfig {
+        torrent_url: "http://example.invalid/mods.torrent".to_string(),
+        download_path: download_dir.path().to_path_buf(),
+        ..AppConfig::default()
+    };
+
+    let first_id = manage_torrent_task(&config, &api, &ui_tx, None, torrent_bytes.clone())
+        .await
+        .unwrap()
+        .expect("first add should return an ID");
+
+    // Second add of identical content, passing the first as the torrent to replace
+    let second_id = manage_torrent_task(&config, &api, &ui_tx, Some(first_id), torrent_bytes)
+        .await
+        .unwrap()
+        .expect("duplicate add should return an ID");
+
+    assert_eq!(second_id, first_id, "duplicate add should reuse the existing torrent ID");
+    assert_eq!(api.api_torrent_list().torrents.len(), 1, "session should still hold one torrent");
+
+    // Both adds report the same ID to the UI
+    let mut added = Vec::new();
+    while let Ok(event) = ui_rx.try_recv() {
+        if let SyncEvent::TorrentAdded(id) = event {
+            added.push(id);
+        }
+    }
+    assert_eq!(added, vec![first_id, first_id]);
+
+    session.stop().await;
+}
@@ -0,0 +1,641 @@
+// tests/full_sync.rs
+
+//! End-to-end test: seed a generated torrent from one librqbit session and
+//! sync it into a temp folder through `manage_torrent_task`, with a minimal
+//! in-process HTTP tracker introducing the two sessions to each other. A
+//! non-seeding config downloads the same way and is paused once finished, and
+//! an update to a folder that's already synced only fetches the changed pieces.
+//! Restarting the torrent to fix missing files downloads a deleted file again, and
+//! files fetched by the HTTP fallback are checked and seeded by the re-added torrent.
+//! A file damaged after the download is fetched again before completion is reported,
+//! and switching downloading off pauses an unfinished torrent.
+
+use std::fs;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::time::Duration;
+
+use librqbit::{AddTorrent, AddTorrentOptions, Api, CreateTorrentOptions, Session, SessionOptions};
+use modsync::config::AppConfig;
+use modsync::sync::completion::{check_completion, fetched_and_reused};
+use modsync::sync::http_fallback::{FallbackWatch, check_http_fallback};
+use modsync::sync::local::{fix_missing_files, refresh_managed_torrent_status_event};
+use modsync::sync::seeding::check_seeding;
+use modsync::sync::types::{LocalTorrentState, SyncState};
+use modsync::sync::{manage_torrent_task, SyncEvent};
+use tempfile::tempdir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+// Files making up the generated torrent (relative path, size in bytes)
+const TEST_FILES: &[(&str, usize)] = &[
+    ("readme.txt", 1_000),
+    ("addons/mod_a.pbo", 150_000),
+    ("addons/mod_b.pbo", 320_000),
+];
+
+// Write deterministic, non-repeating content for each test file
+fn write_test_files(root: &Path) {
+    for (index, (relative_path, size)) in TEST_FILES.iter().enumerate() {
+        let full_path = root.join(relative_path);
+        fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+        let content: Vec<u8> = (0..*size)
+            .map(|i| ((i * 31 + index * 7) % 251) as u8)
+            .collect();
+        fs::write(full_path, content).unwrap();
+    }
+}
+
+// Insert an announce URL into bencoded torrent bytes. `announce` sorts before
+// every other top-level key, so it can go straight after the opening `d`.
+fn with_announce(torrent_bytes: &[u8], announce_url: &str) -> Vec<u8> {
+    assert_eq!(torrent_bytes.first(), Some(&b'd'));
+    let mut out = b"d8:announce".to_vec();
+    out.extend_from_slice(format!("{}:{}", announce_url.len(), announce_url).as_bytes());
+    out.extend_from_slice(&torrent_bytes[1..]);
+    out
+}
+
+// Spawn a tracker that answers every announce with the given peer
+async fn spawn_tracker(peer: SocketAddr) -> String {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let tracker_addr = listener.local_addr().unwrap();
+
+    let mut compact_peer = Vec::new();
+    if let SocketAddr::V4(v4) = peer {
+        compact_peer.extend_from_slice(&v4.ip().octets());
+        compact_peer.extend_from_slice(&v4.port().to_be_bytes());
+    }
+    let mut body = b"d8:completei1e10:incompletei0e8:intervali30e5:peers".to_vec();
+    body.extend_from_slice(format!("{}:", compact_peer.len()).as_bytes());
+    body.extend_from_slice(&compact_peer);
+    body.push(b'e');
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut socket, _)) = listener.accept().await else {
+                break;
+            };
+            let body = body.clone();
+            tokio::spawn(async move {
+                let mut request = [0u8; 4096];
+                let _ = socket.read(&mut request).await;
+                let header = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                let _ = socket.write_all(header.as_bytes()).await;
+                let _ = socket.write_all(&body).await;
+            });
+        }
+    });
+
+    format!("http://{}/announce", tracker_addr)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_full_sync_from_local_seeder() {
+    full_sync_from_local_seeder(true, 47100..47200).await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_non_seeding_config_still_downloads() {
+    full_sync_from_local_seeder(false, 47200..47300).await;
+}
+
+async fn full_sync_from_local_seeder(should_seed: bool, seed_ports: std::ops::Range<u16>) {
+    // 1. Generate content and a torrent for it
+    let seed_dir = tempdir().unwrap();
+    write_test_files(seed_dir.path());
+    let created = librqbit::create_torrent(
+        seed_dir.path(),
+        CreateTorrentOptions {
+            piece_length: Some(32 * 1024),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // 2. Start a seeding session listening on a local port
+    let seed_session = Session::new_with_opts(
+        seed_dir.path().to_path_buf(),
+        SessionOptions {
+            disable_dht: true,
+            disable_dht_persistence: true,
+            listen_port_range: Some(seed_ports),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let seed_port = seed_session.tcp_listen_port().expect("seeder should listen");
+    let seed_api = Api::new(seed_session.clone(), None);
+
+    let tracker_url = spawn_tracker(SocketAddr::from((Ipv4Addr::LOCALHOST, seed_port))).await;
+    let torrent_bytes = with_announce(&created.as_bytes().unwrap(), &tracker_url);
+
+    seed_api
+        .api_add_torrent(
+            AddTorrent::from_bytes(torrent_bytes.clone()),
+            Some(AddTorrentOptions {
+                output_folder: Some(seed_dir.path().to_string_lossy().into_owned()),
+                overwrite: true,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+
+    // 3. Sync into an empty folder through the regular torrent task
+    let download_dir = tempdir().unwrap();
+    let sync_session = Session::new_with_opts(
+        download_dir.path().to_path_buf(),
+        SessionOptions {
+            disable_dht: true,
+            disable_dht_persistence: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let sync_api = Api::new(sync_session.clone(), None);
+    let (ui_tx, mut ui_rx) = mpsc::unbounded_channel::<SyncEvent>();
+
+    let config = AppConfig {
+        torrent_url: tracker_url.clone(),
+        download_path: download_dir.path().to_path_buf(),
+        public_seeding_acknowledged: true, // The test swarm is public; don't hold it for confirmation
+        should_seed,
+        ..AppConfig::default()
+    };
+    let id = manage_torrent_task(&config, &sync_api, &ui_tx, None, torrent_bytes)
+        .await
+        .unwrap()
+        .expect("torrent should be added");
+
+    // 4. Poll status the same way the sync manager does until finished
+    let finished = tokio::time::timeout(Duration::from_secs(60), async {
+        loop {
+            refresh_managed_torrent_status_event(&sync_api, &ui_tx, id);
+            if sync_api.api_stats_v1(id.into()).unwrap().finished {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+    })
+    .await;
+    assert!(finished.is_ok(), "download did not finish in time");
+
+    // 5. Files arrived intact
+    for (relative_path, _) in TEST_FILES {
+        let expected = fs::read(seed_dir.path().join(relative_path)).unwrap();
+        let actual = fs::read(download_dir.path().join(relative_path)).unwrap();
+        assert_eq!(expected, actual, "content mismatch for {}", relative_path);
+    }
+
+    // 6. Events went added -> progress -> complete
+    let mut events = Vec::new();
+    while let Ok(event) = ui_rx.try_recv() {
+        events.push(event);
+    }
+    let added_at = events
+        .iter()
+        .position(|e| matches!(e, SyncEvent::TorrentAdded(added) if *added == id))
+        .expect("TorrentAdded event");
+    let progress_at = events
+        .iter()
+        .position(|e| matches!(e, SyncEvent::ManagedTorrentUpdate(Some((_, stats))) if !stats.finished))
+        .expect("in-progress stats event");
+    let complete_at = events
+        .iter()
+        .rposition(|e| matches!(e, SyncEvent::ManagedTorrentUpdate(Some((_, stats))) if stats.finished))
+        .expect("finished stats event");
+    assert!(added_at < progress_at, "progress reported before torrent was added");
+    assert!(progress_at < complete_at, "completion reported before progress");
+
+    // 7. Seeding after completion follows should_seed
+    let mut state = SyncState { local: LocalTorrentState::Active { id }, ..SyncState::default() };
+    check_seeding(&config, &mut state, &sync_api, &ui_tx).await;
+    let stats = sync_api.api_stats_v1(id.into()).unwrap();
+    assert!(stats.finished);
+    assert_eq!(stats.live.is_none(), !should_seed, "only a non-seeder is paused once finished");
+    assert_eq!(state.seeding_held, (!should_seed).then_some(id));
+
+    sync_session.stop().await;
+    seed_session.stop().await;
+}
+
+// Torrent of the files in `root`, named the same whichever folder they're in
+async fn create_named_torrent(root: &Path) -> Vec<u8> {
+    librqbit::create_torrent(
+        root,
+        CreateTorrentOptions {
+            name: Some("mods"),
+            piece_length: Some(16 * 1024),
+        },
+    )
+    .await
+    .unwrap()
+    .as_bytes()
+    .unwrap()
+    .to_vec()
+}
+
+// Poll until librqbit reports the torrent finished
+async fn wait_finished(api: &Api, id: usize) {
+    let finished = tokio::time::timeout(Duration::from_secs(60), async {
+        while !api.api_stats_v1(id.into()).unwrap().finished {
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+    })
+    .await;
+    assert!(finished.is_ok(), "torrent did not finish in time");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_update_only_fetches_changed_pieces() {
+    // 1. The synced folder holds the old version; the new one changes 40 KB inside one file
+    let download_dir = tempdir().unwrap();
+    write_test_files(download_dir.path());
+    let old_torrent = create_named_torrent(download_dir.path()).await;
+    let seed_dir = tempdir().unwrap();
+    write_test_files(seed_dir.path());
+    let changed_path = seed_dir.path().join("addons/mod_b.pbo");
+    let mut changed = fs::read(&changed_path).unwrap();
+    let changed_range = 100_000..140_000;
+    for byte in &mut changed[changed_range.clone()] {
+        *byte = byte.wrapping_add(1);
+    }
+    fs::write(&changed_path, &changed).unwrap();
+
+    // 2. Seed the new version
+    let seed_session = Session::new_with_opts(
+        seed_dir.path().to_path_buf(),
+        SessionOptions {
+            disable_dht: true,
+            disable_dht_persistence: true,
+            listen_port_range: Some(47300..47400),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let seed_port = seed_session.tcp_listen_port().expect("seeder should listen");
+    let seed_api = Api::new(seed_session.clone(), None);
+    let tracker_url = spawn_tracker(SocketAddr::from((Ipv4Addr::LOCALHOST, seed_port))).await;
+    let new_torrent = with_announce(&create_named_torrent(seed_dir.path()).await, &tracker_url);
+    seed_api
+        .api_add_torrent(
+            AddTorrent::from_bytes(new_torrent.clone()),
+            Some(AddTorrentOptions {
+                output_folder: Some(seed_dir.path().to_string_lossy().into_owned()),
+                overwrite: true,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+
+    // 3. Load the old version, then apply the update the way the sync manager does
+    let sync_session = Session::new_with_opts(
+        download_dir.path().to_path_buf(),
+        SessionOptions {
+            disable_dht: true,
+            disable_dht_persistence: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let sync_api = Api::new(sync_session.clone(), None);
+    let (ui_tx, _ui_rx) = mpsc::unbounded_channel::<SyncEvent>();
+    let config = AppConfig {
+        torrent_url: tracker_url.clone(),
+        download_path: download_dir.path().to_path_buf(),
+        public_seeding_acknowledged: true,
+        ..AppConfig::default()
+    };
+    let old_id = manage_torrent_task(&config, &sync_api, &ui_tx, None, old_torrent)
+        .await
+        .unwrap()
+        .expect("old torrent should be added");
+    wait_finished(&sync_api, old_id).await;
+    let new_id = manage_torrent_task(&config, &sync_api, &ui_tx, Some(old_id), new_torrent)
+        .await
+        .unwrap()
+        .expect("update should be added");
+    wait_finished(&sync_api, new_id).await;
+
+    // 4. Only the pieces overlapping the change came from the seeder
+    assert_eq!(fs::read(download_dir.path().join("addons/mod_b.pbo")).unwrap(), changed);
+    let stats = sync_api.api_stats_v1(new_id.into()).unwrap();
+    let downloaded = stats.live.expect("update should be live").snapshot.downloaded_and_checked_bytes;
+    let (fetched, reused) = fetched_and_reused(stats.total_bytes, downloaded);
+    let changed_bytes = (changed_range.end - changed_range.start) as u64;
+    assert!(fetched >= changed_bytes, "fetched {} bytes, less than the {} changed", fetched, changed_bytes);
+    assert!(
+        fetched <= changed_bytes + 2 * 16 * 1024,
+        "fetched {} of {} bytes for a {} byte change",
+        fetched,
+        stats.total_bytes,
+        changed_bytes
+    );
+    assert_eq!(fetched + reused, stats.total_bytes);
+
+    sync_session.stop().await;
+    seed_session.stop().await;
+}
+
+// A seeder holding the test files and an empty folder to sync them into
+struct Swarm {
+    seed_dir: tempfile::TempDir,
+    download_dir: tempfile::TempDir,
+    seed_session: std::sync::Arc<Session>,
+    sync_session: std::sync::Arc<Session>,
+    sync_api: Api,
+    torrent: Vec<u8>,
+    config: AppConfig,
+}
+
+impl Swarm {
+    async fn start(seed_ports: std::ops::Range<u16>) -> Self {
+        let seed_dir = tempdir().unwrap();
+        write_test_files(seed_dir.path());
+        let seed_session = Session::new_with_opts(
+            seed_dir.path().to_path_buf(),
+            SessionOptions {
+                disable_dht: true,
+                disable_dht_persistence: true,
+                listen_port_range: Some(seed_ports),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let seed_port = seed_session.tcp_listen_port().expect("seeder should listen");
+        let tracker_url = spawn_tracker(SocketAddr::from((Ipv4Addr::LOCALHOST, seed_port))).await;
+        let torrent = with_announce(&create_named_torrent(seed_dir.path()).await, &tracker_url);
+        Api::new(seed_session.clone(), None)
+            .api_add_torrent(
+                AddTorrent::from_bytes(torrent.clone()),
+                Some(AddTorrentOptions {
+                    output_folder: Some(seed_dir.path().to_string_lossy().into_owned()),
+                    overwrite: true,
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap();
+
+        let download_dir = tempdir().unwrap();
+        let sync_session = Session::new_with_opts(
+            download_dir.path().to_path_buf(),
+            SessionOptions {
+                disable_dht: true,
+                disable_dht_persistence: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let sync_api = Api::new(sync_session.clone(), None);
+        let config = AppConfig {
+            torrent_url: tracker_url,
+            download_path: download_dir.path().to_path_buf(),
+            public_seeding_acknowledged: true,
+            ..AppConfig::default()
+        };
+        Swarm { seed_dir, download_dir, seed_session, sync_session, sync_api, torrent, config }
+    }
+
+    // Sync the torrent into the download folder and wait for it to finish
+    async fn sync(&self, ui_tx: &mpsc::UnboundedSender<SyncEvent>) -> usize {
+        let id = manage_torrent_task(&self.config, &self.sync_api, ui_tx, None, self.torrent.clone())
+            .await
+            .unwrap()
+            .expect("torrent should be added");
+        wait_finished(&self.sync_api, id).await;
+        id
+    }
+
+    fn assert_synced(&self, relative_path: &str) {
+        let expected = fs::read(self.seed_dir.path().join(relative_path)).unwrap();
+        let actual = fs::read(self.download_dir.path().join(relative_path)).unwrap();
+        assert!(expected == actual, "content mismatch for {}", relative_path);
+    }
+
+    async fn stop(self) {
+        self.sync_session.stop().await;
+        self.seed_session.stop().await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_fix_missing_files_restores_deleted_file() {
+    let swarm = Swarm::start(47400..47500).await;
+    let (ui_tx, _ui_rx) = mpsc::unbounded_channel::<SyncEvent>();
+    let id = swarm.sync(&ui_tx).await;
+
+    fs::remove_file(swarm.download_dir.path().join("addons/mod_a.pbo")).unwrap();
+    let mut state = SyncState { local: LocalTorrentState::Active { id }, ..SyncState::default() };
+    assert!(fix_missing_files(&swarm.config, &mut state, &swarm.sync_api, &ui_tx).await);
+    let LocalTorrentState::Active { id: new_id } = state.local else {
+        panic!("torrent should be loaded again");
+    };
+    assert_ne!(new_id, id, "the torrent should be added again, not reused");
+    wait_finished(&swarm.sync_api, new_id).await;
+    swarm.assert_synced("addons/mod_a.pbo");
+
+    swarm.stop().await;
+}
+
+// Spawn a server answering `GET /<path>` with the file at that path under `root`
+async fn spawn_file_server(root: &Path) -> String {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let server_addr = listener.local_addr().unwrap();
+    let root = root.to_path_buf();
+
+    tokio::spawn(async move {
+        loop {
+            let Ok((mut socket, _)) = listener.accept().await else {
+                break;
+            };
+            let root = root.clone();
+            tokio::spawn(async move {
+                let mut request = [0u8; 4096];
+                let read = socket.read(&mut request).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&request[..read]);
+                let path = request.split_whitespace().nth(1).unwrap_or("/").trim_start_matches('/');
+                let (status, body) = match fs::read(root.join(path)) {
+                    Ok(body) => ("200 OK", body),
+                    Err(_) => ("404 Not Found", Vec::new()),
+                };
+                let header = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                let _ = socket.write_all(header.as_bytes()).await;
+                let _ = socket.write_all(&body).await;
+            });
+        }
+    });
+
+    format!("http://{}/", server_addr)
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_http_fallback_files_are_verified_and_torrent_runs() {
+    // 1. A torrent whose tracker only knows a peer that isn't there, and a mirror of its files
+    let mirror_dir = tempdir().unwrap();
+    write_test_files(mirror_dir.path());
+    let tracker_url = spawn_tracker(SocketAddr::from((Ipv4Addr::LOCALHOST, 9))).await;
+    let torrent = with_announce(&create_named_torrent(mirror_dir.path()).await, &tracker_url);
+    let mirror_url = spawn_file_server(mirror_dir.path()).await;
+
+    // 2. Start syncing it; nobody seeds
+    let download_dir = tempdir().unwrap();
+    let sync_session = Session::new_with_opts(
+        download_dir.path().to_path_buf(),
+        SessionOptions {
+            disable_dht: true,
+            disable_dht_persistence: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    let sync_api = Api::new(sync_session.clone(), None);
+    let (ui_tx, _ui_rx) = mpsc::unbounded_channel::<SyncEvent>();
+    let config = AppConfig {
+        torrent_url: tracker_url,
+        download_path: download_dir.path().to_path_buf(),
+        public_seeding_acknowledged: true,
+        http_fallback_base_url: Some(mirror_url),
+        http_fallback_after_seconds: 0,
+        ..AppConfig::default()
+    };
+    let id = manage_torrent_task(&config, &sync_api, &ui_tx, None, torrent)
+        .await
+        .unwrap()
+        .expect("torrent should be added");
+    let live = tokio::time::timeout(Duration::from_secs(30), async {
+        while sync_api.api_stats_v1(id.into()).unwrap().live.is_none() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await;
+    assert!(live.is_ok(), "torrent did not start in time");
+
+    // 3. The fallback fetches every file, and the torrent re-added in its place checks them
+    let mut state = SyncState { local: LocalTorrentState::Active { id }, ..SyncState::default() };
+    let mut watch = FallbackWatch::default();
+    // The fetch runs in the background; a later tick picks it up
+    let client = reqwest::Client::new();
+    let picked_up = tokio::time::timeout(Duration::from_secs(30), async {
+        while matches!(state.local, LocalTorrentState::Active { id: current } if current == id) {
+            check_http_fallback(&config, &mut state, &mut watch, &sync_api, &client, &ui_tx, CancellationToken::new).await;
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await;
+    assert!(picked_up.is_ok(), "the fetched files were not picked up in time");
+    let LocalTorrentState::Active { id: new_id } = state.local else {
+        panic!("torrent should be loaded again");
+    };
+    assert_ne!(new_id, id, "the torrent should be added again to check the fetched files");
+    wait_finished(&sync_api, new_id).await;
+    let stats = sync_api.api_stats_v1(new_id.into()).unwrap();
+    assert_eq!(stats.progress_bytes, stats.total_bytes, "every fetched piece should be verified");
+    assert!(stats.live.is_some(), "the torrent should be running, not left paused");
+    for (relative_path, _) in TEST_FILES {
+        let expected = fs::read(mirror_dir.path().join(relative_path)).unwrap();
+        let actual = fs::read(download_dir.path().join(relative_path)).unwrap();
+        assert!(expected == actual, "content mismatch for {}", relative_path);
+    }
+
+    sync_session.stop().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_file_damaged_after_download_is_fetched_before_completion() {
+    let mut swarm = Swarm::start(47500..47600).await;
+    swarm.config.verify_after_complete = true;
+    let (ui_tx, mut ui_rx) = mpsc::unbounded_channel::<SyncEvent>();
+    let id = swarm.sync(&ui_tx).await;
+
+    // Damage a file behind librqbit's back, which still counts it as finished
+    let damaged_path = swarm.download_dir.path().join("addons/mod_b.pbo");
+    let mut damaged = fs::read(&damaged_path).unwrap();
+    for byte in &mut damaged[50_000..60_000] {
+        *byte = byte.wrapping_add(1);
+    }
+    fs::write(&damaged_path, &damaged).unwrap();
+
+    // The full check catches it and re-adds the torrent instead of reporting it complete.
+    // With the seeder paused, the re-added torrent's check leaves the damaged pieces missing.
+    let seed_api = Api::new(swarm.seed_session.clone(), None);
+    let seed_id = seed_api.api_torrent_list().torrents[0].id.expect("seeded torrent ID");
+    seed_api.api_torrent_action_pause(seed_id.into()).await.unwrap();
+    let mut state = SyncState { local: LocalTorrentState::Active { id }, ..SyncState::default() };
+    check_completion(&swarm.config, &mut state, &swarm.sync_api, &ui_tx).await;
+    let LocalTorrentState::Active { id: new_id } = state.local else {
+        panic!("torrent should be loaded again");
+    };
+    assert_ne!(new_id, id, "the torrent should be added again to fetch the damaged file");
+    assert_eq!(state.verified, None, "nothing is verified while damaged pieces are missing");
+    assert_eq!(state.completed, None);
+    assert!(!swarm.sync_api.api_stats_v1(new_id.into()).unwrap().finished);
+    let completions = |ui_rx: &mut mpsc::UnboundedReceiver<SyncEvent>| {
+        std::iter::from_fn(|| ui_rx.try_recv().ok())
+            .filter(|event| matches!(event, SyncEvent::SyncComplete { .. }))
+            .count()
+    };
+    assert_eq!(completions(&mut ui_rx), 0, "damaged files aren't reported complete");
+
+    // Once the seeder is back the damaged pieces are fetched again
+    seed_api.api_torrent_action_start(seed_id.into()).await.unwrap();
+    wait_finished(&swarm.sync_api, new_id).await;
+    swarm.assert_synced("addons/mod_b.pbo");
+    check_completion(&swarm.config, &mut state, &swarm.sync_api, &ui_tx).await;
+    assert!(state.verified.is_some());
+    assert_eq!(state.completed, state.verified);
+    assert_eq!(completions(&mut ui_rx), 1, "complete is reported once the damaged file is fetched");
+
+    swarm.stop().await;
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_download_switched_off_pauses_unfinished_torrent() {
+    let mut swarm = Swarm::start(47600..47700).await;
+    swarm.config.allow_download = false;
+    let (ui_tx, _ui_rx) = mpsc::unbounded_channel::<SyncEvent>();
+    let id = manage_torrent_task(&swarm.config, &swarm.sync_api, &ui_tx, None, swarm.torrent.clone())
+        .await
+        .unwrap()
+        .expect("torrent should be added");
+    let live = tokio::time::timeout(Duration::from_secs(30), async {
+        while swarm.sync_api.api_stats_v1(id.into()).unwrap().live.is_none() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await;
+    assert!(live.is_ok(), "torrent did not start in time");
+
+    let mut state = SyncState { local: LocalTorrentState::Active { id }, ..SyncState::default() };
+    check_seeding(&swarm.config, &mut state, &swarm.sync_api, &ui_tx).await;
+    let stats = swarm.sync_api.api_stats_v1(id.into()).unwrap();
+    assert!(stats.live.is_none() && !stats.finished, "an unfinished torrent is paused while downloading is off");
+    assert_eq!(state.seeding_held, Some(id));
+
+    // Switched back on, it's resumed
+    swarm.config.allow_download = true;
+    check_seeding(&swarm.config, &mut state, &swarm.sync_api, &ui_tx).await;
+    assert_eq!(state.seeding_held, None);
+    assert!(swarm.sync_api.api_stats_v1(id.into()).unwrap().live.is_some());
+
+    swarm.stop().await;
+}